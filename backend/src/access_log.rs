@@ -0,0 +1,182 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Access log of the API operations for offline analysis of what the users look for.
+//!
+//! The records are emitted as events of a separate target, so that they can be written into
+//! their own file. Only a sample of the requests is recorded and no client data is logged.
+//! The query texts are anonymized: words which may identify a person, like e-mail addresses,
+//! links or phone numbers, are replaced with placeholders.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Target of the access log events.
+pub const TARGET: &str = "transpaer_backend::access";
+
+/// Maximal number of characters of a recorded query.
+const MAX_QUERY_LENGTH: usize = 200;
+
+/// Minimal number of digits in a word or a run of numeric words for it to be considered a
+/// possibly personal number.
+const MIN_NUMBER_DIGITS: usize = 5;
+
+/// Lengths of the product barcodes (EAN-8, UPC-A, EAN-13 and GTIN-14), kept in the queries.
+const BARCODE_LENGTHS: [usize; 4] = [8, 12, 13, 14];
+
+/// Placeholder of the words which may be a personal number.
+const NUMBER_PLACEHOLDER: &str = "<number>";
+
+/// Checks if the word is a product barcode with a valid check digit.
+fn is_barcode(word: &str) -> bool {
+    if !BARCODE_LENGTHS.contains(&word.len()) || !word.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let sum: u32 = word
+        .bytes()
+        .rev()
+        .map(|b| u32::from(b - b'0'))
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 1 { 3 * digit } else { digit })
+        .sum();
+    sum % 10 == 0
+}
+
+/// Checks if the word consists only of digits and characters used to format phone numbers.
+fn is_numeric(word: &str) -> bool {
+    word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().all(|c| c.is_ascii_digit() || "+-().".contains(c))
+}
+
+/// Replaces a word of a query with a placeholder if it may identify a person.
+fn anonymize_word(word: &str) -> &str {
+    let num_digits = word.chars().filter(char::is_ascii_digit).count();
+    if word.contains('@') {
+        "<email>"
+    } else if word.contains('/') || word.contains('?') {
+        "<link>"
+    } else if num_digits >= MIN_NUMBER_DIGITS && !is_barcode(word) {
+        NUMBER_PLACEHOLDER
+    } else {
+        word
+    }
+}
+
+/// Moves the run of numeric words into the words, replaced with a placeholder if it may be a
+/// personal number.
+fn push_numbers<'a>(words: &mut Vec<&'a str>, run: &mut Vec<&'a str>) {
+    let num_digits: usize =
+        run.iter().map(|word| word.chars().filter(char::is_ascii_digit).count()).sum();
+    let is_single_barcode = run.len() == 1 && is_barcode(run[0]);
+    if num_digits >= MIN_NUMBER_DIGITS && !is_single_barcode {
+        words.push(NUMBER_PLACEHOLDER);
+        run.clear();
+    } else {
+        words.append(run);
+    }
+}
+
+/// Normalizes the query and replaces the words which may identify a person with placeholders.
+///
+/// Phone numbers are often written in groups, e.g. `+49 170 123 4567`, so runs of numeric words
+/// are replaced as a whole if they together contain enough digits. Only single words being valid
+/// barcodes are kept.
+pub fn anonymize(query: &str) -> String {
+    let mut words = Vec::new();
+    let mut run = Vec::new();
+    for word in query.split_whitespace() {
+        if is_numeric(word) {
+            run.push(word);
+        } else {
+            push_numbers(&mut words, &mut run);
+            words.push(anonymize_word(word));
+        }
+    }
+    push_numbers(&mut words, &mut run);
+    words.join(" ").to_lowercase().chars().take(MAX_QUERY_LENGTH).collect()
+}
+
+/// Records a sample of the API operations.
+#[derive(Clone, Debug)]
+pub struct AccessLog {
+    /// Fraction of the operations to record (from 0.0 to 1.0).
+    sampling: f64,
+}
+
+impl AccessLog {
+    pub fn new(sampling: f64) -> Self {
+        Self { sampling }
+    }
+
+    /// Records the operation if it gets selected into the sample.
+    ///
+    /// The query is anonymized before being recorded.
+    pub fn record(
+        &self,
+        operation: &'static str,
+        query: Option<&str>,
+        results: usize,
+        latency: Duration,
+    ) {
+        if !rand::rng().random_bool(self.sampling) {
+            return;
+        }
+
+        let query = query.map(anonymize);
+        tracing::info!(
+            target: TARGET,
+            operation,
+            query = query.as_deref(),
+            results,
+            latency_ms = latency.as_millis(),
+            "access"
+        );
+    }
+}
+
+/// Parses the fraction of the sampled operations.
+pub fn parse_sampling(value: &str) -> Result<f64, String> {
+    let sampling: f64 = value.parse().map_err(|err| format!("{err}"))?;
+    if (0.0..=1.0).contains(&sampling) {
+        Ok(sampling)
+    } else {
+        Err(format!("`{value}` is not between 0.0 and 1.0"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymization() {
+        assert_eq!(anonymize("  Fairphone   4 "), "fairphone 4");
+        assert_eq!(
+            anonymize("brand:Patagonia -fleece patagonia.com"),
+            "brand:patagonia -fleece patagonia.com"
+        );
+        assert_eq!(anonymize("8717677339556"), "8717677339556");
+        assert_eq!(anonymize("oat milk 2024"), "oat milk 2024");
+        assert_eq!(anonymize("call +49 170 1234567"), "call <number>");
+        assert_eq!(anonymize("call +49 170 123 4567 now"), "call <number> now");
+        assert_eq!(anonymize("(030) 1234-5678"), "<number>");
+        assert_eq!(anonymize("4917012345678"), "<number>");
+        assert_eq!(anonymize("8717677339556 8717677339556"), "<number>");
+        assert_eq!(anonymize("fairphone4 12345x"), "fairphone4 <number>");
+        assert_eq!(anonymize("96385074"), "96385074");
+        assert_eq!(anonymize("jane.doe@example.com shoes"), "<email> shoes");
+        assert_eq!(anonymize("https://example.com/orders?id=1"), "<link>");
+        assert_eq!(anonymize(&"a".repeat(300)).len(), MAX_QUERY_LENGTH);
+    }
+
+    #[test]
+    fn sampling_parsing() {
+        assert_eq!(parse_sampling("0.25"), Ok(0.25));
+        assert_eq!(parse_sampling("1"), Ok(1.0));
+        assert!(parse_sampling("1.5").is_err());
+        assert!(parse_sampling("-0.1").is_err());
+        assert!(parse_sampling("often").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Service layer handling CORS, `OPTIONS` preflight and `HEAD` requests.
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use hyper::{
+    Method, Request, Response, StatusCode,
+    header::{self, HeaderMap, HeaderValue},
+    service::Service,
+};
+
+const ANY_ORIGIN: &str = "*";
+const ALLOWED_METHODS: &str = "GET, HEAD, OPTIONS";
+const MAX_AGE: &str = "86400";
+
+/// CORS configuration.
+#[derive(Clone, Debug)]
+pub struct CorsConfig {
+    /// Origins allowed to access the API. `*` allows any origin.
+    pub allowed_origins: Vec<String>,
+
+    /// Headers allowed in the requests.
+    pub allowed_headers: Vec<String>,
+}
+
+impl CorsConfig {
+    /// Returns the value of the `Access-Control-Allow-Origin` header for the given request origin.
+    fn allowed_origin(&self, origin: Option<&HeaderValue>) -> Option<HeaderValue> {
+        if self.allowed_origins.iter().any(|allowed| allowed == ANY_ORIGIN) {
+            return Some(HeaderValue::from_static(ANY_ORIGIN));
+        }
+
+        let origin = origin?;
+        let origin_str = origin.to_str().ok()?;
+        self.allowed_origins.iter().any(|allowed| allowed == origin_str).then(|| origin.clone())
+    }
+
+    /// Sets the CORS headers overriding the ones set by the API handlers.
+    fn apply(&self, origin: Option<&HeaderValue>, headers: &mut HeaderMap) {
+        headers.remove(header::ACCESS_CONTROL_ALLOW_ORIGIN);
+        if let Some(allowed_origin) = self.allowed_origin(origin) {
+            if allowed_origin != ANY_ORIGIN {
+                headers.append(header::VARY, HeaderValue::from_static("Origin"));
+            }
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allowed_origin);
+        }
+
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static(ALLOWED_METHODS),
+        );
+        if let Ok(allowed_headers) = HeaderValue::from_str(&self.allowed_headers.join(", ")) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers);
+        }
+    }
+}
+
+/// Wraps the API service adding CORS headers to all responses.
+///
+/// - `OPTIONS` requests are answered directly without reaching the API.
+/// - `HEAD` requests are passed to the API as `GET` requests and the body of the response is dropped.
+#[derive(Clone)]
+pub struct Cors<S> {
+    inner: S,
+    config: Arc<CorsConfig>,
+}
+
+impl<S> Cors<S> {
+    pub fn new(inner: S, config: Arc<CorsConfig>) -> Self {
+        Self { inner, config }
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for Cors<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    RB: Default + Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let origin = request.headers().get(header::ORIGIN).cloned();
+        let config = self.config.clone();
+
+        if request.method() == Method::OPTIONS {
+            let mut response = Response::new(RB::default());
+            *response.status_mut() = StatusCode::NO_CONTENT;
+            config.apply(origin.as_ref(), response.headers_mut());
+            response
+                .headers_mut()
+                .insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static(MAX_AGE));
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let is_head = request.method() == Method::HEAD;
+        if is_head {
+            *request.method_mut() = Method::GET;
+        }
+
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            config.apply(origin.as_ref(), response.headers_mut());
+            if is_head {
+                *response.body_mut() = RB::default();
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: origins.iter().map(|o| (*o).to_owned()).collect(),
+            allowed_headers: vec!["Origin".to_owned(), "Content-Type".to_owned()],
+        }
+    }
+
+    #[test]
+    fn any_origin() {
+        let config = config(&["*"]);
+        let origin = HeaderValue::from_static("https://transpaer.com");
+        assert_eq!(config.allowed_origin(Some(&origin)), Some(HeaderValue::from_static("*")));
+        assert_eq!(config.allowed_origin(None), Some(HeaderValue::from_static("*")));
+    }
+
+    #[test]
+    fn listed_origins() {
+        let config = config(&["https://transpaer.com", "http://localhost:3000"]);
+        let allowed = HeaderValue::from_static("http://localhost:3000");
+        let forbidden = HeaderValue::from_static("https://example.com");
+        assert_eq!(config.allowed_origin(Some(&allowed)), Some(allowed.clone()));
+        assert_eq!(config.allowed_origin(Some(&forbidden)), None);
+        assert_eq!(config.allowed_origin(None), None);
+    }
+
+    #[test]
+    fn apply_headers() {
+        let config = config(&["https://transpaer.com"]);
+        let origin = HeaderValue::from_static("https://transpaer.com");
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+
+        config.apply(Some(&origin), &mut headers);
+        assert_eq!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN), Some(&origin));
+        assert_eq!(headers.get(header::VARY), Some(&HeaderValue::from_static("Origin")));
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_HEADERS),
+            Some(&HeaderValue::from_static("Origin, Content-Type"))
+        );
+    }
+}
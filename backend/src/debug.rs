@@ -0,0 +1,140 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Service layer exposing the merge provenance of products and organisations for debugging.
+//!
+//! When enabled, successful product and organisation responses carry a header with a JSON object
+//! holding the internal unique ID and the external IDs merged into the entry with their sources.
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use hyper::{
+    Method, Request, Response, StatusCode,
+    header::{HeaderName, HeaderValue},
+    service::Service,
+};
+
+use transpaer_api::models::{OrganisationIdVariant, ProductIdVariant};
+
+use crate::{errors::BackendError, models::DebugIds, pool::BlockingPool, retrieve::Retriever};
+
+// TODO: Move the debug IDs to the response bodies once the API spec has a field for them.
+pub const DEBUG_IDS: HeaderName = HeaderName::from_static("x-transpaer-debug-ids");
+
+/// Entry requested by a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Organisation(OrganisationIdVariant, String),
+    Product(ProductIdVariant, String),
+}
+
+impl Target {
+    /// Recognises paths of the organisation and product endpoints, e.g. `/products/ean/123`.
+    fn from_path(path: &str) -> Option<Self> {
+        let mut segments = path.strip_prefix('/')?.split('/');
+        let (kind, variant, id) = (segments.next()?, segments.next()?, segments.next()?);
+        if segments.next().is_some() || id.is_empty() {
+            return None;
+        }
+
+        match kind {
+            "organisations" => Some(Self::Organisation(variant.parse().ok()?, id.to_owned())),
+            "products" => Some(Self::Product(variant.parse().ok()?, id.to_owned())),
+            _ => None,
+        }
+    }
+
+    fn retrieve(&self, retriever: &Retriever) -> Result<Option<DebugIds>, BackendError> {
+        match self {
+            Self::Organisation(variant, id) => retriever.organisation_debug_ids(*variant, id),
+            Self::Product(variant, id) => retriever.product_debug_ids(*variant, id),
+        }
+    }
+}
+
+/// Database access needed to look up the debug IDs.
+#[derive(Clone)]
+pub struct DebugIdsSource {
+    pub retriever: Arc<Retriever>,
+    pub pool: BlockingPool,
+}
+
+/// Wraps the API service adding the debug IDs header to product and organisation responses.
+///
+/// Without a source the requests are passed through untouched.
+#[derive(Clone)]
+pub struct ExposeDebugIds<S> {
+    inner: S,
+    source: Option<DebugIdsSource>,
+}
+
+impl<S> ExposeDebugIds<S> {
+    pub fn new(inner: S, source: Option<DebugIdsSource>) -> Self {
+        Self { inner, source }
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for ExposeDebugIds<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    RB: Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let lookup = self.source.clone().and_then(|source| {
+            (request.method() == Method::GET)
+                .then(|| Target::from_path(request.uri().path()))
+                .flatten()
+                .map(|target| (source, target))
+        });
+
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Some((source, target)) = lookup
+                && response.status() == StatusCode::OK
+            {
+                let retriever = source.retriever.clone();
+                match source.pool.run(move || target.retrieve(&retriever)).await {
+                    Ok(Some(ids)) => match serde_json::to_string(&ids) {
+                        Ok(ids) => {
+                            if let Ok(value) = HeaderValue::try_from(ids) {
+                                response.headers_mut().insert(DEBUG_IDS, value);
+                            }
+                        }
+                        Err(error) => tracing::warn!(%error, "Failed to serialize debug IDs"),
+                    },
+                    Ok(None) => {}
+                    Err(error) => tracing::warn!(%error, "Failed to retrieve debug IDs"),
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        assert_eq!(
+            Target::from_path("/products/ean/123"),
+            Some(Target::Product(ProductIdVariant::Ean, "123".to_owned()))
+        );
+        assert_eq!(
+            Target::from_path("/organisations/wiki/Q123"),
+            Some(Target::Organisation(OrganisationIdVariant::Wiki, "Q123".to_owned()))
+        );
+        assert_eq!(Target::from_path("/products/ean/123/alternatives"), None);
+        assert_eq!(Target::from_path("/products/unknown/123"), None);
+        assert_eq!(Target::from_path("/products/ean/"), None);
+        assert_eq!(Target::from_path("/library/info"), None);
+    }
+}
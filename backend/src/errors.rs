@@ -28,6 +28,30 @@ pub enum BackendError {
 
     #[snafu(display("Parsing request input `{input}` as {variant}: {source}"))]
     ParsingInput { source: ParseIdError, input: String, variant: InputVariant },
+
+    #[snafu(display("Reading `{}`: {source}", path.display()))]
+    Reading { source: std::io::Error, path: std::path::PathBuf },
+
+    #[snafu(display("Deserializing `{}`: {source}", path.display()))]
+    Deserializing { source: serde_json::Error, path: std::path::PathBuf },
+
+    #[snafu(display("Invalid tenants configuration: {message}"))]
+    InvalidTenants { message: String },
+
+    #[snafu(display("Writing `{}`: {source}", path.display()))]
+    Writing { source: std::io::Error, path: std::path::PathBuf },
+
+    #[snafu(display("Serializing `{}`: {source}", path.display()))]
+    Serializing { source: serde_json::Error, path: std::path::PathBuf },
+
+    #[snafu(display("Streaming the response: {source}"))]
+    Streaming { source: std::io::Error },
+
+    #[snafu(display("Database worker pool closed: {source}"))]
+    PoolClosed { source: tokio::sync::AcquireError },
+
+    #[snafu(display("Database worker failed: {source}"))]
+    Worker { source: tokio::task::JoinError },
 }
 
 impl From<BackendError> for swagger::ApiError {
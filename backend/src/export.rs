@@ -0,0 +1,121 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Export of the data the frontend needs into static JSON files.
+//!
+//! Allows deploying the frontend without the backend. The files follow the API paths and contain
+//! the same models as the API responses:
+//!
+//! - `library.json`
+//! - `library/<topic>.json`
+//! - `category.json` (the root category)
+//! - `category/<category-id>.json` (the top-level categories)
+//!
+//! Also exports the list of the most looked up products missing from the dataset for the curators.
+
+use std::path::{Path, PathBuf};
+
+use snafu::prelude::*;
+
+use transpaer_api::models as api;
+
+use crate::{
+    errors::{self, BackendError},
+    retrieve::Retriever,
+};
+
+/// Missing product in the curator export.
+#[derive(serde::Serialize, Debug)]
+struct MissingProductEntry {
+    gtin: String,
+    lookups: u64,
+    first_seen: u64,
+    last_seen: u64,
+}
+
+/// Writes the static JSON files into the output directory.
+pub struct StaticExporter<'a> {
+    retriever: &'a Retriever,
+    output: &'a Path,
+}
+
+impl<'a> StaticExporter<'a> {
+    pub fn new(retriever: &'a Retriever, output: &'a Path) -> Self {
+        Self { retriever, output }
+    }
+
+    pub fn export(&self) -> Result<(), BackendError> {
+        self.export_library()?;
+        self.export_categories()?;
+        tracing::info!(
+            output = %self.output.display(),
+            truncated_texts = transpaer_models::models::num_truncated_texts(),
+            "Static export finished"
+        );
+        Ok(())
+    }
+
+    fn export_library(&self) -> Result<(), BackendError> {
+        let items = self.retriever.library_contents()?;
+        self.write(self.output.join("library.json"), &api::LibraryContents { items })?;
+
+        for topic in self.retriever.library_topics()? {
+            if let Some(item) = self.retriever.library_item(&topic)? {
+                self.write(self.output.join("library").join(format!("{topic}.json")), &item)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn export_categories(&self) -> Result<(), BackendError> {
+        let Some(root) = self.retriever.category(String::new())? else {
+            tracing::warn!("Root category not found, skipping categories");
+            return Ok(());
+        };
+
+        for subcategory in &root.subcategories {
+            if let Some(category) = self.retriever.category(subcategory.id.clone())? {
+                let path = self.output.join("category").join(format!("{}.json", subcategory.id));
+                self.write(path, &category)?;
+            }
+        }
+        self.write(self.output.join("category.json"), &root)?;
+        Ok(())
+    }
+
+    fn write<T: serde::Serialize>(&self, path: PathBuf, value: &T) -> Result<(), BackendError> {
+        write_json(path, value)
+    }
+}
+
+/// Writes up to `limit` most looked up missing products into a JSON file for the curators.
+pub fn export_missing_products(
+    retriever: &Retriever,
+    output: &Path,
+    limit: usize,
+) -> Result<(), BackendError> {
+    let entries: Vec<_> = retriever
+        .most_missed_lookups(limit)?
+        .into_iter()
+        .map(|(gtin, lookup)| MissingProductEntry {
+            gtin: gtin.to_canonical_string(),
+            lookups: lookup.count,
+            first_seen: lookup.first_seen,
+            last_seen: lookup.last_seen,
+        })
+        .collect();
+    write_json(output.to_owned(), &entries)?;
+    tracing::info!(output = %output.display(), products = entries.len(), "Missing products exported");
+    Ok(())
+}
+
+fn write_json<T: serde::Serialize>(path: PathBuf, value: &T) -> Result<(), BackendError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context(errors::WritingSnafu { path: parent })?;
+    }
+    let file = std::fs::File::create(&path).context(errors::WritingSnafu { path: &path })?;
+    serde_json::to_writer(std::io::BufWriter::new(file), value)
+        .context(errors::SerializingSnafu { path })?;
+    Ok(())
+}
@@ -6,7 +6,7 @@
 // #[deny(clippy::unwrap_used)]
 // #[deny(clippy::expect_used)]
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
 
 use clap::Parser;
 use hyper::service::Service;
@@ -14,48 +14,367 @@ use tokio::net::TcpListener;
 
 use tracing_subscriber::prelude::*;
 
+mod access_log;
+mod cors;
+mod debug;
 mod errors;
+mod export;
 mod models;
+mod pool;
+mod query;
+mod rate_limit;
 mod retrieve;
 mod server;
+mod streaming;
+mod tenant;
+mod warming;
+
+/// Interval of writing the recorded missed product lookups into the database.
+const MISSED_LOOKUP_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Format of the log output.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[clap(rename_all = "kebab_case")]
+enum LogFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+
+    /// One JSON object per line.
+    Json,
+}
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    db_path: String,
+    /// Directory with the databases of the served dataset.
+    #[arg(short, long, required_unless_present = "tenants", conflicts_with = "tenants")]
+    db_path: Option<String>,
+
+    /// JSON file listing the served datasets, each under its own name, instead of `--db-path`.
+    ///
+    /// Requests select the dataset with the `/tenants/<NAME>` path prefix or the tenant header.
+    /// Requests selecting neither are served by the default dataset.
+    #[arg(long)]
+    tenants: Option<PathBuf>,
+
+    /// Header selecting the dataset when serving several of them.
+    #[arg(long, default_value = "X-Transpaer-Tenant")]
+    tenant_header: String,
 
     #[arg(short, long)]
     log_path: Option<String>,
+
+    /// Address to listen on.
+    #[arg(long, default_value = "0.0.0.0:8080")]
+    address: SocketAddr,
+
+    /// Format of the log output printed to the standard output.
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Directory to write the access log into. The access log is disabled if not given.
+    ///
+    /// The access log records the operations with anonymized query texts, the numbers of results
+    /// and the latencies for offline analysis of what the users look for.
+    #[arg(long)]
+    access_log_path: Option<String>,
+
+    /// Fraction of the operations recorded in the access log (from 0.0 to 1.0).
+    #[arg(long, default_value_t = 0.1, value_parser = access_log::parse_sampling)]
+    access_log_sampling: f64,
+
+    /// Comma-separated list of origins allowed to access the API.
+    #[arg(long, value_delimiter = ',', default_value = "*")]
+    cors_allowed_origins: Vec<String>,
+
+    /// Comma-separated list of headers allowed in cross-origin requests.
+    #[arg(long, value_delimiter = ',', default_value = "Origin,Content-Type")]
+    cors_allowed_headers: Vec<String>,
+
+    /// How strictly text search results must match all the keywords of multi-keyword queries.
+    #[arg(long, value_enum, default_value_t = retrieve::SearchStrictness::default())]
+    search_strictness: retrieve::SearchStrictness,
+
+    /// Enables limiting the rate of requests per client.
+    #[arg(long)]
+    rate_limit: bool,
+
+    /// Maximal number of requests a single client can make in a burst.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit_burst: u32,
+
+    /// Number of requests per second a single client can make in a longer run.
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit_per_second: u32,
+
+    /// Header carrying an API key. Clients sending it are rate-limited per key instead of per IP.
+    #[arg(long, requires = "rate_limit_api_keys")]
+    rate_limit_api_key_header: Option<String>,
+
+    /// File listing the accepted API keys, one per line.
+    ///
+    /// Requests with keys not listed there are rate-limited per IP.
+    #[arg(long)]
+    rate_limit_api_keys: Option<PathBuf>,
+
+    /// Header carrying the client address set by a trusted reverse proxy, e.g. `X-Forwarded-For`.
+    ///
+    /// Clients are rate-limited per the last address in the header instead of the peer address.
+    /// The header should be used only when all requests pass through the proxy.
+    #[arg(long)]
+    rate_limit_forwarded_for_header: Option<String>,
+
+    /// Opens the existing databases only for reading.
+    #[arg(long)]
+    read_only: bool,
+
+    /// Reads the most used buckets before serving requests.
+    ///
+    /// Makes the startup slower but the first requests faster.
+    #[arg(long)]
+    warm_start: bool,
+
+    /// Warms up the caches in the background after the server starts by reading the most common
+    /// keywords and their products in small steps.
+    ///
+    /// The warming runs only at startup, so it also covers new databases taken into use by
+    /// a restart.
+    #[arg(long)]
+    background_warming: bool,
+
+    /// Number of keywords read by the background warming.
+    #[arg(long, default_value_t = 1000)]
+    warming_keywords: usize,
+
+    /// Maximal number of database reads running at once, separately for every dataset.
+    ///
+    /// Defaults to the number of available CPUs.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    db_workers: Option<u16>,
+
+    /// Interval in seconds between logging the database worker pool metrics. Zero disables it.
+    #[arg(long, default_value_t = 60)]
+    db_metrics_interval: u64,
+
+    /// Exposes the internal ID and the merged external IDs of products and organisations in the
+    /// `X-Transpaer-Debug-Ids` response header.
+    ///
+    /// Meant for inspecting the merge provenance, enable only for trusted users.
+    #[arg(long)]
+    debug_ids: bool,
+
+    /// Minimal number of products of an organisation for its page to be streamed in chunks
+    /// instead of being built in memory as a whole. Zero disables the streaming.
+    #[arg(long, default_value_t = 1000)]
+    streaming_threshold: usize,
+
+    /// Runs a one-off command instead of the server.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Exports library items, presentations and top-level category pages into static JSON files
+    /// matching the API schemas.
+    ExportStatic {
+        /// Directory to write the files into.
+        #[arg(long)]
+        output: PathBuf,
+    },
+
+    /// Exports the products missing from the dataset which the users looked up the most into
+    /// a JSON file for the curators.
+    ExportMissing {
+        /// File to write the list into.
+        #[arg(long)]
+        output: PathBuf,
+
+        /// Maximal number of listed products.
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
 
-    setup_logger(args.log_path.as_ref());
+    setup_logger(args.log_path.as_ref(), args.log_format, args.access_log_path.as_ref());
     tracing::info!(
         build_date = env!("VERGEN_BUILD_TIMESTAMP"),
         commit = env!("VERGEN_GIT_SHA"),
         "Starting Transpaer backend!"
     );
 
-    let retriever = retrieve::Retriever::new(&args.db_path).expect("DB error");
+    let tenants = match &args.tenants {
+        Some(path) => tenant::TenantsConfig::read(path).expect("Tenants configuration error"),
+        None => tenant::TenantsConfig::single(args.db_path.as_deref().expect("DB path required")),
+    };
+    let default_tenant = tenants.default_index();
+
+    let mut retrievers = Vec::with_capacity(tenants.tenants.len());
+    for tenant in &tenants.tenants {
+        let start = std::time::Instant::now();
+        let retriever = if args.read_only {
+            retrieve::Retriever::new_read_only(&tenant.db_path)
+        } else {
+            retrieve::Retriever::new(&tenant.db_path)
+        };
+        let retriever = retriever.expect("DB error").with_search_strictness(args.search_strictness);
+        tracing::info!(
+            tenant = %tenant.name,
+            elapsed_ms = start.elapsed().as_millis(),
+            read_only = args.read_only,
+            "Database opened"
+        );
+
+        if args.warm_start {
+            let start = std::time::Instant::now();
+            let entries = retriever.warm_up().expect("DB warm-up failed");
+            tracing::info!(
+                tenant = %tenant.name,
+                elapsed_ms = start.elapsed().as_millis(),
+                entries,
+                "Database warmed up"
+            );
+        }
+        retrievers.push(Arc::new(retriever));
+    }
+
+    // The one-off commands work on the default tenant.
+    match &args.command {
+        Some(Command::ExportStatic { output }) => {
+            export::StaticExporter::new(&retrievers[default_tenant], output)
+                .export()
+                .expect("Static export failed");
+            return;
+        }
+        Some(Command::ExportMissing { output, limit }) => {
+            export::export_missing_products(&retrievers[default_tenant], output, *limit)
+                .expect("Missing products export failed");
+            return;
+        }
+        None => {}
+    }
+
+    let db_workers = args.db_workers.map_or_else(
+        || std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+        usize::from,
+    );
+
+    let access_log = args
+        .access_log_path
+        .is_some()
+        .then(|| access_log::AccessLog::new(args.access_log_sampling));
+    if access_log.is_some() {
+        tracing::info!(sampling = args.access_log_sampling, "Access log enabled");
+    }
+    if args.debug_ids {
+        tracing::warn!("Debug IDs are exposed in the responses");
+    }
+
+    let mut tenant_services = Vec::with_capacity(retrievers.len());
+    for (tenant, retriever) in tenants.tenants.into_iter().zip(retrievers) {
+        let pool = pool::BlockingPool::new(db_workers);
+        if args.db_metrics_interval > 0 {
+            let interval = std::time::Duration::from_secs(args.db_metrics_interval);
+            pool.spawn_reporter(interval, tenant.name.clone());
+        }
 
-    let server = server::Server::new(retriever);
-    let service = transpaer_api::server::MakeService::new(server);
-    let service = swagger::auth::MakeAllowAllAuthenticator::new(service, "cosmo");
-    let service =
-        transpaer_api::server::context::MakeAddContext::<_, swagger::EmptyContext>::new(service);
+        if args.background_warming {
+            warming::Warmer::new(retriever.clone(), pool.clone(), args.warming_keywords).spawn();
+            tracing::info!(
+                tenant = %tenant.name,
+                keywords = args.warming_keywords,
+                "Background warming started"
+            );
+        }
+        spawn_missed_lookup_flusher(retriever.clone(), pool.clone());
+        let debug_ids = args
+            .debug_ids
+            .then(|| debug::DebugIdsSource { retriever: retriever.clone(), pool: pool.clone() });
+        let streaming = (args.streaming_threshold > 0).then(|| streaming::StreamingSource {
+            retriever: retriever.clone(),
+            pool: pool.clone(),
+            threshold: args.streaming_threshold,
+        });
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+        let server = server::Server::new(retriever, pool).with_access_log(access_log.clone());
+        let service = transpaer_api::server::MakeService::new(server);
+        let service = swagger::auth::MakeAllowAllAuthenticator::new(service, "cosmo");
+        let service =
+            transpaer_api::server::context::MakeAddContext::<_, swagger::EmptyContext>::new(
+                service,
+            );
+        tenant_services.push(TenantServices {
+            name: tenant.name,
+            make_service: service,
+            streaming,
+            debug_ids,
+        });
+    }
+    tracing::info!(db_workers, tenants = tenant_services.len(), "Database worker pools ready");
+
+    let tenant_header = hyper::header::HeaderName::try_from(args.tenant_header)
+        .expect("Invalid tenant header name");
+
+    let cors = Arc::new(cors::CorsConfig {
+        allowed_origins: args.cors_allowed_origins,
+        allowed_headers: args.cors_allowed_headers,
+    });
+
+    let rate_limiter = args.rate_limit.then(|| {
+        Arc::new(rate_limit::RateLimiter::new(rate_limit::RateLimitConfig {
+            capacity: args.rate_limit_burst,
+            requests_per_second: args.rate_limit_per_second,
+            api_key_header: args.rate_limit_api_key_header.map(|name| {
+                hyper::header::HeaderName::try_from(name).expect("Invalid API key header name")
+            }),
+            api_keys: args
+                .rate_limit_api_keys
+                .map(|path| {
+                    std::fs::read_to_string(path)
+                        .expect("Read API keys")
+                        .lines()
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            forwarded_for_header: args.rate_limit_forwarded_for_header.map(|name| {
+                hyper::header::HeaderName::try_from(name)
+                    .expect("Invalid forwarded-for header name")
+            }),
+        }))
+    });
+
+    let addr = args.address;
     let listener = TcpListener::bind(addr).await.expect("Bind TCP listener");
     tracing::info!("Listening on {:?}", addr);
 
     loop {
         match listener.accept().await {
-            Ok((stream, _)) => {
-                let service = service.call(addr).await.expect("Failed to accept connection");
+            Ok((stream, peer)) => {
+                let mut services = Vec::with_capacity(tenant_services.len());
+                for tenant in &tenant_services {
+                    let service =
+                        tenant.make_service.call(addr).await.expect("Failed to accept connection");
+                    let service =
+                        streaming::StreamLargeCollections::new(service, tenant.streaming.clone());
+                    let service = debug::ExposeDebugIds::new(service, tenant.debug_ids.clone());
+                    let service = server::Versioned::new(
+                        vec![(server::ApiVersion::V1, service)],
+                        server::DEPRECATIONS,
+                    );
+                    services.push((tenant.name.clone(), service));
+                }
+                let service =
+                    tenant::Tenanted::new(services, default_tenant, tenant_header.clone());
+                let service = rate_limit::RateLimit::new(service, rate_limiter.clone(), peer.ip());
+                let service = cors::Cors::new(service, cors.clone());
                 let io = hyper_util::rt::TokioIo::new(stream);
                 tokio::task::spawn(async move {
                     if let Err(err) = hyper::server::conn::http1::Builder::new()
@@ -71,24 +390,91 @@ async fn main() {
     }
 }
 
-fn setup_logger(log_path: Option<&String>) {
+/// Services of a single tenant from which the services of the connections are made.
+struct TenantServices<M> {
+    name: String,
+    make_service: M,
+    streaming: Option<streaming::StreamingSource>,
+    debug_ids: Option<debug::DebugIdsSource>,
+}
+
+/// Periodically writes the missed product lookups collected by the requests into the database.
+fn spawn_missed_lookup_flusher(retriever: Arc<retrieve::Retriever>, pool: pool::BlockingPool) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(MISSED_LOOKUP_FLUSH_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let retriever = retriever.clone();
+            match pool.run(move || retriever.flush_missed_lookups()).await {
+                Ok(0) => {}
+                Ok(entries) => tracing::debug!(entries, "Missed lookups written"),
+                Err(error) => tracing::warn!(%error, "Failed to write missed lookups"),
+            }
+        }
+    });
+}
+
+fn setup_logger(
+    log_path: Option<&String>,
+    log_format: LogFormat,
+    access_log_path: Option<&String>,
+) {
     let filter = tracing_subscriber::EnvFilter::builder()
         .with_default_directive(tracing::Level::INFO.into())
         .from_env_lossy();
-    let output = tracing_subscriber::fmt::layer();
+    // The access log is written only into its own file.
+    let not_access = || {
+        tracing_subscriber::filter::filter_fn(|metadata| metadata.target() != access_log::TARGET)
+    };
+    let text_output = (log_format == LogFormat::Text)
+        .then(|| tracing_subscriber::fmt::layer().with_filter(not_access()));
+    let json_output = (log_format == LogFormat::Json).then(|| {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_filter(not_access())
+    });
 
-    if let Some(log_path) = log_path {
+    let file = log_path.map(|log_path| {
         let appender = tracing_appender::rolling::Builder::new()
             .rotation(tracing_appender::rolling::Rotation::MINUTELY)
             .filename_prefix("backend")
             .filename_suffix("log")
             .build(log_path)
             .expect("failed to initialize log file appender");
-        let file =
-            tracing_subscriber::fmt::layer().with_writer(appender).json().flatten_event(true);
+        tracing_subscriber::fmt::layer()
+            .with_writer(appender)
+            .json()
+            .flatten_event(true)
+            .with_filter(not_access())
+    });
 
-        tracing_subscriber::registry().with(filter).with(output).with(file).init()
-    } else {
-        tracing_subscriber::registry().with(filter).with(output).init()
-    }
+    let access = access_log_path.map(|access_log_path| {
+        let appender = tracing_appender::rolling::Builder::new()
+            .rotation(tracing_appender::rolling::Rotation::DAILY)
+            .filename_prefix("access")
+            .filename_suffix("log")
+            .build(access_log_path)
+            .expect("failed to initialize access log file appender");
+        tracing_subscriber::fmt::layer()
+            .with_writer(appender)
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .with_filter(
+                tracing_subscriber::filter::Targets::new()
+                    .with_target(access_log::TARGET, tracing::Level::INFO),
+            )
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(text_output)
+        .with(json_output)
+        .with(file)
+        .with(access)
+        .init()
 }
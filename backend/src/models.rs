@@ -47,6 +47,10 @@ pub struct OrganisationSearchResult {
     /// Product name.
     #[serde(rename = "name")]
     pub name: Option<transpaer_models::store::Text>,
+
+    /// Static rank of the organisation.
+    #[serde(rename = "rank", default)]
+    pub rank: transpaer_models::store::SearchRank,
 }
 
 impl OrganisationSearchResult {
@@ -55,6 +59,7 @@ impl OrganisationSearchResult {
             id: id.to_canonical_string(),
             ids: organisation.ids.clone(),
             name: organisation.names.first().cloned(),
+            rank: organisation.transpaer.rank,
         }
     }
 
@@ -102,6 +107,10 @@ pub struct ProductSearchResult {
     /// Product name.
     #[serde(rename = "name")]
     pub name: Option<transpaer_models::store::Text>,
+
+    /// DB IDs of the product manufacturers.
+    #[serde(rename = "manufacturers", default)]
+    pub manufacturers: Vec<ids::OrganisationId>,
 }
 
 impl ProductSearchResult {
@@ -110,6 +119,7 @@ impl ProductSearchResult {
             id: id.to_canonical_string(),
             ids: product.ids.clone(),
             name: product.names.first().cloned(),
+            manufacturers: product.manufacturers.iter().map(|m| m.id.clone()).collect(),
         }
     }
 
@@ -142,3 +152,83 @@ impl ProductSearchResult {
         ))
     }
 }
+
+/// External ID merged into a product or an organisation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DebugExternalId {
+    /// Kind of the ID, e.g. `wiki` or `ean`.
+    #[serde(rename = "variant")]
+    pub variant: String,
+
+    /// The ID in its canonical form.
+    #[serde(rename = "id")]
+    pub id: String,
+
+    /// Labels of the sources which provided the ID.
+    #[serde(rename = "sources")]
+    pub sources: Vec<String>,
+}
+
+impl DebugExternalId {
+    fn new(variant: &str, id: String, sources: &[transpaer_models::store::Source]) -> Self {
+        Self {
+            variant: variant.to_owned(),
+            id,
+            sources: sources.iter().map(transpaer_models::store::Source::to_label).collect(),
+        }
+    }
+}
+
+/// Provenance of a product or an organisation merged during coagulation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DebugIds {
+    /// Internal unique DB entry ID.
+    #[serde(rename = "id")]
+    pub id: String,
+
+    /// External IDs merged into the entry.
+    #[serde(rename = "external")]
+    pub external: Vec<DebugExternalId>,
+}
+
+impl DebugIds {
+    pub fn from_product(id: &ids::ProductId, product: &Product) -> Self {
+        let mut external = Vec::new();
+        for wiki in &product.ids.wiki {
+            external.push(DebugExternalId::new(
+                "wiki",
+                wiki.id.to_canonical_string(),
+                &wiki.sources,
+            ));
+        }
+        for gtin in &product.ids.gtins {
+            external.push(DebugExternalId::new(
+                "gtin",
+                gtin.id.to_canonical_string(),
+                &gtin.sources,
+            ));
+        }
+        for ean in &product.ids.eans {
+            external.push(DebugExternalId::new("ean", ean.id.to_canonical_string(), &ean.sources));
+        }
+        Self { id: id.to_canonical_string(), external }
+    }
+
+    pub fn from_organisation(id: &ids::OrganisationId, organisation: &Organisation) -> Self {
+        let mut external = Vec::new();
+        for wiki in &organisation.ids.wiki {
+            external.push(DebugExternalId::new(
+                "wiki",
+                wiki.id.to_canonical_string(),
+                &wiki.sources,
+            ));
+        }
+        for vat in &organisation.ids.vat_ids {
+            external.push(DebugExternalId::new("vat", vat.id.to_canonical_string(), &vat.sources));
+        }
+        for domain in &organisation.ids.domains {
+            external.push(DebugExternalId::new("www", domain.website.clone(), &domain.sources));
+        }
+        Self { id: id.to_canonical_string(), external }
+    }
+}
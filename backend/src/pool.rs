@@ -0,0 +1,253 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Worker pool running blocking database reads outside of the async runtime threads.
+//!
+//! Bucket reads block the calling thread. Running them directly in request handlers would stall
+//! every other request scheduled on the same runtime thread, so the handlers hand them over to
+//! the pool. The pool limits the number of concurrently running reads, so that a burst of heavy
+//! requests (e.g. large product pages) queues up instead of exhausting the blocking threads.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use snafu::prelude::*;
+use tokio::sync::Semaphore;
+
+use crate::errors::{self, BackendError};
+
+/// Tasks running longer than this are reported as slow.
+const SLOW_TASK_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Counters describing the work done by the pool.
+#[derive(Debug, Default)]
+struct PoolMetrics {
+    /// Number of tasks submitted to the pool.
+    submitted: AtomicU64,
+
+    /// Number of tasks which finished, successfully or not.
+    finished: AtomicU64,
+
+    /// Number of tasks which returned an error or panicked.
+    failed: AtomicU64,
+
+    /// Number of tasks which ran longer than `SLOW_TASK_THRESHOLD`.
+    slow: AtomicU64,
+
+    /// Total time tasks spent waiting for a free worker.
+    wait_micros: AtomicU64,
+
+    /// Total time tasks spent running.
+    run_micros: AtomicU64,
+}
+
+impl PoolMetrics {
+    fn record(&self, waited: Duration, ran: Duration, succeeded: bool) {
+        self.finished.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        if ran > SLOW_TASK_THRESHOLD {
+            self.slow.fetch_add(1, Ordering::Relaxed);
+        }
+        self.wait_micros.fetch_add(as_micros(waited), Ordering::Relaxed);
+        self.run_micros.fetch_add(as_micros(ran), Ordering::Relaxed);
+    }
+}
+
+/// Snapshot of the pool metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Number of tasks submitted to the pool.
+    pub submitted: u64,
+
+    /// Number of tasks which finished, successfully or not.
+    pub finished: u64,
+
+    /// Number of tasks which returned an error or panicked.
+    pub failed: u64,
+
+    /// Number of tasks which ran longer than one second.
+    pub slow: u64,
+
+    /// Number of tasks currently running.
+    pub running: usize,
+
+    /// Total time tasks spent waiting for a free worker.
+    pub wait_micros: u64,
+
+    /// Total time tasks spent running.
+    pub run_micros: u64,
+}
+
+/// Runs blocking closures on the `tokio` blocking threads, at most `size` of them at once.
+#[derive(Debug, Clone)]
+pub struct BlockingPool {
+    size: usize,
+    permits: Arc<Semaphore>,
+    metrics: Arc<PoolMetrics>,
+}
+
+impl BlockingPool {
+    /// Constructs a new `BlockingPool` running at most `size` tasks at once.
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        Self {
+            size,
+            permits: Arc::new(Semaphore::new(size)),
+            metrics: Arc::new(PoolMetrics::default()),
+        }
+    }
+
+    /// Runs the closure on a worker once one is free and waits for its result.
+    pub async fn run<T, F>(&self, task: F) -> Result<T, BackendError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, BackendError> + Send + 'static,
+    {
+        self.metrics.submitted.fetch_add(1, Ordering::Relaxed);
+        let queued = Instant::now();
+        let permit = self.permits.clone().acquire_owned().await.context(errors::PoolClosedSnafu)?;
+        let waited = queued.elapsed();
+
+        let metrics = self.metrics.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let started = Instant::now();
+            let result = task();
+            let ran = started.elapsed();
+            metrics.record(waited, ran, result.is_ok());
+            if ran > SLOW_TASK_THRESHOLD {
+                tracing::warn!(
+                    wait_ms = waited.as_millis(),
+                    run_ms = ran.as_millis(),
+                    "Slow database read"
+                );
+            }
+            result
+        })
+        .await;
+
+        match result {
+            Ok(result) => result,
+            Err(source) => {
+                self.metrics.finished.fetch_add(1, Ordering::Relaxed);
+                self.metrics.failed.fetch_add(1, Ordering::Relaxed);
+                Err(BackendError::Worker { source })
+            }
+        }
+    }
+
+    /// Returns the current values of the metrics.
+    #[must_use]
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            submitted: self.metrics.submitted.load(Ordering::Relaxed),
+            finished: self.metrics.finished.load(Ordering::Relaxed),
+            failed: self.metrics.failed.load(Ordering::Relaxed),
+            slow: self.metrics.slow.load(Ordering::Relaxed),
+            running: self.size - self.permits.available_permits(),
+            wait_micros: self.metrics.wait_micros.load(Ordering::Relaxed),
+            run_micros: self.metrics.run_micros.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Periodically logs the metrics labelled with the name of the tenant the pool serves.
+    ///
+    /// The number of texts truncated while converting the results is logged with them, as the
+    /// conversions run in the pool.
+    pub fn spawn_reporter(&self, interval: Duration, tenant: String) {
+        let pool = self.clone();
+        tokio::task::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let stats = pool.stats();
+                tracing::info!(
+                    tenant = %tenant,
+                    workers = pool.size,
+                    running = stats.running,
+                    queued = stats
+                        .submitted
+                        .saturating_sub(stats.finished)
+                        .saturating_sub(stats.running as u64),
+                    submitted = stats.submitted,
+                    failed = stats.failed,
+                    slow = stats.slow,
+                    wait_ms = stats.wait_micros / 1000,
+                    run_ms = stats.run_micros / 1000,
+                    truncated_texts = transpaer_models::models::num_truncated_texts(),
+                    "Database pool metrics"
+                );
+            }
+        });
+    }
+}
+
+fn as_micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn runs_tasks_and_counts_them() {
+        let pool = BlockingPool::new(2);
+
+        let results = futures::future::join_all((0..5).map(|i| pool.run(move || Ok(i * 2)))).await;
+        let results: Vec<u32> = results.into_iter().map(Result::unwrap).collect();
+        assert_eq!(results, vec![0, 2, 4, 6, 8]);
+
+        let stats = pool.stats();
+        assert_eq!(stats.submitted, 5);
+        assert_eq!(stats.finished, 5);
+        assert_eq!(stats.failed, 0);
+        assert_eq!(stats.running, 0);
+    }
+
+    #[tokio::test]
+    async fn counts_panicked_tasks_as_failed() {
+        let pool = BlockingPool::new(1);
+
+        let result: Result<(), _> = pool.run(|| panic!("task failure")).await;
+        assert!(matches!(result, Err(BackendError::Worker { .. })));
+
+        let stats = pool.stats();
+        assert_eq!(stats.finished, 1);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.running, 0);
+    }
+
+    #[tokio::test]
+    async fn limits_concurrency() {
+        let pool = BlockingPool::new(1);
+        let running = Arc::new(AtomicU64::new(0));
+        let max_running = Arc::new(AtomicU64::new(0));
+
+        let tasks = (0..4).map(|_| {
+            let running = running.clone();
+            let max_running = max_running.clone();
+            pool.run(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(10));
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            })
+        });
+        for result in futures::future::join_all(tasks).await {
+            result.unwrap();
+        }
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Minimal syntax of the text search queries.
+//!
+//! - plain words are searched as independent keywords,
+//! - `"quoted phrases"` must match adjacent tokens,
+//! - `-term` excludes results containing the term,
+//! - `brand:foo` limits the results to organisations with `foo` in their names and products of
+//!   such manufacturers.
+//!
+//! Keywords looking like web domains (e.g. `patagonia.com`) are also looked up in the domain index.
+
+use transpaer_models::utils::{extract_domain_from_str, tokenize};
+
+const EXCLUSION_PREFIX: char = '-';
+const BRAND_PREFIX: &str = "brand:";
+const PHRASE_DELIMITER: char = '"';
+
+/// Parsed text search query.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    /// Independent keywords.
+    pub keywords: Vec<String>,
+
+    /// Tokens of quoted phrases.
+    pub phrases: Vec<Vec<String>>,
+
+    /// Tokens which must not be present in the results.
+    pub excluded: Vec<String>,
+
+    /// Tokens which must be present in the organisation names.
+    pub brands: Vec<String>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let mut result = Self::default();
+        for (i, part) in query.split(PHRASE_DELIMITER).enumerate() {
+            // Every odd part was enclosed in quotes.
+            if i % 2 == 1 {
+                let phrase = tokenize(part);
+                if !phrase.is_empty() {
+                    result.phrases.push(phrase);
+                }
+                continue;
+            }
+
+            for word in part.split_whitespace() {
+                if let Some(term) = word.strip_prefix(EXCLUSION_PREFIX) {
+                    result.excluded.extend(tokenize(term));
+                } else if let Some(term) = Self::strip_brand_prefix(word) {
+                    result.brands.extend(tokenize(term));
+                } else {
+                    result.keywords.extend(tokenize(word));
+                }
+            }
+        }
+        result
+    }
+
+    fn strip_brand_prefix(word: &str) -> Option<&str> {
+        let prefix = word.get(..BRAND_PREFIX.len())?;
+        prefix.eq_ignore_ascii_case(BRAND_PREFIX).then(|| &word[BRAND_PREFIX.len()..])
+    }
+
+    /// Returns all the tokens used to look up the keyword index.
+    pub fn lookup_tokens(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let all = self.keywords.iter().chain(self.phrases.iter().flatten()).chain(&self.brands);
+        for token in all {
+            if !tokens.contains(token) {
+                tokens.push(token.clone());
+            }
+        }
+        tokens
+    }
+
+    /// Checks if the query consists of a single plain word.
+    pub fn single_keyword(&self) -> Option<&str> {
+        match self.keywords.as_slice() {
+            [keyword] if self.phrases.is_empty() && self.brands.is_empty() => Some(keyword),
+            _ => None,
+        }
+    }
+
+    /// Checks if a result with the given label satisfies the phrases, exclusions and brands.
+    ///
+    /// The brands are checked only against organisation labels. Products are checked against the
+    /// names of their manufacturers with `matches_brand`.
+    pub fn matches(&self, label: &str, is_organisation: bool) -> bool {
+        let tokens = tokenize(label);
+        let contains = |token: &String| tokens.contains(token);
+        self.phrases.iter().all(|phrase| tokens.windows(phrase.len()).any(|w| w == phrase))
+            && !self.excluded.iter().any(contains)
+            && (!is_organisation || self.matches_brand(label))
+    }
+
+    /// Checks if the organisation name contains all the brands.
+    pub fn matches_brand(&self, name: &str) -> bool {
+        let tokens = tokenize(name);
+        self.brands.iter().all(|brand| tokens.contains(brand))
+    }
+}
+
+/// Returns the web domain if the token looks like one, e.g. `patagonia.com` or
+/// `https://www.patagonia.com/shop`.
+pub fn as_domain(token: &str) -> Option<String> {
+    let domain = extract_domain_from_str(token).unwrap_or_else(|| token.to_lowercase());
+    let (name, tld) = domain.trim_end_matches('.').rsplit_once('.')?;
+    let is_label =
+        |label: &str| !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-');
+    let is_domain =
+        name.split('.').all(is_label) && tld.len() >= 2 && tld.chars().all(char::is_alphabetic);
+    is_domain.then(|| format!("{name}.{tld}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_plain() {
+        let query = Query::parse("Fairphone  4 ");
+        assert_eq!(query.keywords, vec!["fairphone", "4"]);
+        assert!(query.phrases.is_empty());
+        assert!(query.excluded.is_empty());
+        assert!(query.brands.is_empty());
+        assert_eq!(query.single_keyword(), None);
+    }
+
+    #[test]
+    fn parse_operators() {
+        let query = Query::parse(r#"phone "Dual SIM" -refurbished Brand:Fairphone "unclosed"#);
+        assert_eq!(query.keywords, vec!["phone"]);
+        assert_eq!(query.phrases, vec![vec!["dual", "sim"], vec!["unclosed"]]);
+        assert_eq!(query.excluded, vec!["refurbished"]);
+        assert_eq!(query.brands, vec!["fairphone"]);
+    }
+
+    #[test]
+    fn matches_phrases_and_exclusions() {
+        let query = Query::parse(r#""dual sim" -refurbished"#);
+        assert!(query.matches("Phone Dual SIM", false));
+        assert!(!query.matches("Phone SIM Dual", false));
+        assert!(!query.matches("Phone Dual SIM refurbished", false));
+    }
+
+    #[test]
+    fn matches_brands() {
+        let query = Query::parse("brand:fairphone");
+        assert!(query.matches("Fairphone B.V.", true));
+        assert!(query.matches("Fairphone 4", false));
+        assert!(!query.matches("Other", true));
+        assert!(query.matches_brand("Fairphone B.V."));
+        assert!(!query.matches_brand("Other"));
+    }
+
+    #[test]
+    fn domains() {
+        assert_eq!(as_domain("patagonia.com"), Some("patagonia.com".to_owned()));
+        assert_eq!(as_domain("Shop.Patagonia.com."), Some("shop.patagonia.com".to_owned()));
+        assert_eq!(as_domain("https://www.patagonia.com/shop"), Some("patagonia.com".to_owned()));
+        assert_eq!(as_domain("patagonia"), None);
+        assert_eq!(as_domain("b.v."), None);
+        assert_eq!(as_domain("4.5"), None);
+        assert_eq!(as_domain(".com"), None);
+    }
+}
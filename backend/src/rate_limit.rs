@@ -0,0 +1,303 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Service layer limiting the rate of requests per client using token buckets.
+
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::IpAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::{
+    Request, Response, StatusCode,
+    header::{self, HeaderName, HeaderValue},
+    service::Service,
+};
+
+/// Number of tracked clients above which buckets of idle clients are dropped.
+///
+/// If there are no idle clients to drop, new clients are refused until some become idle.
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+/// Rate limiting configuration.
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    /// Maximal number of requests a client can burst.
+    pub capacity: u32,
+
+    /// Number of requests per second a client can make in a longer run.
+    pub requests_per_second: u32,
+
+    /// Header carrying the API key. If present, clients are identified by the key instead of IP.
+    pub api_key_header: Option<HeaderName>,
+
+    /// Accepted API keys. Requests with other keys are identified by IP.
+    pub api_keys: HashSet<String>,
+
+    /// Header set by a trusted reverse proxy to the address of the client it received the
+    /// request from (e.g. `X-Forwarded-For`). If present, it is used instead of the peer address.
+    pub forwarded_for_header: Option<HeaderName>,
+}
+
+/// Identifies a rate-limited client.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ClientKey {
+    Ip(IpAddr),
+    ApiKey(String),
+}
+
+/// Token bucket of a single client.
+#[derive(Clone, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(config: &RateLimitConfig, now: Instant) -> Self {
+        Self { tokens: f64::from(config.capacity), updated: now }
+    }
+
+    fn refill(&mut self, config: &RateLimitConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * f64::from(config.requests_per_second))
+            .min(f64::from(config.capacity));
+        self.updated = now;
+    }
+
+    fn is_full(&self, config: &RateLimitConfig, now: Instant) -> bool {
+        let mut bucket = self.clone();
+        bucket.refill(config, now);
+        bucket.tokens >= f64::from(config.capacity)
+    }
+
+    /// Takes one token or returns the time after which a token will be available.
+    fn acquire(&mut self, config: &RateLimitConfig, now: Instant) -> Result<(), Duration> {
+        self.refill(config, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / f64::from(config.requests_per_second),
+            ))
+        }
+    }
+}
+
+/// Keeps track of the token buckets of all clients.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<ClientKey, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Identifies the client sending the request.
+    fn client_key<B>(&self, request: &Request<B>, peer: IpAddr) -> ClientKey {
+        self.config
+            .api_key_header
+            .as_ref()
+            .and_then(|name| request.headers().get(name))
+            .and_then(|value| value.to_str().ok())
+            .filter(|key| self.config.api_keys.contains(*key))
+            .map_or_else(
+                || ClientKey::Ip(self.client_ip(request, peer)),
+                |key| ClientKey::ApiKey(key.to_owned()),
+            )
+    }
+
+    /// Finds the address of the client.
+    ///
+    /// The trusted proxy appends the address it received the request from to the forwarded-for
+    /// header, so only the last entry is used. The preceding ones can be forged by the client.
+    fn client_ip<B>(&self, request: &Request<B>, peer: IpAddr) -> IpAddr {
+        self.config
+            .forwarded_for_header
+            .as_ref()
+            .and_then(|name| request.headers().get_all(name).iter().next_back())
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+
+    /// Takes one token from the client's bucket or returns the time to wait before retrying.
+    fn acquire(&self, key: ClientKey, now: Instant) -> Result<(), Duration> {
+        self.acquire_bounded(key, now, MAX_TRACKED_CLIENTS)
+    }
+
+    fn acquire_bounded(
+        &self,
+        key: ClientKey,
+        now: Instant,
+        max_clients: usize,
+    ) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if buckets.len() >= max_clients && !buckets.contains_key(&key) {
+            buckets.retain(|_, bucket| !bucket.is_full(&self.config, now));
+            if buckets.len() >= max_clients {
+                // A bucket becomes idle at the latest after it refills completely.
+                return Err(Duration::from_secs_f64(
+                    f64::from(self.config.capacity) / f64::from(self.config.requests_per_second),
+                ));
+            }
+        }
+        buckets
+            .entry(key)
+            .or_insert_with(|| Bucket::new(&self.config, now))
+            .acquire(&self.config, now)
+    }
+}
+
+/// Formats the `Retry-After` header value rounding up to whole seconds.
+fn retry_after(wait: Duration) -> HeaderValue {
+    let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    HeaderValue::from(seconds.max(1))
+}
+
+/// Wraps the API service rejecting requests of clients exceeding the rate limit.
+///
+/// Rejected requests are answered with `429 Too Many Requests` and a `Retry-After` header.
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: Option<Arc<RateLimiter>>,
+    peer: IpAddr,
+}
+
+impl<S> RateLimit<S> {
+    /// Creates the service. Requests are passed through unlimited if `limiter` is `None`.
+    pub fn new(inner: S, limiter: Option<Arc<RateLimiter>>, peer: IpAddr) -> Self {
+        Self { inner, limiter, peer }
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for RateLimit<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    RB: Default + Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        if let Some(limiter) = &self.limiter {
+            let key = limiter.client_key(&request, self.peer);
+            if let Err(wait) = limiter.acquire(key.clone(), Instant::now()) {
+                tracing::debug!(client = ?key, ?wait, "Rate limit exceeded");
+                let mut response = Response::new(RB::default());
+                *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                response.headers_mut().insert(header::RETRY_AFTER, retry_after(wait));
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            capacity: 2,
+            requests_per_second: 4,
+            api_key_header: Some(HeaderName::from_static("x-api-key")),
+            api_keys: HashSet::from(["secret".to_owned()]),
+            forwarded_for_header: Some(HeaderName::from_static("x-forwarded-for")),
+        }
+    }
+
+    #[test]
+    fn bucket_refills() {
+        let config = config();
+        let now = Instant::now();
+        let mut bucket = Bucket::new(&config, now);
+
+        assert_eq!(bucket.acquire(&config, now), Ok(()));
+        assert_eq!(bucket.acquire(&config, now), Ok(()));
+        assert_eq!(bucket.acquire(&config, now), Err(Duration::from_millis(250)));
+        assert!(!bucket.is_full(&config, now + Duration::from_millis(250)));
+        assert!(bucket.is_full(&config, now + Duration::from_millis(500)));
+
+        assert_eq!(bucket.acquire(&config, now + Duration::from_millis(250)), Ok(()));
+        assert!(bucket.acquire(&config, now + Duration::from_millis(250)).is_err());
+    }
+
+    #[test]
+    fn clients_are_limited_separately() {
+        let limiter = RateLimiter::new(config());
+        let peer1 = IpAddr::from([127, 0, 0, 1]);
+        let peer2 = IpAddr::from([127, 0, 0, 2]);
+        let now = Instant::now();
+
+        let request = Request::new(());
+        let key1 = limiter.client_key(&request, peer1);
+        let key2 = limiter.client_key(&request, peer2);
+        assert_eq!(key1, ClientKey::Ip(peer1));
+
+        let request = Request::builder().header("x-api-key", "secret").body(()).unwrap();
+        let key3 = limiter.client_key(&request, peer1);
+        assert_eq!(key3, ClientKey::ApiKey("secret".to_owned()));
+
+        let request = Request::builder().header("x-api-key", "forged").body(()).unwrap();
+        assert_eq!(limiter.client_key(&request, peer1), ClientKey::Ip(peer1));
+
+        for _ in 0..2 {
+            assert!(limiter.acquire(key1.clone(), now).is_ok());
+        }
+        assert!(limiter.acquire(key1, now).is_err());
+        assert!(limiter.acquire(key2, now).is_ok());
+        assert!(limiter.acquire(key3, now).is_ok());
+    }
+
+    #[test]
+    fn tracked_clients_are_bounded() {
+        let limiter = RateLimiter::new(config());
+        let key1 = ClientKey::Ip(IpAddr::from([127, 0, 0, 1]));
+        let key2 = ClientKey::Ip(IpAddr::from([127, 0, 0, 2]));
+        let now = Instant::now();
+
+        assert!(limiter.acquire_bounded(key1.clone(), now, 1).is_ok());
+        assert_eq!(limiter.acquire_bounded(key2.clone(), now, 1), Err(Duration::from_millis(500)));
+        assert!(limiter.acquire_bounded(key1, now, 1).is_ok());
+
+        let later = now + Duration::from_millis(500);
+        assert!(limiter.acquire_bounded(key2, later, 1).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn forwarded_for_uses_last_entry() {
+        let limiter = RateLimiter::new(config());
+        let peer = IpAddr::from([127, 0, 0, 1]);
+
+        let request =
+            Request::builder().header("x-forwarded-for", "10.0.0.1, 10.0.0.2").body(()).unwrap();
+        assert_eq!(limiter.client_key(&request, peer), ClientKey::Ip(IpAddr::from([10, 0, 0, 2])));
+
+        let request = Request::builder().header("x-forwarded-for", "unknown").body(()).unwrap();
+        assert_eq!(limiter.client_key(&request, peer), ClientKey::Ip(peer));
+    }
+
+    #[test]
+    fn retry_after_rounds_up() {
+        assert_eq!(retry_after(Duration::from_millis(250)), HeaderValue::from(1_u64));
+        assert_eq!(retry_after(Duration::from_millis(1500)), HeaderValue::from(2_u64));
+        assert_eq!(retry_after(Duration::from_secs(3)), HeaderValue::from(3_u64));
+    }
+}
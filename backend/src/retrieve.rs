@@ -2,25 +2,84 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use rand::Rng;
 use snafu::prelude::*;
 
 use transpaer_api::models as api;
 use transpaer_models::{
-    buckets::{AppStore, DbStore},
-    ids, store, utils,
+    buckets::{AppStore, Bucket, DbStore, Versioned},
+    ids, store,
 };
 
 use crate::{
     errors::{self, BackendError},
-    models::{OrganisationSearchResult, ProductSearchResult, SearchResultId},
+    models::{DebugIds, OrganisationSearchResult, ProductSearchResult, SearchResultId},
+    query::{self, Query},
 };
 
 const CATEGORY_DBID_SEPARATOR: char = '/';
 const CATEGORY_PARAM_SEPARATOR: char = '.';
 
+/// Score multiplier for results matching all keywords of a multi-keyword query.
+const ALL_KEYWORDS_BOOST: f64 = 4.0;
+
+/// Score multiplier for results matching only one keyword of a multi-keyword query.
+const SINGLE_KEYWORD_PENALTY: f64 = 0.25;
+
+/// Base score of organisations whose web domain was given in the query.
+const DOMAIN_MATCH_SCORE: f64 = 100.0;
+
+/// Weight of the number of products in the static rank of organisations.
+const RANK_PRODUCTS_WEIGHT: f64 = 0.1;
+
+/// Weight of the number of certifications in the static rank of organisations.
+const RANK_CERTIFICATIONS_WEIGHT: f64 = 0.2;
+
+/// Weight of the notability in the static rank of organisations.
+const RANK_NOTABILITY_WEIGHT: f64 = 0.1;
+
+/// Score added to alternatives with the same quantity as the compared product.
+///
+/// Alternatives with similar quantities get a proportional part of it. It only breaks ties
+/// between similarly scored alternatives, so it is kept well below the weight of any
+/// certification, but above the random jitter.
+const QUANTITY_MATCH_SCORE: f64 = 0.05;
+
+/// Minimal time between two recorded lookups of the same missing GTIN.
+const MISSED_LOOKUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of remembered missing GTINs above which no new lookups are recorded until the
+/// remembered ones expire.
+const MAX_TRACKED_MISSED_LOOKUPS: usize = 10_000;
+
+/// Maximal number of missed lookups waiting to be written into the database.
+///
+/// Limits the number of database writes per flush of the missed lookups regardless of how many
+/// different GTINs are looked up.
+const MAX_PENDING_MISSED_LOOKUPS: usize = 1_000;
+
+/// How strictly results of multi-keyword queries must match all the keywords.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[clap(rename_all = "kebab_case")]
+pub enum SearchStrictness {
+    /// Each keyword contributes to the score independently.
+    Any,
+
+    /// Results matching all keywords are boosted and results matching a single keyword demoted.
+    #[default]
+    Boost,
+
+    /// Only results matching all keywords are returned.
+    All,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct ScoredResult {
     score: f64,
@@ -36,6 +95,15 @@ impl ScoredResult {
 #[derive(Clone, Debug, Default)]
 struct ResultCollector {
     results: HashMap<SearchResultId, ScoredResult>,
+
+    /// Indices of the query keywords matched by each result.
+    matched: HashMap<SearchResultId, HashSet<usize>>,
+
+    /// Score multipliers derived from the static ranks of the results.
+    ranks: HashMap<SearchResultId, f64>,
+
+    /// DB IDs of the manufacturers of the product results.
+    manufacturers: HashMap<SearchResultId, Vec<ids::OrganisationId>>,
 }
 
 impl ResultCollector {
@@ -56,6 +124,10 @@ impl ResultCollector {
             let item_score = matching.len() as f64 / result.label.len() as f64;
             let total_score = 1.0 + index_score + item_score;
 
+            if let Some(index) = index {
+                self.matched.entry(id.clone()).or_default().insert(index);
+            }
+
             self.results
                 .entry(id.clone())
                 .and_modify(|e| e.with_added_score(total_score))
@@ -70,10 +142,27 @@ impl ResultCollector {
         index: Option<usize>,
     ) {
         let results: Vec<(SearchResultId, api::TextSearchResult)> =
-            results.into_iter().filter_map(|r| r.convert()).collect();
+            results.into_iter().filter_map(|r| self.convert_ranked(r)).collect();
         self.add(&results, matching, index)
     }
 
+    /// Adds organisations matched exactly by the keyword at `index`, e.g. by their web domain.
+    pub fn add_exact_organisations(
+        &mut self,
+        results: Vec<OrganisationSearchResult>,
+        index: usize,
+    ) {
+        let results: Vec<(SearchResultId, api::TextSearchResult)> =
+            results.into_iter().filter_map(|r| self.convert_ranked(r)).collect();
+        for (id, result) in results {
+            self.matched.entry(id.clone()).or_default().insert(index);
+            self.results
+                .entry(id)
+                .and_modify(|e| e.with_added_score(DOMAIN_MATCH_SCORE))
+                .or_insert(ScoredResult { score: DOMAIN_MATCH_SCORE, result });
+        }
+    }
+
     pub fn add_products(
         &mut self,
         results: Vec<ProductSearchResult>,
@@ -81,10 +170,94 @@ impl ResultCollector {
         index: Option<usize>,
     ) {
         let results: Vec<(SearchResultId, api::TextSearchResult)> =
-            results.into_iter().filter_map(|r| r.convert()).collect();
+            results.into_iter().filter_map(|r| self.convert_product(r)).collect();
         self.add(&results, matching, index)
     }
 
+    /// Converts the product remembering its manufacturers.
+    fn convert_product(
+        &mut self,
+        mut result: ProductSearchResult,
+    ) -> Option<(SearchResultId, api::TextSearchResult)> {
+        let manufacturers = std::mem::take(&mut result.manufacturers);
+        let (id, result) = result.convert()?;
+        self.manufacturers.insert(id.clone(), manufacturers);
+        Some((id, result))
+    }
+
+    /// Returns the DB IDs of the manufacturers of the collected products.
+    pub fn manufacturer_ids(&self) -> HashSet<ids::OrganisationId> {
+        self.results.keys().filter_map(|id| self.manufacturers.get(id)).flatten().cloned().collect()
+    }
+
+    /// Keeps only organisations and products made by one of the given manufacturers.
+    pub fn retain_manufacturers(&mut self, manufacturers: &HashSet<ids::OrganisationId>) {
+        let products = &self.manufacturers;
+        self.results.retain(|id, _| match id {
+            SearchResultId::Organisation(_) => true,
+            SearchResultId::Product(_) => {
+                products.get(id).into_iter().flatten().any(|m| manufacturers.contains(m))
+            }
+        });
+    }
+
+    /// Adjusts scores according to how many of the `num_keywords` query keywords each result
+    /// matched.
+    ///
+    /// Has no effect for queries with less than two keywords.
+    pub fn apply_strictness(&mut self, num_keywords: usize, strictness: SearchStrictness) {
+        if num_keywords < 2 {
+            return;
+        }
+
+        let matched = &self.matched;
+        let num_matched = |id: &SearchResultId| matched.get(id).map_or(0, HashSet::len);
+        match strictness {
+            SearchStrictness::Any => {}
+            SearchStrictness::Boost => {
+                for (id, result) in &mut self.results {
+                    match num_matched(id) {
+                        n if n == num_keywords => result.score *= ALL_KEYWORDS_BOOST,
+                        0 | 1 => result.score *= SINGLE_KEYWORD_PENALTY,
+                        _ => {}
+                    }
+                }
+            }
+            SearchStrictness::All => {
+                self.results.retain(|id, _| num_matched(id) == num_keywords);
+            }
+        }
+    }
+
+    /// Converts the organisation remembering the multiplier derived from its static rank.
+    fn convert_ranked(
+        &mut self,
+        result: OrganisationSearchResult,
+    ) -> Option<(SearchResultId, api::TextSearchResult)> {
+        let boost = rank_boost(&result.rank);
+        let (id, result) = result.convert()?;
+        self.ranks.insert(id.clone(), boost);
+        Some((id, result))
+    }
+
+    /// Multiplies scores of the results by the multipliers derived from their static ranks.
+    ///
+    /// This way notable organisations are preferred over trivial ones matching the query equally.
+    pub fn apply_ranks(&mut self) {
+        for (id, result) in &mut self.results {
+            if let Some(boost) = self.ranks.get(id) {
+                result.score *= boost;
+            }
+        }
+    }
+
+    /// Removes results not matching the query operators.
+    pub fn retain_matching(&mut self, query: &Query) {
+        self.results.retain(|id, result| {
+            query.matches(&result.result.label, matches!(id, SearchResultId::Organisation(_)))
+        });
+    }
+
     pub fn gather_scored_results(self) -> Vec<ScoredResult> {
         use std::cmp::Ordering;
 
@@ -101,10 +274,77 @@ impl ResultCollector {
     }
 }
 
+/// Returns the score multiplier for an organisation with the given static rank.
+///
+/// Counts are taken logarithmically, so that huge organisations do not overshadow the matching.
+fn rank_boost(rank: &store::SearchRank) -> f64 {
+    let log = |count: u32| f64::from(count).ln_1p();
+    1.0 + RANK_PRODUCTS_WEIGHT * log(rank.num_products)
+        + RANK_CERTIFICATIONS_WEIGHT * f64::from(rank.num_certifications)
+        + RANK_NOTABILITY_WEIGHT * log(rank.notability)
+}
+
+/// Limits how often lookups of missing GTINs are recorded and collects them until they are
+/// written into the database.
+///
+/// Keeps repeated or automated requests from inflating the counters and the requests from writing
+/// into the database. The lookups of the same GTIN are recorded at most once per interval and at
+/// most `MAX_PENDING_MISSED_LOOKUPS` lookups wait for being written.
+#[derive(Debug, Default)]
+struct MissedLookupLimiter {
+    recorded: Mutex<HashMap<ids::Gtin, Instant>>,
+    pending: Mutex<HashMap<ids::Gtin, store::MissedLookup>>,
+}
+
+impl MissedLookupLimiter {
+    /// Records the lookup of the GTIN unless it was recorded recently or too many lookups wait
+    /// for being written.
+    fn record(&self, gtin: ids::Gtin, now: Instant, seconds: u64) {
+        let mut pending = self.pending.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if pending.len() >= MAX_PENDING_MISSED_LOOKUPS && !pending.contains_key(&gtin) {
+            return;
+        }
+        if !self.acquire(&gtin, now) {
+            return;
+        }
+        pending
+            .entry(gtin)
+            .and_modify(|lookup| {
+                lookup.count += 1;
+                lookup.last_seen = seconds;
+            })
+            .or_insert(store::MissedLookup { count: 1, first_seen: seconds, last_seen: seconds });
+    }
+
+    /// Returns the lookups waiting for being written and forgets them.
+    fn take_pending(&self) -> HashMap<ids::Gtin, store::MissedLookup> {
+        std::mem::take(&mut *self.pending.lock().unwrap_or_else(std::sync::PoisonError::into_inner))
+    }
+
+    /// Checks if a lookup of the GTIN can be recorded now and if so, remembers it.
+    fn acquire(&self, gtin: &ids::Gtin, now: Instant) -> bool {
+        let is_recent = |at: &Instant| now.saturating_duration_since(*at) < MISSED_LOOKUP_INTERVAL;
+        let mut recorded = self.recorded.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        if recorded.get(gtin).is_some_and(is_recent) {
+            return false;
+        }
+        if recorded.len() >= MAX_TRACKED_MISSED_LOOKUPS && !recorded.contains_key(gtin) {
+            recorded.retain(|_, at| is_recent(at));
+            if recorded.len() >= MAX_TRACKED_MISSED_LOOKUPS {
+                return false;
+            }
+        }
+        recorded.insert(gtin.clone(), now);
+        true
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Retriever {
     db: DbStore,
     app: AppStore,
+    search_strictness: SearchStrictness,
+    missed_lookups: Arc<MissedLookupLimiter>,
 }
 
 impl Retriever {
@@ -112,7 +352,75 @@ impl Retriever {
         let path = std::path::Path::new(path);
         let db = DbStore::new(&path.join("db"))?;
         let app = AppStore::new(&path.join("app"))?;
-        Ok(Self { db, app })
+        Ok(Self {
+            db,
+            app,
+            search_strictness: SearchStrictness::default(),
+            missed_lookups: Arc::default(),
+        })
+    }
+
+    /// Opens the existing databases only for reading.
+    pub fn new_read_only(path: &str) -> Result<Self, BackendError> {
+        let path = std::path::Path::new(path);
+        let db = DbStore::open_read_only(&path.join("db"))?;
+        let app = AppStore::open_read_only(&path.join("app"))?;
+        Ok(Self {
+            db,
+            app,
+            search_strictness: SearchStrictness::default(),
+            missed_lookups: Arc::default(),
+        })
+    }
+
+    /// Reads the buckets used by most of the requests so that the first requests are not slowed
+    /// down by cold reads. Returns the number of read entries.
+    pub fn warm_up(&self) -> Result<usize, BackendError> {
+        Ok(self.db.preload_hot_buckets()? + self.app.preload_hot_buckets()?)
+    }
+
+    /// Returns up to `limit` product keywords matching the most products, the biggest first.
+    ///
+    /// Keywords matching many products are likely to be the most searched for.
+    // TODO: Rank the keywords by the search counters once the backend collects analytics.
+    pub fn hottest_keywords(&self, limit: usize) -> Result<Vec<String>, BackendError> {
+        let mut heap = BinaryHeap::<Reverse<(usize, String)>>::with_capacity(limit + 1);
+        for entry in self.db.get_keyword_to_product_ids_bucket()?.iter() {
+            let (keyword, product_ids) = entry?;
+            heap.push(Reverse((product_ids.len(), keyword)));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+        Ok(heap.into_sorted_vec().into_iter().map(|Reverse((_, keyword))| keyword).collect())
+    }
+
+    /// Reads the entries of the passed product keywords and of up to `products_per_keyword`
+    /// products each of them points to. Returns the number of read entries.
+    pub fn warm_keywords(
+        &self,
+        keywords: &[String],
+        products_per_keyword: usize,
+    ) -> Result<usize, BackendError> {
+        let product_keywords = self.db.get_keyword_to_product_ids_bucket()?;
+        let products = self.db.get_product_bucket()?;
+        let mut count = 0;
+        for keyword in keywords {
+            let Some(product_ids) = product_keywords.get(keyword)? else { continue };
+            count += 1;
+            for product_id in product_ids.iter().take(products_per_keyword) {
+                if products.get(product_id)?.is_some() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Sets how strictly text search results must match all the keywords of the query.
+    pub fn with_search_strictness(mut self, search_strictness: SearchStrictness) -> Self {
+        self.search_strictness = search_strictness;
+        self
     }
 
     pub fn library_contents(&self) -> Result<Vec<api::LibraryItemShort>, BackendError> {
@@ -121,6 +429,11 @@ impl Retriever {
         Ok(library.gather()?.into_values().map(|item| item.into_api_short()).collect())
     }
 
+    pub fn library_topics(&self) -> Result<Vec<store::LibraryTopic>, BackendError> {
+        let library = self.app.get_library_bucket()?;
+        Ok(library.gather()?.into_keys().collect())
+    }
+
     pub fn library_item(
         &self,
         topic: &String,
@@ -156,6 +469,74 @@ impl Retriever {
         }
     }
 
+    /// Returns the number of products of the organisation or `None` if it does not exist.
+    pub fn organisation_product_count(
+        &self,
+        id_variant: api::OrganisationIdVariant,
+        id: &str,
+    ) -> Result<Option<usize>, BackendError> {
+        let Some(organisation_id) = self.organisation_id(id_variant, id)? else { return Ok(None) };
+        let organisation = self.db.get_organisation_bucket()?.get(&organisation_id)?;
+        Ok(organisation.map(|org| org.products.len()))
+    }
+
+    /// Writes the organisation as JSON converting its products one by one.
+    ///
+    /// Produces the same JSON as serializing the result of `organisation`, but without holding all
+    /// the products in memory at once. Returns `false` if the organisation does not exist.
+    pub fn write_organisation(
+        &self,
+        id_variant: api::OrganisationIdVariant,
+        id: &str,
+        writer: &mut impl std::io::Write,
+    ) -> Result<bool, BackendError> {
+        const PRODUCTS_MARKER: &[u8] = br#""products":[]"#;
+
+        let Some(organisation_id) = self.organisation_id(id_variant, id)? else { return Ok(false) };
+        let Some(mut org) = self.db.get_organisation_bucket()?.get(&organisation_id)? else {
+            return Ok(false);
+        };
+        tracing::info!(significance = ?org.transpaer.significance, "organisation viewed");
+        let product_ids = std::mem::take(&mut org.products);
+        let mut org = org.into_api_full(Vec::new());
+        let head = serde_json::to_vec(&org)
+            .map_err(std::io::Error::from)
+            .context(errors::StreamingSnafu)?;
+        let Some(split) =
+            head.windows(PRODUCTS_MARKER.len()).position(|window| window == PRODUCTS_MARKER)
+        else {
+            tracing::warn!("Products not found in the serialized organisation, writing it whole");
+            org.products = self.short_products(&product_ids)?;
+            serde_json::to_writer(&mut *writer, &org)
+                .map_err(std::io::Error::from)
+                .context(errors::StreamingSnafu)?;
+            writer.flush().context(errors::StreamingSnafu)?;
+            return Ok(true);
+        };
+
+        let (prefix, suffix) = head.split_at(split);
+        writer.write_all(prefix).context(errors::StreamingSnafu)?;
+        writer.write_all(br#""products":["#).context(errors::StreamingSnafu)?;
+        let products = self.db.get_product_bucket()?;
+        let mut first = true;
+        for product_id in &product_ids {
+            let Some(product) = products.get(product_id)? else {
+                tracing::warn!(%product_id, "Product not found");
+                continue;
+            };
+            if !first {
+                writer.write_all(b",").context(errors::StreamingSnafu)?;
+            }
+            first = false;
+            serde_json::to_writer(&mut *writer, &product.into_api_short())
+                .map_err(std::io::Error::from)
+                .context(errors::StreamingSnafu)?;
+        }
+        writer.write_all(&suffix[PRODUCTS_MARKER.len() - 1..]).context(errors::StreamingSnafu)?;
+        writer.flush().context(errors::StreamingSnafu)?;
+        Ok(true)
+    }
+
     pub fn product(
         &self,
         id_variant: api::ProductIdVariant,
@@ -166,6 +547,9 @@ impl Retriever {
             let prods = self.db.get_product_bucket()?;
             if let Some(prod) = prods.get(&product_id)? {
                 tracing::info!(significance = ?prod.transpaer.significance, "product viewed");
+                // TODO: Expose the availability and retailer links once the API spec has fields for them.
+                let availability = self.app.get_gtin_to_retailer_availability_bucket()?;
+                let prod = Self::overlay_availability(&availability, prod)?;
                 let manufacturers = self.short_organisations(&prod.manufacturers)?;
                 let alternatives =
                     self.product_alternatives_impl(product_id, &prod.categories, region)?;
@@ -179,6 +563,110 @@ impl Retriever {
         }
     }
 
+    /// Returns the internal ID and the merged external IDs of the organisation for debugging.
+    pub fn organisation_debug_ids(
+        &self,
+        id_variant: api::OrganisationIdVariant,
+        id: &str,
+    ) -> Result<Option<DebugIds>, BackendError> {
+        let Some(organisation_id) = self.organisation_id(id_variant, id)? else { return Ok(None) };
+        let organisation = self.db.get_organisation_bucket()?.get(&organisation_id)?;
+        Ok(organisation.map(|org| DebugIds::from_organisation(&organisation_id, &org)))
+    }
+
+    /// Returns the internal ID and the merged external IDs of the product for debugging.
+    pub fn product_debug_ids(
+        &self,
+        id_variant: api::ProductIdVariant,
+        id: &str,
+    ) -> Result<Option<DebugIds>, BackendError> {
+        let Some(product_id) = self.product_id(id_variant, id)? else { return Ok(None) };
+        let product = self.db.get_product_bucket()?.get(&product_id)?;
+        Ok(product.map(|prod| DebugIds::from_product(&product_id, &prod)))
+    }
+
+    /// Records a lookup of a product missing from the dataset.
+    ///
+    /// Only lookups of GTINs and EANs are recorded. They are only collected in memory,
+    /// `flush_missed_lookups` writes them into the database.
+    pub fn missing_product(
+        &self,
+        id_variant: api::ProductIdVariant,
+        id: &str,
+    ) -> Result<(), BackendError> {
+        let gtin = match id_variant {
+            api::ProductIdVariant::Ean => ids::Ean::try_from(id)
+                .context(errors::ParsingInputSnafu {
+                    input: id.to_owned(),
+                    variant: errors::InputVariant::Ean,
+                })?
+                .to_gtin(),
+            api::ProductIdVariant::Gtin => {
+                ids::Gtin::try_from(id).context(errors::ParsingInputSnafu {
+                    input: id.to_owned(),
+                    variant: errors::InputVariant::Gtin,
+                })?
+            }
+            api::ProductIdVariant::Wiki => return Ok(()),
+        };
+        if !self.app.is_read_only() {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            self.missed_lookups.record(gtin, Instant::now(), now);
+        }
+        Ok(())
+    }
+
+    /// Writes the collected missed lookups into the database in one batch.
+    ///
+    /// Returns the number of written entries.
+    pub fn flush_missed_lookups(&self) -> Result<usize, BackendError> {
+        let pending = self.missed_lookups.take_pending();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let lookups = self.app.get_missed_lookup_bucket()?;
+        for (gtin, lookup) in &pending {
+            let lookup = match lookups.get(gtin)? {
+                Some(stored) => store::MissedLookup {
+                    count: stored.count + lookup.count,
+                    first_seen: stored.first_seen,
+                    last_seen: lookup.last_seen,
+                },
+                None => lookup.clone(),
+            };
+            lookups.insert(gtin, &lookup)?;
+        }
+        lookups.flush()?;
+        Ok(pending.len())
+    }
+
+    /// Returns up to `limit` missing GTINs looked up the most, the most looked up first.
+    pub fn most_missed_lookups(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<(ids::Gtin, store::MissedLookup)>, BackendError> {
+        let mut heap = BinaryHeap::<Reverse<(u64, ids::Gtin)>>::with_capacity(limit + 1);
+        let lookups = self.app.get_missed_lookup_bucket()?;
+        for entry in lookups.iter() {
+            let (gtin, lookup) = entry?;
+            heap.push(Reverse((lookup.count, gtin)));
+            if heap.len() > limit {
+                heap.pop();
+            }
+        }
+
+        let mut result = Vec::with_capacity(heap.len());
+        for Reverse((_, gtin)) in heap.into_sorted_vec() {
+            if let Some(lookup) = lookups.get(&gtin)? {
+                result.push((gtin, lookup));
+            }
+        }
+        Ok(result)
+    }
+
     pub fn product_alternatives(
         &self,
         id_variant: api::ProductIdVariant,
@@ -219,11 +707,13 @@ impl Retriever {
             results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
             results.truncate(100);
             let results = results.iter().map(|r| r.1.clone().into_api_short()).collect();
-            let subcategories = Self::prepare_subcategories(&category_param, &category);
-            let supercategories = Self::prepare_supercategories(&category_param);
+            let mut subcategories = Self::prepare_subcategories(&category_param, &category);
+            let mut supercategories = Self::prepare_supercategories(&category_param);
+            Self::label_categories(&categories, &mut subcategories)?;
+            Self::label_categories(&categories, &mut supercategories)?;
 
             Ok(Some(api::CategoryFull {
-                label: category_name.to_string(),
+                label: category.label_or(&category_name).to_owned(),
                 products: results,
                 status: category.status.into_api(),
                 subcategories,
@@ -235,16 +725,17 @@ impl Retriever {
         }
     }
 
+    /// Searches for organisations and products matching the query.
+    ///
+    /// See `Query` for the supported syntax.
     pub fn search_by_text(
         &self,
         query: String,
     ) -> Result<Vec<api::TextSearchResult>, BackendError> {
         let mut collector = ResultCollector::default();
-        let mut tokens: Vec<&str> = query.split(' ').collect();
-        tokens.retain(|m| !m.is_empty());
+        let query = Query::parse(&query);
 
-        if tokens.len() == 1 {
-            let token = tokens.first().unwrap();
+        if let Some(token) = query.single_keyword() {
             match token.parse::<u64>() {
                 Ok(number) => {
                     // Search product by GTIN
@@ -263,7 +754,13 @@ impl Retriever {
         }
 
         // Search organisations and products by keyword
-        let keywords: Vec<String> = tokens.into_iter().map(|m| m.to_lowercase()).collect();
+        let keywords = query.lookup_tokens();
+        for (i, keyword) in keywords.iter().enumerate() {
+            if let Some(domain) = query::as_domain(keyword) {
+                let items = self.organisations_by_domain(&domain)?;
+                collector.add_exact_organisations(items, i);
+            }
+        }
         for (i, keyword) in keywords.iter().enumerate() {
             let items = self.organisations_by_keyword(keyword)?;
             collector.add_organisations(items, keyword, Some(i));
@@ -273,6 +770,14 @@ impl Retriever {
             collector.add_products(items, keyword, Some(i));
         }
 
+        collector.apply_ranks();
+        collector.apply_strictness(keywords.len(), self.search_strictness);
+        collector.retain_matching(&query);
+
+        if !query.brands.is_empty() {
+            let manufacturers = self.brand_manufacturers(&query, collector.manufacturer_ids())?;
+            collector.retain_manufacturers(&manufacturers);
+        }
         Ok(collector.gather_results())
     }
 }
@@ -375,13 +880,10 @@ impl Retriever {
     ) -> Result<Vec<api::CategoryAlternatives>, BackendError> {
         let mut result = Vec::new();
         for category in categories.iter() {
-            // TODO: format the category nicely.
-            let category_label = category.text.clone();
             let category_id = Self::encode_category_param(&category.text);
 
-            let excluded = vec![id.clone()];
-            if let Some(alternatives) =
-                self.product_category_alternatives(&category.text, region_code, &excluded)?
+            if let Some((category_label, alternatives)) =
+                self.product_category_alternatives(&category.text, region_code, &id)?
             {
                 result.push(api::CategoryAlternatives {
                     category_id,
@@ -393,29 +895,40 @@ impl Retriever {
         Ok(result)
     }
 
+    /// Returns the label of the category and the best alternatives in it.
     fn product_category_alternatives(
         &self,
-        category: &String,
+        category_name: &String,
         region_code: Option<&str>,
-        excluded: &[ids::ProductId],
-    ) -> Result<Option<Vec<api::ProductShort>>, BackendError> {
+        compared: &ids::ProductId,
+    ) -> Result<Option<(String, Vec<api::ProductShort>)>, BackendError> {
         let categories = self.db.get_categories_bucket()?;
         let products = self.db.get_product_bucket()?;
-        if let Some(category) = categories.get(category)? {
+        let availability = self.app.get_gtin_to_retailer_availability_bucket()?;
+        if let Some(category) = categories.get(category_name)? {
             let mut rng = rand::rng();
             // TODO: Do this during precomputation and here only filter by region
             let mut results = Vec::new();
+            let compared_quantity = products
+                .get(compared)?
+                .and_then(|product| product.quantity())
+                .or(category.quantity);
             if let Some(product_ids) = &category.products {
                 for product_id in product_ids {
-                    if excluded.contains(product_id) {
+                    if product_id == compared {
                         continue;
                     }
                     if let Some(product) = products.get(product_id)? {
-                        if product.availability.regions.is_available_in(region_code) {
+                        let product = Self::overlay_availability(&availability, product)?;
+                        if !Self::may_be_available_in(&product.availability.regions, region_code) {
                             continue;
                         }
 
-                        let score = product.score();
+                        let score = product.score()
+                            + Self::quantity_match_score(
+                                compared_quantity.as_ref(),
+                                product.quantity().as_ref(),
+                            );
                         let randomized_score = score + rng.random_range(0.0..0.01);
                         results.push((randomized_score, product));
                     }
@@ -423,13 +936,65 @@ impl Retriever {
             }
             results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
             results.truncate(10);
-            Ok(Some(results.iter().map(|r| r.1.clone().into_api_short()).collect()))
+            let label = category.label_or(category_name).to_owned();
+            Ok(Some((label, results.iter().map(|r| r.1.clone().into_api_short()).collect())))
         } else {
-            tracing::warn!(category, "Category not found");
+            tracing::warn!(category = category_name, "Category not found");
             Ok(None)
         }
     }
 
+    /// Scores how well the quantity of an alternative matches the quantity of the compared product.
+    fn quantity_match_score(
+        compared: Option<&store::ProductQuantity>,
+        alternative: Option<&store::ProductQuantity>,
+    ) -> f64 {
+        match (compared, alternative) {
+            (Some(compared), Some(alternative)) => {
+                QUANTITY_MATCH_SCORE * compared.similarity(alternative)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Applies availability reported in retailer feeds on top of the crystalized data.
+    fn overlay_availability(
+        availability: &Bucket<'_, store::Gtin, Vec<store::RetailerAvailability>, Versioned>,
+        mut product: store::Product,
+    ) -> Result<store::Product, BackendError> {
+        for gtin in &product.ids.gtins {
+            if let Some(entries) = availability.get(&gtin.id)? {
+                product.availability.overlay(&entries);
+            }
+        }
+        Ok(product)
+    }
+
+    /// Checks if the product may be offered in the region.
+    ///
+    /// Products of unknown availability are kept, as are all the products if no region is given.
+    fn may_be_available_in(regions: &store::Regions, region_code: Option<&str>) -> bool {
+        region_code.is_none() || regions.is_unknown() || regions.is_available_in(region_code)
+    }
+
+    /// Selects the manufacturers whose names match the brands of the query.
+    fn brand_manufacturers(
+        &self,
+        query: &Query,
+        candidates: HashSet<ids::OrganisationId>,
+    ) -> Result<HashSet<ids::OrganisationId>, BackendError> {
+        let mut result = HashSet::new();
+        let organisations = self.db.get_organisation_bucket()?;
+        for organisation_id in candidates {
+            if let Some(organisation) = organisations.get(&organisation_id)?
+                && organisation.names.iter().any(|name| query.matches_brand(&name.text))
+            {
+                result.insert(organisation_id);
+            }
+        }
+        Ok(result)
+    }
+
     fn products_by_token(&self, token: u64) -> Result<Vec<ProductSearchResult>, BackendError> {
         let gtins = self.db.get_gtin_to_product_id_bucket()?;
         if let Some(product_id) = gtins.get(&ids::Gtin::new(token))? {
@@ -449,37 +1014,35 @@ impl Retriever {
         token: &str,
     ) -> Result<Vec<OrganisationSearchResult>, BackendError> {
         let mut results = Vec::new();
-        let lowercase_token = token.to_lowercase();
         let uppercase_token = token.to_uppercase();
 
         // TODO: prepare index of not full VAT numbers to speedup search.
-        // TODO: extract domain from token to speedup search
+        // Domains are looked up in `organisations_by_domain`.
         let organisations = self.db.get_organisation_bucket()?;
         for (organisation_id, organisation) in organisations.gather()? {
-            let mut matched = false;
-
-            for vat in &organisation.ids.vat_ids {
-                if vat.id.as_str().contains(&uppercase_token) {
-                    matched = true;
-                    break;
-                }
-            }
-
-            if !matched {
-                let domain = utils::extract_domain_from_str(token)
-                    .unwrap_or_else(|| lowercase_token.to_owned());
-                if organisation.ids.domains.iter().any(|d| d.website == domain) {
-                    matched = true;
-                }
-            }
-
-            if matched {
+            if organisation.ids.vat_ids.iter().any(|vat| vat.id.as_str().contains(&uppercase_token))
+            {
                 results.push(OrganisationSearchResult::from_db(organisation_id, organisation));
             }
         }
         Ok(results)
     }
 
+    fn organisations_by_domain(
+        &self,
+        domain: &str,
+    ) -> Result<Vec<OrganisationSearchResult>, BackendError> {
+        let domains = self.db.get_www_domain_to_organisation_id_bucket()?;
+        if let Some(organisation_id) = domains.get(&domain.to_owned())? {
+            let organisations = self.db.get_organisation_bucket()?;
+            if let Some(organisation) = organisations.get(&organisation_id)? {
+                return Ok(vec![OrganisationSearchResult::from_db(organisation_id, organisation)]);
+            }
+            tracing::warn!(%organisation_id, domain, "Organisation from domain not found");
+        }
+        Ok(Vec::new())
+    }
+
     fn products_by_keyword(
         &self,
         keyword: &String,
@@ -547,6 +1110,21 @@ impl Retriever {
             .collect()
     }
 
+    /// Replaces the raw names in the labels with the human-readable names of the categories.
+    fn label_categories(
+        categories: &Bucket<String, store::Category, Versioned>,
+        shorts: &mut [api::CategoryShort],
+    ) -> Result<(), BackendError> {
+        for short in shorts {
+            if let Some(category) = categories.get(&Self::decode_category_param(&short.id))?
+                && !category.label.is_empty()
+            {
+                short.label = category.label;
+            }
+        }
+        Ok(())
+    }
+
     fn prepare_supercategories(category_name: &str) -> Vec<api::CategoryShort> {
         if category_name.is_empty() {
             return Vec::new();
@@ -685,13 +1263,148 @@ mod tests {
         assert_eq!(collector.gather_scored_results(), expected_results);
     }
 
+    /// Number of matched keywords given as a sorting hint.
+    /// - with `Any` repeated matches of a single keyword may win
+    /// - with `Boost` the result matching all keywords is given a boost and single matches demoted
+    /// - with `All` results not matching all keywords are dropped
+    #[test]
+    fn strictness() {
+        let (r1, r2, r3) = prepare_data();
+
+        let collect = |strictness| {
+            let mut collector = ResultCollector::default();
+            for _ in 0..3 {
+                collector.add(&[r3.clone()], "", Some(0));
+            }
+            for i in 0..3 {
+                collector.add(&[r2.clone()], "", Some(i));
+            }
+            for i in 0..2 {
+                collector.add(&[r1.clone()], "", Some(i));
+            }
+            collector.apply_strictness(3, strictness);
+            collector.gather_results()
+        };
+
+        assert_eq!(collect(SearchStrictness::Any), vec![r3.1.clone(), r2.1.clone(), r1.1.clone()]);
+        assert_eq!(
+            collect(SearchStrictness::Boost),
+            vec![r2.1.clone(), r1.1.clone(), r3.1.clone()]
+        );
+        assert_eq!(collect(SearchStrictness::All), vec![r2.1.clone()]);
+    }
+
+    #[test]
+    fn manufacturer_filter() {
+        let (r1, r2, r3) = prepare_data();
+
+        let mut collector = ResultCollector::default();
+        collector.add(&[r1.clone(), r2.clone(), r3.clone()], "", None);
+        let fairphone = ids::OrganisationId::from_value(1);
+        let samsung = ids::OrganisationId::from_value(2);
+        collector.manufacturers.insert(r1.0.clone(), vec![fairphone.clone()]);
+        collector.manufacturers.insert(r2.0.clone(), vec![samsung.clone()]);
+        assert_eq!(collector.manufacturer_ids(), HashSet::from([fairphone.clone(), samsung]));
+
+        collector.retain_manufacturers(&HashSet::from([fairphone]));
+        assert_eq!(collector.gather_results(), vec![r1.1.clone()]);
+    }
+
+    /// Organisations with many products, certifications and sources are given a boost.
+    #[test]
+    fn organisation_ranks() {
+        let organisation = |id: u64, name: &str, rank| OrganisationSearchResult {
+            id: id.to_string(),
+            ids: store::OrganisationIds {
+                wiki: vec![store::SourcedWikiId { id: ids::WikiId::new(id), sources: Vec::new() }],
+                vat_ids: Vec::new(),
+                domains: Vec::new(),
+            },
+            name: Some(store::Text {
+                text: name.to_owned(),
+                sources: vec![store::Source::Wikidata],
+            }),
+            rank,
+        };
+        let notable = store::SearchRank { num_products: 200, num_certifications: 1, notability: 5 };
+
+        let collect = |apply_ranks| {
+            let mut collector = ResultCollector::default();
+            collector.add_organisations(
+                vec![
+                    organisation(1, "Apple", store::SearchRank::default()),
+                    organisation(2, "Apple Inc.", notable.clone()),
+                ],
+                "apple",
+                Some(0),
+            );
+            if apply_ranks {
+                collector.apply_ranks();
+            }
+            collector.gather_results().into_iter().map(|r| r.label.to_string()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(collect(false), vec!["Apple", "Apple Inc."]);
+        assert_eq!(collect(true), vec!["Apple Inc.", "Apple"]);
+        assert!((rank_boost(&store::SearchRank::default()) - 1.0).abs() < f64::EPSILON);
+    }
+
+    /// Alternatives of a similar quantity in the same unit are preferred.
+    #[test]
+    fn quantity_match() {
+        let q = |amount, unit| store::ProductQuantity { amount, unit };
+        let half_kilo = q(0.5, store::QuantityUnit::Kilogram);
+
+        let same = Retriever::quantity_match_score(Some(&half_kilo), Some(&half_kilo));
+        let similar = Retriever::quantity_match_score(
+            Some(&half_kilo),
+            Some(&q(0.4, store::QuantityUnit::Kilogram)),
+        );
+        let other_unit = Retriever::quantity_match_score(
+            Some(&half_kilo),
+            Some(&q(0.5, store::QuantityUnit::Litre)),
+        );
+        let unknown = Retriever::quantity_match_score(None, Some(&half_kilo));
+
+        assert!(same > similar);
+        assert!(similar > other_unit);
+        assert_eq!(other_unit, 0.0);
+        assert_eq!(unknown, 0.0);
+    }
+
+    /// Alternatives are limited to the products available in the region, including the regions
+    /// reported by the retailer feeds.
+    #[test]
+    fn alternatives_availability() {
+        use isocountry::CountryCode::{DEU, POL};
+
+        let mut availability =
+            store::Availability { regions: store::Regions::List(vec![DEU]), ..Default::default() };
+        assert!(Retriever::may_be_available_in(&availability.regions, Some("DEU")));
+        assert!(!Retriever::may_be_available_in(&availability.regions, Some("POL")));
+        assert!(Retriever::may_be_available_in(&availability.regions, None));
+
+        availability.overlay(&[store::RetailerAvailability {
+            retailer: "shop".to_owned(),
+            region: POL,
+            available: true,
+            url: None,
+        }]);
+        assert!(Retriever::may_be_available_in(&availability.regions, Some("POL")));
+
+        assert!(Retriever::may_be_available_in(&store::Regions::World, Some("POL")));
+        assert!(Retriever::may_be_available_in(&store::Regions::Unknown, Some("POL")));
+    }
+
     /// Tests if the subcategories are prepared correctly in the most common case.
     #[test]
     fn prepare_subcategories() {
         let category = store::Category {
             status: store::CategoryStatus::Incomplete,
             subcategories: vec!["mobile_phones".to_string()],
+            label: String::new(),
             products: None,
+            quantity: None,
         };
         let obtained =
             Retriever::prepare_subcategories("electronics.communications.telephony", &category);
@@ -708,7 +1421,9 @@ mod tests {
         let category = store::Category {
             status: store::CategoryStatus::Incomplete,
             subcategories: vec!["sub1".to_string(), "sub2".to_string()],
+            label: String::new(),
             products: None,
+            quantity: None,
         };
         let obtained = Retriever::prepare_subcategories("", &category);
         let expected = vec![
@@ -752,4 +1467,68 @@ mod tests {
         let expected = vec![api::CategoryShort { id: "top".to_owned(), label: "top".to_owned() }];
         assert_eq!(obtained, expected);
     }
+
+    /// Tests if the keywords matching the most products are selected for warming.
+    #[test]
+    fn hottest_keywords() {
+        let dir = tempfile::tempdir().unwrap();
+        let retriever = Retriever::new(dir.path().to_str().unwrap()).unwrap();
+
+        let ids = |n: u32| (0..n).map(|i| ids::ProductId::from_value(i)).collect::<Vec<_>>();
+        let keywords = retriever.db.get_keyword_to_product_ids_bucket().unwrap();
+        keywords.insert(&"fairphone".to_owned(), &ids(3)).unwrap();
+        keywords.insert(&"phone".to_owned(), &ids(10)).unwrap();
+        keywords.insert(&"shift".to_owned(), &ids(1)).unwrap();
+        keywords.flush().unwrap();
+
+        let obtained = retriever.hottest_keywords(2).unwrap();
+        assert_eq!(obtained, vec!["phone".to_owned(), "fairphone".to_owned()]);
+
+        // No products are stored, so only the keyword entries are read.
+        let warmed = retriever.warm_keywords(&obtained, 5).unwrap();
+        assert_eq!(warmed, 2);
+    }
+
+    /// Tests if lookups of missing GTINs are recorded at most once per interval and listed.
+    #[test]
+    fn missed_lookups() {
+        let dir = tempfile::tempdir().unwrap();
+        let retriever = Retriever::new(dir.path().to_str().unwrap()).unwrap();
+        let gtin = api::ProductIdVariant::Gtin;
+
+        retriever.missing_product(gtin, "4006381333931").unwrap();
+
+        // Recorded too recently, so not counted again.
+        retriever.missing_product(gtin, "4006381333931").unwrap();
+        retriever.missing_product(gtin, "5901234123457").unwrap();
+        retriever.missing_product(api::ProductIdVariant::Wiki, "Q1").unwrap();
+
+        // Nothing is written until the lookups are flushed.
+        let lookups = retriever.app.get_missed_lookup_bucket().unwrap();
+        let key = ids::Gtin::new(4006381333931);
+        assert_eq!(lookups.get(&key).unwrap(), None);
+        assert_eq!(retriever.flush_missed_lookups().unwrap(), 2);
+        assert_eq!(retriever.flush_missed_lookups().unwrap(), 0);
+
+        let mut lookup = lookups.get(&key).unwrap().unwrap();
+        assert_eq!(lookup.count, 1);
+        lookup.count = 5;
+        lookups.insert(&key, &lookup).unwrap();
+        lookups.flush().unwrap();
+
+        let obtained = retriever.most_missed_lookups(1).unwrap();
+        assert_eq!(obtained, vec![(key, lookup)]);
+    }
+
+    /// Tests if at most `MAX_PENDING_MISSED_LOOKUPS` lookups wait for being written.
+    #[test]
+    fn missed_lookups_are_limited_globally() {
+        let limiter = MissedLookupLimiter::default();
+        let now = Instant::now();
+        for number in 0..2 * MAX_PENDING_MISSED_LOOKUPS {
+            limiter.record(ids::Gtin::new(u64::try_from(number).unwrap()), now, 0);
+        }
+        assert_eq!(limiter.take_pending().len(), MAX_PENDING_MISSED_LOOKUPS);
+        assert!(limiter.take_pending().is_empty());
+    }
 }
@@ -2,9 +2,21 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::marker::PhantomData;
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
 use async_trait::async_trait;
+use hyper::{
+    Request, Response, StatusCode,
+    header::{HeaderName, HeaderValue},
+    http::uri::{PathAndQuery, Uri},
+    service::Service,
+};
 use swagger::ApiError;
 
 use transpaer_api::{
@@ -13,24 +25,151 @@ use transpaer_api::{
     models::{LibraryContents, OrganisationIdVariant, ProductIdVariant, TextSearchResults},
 };
 
-use crate::retrieve;
+use crate::{access_log::AccessLog, errors::BackendError, pool::BlockingPool, retrieve};
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// Version of the API.
+///
+/// Requests select the version with a path prefix, e.g. `/v1/library`.
+/// Requests without the prefix are served by the current version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    /// Version served to requests without a version prefix.
+    pub const CURRENT: Self = Self::V1;
+
+    /// Returns the path prefix selecting this version.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::V1 => "/v1",
+        }
+    }
+
+    /// Splits the path into the version and the path within that version.
+    ///
+    /// Returns `None` if the path has a version prefix not matching any known version.
+    fn split_path(path: &str) -> Option<(Self, &str)> {
+        let Some(rest) = path.strip_prefix("/v") else { return Some((Self::CURRENT, path)) };
+        let end = rest.find('/').unwrap_or(rest.len());
+        if end == 0 || !rest[..end].bytes().all(|b| b.is_ascii_digit()) {
+            return Some((Self::CURRENT, path));
+        }
+
+        let version = match &rest[..end] {
+            "1" => Self::V1,
+            _ => return None,
+        };
+        let path = &rest[end..];
+        Some((version, if path.is_empty() { "/" } else { path }))
+    }
+}
+
+/// Deprecation of an operation, or of all operations, of an API version.
+///
+/// Responses of deprecated operations carry the `Deprecation` header and, if the removal date is
+/// known, the `Sunset` header. After the sunset the operations respond with `410 Gone`.
+#[derive(Clone, Debug)]
+pub struct Deprecation {
+    /// Deprecated API version.
+    pub version: ApiVersion,
+
+    /// Prefix of the deprecated paths within the version. Empty deprecates the whole version.
+    pub path_prefix: &'static str,
+
+    /// Time of deprecation in seconds since the Unix epoch.
+    pub deprecated_at: u64,
+
+    /// Time of removal in seconds since the Unix epoch.
+    pub sunset_at: Option<u64>,
+}
+
+impl Deprecation {
+    /// Checks if the path is the prefix itself or lies under it, e.g. `/library` matches
+    /// `/library/topic` but not `/library-old`.
+    fn matches(&self, version: ApiVersion, path: &str) -> bool {
+        self.version == version
+            && path
+                .strip_prefix(self.path_prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    fn is_sunset(&self, now: SystemTime) -> bool {
+        self.sunset_at.is_some_and(|sunset| now >= epoch_time(sunset))
+    }
+
+    fn apply<RB>(&self, response: &mut Response<RB>) {
+        let headers = response.headers_mut();
+        if let Ok(deprecation) = HeaderValue::try_from(format!("@{}", self.deprecated_at)) {
+            headers.insert(DEPRECATION, deprecation);
+        }
+        if let Some(sunset) = self.sunset_at {
+            let sunset = httpdate::fmt_http_date(epoch_time(sunset));
+            if let Ok(sunset) = HeaderValue::try_from(sunset) {
+                headers.insert(SUNSET, sunset);
+            }
+        }
+    }
+}
 
-const CORS_ORIGIN: &str = "*";
-const CORS_METHODS: &str = "GET, POST, DELETE, OPTIONS";
-const CORS_HEADERS: &str = "Origin, Content-Type";
+/// Deprecated operations.
+///
+/// To retire an operation, add an entry here and let it live through a release before the sunset.
+pub const DEPRECATIONS: &[Deprecation] = &[];
+
+fn epoch_time(seconds: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+}
 
 #[derive(Clone)]
 pub struct Server<C> {
-    retriever: retrieve::Retriever,
+    retriever: Arc<retrieve::Retriever>,
+    pool: BlockingPool,
+    access_log: Option<AccessLog>,
     marker: PhantomData<C>,
 }
 
 impl<C> Server<C> {
-    pub fn new(retriever: retrieve::Retriever) -> Self {
-        Server { retriever, marker: PhantomData }
+    pub fn new(retriever: Arc<retrieve::Retriever>, pool: BlockingPool) -> Self {
+        Server { retriever, pool, access_log: None, marker: PhantomData }
+    }
+
+    /// Records a sample of the operations in the access log.
+    pub fn with_access_log(mut self, access_log: Option<AccessLog>) -> Self {
+        self.access_log = access_log;
+        self
+    }
+
+    /// Records the operation in the access log, if enabled.
+    fn log_access(
+        &self,
+        operation: &'static str,
+        query: Option<&str>,
+        results: usize,
+        start: Instant,
+    ) {
+        if let Some(access_log) = &self.access_log {
+            access_log.record(operation, query, results, start.elapsed());
+        }
+    }
+
+    /// Runs the retrieval in the worker pool so that the blocking database reads do not stall
+    /// other requests handled by the same runtime thread.
+    async fn retrieve<T, F>(&self, retrieval: F) -> Result<T, BackendError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&retrieve::Retriever) -> Result<T, BackendError> + Send + 'static,
+    {
+        let retriever = self.retriever.clone();
+        self.pool.run(move || retrieval(&retriever)).await
     }
 }
 
+// The CORS headers are left empty, the `cors::Cors` layer sets them on all the responses.
 #[async_trait]
 impl<C> Api<C> for Server<C>
 where
@@ -39,20 +178,20 @@ where
     async fn check_health(&self, _context: &C) -> Result<CheckHealthResponse, ApiError> {
         tracing::info_span!("request", request = "health-check");
         Ok(CheckHealthResponse::Ok {
-            access_control_allow_origin: CORS_ORIGIN.to_string(),
-            access_control_allow_methods: CORS_METHODS.to_string(),
-            access_control_allow_headers: CORS_HEADERS.to_string(),
+            access_control_allow_origin: String::new(),
+            access_control_allow_methods: String::new(),
+            access_control_allow_headers: String::new(),
         })
     }
 
     async fn get_library(&self, _context: &C) -> Result<GetLibraryResponse, ApiError> {
         tracing::info_span!("request", request = "get-library");
-        let items = self.retriever.library_contents()?;
+        let items = self.retrieve(retrieve::Retriever::library_contents).await?;
         Ok(GetLibraryResponse::Ok {
             body: LibraryContents { items },
-            access_control_allow_origin: CORS_ORIGIN.to_string(),
-            access_control_allow_methods: CORS_METHODS.to_string(),
-            access_control_allow_headers: CORS_HEADERS.to_string(),
+            access_control_allow_origin: String::new(),
+            access_control_allow_methods: String::new(),
+            access_control_allow_headers: String::new(),
         })
     }
 
@@ -62,18 +201,19 @@ where
         _context: &C,
     ) -> Result<GetLibraryItemResponse, ApiError> {
         tracing::info_span!("request", request = "get-library-item", topic);
-        if let Some(item) = self.retriever.library_item(&topic)? {
+        let item = self.retrieve(move |retriever| retriever.library_item(&topic)).await?;
+        if let Some(item) = item {
             Ok(GetLibraryItemResponse::Ok {
                 body: item,
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         } else {
             Ok(GetLibraryItemResponse::NotFound {
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         }
     }
@@ -84,12 +224,15 @@ where
         _context: &C,
     ) -> Result<SearchByTextResponse, ApiError> {
         tracing::info_span!("request", request = "search-by-text", query);
-        let results = self.retriever.search_by_text(query)?;
+        let start = Instant::now();
+        let logged_query = query.clone();
+        let results = self.retrieve(move |retriever| retriever.search_by_text(query)).await?;
+        self.log_access("search-by-text", Some(&logged_query), results.len(), start);
         Ok(SearchByTextResponse::Ok {
             body: TextSearchResults { results },
-            access_control_allow_origin: CORS_ORIGIN.to_string(),
-            access_control_allow_methods: CORS_METHODS.to_string(),
-            access_control_allow_headers: CORS_HEADERS.to_string(),
+            access_control_allow_origin: String::new(),
+            access_control_allow_methods: String::new(),
+            access_control_allow_headers: String::new(),
         })
     }
 
@@ -100,18 +243,21 @@ where
         _context: &C,
     ) -> Result<GetOrganisationResponse, ApiError> {
         tracing::info_span!("request", request = "get-organisation", %id_variant, organisation_id = %id);
-        if let Some(org) = self.retriever.organisation(id_variant, &id)? {
+        let start = Instant::now();
+        let org = self.retrieve(move |retriever| retriever.organisation(id_variant, &id)).await?;
+        self.log_access("get-organisation", None, usize::from(org.is_some()), start);
+        if let Some(org) = org {
             Ok(GetOrganisationResponse::Ok {
                 body: org,
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         } else {
             Ok(GetOrganisationResponse::NotFound {
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         }
     }
@@ -124,18 +270,29 @@ where
         _context: &C,
     ) -> Result<GetProductResponse, ApiError> {
         tracing::info_span!("request", request = "get-product", %id_variant, product_id = %id);
-        if let Some(prod) = self.retriever.product(id_variant, &id, region.as_deref())? {
+        let start = Instant::now();
+        let prod = self
+            .retrieve(move |retriever| {
+                let prod = retriever.product(id_variant, &id, region.as_deref())?;
+                if prod.is_none() {
+                    retriever.missing_product(id_variant, &id)?;
+                }
+                Ok(prod)
+            })
+            .await?;
+        self.log_access("get-product", None, usize::from(prod.is_some()), start);
+        if let Some(prod) = prod {
             Ok(GetProductResponse::Ok {
                 body: prod,
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         } else {
             Ok(GetProductResponse::NotFound {
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         }
     }
@@ -148,13 +305,21 @@ where
         _context: &C,
     ) -> Result<GetAlternativesResponse, ApiError> {
         tracing::info_span!("request", request = "get-alternatives", %id_variant, product_id = %id, region);
-        let alternatives =
-            self.retriever.product_alternatives(id_variant, &id, region.as_deref())?;
+        let start = Instant::now();
+        let alternatives = self
+            .retrieve(move |retriever| {
+                retriever.product_alternatives(id_variant, &id, region.as_deref())
+            })
+            .await?;
+        let num_alternatives = alternatives.as_ref().map_or(0, |alternatives| {
+            alternatives.iter().map(|category| category.alternatives.len()).sum()
+        });
+        self.log_access("get-alternatives", None, num_alternatives, start);
         Ok(GetAlternativesResponse::Ok {
             body: alternatives.unwrap_or_else(Vec::new),
-            access_control_allow_origin: CORS_ORIGIN.to_string(),
-            access_control_allow_methods: CORS_METHODS.to_string(),
-            access_control_allow_headers: CORS_HEADERS.to_string(),
+            access_control_allow_origin: String::new(),
+            access_control_allow_methods: String::new(),
+            access_control_allow_headers: String::new(),
         })
     }
 
@@ -164,19 +329,172 @@ where
         _context: &C,
     ) -> Result<GetCategoryResponse, ApiError> {
         tracing::info_span!("request", request = "get-category", category = %category_id);
-        if let Some(category) = self.retriever.category(category_id)? {
+        let start = Instant::now();
+        let category = self.retrieve(move |retriever| retriever.category(category_id)).await?;
+        self.log_access("get-category", None, usize::from(category.is_some()), start);
+        if let Some(category) = category {
             Ok(GetCategoryResponse::Ok {
                 body: category,
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         } else {
             Ok(GetCategoryResponse::NotFound {
-                access_control_allow_origin: CORS_ORIGIN.to_string(),
-                access_control_allow_methods: CORS_METHODS.to_string(),
-                access_control_allow_headers: CORS_HEADERS.to_string(),
+                access_control_allow_origin: String::new(),
+                access_control_allow_methods: String::new(),
+                access_control_allow_headers: String::new(),
             })
         }
     }
 }
+
+/// Routes requests to the services of the API versions by the version prefix of the path.
+///
+/// The prefix is stripped before passing the request on, so that all versions can be served by
+/// services generated from the same spec and backed by the same retriever. Requests for unknown
+/// versions are answered with `404 Not Found`.
+#[derive(Clone)]
+pub struct Versioned<S> {
+    versions: Vec<(ApiVersion, S)>,
+    deprecations: &'static [Deprecation],
+}
+
+impl<S> Versioned<S> {
+    pub fn new(versions: Vec<(ApiVersion, S)>, deprecations: &'static [Deprecation]) -> Self {
+        Self { versions, deprecations }
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for Versioned<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    RB: Default + Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let route = ApiVersion::split_path(request.uri().path()).and_then(|(version, path)| {
+            let service = self.versions.iter().find(|(v, _)| *v == version).map(|(_, s)| s)?;
+            let deprecation = self.deprecations.iter().find(|d| d.matches(version, path));
+            let uri = replace_path(request.uri(), path)?;
+            Some((service, deprecation, uri))
+        });
+        let Some((service, deprecation, uri)) = route else {
+            let mut response = Response::new(RB::default());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            return Box::pin(async move { Ok(response) });
+        };
+
+        if let Some(deprecation) = deprecation.filter(|d| d.is_sunset(SystemTime::now())) {
+            let mut response = Response::new(RB::default());
+            *response.status_mut() = StatusCode::GONE;
+            deprecation.apply(&mut response);
+            return Box::pin(async move { Ok(response) });
+        }
+
+        *request.uri_mut() = uri;
+        let deprecation = deprecation.cloned();
+        let future = service.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Some(deprecation) = deprecation {
+                deprecation.apply(&mut response);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Replaces the path of the URI keeping the query.
+pub(crate) fn replace_path(uri: &Uri, path: &str) -> Option<Uri> {
+    let path_and_query = match uri.query() {
+        Some(query) => PathAndQuery::try_from(format!("{path}?{query}")),
+        None => PathAndQuery::try_from(path),
+    };
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(path_and_query.ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPRECATED: &[Deprecation] = &[
+        Deprecation {
+            version: ApiVersion::V1,
+            path_prefix: "/library",
+            deprecated_at: 1_700_000_000,
+            sunset_at: Some(4_000_000_000),
+        },
+        Deprecation {
+            version: ApiVersion::V1,
+            path_prefix: "/categories",
+            deprecated_at: 1_600_000_000,
+            sunset_at: Some(1_700_000_000),
+        },
+    ];
+
+    /// Calls a versioned service responding with the URI of the request.
+    async fn call(path: &str) -> Response<String> {
+        let echo = hyper::service::service_fn(|request: Request<()>| async move {
+            Ok::<_, std::convert::Infallible>(Response::new(request.uri().to_string()))
+        });
+        let service = Versioned::new(vec![(ApiVersion::V1, echo)], DEPRECATED);
+        let request = Request::get(path).body(()).unwrap();
+        service.call(request).await.unwrap()
+    }
+
+    #[test]
+    fn split_path() {
+        assert_eq!(ApiVersion::split_path("/v1/library"), Some((ApiVersion::V1, "/library")));
+        assert_eq!(ApiVersion::split_path("/v1"), Some((ApiVersion::V1, "/")));
+        assert_eq!(ApiVersion::split_path("/library"), Some((ApiVersion::V1, "/library")));
+        assert_eq!(ApiVersion::split_path("/vegan"), Some((ApiVersion::V1, "/vegan")));
+        assert_eq!(ApiVersion::split_path("/v9/library"), None);
+    }
+
+    #[tokio::test]
+    async fn routes_versions() {
+        let response = call("/v1/products/ean/123?region=world").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "/products/ean/123?region=world");
+        assert!(response.headers().get(DEPRECATION).is_none());
+
+        let response = call("/products/ean/123").await;
+        assert_eq!(response.body(), "/products/ean/123");
+
+        let response = call("/v2/products/ean/123").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn marks_deprecated_operations() {
+        let response = call("/v1/library/topic").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(DEPRECATION).unwrap(), "@1700000000");
+        assert_eq!(response.headers().get(SUNSET).unwrap(), "Tue, 02 Oct 2096 07:06:40 GMT");
+
+        let response = call("/v1/categories/food").await;
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert_eq!(response.headers().get(DEPRECATION).unwrap(), "@1600000000");
+        assert_eq!(response.headers().get(SUNSET).unwrap(), "Tue, 14 Nov 2023 22:13:20 GMT");
+    }
+
+    #[tokio::test]
+    async fn matches_deprecated_paths_by_segments() {
+        let response = call("/v1/library").await;
+        assert_eq!(response.headers().get(DEPRECATION).unwrap(), "@1700000000");
+
+        let response = call("/v1/library-old/topic").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(DEPRECATION).is_none());
+
+        let response = call("/v1/categoriesx").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,305 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Service layer streaming large collections in chunked responses.
+//!
+//! The generated API service serializes whole response bodies in memory, which for organisations
+//! with thousands of products takes a lot of memory per request. This layer serves such
+//! organisations itself: the body is serialized on a dedicated blocking task product by product and
+//! sent with chunked transfer encoding as it is written, so only a few chunks are held at once.
+//!
+//! The serialization runs outside of the database worker pool, as its pace is set by the client.
+//! A client not receiving a chunk for `SEND_TIMEOUT` aborts the stream, so that stalled clients
+//! do not hold blocking threads indefinitely.
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    io::{self, Write},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use http_body_util::combinators::BoxBody;
+use hyper::{
+    Method, Request, Response,
+    body::{Body, Bytes, Frame},
+    header::{self, HeaderValue},
+    service::Service,
+};
+use tokio::{runtime::Handle, sync::mpsc};
+
+use transpaer_api::models::OrganisationIdVariant;
+
+use crate::{pool::BlockingPool, retrieve::Retriever};
+
+/// Size of the chunks the streamed responses are sent in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of chunks buffered before the serialization waits for the client to receive them.
+const MAX_BUFFERED_CHUNKS: usize = 4;
+
+/// Time the client has to receive a chunk before the stream is aborted.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Response body receiving chunks from a `ChunkWriter`.
+pub struct ChunkedBody {
+    chunks: mpsc::Receiver<Bytes>,
+}
+
+impl Body for ChunkedBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.get_mut().chunks.poll_recv(cx).map(|chunk| chunk.map(|chunk| Ok(Frame::data(chunk))))
+    }
+}
+
+/// Body types able to carry streamed responses.
+pub trait StreamableBody {
+    fn from_chunks(body: ChunkedBody) -> Self;
+}
+
+impl StreamableBody for BoxBody<Bytes, Infallible> {
+    fn from_chunks(body: ChunkedBody) -> Self {
+        BoxBody::new(body)
+    }
+}
+
+/// Writer sending the written bytes to a `ChunkedBody` in chunks.
+///
+/// Blocks when the client does not keep up, so it must be used only on blocking threads. Fails
+/// with `BrokenPipe` when the client goes away and with `TimedOut` when the client stops receiving
+/// the chunks, which stops the serialization.
+pub struct ChunkWriter {
+    buffer: Vec<u8>,
+    sender: mpsc::Sender<Bytes>,
+    runtime: Handle,
+    timeout: Duration,
+}
+
+impl ChunkWriter {
+    fn send(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        match self
+            .runtime
+            .block_on(tokio::time::timeout(self.timeout, self.sender.send(chunk.into())))
+        {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) => Err(io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected")),
+            Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "client stopped receiving")),
+        }
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
+
+/// Creates a writer and the body receiving what is written into it.
+///
+/// The writer fails if a chunk is not received within `timeout`. Must be called from within the
+/// `tokio` runtime.
+pub fn chunked(timeout: Duration) -> (ChunkWriter, ChunkedBody) {
+    let (sender, chunks) = mpsc::channel(MAX_BUFFERED_CHUNKS);
+    let writer = ChunkWriter {
+        buffer: Vec::with_capacity(CHUNK_SIZE),
+        sender,
+        runtime: Handle::current(),
+        timeout,
+    };
+    (writer, ChunkedBody { chunks })
+}
+
+/// Recognises paths of the organisation endpoint, e.g. `/organisations/wiki/Q123`.
+fn organisation_path(path: &str) -> Option<(OrganisationIdVariant, String)> {
+    let mut segments = path.strip_prefix('/')?.split('/');
+    let (kind, variant, id) = (segments.next()?, segments.next()?, segments.next()?);
+    if kind != "organisations" || segments.next().is_some() || id.is_empty() {
+        return None;
+    }
+    Some((variant.parse().ok()?, id.to_owned()))
+}
+
+/// Database access and configuration of the streaming.
+#[derive(Clone)]
+pub struct StreamingSource {
+    pub retriever: Arc<Retriever>,
+    pub pool: BlockingPool,
+
+    /// Minimal number of products of an organisation for its page to be streamed.
+    pub threshold: usize,
+}
+
+impl StreamingSource {
+    /// Starts streaming the organisation if it has enough products.
+    ///
+    /// Returns `None` if the organisation should be served by the API service.
+    async fn stream_organisation(
+        self,
+        variant: OrganisationIdVariant,
+        id: String,
+    ) -> Option<ChunkedBody> {
+        let retriever = self.retriever.clone();
+        let lookup_id = id.clone();
+        let count =
+            self.pool.run(move || retriever.organisation_product_count(variant, &lookup_id));
+        let num_products = match count.await {
+            Ok(num_products) => num_products?,
+            Err(error) => {
+                tracing::warn!(%error, "Failed to count organisation products");
+                return None;
+            }
+        };
+        if num_products < self.threshold {
+            return None;
+        }
+
+        tracing::info!(num_products, "Streaming organisation");
+        let (mut writer, body) = chunked(SEND_TIMEOUT);
+        let retriever = self.retriever;
+        tokio::task::spawn_blocking(move || {
+            match retriever.write_organisation(variant, &id, &mut writer) {
+                Ok(true) => {}
+                Ok(false) => tracing::warn!("Streamed organisation disappeared"),
+                Err(error) => tracing::warn!(%error, "Failed to stream organisation"),
+            }
+        });
+        Some(body)
+    }
+}
+
+/// Wraps the API service streaming the pages of organisations with many products.
+///
+/// Without a source the requests are passed through untouched.
+#[derive(Clone)]
+pub struct StreamLargeCollections<S> {
+    inner: Arc<S>,
+    source: Option<StreamingSource>,
+}
+
+impl<S> StreamLargeCollections<S> {
+    pub fn new(inner: S, source: Option<StreamingSource>) -> Self {
+        Self { inner: Arc::new(inner), source }
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for StreamLargeCollections<S>
+where
+    S: Service<Request<B>, Response = Response<RB>> + Send + Sync + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Send + 'static,
+    RB: StreamableBody + Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, request: Request<B>) -> Self::Future {
+        let target = self.source.clone().and_then(|source| {
+            (request.method() == Method::GET)
+                .then(|| organisation_path(request.uri().path()))
+                .flatten()
+                .map(|(variant, id)| (source, variant, id))
+        });
+        let Some((source, variant, id)) = target else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        let inner = self.inner.clone();
+        Box::pin(async move {
+            match source.stream_organisation(variant, id).await {
+                Some(body) => {
+                    let mut response = Response::new(RB::from_chunks(body));
+                    response
+                        .headers_mut()
+                        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                    Ok(response)
+                }
+                None => inner.call(request).await,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn organisation_paths() {
+        assert_eq!(
+            organisation_path("/organisations/wiki/Q123"),
+            Some((OrganisationIdVariant::Wiki, "Q123".to_owned()))
+        );
+        assert_eq!(organisation_path("/organisations/wiki/"), None);
+        assert_eq!(organisation_path("/organisations/unknown/Q123"), None);
+        assert_eq!(organisation_path("/organisations/wiki/Q123/products"), None);
+        assert_eq!(organisation_path("/products/wiki/Q123"), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn writer_sends_chunks() {
+        let (mut writer, mut body) = chunked(SEND_TIMEOUT);
+        tokio::task::spawn_blocking(move || {
+            writer.write_all(&[1; CHUNK_SIZE + 1]).unwrap();
+            writer.write_all(&[2; 10]).unwrap();
+            writer.flush().unwrap();
+        })
+        .await
+        .unwrap();
+
+        let mut sizes = Vec::new();
+        while let Ok(chunk) = body.chunks.try_recv() {
+            sizes.push(chunk.len());
+        }
+        assert_eq!(sizes, vec![CHUNK_SIZE + 1, 10]);
+
+        // Writing fails once the body is dropped, e.g. when the client disconnects.
+        let (mut writer, body) = chunked(SEND_TIMEOUT);
+        drop(body);
+        let error = tokio::task::spawn_blocking(move || {
+            writer.write_all(b"{}").and_then(|()| writer.flush()).unwrap_err()
+        })
+        .await
+        .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn writer_times_out_on_stalled_client() {
+        let (mut writer, _body) = chunked(Duration::from_millis(10));
+        let error = tokio::task::spawn_blocking(move || {
+            for _ in 0..=MAX_BUFFERED_CHUNKS {
+                writer.write_all(b"{}")?;
+                writer.flush()?;
+            }
+            Ok::<_, io::Error>(())
+        })
+        .await
+        .unwrap()
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -0,0 +1,251 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Serving several datasets, e.g. a staging and a production one, from a single process.
+//!
+//! Every tenant has its own databases and its own database worker pool, so that load on one
+//! tenant does not slow down the others and the pool metrics are reported per tenant. Requests
+//! select the tenant with a path prefix (e.g. `/tenants/staging/v1/library`) or a header.
+//! Requests selecting neither are served by the default tenant.
+
+use std::{collections::HashSet, future::Future, path::Path, pin::Pin};
+
+use hyper::{Request, Response, StatusCode, header::HeaderName, service::Service};
+use serde::Deserialize;
+use snafu::prelude::*;
+
+use crate::{
+    errors::{self, BackendError},
+    server,
+};
+
+/// Prefix of the paths selecting a tenant.
+const PATH_PREFIX: &str = "/tenants/";
+
+/// Name of the tenant when serving a single dataset.
+const SINGLE_TENANT: &str = "default";
+
+/// A single dataset served by the backend.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TenantConfig {
+    /// Name selecting the tenant in requests.
+    pub name: String,
+
+    /// Directory with the `db` and `app` databases of the tenant.
+    pub db_path: String,
+}
+
+/// Tenants served by the backend, read from a JSON file.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TenantsConfig {
+    /// Name of the tenant serving requests which do not select any.
+    pub default: String,
+
+    /// All the served tenants.
+    pub tenants: Vec<TenantConfig>,
+}
+
+impl TenantsConfig {
+    /// Constructs a configuration serving a single dataset.
+    pub fn single(db_path: &str) -> Self {
+        Self {
+            default: SINGLE_TENANT.to_owned(),
+            tenants: vec![TenantConfig {
+                name: SINGLE_TENANT.to_owned(),
+                db_path: db_path.to_owned(),
+            }],
+        }
+    }
+
+    /// Reads and validates the configuration.
+    pub fn read(path: &Path) -> Result<Self, BackendError> {
+        let contents = std::fs::read_to_string(path).context(errors::ReadingSnafu { path })?;
+        let config: Self =
+            serde_json::from_str(&contents).context(errors::DeserializingSnafu { path })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), BackendError> {
+        let mut names = HashSet::new();
+        for tenant in &self.tenants {
+            let valid = !tenant.name.is_empty()
+                && tenant.name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+            ensure!(
+                valid,
+                errors::InvalidTenantsSnafu {
+                    message: format!("`{}` is not a valid tenant name", tenant.name)
+                }
+            );
+            ensure!(
+                names.insert(tenant.name.as_str()),
+                errors::InvalidTenantsSnafu {
+                    message: format!("tenant `{}` is configured twice", tenant.name)
+                }
+            );
+        }
+        ensure!(
+            names.contains(self.default.as_str()),
+            errors::InvalidTenantsSnafu {
+                message: format!("default tenant `{}` is not configured", self.default)
+            }
+        );
+        Ok(())
+    }
+
+    /// Returns the index of the default tenant.
+    pub fn default_index(&self) -> usize {
+        self.tenants.iter().position(|tenant| tenant.name == self.default).unwrap_or(0)
+    }
+}
+
+/// Splits the path into the tenant name and the path within that tenant.
+///
+/// Returns `None` if the path does not select a tenant.
+fn split_path(path: &str) -> Option<(&str, &str)> {
+    let rest = path.strip_prefix(PATH_PREFIX)?;
+    let end = rest.find('/').unwrap_or(rest.len());
+    let path = &rest[end..];
+    Some((&rest[..end], if path.is_empty() { "/" } else { path }))
+}
+
+/// Routes requests to the services of the tenants.
+///
+/// The tenant is selected by the path prefix, which is stripped before passing the request on,
+/// or by the tenant header. Requests for unknown tenants are answered with `404 Not Found`.
+#[derive(Clone)]
+pub struct Tenanted<S> {
+    tenants: Vec<(String, S)>,
+    default: usize,
+    header: HeaderName,
+}
+
+impl<S> Tenanted<S> {
+    pub fn new(tenants: Vec<(String, S)>, default: usize, header: HeaderName) -> Self {
+        Self { tenants, default, header }
+    }
+
+    fn find(&self, name: &str) -> Option<&S> {
+        self.tenants.iter().find(|(n, _)| n == name).map(|(_, service)| service)
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for Tenanted<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    RB: Default + Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, mut request: Request<B>) -> Self::Future {
+        let service = if let Some((name, path)) = split_path(request.uri().path()) {
+            let route = self.find(name).zip(server::replace_path(request.uri(), path));
+            route.map(|(service, uri)| {
+                *request.uri_mut() = uri;
+                service
+            })
+        } else if let Some(name) = request.headers().get(&self.header) {
+            name.to_str().ok().and_then(|name| self.find(name))
+        } else {
+            self.tenants.get(self.default).map(|(_, service)| service)
+        };
+
+        if let Some(service) = service {
+            Box::pin(service.call(request))
+        } else {
+            let mut response = Response::new(RB::default());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            Box::pin(async move { Ok(response) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Calls a tenanted service responding with the tenant name and the URI of the request.
+    async fn call(path: &str, tenant: Option<&str>) -> Response<String> {
+        let echo = |name: &'static str| {
+            hyper::service::service_fn(move |request: Request<()>| async move {
+                Ok::<_, std::convert::Infallible>(Response::new(format!(
+                    "{name} {}",
+                    request.uri()
+                )))
+            })
+        };
+        let service = Tenanted::new(
+            vec![
+                ("production".to_owned(), echo("production")),
+                ("staging".to_owned(), echo("staging")),
+            ],
+            0,
+            HeaderName::from_static("x-transpaer-tenant"),
+        );
+        let mut request = Request::get(path);
+        if let Some(tenant) = tenant {
+            request = request.header("x-transpaer-tenant", tenant);
+        }
+        service.call(request.body(()).unwrap()).await.unwrap()
+    }
+
+    #[test]
+    fn config_validation() {
+        let config: TenantsConfig = serde_json::from_str(
+            r#"{
+                "default": "production",
+                "tenants": [
+                    { "name": "production", "db_path": "/data/production" },
+                    { "name": "staging", "db_path": "/data/staging" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.default_index(), 0);
+        assert!(TenantsConfig::single("/data").validate().is_ok());
+
+        let mut missing_default = config.clone();
+        missing_default.default = "testing".to_owned();
+        assert!(missing_default.validate().is_err());
+
+        let mut duplicate = config.clone();
+        duplicate.tenants[1].name = "production".to_owned();
+        assert!(duplicate.validate().is_err());
+
+        let mut invalid = config;
+        invalid.tenants[1].name = "stag/ing".to_owned();
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn path_splitting() {
+        assert_eq!(split_path("/tenants/staging/v1/library"), Some(("staging", "/v1/library")));
+        assert_eq!(split_path("/tenants/staging"), Some(("staging", "/")));
+        assert_eq!(split_path("/v1/library"), None);
+    }
+
+    #[tokio::test]
+    async fn routes_tenants() {
+        let response = call("/tenants/staging/v1/products/ean/123?region=world", None).await;
+        assert_eq!(response.body(), "staging /v1/products/ean/123?region=world");
+
+        let response = call("/v1/library", Some("staging")).await;
+        assert_eq!(response.body(), "staging /v1/library");
+
+        let response = call("/v1/library", None).await;
+        assert_eq!(response.body(), "production /v1/library");
+
+        let response = call("/tenants/testing/v1/library", None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = call("/v1/library", Some("testing")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
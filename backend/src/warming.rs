@@ -0,0 +1,70 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background warming of the database caches.
+//!
+//! Preloading all the hot buckets before serving (the `--warm-start` option) delays the startup.
+//! The warmer instead runs next to the server and reads the most common keywords together with
+//! their products in small chunks. The chunks go through the worker pool one at a time, so the
+//! warming never occupies more than one worker and the requests are not starved.
+//!
+//! The warming runs once after startup. The backend does not reload databases, so a new database
+//! is taken into use by restarting the backend, which warms it again.
+
+use std::{sync::Arc, time::Instant};
+
+use crate::{errors::BackendError, pool::BlockingPool, retrieve::Retriever};
+
+/// Number of keywords read in one pool task.
+const CHUNK_SIZE: usize = 50;
+
+/// Maximal number of products read for each keyword.
+const PRODUCTS_PER_KEYWORD: usize = 20;
+
+/// Warms up the caches of the storage engine in the background.
+pub struct Warmer {
+    retriever: Arc<Retriever>,
+    pool: BlockingPool,
+
+    /// Number of keywords to warm.
+    keywords: usize,
+}
+
+impl Warmer {
+    pub fn new(retriever: Arc<Retriever>, pool: BlockingPool, keywords: usize) -> Self {
+        Self { retriever, pool, keywords }
+    }
+
+    /// Starts the warming in a background task.
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let start = Instant::now();
+            match self.warm().await {
+                Ok(entries) => tracing::info!(
+                    elapsed_ms = start.elapsed().as_millis(),
+                    entries,
+                    "Background warming finished"
+                ),
+                Err(err) => tracing::warn!("Background warming failed: {err}"),
+            }
+        })
+    }
+
+    async fn warm(&self) -> Result<usize, BackendError> {
+        let retriever = self.retriever.clone();
+        let limit = self.keywords;
+        let keywords = self.pool.run(move || retriever.hottest_keywords(limit)).await?;
+
+        let mut entries = 0;
+        for chunk in keywords.chunks(CHUNK_SIZE) {
+            let retriever = self.retriever.clone();
+            let chunk = chunk.to_vec();
+            entries += self
+                .pool
+                .run(move || retriever.warm_keywords(&chunk, PRODUCTS_PER_KEYWORD))
+                .await?;
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,389 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Contract tests of the backend against the OpenAPI spec.
+//!
+//! The backend binary is started on a fixture database and every operation is called using the
+//! client generated from the spec. The client deserializes the responses into the spec models,
+//! so any drift between the server and the spec shows up as a client error.
+
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream},
+    process::{Child, Command},
+    time::{Duration, Instant},
+};
+
+use swagger::{AuthData, ContextBuilder, EmptyContext, XSpanIdString, make_context};
+
+use transpaer_api::{
+    ApiNoContext, CheckHealthResponse, Client, ContextWrapperExt, GetAlternativesResponse,
+    GetCategoryResponse, GetLibraryItemResponse, GetLibraryResponse, GetOrganisationResponse,
+    GetProductResponse, SearchByTextResponse,
+    models::{
+        CategoryFull, LibraryContents, LibraryItemFull, OrganisationIdVariant, ProductIdVariant,
+    },
+};
+use transpaer_models::{
+    buckets::{AppStore, DbStore},
+    ids,
+    store::{
+        Availability, Category, CategoryStatus, Certifications, LibraryItem, Organisation,
+        OrganisationIds, PriceLevels, Product, ProductIds, Source, SourcedGtin,
+        SourcedOrganisationId, SourcedWikiId, Text, TranspaerOrganisationData,
+        TranspaerProductData,
+    },
+};
+
+type ClientContext =
+    swagger::make_context_ty!(ContextBuilder, EmptyContext, Option<AuthData>, XSpanIdString);
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+const PRODUCT_GTIN: &str = "08717677339556";
+const PRODUCT_WIKI_ID: &str = "109851604";
+const ORGANISATION_WIKI_ID: &str = "5019402";
+const CATEGORY: &str = "electronics.communications";
+const LIBRARY_TOPIC: &str = "info:about";
+
+/// Kills the backend when the test finishes, also on failure.
+struct Backend {
+    process: Child,
+    addr: SocketAddr,
+}
+
+impl Backend {
+    fn start(db_path: &std::path::Path) -> Self {
+        Self::start_with_args(db_path, &[])
+    }
+
+    fn start_with_args(db_path: &std::path::Path, args: &[&str]) -> Self {
+        let addr = free_addr();
+        let process = Command::new(env!("CARGO_BIN_EXE_transpaer-backend"))
+            .arg("--db-path")
+            .arg(db_path)
+            .arg("--address")
+            .arg(addr.to_string())
+            .args(args)
+            .spawn()
+            .expect("start the backend");
+
+        let start = Instant::now();
+        while TcpStream::connect(addr).is_err() {
+            assert!(start.elapsed() < STARTUP_TIMEOUT, "backend did not start in time");
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        Self { process, addr }
+    }
+
+    fn client(&self) -> impl ApiNoContext<ClientContext> {
+        self.client_at("")
+    }
+
+    /// Creates a client sending requests to paths starting with the given prefix.
+    fn client_at(&self, prefix: &str) -> impl ApiNoContext<ClientContext> {
+        let context: ClientContext = make_context!(
+            ContextBuilder,
+            EmptyContext,
+            None as Option<AuthData>,
+            XSpanIdString::default()
+        );
+        Client::try_new_http(&format!("http://{}{prefix}", self.addr))
+            .expect("create the client")
+            .with_context(context)
+    }
+}
+
+impl Drop for Backend {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+fn free_addr() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap()
+}
+
+fn product() -> Product {
+    Product {
+        ids: ProductIds {
+            eans: Vec::new(),
+            gtins: vec![SourcedGtin {
+                id: ids::Gtin::try_from(PRODUCT_GTIN).unwrap(),
+                sources: vec![Source::Wikidata],
+            }],
+            wiki: vec![SourcedWikiId::new(
+                ids::WikiId::try_from(PRODUCT_WIKI_ID).unwrap(),
+                Source::Wikidata,
+            )],
+        },
+        names: vec![Text::new("Fairphone 4", Source::Wikidata)],
+        descriptions: vec![Text::new("smartphone", Source::Wikidata)],
+        summaries: Vec::new(),
+        images: Vec::new(),
+        categories: vec![Text::new("electronics/communications", Source::Wikidata)],
+        google_product_category: Some(262),
+        availability: Availability::default(),
+        origins: Vec::new(),
+        certifications: Certifications::default(),
+        ineligible_badges: Vec::new(),
+        nutri_score: None,
+        price_levels: PriceLevels::default(),
+        manufacturers: vec![SourcedOrganisationId {
+            id: ids::OrganisationId::from_value(1),
+            sources: vec![Source::Wikidata],
+        }],
+        shopping: Vec::new(),
+        media: Vec::new(),
+        reports: Vec::new(),
+        follows: Vec::new(),
+        followed_by: Vec::new(),
+        sitelinks: 0,
+        transpaer: TranspaerProductData::default(),
+    }
+}
+
+fn organisation() -> Organisation {
+    Organisation {
+        ids: OrganisationIds {
+            wiki: vec![SourcedWikiId::new(
+                ids::WikiId::try_from(ORGANISATION_WIKI_ID).unwrap(),
+                Source::Wikidata,
+            )],
+            vat_ids: Vec::new(),
+            domains: Vec::new(),
+        },
+        names: vec![Text::new("Fairphone", Source::Wikidata)],
+        descriptions: Vec::new(),
+        images: Vec::new(),
+        websites: Vec::new(),
+        origins: Vec::new(),
+        products: vec![ids::ProductId::from_value(1)],
+        certifications: Certifications::default(),
+        media: Vec::new(),
+        transpaer: TranspaerOrganisationData::default(),
+    }
+}
+
+/// Prepares a minimal database with one entry for each operation.
+fn prepare_fixture(path: &std::path::Path) {
+    let product_id = ids::ProductId::from_value(1);
+    let organisation_id = ids::OrganisationId::from_value(1);
+
+    let db = DbStore::new(&path.join("db")).unwrap();
+
+    let products = db.get_product_bucket().unwrap();
+    products.insert(&product_id, &product()).unwrap();
+    products.flush().unwrap();
+
+    let gtins = db.get_gtin_to_product_id_bucket().unwrap();
+    gtins.insert(&ids::Gtin::try_from(PRODUCT_GTIN).unwrap(), &product_id).unwrap();
+    gtins.flush().unwrap();
+
+    let product_wiki_ids = db.get_wiki_id_to_product_id_bucket().unwrap();
+    product_wiki_ids.insert(&ids::WikiId::try_from(PRODUCT_WIKI_ID).unwrap(), &product_id).unwrap();
+    product_wiki_ids.flush().unwrap();
+
+    let product_keywords = db.get_keyword_to_product_ids_bucket().unwrap();
+    product_keywords.insert(&"fairphone".to_owned(), &vec![product_id.clone()]).unwrap();
+    product_keywords.flush().unwrap();
+
+    let organisations = db.get_organisation_bucket().unwrap();
+    organisations.insert(&organisation_id, &organisation()).unwrap();
+    organisations.flush().unwrap();
+
+    let organisation_wiki_ids = db.get_wiki_id_to_organisation_id_bucket().unwrap();
+    organisation_wiki_ids
+        .insert(&ids::WikiId::try_from(ORGANISATION_WIKI_ID).unwrap(), &organisation_id)
+        .unwrap();
+    organisation_wiki_ids.flush().unwrap();
+
+    let organisation_keywords = db.get_keyword_to_organisation_ids_bucket().unwrap();
+    organisation_keywords.insert(&"fairphone".to_owned(), &vec![organisation_id.clone()]).unwrap();
+    organisation_keywords.flush().unwrap();
+
+    let categories = db.get_categories_bucket().unwrap();
+    let category = Category {
+        status: CategoryStatus::Satisfactory,
+        subcategories: Vec::new(),
+        label: "Communications".to_owned(),
+        products: Some(vec![product_id.clone()]),
+        quantity: None,
+    };
+    categories.insert(&"electronics/communications".to_owned(), &category).unwrap();
+    let root = Category {
+        status: CategoryStatus::Broad,
+        subcategories: vec!["electronics".to_owned()],
+        label: String::new(),
+        products: None,
+        quantity: None,
+    };
+    categories.insert(&String::new(), &root).unwrap();
+    let electronics = Category {
+        status: CategoryStatus::Broad,
+        subcategories: vec!["communications".to_owned()],
+        label: "Electronics".to_owned(),
+        products: None,
+        quantity: None,
+    };
+    categories.insert(&"electronics".to_owned(), &electronics).unwrap();
+    categories.flush().unwrap();
+
+    let app = AppStore::new(&path.join("app")).unwrap();
+    let library = app.get_library_bucket().unwrap();
+    let item = LibraryItem {
+        id: LIBRARY_TOPIC.to_owned(),
+        title: "About".to_owned(),
+        summary: "About Transpaer".to_owned(),
+        article: "Transpaer helps to make informed choices.".to_owned(),
+        links: Vec::new(),
+    };
+    library.insert(&LIBRARY_TOPIC.to_owned(), &item).unwrap();
+    library.flush().unwrap();
+}
+
+#[tokio::test]
+async fn operations_conform_to_spec() {
+    let dir = tempfile::tempdir().unwrap();
+    prepare_fixture(dir.path());
+    let backend = Backend::start(dir.path());
+    let client = backend.client();
+
+    let response = client.check_health().await.unwrap();
+    assert!(matches!(response, CheckHealthResponse::Ok { .. }), "{response:?}");
+
+    let response = client.get_library().await.unwrap();
+    match response {
+        GetLibraryResponse::Ok { body, .. } => assert_eq!(body.items.len(), 1),
+        #[allow(unreachable_patterns)]
+        _ => panic!("{response:?}"),
+    }
+
+    let response = client.get_library_item(LIBRARY_TOPIC.to_owned()).await.unwrap();
+    assert!(matches!(response, GetLibraryItemResponse::Ok { .. }), "{response:?}");
+
+    let response = client.search_by_text("fairphone".to_owned()).await.unwrap();
+    match response {
+        SearchByTextResponse::Ok { body, .. } => assert!(!body.results.is_empty()),
+        #[allow(unreachable_patterns)]
+        _ => panic!("{response:?}"),
+    }
+
+    let response = client
+        .get_organisation(OrganisationIdVariant::Wiki, ORGANISATION_WIKI_ID.to_owned())
+        .await
+        .unwrap();
+    assert!(matches!(response, GetOrganisationResponse::Ok { .. }), "{response:?}");
+
+    for (variant, id) in
+        [(ProductIdVariant::Gtin, PRODUCT_GTIN), (ProductIdVariant::Wiki, PRODUCT_WIKI_ID)]
+    {
+        let response = client.get_product(variant, id.to_owned(), None).await.unwrap();
+        assert!(matches!(response, GetProductResponse::Ok { .. }), "{response:?}");
+    }
+
+    let response = client
+        .get_alternatives(ProductIdVariant::Gtin, PRODUCT_GTIN.to_owned(), Some("NLD".to_owned()))
+        .await
+        .unwrap();
+    assert!(matches!(response, GetAlternativesResponse::Ok { .. }), "{response:?}");
+
+    let response = client.get_category(CATEGORY.to_owned()).await.unwrap();
+    assert!(matches!(response, GetCategoryResponse::Ok { .. }), "{response:?}");
+}
+
+#[tokio::test]
+async fn versioned_paths_conform_to_spec() {
+    let dir = tempfile::tempdir().unwrap();
+    prepare_fixture(dir.path());
+    let backend = Backend::start(dir.path());
+
+    let response = backend.client_at("/v1").check_health().await.unwrap();
+    assert!(matches!(response, CheckHealthResponse::Ok { .. }), "{response:?}");
+
+    let response = backend.client_at("/v1").get_category(CATEGORY.to_owned()).await.unwrap();
+    assert!(matches!(response, GetCategoryResponse::Ok { .. }), "{response:?}");
+
+    let response = backend.client_at("/v99").check_health().await;
+    assert!(response.is_err(), "{response:?}");
+}
+
+#[tokio::test]
+async fn streamed_organisations_conform_to_spec() {
+    let dir = tempfile::tempdir().unwrap();
+    prepare_fixture(dir.path());
+    let backend = Backend::start_with_args(dir.path(), &["--streaming-threshold", "1"]);
+
+    let response = backend
+        .client()
+        .get_organisation(OrganisationIdVariant::Wiki, ORGANISATION_WIKI_ID.to_owned())
+        .await
+        .unwrap();
+    match response {
+        GetOrganisationResponse::Ok { body, .. } => assert_eq!(body.products.len(), 1),
+        #[allow(unreachable_patterns)]
+        _ => panic!("{response:?}"),
+    }
+}
+
+#[tokio::test]
+async fn missing_entries_conform_to_spec() {
+    let dir = tempfile::tempdir().unwrap();
+    prepare_fixture(dir.path());
+    let backend = Backend::start(dir.path());
+    let client = backend.client();
+
+    let response = client.get_library_item("info:missing".to_owned()).await.unwrap();
+    assert!(matches!(response, GetLibraryItemResponse::NotFound { .. }), "{response:?}");
+
+    let response =
+        client.get_organisation(OrganisationIdVariant::Wiki, "Q1".to_owned()).await.unwrap();
+    assert!(matches!(response, GetOrganisationResponse::NotFound { .. }), "{response:?}");
+
+    let response =
+        client.get_product(ProductIdVariant::Gtin, "12345678".to_owned(), None).await.unwrap();
+    assert!(matches!(response, GetProductResponse::NotFound { .. }), "{response:?}");
+
+    let response = client.get_category("missing".to_owned()).await.unwrap();
+    assert!(matches!(response, GetCategoryResponse::NotFound { .. }), "{response:?}");
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> T {
+    let file = std::fs::File::open(path).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+    serde_json::from_reader(file).unwrap()
+}
+
+#[test]
+fn static_export_conforms_to_spec() {
+    let dir = tempfile::tempdir().unwrap();
+    prepare_fixture(dir.path());
+    let output = dir.path().join("static");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_transpaer-backend"))
+        .arg("--db-path")
+        .arg(dir.path())
+        .arg("export-static")
+        .arg("--output")
+        .arg(&output)
+        .status()
+        .expect("run the backend");
+    assert!(status.success());
+
+    let library: LibraryContents = read_json(&output.join("library.json"));
+    assert_eq!(library.items.len(), 1);
+
+    let _: LibraryItemFull =
+        read_json(&output.join("library").join(format!("{LIBRARY_TOPIC}.json")));
+
+    let root: CategoryFull = read_json(&output.join("category.json"));
+    assert_eq!(root.subcategories.len(), 1);
+    assert_eq!(root.subcategories[0].label, "Electronics");
+
+    let electronics: CategoryFull = read_json(&output.join("category").join("electronics.json"));
+    assert_eq!(electronics.label, "Electronics");
+    assert_eq!(electronics.subcategories.len(), 1);
+    assert_eq!(electronics.subcategories[0].label, "Communications");
+}
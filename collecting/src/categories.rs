@@ -54,16 +54,18 @@ impl Category {
     pub fn get_info(&self) -> Option<Info> {
         let mut node: &Node = &CATEGORIES;
         if node.name == self.string {
-            return Some(node.to_info());
+            return Some(node.to_info(node.id));
         }
+        let mut google_product_category = node.id;
         for cat in self.string.split(SEPARATOR) {
             if let Some(find) = node.sub.iter().find(|e| e.name == cat) {
                 node = find;
+                google_product_category = node.id.or(google_product_category);
             } else {
                 return None;
             }
         }
-        Some(node.to_info())
+        Some(node.to_info(google_product_category))
     }
 
     #[must_use]
@@ -103,18 +105,23 @@ impl Status {
 pub struct Info {
     pub status: Status,
     pub subcategories: Vec<String>,
+
+    /// Human-readable name of the category in English.
+    // TODO: Add translations to the taxonomy to present the names in other languages.
+    pub title: String,
+
+    /// ID of the Google Product Category of this category or of its closest parent having one.
+    pub google_product_category: Option<u32>,
 }
 
 struct Node {
     /// Google Product Category associated with this (sub)category.
-    #[allow(dead_code)]
-    id: Option<usize>,
+    id: Option<u32>,
 
     /// Name for this category.
     name: &'static str,
 
     /// Human-readable name of this (sub)category.
-    #[allow(dead_code)]
     title: &'static str,
 
     status: Status,
@@ -124,10 +131,12 @@ struct Node {
 }
 
 impl Node {
-    fn to_info(&self) -> Info {
+    fn to_info(&self, google_product_category: Option<u32>) -> Info {
         Info {
             status: self.status,
             subcategories: self.sub.iter().map(|n| n.name.to_string()).collect(),
+            title: self.title.to_string(),
+            google_product_category,
         }
     }
 }
@@ -564,6 +573,31 @@ mod tests {
         assert!(Category::new("food_beverages_and_tobacco/snack_foods".into()).is_err());
     }
 
+    #[test]
+    fn google_product_category() {
+        let get = |name: &str| Category::new(name.into()).unwrap().get_info().unwrap();
+        assert_eq!(get("").google_product_category, None);
+        assert_eq!(get("food_beverages_and_tobacco/food").google_product_category, Some(422));
+        assert_eq!(
+            get("food_beverages_and_tobacco/food/snack_foods").google_product_category,
+            Some(423)
+        );
+
+        // Falls back to the closest parent
+        assert_eq!(
+            get("food_beverages_and_tobacco/beverages/alcoholic_beverages").google_product_category,
+            Some(413)
+        );
+    }
+
+    #[test]
+    fn category_titles() {
+        let get = |name: &str| Category::new(name.into()).unwrap().get_info().unwrap();
+        assert_eq!(get("").title, "");
+        assert_eq!(get("cameras_and_optics").title, "Cameras & Optics");
+        assert_eq!(get("cameras_and_optics/cameras").title, "Cameras");
+    }
+
     #[test]
     fn root_category_info() {
         let cat = Category::new("".into()).unwrap();
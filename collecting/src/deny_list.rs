@@ -0,0 +1,42 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// Data structures for parsing the deny-list.
+pub mod data {
+    use serde::{Deserialize, Serialize};
+
+    use crate::categories::Category;
+
+    /// Products which should not be presented as consumer products, e.g. medications.
+    #[derive(Serialize, Deserialize, Debug, Default)]
+    pub struct DenyList {
+        /// Transpaer categories. Products in these categories or their subcategories are dropped.
+        #[serde(default)]
+        pub categories: Vec<Category>,
+
+        /// Wikidata IDs of products to drop.
+        #[serde(
+            default,
+            deserialize_with = "transpaer_wikidata::data::deserialize_vec_id_from_vec_string"
+        )]
+        pub wiki: Vec<transpaer_wikidata::data::Id>,
+    }
+}
+
+/// Reader to loading the deny-list.
+pub mod reader {
+    use super::data::DenyList;
+    use crate::errors::{IoOrSerdeError, MapIo, MapSerde};
+
+    /// Loads the deny-list from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to read from `path` or parse the contents.
+    pub fn parse(path: &std::path::Path) -> Result<DenyList, IoOrSerdeError> {
+        let contents = std::fs::read_to_string(path).map_with_path(path)?;
+        let parsed: DenyList = serde_yaml::from_str(&contents).map_with_path(path)?;
+        Ok(parsed)
+    }
+}
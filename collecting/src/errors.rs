@@ -24,6 +24,9 @@ pub enum IoOrSerdeError {
     #[error("In file `{1}`.\nYAML parsing error: {0}")]
     ReadYaml(serde_yaml::Error, std::path::PathBuf),
 
+    #[error("In file `{1}`.\nXML parsing error: {0}")]
+    ReadXml(quick_xml::Error, std::path::PathBuf),
+
     #[error("CSV serialization error: {0}")]
     WriteCsv(csv::Error),
 
@@ -2,17 +2,122 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::collections::BTreeMap;
+
 use crate::errors::{IoOrSerdeError, MapIo, MapSerde};
 
+/// Scheme of the authorization header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <token>`
+    Bearer,
+
+    /// `Authorization: Token token=<token>`
+    Token,
+}
+
+/// Authorization needed to download a source.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FetchAuth {
+    /// Scheme of the authorization header.
+    pub scheme: AuthScheme,
+
+    /// Name of the environment variable holding the token.
+    ///
+    /// Tokens are not stored in the fetch descriptions so that they can be committed.
+    pub env: String,
+}
+
+impl FetchAuth {
+    /// Returns the value of the authorization header.
+    #[must_use]
+    pub fn header_value(&self, token: &str) -> String {
+        match self.scheme {
+            AuthScheme::Bearer => format!("Bearer {token}"),
+            AuthScheme::Token => format!("Token token={token}"),
+        }
+    }
+}
+
+/// Format of a downloaded file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchFormat {
+    Csv,
+    Json,
+    JsonLines,
+    Xml,
+    Gzip,
+    #[default]
+    Binary,
+}
+
+impl FetchFormat {
+    /// Returns the MIME type accepted when downloading the file.
+    #[must_use]
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv",
+            Self::Json => "application/json",
+            Self::JsonLines => "application/jsonl",
+            Self::Xml => "application/xml",
+            Self::Gzip => "application/gzip",
+            Self::Binary => "application/octet-stream",
+        }
+    }
+}
+
+/// Describes how to download one of the raw inputs.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FetchDescription {
+    /// Name of the source, used as the key in the fetch info.
+    pub name: String,
+
+    /// URL to download the file from.
+    pub url: String,
+
+    /// Authorization, if the source requires one.
+    #[serde(default)]
+    pub auth: Option<FetchAuth>,
+
+    /// Format of the file.
+    #[serde(default)]
+    pub format: FetchFormat,
+
+    /// Path to the downloaded file relative to the origin directory.
+    pub target: String,
+
+    /// Expected SHA-256 checksum of the file in hex, if known.
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl FetchDescription {
+    /// Reads the fetch descriptions from the passed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to read from `path` or parse the contents
+    pub fn read_all(path: &std::path::Path) -> Result<Vec<Self>, IoOrSerdeError> {
+        let contents = std::fs::read_to_string(path).map_with_path(path)?;
+        let parsed: Vec<Self> = serde_yaml::from_str(&contents).map_with_path(path)?;
+        Ok(parsed)
+    }
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 struct FetchData {
     access_time: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
 }
 
 impl FetchData {
     fn now() -> Self {
         let access_time = chrono::Utc::now().to_rfc3339();
-        Self { access_time }
+        Self { access_time, sha256: None }
     }
 }
 
@@ -22,6 +127,10 @@ pub struct FetchInfo {
     eu_ecolabel: Option<FetchData>,
     open_food_facts: Option<FetchData>,
     open_food_repo: Option<FetchData>,
+
+    /// Sources downloaded by the `fetch` command by their names.
+    #[serde(default)]
+    sources: BTreeMap<String, FetchData>,
 }
 
 impl FetchInfo {
@@ -66,4 +175,16 @@ impl FetchInfo {
     pub fn update_open_food_repo(&mut self) {
         self.open_food_repo = Some(FetchData::now());
     }
+
+    /// Records that the source was fetched now and the checksum of the downloaded file.
+    pub fn update_source(&mut self, name: &str, sha256: String) {
+        self.sources
+            .insert(name.to_owned(), FetchData { sha256: Some(sha256), ..FetchData::now() });
+    }
+
+    /// Returns the time the source was fetched in the RFC 3339 format.
+    #[must_use]
+    pub fn source_access_time(&self, name: &str) -> Option<&str> {
+        self.sources.get(name).map(|data| data.access_time.as_str())
+    }
 }
@@ -13,12 +13,17 @@ pub mod data {
 pub mod errors;
 
 pub mod categories;
+pub mod deny_list;
 pub mod fetch_info;
+pub mod shopping_availability;
+pub mod sitelinks;
 
 pub mod bcorp;
 pub mod eu_ecolabel;
 pub mod fashion_transparency_index;
 pub mod open_food_facts;
 pub mod open_food_repo;
+pub mod open_supply_hub;
+pub mod podcasts;
 pub mod tco;
 pub mod transpaer;
@@ -127,6 +127,16 @@ pub mod data {
             }
         }
 
+        /// Extracts country tags describing where the ingredients come from.
+        #[must_use]
+        pub fn extract_origin_countries(&self) -> Vec<String> {
+            if self.origins_tags.is_empty() {
+                Vec::new()
+            } else {
+                self.origins_tags.split(',').map(String::from).collect()
+            }
+        }
+
         /// Extracts sell country tags.
         #[must_use]
         pub fn extract_sell_countries(&self) -> Vec<String> {
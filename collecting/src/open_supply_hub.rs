@@ -0,0 +1,86 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// Data structures for parsing Open Supply Hub data.
+pub mod data {
+    use serde::{Deserialize, Serialize};
+
+    /// Separator of the entries in list columns.
+    const LIST_SEPARATOR: char = '|';
+
+    /// Record in the Open Supply Hub facility export.
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct Record {
+        /// Open Supply Hub ID of the facility.
+        pub os_id: String,
+
+        /// Facility name.
+        pub name: String,
+
+        /// Facility address.
+        pub address: String,
+
+        /// ISO 3166-1 alpha-2 code of the country the facility is located in.
+        pub country_code: String,
+
+        /// Organisations which disclosed the facility.
+        ///
+        /// These are mostly brands publishing the list of their suppliers.
+        #[serde(rename = "contributor (list)")]
+        pub contributors: String,
+
+        /// Parent company of the facility.
+        #[serde(default)]
+        pub parent_company: Option<String>,
+    }
+
+    impl Record {
+        /// Extracts names of the brands the facility produces for.
+        ///
+        /// The contributor entries may be annotated with the contributor type in parentheses,
+        /// e.g. "Brand (Brand/Retailer)". The annotations are removed.
+        #[must_use]
+        pub fn extract_brands(&self) -> Vec<String> {
+            let mut brands: Vec<String> = self
+                .contributors
+                .split(LIST_SEPARATOR)
+                .map(|entry| match entry.rsplit_once(" (") {
+                    Some((name, annotation)) if annotation.ends_with(')') => name,
+                    _ => entry,
+                })
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(ToOwned::to_owned)
+                .collect();
+            brands.sort();
+            brands.dedup();
+            brands
+        }
+    }
+}
+
+/// Reader to loading Open Supply Hub data.
+pub mod reader {
+    use super::data::Record;
+    use crate::errors::{IoOrSerdeError, MapSerde};
+
+    /// Loads the Open Supply Hub facility data from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to read from `path` or parse the contents.
+    pub fn parse(path: &std::path::Path) -> Result<Vec<Record>, IoOrSerdeError> {
+        let mut parsed = Vec::<Record>::new();
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b',')
+            .quote(b'"')
+            .flexible(true)
+            .from_path(path)
+            .map_with_path(path)?;
+        for result in reader.deserialize() {
+            parsed.push(result.map_with_path(path)?);
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,248 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/// Data structures for parsing podcast data.
+pub mod data {
+    use serde::{Deserialize, Serialize};
+
+    /// Separator of hours, minutes and seconds in timestamps.
+    const TIMESTAMP_SEPARATOR: char = ':';
+
+    /// Curated list of products and producers discussed in a podcast.
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct Podcast {
+        /// Name of the podcast.
+        pub name: String,
+
+        /// Path to the RSS feed of the podcast relative to the podcasts file.
+        pub feed: String,
+
+        /// Mentions of products and producers in the episodes.
+        #[serde(default)]
+        pub mentions: Vec<EpisodeMention>,
+    }
+
+    /// Kind of the mentioned entity.
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MentionedKind {
+        #[default]
+        Producer,
+        Product,
+    }
+
+    /// Mention of a product or a producer in an episode.
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    pub struct EpisodeMention {
+        /// GUID of the episode in the RSS feed.
+        pub episode: String,
+
+        /// Name of the mentioned product or producer.
+        ///
+        /// Used to find the timestamp in the show notes if it was not given explicitly.
+        pub name: String,
+
+        /// ID of the mentioned product or producer in Wikidata.
+        #[serde(
+            rename = "wiki",
+            deserialize_with = "transpaer_wikidata::data::Id::deserialize_from_string"
+        )]
+        pub wikidata_id: transpaer_wikidata::data::Id,
+
+        /// Kind of the mentioned entity.
+        #[serde(default)]
+        pub kind: MentionedKind,
+
+        /// Time in the episode when the discussion starts, e.g. `12:34` or `1:02:03`.
+        #[serde(default)]
+        pub timestamp: Option<String>,
+    }
+
+    /// Episode read from an RSS feed.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct Episode {
+        /// Unique ID of the episode.
+        pub guid: String,
+
+        /// Title of the episode.
+        pub title: String,
+
+        /// Link to the web page of the episode.
+        pub link: Option<String>,
+
+        /// Link to the audio file of the episode.
+        pub enclosure: Option<String>,
+
+        /// Description and show notes of the episode.
+        pub notes: String,
+    }
+
+    impl Episode {
+        /// Returns the link to the episode preferring the web page over the audio file.
+        #[must_use]
+        pub fn url(&self) -> Option<&str> {
+            self.link.as_deref().or(self.enclosure.as_deref())
+        }
+
+        /// Looks for the timestamp of the first line of the show notes mentioning `name`.
+        ///
+        /// Show notes usually list the discussed topics as lines starting with a timestamp,
+        /// e.g. `12:34 - Interview with Fairphone`. HTML tags are treated as line breaks.
+        #[must_use]
+        pub fn find_timestamp(&self, name: &str) -> Option<u32> {
+            let name = name.to_lowercase();
+            self.notes
+                .split(['\n', '<', '>'])
+                .filter(|line| line.to_lowercase().contains(&name))
+                .find_map(|line| {
+                    let first = line.split_whitespace().next()?;
+                    parse_timestamp(first.trim_matches(|c: char| !c.is_ascii_digit()))
+                })
+        }
+    }
+
+    /// Parses a timestamp in the `H:MM:SS` or `M:SS` format into seconds.
+    #[must_use]
+    pub fn parse_timestamp(timestamp: &str) -> Option<u32> {
+        let parts: Vec<&str> = timestamp.trim().split(TIMESTAMP_SEPARATOR).collect();
+        if !(2..=3).contains(&parts.len()) {
+            return None;
+        }
+
+        let mut seconds = 0;
+        for (i, part) in parts.iter().enumerate() {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let value: u32 = part.parse().ok()?;
+            if i > 0 && (part.len() != 2 || value >= 60) {
+                return None;
+            }
+            seconds = seconds.checked_mul(60)?.checked_add(value)?;
+        }
+        Some(seconds)
+    }
+}
+
+/// Reader to loading podcast data.
+pub mod reader {
+    use quick_xml::events::{BytesStart, Event};
+
+    use super::data::{Episode, Podcast};
+    use crate::errors::{IoOrSerdeError, MapIo, MapSerde};
+
+    /// RSS elements of an episode the reader is interested in.
+    #[derive(Clone, Copy, Debug)]
+    enum Field {
+        Guid,
+        Title,
+        Link,
+        Notes,
+    }
+
+    impl Field {
+        fn from_tag(tag: &[u8]) -> Option<Self> {
+            match tag {
+                b"guid" => Some(Self::Guid),
+                b"title" => Some(Self::Title),
+                b"link" => Some(Self::Link),
+                b"description" | b"content:encoded" => Some(Self::Notes),
+                _ => None,
+            }
+        }
+
+        fn append(self, episode: &mut Episode, text: &str) {
+            match self {
+                Self::Guid => episode.guid.push_str(text.trim()),
+                Self::Title => episode.title.push_str(text.trim()),
+                Self::Link => episode.link = Some(text.trim().to_owned()),
+                Self::Notes => {
+                    episode.notes.push_str(text);
+                    episode.notes.push('\n');
+                }
+            }
+        }
+    }
+
+    /// Loads the curated podcast data from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to read from `path` or parse the contents.
+    pub fn parse(path: &std::path::Path) -> Result<Vec<Podcast>, IoOrSerdeError> {
+        let contents = std::fs::read_to_string(path).map_with_path(path)?;
+        let parsed: Vec<Podcast> = serde_yaml::from_str(&contents).map_with_path(path)?;
+        Ok(parsed)
+    }
+
+    /// Loads episodes from an RSS feed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to read from `path` or parse the contents.
+    pub fn parse_feed(path: &std::path::Path) -> Result<Vec<Episode>, IoOrSerdeError> {
+        let contents = std::fs::read_to_string(path).map_with_path(path)?;
+        parse_rss(&contents).map_err(|e| IoOrSerdeError::ReadXml(e, path.into()))
+    }
+
+    /// Parses episodes from the contents of an RSS feed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the contents is not a valid XML.
+    pub fn parse_rss(contents: &str) -> Result<Vec<Episode>, quick_xml::Error> {
+        let mut reader = quick_xml::Reader::from_str(contents);
+        let mut episodes = Vec::new();
+        let mut current: Option<Episode> = None;
+        let mut field: Option<Field> = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) => {
+                    if tag.name().as_ref() == b"item" {
+                        current = Some(Episode::default());
+                    } else if let Some(episode) = &mut current {
+                        read_enclosure(&tag, episode)?;
+                        field = Field::from_tag(tag.name().as_ref());
+                    }
+                }
+                Event::Empty(tag) => {
+                    if let Some(episode) = &mut current {
+                        read_enclosure(&tag, episode)?;
+                    }
+                }
+                Event::Text(text) => {
+                    if let (Some(episode), Some(field)) = (&mut current, field) {
+                        field.append(episode, &text.unescape()?);
+                    }
+                }
+                Event::CData(data) => {
+                    if let (Some(episode), Some(field)) = (&mut current, field) {
+                        field.append(episode, &String::from_utf8_lossy(&data.into_inner()));
+                    }
+                }
+                Event::End(tag) => {
+                    field = None;
+                    if tag.name().as_ref() == b"item"
+                        && let Some(episode) = current.take()
+                    {
+                        episodes.push(episode);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(episodes)
+    }
+
+    /// Sets the enclosure of the episode if the tag is an enclosure.
+    fn read_enclosure(tag: &BytesStart, episode: &mut Episode) -> Result<(), quick_xml::Error> {
+        if tag.name().as_ref() == b"enclosure"
+            && let Some(url) = tag.try_get_attribute("url")?
+        {
+            episode.enclosure = Some(url.unescape_value()?.into_owned());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Availability of the shopping links recorded by the `check-shopping` command.
+
+use std::collections::BTreeMap;
+
+use crate::errors::{IoOrSerdeError, MapIo, MapSerde};
+
+/// Outcome of checking a shopping link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    /// The shop presented the product page.
+    Available,
+
+    /// The shop responded that the product page does not exist.
+    Dead,
+
+    /// The check was inconclusive, e.g. the shop did not respond or refused to answer a bot.
+    Unknown,
+}
+
+/// Result of the last check of a shopping link.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LinkCheck {
+    /// Availability of the link.
+    pub availability: Availability,
+
+    /// HTTP status of the response, if any was received.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// Time of the check in the RFC 3339 format.
+    pub check_time: String,
+}
+
+impl LinkCheck {
+    /// Constructs a new `LinkCheck` performed now.
+    #[must_use]
+    pub fn now(availability: Availability, status: Option<u16>) -> Self {
+        Self { availability, status, check_time: chrono::Utc::now().to_rfc3339() }
+    }
+}
+
+/// Results of checks of the shopping links by the shop name and the ID of the product in the shop.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ShoppingAvailability {
+    #[serde(flatten)]
+    shops: BTreeMap<String, BTreeMap<String, LinkCheck>>,
+}
+
+impl ShoppingAvailability {
+    /// Reads the shopping availability from the passed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to read from `path` or parse the contents
+    pub fn read(path: &std::path::Path) -> Result<Self, IoOrSerdeError> {
+        if path.exists() {
+            let contents = std::fs::read_to_string(path).map_with_path(path)?;
+            let parsed: Self = serde_yaml::from_str(&contents).map_with_path(path)?;
+            Ok(parsed)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Writes the shopping availability to the passed file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if fails to write to the passed file or serialize the contents.
+    pub fn write(&self, path: &std::path::Path) -> Result<(), IoOrSerdeError> {
+        let contents = serde_yaml::to_string(self).map_serde()?;
+        std::fs::write(path, contents).map_with_path(path)?;
+        Ok(())
+    }
+
+    /// Returns the result of the last check of the link.
+    #[must_use]
+    pub fn get(&self, shop: &str, id: &str) -> Option<&LinkCheck> {
+        self.shops.get(shop).and_then(|checks| checks.get(id))
+    }
+
+    /// Checks if the link was found dead.
+    #[must_use]
+    pub fn is_dead(&self, shop: &str, id: &str) -> bool {
+        self.get(shop, id).is_some_and(|check| check.availability == Availability::Dead)
+    }
+
+    /// Records the result of a check of the link.
+    ///
+    /// An inconclusive check does not override the availability found by an earlier one.
+    pub fn record(&mut self, shop: &str, id: &str, mut check: LinkCheck) {
+        let checks = self.shops.entry(shop.to_owned()).or_default();
+        if check.availability == Availability::Unknown
+            && let Some(previous) = checks.get(id)
+        {
+            check.availability = previous.availability;
+        }
+        checks.insert(id.to_owned(), check);
+    }
+
+    /// Returns the number of links with the given availability.
+    #[must_use]
+    pub fn count(&self, availability: Availability) -> usize {
+        self.shops
+            .values()
+            .flat_map(BTreeMap::values)
+            .filter(|check| check.availability == availability)
+            .count()
+    }
+}
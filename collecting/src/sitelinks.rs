@@ -0,0 +1,51 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Sitelink counts of Wikidata products recorded by the `filter` command.
+//!
+//! The substrates cannot carry the sitelink counts, so they are passed to crystalization in
+//! a separate CSV file.
+
+use std::collections::HashMap;
+
+use crate::errors::{IoOrSerdeError, MapSerde};
+
+/// Number of sitelinks of a single Wikidata item.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SitelinkCount {
+    /// Numeric Wikidata ID of the item.
+    pub wiki_id: u64,
+
+    /// Number of sitelinks (links to Wikipedia and other Wikimedia projects).
+    pub sitelinks: u32,
+}
+
+/// Reads the sitelink counts by the numeric Wikidata IDs.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to read from `path` or parse the contents.
+pub fn read(path: &std::path::Path) -> Result<HashMap<u64, u32>, IoOrSerdeError> {
+    let mut reader = csv::ReaderBuilder::new().from_path(path).map_with_path(path)?;
+    let mut result = HashMap::new();
+    for record in reader.deserialize() {
+        let record: SitelinkCount = record.map_with_path(path)?;
+        result.insert(record.wiki_id, record.sitelinks);
+    }
+    Ok(result)
+}
+
+/// Writes the sitelink counts to the passed file.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to write to the passed file.
+pub fn write(path: &std::path::Path, counts: &[SitelinkCount]) -> Result<(), IoOrSerdeError> {
+    let mut writer = csv::WriterBuilder::new().from_path(path).map_serde()?;
+    for count in counts {
+        writer.serialize(count).map_serde()?;
+    }
+    writer.flush().map_err(|e| IoOrSerdeError::Io(e, path.to_owned()))?;
+    Ok(())
+}
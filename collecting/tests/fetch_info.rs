@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use transpaer_collecting::fetch_info::{
+    AuthScheme, FetchAuth, FetchDescription, FetchFormat, FetchInfo,
+};
+
+#[test]
+fn fetch_description_serde() {
+    let contents = r"
+- name: bcorp
+  url: https://example.com/bcorp.csv
+  auth:
+    scheme: bearer
+    env: BCORP_TOKEN
+  format: csv
+  target: bcorp.csv
+  sha256: abc123
+- name: dump
+  url: https://example.com/dump.bin
+  target: dump.bin
+";
+
+    let parsed: Vec<FetchDescription> = serde_yaml::from_str(contents).unwrap();
+    assert_eq!(
+        parsed,
+        vec![
+            FetchDescription {
+                name: "bcorp".to_owned(),
+                url: "https://example.com/bcorp.csv".to_owned(),
+                auth: Some(FetchAuth { scheme: AuthScheme::Bearer, env: "BCORP_TOKEN".to_owned() }),
+                format: FetchFormat::Csv,
+                target: "bcorp.csv".to_owned(),
+                sha256: Some("abc123".to_owned()),
+            },
+            FetchDescription {
+                name: "dump".to_owned(),
+                url: "https://example.com/dump.bin".to_owned(),
+                auth: None,
+                format: FetchFormat::Binary,
+                target: "dump.bin".to_owned(),
+                sha256: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn auth_header() {
+    let auth = |scheme| FetchAuth { scheme, env: "TOKEN".to_owned() };
+    assert_eq!(auth(AuthScheme::Bearer).header_value("xyz"), "Bearer xyz");
+    assert_eq!(auth(AuthScheme::Token).header_value("xyz"), "Token token=xyz");
+}
+
+#[test]
+fn fetch_info_sources() {
+    let mut info: FetchInfo = serde_yaml::from_str("bcorp: null\n").unwrap();
+    assert_eq!(info.source_access_time("bcorp"), None);
+
+    info.update_source("bcorp", "abc123".to_owned());
+    let written = serde_yaml::to_string(&info).unwrap();
+    let read: FetchInfo = serde_yaml::from_str(&written).unwrap();
+    assert!(read.source_access_time("bcorp").is_some());
+}
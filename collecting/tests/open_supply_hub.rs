@@ -0,0 +1,22 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#[test]
+fn record_brands() {
+    use transpaer_collecting::open_supply_hub::data::Record;
+
+    let record = Record {
+        os_id: "BD2019123ABCDEF".to_string(),
+        name: "Factory".to_string(),
+        address: "Dhaka".to_string(),
+        country_code: "BD".to_string(),
+        contributors: "Brand B (Brand/Retailer)|Brand A| |Brand (Sub) Label|Brand A".to_string(),
+        parent_company: None,
+    };
+
+    assert_eq!(
+        record.extract_brands(),
+        vec!["Brand (Sub) Label".to_string(), "Brand A".to_string(), "Brand B".to_string()]
+    );
+}
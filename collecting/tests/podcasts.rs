@@ -0,0 +1,60 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use transpaer_collecting::podcasts::{data::parse_timestamp, reader::parse_rss};
+
+const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>Repair Talk</title>
+    <link>https://example.com</link>
+    <item>
+      <title>Episode 12: Modular phones</title>
+      <guid isPermaLink="false">ep-12</guid>
+      <link>https://example.com/episodes/12</link>
+      <enclosure url="https://example.com/audio/12.mp3?a=1&amp;b=2" type="audio/mpeg" length="1"/>
+      <description><![CDATA[<p>Show notes:</p><p>02:10 Intro</p><p>12:34 - Interview with Fairphone</p>]]></description>
+    </item>
+    <item>
+      <title>Episode 13 &amp; friends</title>
+      <guid>ep-13</guid>
+      <enclosure url="https://example.com/audio/13.mp3" type="audio/mpeg" length="1"></enclosure>
+      <content:encoded>(1:02:03) Patagonia jackets</content:encoded>
+    </item>
+  </channel>
+</rss>
+"#;
+
+#[test]
+fn timestamps() {
+    assert_eq!(parse_timestamp("12:34"), Some(754));
+    assert_eq!(parse_timestamp("0:05"), Some(5));
+    assert_eq!(parse_timestamp("1:02:03"), Some(3723));
+    assert_eq!(parse_timestamp("12"), None);
+    assert_eq!(parse_timestamp("12:3"), None);
+    assert_eq!(parse_timestamp("12:60"), None);
+    assert_eq!(parse_timestamp("1:2:3:4"), None);
+    assert_eq!(parse_timestamp("a:bc"), None);
+}
+
+#[test]
+fn rss_episodes() {
+    let episodes = parse_rss(FEED).unwrap();
+    assert_eq!(episodes.len(), 2);
+
+    let first = &episodes[0];
+    assert_eq!(first.guid, "ep-12");
+    assert_eq!(first.title, "Episode 12: Modular phones");
+    assert_eq!(first.url(), Some("https://example.com/episodes/12"));
+    assert_eq!(first.enclosure.as_deref(), Some("https://example.com/audio/12.mp3?a=1&b=2"));
+    assert_eq!(first.find_timestamp("fairphone"), Some(754));
+    assert_eq!(first.find_timestamp("Show notes"), None);
+    assert_eq!(first.find_timestamp("Patagonia"), None);
+
+    let second = &episodes[1];
+    assert_eq!(second.guid, "ep-13");
+    assert_eq!(second.title, "Episode 13 & friends");
+    assert_eq!(second.url(), Some("https://example.com/audio/13.mp3"));
+    assert_eq!(second.find_timestamp("Patagonia"), Some(3723));
+}
@@ -0,0 +1,48 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use transpaer_collecting::shopping_availability::{Availability, LinkCheck, ShoppingAvailability};
+
+#[test]
+fn shopping_availability_serde() {
+    let contents = r"
+amazon:
+  B0000001:
+    availability: dead
+    status: 404
+    check_time: 2026-01-01T00:00:00+00:00
+fairphone:
+  fairphone-5:
+    availability: available
+    status: 200
+    check_time: 2026-01-02T00:00:00+00:00
+";
+
+    let parsed: ShoppingAvailability = serde_yaml::from_str(contents).unwrap();
+    assert!(parsed.is_dead("amazon", "B0000001"));
+    assert!(!parsed.is_dead("fairphone", "fairphone-5"));
+    assert!(!parsed.is_dead("amazon", "B0000002"));
+    assert_eq!(parsed.get("fairphone", "fairphone-5").unwrap().status, Some(200));
+    assert_eq!(parsed.count(Availability::Dead), 1);
+
+    let serialized = serde_yaml::to_string(&parsed).unwrap();
+    assert_eq!(serde_yaml::from_str::<ShoppingAvailability>(&serialized).unwrap(), parsed);
+}
+
+#[test]
+fn inconclusive_checks_keep_availability() {
+    let mut availability = ShoppingAvailability::default();
+
+    availability.record("amazon", "B0000001", LinkCheck::now(Availability::Unknown, Some(503)));
+    assert_eq!(availability.get("amazon", "B0000001").unwrap().availability, Availability::Unknown);
+
+    availability.record("amazon", "B0000001", LinkCheck::now(Availability::Dead, Some(404)));
+    availability.record("amazon", "B0000001", LinkCheck::now(Availability::Unknown, Some(503)));
+    let check = availability.get("amazon", "B0000001").unwrap();
+    assert_eq!(check.availability, Availability::Dead);
+    assert_eq!(check.status, Some(503));
+
+    availability.record("amazon", "B0000001", LinkCheck::now(Availability::Available, Some(200)));
+    assert!(!availability.is_dead("amazon", "B0000001"));
+}
@@ -4,7 +4,7 @@
 
 //! Contains code ralated to parsing source data.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use transpaer_collecting::{
     bcorp, categories::Category, fashion_transparency_index, tco, transpaer,
@@ -16,6 +16,7 @@ use crate::{
     cache, convert, errors,
     substrate::Substrates,
     utils,
+    validating::{self, TagKind},
     wikidata::{ItemExt, WikiId},
 };
 
@@ -77,7 +78,7 @@ impl BCorpAdvisor {
     {
         let path = original_path.as_ref();
         let original_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer_collecting::bcorp::reader::parse(path)?)
+            Some(validating::read(path, bcorp::reader::parse)?)
         } else {
             log::warn!(
                 "Could not access `{}`. BCorp original data won't be loaded!",
@@ -88,7 +89,7 @@ impl BCorpAdvisor {
 
         let path = regions_path.as_ref();
         let regions_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer::reader::parse_countries(path)?)
+            Some(validating::load_countries(path, TagKind::Plain)?)
         } else {
             log::warn!(
                 "Could not access `{}`. BCorp support data won't be loaded!",
@@ -121,18 +122,25 @@ impl BCorpAdvisor {
 pub struct EuEcolabelAdvisor {
     /// Map from Eu Ecolabel countries to transpaer regions.
     country_to_regions: HashMap<String, models::Regions>,
+
+    /// Map from disambiguated company names to their matches in Wikidata.
+    name_to_match: HashMap<String, transpaer::data::Match>,
 }
 
 impl EuEcolabelAdvisor {
     /// Constructs a new `OpenFoodFactsAdvisor`.
     #[must_use]
-    pub fn new(country_to_regions: HashMap<String, models::Regions>) -> Self {
-        Self { country_to_regions }
+    pub fn new(
+        country_to_regions: HashMap<String, models::Regions>,
+        name_to_match: HashMap<String, transpaer::data::Match>,
+    ) -> Self {
+        Self { country_to_regions, name_to_match }
     }
 
     /// Constructs a new `OpenFoodFactsAdvisor` with loaded data.
     pub fn assemble(
         country_data: Option<transpaer::data::Countries>,
+        match_data: Option<Vec<transpaer::data::NameMatching>>,
     ) -> Result<Self, errors::ProcessingError> {
         let country_to_regions = if let Some(data) = country_data {
             let mut country_to_regions = HashMap::new();
@@ -146,7 +154,13 @@ impl EuEcolabelAdvisor {
             HashMap::new()
         };
 
-        Ok(Self::new(country_to_regions))
+        let name_to_match = match_data
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|entry| entry.matched().map(|m| (entry.name, m)))
+            .collect();
+
+        Ok(Self::new(country_to_regions, name_to_match))
     }
 
     /// Loads a new `EuEcolabelAdvisor` from a file.
@@ -155,13 +169,13 @@ impl EuEcolabelAdvisor {
     ///
     /// Returns `Err` if fails to read from `path`, fails to parse the contents or the contents
     /// contain invalid data.
-    pub fn load<P>(country_path: P) -> Result<Self, errors::ProcessingError>
+    pub fn load<P>(country_path: P, match_path: P) -> Result<Self, errors::ProcessingError>
     where
         P: AsRef<std::path::Path>,
     {
         let path = country_path.as_ref();
         let country_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer::reader::parse_countries(path)?)
+            Some(validating::load_countries(path, TagKind::Plain)?)
         } else {
             log::warn!(
                 "Could not access `{}`. EU Ecolabel country data won't be loaded!",
@@ -169,13 +183,31 @@ impl EuEcolabelAdvisor {
             );
             None
         };
-        Self::assemble(country_data)
+
+        let path = match_path.as_ref();
+        let match_data = if utils::file_exists(path).is_ok() {
+            Some(validating::load_matches(path)?)
+        } else {
+            log::warn!(
+                "Could not access `{}`. EU Ecolabel name matches won't be loaded!",
+                path.display(),
+            );
+            None
+        };
+
+        Self::assemble(country_data, match_data)
     }
 
     #[must_use]
     pub fn get_countries(&self, country_tag: &str) -> Option<&models::Regions> {
         self.country_to_regions.get(country_tag)
     }
+
+    /// Returns the Wikidata match of the company with the given name, if any.
+    #[must_use]
+    pub fn get_match(&self, company_name: &str) -> Option<&transpaer::data::Match> {
+        self.name_to_match.get(&utils::disambiguate_name(company_name))
+    }
 }
 
 /// Holds the information read from the Open Food Facts data.
@@ -243,7 +275,7 @@ impl OpenFoodFactsAdvisor {
     {
         let path = country_path.as_ref();
         let country_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer::reader::parse_countries(path)?)
+            Some(validating::load_countries(path, TagKind::Plain)?)
         } else {
             log::warn!(
                 "Could not access `{}`. Open Food Facts country data won't be loaded!",
@@ -254,7 +286,7 @@ impl OpenFoodFactsAdvisor {
 
         let path = category_path.as_ref();
         let category_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer::reader::parse_categories(path)?)
+            Some(validating::load_categories(path, TagKind::Plain)?)
         } else {
             log::warn!(
                 "Could not access `{}`. Open Food Facts category data won't be loaded!",
@@ -302,7 +334,7 @@ impl TcoAdvisor {
     /// Returns `Err` if fails to read from `path` or parse the contents.
     pub fn load(path: &std::path::Path) -> Result<Self, errors::ProcessingError> {
         if utils::file_exists(path).is_ok() {
-            let data = tco::reader::parse(path)?;
+            let data = validating::read(path, tco::reader::parse)?;
             Ok(Self::new(&data))
         } else {
             log::warn!("Could not access `{}`. TCO data won't be loaded!", path.display());
@@ -358,7 +390,7 @@ impl FashionTransparencyIndexAdvisor {
     /// Returns `Err` if fails to read from `path`, fails to parse the contents or the contents are invalid.
     pub fn load(path: &std::path::Path) -> Result<Self, errors::ProcessingError> {
         if utils::file_exists(path).is_ok() {
-            let data = fashion_transparency_index::reader::parse(path)?;
+            let data = validating::load_fashion_transparency_index(path)?;
             let result = Self::new(&data)?;
             Ok(result)
         } else {
@@ -409,6 +441,19 @@ pub struct WikidataAdvisor {
 
     /// Map from Wikidata countries to transpaer regionss.
     class_to_categories: HashMap<WikiId, HashSet<String>>,
+
+    /// Map from Wikidata classes to their direct superclasses.
+    superclasses: HashMap<WikiId, Vec<WikiId>>,
+}
+
+/// Categories found for a Wikidata item.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CategoryMatch {
+    /// Found categories.
+    pub categories: HashSet<String>,
+
+    /// Number of superclass steps from the item's own classes to the closest category match.
+    pub depth: Option<usize>,
 }
 
 // TODO: Introduce the `new`, `assemble`, `load` pattern for every advisor.
@@ -418,8 +463,9 @@ impl WikidataAdvisor {
         manufacturer_ids: HashSet<WikiId>,
         country_to_regions: HashMap<WikiId, models::Regions>,
         class_to_categories: HashMap<WikiId, HashSet<String>>,
+        superclasses: HashMap<WikiId, Vec<WikiId>>,
     ) -> Self {
-        Self { manufacturer_ids, country_to_regions, class_to_categories }
+        Self { manufacturer_ids, country_to_regions, class_to_categories, superclasses }
     }
 
     /// Constructs a new `WikidataAdvisor` with loaded data.
@@ -457,13 +503,21 @@ impl WikidataAdvisor {
             HashMap::new()
         };
 
-        let manufacturer_ids = if let Some(cache) = cache {
-            cache.manufacturer_ids.iter().copied().collect()
+        let (manufacturer_ids, superclasses) = if let Some(cache) = cache {
+            let manufacturer_ids = cache.manufacturer_ids.iter().copied().collect();
+            let superclasses = cache
+                .superclasses
+                .into_iter()
+                .map(|(class, superclasses)| {
+                    (WikiId::new(class), superclasses.into_iter().map(WikiId::new).collect())
+                })
+                .collect();
+            (manufacturer_ids, superclasses)
         } else {
-            HashSet::new()
+            (HashSet::new(), HashMap::new())
         };
 
-        Ok(Self::new(manufacturer_ids, country_to_regions, class_to_categories))
+        Ok(Self::new(manufacturer_ids, country_to_regions, class_to_categories, superclasses))
     }
 
     /// Loads a new `WikidataAdvisor` from a file.
@@ -489,7 +543,7 @@ impl WikidataAdvisor {
 
         let path = region_path.as_ref();
         let region_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer::reader::parse_countries(path)?)
+            Some(validating::load_countries(path, TagKind::WikiId)?)
         } else {
             log::warn!("Could not access `{}`. Wikidata region won't be loaded!", path.display());
             None
@@ -497,7 +551,7 @@ impl WikidataAdvisor {
 
         let path = category_path.as_ref();
         let category_data = if utils::file_exists(path).is_ok() {
-            Some(transpaer::reader::parse_categories(path)?)
+            Some(validating::load_categories(path, TagKind::WikiId)?)
         } else {
             log::warn!(
                 "Could not access `{}`. Wikidata categories won't be loaded!",
@@ -528,6 +582,39 @@ impl WikidataAdvisor {
         self.class_to_categories.get(class_id)
     }
 
+    /// Finds categories of the given classes walking up the class hierarchy.
+    ///
+    /// The walk is breadth-first and goes at most `max_depth` superclass steps up from the given
+    /// classes. Classes mapped to categories are not walked past, so that items are tagged with the
+    /// most specific categories only. Each class is visited once, so cycles in the hierarchy are
+    /// harmless.
+    #[must_use]
+    pub fn find_categories(&self, classes: &[WikiId], max_depth: usize) -> CategoryMatch {
+        let mut result = CategoryMatch::default();
+        let mut visited: HashSet<WikiId> = classes.iter().copied().collect();
+        let mut queue: VecDeque<(WikiId, usize)> =
+            classes.iter().map(|class| (*class, 0)).collect();
+
+        while let Some((class, depth)) = queue.pop_front() {
+            if let Some(categories) = self.class_to_categories.get(&class) {
+                result.categories.extend(categories.iter().cloned());
+                result.depth = Some(result.depth.map_or(depth, |d| d.min(depth)));
+                continue;
+            }
+
+            if depth >= max_depth {
+                continue;
+            }
+            for superclass in self.superclasses.get(&class).into_iter().flatten() {
+                if visited.insert(*superclass) {
+                    queue.push_back((*superclass, depth + 1));
+                }
+            }
+        }
+
+        result
+    }
+
     #[allow(clippy::unused_self)]
     #[must_use]
     pub fn is_product(&self, item: &transpaer_wikidata::data::Item) -> bool {
@@ -715,7 +802,7 @@ impl TranspaerLibraryAdvisor {
     /// Returns `Err` if fails to read from `path` or parse the contents.
     pub fn load(path: &std::path::Path) -> Result<Self, errors::ProcessingError> {
         if utils::file_exists(path).is_ok() {
-            let data = transpaer::reader::parse_library(path)?;
+            let data = validating::read(path, transpaer::reader::parse_library)?;
             Ok(Self::new(data))
         } else {
             log::warn!(
@@ -732,3 +819,57 @@ impl TranspaerLibraryAdvisor {
         &self.info
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advisor() -> WikidataAdvisor {
+        let id = WikiId::new;
+        let category = |name: &str| HashSet::from([name.to_owned()]);
+        WikidataAdvisor::new(
+            HashSet::new(),
+            HashMap::new(),
+            HashMap::from([(id(10), category("electronics")), (id(20), category("smartphone"))]),
+            HashMap::from([
+                // 1 -> 2 -> 3 -> 10
+                (id(1), vec![id(2)]),
+                (id(2), vec![id(3)]),
+                (id(3), vec![id(10)]),
+                // 4 -> 5 -> 4 (a cycle)
+                (id(4), vec![id(5)]),
+                (id(5), vec![id(4)]),
+                // 20 -> 10
+                (id(20), vec![id(10)]),
+            ]),
+        )
+    }
+
+    #[test]
+    fn find_categories_in_superclasses() {
+        let advisor = advisor();
+
+        let found = advisor.find_categories(&[WikiId::new(10)], 0);
+        assert_eq!(found.categories, HashSet::from(["electronics".to_owned()]));
+        assert_eq!(found.depth, Some(0));
+
+        let found = advisor.find_categories(&[WikiId::new(1)], 2);
+        assert_eq!(found, CategoryMatch::default());
+
+        let found = advisor.find_categories(&[WikiId::new(1)], 3);
+        assert_eq!(found.categories, HashSet::from(["electronics".to_owned()]));
+        assert_eq!(found.depth, Some(3));
+    }
+
+    #[test]
+    fn find_categories_stops_at_matches_and_cycles() {
+        let advisor = advisor();
+
+        let found = advisor.find_categories(&[WikiId::new(20)], 5);
+        assert_eq!(found.categories, HashSet::from(["smartphone".to_owned()]));
+        assert_eq!(found.depth, Some(0));
+
+        let found = advisor.find_categories(&[WikiId::new(4)], 100);
+        assert_eq!(found, CategoryMatch::default());
+    }
+}
@@ -0,0 +1,340 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `bundle-repro` command.
+//!
+//! Reproducing a problem with a single organisation or product usually requires the whole
+//! dataset. This command extracts only the substrate entries merged into the entity and their
+//! coagulate mappings into a small tarball, which can be attached to bug reports and replayed with
+//! the `trace` command or in tests.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use transpaer_models::gather;
+
+use crate::{
+    coagulate::{Coagulate, ExternalId, InnerId},
+    commands::TraceKind,
+    condensing, config,
+    crystalizing::TraceTarget,
+    errors::{self, CoagulationError},
+    substrate::{DataSetId, Substrates},
+};
+
+/// Name of the manifest file.
+const MANIFEST_FILE: &str = "BUNDLE.json";
+
+/// Directory of the substrate files in the bundle.
+const SUBSTRATE_DIR: &str = "substrate";
+
+/// Directory of the coagulate in the bundle.
+const COAGULATE_DIR: &str = "coagulate";
+
+/// Substrate file included in the bundle.
+#[derive(Debug, Serialize)]
+struct BundledSubstrate {
+    /// Name of the substrate.
+    name: String,
+
+    /// Number of the bundled entries or `None` if the whole file was bundled.
+    num_entries: Option<usize>,
+}
+
+/// Description of the bundle.
+#[derive(Debug, Serialize)]
+struct Manifest {
+    /// Kind of the bundled entity.
+    kind: &'static str,
+
+    /// Unique ID of the entity in the original coagulate.
+    unique_id: u32,
+
+    /// Name of the substrate of the entry the bundle was requested for.
+    source: Option<String>,
+
+    /// ID the bundle was requested for.
+    id: String,
+
+    created: String,
+    generator: String,
+
+    /// Bundled substrate files.
+    substrates: Vec<BundledSubstrate>,
+
+    /// Command replaying the coagulation and crystalization of the entity from the bundle.
+    replay: String,
+}
+
+pub struct BundleRunner;
+
+impl BundleRunner {
+    pub fn run(config: &config::BundleConfig) -> Result<(), errors::ProcessingError> {
+        let staging = config.output.with_extension("staging");
+        let Some(manifest) = Self::stage(config, &staging)? else {
+            return Ok(());
+        };
+        for substrate in &manifest.substrates {
+            match substrate.num_entries {
+                Some(num_entries) => log::info!("    {}: {num_entries} entries", substrate.name),
+                None => log::info!("    {}: whole file", substrate.name),
+            }
+        }
+
+        log::info!("Packing into `{}`", config.output.display());
+        let prefix = format!("bundle-{}-{}", manifest.kind, manifest.unique_id);
+        Self::pack(&staging, &prefix, &config.output)?;
+        std::fs::remove_dir_all(&staging).map_err(|e| errors::ProcessingError::Io(e, staging))?;
+        Ok(())
+    }
+
+    /// Writes the bundled files into the staging directory.
+    ///
+    /// Returns `None` if there is nothing to bundle.
+    fn stage(
+        config: &config::BundleConfig,
+        staging: &Path,
+    ) -> Result<Option<Manifest>, errors::ProcessingError> {
+        let (substrates, substrates_report) =
+            Substrates::prepare(&config.substrate.substrate_path)?;
+        substrates_report.report();
+
+        let mut coagulate = Coagulate::read(&config.coagulate, &substrates)?;
+        let Some(target) = Self::resolve(config, &substrates, &coagulate)? else {
+            log::warn!("The entry `{}` was not coagulated", config.id);
+            return Ok(None);
+        };
+        let members: BTreeSet<ExternalId> = match &target {
+            TraceTarget::Organisation(id) => coagulate.get_producer_external_ids(id),
+            TraceTarget::Product(id) => coagulate.get_product_external_ids(id),
+        }
+        .into_iter()
+        .collect();
+        if members.is_empty() {
+            log::warn!("No substrate entries were merged into {target:?}");
+            return Ok(None);
+        }
+
+        let substrate_dir = staging.join(SUBSTRATE_DIR);
+        let (bundled, whole) =
+            Self::bundle_substrates(&substrates, &members, &target, &substrate_dir)?;
+
+        let coagulate_dir = staging.join(COAGULATE_DIR);
+        std::fs::create_dir_all(&coagulate_dir)
+            .map_err(|e| errors::ProcessingError::Io(e, coagulate_dir.clone()))?;
+        coagulate.retain(|external| {
+            members.contains(external) || whole.contains(&external.data_set_id())
+        });
+        coagulate.save(&coagulate_dir.join("coagulate.yaml"), &substrates)?;
+
+        let (kind, unique_id) = match &target {
+            TraceTarget::Organisation(id) => ("organisation", id.as_value()),
+            TraceTarget::Product(id) => ("product", id.as_value()),
+        };
+        let manifest = Manifest {
+            kind,
+            unique_id,
+            source: config.source.clone(),
+            id: config.id.clone(),
+            created: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            generator: format!("transpaer-lab {}", env!("CARGO_PKG_VERSION")),
+            substrates: bundled,
+            replay: format!(
+                "transpaer-lab trace {unique_id} --kind {kind} --substrate {SUBSTRATE_DIR} \
+                 --coagulate {COAGULATE_DIR}"
+            ),
+        };
+        let path = staging.join(MANIFEST_FILE);
+        let contents =
+            serde_json::to_string_pretty(&manifest).map_err(errors::ProcessingError::WriteJson)?;
+        std::fs::write(&path, contents).map_err(|e| errors::ProcessingError::Io(e, path))?;
+        Ok(Some(manifest))
+    }
+
+    /// Writes the substrate entries merged into the entity into the substrate directory.
+    ///
+    /// Files which cannot be filtered are bundled whole and their IDs returned, so that all their
+    /// mappings can be kept, as crystalization requires every entry to be coagulated.
+    fn bundle_substrates(
+        substrates: &Substrates,
+        members: &BTreeSet<ExternalId>,
+        target: &TraceTarget,
+        substrate_dir: &Path,
+    ) -> Result<(Vec<BundledSubstrate>, BTreeSet<DataSetId>), errors::ProcessingError> {
+        std::fs::create_dir_all(substrate_dir)
+            .map_err(|e| errors::ProcessingError::Io(e, substrate_dir.to_owned()))?;
+
+        let mut whole = BTreeSet::<DataSetId>::new();
+        let mut bundled = Vec::new();
+        for substrate in substrates.list() {
+            let ids: HashSet<String> = members
+                .iter()
+                .filter(|member| member.data_set_id() == substrate.id)
+                .map(|member| member.inner().to_owned())
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+
+            let num_entries = ids.len();
+            let (producer_ids, product_ids) = match target {
+                TraceTarget::Organisation(_) => (ids, HashSet::new()),
+                TraceTarget::Product(_) => (HashSet::new(), ids),
+            };
+            let extracted = condensing::extract_substrate_entries(
+                &substrate.name,
+                &substrate.path,
+                &producer_ids,
+                &product_ids,
+            )?;
+            if let Some((mut extracted, extension)) = extracted {
+                let path = substrate_dir.join(&substrate.name).with_extension(extension.as_str());
+                extracted.sort();
+                extracted.save(&path)?;
+                bundled.push(BundledSubstrate {
+                    name: substrate.name.clone(),
+                    num_entries: Some(num_entries),
+                });
+            } else {
+                log::warn!(
+                    "Entries of `{}` cannot be extracted, bundling the whole file",
+                    substrate.name
+                );
+                let path = substrate_dir.join(substrate.path.file_name().unwrap_or_default());
+                std::fs::copy(&substrate.path, &path)
+                    .map_err(|e| errors::ProcessingError::Io(e, path))?;
+                whole.insert(substrate.id);
+                bundled.push(BundledSubstrate { name: substrate.name.clone(), num_entries: None });
+            }
+        }
+        Ok((bundled, whole))
+    }
+
+    /// Finds the unique ID of the bundled entity.
+    ///
+    /// Returns `None` if the requested substrate entry was not coagulated.
+    fn resolve(
+        config: &config::BundleConfig,
+        substrates: &Substrates,
+        coagulate: &Coagulate,
+    ) -> Result<Option<TraceTarget>, errors::ProcessingError> {
+        if let Some(unique_id) = config.unique_id()? {
+            return Ok(Some(match config.kind {
+                TraceKind::Organisation => {
+                    TraceTarget::Organisation(gather::OrganisationId::from_value(unique_id))
+                }
+                TraceKind::Product => {
+                    TraceTarget::Product(gather::ProductId::from_value(unique_id))
+                }
+            }));
+        }
+
+        let name = config.source.clone().unwrap_or_default();
+        let data_set_id = *substrates
+            .get_id_for_name(&name)
+            .ok_or(CoagulationError::SubstrateIdNotFoundForName { name })?;
+        let external = ExternalId::new(data_set_id, InnerId::new(config.id.clone()));
+        Ok(match config.kind {
+            TraceKind::Organisation => coagulate
+                .get_unique_id_for_producer_external_id(&external)
+                .ok()
+                .map(TraceTarget::Organisation),
+            TraceKind::Product => coagulate
+                .get_unique_id_for_product_external_id(&external)
+                .ok()
+                .map(TraceTarget::Product),
+        })
+    }
+
+    /// Packs the staging directory into a gzipped tarball.
+    fn pack(dir: &Path, prefix: &str, output: &Path) -> Result<(), errors::ProcessingError> {
+        let file = std::fs::File::create(output)
+            .map_err(|e| errors::ProcessingError::Io(e, output.to_owned()))?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder
+            .append_dir_all(prefix, dir)
+            .map_err(|e| errors::ProcessingError::Io(e, dir.to_owned()))?;
+        builder
+            .into_inner()
+            .and_then(flate2::write::GzEncoder::finish)
+            .map_err(|e| errors::ProcessingError::Io(e, output.to_owned()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use transpaer_testgen::{ProducerBuilder, ProductBuilder, SubstrateBuilder, SubstrateDir};
+
+    use super::*;
+    use crate::condensing::{Collector, ReviewerCollector};
+
+    #[test]
+    fn bundle_product() {
+        let dir = SubstrateDir::with([
+            SubstrateBuilder::reviewer("tco")
+                .producer(ProducerBuilder::new("1").name("Fairphone"))
+                .product(ProductBuilder::new("10").name("Fairphone 5"))
+                .product(ProductBuilder::new("11").name("Shift 6mq")),
+            SubstrateBuilder::cataloger("custom")
+                .product(ProductBuilder::new("20").name("Fairphone 5")),
+        ])
+        .unwrap();
+        let output = tempfile::tempdir().unwrap();
+
+        let (substrates, _) = Substrates::prepare(dir.path()).unwrap();
+        let tco = *substrates.get_id_for_name("tco").unwrap();
+        let custom = *substrates.get_id_for_name("custom").unwrap();
+        let external =
+            |data_set_id, id: &str| ExternalId::new(data_set_id, InnerId::new(id.into()));
+        let coagulate = Coagulate::new(
+            BTreeMap::from([(external(tco, "1"), gather::OrganisationId::from_value(1))]),
+            BTreeMap::from([
+                (external(tco, "10"), gather::ProductId::from_value(1)),
+                (external(tco, "11"), gather::ProductId::from_value(2)),
+                (external(custom, "20"), gather::ProductId::from_value(1)),
+            ]),
+        );
+        let coagulate_path = output.path().join("coagulate.yaml");
+        coagulate.save(&coagulate_path, &substrates).unwrap();
+
+        let config = config::BundleConfig {
+            substrate: config::SubstrateConfig::new(&dir.path().to_string_lossy()),
+            coagulate: coagulate_path,
+            output: output.path().join("bundle.tar.gz"),
+            kind: TraceKind::Product,
+            id: "10".to_owned(),
+            source: Some("tco".to_owned()),
+        };
+        let staging = output.path().join("staging");
+        let manifest = BundleRunner::stage(&config, &staging).unwrap().unwrap();
+        assert_eq!(manifest.unique_id, 1);
+        assert_eq!(manifest.substrates.len(), 2);
+
+        let (bundled, _) = Substrates::prepare(&staging.join(SUBSTRATE_DIR)).unwrap();
+        let path = bundled.get_path_for_id(*bundled.get_id_for_name("tco").unwrap()).unwrap();
+        let collector = ReviewerCollector::read(path).unwrap().unwrap();
+        assert_eq!(collector.product_ids(), vec!["10".to_owned()]);
+        assert!(collector.producer_ids().is_empty());
+
+        let coagulate =
+            Coagulate::read(&staging.join(COAGULATE_DIR).join("coagulate.yaml"), &bundled).unwrap();
+        let members = coagulate.get_product_external_ids(&gather::ProductId::from_value(1));
+        assert_eq!(members.len(), 2);
+        assert!(coagulate.get_product_external_ids(&gather::ProductId::from_value(2)).is_empty());
+        assert!(
+            coagulate.get_producer_external_ids(&gather::OrganisationId::from_value(1)).is_empty()
+        );
+
+        BundleRunner::pack(&staging, "bundle", &config.output).unwrap();
+        assert!(config.output.exists());
+    }
+}
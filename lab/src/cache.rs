@@ -3,17 +3,44 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 //! Contains code ralated to parsing and saving cache data.
+//!
+//! Caches derived from a Wikidata dump are kept in a separate namespace per dump version, so
+//! switching between dump versions never mixes their data. The `current` link in the cache
+//! directory points to the namespace of the dump that was extracted most recently.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
 use transpaer_collecting::errors::{IoOrSerdeError, MapIo, MapSerde};
 
+use crate::{config, errors, remote, utils};
+
+/// Name of the link pointing to the namespace of the current dump.
+pub const CURRENT_NAMESPACE: &str = "current";
+
+/// Name of the directory holding all the namespaces.
+const NAMESPACES_DIR: &str = "namespaces";
+
+/// Number of the newest namespaces kept by the automatic cleanup.
+///
+/// The previous namespace is kept to allow switching back to the previous dump without
+/// re-extracting it.
+const NUM_NAMESPACES_TO_KEEP: usize = 2;
+
 /// Cached data from search over Wikidata data.
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Wikidata {
     /// Manufacturer IDs.
     #[serde(deserialize_with = "transpaer_wikidata::data::deserialize_vec_id_from_vec_integer")]
     pub manufacturer_ids: Vec<transpaer_wikidata::data::Id>,
+
+    /// Map from numeric IDs of classes to numeric IDs of their direct superclasses.
+    #[serde(default)]
+    pub superclasses: BTreeMap<u64, Vec<u64>>,
 }
 
 /// Reads in the cache data.
@@ -30,3 +57,135 @@ pub fn load(path: &std::path::Path) -> Result<Wikidata, IoOrSerdeError> {
         Ok(Wikidata::default())
     }
 }
+
+/// Returns the name of the namespace for caches derived from the `source` file.
+///
+/// Calculating a checksum of a whole dump would take too long, so the name is composed from the
+/// modification date and the size of the file instead. It stays the same until the file is
+/// replaced, so re-running the extraction on the same dump reuses its namespace.
+///
+/// # Errors
+///
+/// Returns `Err` if the metadata of the file cannot be read.
+pub fn namespace_name(source: &Path) -> std::io::Result<String> {
+    let metadata = std::fs::metadata(source)?;
+    let modified = humantime::format_rfc3339_seconds(metadata.modified()?).to_string();
+    let date = modified.split('T').next().unwrap_or(&modified);
+    Ok(format!("{date}-{:x}", metadata.len()))
+}
+
+/// Returns the name of the namespace for a dump streamed from the `url`.
+///
+/// The name is composed the same way as in `namespace_name` from the metadata sent by the server.
+///
+/// # Errors
+///
+/// Returns `Err` if the metadata cannot be requested.
+pub fn remote_namespace_name(url: &str) -> std::io::Result<String> {
+    let metadata = remote::metadata(url)?;
+    Ok(format!("{}-{:x}", metadata.date, metadata.length))
+}
+
+/// Returns the name of the namespace the `current` link points to.
+pub fn current_namespace(cache: &Path) -> Option<String> {
+    let target = std::fs::read_link(cache.join(CURRENT_NAMESPACE)).ok()?;
+    target.file_name().map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Creates the namespace if needed and points the `current` link to it.
+///
+/// # Errors
+///
+/// Returns `Err` if the namespace or the link cannot be created.
+pub fn activate_namespace(cache: &Path, name: &str) -> Result<PathBuf, errors::ProcessingError> {
+    let namespace = cache.join(NAMESPACES_DIR).join(name);
+    std::fs::create_dir_all(&namespace).map_with_path(&namespace)?;
+    utils::switch_link(&namespace, &cache.join(CURRENT_NAMESPACE))?;
+    log::info!("Using cache namespace `{name}`");
+    Ok(namespace)
+}
+
+/// Removes the oldest namespaces keeping at most `num_to_keep` of them.
+///
+/// The current namespace is never removed.
+///
+/// # Errors
+///
+/// Returns `Err` if the namespaces cannot be listed or removed.
+pub fn remove_old_namespaces(
+    cache: &Path,
+    num_to_keep: usize,
+) -> Result<(), errors::ProcessingError> {
+    let dir = cache.join(NAMESPACES_DIR);
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let current = current_namespace(cache);
+    let mut namespaces = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_with_path(&dir)? {
+        let path = entry.map_with_path(&dir)?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned());
+        if current.is_none() || name != current {
+            namespaces.push(path);
+        }
+    }
+
+    // Names start with the date of the dump, so they sort from the oldest.
+    namespaces.sort();
+    let num_to_keep = num_to_keep.saturating_sub(usize::from(current.is_some()));
+    let num_to_remove = namespaces.len().saturating_sub(num_to_keep);
+    for namespace in namespaces.into_iter().take(num_to_remove) {
+        log::info!("Removing `{}`", namespace.display());
+        std::fs::remove_dir_all(&namespace).map_with_path(&namespace)?;
+    }
+    Ok(())
+}
+
+/// Removes old namespaces after a new one was populated.
+///
+/// # Errors
+///
+/// Returns `Err` if the namespaces cannot be listed or removed.
+pub fn cleanup(cache: &Path) -> Result<(), errors::ProcessingError> {
+    remove_old_namespaces(cache, NUM_NAMESPACES_TO_KEEP)
+}
+
+pub struct CachingRunner;
+
+impl CachingRunner {
+    pub fn run(config: &config::CachingConfig) -> Result<(), errors::ProcessingError> {
+        match config.action {
+            config::CachingAction::Gc => {
+                // Only the current namespace is kept.
+                remove_old_namespaces(&config.cache.cache_path, 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{activate_namespace, current_namespace, remove_old_namespaces};
+
+    #[test]
+    fn namespaces_cleanup() {
+        let cache = tempfile::tempdir().unwrap();
+        for name in ["2024-01-01-10", "2024-02-01-20", "2024-03-01-30"] {
+            activate_namespace(cache.path(), name).unwrap();
+        }
+        activate_namespace(cache.path(), "2024-01-01-10").unwrap();
+        assert_eq!(current_namespace(cache.path()).as_deref(), Some("2024-01-01-10"));
+
+        remove_old_namespaces(cache.path(), 2).unwrap();
+        let namespaces = cache.path().join("namespaces");
+        assert!(namespaces.join("2024-01-01-10").exists());
+        assert!(!namespaces.join("2024-02-01-20").exists());
+        assert!(namespaces.join("2024-03-01-30").exists());
+
+        remove_old_namespaces(cache.path(), 1).unwrap();
+        assert!(namespaces.join("2024-01-01-10").exists());
+        assert!(!namespaces.join("2024-03-01-30").exists());
+    }
+}
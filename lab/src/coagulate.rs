@@ -14,6 +14,12 @@ use crate::{
     substrate::{DataSetId, Substrate, Substrates},
 };
 
+/// Namespace of the IDs in the data curated by the Transpaer team.
+pub const TRANSPAER_NAMESPACE: &str = "transpaer";
+
+/// Separates the namespace from the rest of a source-qualified ID.
+const NAMESPACE_SEPARATOR: char = ':';
+
 /// An ID unique within a context inside of a single substrate file.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct InnerId(String);
@@ -23,6 +29,18 @@ impl InnerId {
     pub fn new(id: String) -> Self {
         Self(id)
     }
+
+    /// Creates an ID qualified with the namespace of its source, e.g. `transpaer:fairphone`.
+    #[must_use]
+    pub fn namespaced(namespace: &str, id: &str) -> Self {
+        Self(format!("{namespace}{NAMESPACE_SEPARATOR}{id}"))
+    }
+
+    /// Returns the namespace the ID is qualified with, if any.
+    #[must_use]
+    pub fn namespace(&self) -> Option<&str> {
+        self.0.split_once(NAMESPACE_SEPARATOR).map(|(namespace, _)| namespace)
+    }
 }
 
 impl std::fmt::Display for InnerId {
@@ -44,11 +62,39 @@ impl ExternalId {
         Self { data_set_id, inner: inner.0 }
     }
 
+    /// Creates an external ID for an entry read from the substrate.
+    #[must_use]
+    pub fn from_substrate(substrate: &Substrate, inner: &str) -> Self {
+        Self::new(substrate.id, InnerId::new(inner.to_owned()))
+    }
+
+    /// Checks if the inner ID lacks the namespace its source should qualify it with.
+    ///
+    /// The curated Transpaer data refers to entries known also from other sources, so its IDs
+    /// should be qualified with the Transpaer namespace to not be confused with the source IDs.
+    /// Unqualified IDs are still accepted, since the data set ID keeps them apart, but they are
+    /// reported so the curated data can be migrated.
+    #[must_use]
+    pub fn is_ambiguous(&self, substrate: &Substrate) -> bool {
+        matches!(substrate.source, gather::Source::Transpaer)
+            && InnerId::new(self.inner.clone()).namespace() != Some(TRANSPAER_NAMESPACE)
+    }
+
     #[must_use]
     pub fn decompose(self) -> (DataSetId, InnerId) {
         (self.data_set_id, InnerId(self.inner))
     }
 
+    #[must_use]
+    pub fn data_set_id(&self) -> DataSetId {
+        self.data_set_id
+    }
+
+    #[must_use]
+    pub fn inner(&self) -> &str {
+        &self.inner
+    }
+
     #[must_use]
     pub fn to_error_not_found(&self, substrate: &Substrate, when: &str) -> CoagulationError {
         CoagulationError::UniqueIdNotFoundForInnerId {
@@ -122,6 +168,31 @@ impl Coagulate {
         }
     }
 
+    /// Returns the external IDs of producers merged into the given unique ID.
+    #[must_use]
+    pub fn get_producer_external_ids(&self, unique_id: &gather::OrganisationId) -> Vec<ExternalId> {
+        Self::find_external_ids(&self.producer, unique_id)
+    }
+
+    /// Returns the external IDs of products merged into the given unique ID.
+    #[must_use]
+    pub fn get_product_external_ids(&self, unique_id: &gather::ProductId) -> Vec<ExternalId> {
+        Self::find_external_ids(&self.product, unique_id)
+    }
+
+    /// Keeps only the mappings of the external IDs accepted by the predicate.
+    pub fn retain(&mut self, keep: impl Fn(&ExternalId) -> bool) {
+        self.producer.retain(|external, _| keep(external));
+        self.product.retain(|external, _| keep(external));
+    }
+
+    fn find_external_ids<U: Eq>(map: &BTreeMap<ExternalId, U>, unique_id: &U) -> Vec<ExternalId> {
+        map.iter()
+            .filter(|(_, id)| *id == unique_id)
+            .map(|(external, _)| external.clone())
+            .collect()
+    }
+
     pub fn save(
         self,
         path: &std::path::Path,
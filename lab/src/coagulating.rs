@@ -8,21 +8,112 @@ use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use thiserror::Error;
 
 use transpaer_models::{
-    buckets::{Bucket, BucketError},
+    buckets::{Bucket, BucketError, Storage, StorageEngine},
     gather, ids,
+    utils::normalize_company_name,
 };
 use transpaer_schema as schema;
 
 use crate::{
     coagulate::{Coagulate, ExternalId, InnerId, UniqueId},
     config, errors,
-    substrate::{DataSetId, Substrates},
+    substrate::{DataSetId, Substrate, Substrates},
+    utils::InvalidVatIds,
+    workspace::RunDir,
 };
 
 #[derive(Default)]
 pub struct Summary {
     pub product_ids: Vec<ProductIds>,
     pub producer_ids: Vec<ProducerIds>,
+
+    /// Brands which are matched to other producers by name instead of by IDs.
+    pub brands: Vec<BrandNames>,
+
+    /// Producers with names equal to any of the brand names.
+    pub producer_names: BTreeMap<String, BTreeSet<ExternalId>>,
+}
+
+impl Summary {
+    fn add_brand(&mut self, brand: BrandNames) {
+        for name in &brand.names {
+            self.producer_names.entry(name.clone()).or_default();
+        }
+        self.brands.push(brand);
+    }
+
+    /// Checks if the producer is a brand matched to other producers by name.
+    #[must_use]
+    pub fn is_brand(&self, external: &ExternalId) -> bool {
+        self.brands.iter().any(|brand| &brand.external == external)
+    }
+
+    /// Lists the individual IDs shared by at least two of the given producers.
+    ///
+    /// These are the IDs which caused the producers to be merged.
+    #[must_use]
+    pub fn shared_producer_ids(
+        &self,
+        members: &BTreeSet<ExternalId>,
+    ) -> BTreeMap<String, BTreeSet<ExternalId>> {
+        Self::shared_ids(&self.producer_ids, members)
+    }
+
+    /// Lists the individual IDs shared by at least two of the given products.
+    ///
+    /// These are the IDs which caused the products to be merged.
+    #[must_use]
+    pub fn shared_product_ids(
+        &self,
+        members: &BTreeSet<ExternalId>,
+    ) -> BTreeMap<String, BTreeSet<ExternalId>> {
+        Self::shared_ids(&self.product_ids, members)
+    }
+
+    fn shared_ids<T: IdStructure>(
+        ids: &[T],
+        members: &BTreeSet<ExternalId>,
+    ) -> BTreeMap<String, BTreeSet<ExternalId>> {
+        let mut result = BTreeMap::<String, BTreeSet<ExternalId>>::new();
+        for entry in ids {
+            let external = entry.get_external_id();
+            if members.contains(&external) {
+                for individual in entry.get_individual_ids() {
+                    result.entry(format!("{individual:?}")).or_default().insert(external.clone());
+                }
+            }
+        }
+        result.retain(|_, externals| externals.len() > 1);
+        result
+    }
+
+    /// Remembers the producer if it has the same name as any of the already added brands.
+    fn add_producer_names(&mut self, external: &ExternalId, names: &[String]) {
+        for name in names {
+            if let Some(externals) = self.producer_names.get_mut(&normalize_company_name(name)) {
+                externals.insert(external.clone());
+            }
+        }
+    }
+}
+
+/// Normalized names of a brand.
+#[derive(Clone, Debug)]
+pub struct BrandNames {
+    external: ExternalId,
+    names: Vec<String>,
+}
+
+impl BrandNames {
+    #[must_use]
+    pub fn from_catalog(producer: &schema::CatalogProducer, data_set_id: DataSetId) -> Self {
+        let external = ExternalId::new(data_set_id, InnerId::new(producer.id.clone()));
+        let mut names: Vec<String> =
+            producer.names.iter().map(|name| normalize_company_name(name)).collect();
+        names.sort();
+        names.dedup();
+        Self { external, names }
+    }
 }
 
 pub struct Coagulator;
@@ -34,10 +125,10 @@ impl Coagulator {
                 Substrates::prepare(&config.substrate.substrate_path)?;
             substrates_report.report();
 
-            let (summary, coagulator_report) = Self::summarize(&substrates)?;
+            let (summary, mut coagulator_report) = Self::summarize(&substrates)?;
+            let coagulate = Self::group(&summary, config, &mut coagulator_report)?;
             coagulator_report.report(&substrates);
 
-            let coagulate = Self::group(&summary, config)?;
             log::info!("Saving the coagulate");
             coagulate.save(&config.coagulate, &substrates)?;
 
@@ -45,24 +136,36 @@ impl Coagulator {
         })
     }
 
-    fn summarize(
+    pub fn summarize(
         substrates: &Substrates,
     ) -> Result<(Summary, CoagulationReport), errors::CoagulationError> {
         log::info!("Gathering IDs");
         let mut result = Summary::default();
         let mut report = CoagulationReport::default();
-        for substrate in substrates.list() {
+
+        // Brands are processed first so that only the matching producer names need to be kept.
+        let (brand_substrates, other_substrates): (Vec<_>, Vec<_>) =
+            substrates.list().iter().partition(|substrate| substrate.source.is_open_supply_hub());
+        for substrate in brand_substrates.into_iter().chain(other_substrates) {
             match schema::read::iter_file(&substrate.path)? {
                 schema::read::FileIterVariant::Catalog(iter) => {
                     for entry in iter {
                         match entry? {
+                            schema::CatalogEntry::Producer(producer)
+                                if substrate.source.is_open_supply_hub() =>
+                            {
+                                result.add_brand(BrandNames::from_catalog(&producer, substrate.id));
+                            }
                             schema::CatalogEntry::Producer(producer) => {
+                                Self::check_id(substrate, &producer.id, &mut report);
                                 let (ids, warnings) =
                                     ProducerIds::from_catalog(&producer, substrate.id);
+                                result.add_producer_names(&ids.external, &producer.names);
                                 result.producer_ids.push(ids);
                                 report.add_many(warnings);
                             }
                             schema::CatalogEntry::Product(product) => {
+                                Self::check_id(substrate, &product.id, &mut report);
                                 let (ids, warnings) =
                                     ProductIds::from_catalog(&product, substrate.id);
                                 result.product_ids.push(ids);
@@ -75,6 +178,7 @@ impl Coagulator {
                     for entry in iter {
                         match entry? {
                             schema::ProducerEntry::Product(product) => {
+                                Self::check_id(substrate, &product.id, &mut report);
                                 let (ids, warnings) =
                                     ProductIds::from_producer(&product, substrate.id);
                                 result.product_ids.push(ids);
@@ -90,12 +194,15 @@ impl Coagulator {
                     for entry in iter {
                         match entry? {
                             schema::ReviewEntry::Producer(producer) => {
+                                Self::check_id(substrate, &producer.id, &mut report);
                                 let (ids, warnings) =
                                     ProducerIds::from_review(&producer, substrate.id);
+                                result.add_producer_names(&ids.external, &producer.names);
                                 result.producer_ids.push(ids);
                                 report.add_many(warnings);
                             }
                             schema::ReviewEntry::Product(product) => {
+                                Self::check_id(substrate, &product.id, &mut report);
                                 let (ids, warnings) =
                                     ProductIds::from_review(&product, substrate.id);
                                 result.product_ids.push(ids);
@@ -110,15 +217,24 @@ impl Coagulator {
         Ok((result, report))
     }
 
+    /// Checks if the ID of an entry is qualified with the namespace of its source.
+    ///
+    /// Ambiguous IDs are still used for coagulation but added to the report.
+    fn check_id(substrate: &Substrate, id: &str, report: &mut CoagulationReport) {
+        let external_id = ExternalId::from_substrate(substrate, id);
+        if external_id.is_ambiguous(substrate) {
+            let (data_set_id, inner_id) = external_id.decompose();
+            report.add(CoagulationWarning::AmbiguousInnerId { data_set_id, inner_id });
+        }
+    }
+
     fn group(
         summary: &Summary,
         config: &config::CoagulationConfig,
+        report: &mut CoagulationReport,
     ) -> Result<Coagulate, errors::CoagulationError> {
-        if config.runtime.exists() {
-            std::fs::remove_dir_all(&config.runtime)
-                .map_err(|e| errors::CoagulationError::Io(e, config.runtime.clone()))?;
-        }
-        let store = GroupingStore::new(&config.runtime)?;
+        let run_dir = RunDir::create(&config.workspace, "coagulation")?;
+        let store = GroupingStore::new(run_dir.path())?;
 
         log::info!("Grouping producer IDs");
         let producer = {
@@ -133,7 +249,10 @@ impl Coagulator {
             log::info!(" - unique IDs: {}", result.num_unique_ids);
             log::info!(" - empty IDs: {}", result.num_empty_ids);
 
-            result.external_to_unique
+            report.producer_clusters = result.clusters;
+            let mut external_to_unique = result.external_to_unique;
+            Self::match_brands(summary, &mut external_to_unique, report);
+            external_to_unique
         };
 
         log::info!("Grouping product IDs");
@@ -148,11 +267,42 @@ impl Coagulator {
             log::info!(" - unique IDs: {}", result.num_unique_ids);
             log::info!(" - empty IDs: {}", result.num_empty_ids);
 
+            report.product_clusters = result.clusters;
             result.external_to_unique
         };
 
         Ok(Coagulate::new(producer, product))
     }
+
+    /// Assigns the brands to the producers with the same names.
+    ///
+    /// Brands matching no producer or more than one producer are left out.
+    fn match_brands(
+        summary: &Summary,
+        producers: &mut BTreeMap<ExternalId, gather::OrganisationId>,
+        report: &mut CoagulationReport,
+    ) {
+        log::info!("Matching brands");
+        for brand in &summary.brands {
+            let matches: BTreeSet<gather::OrganisationId> = brand
+                .names
+                .iter()
+                .filter_map(|name| summary.producer_names.get(name))
+                .flatten()
+                .filter_map(|external| producers.get(external).cloned())
+                .collect();
+
+            let mut matches = matches.into_iter();
+            match (matches.next(), matches.next()) {
+                (Some(unique_id), None) => {
+                    producers.insert(brand.external.clone(), unique_id);
+                    report.matched_brands += 1;
+                }
+                (None, _) => report.unmatched_brands += 1,
+                (Some(_), Some(_)) => report.ambiguous_brands += 1,
+            }
+        }
+    }
 }
 
 impl UniqueId for gather::OrganisationId {
@@ -185,6 +335,64 @@ trait IdStructure {
     fn get_individual_ids(&self) -> Vec<Self::IndividualId>;
 }
 
+/// Number of the largest clusters kept for the report.
+const NUM_LARGEST_CLUSTERS: usize = 10;
+
+/// Histogram of the numbers of external IDs merged into a single unique ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClusterSizeHistogram {
+    /// Clusters with a single external ID.
+    pub single: u64,
+
+    /// Clusters with 2 to 5 external IDs.
+    pub small: u64,
+
+    /// Clusters with 6 to 20 external IDs.
+    pub medium: u64,
+
+    /// Clusters with more than 20 external IDs.
+    pub large: u64,
+}
+
+impl ClusterSizeHistogram {
+    pub fn add(&mut self, size: usize) {
+        match size {
+            0 | 1 => self.single += 1,
+            2..=5 => self.small += 1,
+            6..=20 => self.medium += 1,
+            _ => self.large += 1,
+        }
+    }
+}
+
+/// Statistics about the clusters of merged IDs.
+///
+/// Allows to detect pathological merges, e.g. caused by an ID shared by many unrelated entries.
+#[derive(Clone, Debug, Default)]
+pub struct ClusterStats {
+    /// Distribution of the cluster sizes.
+    pub histogram: ClusterSizeHistogram,
+
+    /// External IDs of the members of the largest clusters, sorted from the largest cluster.
+    pub largest: Vec<Vec<ExternalId>>,
+}
+
+impl ClusterStats {
+    pub fn add(&mut self, mut members: Vec<ExternalId>) {
+        self.histogram.add(members.len());
+        if members.len() < 2 {
+            return;
+        }
+
+        let position = self.largest.partition_point(|cluster| cluster.len() >= members.len());
+        if position < NUM_LARGEST_CLUSTERS {
+            members.sort();
+            self.largest.insert(position, members);
+            self.largest.truncate(NUM_LARGEST_CLUSTERS);
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct IdCombinationResult<U>
 where
@@ -193,6 +401,7 @@ where
     pub num_empty_ids: u64,
     pub num_unique_ids: u64,
     pub external_to_unique: BTreeMap<ExternalId, U>,
+    pub clusters: ClusterStats,
 }
 
 impl<U> Default for IdCombinationResult<U>
@@ -200,17 +409,22 @@ where
     U: UniqueId + std::fmt::Debug,
 {
     fn default() -> Self {
-        Self { num_empty_ids: 0, num_unique_ids: 0, external_to_unique: BTreeMap::new() }
+        Self {
+            num_empty_ids: 0,
+            num_unique_ids: 0,
+            external_to_unique: BTreeMap::new(),
+            clusters: ClusterStats::default(),
+        }
     }
 }
 
 struct GroupingStore {
-    store: kv::Store,
+    store: Storage,
 }
 
 impl GroupingStore {
     pub fn new(path: &std::path::Path) -> Result<Self, BucketError> {
-        Ok(Self { store: kv::Store::new(kv::Config::new(path))? })
+        Ok(Self { store: Storage::open(path, StorageEngine::Kv)? })
     }
 
     pub fn get_producer_external_to_individuals_bucket(
@@ -334,20 +548,24 @@ where
             if individual_ids.is_empty() {
                 self.external_to_individuals.remove(&external_id)?;
                 self.result.num_empty_ids += 1;
-                self.result.external_to_unique.insert(external_id, unique_id.clone());
+                self.result.external_to_unique.insert(external_id.clone(), unique_id.clone());
+                self.result.clusters.add(vec![external_id]);
                 continue;
             }
 
+            let mut members = Vec::new();
             loop {
                 let external_ids = self.gather_external_ids(individual_ids, &unique_id)?;
                 if external_ids.is_empty() {
                     break;
                 }
+                members.extend(external_ids.iter().cloned());
                 individual_ids = self.gather_individual_ids(external_ids)?;
                 if individual_ids.is_empty() {
                     break;
                 }
             }
+            self.result.clusters.add(members);
         }
 
         Ok(self.result)
@@ -447,10 +665,9 @@ impl ProducerIds {
             for id in vat {
                 match ids::VatId::try_from(id) {
                     Ok(id) => individual.push(IndividualProducerId::Vat(id)),
-                    Err(_) => warnings.push(CoagulationWarning::InvalidIndividualId {
-                        data_set_id,
-                        individual_id: id.clone(),
-                    }),
+                    Err(error) => {
+                        warnings.push(CoagulationWarning::InvalidVatId { data_set_id, error });
+                    }
                 }
             }
         }
@@ -518,10 +735,9 @@ impl From<&schema::ShoppingEntry> for ShopLink {
 /// One product may have multiple individual IDs, but products cannot share IDs.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 enum IndividualProductId {
-    /// EAN.
-    Ean(ids::Ean),
-
     /// GTIN.
+    ///
+    /// EANs are converted to GTINs so that the same product merges regardless of the ID variant.
     Gtin(ids::Gtin),
 
     /// Wikidata ID.
@@ -583,7 +799,7 @@ impl ProductIds {
         if let Some(ean) = &ids.ean {
             for id in ean {
                 match ids::Ean::try_from(id) {
-                    Ok(id) => individual.push(IndividualProductId::Ean(id)),
+                    Ok(id) => individual.push(IndividualProductId::Gtin(id.to_gtin())),
                     Err(_) => warnings.push(CoagulationWarning::InvalidIndividualId {
                         data_set_id,
                         individual_id: id.clone(),
@@ -642,6 +858,12 @@ pub enum CoagulationWarning {
     // TODO: add more detailed info about the data set and ID variant.
     #[error("Individual ID is not valid: {individual_id}, data_set: {data_set_id:?})")]
     InvalidIndividualId { data_set_id: DataSetId, individual_id: String },
+
+    #[error("VAT number rejected: {error}, data_set: {data_set_id:?}")]
+    InvalidVatId { data_set_id: DataSetId, error: ids::ParseIdError },
+
+    #[error("Inner ID lacks the source namespace: {inner_id}, data_set: {data_set_id:?}")]
+    AmbiguousInnerId { data_set_id: DataSetId, inner_id: InnerId },
 }
 
 // TODO: Rework as repotts per data source
@@ -650,8 +872,15 @@ pub enum CoagulationWarning {
 #[derive(Debug, Default)]
 pub struct CoagulationReport {
     invalid_ids: BTreeMap<DataSetId, BTreeSet<String>>,
+    invalid_vat_ids: BTreeMap<DataSetId, InvalidVatIds>,
     empty_ids: BTreeMap<DataSetId, BTreeSet<InnerId>>,
     missing_inner_ids: BTreeMap<DataSetId, BTreeSet<InnerId>>,
+    ambiguous_ids: BTreeMap<DataSetId, BTreeSet<InnerId>>,
+    producer_clusters: ClusterStats,
+    product_clusters: ClusterStats,
+    matched_brands: usize,
+    unmatched_brands: usize,
+    ambiguous_brands: usize,
 }
 
 impl CoagulationReport {
@@ -660,6 +889,12 @@ impl CoagulationReport {
             CoagulationWarning::InvalidIndividualId { data_set_id, individual_id } => {
                 self.add_invalid_id(data_set_id, individual_id);
             }
+            CoagulationWarning::InvalidVatId { data_set_id, error } => {
+                self.invalid_vat_ids.entry(data_set_id).or_default().add(&error);
+            }
+            CoagulationWarning::AmbiguousInnerId { data_set_id, inner_id } => {
+                self.ambiguous_ids.entry(data_set_id).or_default().insert(inner_id);
+            }
         }
     }
 
@@ -694,6 +929,13 @@ impl CoagulationReport {
                 log::warn!("  - {}: {}", name, ids.len());
             }
         }
+        if !self.invalid_vat_ids.is_empty() {
+            log::warn!(" rejected VAT numbers:");
+            for (data_set_id, rejects) in &self.invalid_vat_ids {
+                let name = substrates.get_name_for_id(*data_set_id).unwrap_or(UNKNOWN);
+                log::warn!("  - {}: {}", name, rejects);
+            }
+        }
         if !self.empty_ids.is_empty() {
             log::warn!(" empty IDs:");
             for (data_set_id, ids) in &self.empty_ids {
@@ -708,8 +950,49 @@ impl CoagulationReport {
                 log::warn!("  - {}: {}", name, ids.len());
             }
         }
+        if !self.ambiguous_ids.is_empty() {
+            log::warn!(" IDs lacking the source namespace:");
+            for (data_set_id, ids) in &self.ambiguous_ids {
+                let name = substrates.get_name_for_id(*data_set_id).unwrap_or(UNKNOWN);
+                let ids = ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+                log::warn!("  - {}: {}", name, ids.join(", "));
+            }
+        }
+        if self.matched_brands + self.unmatched_brands + self.ambiguous_brands > 0 {
+            log::warn!(" brands:");
+            log::warn!("  - matched: {}", self.matched_brands);
+            log::warn!("  - unmatched: {}", self.unmatched_brands);
+            log::warn!("  - ambiguous: {}", self.ambiguous_brands);
+        }
+        Self::report_clusters("producer", &self.producer_clusters, substrates);
+        Self::report_clusters("product", &self.product_clusters, substrates);
         log::warn!("End of the report");
     }
+
+    fn report_clusters(kind: &str, clusters: &ClusterStats, substrates: &Substrates) {
+        const UNKNOWN: &str = "unknown";
+
+        let histogram = &clusters.histogram;
+        log::warn!(" {kind} cluster sizes:");
+        log::warn!("  - 1: {}", histogram.single);
+        log::warn!("  - 2-5: {}", histogram.small);
+        log::warn!("  - 6-20: {}", histogram.medium);
+        log::warn!("  - >20: {}", histogram.large);
+
+        if !clusters.largest.is_empty() {
+            log::warn!(" largest {kind} clusters:");
+            for cluster in &clusters.largest {
+                let members = cluster
+                    .iter()
+                    .map(|id| {
+                        let name = substrates.get_name_for_id(id.data_set_id()).unwrap_or(UNKNOWN);
+                        format!("{name}:{}", id.inner())
+                    })
+                    .collect::<Vec<_>>();
+                log::warn!("  - {}: {}", cluster.len(), members.join(", "));
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -717,8 +1000,8 @@ mod test {
     use serde::{Deserialize, Serialize};
 
     use super::{
-        Bucket, DataSetId, ExternalId, IdCombiner, IdStructure, IndividualId, InnerId, UniqueId,
-        gather,
+        Bucket, ClusterSizeHistogram, DataSetId, ExternalId, IdCombiner, IdStructure, IndividualId,
+        InnerId, Substrate, UniqueId, gather,
     };
 
     fn e(data_set_id: usize, inner_id: usize) -> ExternalId {
@@ -782,12 +1065,13 @@ mod test {
     }
 
     struct TestKvStore {
-        store: kv::Store,
+        store: Storage,
     }
 
     impl TestKvStore {
         pub fn new() -> Self {
-            Self { store: kv::Store::new(kv::Config::new(tempfile::tempdir().unwrap())).unwrap() }
+            let store = kv::Store::new(kv::Config::new(tempfile::tempdir().unwrap())).unwrap();
+            Self { store: Storage::Kv(store) }
         }
 
         pub fn get_external_to_individuals_bucket<'a>(
@@ -890,4 +1174,67 @@ mod test {
         assert_eq!(result.external_to_unique, expected_external_to_unique);
         assert_eq!(result.num_empty_ids, 1);
     }
+
+    #[test]
+    fn id_combiner_cluster_stats() {
+        let ids = vec![
+            TestIds { external: e(1, 1), a: Some(10), b: Some(14), c: None }, // group 1
+            TestIds { external: e(2, 1), a: Some(10), b: None, c: Some("18") }, // group 1
+            TestIds { external: e(3, 1), a: Some(20), b: Some(20), c: Some("20") }, // group 2
+            TestIds { external: e(4, 1), a: None, b: Some(31), c: None },     // group 3
+            TestIds { external: e(5, 1), a: Some(32), b: Some(31), c: Some("33") }, // group 3
+            TestIds { external: e(7, 1), a: Some(11), b: Some(14), c: Some("15") }, // group 1
+            TestIds { external: e(8, 1), a: Some(12), b: Some(13), c: Some("15") }, // group 1
+            TestIds { external: e(9, 1), a: None, b: None, c: None },         // group 4
+        ];
+
+        let store = TestKvStore::new();
+        let combiner = IdCombiner::<TestIds, IndividualTestId, UniqueTestId>::new(
+            store.get_external_to_individuals_bucket(),
+            store.get_individual_to_externals_bucket(),
+        );
+        let result = combiner.combine(&ids).unwrap();
+
+        assert_eq!(
+            result.clusters.histogram,
+            ClusterSizeHistogram { single: 2, small: 2, medium: 0, large: 0 }
+        );
+        assert_eq!(
+            result.clusters.largest,
+            vec![vec![e(1, 1), e(2, 1), e(7, 1), e(8, 1)], vec![e(4, 1), e(5, 1)]]
+        );
+    }
+
+    #[test]
+    fn namespaced_inner_ids() {
+        let substrate = |name: &str| Substrate {
+            id: DataSetId::new(1),
+            path: format!("{name}.yaml").into(),
+            name: name.to_owned(),
+            source: gather::Source::from_stem(name),
+            created: None,
+            version: None,
+        };
+        let curated = substrate("transpaer");
+        let wikidata = substrate("wikidata");
+
+        let id = InnerId::namespaced("transpaer", "fairphone");
+        assert_eq!(id.to_string(), "transpaer:fairphone");
+        assert_eq!(id.namespace(), Some("transpaer"));
+        assert_eq!(InnerId::new("fairphone".to_owned()).namespace(), None);
+
+        let namespaced = ExternalId::from_substrate(&curated, "transpaer:fairphone");
+        assert_eq!(namespaced, ExternalId::new(DataSetId::new(1), id));
+        assert!(!namespaced.is_ambiguous(&curated));
+
+        // Unqualified curated IDs are accepted, but reported as ambiguous.
+        let unqualified = ExternalId::from_substrate(&curated, "fairphone");
+        assert_eq!(
+            unqualified,
+            ExternalId::new(DataSetId::new(1), InnerId::new("fairphone".to_owned()))
+        );
+        assert!(unqualified.is_ambiguous(&curated));
+        assert!(ExternalId::from_substrate(&curated, "wikidata:Q123").is_ambiguous(&curated));
+        assert!(!ExternalId::from_substrate(&wikidata, "Q123").is_ambiguous(&wikidata));
+    }
 }
@@ -3,6 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use clap::{Parser, Subcommand, ValueEnum};
+use schemars::JsonSchema;
 
 /// Arguments of the `absorb` command.
 #[derive(Parser, Debug)]
@@ -91,6 +92,53 @@ pub struct ExtractingArgs {
     /// Cache directory.
     #[arg(long)]
     pub cache: String,
+
+    /// HTTP(S) or S3 URL to stream the Wikidata dump from instead of reading it from the origin.
+    ///
+    /// Interrupted downloads are resumed using range requests.
+    #[arg(long)]
+    pub wikidata_url: Option<String>,
+
+    /// Save the streamed Wikidata dump to the origin directory on the fly.
+    #[arg(long, requires = "wikidata_url")]
+    pub keep_download: bool,
+
+    /// Format of the Wikidata dump.
+    ///
+    /// The truthy N-Triples dump is read from `wikidata-truthy.nt.gz` in the origin directory.
+    #[arg(long, value_enum, default_value_t)]
+    pub wikidata_format: WikidataFormat,
+}
+
+/// Arguments of the `gc` subcommand of the `cache` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Remove caches of old dumps",
+    long_about = "Removes all the cache namespaces except the one of the most recently extracted dump"
+)]
+pub struct CachingGcArgs {}
+
+/// Subcommands of the `cache` command.
+#[derive(Subcommand, Debug)]
+pub enum CachingCommands {
+    Gc(CachingGcArgs),
+}
+
+/// Arguments of the `cache` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Manage the cache directory",
+    long_about = "Caches derived from every Wikidata dump version are kept in a separate namespace. \
+                  This command allows to manage those namespaces."
+)]
+pub struct CachingArgs {
+    /// Cache directory.
+    #[arg(long)]
+    pub cache: String,
+
+    /// Subommands.
+    #[command(subcommand)]
+    pub command: CachingCommands,
 }
 
 /// Arguments of the `filter` command.
@@ -117,6 +165,28 @@ pub struct FilteringArgs {
     /// Substrate directory.
     #[arg(long)]
     pub substrate: String,
+
+    /// HTTP(S) or S3 URL to stream the Wikidata dump from instead of reading it from the origin.
+    ///
+    /// Interrupted downloads are resumed using range requests.
+    #[arg(long)]
+    pub wikidata_url: Option<String>,
+
+    /// Save the streamed Wikidata dump to the origin directory on the fly.
+    #[arg(long, requires = "wikidata_url")]
+    pub keep_download: bool,
+
+    /// Format of the Wikidata dump.
+    ///
+    /// The truthy N-Triples dump is read from `wikidata-truthy.nt.gz` in the origin directory.
+    #[arg(long, value_enum, default_value_t)]
+    pub wikidata_format: WikidataFormat,
+
+    /// Drop Wikidata products and organisations with fewer sitelinks than this.
+    ///
+    /// Items with GTINs or referenced by other substrates (e.g. certified ones) are always kept.
+    #[arg(long, default_value_t = 0)]
+    pub wikidata_min_sitelinks: usize,
 }
 
 /// Arguments of the `update` command.
@@ -151,8 +221,9 @@ pub struct UpdatingArgs {
     pub substrate: String,
 }
 
-#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
 #[clap(rename_all = "kebab_case")]
+#[schemars(rename_all = "kebab-case")]
 pub enum CondensationGroup {
     Filtered,
     Immediate,
@@ -175,6 +246,81 @@ impl CondensationGroup {
     }
 }
 
+/// Format of the log output.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[clap(rename_all = "kebab_case")]
+#[schemars(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+
+    /// One JSON object per line.
+    Json,
+}
+
+/// Engine of the database created by the `crystalize` command.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[clap(rename_all = "kebab_case")]
+pub enum DbEngine {
+    /// The `kv` crate. The database is a directory.
+    #[default]
+    Kv,
+
+    /// The `redb` crate. The database is a single file.
+    Redb,
+}
+
+/// Format of the Wikidata dump.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[clap(rename_all = "kebab_case")]
+pub enum WikidataFormat {
+    /// The full JSON dump.
+    #[default]
+    Json,
+
+    /// The truthy N-Triples dump. Much smaller, but lacks qualifiers, references and units.
+    TruthyNt,
+}
+
+impl From<WikidataFormat> for transpaer_wikidata::dump::DumpFormat {
+    fn from(format: WikidataFormat) -> Self {
+        match format {
+            WikidataFormat::Json => Self::Json,
+            WikidataFormat::TruthyNt => Self::TruthyNt,
+        }
+    }
+}
+
+/// Deduplication of near-identical products in the ranked category indices.
+///
+/// The indices are used both for picking alternatives and for listing the category products, so
+/// the deduplication applies to both.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[clap(rename_all = "kebab_case")]
+#[schemars(rename_all = "kebab-case")]
+pub enum CategoryDedup {
+    /// All products are listed.
+    #[default]
+    None,
+
+    /// Only the best scored product of each producer is listed.
+    Producer,
+}
+
+/// Handling of categories with more products than allowed.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, JsonSchema)]
+#[clap(rename_all = "kebab_case")]
+#[schemars(rename_all = "kebab-case")]
+pub enum CategoryOverflow {
+    /// No products are listed in the category.
+    Drop,
+
+    /// Only the best scored products are listed.
+    #[default]
+    Truncate,
+}
+
 /// Arguments of the `condense` command.
 #[derive(Parser, Debug)]
 #[command(
@@ -209,6 +355,36 @@ pub struct CondensationArgs {
     /// Uses only the origins from the given group.
     #[clap(long, action)]
     pub group: CondensationGroup,
+
+    /// Comma-separated list of preferred Wikidata label languages ordered by preference.
+    ///
+    /// Labels in other languages are kept after the preferred ones.
+    #[arg(long, value_delimiter = ',', default_value = "en")]
+    pub languages: Vec<String>,
+
+    /// Skips Open Food Facts records without a product name.
+    #[arg(long)]
+    pub off_require_name: bool,
+
+    /// Skips Open Food Facts records with fewer non-empty fields.
+    #[arg(long, default_value_t = 0)]
+    pub off_min_fields: usize,
+
+    /// Skips Open Food Facts records not modified in the given number of days.
+    #[arg(long)]
+    pub off_max_age_days: Option<u64>,
+
+    /// Drop Wikidata products and organisations with fewer sitelinks than this.
+    ///
+    /// Items with GTINs or certifications are always kept.
+    #[arg(long, default_value_t = 0)]
+    pub wikidata_min_sitelinks: usize,
+
+    /// Maximal number of superclass steps taken when looking for categories of Wikidata products.
+    ///
+    /// Zero checks only the direct classes and superclasses of the products.
+    #[arg(long, default_value_t = 4)]
+    pub wikidata_category_depth: usize,
 }
 
 /// Arguments of the `coagulate` command.
@@ -226,6 +402,16 @@ pub struct CoagulationArgs {
     /// Target data directory.
     #[arg(long)]
     pub coagulate: String,
+
+    /// Directory holding the temporary directories of the runs.
+    ///
+    /// Defaults to the `runtime` directory inside the target data directory.
+    #[arg(long)]
+    pub workspace: Option<String>,
+
+    /// Number of days after which temporary directories left by interrupted runs are removed.
+    #[arg(long, default_value_t = crate::workspace::DEFAULT_MAX_AGE_DAYS)]
+    pub workspace_max_age_days: u64,
 }
 
 /// Arguments of the `crystalize` command.
@@ -249,6 +435,86 @@ pub struct CrystalizationArgs {
     /// Target data directory.
     #[arg(long)]
     pub target: String,
+
+    /// Number of entries written to the database at once.
+    #[arg(long, default_value_t = 10_000)]
+    pub write_batch_size: usize,
+
+    /// Storage engine of the database.
+    #[arg(long, value_enum, default_value_t = DbEngine::default())]
+    pub engine: DbEngine,
+
+    /// YAML file selecting the scorer and its configuration.
+    ///
+    /// If not given, the tree scorer with default weights is used.
+    #[arg(long)]
+    pub scorer: Option<String>,
+
+    /// Name matches created by the `connect` command.
+    ///
+    /// If given, the accuracy of the matches is recorded in the merged organisations.
+    #[arg(long)]
+    pub matches: Option<String>,
+
+    /// Deduplication of products in the ranked category indices.
+    ///
+    /// Applies to both the alternatives and the listed category products.
+    #[arg(long, value_enum, default_value_t = CategoryDedup::default())]
+    pub category_dedup: CategoryDedup,
+
+    /// Maximal number of products listed in a single category.
+    ///
+    /// If not given, the number of products in a category is not limited.
+    #[arg(long)]
+    pub max_category_products: Option<usize>,
+
+    /// Handling of categories with more than `--max-category-products` products.
+    #[arg(long, value_enum, default_value_t = CategoryOverflow::default())]
+    pub category_overflow: CategoryOverflow,
+
+    /// Number of shards the product keyword indices are built in.
+    ///
+    /// Keywords are assigned to shards by their hash. Only the keywords of the shards being built
+    /// are held in memory, so more shards lower the peak memory usage.
+    #[arg(long, default_value_t = 16)]
+    pub keyword_shards: usize,
+
+    /// Number of threads building the keyword index shards in parallel.
+    #[arg(long, default_value_t = 4)]
+    pub keyword_workers: usize,
+
+    /// YAML file listing categories and Wikidata IDs of products to drop, usually
+    /// `deny_list.yaml` in the meta directory.
+    ///
+    /// Meant for products which are not consumer products, e.g. medications.
+    #[arg(long)]
+    pub deny_list: Option<String>,
+
+    /// Shopping availability file created by the `check-shopping` command, usually
+    /// `shopping_availability.yaml` in the meta directory.
+    ///
+    /// Shopping entries found dead are removed from the products.
+    #[arg(long)]
+    pub shopping_availability: Option<String>,
+
+    /// Sitelink counts of the Wikidata products created by the `filter` command, usually
+    /// `wikidata_sitelinks.csv` in the cache namespace directory.
+    #[arg(long)]
+    pub wikidata_sitelinks: Option<String>,
+
+    /// Keep running and re-run coagulation and crystalization whenever the substrate files change.
+    ///
+    /// Each run creates a fresh database and `<target>/db` is switched to link to it.
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Time without further changes to the substrate files to wait for before re-running.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+    pub watch_debounce: std::time::Duration,
+
+    /// URL to send a `POST` request to after a new database was created in the watch mode.
+    #[arg(long)]
+    pub reload_url: Option<String>,
 }
 
 /// Arguments of the `oxidize` command.
@@ -272,6 +538,29 @@ pub struct OxidationArgs {
     pub target: String,
 }
 
+/// Arguments of the `feed` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Imports product availability from a retailer feed",
+    long_about = "Reads a CSV or JSON feed with GTIN, region, availability and optional URL columns \
+                  and updates the availability data in the app storage. The main product records \
+                  are left untouched, so the feeds can be imported without recrystalizing. \
+                  The feed replaces all the data imported earlier from the same retailer."
+)]
+pub struct FeedingArgs {
+    /// Path to the feed file.
+    #[arg(long)]
+    pub feed: String,
+
+    /// Name of the retailer which provided the feed.
+    #[arg(long)]
+    pub retailer: String,
+
+    /// Target data directory.
+    #[arg(long)]
+    pub target: String,
+}
+
 /// Arguments of the `connect` command.
 #[derive(Parser, Debug)]
 #[command(
@@ -292,11 +581,349 @@ pub struct ConnectionArgs {
     pub meta: String,
 }
 
+/// Kind of the entity traced by the `trace` command.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[clap(rename_all = "kebab_case")]
+#[schemars(rename_all = "kebab-case")]
+pub enum TraceKind {
+    Organisation,
+    Product,
+}
+
+/// Arguments of the `trace` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Show how a single organisation or product was merged",
+    long_about = "Replays coagulation and crystalization for a single organisation or product, \
+                  printing which substrate entries were merged into it, which IDs linked them and \
+                  what each of them contributed."
+)]
+pub struct TraceArgs {
+    /// Unique ID assigned to the entity during coagulation.
+    pub unique_id: u32,
+
+    /// Kind of the entity.
+    #[arg(long, value_enum)]
+    pub kind: TraceKind,
+
+    /// Substrate data directory.
+    #[arg(long)]
+    pub substrate: String,
+
+    /// Coagulation data.
+    #[arg(long)]
+    pub coagulate: String,
+}
+
+/// Arguments of the `check-shopping` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Check if the shopping links still lead to the products",
+    long_about = "Collects the shopping links from the substrate files and checks if the shops \
+                  still present the products. Requests to the same shop are rate-limited. The \
+                  results are merged into the shopping availability meta file, which the \
+                  `crystalize` command uses to drop dead shopping entries."
+)]
+pub struct ShoppingCheckArgs {
+    /// Substrate data directory.
+    #[arg(long)]
+    pub substrate: String,
+
+    /// Meta data directory.
+    #[arg(long)]
+    pub meta: String,
+
+    /// Check only this many links, the ones not checked for the longest time first.
+    ///
+    /// All links are checked if not set.
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    /// Minimal delay between requests to the same shop.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "5s")]
+    pub delay: std::time::Duration,
+
+    /// Timeout of a single request.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+    pub timeout: std::time::Duration,
+}
+
+/// Arguments of the `review-substrate` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Review a substrate file submitted by a producer",
+    long_about = "Scores a single substrate file by the coverage of product IDs, the completeness \
+                  of product fields, the coverage of categories and, if a crystal is given, the \
+                  share of products matching already known products. Renders a readable report \
+                  with feedback on what to improve."
+)]
+pub struct SubstrateReviewArgs {
+    /// Substrate file to review.
+    pub substrate_file: String,
+
+    /// Crystal data directory to match the products against.
+    #[arg(long)]
+    pub crystal: Option<String>,
+
+    /// Path to the output report. The report is printed if not set.
+    #[arg(long)]
+    pub output: Option<String>,
+}
+
+/// Arguments of the `bundle-repro` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Bundle the data of a single organisation or product for reproducing bugs",
+    long_about = "Extracts the substrate entries merged into a single organisation or product \
+                  together with their coagulate mappings into a small tarball, which can be \
+                  attached to bug reports and replayed with the `trace` command or in tests \
+                  without the whole dataset."
+)]
+pub struct BundleArgs {
+    /// Unique ID assigned to the entity during coagulation, or ID of a substrate entry if
+    /// `--source` is given.
+    pub id: String,
+
+    /// Kind of the entity.
+    #[arg(long, value_enum)]
+    pub kind: TraceKind,
+
+    /// Name of the substrate the ID of the entry comes from.
+    #[arg(long)]
+    pub source: Option<String>,
+
+    /// Substrate data directory.
+    #[arg(long)]
+    pub substrate: String,
+
+    /// Coagulation data.
+    #[arg(long)]
+    pub coagulate: String,
+
+    /// Path of the created tarball.
+    #[arg(long)]
+    pub output: String,
+}
+
+/// Arguments of the `duplicates` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Report likely duplicate products",
+    long_about = "Compares names of products of the same producer and reports pairs with similar \
+                  names into a file which can be reviewed by curators. Such products were not \
+                  merged during coagulation because they do not share any IDs."
+)]
+pub struct DuplicatesArgs {
+    /// Crystal data directory.
+    #[arg(long)]
+    pub crystal: String,
+
+    /// Path to the output report.
+    #[arg(long)]
+    pub output: String,
+
+    /// Minimal similarity of the names (from 0.0 to 1.0) for the products to be reported.
+    #[arg(long, default_value_t = 0.8)]
+    pub threshold: f64,
+}
+
+/// Arguments of the `qa-sample` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Sample products per source for quality review",
+    long_about = "Groups the products in the crystal database by the sources which contributed \
+                  to them and picks a sample of products from every source. The samples are \
+                  written into a readable YAML file which can be reviewed by curators. The \
+                  sample stays the same between runs as long as the seed does not change."
+)]
+pub struct QaSampleArgs {
+    /// Crystal data directory.
+    #[arg(long)]
+    pub crystal: String,
+
+    /// Path to the output report.
+    #[arg(long)]
+    pub output: String,
+
+    /// Number of products sampled from every source.
+    #[arg(long, default_value_t = 20)]
+    pub per_source: usize,
+
+    /// Seed selecting the sample; change it to review different products.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+/// Arguments of the `export-research` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Export the dataset for research",
+    long_about = "Exports products, organisations, certifications and mappings to external IDs \
+                  into a versioned tarball of JSON-lines files with a `DATASET.json` manifest. \
+                  Entries are identified by external IDs which stay the same across versions."
+)]
+pub struct ResearchExportArgs {
+    /// Crystal data directory.
+    #[arg(long)]
+    pub crystal: String,
+
+    /// Path to the output tarball.
+    #[arg(long)]
+    pub output: String,
+
+    /// Version of the dataset, e.g. `2025.06`.
+    #[arg(long)]
+    pub dataset_version: String,
+
+    /// SPDX identifier of the license of the dataset.
+    #[arg(long, default_value = "ODbL-1.0")]
+    pub license: String,
+}
+
+/// Arguments of the `export-sitemap` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Export the sitemap",
+    long_about = "Writes the URLs of all the products and organisations into sitemap shards and a \
+                  `sitemap.xml` index pointing to them. The last-modified dates are taken from \
+                  the version of the dataset."
+)]
+pub struct SitemapExportArgs {
+    /// Crystal data directory.
+    #[arg(long)]
+    pub crystal: String,
+
+    /// Output directory for the sitemap files.
+    #[arg(long)]
+    pub output: String,
+
+    /// URL under which the pages and the sitemap files are served, e.g. `https://transpaer.com`.
+    #[arg(long)]
+    pub base_url: String,
+
+    /// Maximal number of URLs in a single sitemap shard.
+    #[arg(long, default_value_t = crate::sitemap::MAX_URLS_PER_SHARD)]
+    pub shard_size: usize,
+}
+
+/// Arguments of the `fetch` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Download raw inputs",
+    long_about = "Downloads all the raw inputs listed in the `fetch.yaml` file in the meta \
+                  directory. Interrupted downloads are resumed, checksums are verified and fetch \
+                  timestamps are recorded in the absorbents file."
+)]
+pub struct FetchArgs {
+    /// Origin data directory.
+    #[arg(long)]
+    pub origin: String,
+
+    /// Meta data directory.
+    #[arg(long)]
+    pub meta: String,
+
+    /// Names of the sources to fetch. All sources are fetched if none are given.
+    #[arg(long, value_delimiter = ',')]
+    pub sources: Vec<String>,
+}
+
+/// Arguments of the `merge-substrates` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Merge two substrate directories",
+    long_about = "Combines substrates prepared on different machines into a single directory. \
+                  Files of the same source are merged using the same logic as the `condense` \
+                  command and conflicts are reported."
+)]
+pub struct SubstrateMergingArgs {
+    /// First substrate directory. Its files are kept on conflicts.
+    #[arg(long)]
+    pub first: String,
+
+    /// Second substrate directory.
+    #[arg(long)]
+    pub second: String,
+
+    /// Output substrate directory. Must not exist.
+    #[arg(long)]
+    pub output: String,
+}
+
+/// Arguments of the `check-advisors` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Validate the advisor data files",
+    long_about = "Reads all the meta and support files used by the advisors (name matches, region \
+                  maps, category maps) and reports issues with the file, line and offending key \
+                  without running the pipeline. Fails if any issues were found."
+)]
+pub struct AdvisorCheckArgs {
+    /// Meta data directory.
+    #[arg(long)]
+    pub meta: String,
+
+    /// Support data directory.
+    #[arg(long)]
+    pub support: String,
+}
+
+/// Arguments of the `explain-config` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Describe the configuration of the commands",
+    long_about = "Prints the options of the given command with their default values followed by \
+                  the meaning of every path and knob of the configuration created from them. \
+                  Without a command lists all the commands."
+)]
+pub struct ExplainArgs {
+    /// Name of the command to explain.
+    pub command: Option<String>,
+
+    /// Print the JSON schema of the configuration instead of the description.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments of the `verify-db` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Verify integrity of the database",
+    long_about = "Reads all entries of all buckets of the database and checks that the IDs stored \
+                  in the entries and indices point to existing records. Prints a pass/fail report \
+                  and fails if any of the checks failed."
+)]
+pub struct VerificationArgs {
+    /// Crystal data directory.
+    #[arg(long)]
+    pub crystal: String,
+}
+
+/// Arguments of the `digest` command.
+#[derive(Parser, Debug)]
+#[command(
+    about = "Compute digests of the database buckets",
+    long_about = "Computes a hash of the contents of every bucket of the database and stores them \
+                  in the dataset metadata. If another database is given, lists the buckets whose \
+                  contents differ between the two databases."
+)]
+pub struct DigestArgs {
+    /// Crystal data directory.
+    #[arg(long)]
+    pub crystal: String,
+
+    /// Crystal data directory of a database to compare with, e.g. from the previous run.
+    #[arg(long)]
+    pub against: Option<String>,
+}
+
 /// Arguments of the `sample` command.
 #[derive(Parser, Debug)]
 #[command(
     about = "Verify the result of crystalisation",
-    long_about = "Verify the result of crystalisation by checking sample entries."
+    long_about = "Verify the result of crystalisation by checking sample entries. Can also fetch \
+                  selected entities from the live Wikidata service to build small test substrates \
+                  without downloading the full Wikidata dump."
 )]
 pub struct SampleArgs {
     #[arg(long)]
@@ -304,21 +931,87 @@ pub struct SampleArgs {
 
     #[arg(long)]
     pub url: Option<String>,
+
+    /// Wikidata IDs of entities to fetch from the live Wikidata service.
+    #[arg(long, value_delimiter = ',')]
+    pub wikidata_ids: Vec<String>,
+
+    /// Path to a file with a SPARQL query selecting entities to fetch from the live Wikidata service.
+    #[arg(long)]
+    pub wikidata_sparql: Option<String>,
+
+    /// Cache directory where the fetched Wikidata entities will be saved.
+    #[arg(long)]
+    pub cache: Option<String>,
+
+    /// Minimal delay between consecutive requests to the live Wikidata service.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "500ms")]
+    pub wikidata_delay: std::time::Duration,
 }
 
 /// All arguments of the program.
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     Absorb(AbsorbingArgs),
+    Fetch(FetchArgs),
     Extract(ExtractingArgs),
+    Cache(CachingArgs),
     Condense(CondensationArgs),
     Filter(FilteringArgs),
     Coagulate(CoagulationArgs),
     Crystalize(CrystalizationArgs),
     Oxidize(OxidationArgs),
+    Feed(FeedingArgs),
     Update(UpdatingArgs),
     Connect(ConnectionArgs),
     Sample(SampleArgs),
+    Trace(TraceArgs),
+    BundleRepro(BundleArgs),
+    Duplicates(DuplicatesArgs),
+    QaSample(QaSampleArgs),
+    ExportResearch(ResearchExportArgs),
+    ExportSitemap(SitemapExportArgs),
+    VerifyDb(VerificationArgs),
+    Digest(DigestArgs),
+    MergeSubstrates(SubstrateMergingArgs),
+    CheckAdvisors(AdvisorCheckArgs),
+    ExplainConfig(ExplainArgs),
+    CheckShopping(ShoppingCheckArgs),
+    ReviewSubstrate(SubstrateReviewArgs),
+}
+
+impl Commands {
+    /// Returns the name of the command.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Absorb(_) => "absorb",
+            Self::Fetch(_) => "fetch",
+            Self::Extract(_) => "extract",
+            Self::Cache(_) => "cache",
+            Self::Condense(_) => "condense",
+            Self::Filter(_) => "filter",
+            Self::Coagulate(_) => "coagulate",
+            Self::Crystalize(_) => "crystalize",
+            Self::Oxidize(_) => "oxidize",
+            Self::Feed(_) => "feed",
+            Self::Update(_) => "update",
+            Self::Connect(_) => "connect",
+            Self::Sample(_) => "sample",
+            Self::Trace(_) => "trace",
+            Self::BundleRepro(_) => "bundle-repro",
+            Self::Duplicates(_) => "duplicates",
+            Self::QaSample(_) => "qa-sample",
+            Self::ExportResearch(_) => "export-research",
+            Self::ExportSitemap(_) => "export-sitemap",
+            Self::VerifyDb(_) => "verify-db",
+            Self::Digest(_) => "digest",
+            Self::MergeSubstrates(_) => "merge-substrates",
+            Self::CheckAdvisors(_) => "check-advisors",
+            Self::ExplainConfig(_) => "explain-config",
+            Self::CheckShopping(_) => "check-shopping",
+            Self::ReviewSubstrate(_) => "review-substrate",
+        }
+    }
 }
 
 /// Program arguments.
@@ -328,4 +1021,8 @@ pub struct Args {
     /// Commands.
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Format of the log output.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
 }
@@ -3,18 +3,21 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::{
-    collections::{HashMap, HashSet, hash_map::Entry},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, hash_map::Entry},
     sync::Arc,
 };
 
 use async_trait::async_trait;
 
 use transpaer_collecting::{
-    bcorp, eu_ecolabel, fashion_transparency_index, open_food_facts, open_food_repo, tco,
+    bcorp, eu_ecolabel, fashion_transparency_index, open_food_facts, open_food_repo,
+    open_supply_hub, podcasts, tco,
 };
 use transpaer_models::{
     gather as models,
-    utils::{extract_domain_from_url, extract_domains_from_urls},
+    utils::{
+        extract_domain_from_url, extract_domains_from_urls, normalize_company_name, with_timestamp,
+    },
 };
 use transpaer_schema as schema;
 use transpaer_wikidata::{
@@ -24,8 +27,6 @@ use transpaer_wikidata::{
 
 use crate::{advisors, config, errors, parallel, runners, utils, wikidata::ItemExt};
 
-const LANG_EN: &str = "en";
-
 /// Holds all the supplementary source data.
 pub struct CondensationSources {
     /// Wikidata data.
@@ -68,6 +69,18 @@ impl CondensationSources {
             return true;
         }
 
+        if self.is_certified_organisation(item) {
+            return true;
+        }
+
+        false
+    }
+}
+
+impl CondensationSources {
+    /// Checks if the organisation is known to any of the certifiers.
+    #[must_use]
+    pub fn is_certified_organisation(&self, item: &transpaer_wikidata::data::Item) -> bool {
         if self.fti.has_company(&item.id) || self.tco.has_company(&item.id) {
             return true;
         }
@@ -81,9 +94,17 @@ impl CondensationSources {
 
         false
     }
-}
 
-impl CondensationSources {
+    /// Checks if the item is notable enough to be kept.
+    ///
+    /// Manufacturers and certified organisations are always kept.
+    #[must_use]
+    pub fn is_notable(&self, item: &transpaer_wikidata::data::Item, min_sitelinks: usize) -> bool {
+        item.is_notable(min_sitelinks)
+            || self.wikidata.has_manufacturer_id(&item.id)
+            || self.is_certified_organisation(item)
+    }
+
     /// Constructs a new `CondensationSources`.
     fn load(config: &config::CondensationConfig) -> Result<Self, errors::ProcessingError> {
         let wikidata = advisors::WikidataAdvisor::load(
@@ -95,7 +116,10 @@ impl CondensationSources {
             &config.origin.bcorp_path,
             &config.meta.bcorp_regions_path,
         )?;
-        let eu_ecolabel = advisors::EuEcolabelAdvisor::load(&config.meta.eu_ecolabel_regions_path)?;
+        let eu_ecolabel = advisors::EuEcolabelAdvisor::load(
+            &config.meta.eu_ecolabel_regions_path,
+            &config.meta.matches_path,
+        )?;
         let tco = advisors::TcoAdvisor::load(&config.support.tco_path)?;
         let fti = advisors::FashionTransparencyIndexAdvisor::load(
             &config.support.fashion_transparency_index_path,
@@ -109,13 +133,21 @@ impl CondensationSources {
     }
 }
 
-fn prepare_meta(variant: schema::ProviderVariant) -> schema::Meta {
+fn prepare_meta(
+    variant: schema::ProviderVariant,
+    title: &str,
+    num_producers: usize,
+    num_products: usize,
+) -> schema::Meta {
     schema::Meta {
-        version: "0.0.0".to_owned(),
+        version: env!("CARGO_PKG_VERSION").to_owned(),
         variant,
         authors: vec!["Transpaer Development Team".to_owned()],
-        title: String::new(),
-        description: Some("Data prepared by the Transpaer Development Team".to_owned()),
+        title: title.to_owned(),
+        description: Some(format!(
+            "Data prepared by the Transpaer Development Team. \
+             Records: {num_producers} producers, {num_products} products."
+        )),
         creation_timestamp: Some(schema::chrono::Utc::now()),
         valid_from: None,
         valid_to: None,
@@ -137,6 +169,20 @@ pub trait Collector: Clone + Default + Send {
 
     fn build_substrate(self, about: Self::About) -> schema::Substrate;
     fn merge(&mut self, other: Self) -> Result<(), errors::CondensationError>;
+
+    /// Reads back a substrate file saved from a collector of this kind.
+    ///
+    /// Returns `None` if the file holds a different kind of substrate.
+    fn read(path: &std::path::Path) -> Result<Option<Self>, schema::errors::ReadError>;
+
+    /// Returns IDs of the collected producers.
+    fn producer_ids(&self) -> Vec<String>;
+
+    /// Returns IDs of the collected products.
+    fn product_ids(&self) -> Vec<String>;
+
+    /// Keeps only the producers and products with the given IDs.
+    fn retain(&mut self, producer_ids: &HashSet<String>, product_ids: &HashSet<String>);
 }
 
 /// Data storage for gathered data from a cataloger.
@@ -146,18 +192,63 @@ pub trait Collector: Clone + Default + Send {
 pub struct CatalogerCollector {
     producers: HashMap<String, schema::CatalogProducer>,
     products: Vec<schema::CatalogProduct>,
+
+    /// Number of individual artifacts dropped because they are not product models.
+    num_dropped_individuals: usize,
+
+    /// Number of items dropped because of too few sitelinks.
+    num_dropped_obscure: usize,
+
+    /// Number of products by the depth in the class hierarchy at which their categories were found.
+    category_depths: BTreeMap<usize, usize>,
+
+    /// Number of products with no categories found.
+    num_uncategorised: usize,
 }
 
 impl Collector for CatalogerCollector {
     type About = schema::AboutCataloger;
 
     fn build_substrate(mut self, about: Self::About) -> schema::Substrate {
+        if self.num_dropped_individuals > 0 {
+            log::info!(
+                "Summary for `{}`: dropped {} individual items (not product models)",
+                about.id,
+                self.num_dropped_individuals,
+            );
+        }
+        if self.num_dropped_obscure > 0 {
+            log::info!(
+                "Summary for `{}`: dropped {} obscure items (too few sitelinks)",
+                about.id,
+                self.num_dropped_obscure,
+            );
+        }
+        if !self.category_depths.is_empty() || self.num_uncategorised > 0 {
+            let depths: Vec<String> = self
+                .category_depths
+                .iter()
+                .map(|(depth, count)| format!("{depth}: {count}"))
+                .collect();
+            log::info!(
+                "Summary for `{}`: categories found by depth: {{{}}}, uncategorised: {}",
+                about.id,
+                depths.join(", "),
+                self.num_uncategorised,
+            );
+        }
+
         let mut producers: Vec<schema::CatalogProducer> = self.producers.into_values().collect();
         producers.sort_by(|a, b| a.id.cmp(&b.id));
         self.products.sort_by(|a, b| a.id.cmp(&b.id));
 
         schema::Substrate {
-            meta: prepare_meta(schema::ProviderVariant::Cataloger),
+            meta: prepare_meta(
+                schema::ProviderVariant::Cataloger,
+                &about.name,
+                producers.len(),
+                self.products.len(),
+            ),
             data: schema::Data::Cataloger(schema::CatalogerData {
                 cataloger: about,
                 producers,
@@ -169,8 +260,42 @@ impl Collector for CatalogerCollector {
     fn merge(&mut self, other: Self) -> Result<(), errors::CondensationError> {
         utils::merge_hashmaps_with(&mut self.producers, other.producers, merge_catalog_producers);
         merge::vec::append(&mut self.products, other.products);
+        self.num_dropped_individuals += other.num_dropped_individuals;
+        self.num_dropped_obscure += other.num_dropped_obscure;
+        for (depth, count) in other.category_depths {
+            *self.category_depths.entry(depth).or_insert(0) += count;
+        }
+        self.num_uncategorised += other.num_uncategorised;
         Ok(())
     }
+
+    fn read(path: &std::path::Path) -> Result<Option<Self>, schema::errors::ReadError> {
+        let schema::read::FileIterVariant::Catalog(iter) = schema::read::iter_file(path)? else {
+            return Ok(None);
+        };
+
+        let mut collector = Self::default();
+        for entry in iter {
+            match entry? {
+                schema::CatalogEntry::Producer(producer) => collector.insert_producer(producer),
+                schema::CatalogEntry::Product(product) => collector.add_product(product),
+            }
+        }
+        Ok(Some(collector))
+    }
+
+    fn producer_ids(&self) -> Vec<String> {
+        self.producers.keys().cloned().collect()
+    }
+
+    fn product_ids(&self) -> Vec<String> {
+        self.products.iter().map(|product| product.id.clone()).collect()
+    }
+
+    fn retain(&mut self, producer_ids: &HashSet<String>, product_ids: &HashSet<String>) {
+        self.producers.retain(|id, _| producer_ids.contains(id));
+        self.products.retain(|product| product_ids.contains(&product.id));
+    }
 }
 
 impl CatalogerCollector {
@@ -188,6 +313,24 @@ impl CatalogerCollector {
     pub fn add_product(&mut self, product: schema::CatalogProduct) {
         self.products.push(product);
     }
+
+    /// Counts an item dropped because it is an individual artifact and not a product model.
+    pub fn add_dropped_individual(&mut self) {
+        self.num_dropped_individuals += 1;
+    }
+
+    /// Counts an item dropped because it has too few sitelinks.
+    pub fn add_dropped_obscure(&mut self) {
+        self.num_dropped_obscure += 1;
+    }
+
+    /// Counts a product by the depth at which its categories were found.
+    pub fn add_category_depth(&mut self, depth: Option<usize>) {
+        match depth {
+            Some(depth) => *self.category_depths.entry(depth).or_insert(0) += 1,
+            None => self.num_uncategorised += 1,
+        }
+    }
 }
 
 /// Data storage for gathered data from a reviewer.
@@ -208,7 +351,12 @@ impl Collector for ReviewerCollector {
         self.products.sort_by(|a, b| a.id.cmp(&b.id));
 
         schema::Substrate {
-            meta: prepare_meta(schema::ProviderVariant::Reviewer),
+            meta: prepare_meta(
+                schema::ProviderVariant::Reviewer,
+                &about.name,
+                producers.len(),
+                self.products.len(),
+            ),
             data: schema::Data::Reviewer(schema::ReviewerData {
                 reviewer: about,
                 producers,
@@ -222,6 +370,34 @@ impl Collector for ReviewerCollector {
         merge::vec::append(&mut self.products, other.products);
         Ok(())
     }
+
+    fn read(path: &std::path::Path) -> Result<Option<Self>, schema::errors::ReadError> {
+        let schema::read::FileIterVariant::Review(iter) = schema::read::iter_file(path)? else {
+            return Ok(None);
+        };
+
+        let mut collector = Self::default();
+        for entry in iter {
+            match entry? {
+                schema::ReviewEntry::Producer(producer) => collector.insert_producer(producer),
+                schema::ReviewEntry::Product(product) => collector.add_product(product),
+            }
+        }
+        Ok(Some(collector))
+    }
+
+    fn producer_ids(&self) -> Vec<String> {
+        self.producers.keys().cloned().collect()
+    }
+
+    fn product_ids(&self) -> Vec<String> {
+        self.products.iter().map(|product| product.id.clone()).collect()
+    }
+
+    fn retain(&mut self, producer_ids: &HashSet<String>, product_ids: &HashSet<String>) {
+        self.producers.retain(|id, _| producer_ids.contains(id));
+        self.products.retain(|product| product_ids.contains(&product.id));
+    }
 }
 
 impl ReviewerCollector {
@@ -324,11 +500,34 @@ impl About for AboutFti {
             description: "Data from the Fashion Transparency Index prepared by the Transpaer Team"
                 .to_owned(),
             website: "https://www.fashionrevolution.org".to_owned(),
-            reviews: Some(schema::AboutReview::ScoreReview(schema::AboutScoreReview {
-                min: 0,
-                max: 100,
-                div: 1,
-            })),
+            reviews: Some(schema::AboutReview::ScoreReview(models::FtiCert::SCALE.to_schema())),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AboutPodcasts;
+
+impl About for AboutPodcasts {
+    type Collector = ReviewerCollector;
+
+    fn name() -> &'static str {
+        "podcasts"
+    }
+
+    fn variant() -> schema::SubstrateExtension {
+        schema::SubstrateExtension::JsonLines
+    }
+
+    fn build() -> schema::AboutReviewer {
+        schema::AboutReviewer {
+            id: "podcasts".to_owned(),
+            name: "Podcasts".to_owned(),
+            description: "Podcast episodes discussing products and producers selected by the \
+                          Transpaer Team"
+                .to_owned(),
+            website: "https://transpaer.com".to_owned(),
+            reviews: None,
         }
     }
 }
@@ -360,6 +559,60 @@ impl About for AboutOff {
     }
 }
 
+#[derive(Clone, Default)]
+struct AboutOsh;
+
+impl About for AboutOsh {
+    type Collector = CatalogerCollector;
+
+    fn name() -> &'static str {
+        "open_supply_hub"
+    }
+
+    fn variant() -> schema::SubstrateExtension {
+        schema::SubstrateExtension::JsonLines
+    }
+
+    fn build() -> schema::AboutCataloger {
+        schema::AboutCataloger {
+            id: "open_supply_hub".to_owned(),
+            name: "Open Supply Hub".to_owned(),
+            description: Some(
+                "Data from the Open Supply Hub prepared by the Transpaer Team".to_owned(),
+            ),
+            variant: schema::CatalogVariant::Database,
+            website: "https://opensupplyhub.org".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct AboutNutriScore;
+
+impl About for AboutNutriScore {
+    type Collector = ReviewerCollector;
+
+    fn name() -> &'static str {
+        "nutri_score"
+    }
+
+    fn variant() -> schema::SubstrateExtension {
+        schema::SubstrateExtension::JsonLines
+    }
+
+    fn build() -> schema::AboutReviewer {
+        schema::AboutReviewer {
+            id: "nutri_score".to_owned(),
+            name: "Nutri-Score".to_owned(),
+            description:
+                "Nutri-Score grades from the Open Food Facts prepared by the Transpaer Team"
+                    .to_owned(),
+            website: "https://world.openfoodfacts.org".to_owned(),
+            reviews: Some(schema::AboutReview::ScoreReview(models::NutriScore::SCALE.to_schema())),
+        }
+    }
+}
+
 #[derive(Clone, Default)]
 struct AboutOfr;
 
@@ -439,43 +692,196 @@ impl About for AboutWiki {
     }
 }
 
+/// Substrate merged from several files of the same source.
+pub struct MergedSubstrate {
+    /// The merged substrate.
+    pub substrate: schema::Substrate,
+
+    /// Extension the substrate of this source is saved with.
+    pub extension: schema::SubstrateExtension,
+
+    /// IDs of producers present in more than one file, merged into one entry.
+    pub merged_producers: BTreeSet<String>,
+
+    /// IDs of products present in more than one file, kept as separate entries.
+    pub duplicated_products: BTreeSet<String>,
+}
+
+fn merge_substrate_files_with<A>(
+    paths: &[std::path::PathBuf],
+) -> Result<Option<MergedSubstrate>, errors::SubstrateMergingError>
+where
+    A: About,
+{
+    let mut collector = A::Collector::default();
+    let mut producers = HashSet::new();
+    let mut products = HashSet::new();
+    let mut merged_producers = BTreeSet::new();
+    let mut duplicated_products = BTreeSet::new();
+    for path in paths {
+        let Some(other) = A::Collector::read(path)? else {
+            return Ok(None);
+        };
+        for id in other.producer_ids() {
+            if let Some(id) = producers.replace(id) {
+                merged_producers.insert(id);
+            }
+        }
+        for id in other.product_ids() {
+            if let Some(id) = products.replace(id) {
+                duplicated_products.insert(id);
+            }
+        }
+        collector.merge(other)?;
+    }
+
+    Ok(Some(MergedSubstrate {
+        substrate: collector.build_substrate(A::build()),
+        extension: A::variant(),
+        merged_producers,
+        duplicated_products,
+    }))
+}
+
+fn extract_substrate_entries_with<A>(
+    path: &std::path::Path,
+    producer_ids: &HashSet<String>,
+    product_ids: &HashSet<String>,
+) -> Result<Option<(schema::Substrate, schema::SubstrateExtension)>, errors::SubstrateMergingError>
+where
+    A: About,
+{
+    let Some(mut collector) = A::Collector::read(path)? else {
+        return Ok(None);
+    };
+    collector.retain(producer_ids, product_ids);
+    Ok(Some((collector.build_substrate(A::build()), A::variant())))
+}
+
+/// Extracts the producers and products with the given IDs from a substrate file using the
+/// collector of its source.
+///
+/// Returns `None` if the source is not one created by the `condense` command or the file holds a
+/// different kind of substrate than the source produces.
+pub fn extract_substrate_entries(
+    name: &str,
+    path: &std::path::Path,
+    producer_ids: &HashSet<String>,
+    product_ids: &HashSet<String>,
+) -> Result<Option<(schema::Substrate, schema::SubstrateExtension)>, errors::SubstrateMergingError>
+{
+    let (producers, products) = (producer_ids, product_ids);
+    match name {
+        n if n == AboutBCorp::name() => {
+            extract_substrate_entries_with::<AboutBCorp>(path, producers, products)
+        }
+        n if n == AboutEprel::name() => {
+            extract_substrate_entries_with::<AboutEprel>(path, producers, products)
+        }
+        n if n == AboutEu::name() => {
+            extract_substrate_entries_with::<AboutEu>(path, producers, products)
+        }
+        n if n == AboutFti::name() => {
+            extract_substrate_entries_with::<AboutFti>(path, producers, products)
+        }
+        n if n == AboutPodcasts::name() => {
+            extract_substrate_entries_with::<AboutPodcasts>(path, producers, products)
+        }
+        n if n == AboutOff::name() => {
+            extract_substrate_entries_with::<AboutOff>(path, producers, products)
+        }
+        n if n == AboutOsh::name() => {
+            extract_substrate_entries_with::<AboutOsh>(path, producers, products)
+        }
+        n if n == AboutNutriScore::name() => {
+            extract_substrate_entries_with::<AboutNutriScore>(path, producers, products)
+        }
+        n if n == AboutOfr::name() => {
+            extract_substrate_entries_with::<AboutOfr>(path, producers, products)
+        }
+        n if n == AboutTco::name() => {
+            extract_substrate_entries_with::<AboutTco>(path, producers, products)
+        }
+        n if n == AboutWiki::name() => {
+            extract_substrate_entries_with::<AboutWiki>(path, producers, products)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Merges substrate files of the same source using the collector of that source.
+///
+/// Returns `None` if the source is not one created by the `condense` command or a file holds a
+/// different kind of substrate than the source produces.
+pub fn merge_substrate_files(
+    name: &str,
+    paths: &[std::path::PathBuf],
+) -> Result<Option<MergedSubstrate>, errors::SubstrateMergingError> {
+    match name {
+        n if n == AboutBCorp::name() => merge_substrate_files_with::<AboutBCorp>(paths),
+        n if n == AboutEu::name() => merge_substrate_files_with::<AboutEu>(paths),
+        n if n == AboutFti::name() => merge_substrate_files_with::<AboutFti>(paths),
+        n if n == AboutPodcasts::name() => merge_substrate_files_with::<AboutPodcasts>(paths),
+        n if n == AboutOff::name() => merge_substrate_files_with::<AboutOff>(paths),
+        n if n == AboutOsh::name() => merge_substrate_files_with::<AboutOsh>(paths),
+        n if n == AboutNutriScore::name() => merge_substrate_files_with::<AboutNutriScore>(paths),
+        n if n == AboutOfr::name() => merge_substrate_files_with::<AboutOfr>(paths),
+        n if n == AboutTco::name() => merge_substrate_files_with::<AboutTco>(paths),
+        n if n == AboutWiki::name() => merge_substrate_files_with::<AboutWiki>(paths),
+        _ => Ok(None),
+    }
+}
+
 #[derive(Clone)]
 pub struct CondensingWikidataWorker {
     sources: Arc<CondensationSources>,
+    languages: Arc<[String]>,
+    min_sitelinks: usize,
+    category_depth: usize,
     collector: CatalogerCollector,
 }
 
 impl CondensingWikidataWorker {
     #[must_use]
-    pub fn new(sources: Arc<CondensationSources>) -> Self {
+    pub fn new(
+        sources: Arc<CondensationSources>,
+        languages: &[String],
+        min_sitelinks: usize,
+        category_depth: usize,
+    ) -> Self {
         log::info!("Using Wikidata");
-        Self { collector: CatalogerCollector::default(), sources }
+        Self {
+            collector: CatalogerCollector::default(),
+            sources,
+            languages: languages.into(),
+            min_sitelinks,
+            category_depth,
+        }
+    }
+
+    /// Extracts names from a Wikidata item.
+    ///
+    /// Names in all languages are kept, ordered by the language preference.
+    // TODO: Store the language on the names once the substrate schema supports it.
+    fn extract_wikidata_names(&self, item: &Item) -> Vec<String> {
+        item.get_labels(&self.languages).into_iter().map(|label| label.value.clone()).collect()
+    }
+
+    /// Extracts the description from a Wikidata item.
+    fn extract_wikidata_description(&self, item: &Item) -> Option<String> {
+        item.get_description(&self.languages).map(|label| label.value.clone())
     }
 
     /// Extracts categories from a Wikidata item.
+    ///
+    /// Starts from the item's classes and direct superclasses and walks up the class hierarchy.
     fn extract_wikidata_categories(
         &self,
         item: &Item,
-    ) -> Result<Vec<String>, errors::ProcessingError> {
-        let mut result = HashSet::<String>::new();
-
-        if let Some(classes) = item.get_classes()? {
-            for class in classes {
-                if let Some(categories) = self.sources.wikidata.get_categories(&class) {
-                    result.extend(categories.iter().cloned());
-                }
-            }
-        }
-
-        if let Some(classes) = item.get_superclasses()? {
-            for class in classes {
-                if let Some(categories) = self.sources.wikidata.get_categories(&class) {
-                    result.extend(categories.iter().cloned());
-                }
-            }
-        }
-
-        Ok(result.into_iter().collect())
+    ) -> Result<advisors::CategoryMatch, errors::ProcessingError> {
+        let mut classes = item.get_classes()?.unwrap_or_default();
+        classes.extend(item.get_superclasses()?.unwrap_or_default());
+        Ok(self.sources.wikidata.find_categories(&classes, self.category_depth))
     }
 
     /// Extracts countries from a Wikidata item.
@@ -514,9 +920,21 @@ impl runners::WikidataWorker for CondensingWikidataWorker {
     ) -> Result<(), errors::ProcessingError> {
         match entity {
             Entity::Item(item) => {
+                if (self.sources.is_product(&item) || self.sources.is_organisation(&item))
+                    && !self.sources.is_notable(&item, self.min_sitelinks)
+                {
+                    self.collector.add_dropped_obscure();
+                    return Ok(());
+                }
+
                 // Gather all products
-                if self.sources.is_product(&item) {
+                // TODO: Pass `item.get_mass()` and `item.get_dimensions()` to the product
+                //       once the substrate schema can hold them.
+                if self.sources.is_product(&item) && item.is_individual() {
+                    self.collector.add_dropped_individual();
+                } else if self.sources.is_product(&item) {
                     let categories = self.extract_wikidata_categories(&item)?;
+                    self.collector.add_category_depth(categories.depth);
                     let regions = self.extract_wikidata_regions(&item)?;
                     let product = schema::CatalogProduct {
                         id: item.id.to_id(),
@@ -525,14 +943,12 @@ impl runners::WikidataWorker for CondensingWikidataWorker {
                             gtin: item.get_gtins(),
                             wiki: Some(vec![item.id.to_id()]),
                         },
-                        names: item.get_labels().into_iter().map(ToString::to_string).collect(),
-                        description: item
-                            .descriptions
-                            .get(LANG_EN)
-                            .map(|label| label.value.clone()),
+                        names: self.extract_wikidata_names(&item),
+                        description: self.extract_wikidata_description(&item),
                         images: item.get_images().unwrap_or_default(),
                         categorisation: Some(schema::ProductCategorisation {
                             categories: categories
+                                .categories
                                 .into_iter()
                                 .map(schema::ProductCategory)
                                 .collect(),
@@ -590,11 +1006,8 @@ impl runners::WikidataWorker for CondensingWikidataWorker {
                             wiki: Some(vec![item.id.to_id()]),
                             domains: item.extract_domains().map(|c| c.into_iter().collect()),
                         },
-                        names: item.get_labels().into_iter().map(ToString::to_string).collect(),
-                        description: item
-                            .descriptions
-                            .get(LANG_EN)
-                            .map(|label| label.value.clone()),
+                        names: self.extract_wikidata_names(&item),
+                        description: self.extract_wikidata_description(&item),
                         images: item.get_logo_images().unwrap_or_default(),
                         websites: item.get_official_websites().unwrap_or_default(),
                         origins: Some(schema::ProducerOrigins { regions }),
@@ -619,14 +1032,38 @@ impl runners::WikidataWorker for CondensingWikidataWorker {
 #[derive(Clone)]
 pub struct CondensingOpenFoodFactsWorker {
     sources: Arc<CondensationSources>,
-    collector: CatalogerCollector,
+    collector: OpenFoodFactsCollector,
 }
 
 impl CondensingOpenFoodFactsWorker {
     #[must_use]
     pub fn new(sources: Arc<CondensationSources>) -> Self {
         log::info!("Using Open Food Facts");
-        Self { collector: CatalogerCollector::default(), sources }
+        Self { collector: OpenFoodFactsCollector::default(), sources }
+    }
+
+    /// Extracts the Nutri-Score grade as a review of the product.
+    fn extract_nutri_score(
+        gtin: &models::Gtin,
+        record: &open_food_facts::data::Record,
+    ) -> Option<schema::ReviewProduct> {
+        let grade = models::NutriScore::from_grade(&record.nutriscore_grade)?;
+        Some(schema::ReviewProduct {
+            id: gtin.to_string(),
+            ids: schema::ProductIds { ean: None, gtin: Some(vec![gtin.to_string()]), wiki: None },
+            names: Self::vec(&record.product_name),
+            summary: None,
+            images: Vec::new(),
+            categorisation: None,
+            origins: None,
+            availability: None,
+            related: None,
+            reports: None,
+            review: Some(schema::Review::ScoreReview(schema::ScoreReview {
+                value: grade.to_value(),
+            })),
+            shopping: None,
+        })
     }
 
     /// Extracts categories from a Wikidata item.
@@ -648,9 +1085,22 @@ impl CondensingOpenFoodFactsWorker {
         &self,
         record: &open_food_facts::data::Record,
     ) -> Option<schema::RegionList> {
+        self.map_open_food_facts_regions(&record.extract_sell_countries())
+    }
+
+    /// Extracts regions the ingredients come from as claimed on the product label.
+    fn extract_open_food_facts_origin_regions(
+        &self,
+        record: &open_food_facts::data::Record,
+    ) -> Option<schema::RegionList> {
+        self.map_open_food_facts_regions(&record.extract_origin_countries())
+    }
+
+    /// Maps Open Food Facts country tags to a list of regions.
+    fn map_open_food_facts_regions(&self, tags: &[String]) -> Option<schema::RegionList> {
         let mut result = HashSet::<isocountry::CountryCode>::new();
-        for tag in record.extract_sell_countries() {
-            match self.sources.off.get_countries(&tag) {
+        for tag in tags {
+            match self.sources.off.get_countries(tag) {
                 Some(models::Regions::List(list)) => result.extend(list.iter()),
                 Some(models::Regions::Unknown | models::Regions::World) | None => {}
             }
@@ -702,7 +1152,7 @@ impl CondensingOpenFoodFactsWorker {
 
 #[async_trait]
 impl runners::OpenFoodFactsWorker for CondensingOpenFoodFactsWorker {
-    type Output = CatalogerCollector;
+    type Output = OpenFoodFactsCollector;
 
     async fn process(
         &mut self,
@@ -731,7 +1181,7 @@ impl runners::OpenFoodFactsWorker for CondensingOpenFoodFactsWorker {
                 }),
                 origins: Some(schema::ProductOrigins {
                     producer_ids: producer_id.as_ref().map_or_else(Vec::new, |id| vec![id.clone()]),
-                    regions: self.extract_open_food_facts_production_regions(&record),
+                    regions: self.extract_open_food_facts_origin_regions(&record),
                 }),
                 availability: Some(schema::ProductAvailability {
                     regions: Self::extract_open_food_facts_sell_regions(&record, &self.sources.off),
@@ -740,7 +1190,10 @@ impl runners::OpenFoodFactsWorker for CondensingOpenFoodFactsWorker {
                 shopping: None,
             };
 
-            self.collector.add_product(product);
+            self.collector.catalog.add_product(product);
+            if let Some(review) = Self::extract_nutri_score(&gtin, &record) {
+                self.collector.nutri_score.add_product(review);
+            }
 
             if let Some(producer_id) = producer_id {
                 let producer = schema::CatalogProducer {
@@ -755,7 +1208,7 @@ impl runners::OpenFoodFactsWorker for CondensingOpenFoodFactsWorker {
                     }),
                 };
 
-                self.collector.insert_producer(producer);
+                self.collector.catalog.insert_producer(producer);
             }
         }
         Ok(())
@@ -886,11 +1339,16 @@ impl runners::EuEcolabelWorker for CondensingEuEcolabelWorker {
         _tx: parallel::Sender<Self::Output>,
     ) -> Result<(), errors::ProcessingError> {
         if let Some(vat_number) = &record.vat_number {
+            let wiki = self
+                .sources
+                .eu_ecolabel
+                .get_match(&record.company_name)
+                .map(|m| vec![m.wiki_id.to_id()]);
             let producer = schema::ReviewProducer {
                 id: vat_number.clone(),
                 ids: schema::ProducerIds {
                     vat: Some(vec![vat_number.clone()]),
-                    wiki: None,
+                    wiki,
                     domains: None,
                 },
                 names: vec![record.product_or_service_name.clone()],
@@ -1129,6 +1587,228 @@ impl parallel::RefProducer for FtiCondenser {
     }
 }
 
+/// Reports about a single product or producer gathered from podcast episodes.
+#[derive(Default)]
+struct PodcastSubject {
+    /// Name of the subject as given in the podcasts file.
+    name: String,
+
+    /// Episodes discussing the subject.
+    reports: Vec<schema::Report>,
+}
+
+struct PodcastsCondenser {
+    /// Sources configuration.
+    config: config::CondensationConfig,
+}
+
+impl PodcastsCondenser {
+    pub fn new(config: config::CondensationConfig) -> Self {
+        log::info!("Using podcasts");
+        Self { config }
+    }
+
+    /// Reads the podcast feeds and groups the mentioned episodes by the discussed subject.
+    fn gather_subjects(
+        path: &std::path::Path,
+    ) -> Result<
+        (BTreeMap<String, PodcastSubject>, BTreeMap<String, PodcastSubject>),
+        errors::ProcessingError,
+    > {
+        let mut producers = BTreeMap::<String, PodcastSubject>::new();
+        let mut products = BTreeMap::<String, PodcastSubject>::new();
+        for podcast in podcasts::reader::parse(path)? {
+            let feed_path = path.with_file_name(&podcast.feed);
+            let episodes: HashMap<String, podcasts::data::Episode> =
+                podcasts::reader::parse_feed(&feed_path)?
+                    .into_iter()
+                    .map(|episode| (episode.guid.clone(), episode))
+                    .collect();
+
+            for mention in podcast.mentions {
+                let Some(episode) = episodes.get(&mention.episode) else {
+                    log::warn!(
+                        "Podcast `{}`: episode `{}` not found",
+                        podcast.name,
+                        mention.episode
+                    );
+                    continue;
+                };
+                let Some(url) = episode.url() else {
+                    log::warn!(
+                        "Podcast `{}`: episode `{}` has no link",
+                        podcast.name,
+                        episode.guid
+                    );
+                    continue;
+                };
+
+                let timestamp = match &mention.timestamp {
+                    Some(timestamp) => podcasts::data::parse_timestamp(timestamp),
+                    None => episode.find_timestamp(&mention.name),
+                };
+                let subjects = match mention.kind {
+                    podcasts::data::MentionedKind::Producer => &mut producers,
+                    podcasts::data::MentionedKind::Product => &mut products,
+                };
+                let subject = subjects.entry(mention.wikidata_id.to_id()).or_default();
+                subject.name = mention.name;
+                subject.reports.push(schema::Report {
+                    title: Some(format!("{}: {}", podcast.name, episode.title)),
+                    url: Some(with_timestamp(url, timestamp)),
+                });
+            }
+        }
+        Ok((producers, products))
+    }
+}
+
+#[async_trait]
+impl parallel::RefProducer for PodcastsCondenser {
+    type Output = SaveMessage;
+    type Error = errors::ProcessingError;
+
+    async fn produce(&self, tx: parallel::Sender<Self::Output>) -> Result<(), Self::Error> {
+        let path = &self.config.support.podcasts_path;
+        if !path.exists() {
+            log::warn!("Podcasts file `{}` does not exist, skipping", path.display());
+            return Ok(());
+        }
+
+        let mut collector = ReviewerCollector::default();
+        let (producers, products) = Self::gather_subjects(path)?;
+        for (wiki_id, subject) in producers {
+            collector.insert_producer(schema::ReviewProducer {
+                id: wiki_id.clone(),
+                ids: schema::ProducerIds { vat: None, wiki: Some(vec![wiki_id]), domains: None },
+                names: vec![subject.name],
+                description: None,
+                images: Vec::new(),
+                websites: Vec::new(),
+                origins: None,
+                reports: Some(schema::Reports(subject.reports)),
+                review: None,
+            });
+        }
+        for (wiki_id, subject) in products {
+            collector.add_product(schema::ReviewProduct {
+                id: wiki_id.clone(),
+                ids: schema::ProductIds { ean: None, gtin: None, wiki: Some(vec![wiki_id]) },
+                names: vec![subject.name],
+                summary: None,
+                images: Vec::new(),
+                categorisation: None,
+                origins: None,
+                availability: None,
+                related: None,
+                reports: Some(schema::Reports(subject.reports)),
+                review: None,
+                shopping: None,
+            });
+        }
+
+        let substrate = collector.build_substrate(AboutPodcasts::build());
+        tx.send(SaveMessage {
+            name: AboutPodcasts::name().to_owned(),
+            variant: AboutPodcasts::variant(),
+            substrate,
+        })
+        .await;
+
+        Ok(())
+    }
+}
+
+/// Brand gathered from the Open Supply Hub facilities.
+#[derive(Default)]
+struct OshBrand {
+    /// All spellings of the brand name.
+    names: BTreeSet<String>,
+
+    /// Countries (alpha-3) of the facilities producing for the brand.
+    regions: BTreeSet<String>,
+}
+
+struct OpenSupplyHubCondenser {
+    /// Sources configuration.
+    config: config::CondensationConfig,
+}
+
+impl OpenSupplyHubCondenser {
+    pub fn new(config: config::CondensationConfig) -> Self {
+        log::info!("Using Open Supply Hub");
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl parallel::RefProducer for OpenSupplyHubCondenser {
+    type Output = SaveMessage;
+    type Error = errors::ProcessingError;
+
+    async fn produce(&self, tx: parallel::Sender<Self::Output>) -> Result<(), Self::Error> {
+        let path = &self.config.origin.open_supply_hub_path;
+        if !path.exists() {
+            log::warn!("Open Supply Hub file `{}` does not exist, skipping", path.display());
+            return Ok(());
+        }
+
+        let mut collector = CatalogerCollector::default();
+
+        // Facilities are listed individually. Here they are grouped by the brands
+        // they produce for. The brands are matched to known organisations during coagulation.
+        let data = open_supply_hub::reader::parse(path)?;
+        let mut brands = HashMap::<String, OshBrand>::new();
+        for record in data {
+            let Some(country) = isocountry::CountryCode::for_alpha2_caseless(&record.country_code)
+                .ok()
+                .map(|code| code.alpha3().to_owned())
+            else {
+                log::warn!(
+                    "Unknown country code '{}' of Open Supply Hub facility '{}'",
+                    record.country_code,
+                    record.os_id,
+                );
+                continue;
+            };
+
+            for name in record.extract_brands() {
+                let id = normalize_company_name(&name);
+                if id.is_empty() {
+                    continue;
+                }
+                let brand = brands.entry(id).or_default();
+                brand.names.insert(name);
+                brand.regions.insert(country.clone());
+            }
+        }
+
+        for (id, brand) in brands {
+            collector.insert_producer(schema::CatalogProducer {
+                id,
+                ids: schema::ProducerIds { vat: None, wiki: None, domains: None },
+                names: brand.names.into_iter().collect(),
+                description: None,
+                images: Vec::new(),
+                websites: Vec::new(),
+                origins: Some(schema::ProducerOrigins {
+                    regions: Some(schema::RegionList(brand.regions.into_iter().collect())),
+                }),
+            });
+        }
+
+        let substrate = collector.build_substrate(AboutOsh::build());
+        tx.send(SaveMessage {
+            name: AboutOsh::name().to_owned(),
+            variant: AboutOsh::variant(),
+            substrate,
+        })
+        .await;
+
+        Ok(())
+    }
+}
+
 struct TcoCondenser {
     /// Sources configuration.
     config: config::CondensationConfig,
@@ -1210,11 +1890,47 @@ where
     }
 
     async fn finish(self, tx: parallel::Sender<Self::Output>) -> Result<(), Self::Error> {
-        let name = A::name().to_owned();
-        let about = A::build();
-        let variant = A::variant();
-        let substrate = self.collector.build_substrate(about);
-        tx.send(SaveMessage { name, variant, substrate }).await;
+        tx.send(SaveMessage::new::<A>(self.collector)).await;
+        Ok(())
+    }
+}
+
+/// Data gathered in a single pass over the Open Food Facts data.
+///
+/// The Nutri-Score grades are saved in a separate reviewer substrate, so that they are kept apart
+/// from the catalog data.
+#[derive(Clone, Default)]
+pub struct OpenFoodFactsCollector {
+    catalog: CatalogerCollector,
+    nutri_score: ReviewerCollector,
+}
+
+/// Combines the Open Food Facts data into the catalog and the Nutri-Score substrates.
+#[derive(Clone, Default)]
+pub struct OpenFoodFactsCombiner {
+    /// Collected data.
+    collector: OpenFoodFactsCollector,
+}
+
+#[async_trait]
+impl parallel::Processor for OpenFoodFactsCombiner {
+    type Input = OpenFoodFactsCollector;
+    type Output = SaveMessage;
+    type Error = errors::CondensationError;
+
+    async fn process(
+        &mut self,
+        input: Self::Input,
+        _tx: parallel::Sender<Self::Output>,
+    ) -> Result<(), Self::Error> {
+        self.collector.catalog.merge(input.catalog)?;
+        self.collector.nutri_score.merge(input.nutri_score)?;
+        Ok(())
+    }
+
+    async fn finish(self, tx: parallel::Sender<Self::Output>) -> Result<(), Self::Error> {
+        tx.send(SaveMessage::new::<AboutOff>(self.collector.catalog)).await;
+        tx.send(SaveMessage::new::<AboutNutriScore>(self.collector.nutri_score)).await;
         Ok(())
     }
 }
@@ -1226,17 +1942,43 @@ pub struct SaveMessage {
     substrate: schema::Substrate,
 }
 
+impl SaveMessage {
+    /// Builds the substrate from the collected data.
+    fn new<A: About>(collector: A::Collector) -> Self {
+        let substrate = collector.build_substrate(A::build());
+        Self { name: A::name().to_owned(), variant: A::variant(), substrate }
+    }
+}
+
 pub struct SubstrateSaver {
     config: config::CondensationConfig,
+
+    /// Hash of the configuration identifying the settings the substrates were created with.
+    config_hash: String,
 }
 
 impl SubstrateSaver {
     #[must_use]
     pub fn new(config: config::CondensationConfig) -> Self {
-        Self { config }
+        let config_hash = format!("{:x}", md5::compute(format!("{config:?}")));
+        Self { config, config_hash }
+    }
+
+    /// Records the source files and the configuration the substrate was created from.
+    fn stamp(&self, name: &str, meta: &mut schema::Meta) {
+        stamp_meta(meta, &self.config.source_paths(name), &self.config_hash);
     }
 }
 
+/// Appends the identifiers of the source dumps and the configuration hash to the description.
+fn stamp_meta(meta: &mut schema::Meta, source_paths: &[&std::path::Path], config_hash: &str) {
+    let sources =
+        source_paths.iter().map(|path| utils::describe_file(path)).collect::<Vec<_>>().join(", ");
+    let description = meta.description.take().unwrap_or_default();
+    meta.description =
+        Some(format!("{description} Sources: {sources}. Configuration: {config_hash}."));
+}
+
 #[async_trait]
 impl parallel::Consumer for SubstrateSaver {
     type Input = SaveMessage;
@@ -1252,6 +1994,7 @@ impl parallel::Consumer for SubstrateSaver {
             .join(&input.name)
             .with_extension(input.variant.as_str());
         log::info!("Saving '{}'", path.display());
+        self.stamp(&input.name, &mut input.substrate.meta);
         input.substrate.sort();
         input.substrate.save(&path)?;
         log::info!("Saved");
@@ -1280,7 +2023,12 @@ impl CondensingRunner {
             let (wiki_process_tx, wiki_process_rx) = parallel::bounded::<String>();
             let (wiki_combine_tx, wiki_combine_rx) = parallel::bounded::<CatalogerCollector>();
             let wiki_producer = runners::WikidataProducer::new(&config.into())?;
-            let wiki_worker = CondensingWikidataWorker::new(sources.clone());
+            let wiki_worker = CondensingWikidataWorker::new(
+                sources.clone(),
+                &config.languages,
+                config.wikidata_min_sitelinks,
+                config.wikidata_category_depth,
+            );
             let wiki_worker = runners::WikidataProcessor::new(wiki_worker);
             let wiki_combiner = Combiner::<AboutWiki>::default();
             flow = flow
@@ -1293,11 +2041,11 @@ impl CondensingRunner {
         if config.group.use_immediate() {
             let (off_process_tx, off_process_rx) =
                 parallel::bounded::<runners::OpenFoodFactsRunnerMessage>();
-            let (off_combine_tx, off_combine_rx) = parallel::bounded::<CatalogerCollector>();
+            let (off_combine_tx, off_combine_rx) = parallel::bounded::<OpenFoodFactsCollector>();
             let off_producer = runners::OpenFoodFactsProducer::new(config.into())?;
             let off_worker = CondensingOpenFoodFactsWorker::new(sources.clone());
             let off_worker = runners::OpenFoodFactsProcessor::new(off_worker);
-            let off_combiner = Combiner::<AboutOff>::default();
+            let off_combiner = OpenFoodFactsCombiner::default();
             flow = flow
                 .name("off")
                 .spawn_producer(off_producer, off_process_tx)?
@@ -1333,8 +2081,10 @@ impl CondensingRunner {
             let bcorp_producer = Box::new(BCorpCondenser::new(config.clone()));
             let fti_producer = Box::new(FtiCondenser::new(config.clone()));
             let tco_producer = Box::new(TcoCondenser::new(config.clone()));
+            let osh_producer = Box::new(OpenSupplyHubCondenser::new(config.clone()));
+            let podcasts_producer = Box::new(PodcastsCondenser::new(config.clone()));
             flow = flow.name("small").spawn_producers(
-                vec![bcorp_producer, fti_producer, tco_producer],
+                vec![bcorp_producer, fti_producer, tco_producer, osh_producer, podcasts_producer],
                 save_tx.clone(),
             )?;
         }
@@ -1345,3 +2095,25 @@ impl CondensingRunner {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_records_provenance() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("bcorp.csv");
+        std::fs::write(&source, [0; 10]).unwrap();
+
+        let mut meta = prepare_meta(schema::ProviderVariant::Reviewer, "BCorp", 3, 5);
+        let config_hash = format!("{:x}", md5::compute("config"));
+        stamp_meta(&mut meta, &[source.as_path()], &config_hash);
+
+        let description = meta.description.unwrap();
+        assert!(description.contains("Records: 3 producers, 5 products."), "{description}");
+        assert!(description.contains("Sources: bcorp.csv (10 bytes, modified "), "{description}");
+        assert!(description.contains(&format!("Configuration: {config_hash}.")), "{description}");
+        assert!(meta.creation_timestamp.is_some());
+    }
+}
@@ -2,39 +2,111 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use schemars::JsonSchema;
 
-use crate::{commands, errors::ConfigCheckError, utils};
+use transpaer_models::{
+    buckets::StorageEngine,
+    ids::{OrganisationId, ProductId},
+};
+use transpaer_wikidata::dump::DumpFormat;
 
-pub use commands::CondensationGroup;
+use crate::{cache, commands, crystalizing::TraceTarget, errors::ConfigCheckError, remote, utils};
+
+pub use commands::{CategoryDedup, CategoryOverflow, CondensationGroup, LogFormat, WikidataFormat};
+
+/// Rough estimates of the sizes of the stage outputs relative to the sizes of their inputs.
+///
+/// Given in percent. They are used only to catch obviously insufficient disk space before
+/// starting long runs, so they err on the larger side.
+mod expansion {
+    /// Wikidata cache compared to the compressed Wikidata dump.
+    pub const EXTRACTING: u64 = 10;
+
+    /// Filtered Wikidata dump compared to the compressed Wikidata dump.
+    pub const FILTERING: u64 = 300;
+
+    /// Substrates compared to the origin files and the filtered Wikidata dump.
+    pub const CONDENSATION: u64 = 100;
+
+    /// Coagulate and its runtime storage compared to the substrates.
+    pub const COAGULATION: u64 = 100;
+
+    /// Database and its runtime storage compared to the substrates.
+    pub const CRYSTALIZATION: u64 = 400;
+}
 
 /// Configuration for `WikidataGather`.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct WikidataProducerConfig {
     /// Path to Wikidata data.
     pub wikidata_path: PathBuf,
+
+    /// HTTP(S) or S3 URL to stream the data from instead of reading them from `wikidata_path`.
+    pub wikidata_url: Option<String>,
+
+    /// Whether to save the streamed data to `wikidata_path` on the fly.
+    pub keep_download: bool,
+
+    /// Format of the dump.
+    #[schemars(with = "String")]
+    pub format: DumpFormat,
 }
 
 impl WikidataProducerConfig {
     /// Constructs a new `WikidataProducerConfig` with filteresd Wikidata dump.
     pub fn new_filtered(cache: &str) -> WikidataProducerConfig {
         let cache = PathBuf::from(&cache);
-        Self { wikidata_path: cache.join("wikidata.jsonl") }
+        Self::new_with_path(cache.join(cache::CURRENT_NAMESPACE).join("wikidata.jsonl"))
     }
 
     /// Constructs a new `WikidataProducerConfig` with full Wikidata dump.
-    pub fn new_full(origin: &str) -> WikidataProducerConfig {
+    ///
+    /// If `url` is given, the dump is streamed from it instead of being read from the origin.
+    pub fn new_full(
+        origin: &str,
+        url: Option<&str>,
+        keep_download: bool,
+        format: WikidataFormat,
+    ) -> WikidataProducerConfig {
         let origin = PathBuf::from(&origin);
-        Self { wikidata_path: origin.join("wikidata.json.gz") }
+        let file_name = match format {
+            WikidataFormat::Json => "wikidata.json.gz",
+            WikidataFormat::TruthyNt => "wikidata-truthy.nt.gz",
+        };
+        Self {
+            wikidata_url: url.map(str::to_owned),
+            keep_download,
+            format: format.into(),
+            ..Self::new_with_path(origin.join(file_name))
+        }
     }
 
     /// Constructs a new `WikidataProducerConfig`.
-    pub fn new_with_path(path: &str) -> WikidataProducerConfig {
-        let wikidata_path = PathBuf::from(&path);
-        Self { wikidata_path }
+    pub fn new_with_path(path: impl Into<PathBuf>) -> WikidataProducerConfig {
+        Self {
+            wikidata_path: path.into(),
+            wikidata_url: None,
+            keep_download: false,
+            format: DumpFormat::Json,
+        }
+    }
+
+    /// Returns the name of the cache namespace for the dump.
+    pub fn namespace_name(&self) -> std::io::Result<String> {
+        match &self.wikidata_url {
+            Some(url) => cache::remote_namespace_name(url),
+            None => cache::namespace_name(&self.wikidata_path),
+        }
+    }
+
+    /// Returns the location of the dump for messages.
+    #[must_use]
+    pub fn location(&self) -> PathBuf {
+        self.wikidata_url.as_ref().map_or_else(|| self.wikidata_path.clone(), PathBuf::from)
     }
 
     /// Checks validity of the configuration.
@@ -43,23 +115,67 @@ impl WikidataProducerConfig {
     ///
     /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
-        utils::file_exists(&self.wikidata_path)?;
+        match &self.wikidata_url {
+            Some(url) if !remote::is_remote(url) => {
+                return Err(ConfigCheckError::UnsupportedUrl(url.clone()));
+            }
+            Some(_) if self.keep_download => utils::path_creatable(&self.wikidata_path)?,
+            Some(_) => {}
+            None => utils::file_exists(&self.wikidata_path)?,
+        }
         Ok(())
     }
 }
 
+/// Quality requirements for Open Food Facts records.
+///
+/// The default configuration accepts all the records.
+#[must_use]
+#[derive(Debug, Clone, Default, JsonSchema)]
+pub struct OpenFoodFactsFilterConfig {
+    /// Skip records without a product name.
+    pub require_name: bool,
+
+    /// Skip records with fewer non-empty fields.
+    pub min_fields: usize,
+
+    /// Skip records not modified in the given number of days.
+    pub max_age_days: Option<u64>,
+}
+
+impl OpenFoodFactsFilterConfig {
+    pub fn new(args: &commands::CondensationArgs) -> Self {
+        Self {
+            require_name: args.off_require_name,
+            min_fields: args.off_min_fields,
+            max_age_days: args.off_max_age_days,
+        }
+    }
+}
+
 /// Configuration for `OpenFoodFactsGatherer`.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct OpenFoodFactsProducerConfig {
     /// Path to Open Food Facts data.
     pub open_food_facts_path: PathBuf,
+
+    /// Requirements the records have to fulfil to be processed.
+    pub filter: OpenFoodFactsFilterConfig,
 }
 
 impl OpenFoodFactsProducerConfig {
     pub fn new(origin: &str) -> Self {
         let origin = PathBuf::from(origin);
-        Self { open_food_facts_path: origin.join("open_food_facts_products.csv.gz") }
+        Self {
+            open_food_facts_path: origin.join("open_food_facts_products.csv.gz"),
+            filter: OpenFoodFactsFilterConfig::default(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: OpenFoodFactsFilterConfig) -> Self {
+        self.filter = filter;
+        self
     }
 
     /// Checks validity of the configuration.
@@ -75,7 +191,7 @@ impl OpenFoodFactsProducerConfig {
 
 /// Configuration for `OpenFoodRepoGatherer`.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct OpenFoodRepoProducerConfig {
     /// Path to Open Food Repo data.
     pub open_food_repo_path: PathBuf,
@@ -100,7 +216,7 @@ impl OpenFoodRepoProducerConfig {
 
 /// Configuration for `WikidataGatherer`.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct EuEcolabelProducerConfig {
     /// Path to Open Food Facts data.
     pub eu_ecolabel_path: PathBuf,
@@ -125,7 +241,7 @@ impl EuEcolabelProducerConfig {
 
 /// Configuration for `FullGatherer`.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct FullProducerConfig {
     /// Wikidata gatherer config.
     pub wiki: WikidataProducerConfig,
@@ -163,7 +279,7 @@ impl FullProducerConfig {
 /// Subconfiguration related to origin files used by several other configs.
 #[allow(clippy::struct_field_names)]
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct OriginConfig {
     /// Path to original B-Corp data.
     pub bcorp_path: PathBuf,
@@ -177,6 +293,11 @@ pub struct OriginConfig {
     /// Path to the original Open Food Repo data.
     pub open_food_repo_path: PathBuf,
 
+    /// Path to the original Open Supply Hub facility data.
+    ///
+    /// Optional, production places of the brands are skipped if it does not exist.
+    pub open_supply_hub_path: PathBuf,
+
     /// Path to the original Wikidata data.
     pub wikidata_path: PathBuf,
 }
@@ -190,6 +311,7 @@ impl OriginConfig {
             eu_ecolabel_path: origin.join("eu_ecolabel_products.csv"),
             open_food_facts_path: origin.join("open_food_facts_products.csv.gz"),
             open_food_repo_path: origin.join("open_food_repo.jsonl"),
+            open_supply_hub_path: origin.join("open_supply_hub.csv"),
             wikidata_path: origin.join("wikidata.json.gz"),
         }
     }
@@ -260,13 +382,18 @@ impl OriginConfig {
 /// Subconfiguration related to support files used by several other configs.
 #[allow(clippy::struct_field_names)]
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct SupportConfig {
     /// Path to TCO data.
     pub tco_path: PathBuf,
 
     /// Path to Fashion Transparency Index data.
     pub fashion_transparency_index_path: PathBuf,
+
+    /// Path to the curated list of podcast episodes discussing products and producers.
+    ///
+    /// Optional, podcast mentions are skipped if it does not exist.
+    pub podcasts_path: PathBuf,
 }
 
 impl SupportConfig {
@@ -276,6 +403,7 @@ impl SupportConfig {
         Self {
             tco_path: support.join("tco.yaml"),
             fashion_transparency_index_path: support.join("fashion_transparency_index.yaml"),
+            podcasts_path: support.join("podcasts.yaml"),
         }
     }
 
@@ -294,14 +422,20 @@ impl SupportConfig {
 /// Subconfiguration related to meta files used by several other configs.
 #[allow(clippy::struct_field_names)]
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct MetaConfig {
     /// Path to the absobents file, which contains info about fetched orinal data sets.
     pub absorbents: PathBuf,
 
+    /// Path to the file describing how to download the raw inputs.
+    pub fetch_path: PathBuf,
+
     /// Path to file mapping EU Ecolabel countries to Transpaer regions.
     pub eu_ecolabel_regions_path: PathBuf,
 
+    /// Path to file matching company names to Wikidata IDs (created by the `connect` command).
+    pub matches_path: PathBuf,
+
     /// Path to file mapping Wikidata countries to Transpaer regions.
     pub wikidata_regions_path: PathBuf,
 
@@ -324,7 +458,9 @@ impl MetaConfig {
         let meta = PathBuf::from(meta);
         Self {
             absorbents: meta.join("absorbents.yaml"),
+            fetch_path: meta.join("fetch.yaml"),
             eu_ecolabel_regions_path: meta.join("eu_ecolabel_regions.yaml"),
+            matches_path: meta.join("matches.yaml"),
             wikidata_regions_path: meta.join("wikidata_regions.yaml"),
             wikidata_categories_path: meta.join("wikidata_categories.yaml"),
             open_food_facts_regions_path: meta.join("open_food_facts_regions.yaml"),
@@ -350,8 +486,11 @@ impl MetaConfig {
 /// Subconfiguration related to cache files used by several other configs.
 #[allow(clippy::struct_field_names)]
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct CacheConfig {
+    /// Path to the cache directory.
+    pub cache_path: PathBuf,
+
     /// Path to the cache wikidata path.
     pub wikidata_cache_path: PathBuf,
 }
@@ -360,7 +499,8 @@ impl CacheConfig {
     /// Constructs a new `CacheConfig`.
     pub fn new(cache: &str) -> Self {
         let cache = PathBuf::from(cache);
-        Self { wikidata_cache_path: cache.join("wikidata_cache.json") }
+        let namespace = cache.join(cache::CURRENT_NAMESPACE);
+        Self { wikidata_cache_path: namespace.join("wikidata_cache.json"), cache_path: cache }
     }
 
     /// Checks validity of the configuration for reading.
@@ -379,14 +519,32 @@ impl CacheConfig {
     ///
     /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
     pub fn check_write(&self) -> Result<(), ConfigCheckError> {
-        utils::parent_creatable(&self.wikidata_cache_path)?;
+        utils::dir_usable(&self.cache_path)?;
+        Ok(())
+    }
+
+    /// Checks that the current cache namespace was created for the `source` dump.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the `source` cannot be read or the cache was extracted from another dump.
+    pub fn check_namespace(&self, source: &WikidataProducerConfig) -> Result<(), ConfigCheckError> {
+        let expected = source
+            .namespace_name()
+            .map_err(|_| ConfigCheckError::NotReadable(source.location()))?;
+        if cache::current_namespace(&self.cache_path).as_ref() != Some(&expected) {
+            return Err(ConfigCheckError::CacheNamespace {
+                path: self.cache_path.clone(),
+                expected,
+            });
+        }
         Ok(())
     }
 }
 
 /// Subconfiguration related to substrate files used by several other configs.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct SubstrateConfig {
     /// Path to the substrate file directory.
     pub substrate_path: PathBuf,
@@ -422,7 +580,7 @@ impl SubstrateConfig {
 
 /// Configuration for the `bcorp` subcommand of the `absorb` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct AbsorbingBCorpConfig {
     /// `data.world` authentication token..
     pub token: String,
@@ -442,7 +600,7 @@ impl AbsorbingBCorpConfig {
 
 /// Configuration for the `eu-ecolabel` subcommand of the `absorb` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct AbsorbingEuEcolabelConfig {}
 
 impl AbsorbingEuEcolabelConfig {
@@ -459,7 +617,7 @@ impl AbsorbingEuEcolabelConfig {
 
 /// Configuration for the `open-food-facts` subcommand of the `absorb` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct AbsorbingOpenFoodFactsConfig {}
 
 impl AbsorbingOpenFoodFactsConfig {
@@ -476,7 +634,7 @@ impl AbsorbingOpenFoodFactsConfig {
 
 /// Configuration for the `open-food-repo` subcommand of the `absorb` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct AbsorbingOpenFoodRepoConfig {
     /// Open Food Repo API key..
     pub api_key: String,
@@ -497,7 +655,7 @@ impl AbsorbingOpenFoodRepoConfig {
 
 /// Configuration for the `wikidata` subcommand of the `absorb` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct AbsorbingWikidataConfig {}
 
 impl AbsorbingWikidataConfig {
@@ -513,7 +671,7 @@ impl AbsorbingWikidataConfig {
 }
 
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub enum AbsorbingSubconfig {
     BCorp(AbsorbingBCorpConfig),
     EuEcolabel(AbsorbingEuEcolabelConfig),
@@ -536,7 +694,7 @@ impl AbsorbingSubconfig {
 
 /// Configuration for the `absorb` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct AbsorbingConfig {
     /// Paths to origin files.
     pub origin: OriginConfig,
@@ -593,7 +751,7 @@ impl AbsorbingConfig {
 
 /// Configuration for the `extract` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct ExtractingConfig {
     /// Path to output Wikidata cache.
     pub cache: CacheConfig,
@@ -607,7 +765,12 @@ impl ExtractingConfig {
     pub fn new(args: &commands::ExtractingArgs) -> ExtractingConfig {
         Self {
             cache: CacheConfig::new(&args.cache),
-            wikidata_gatherer: WikidataProducerConfig::new_full(&args.origin),
+            wikidata_gatherer: WikidataProducerConfig::new_full(
+                &args.origin,
+                args.wikidata_url.as_deref(),
+                args.keep_download,
+                args.wikidata_format,
+            ),
         }
     }
 
@@ -615,21 +778,68 @@ impl ExtractingConfig {
     ///
     /// # Errors
     ///
-    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    /// Returns `Err` if paths expected to exist do not exist, paths expected to not exist do exist
+    /// or there is not enough free disk space for the outputs.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
         self.cache.check_write()?;
         self.wikidata_gatherer.check()?;
+        utils::space_available(
+            &self.cache.wikidata_cache_path,
+            &[&self.wikidata_gatherer.wikidata_path],
+            expansion::EXTRACTING,
+        )?;
+        Ok(())
+    }
+}
+
+/// Action of the `cache` command.
+#[derive(Debug, Clone, Copy, JsonSchema)]
+pub enum CachingAction {
+    /// Remove all namespaces except the current one.
+    Gc,
+}
+
+/// Configuration for the `cache` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct CachingConfig {
+    /// Paths to cache files.
+    pub cache: CacheConfig,
+
+    /// Action to perform.
+    pub action: CachingAction,
+}
+
+impl CachingConfig {
+    /// Constructs a new `CachingConfig`.
+    pub fn new(args: &commands::CachingArgs) -> CachingConfig {
+        let action = match args.command {
+            commands::CachingCommands::Gc(_) => CachingAction::Gc,
+        };
+        Self { cache: CacheConfig::new(&args.cache), action }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.cache.cache_path)?;
         Ok(())
     }
 }
 
 /// Configuration for the `filter` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct FilteringConfig {
     /// Path to output filtered .
     pub wikidata_filtered_dump_path: PathBuf,
 
+    /// Path to output the sitelink counts of the kept products.
+    pub wikidata_sitelinks_path: PathBuf,
+
     /// Paths to meta files.
     pub meta: MetaConfig,
 
@@ -641,6 +851,9 @@ pub struct FilteringConfig {
 
     /// `WikidataGatherer` config.
     pub wikidata_gatherer: WikidataProducerConfig,
+
+    /// Minimal number of sitelinks of notable Wikidata items.
+    pub wikidata_min_sitelinks: usize,
 }
 
 impl FilteringConfig {
@@ -649,11 +862,22 @@ impl FilteringConfig {
         let cache = PathBuf::from(&args.cache);
         let substrate = PathBuf::from(&args.substrate);
         Self {
-            wikidata_filtered_dump_path: cache.join("wikidata.jsonl"),
+            wikidata_filtered_dump_path: cache
+                .join(cache::CURRENT_NAMESPACE)
+                .join("wikidata.jsonl"),
+            wikidata_sitelinks_path: cache
+                .join(cache::CURRENT_NAMESPACE)
+                .join("wikidata_sitelinks.csv"),
             meta: MetaConfig::new(&args.meta),
             cache: CacheConfig::new(&args.cache),
             substrate_path: substrate,
-            wikidata_gatherer: WikidataProducerConfig::new_full(&args.origin),
+            wikidata_gatherer: WikidataProducerConfig::new_full(
+                &args.origin,
+                args.wikidata_url.as_deref(),
+                args.keep_download,
+                args.wikidata_format,
+            ),
+            wikidata_min_sitelinks: args.wikidata_min_sitelinks,
         }
     }
 
@@ -661,25 +885,44 @@ impl FilteringConfig {
     ///
     /// # Errors
     ///
-    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    /// Returns `Err` if paths expected to exist do not exist, paths expected to not exist do exist
+    /// or there is not enough free disk space for the outputs.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
         utils::path_creatable(&self.wikidata_filtered_dump_path)?;
+        utils::path_creatable(&self.wikidata_sitelinks_path)?;
         self.meta.check()?;
         self.cache.check_read()?;
         utils::dir_exists(&self.substrate_path)?;
         self.wikidata_gatherer.check()?;
+        self.cache.check_namespace(&self.wikidata_gatherer)?;
+        utils::space_available(
+            &self.wikidata_filtered_dump_path,
+            &[&self.wikidata_gatherer.wikidata_path],
+            expansion::FILTERING,
+        )?;
         Ok(())
     }
 }
 
 #[must_use]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct UpdatingConfig {
+    /// EU Ecolabel gatherer config.
     pub eu_ecolabel: EuEcolabelProducerConfig,
+
+    /// Wikidata gatherer config.
     pub wikidata_gatherer: WikidataProducerConfig,
+
+    /// Open Food Facts gatherer config.
     pub off: OpenFoodFactsProducerConfig,
+
+    /// Path to the original BCorp data.
     pub bcorp_original_path: PathBuf,
+
+    /// Paths to the updated meta files.
     pub meta: MetaConfig,
+
+    /// Substrate config.
     pub substrate: SubstrateConfig,
 }
 
@@ -716,7 +959,7 @@ impl UpdatingConfig {
 
 /// Configuration for the `condense` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct CondensationConfig {
     /// Skip substrates that require filtration.
     pub group: CondensationGroup,
@@ -747,6 +990,15 @@ pub struct CondensationConfig {
 
     /// Substrate config.
     pub substrate: SubstrateConfig,
+
+    /// Preferred languages of Wikidata labels and descriptions ordered by preference.
+    pub languages: Vec<String>,
+
+    /// Minimal number of sitelinks of notable Wikidata items.
+    pub wikidata_min_sitelinks: usize,
+
+    /// Maximal number of superclass steps taken when looking for categories of Wikidata items.
+    pub wikidata_category_depth: usize,
 }
 
 impl CondensationConfig {
@@ -759,10 +1011,14 @@ impl CondensationConfig {
             support: SupportConfig::new(&args.support),
             cache: CacheConfig::new(&args.cache),
             wiki: WikidataProducerConfig::new_filtered(&args.cache),
-            off: OpenFoodFactsProducerConfig::new(&args.origin),
+            off: OpenFoodFactsProducerConfig::new(&args.origin)
+                .with_filter(OpenFoodFactsFilterConfig::new(args)),
             ofr: OpenFoodRepoProducerConfig::new(&args.origin),
             eu_ecolabel: EuEcolabelProducerConfig::new(&args.origin),
             substrate: SubstrateConfig::new(&args.substrate),
+            languages: args.languages.clone(),
+            wikidata_min_sitelinks: args.wikidata_min_sitelinks,
+            wikidata_category_depth: args.wikidata_category_depth,
         }
     }
 
@@ -770,7 +1026,8 @@ impl CondensationConfig {
     ///
     /// # Errors
     ///
-    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    /// Returns `Err` if paths expected to exist do not exist, paths expected to not exist do exist
+    /// or there is not enough free disk space for the outputs.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
         self.origin.check_read()?;
         self.meta.check()?;
@@ -782,19 +1039,48 @@ impl CondensationConfig {
         self.off.check()?;
         self.eu_ecolabel.check()?;
         self.substrate.check_write()?;
+        utils::space_available(
+            &self.substrate.substrate_path,
+            &[
+                &self.origin.bcorp_path,
+                &self.origin.eu_ecolabel_path,
+                &self.origin.open_food_repo_path,
+                &self.origin.open_supply_hub_path,
+                &self.off.open_food_facts_path,
+                &self.wiki.wikidata_path,
+            ],
+            expansion::CONDENSATION,
+        )?;
         Ok(())
     }
+
+    /// Returns paths to the files the substrate with the given name is created from.
+    #[must_use]
+    pub fn source_paths(&self, substrate_name: &str) -> Vec<&Path> {
+        match substrate_name {
+            "bcorp" => vec![&self.origin.bcorp_path],
+            "eu_ecolabel" => vec![&self.eu_ecolabel.eu_ecolabel_path, &self.meta.matches_path],
+            "fti" => vec![&self.support.fashion_transparency_index_path],
+            "open_food_facts" | "nutri_score" => vec![&self.off.open_food_facts_path],
+            "open_food_repo" => vec![&self.ofr.open_food_repo_path],
+            "open_supply_hub" => vec![&self.origin.open_supply_hub_path],
+            "podcasts" => vec![&self.support.podcasts_path],
+            "tco" => vec![&self.support.tco_path],
+            "wikidata" => vec![&self.wiki.wikidata_path],
+            _ => Vec::new(),
+        }
+    }
 }
 
 /// Configuration for the `coagulate` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct CoagulationConfig {
     /// Data substrate.
     pub substrate: SubstrateConfig,
 
-    /// Runtime storage directory.
-    pub runtime: PathBuf,
+    /// Workspace for the temporary runtime storage.
+    pub workspace: WorkspaceConfig,
 
     /// Path to store the coagulate in.
     pub coagulate: PathBuf,
@@ -806,7 +1092,13 @@ impl CoagulationConfig {
         let coagulate = PathBuf::from(&args.coagulate);
         Self {
             substrate: SubstrateConfig::new(&args.substrate),
-            runtime: coagulate.join("runtime"),
+            workspace: WorkspaceConfig {
+                root: args
+                    .workspace
+                    .as_ref()
+                    .map_or_else(|| coagulate.join("runtime"), PathBuf::from),
+                max_age_days: args.workspace_max_age_days,
+            },
             coagulate: coagulate.join("coagulate.yaml"),
         }
     }
@@ -815,17 +1107,46 @@ impl CoagulationConfig {
     ///
     /// # Errors
     ///
-    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    /// Returns `Err` if paths expected to exist do not exist, paths expected to not exist do exist
+    /// or there is not enough free disk space for the outputs.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
         self.substrate.check_read()?;
         utils::parent_creatable(&self.coagulate)?;
+        utils::parent_creatable(&self.workspace.root)?;
+        utils::space_available(
+            &self.coagulate,
+            &[&self.substrate.substrate_path],
+            expansion::COAGULATION,
+        )?;
         Ok(())
     }
 }
 
+/// Configuration of the per-run temporary directories.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct WorkspaceConfig {
+    /// Directory holding the temporary directories.
+    pub root: PathBuf,
+
+    /// Number of days after which directories left by previous runs are removed.
+    pub max_age_days: u64,
+}
+
+/// Limit of the number of products listed in a single category.
+#[must_use]
+#[derive(Debug, Clone, Copy, Default, JsonSchema)]
+pub struct CategoryCap {
+    /// Maximal number of products listed in a category or `None` if not limited.
+    pub max_products: Option<usize>,
+
+    /// Handling of categories with more products.
+    pub overflow: CategoryOverflow,
+}
+
 /// Configuration for the `crystalize` command.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub struct CrystalizationConfig {
     /// Data substrate.
     pub substrate: SubstrateConfig,
@@ -838,6 +1159,43 @@ pub struct CrystalizationConfig {
 
     /// Runtime storage..
     pub runtime: PathBuf,
+
+    /// Number of entries written to the database at once.
+    pub write_batch_size: usize,
+
+    /// Storage engine of the database.
+    #[schemars(with = "String")]
+    pub engine: StorageEngine,
+
+    /// Scorer configuration.
+    pub scorer: Option<PathBuf>,
+
+    /// Name matches created by the `connect` command.
+    pub matches: Option<PathBuf>,
+
+    /// Deduplication of products in the ranked category indices.
+    pub category_dedup: CategoryDedup,
+
+    /// Limit of the number of products listed in a category.
+    pub category_cap: CategoryCap,
+
+    /// Number of shards the product keyword indices are built in.
+    pub keyword_shards: usize,
+
+    /// Number of threads building the keyword index shards.
+    pub keyword_workers: usize,
+
+    /// Products which should not be presented as consumer products.
+    pub deny_list: Option<PathBuf>,
+
+    /// Results of the checks of the shopping links.
+    pub shopping_availability: Option<PathBuf>,
+
+    /// Sitelink counts of the Wikidata products.
+    pub wikidata_sitelinks: Option<PathBuf>,
+
+    /// Configuration of the watch mode.
+    pub watch: Option<WatchingConfig>,
 }
 
 impl CrystalizationConfig {
@@ -845,11 +1203,38 @@ impl CrystalizationConfig {
     pub fn new(args: &commands::CrystalizationArgs) -> CrystalizationConfig {
         let target = PathBuf::from(&args.target);
         let coagulate = PathBuf::from(&args.coagulate);
+        let watch = args.watch.then(|| WatchingConfig {
+            coagulate_workspace: WorkspaceConfig {
+                root: coagulate.join("runtime"),
+                max_age_days: crate::workspace::DEFAULT_MAX_AGE_DAYS,
+            },
+            generations: target.join("generations"),
+            debounce: args.watch_debounce,
+            reload_url: args.reload_url.clone(),
+        });
         Self {
             substrate: SubstrateConfig::new(&args.substrate),
             coagulate: coagulate.join("coagulate.yaml"),
             crystal: target.join("db"),
             runtime: target.join("runtime"),
+            write_batch_size: args.write_batch_size,
+            engine: match args.engine {
+                commands::DbEngine::Kv => StorageEngine::Kv,
+                commands::DbEngine::Redb => StorageEngine::Redb,
+            },
+            scorer: args.scorer.as_ref().map(PathBuf::from),
+            matches: args.matches.as_ref().map(PathBuf::from),
+            category_dedup: args.category_dedup,
+            category_cap: CategoryCap {
+                max_products: args.max_category_products,
+                overflow: args.category_overflow,
+            },
+            keyword_shards: args.keyword_shards,
+            keyword_workers: args.keyword_workers,
+            deny_list: args.deny_list.as_ref().map(PathBuf::from),
+            shopping_availability: args.shopping_availability.as_ref().map(PathBuf::from),
+            wikidata_sitelinks: args.wikidata_sitelinks.as_ref().map(PathBuf::from),
+            watch,
         }
     }
 
@@ -857,19 +1242,74 @@ impl CrystalizationConfig {
     ///
     /// # Errors
     ///
-    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    /// Returns `Err` if paths expected to exist do not exist, paths expected to not exist do exist
+    /// or there is not enough free disk space for the outputs.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
         self.substrate.check_read()?;
-        utils::file_exists(&self.coagulate)?;
-        utils::parent_creatable(&self.crystal)?;
-        utils::parent_creatable(&self.runtime)?;
+        if let Some(scorer) = &self.scorer {
+            utils::file_exists(scorer)?;
+        }
+        if let Some(matches) = &self.matches {
+            utils::file_exists(matches)?;
+        }
+        if let Some(deny_list) = &self.deny_list {
+            utils::file_exists(deny_list)?;
+        }
+        if let Some(shopping_availability) = &self.shopping_availability {
+            utils::file_exists(shopping_availability)?;
+        }
+        if let Some(wikidata_sitelinks) = &self.wikidata_sitelinks {
+            utils::file_exists(wikidata_sitelinks)?;
+        }
+        if let Some(watch) = &self.watch {
+            // In the watch mode the coagulate is created and the database path is a link.
+            utils::parent_creatable(&self.coagulate)?;
+            if self.crystal.exists() && !self.crystal.is_symlink() {
+                return Err(ConfigCheckError::AlreadyExists(self.crystal.clone()));
+            }
+            utils::parent_creatable(&watch.generations)?;
+
+            // Every generation needs its own database and the previous one is kept.
+            utils::space_available(
+                &watch.generations,
+                &[&self.substrate.substrate_path],
+                2 * expansion::CRYSTALIZATION + expansion::COAGULATION,
+            )?;
+        } else {
+            utils::file_exists(&self.coagulate)?;
+            utils::parent_creatable(&self.crystal)?;
+            utils::parent_creatable(&self.runtime)?;
+            utils::space_available(
+                &self.crystal,
+                &[&self.substrate.substrate_path],
+                expansion::CRYSTALIZATION,
+            )?;
+        }
         Ok(())
     }
 }
 
+/// Configuration for the watch mode of the `crystalize` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct WatchingConfig {
+    /// Workspace for the runtime storage of the coagulation.
+    pub coagulate_workspace: WorkspaceConfig,
+
+    /// Directory where fresh databases are created.
+    pub generations: PathBuf,
+
+    /// Time without changes to wait for before re-running.
+    #[schemars(with = "String")]
+    pub debounce: std::time::Duration,
+
+    /// URL to notify after a new database was created.
+    pub reload_url: Option<String>,
+}
+
 /// Configuration for the `transcribe` command.
 #[must_use]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct OxidationConfig {
     /// Path to the input library file.
     pub library_file_path: PathBuf,
@@ -913,9 +1353,46 @@ impl OxidationConfig {
     }
 }
 
+/// Configuration for the `feed` command.
+#[must_use]
+#[derive(Clone, Debug, JsonSchema)]
+pub struct FeedingConfig {
+    /// Path to the retailer feed.
+    pub feed_path: PathBuf,
+
+    /// Name of the retailer.
+    pub retailer: String,
+
+    /// Application database storage.
+    pub app_storage: PathBuf,
+}
+
+impl FeedingConfig {
+    /// Constructs a new `FeedingConfig`.
+    pub fn new(args: &commands::FeedingArgs) -> FeedingConfig {
+        let target = PathBuf::from(&args.target);
+        Self {
+            feed_path: PathBuf::from(&args.feed),
+            retailer: args.retailer.clone(),
+            app_storage: target.join("app"),
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::file_exists(&self.feed_path)?;
+        utils::dir_usable(&self.app_storage)?;
+        Ok(())
+    }
+}
+
 /// Configuration for the `connect` command.
 #[must_use]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct ConnectionConfig {
     /// Path to input EU Ecolabel data file.
     pub eu_ecolabel_input_path: PathBuf,
@@ -959,29 +1436,53 @@ impl ConnectionConfig {
 
 /// Configuration for the target part of the `sample` command.
 #[must_use]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct SamplingTargetConfig {
-    // DB storage
+    /// DB storage.
     pub db_storage: PathBuf,
 }
 
 /// Configuration for the backend part of the `sample` command.
 #[must_use]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct SamplingBackendConfig {
     /// URL of the backend service.
     pub url: String,
 }
 
+/// Configuration for the live Wikidata part of the `sample` command.
+#[must_use]
+#[derive(Clone, Debug, JsonSchema)]
+pub struct SamplingWikidataConfig {
+    /// IDs of entities to fetch.
+    pub ids: Vec<String>,
+
+    /// Path to a file with a SPARQL query selecting additional entities to fetch.
+    pub sparql_path: Option<PathBuf>,
+
+    /// Directory where the raw responses for single entities are cached.
+    pub entity_cache_dir: PathBuf,
+
+    /// Path to the output file in the format of the filtered Wikidata dump.
+    pub wikidata_path: PathBuf,
+
+    /// Minimal delay between requests.
+    #[schemars(with = "String")]
+    pub delay: std::time::Duration,
+}
+
 /// Configuration for the `sample` command.
 #[must_use]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct SamplingConfig {
     /// Subconfig for the target sampling.
     pub target: Option<SamplingTargetConfig>,
 
     /// Subconfig for the backend sampling.
     pub backend: Option<SamplingBackendConfig>,
+
+    /// Subconfig for fetching entities from the live Wikidata service.
+    pub wikidata: Option<SamplingWikidataConfig>,
 }
 
 impl SamplingConfig {
@@ -994,7 +1495,19 @@ impl SamplingConfig {
             None
         };
         let backend = args.url.as_ref().map(|url| SamplingBackendConfig { url: url.clone() });
-        SamplingConfig { target, backend }
+        let wikidata = if args.wikidata_ids.is_empty() && args.wikidata_sparql.is_none() {
+            None
+        } else {
+            let cache = PathBuf::from(args.cache.as_deref().unwrap_or("."));
+            Some(SamplingWikidataConfig {
+                ids: args.wikidata_ids.clone(),
+                sparql_path: args.wikidata_sparql.as_ref().map(PathBuf::from),
+                entity_cache_dir: cache.join("wikidata-live"),
+                wikidata_path: cache.join(cache::CURRENT_NAMESPACE).join("wikidata.jsonl"),
+                delay: args.wikidata_delay,
+            })
+        };
+        SamplingConfig { target, backend, wikidata }
     }
 
     /// Checks validity of the configuration.
@@ -1004,7 +1517,570 @@ impl SamplingConfig {
     /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
     pub fn check(&self) -> Result<(), ConfigCheckError> {
         if let Some(target) = &self.target {
-            utils::dir_exists(&target.db_storage)?;
+            // Depending on the storage engine the database is a directory or a single file.
+            if !target.db_storage.exists() {
+                return Err(ConfigCheckError::DoesNotExist(target.db_storage.clone()));
+            }
+        }
+        if let Some(wikidata) = &self.wikidata {
+            if let Some(sparql_path) = &wikidata.sparql_path {
+                utils::file_exists(sparql_path)?;
+            }
+            utils::dir_usable(&wikidata.entity_cache_dir)?;
+            utils::file_exists_or_creatable(&wikidata.wikidata_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for the `trace` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct TracingConfig {
+    /// Data substrate.
+    pub substrate: SubstrateConfig,
+
+    /// Path to the coagulate.
+    pub coagulate: PathBuf,
+
+    /// Runtime storage for the replayed crystalization.
+    pub runtime: PathBuf,
+
+    /// The traced entity.
+    #[schemars(with = "String")]
+    pub target: TraceTarget,
+}
+
+impl TracingConfig {
+    /// Constructs a new `TracingConfig`.
+    pub fn new(args: &commands::TraceArgs) -> TracingConfig {
+        let coagulate = PathBuf::from(&args.coagulate);
+        let target = match args.kind {
+            commands::TraceKind::Organisation => {
+                TraceTarget::Organisation(OrganisationId::from_value(args.unique_id))
+            }
+            commands::TraceKind::Product => {
+                TraceTarget::Product(ProductId::from_value(args.unique_id))
+            }
+        };
+        Self {
+            substrate: SubstrateConfig::new(&args.substrate),
+            coagulate: coagulate.join("coagulate.yaml"),
+            runtime: coagulate.join("trace"),
+            target,
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        self.substrate.check_read()?;
+        utils::file_exists(&self.coagulate)?;
+        utils::parent_creatable(&self.runtime)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `check-shopping` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct ShoppingCheckConfig {
+    /// Substrate files to collect the shopping links from.
+    pub substrate: SubstrateConfig,
+
+    /// Path to the shopping availability file the results are merged into.
+    pub shopping_availability: PathBuf,
+
+    /// Maximal number of links to check. All links are checked if not set.
+    pub limit: Option<usize>,
+
+    /// Minimal delay between requests to the same shop.
+    #[schemars(with = "String")]
+    pub delay: std::time::Duration,
+
+    /// Timeout of a single request.
+    #[schemars(with = "String")]
+    pub timeout: std::time::Duration,
+}
+
+impl ShoppingCheckConfig {
+    /// Constructs a new `ShoppingCheckConfig`.
+    pub fn new(args: &commands::ShoppingCheckArgs) -> ShoppingCheckConfig {
+        Self {
+            substrate: SubstrateConfig::new(&args.substrate),
+            shopping_availability: PathBuf::from(&args.meta).join("shopping_availability.yaml"),
+            limit: args.limit,
+            delay: args.delay,
+            timeout: args.timeout,
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        self.substrate.check_read()?;
+        utils::file_exists_or_creatable(&self.shopping_availability)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `review-substrate` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct SubstrateReviewConfig {
+    /// Path to the reviewed substrate file.
+    pub substrate_file: PathBuf,
+
+    /// Path to the crystal to match the products against. Matching is skipped if not set.
+    pub crystal: Option<PathBuf>,
+
+    /// Path to the output report. The report is printed if not set.
+    pub output: Option<PathBuf>,
+}
+
+impl SubstrateReviewConfig {
+    /// Constructs a new `SubstrateReviewConfig`.
+    pub fn new(args: &commands::SubstrateReviewArgs) -> SubstrateReviewConfig {
+        Self {
+            substrate_file: PathBuf::from(&args.substrate_file),
+            crystal: args.crystal.as_ref().map(PathBuf::from),
+            output: args.output.as_ref().map(PathBuf::from),
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::file_exists(&self.substrate_file)?;
+        if let Some(crystal) = &self.crystal {
+            utils::dir_exists(crystal)?;
+        }
+        if let Some(output) = &self.output {
+            utils::file_exists_or_creatable(output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for the `bundle-repro` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct BundleConfig {
+    /// Data substrate.
+    pub substrate: SubstrateConfig,
+
+    /// Path to the coagulate.
+    pub coagulate: PathBuf,
+
+    /// Path of the created tarball.
+    pub output: PathBuf,
+
+    /// Kind of the bundled entity.
+    pub kind: commands::TraceKind,
+
+    /// Unique ID of the entity or ID of a substrate entry if `source` is set.
+    pub id: String,
+
+    /// Name of the substrate the ID of the entry comes from.
+    pub source: Option<String>,
+}
+
+impl BundleConfig {
+    /// Constructs a new `BundleConfig`.
+    pub fn new(args: &commands::BundleArgs) -> BundleConfig {
+        Self {
+            substrate: SubstrateConfig::new(&args.substrate),
+            coagulate: PathBuf::from(&args.coagulate).join("coagulate.yaml"),
+            output: PathBuf::from(&args.output),
+            kind: args.kind,
+            id: args.id.clone(),
+            source: args.source.clone(),
+        }
+    }
+
+    /// Returns the unique ID if the ID does not come from a substrate.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the ID is not a valid unique ID.
+    pub fn unique_id(&self) -> Result<Option<u32>, ConfigCheckError> {
+        if self.source.is_some() {
+            return Ok(None);
+        }
+        self.id.parse().map(Some).map_err(|_| ConfigCheckError::InvalidUniqueId(self.id.clone()))
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        self.substrate.check_read()?;
+        utils::file_exists(&self.coagulate)?;
+        utils::path_creatable(&self.output)?;
+        self.unique_id()?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `duplicates` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct DuplicatesConfig {
+    /// Path to the crystal.
+    pub crystal: PathBuf,
+
+    /// Path to the output report.
+    pub output: PathBuf,
+
+    /// Minimal similarity of the names for the products to be reported.
+    pub threshold: f64,
+}
+
+impl DuplicatesConfig {
+    /// Constructs a new `DuplicatesConfig`.
+    pub fn new(args: &commands::DuplicatesArgs) -> DuplicatesConfig {
+        Self {
+            crystal: PathBuf::from(&args.crystal),
+            output: PathBuf::from(&args.output),
+            threshold: args.threshold,
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.crystal)?;
+        utils::file_exists_or_creatable(&self.output)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `qa-sample` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct QaSampleConfig {
+    /// Path to the crystal.
+    pub crystal: PathBuf,
+
+    /// Path to the output report.
+    pub output: PathBuf,
+
+    /// Number of products sampled from every source.
+    pub per_source: usize,
+
+    /// Seed selecting the sampled products.
+    pub seed: u64,
+}
+
+impl QaSampleConfig {
+    /// Constructs a new `QaSampleConfig`.
+    pub fn new(args: &commands::QaSampleArgs) -> QaSampleConfig {
+        Self {
+            crystal: PathBuf::from(&args.crystal),
+            output: PathBuf::from(&args.output),
+            per_source: args.per_source,
+            seed: args.seed,
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.crystal)?;
+        utils::file_exists_or_creatable(&self.output)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `export-research` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct ResearchExportConfig {
+    /// Path to the crystal.
+    pub crystal: PathBuf,
+
+    /// Path to the output tarball.
+    pub output: PathBuf,
+
+    /// Version of the dataset.
+    pub version: String,
+
+    /// License of the dataset.
+    pub license: String,
+}
+
+impl ResearchExportConfig {
+    /// Constructs a new `ResearchExportConfig`.
+    pub fn new(args: &commands::ResearchExportArgs) -> ResearchExportConfig {
+        Self {
+            crystal: PathBuf::from(&args.crystal),
+            output: PathBuf::from(&args.output),
+            version: args.dataset_version.clone(),
+            license: args.license.clone(),
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.crystal)?;
+        utils::file_exists_or_creatable(&self.output)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `export-sitemap` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct SitemapExportConfig {
+    /// Path to the crystal.
+    pub crystal: PathBuf,
+
+    /// Output directory for the sitemap files.
+    pub output: PathBuf,
+
+    /// URL under which the pages are served, without the trailing slash.
+    pub base_url: String,
+
+    /// Maximal number of URLs in a single sitemap shard.
+    pub shard_size: usize,
+}
+
+impl SitemapExportConfig {
+    /// Constructs a new `SitemapExportConfig`.
+    pub fn new(args: &commands::SitemapExportArgs) -> SitemapExportConfig {
+        Self {
+            crystal: PathBuf::from(&args.crystal),
+            output: PathBuf::from(&args.output),
+            base_url: args.base_url.trim_end_matches('/').to_owned(),
+            shard_size: args.shard_size,
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or the base URL is not an HTTP(S) URL.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.crystal)?;
+        utils::dir_usable(&self.output)?;
+        if !self.base_url.starts_with("https://") && !self.base_url.starts_with("http://") {
+            return Err(ConfigCheckError::InvalidBaseUrl(self.base_url.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for the `fetch` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct FetchConfig {
+    /// Origin data directory the targets are relative to.
+    pub origin: PathBuf,
+
+    /// Meta data.
+    pub meta: MetaConfig,
+
+    /// Names of the sources to fetch. Empty means all.
+    pub sources: Vec<String>,
+}
+
+impl FetchConfig {
+    /// Constructs a new `FetchConfig`.
+    pub fn new(args: &commands::FetchArgs) -> FetchConfig {
+        Self {
+            origin: PathBuf::from(&args.origin),
+            meta: MetaConfig::new(&args.meta),
+            sources: args.sources.clone(),
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.origin)?;
+        utils::file_exists(&self.meta.fetch_path)?;
+        utils::file_exists_or_creatable(&self.meta.absorbents)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `merge-substrates` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct SubstrateMergingConfig {
+    /// First substrate directory. Its files are kept on conflicts.
+    pub first: PathBuf,
+
+    /// Second substrate directory.
+    pub second: PathBuf,
+
+    /// Output substrate directory.
+    pub output: PathBuf,
+}
+
+impl SubstrateMergingConfig {
+    /// Constructs a new `SubstrateMergingConfig`.
+    pub fn new(args: &commands::SubstrateMergingArgs) -> SubstrateMergingConfig {
+        Self {
+            first: PathBuf::from(&args.first),
+            second: PathBuf::from(&args.second),
+            output: PathBuf::from(&args.output),
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.first)?;
+        utils::dir_exists(&self.second)?;
+        utils::path_creatable(&self.output)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `check-advisors` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct AdvisorCheckConfig {
+    /// Meta files to check.
+    pub meta: MetaConfig,
+
+    /// Support files to check.
+    pub support: SupportConfig,
+}
+
+impl AdvisorCheckConfig {
+    /// Constructs a new `AdvisorCheckConfig`.
+    pub fn new(args: &commands::AdvisorCheckArgs) -> AdvisorCheckConfig {
+        Self { meta: MetaConfig::new(&args.meta), support: SupportConfig::new(&args.support) }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist or paths expected to not exist do exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        self.meta.check()?;
+        self.support.check()?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `explain-config` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct ExplainConfig {
+    /// Name of the explained command. All commands are listed if not set.
+    pub command: Option<String>,
+
+    /// Whether to print the JSON schema of the configuration instead of the description.
+    pub json: bool,
+}
+
+impl ExplainConfig {
+    /// Constructs a new `ExplainConfig`.
+    pub fn new(args: &commands::ExplainArgs) -> ExplainConfig {
+        Self { command: args.command.clone(), json: args.json }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the explained command does not exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        if let Some(command) = &self.command {
+            if commands::Args::command().find_subcommand(command).is_none() {
+                return Err(ConfigCheckError::UnknownCommand(command.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for the `verify-db` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct VerificationConfig {
+    /// Path to the crystal.
+    pub crystal: PathBuf,
+}
+
+impl VerificationConfig {
+    /// Constructs a new `VerificationConfig`.
+    pub fn new(args: &commands::VerificationArgs) -> VerificationConfig {
+        Self { crystal: PathBuf::from(&args.crystal) }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.crystal)?;
+        Ok(())
+    }
+}
+
+/// Configuration for the `digest` command.
+#[must_use]
+#[derive(Debug, Clone, JsonSchema)]
+pub struct DigestConfig {
+    /// Path to the crystal.
+    pub crystal: PathBuf,
+
+    /// Path to the crystal to compare with.
+    pub against: Option<PathBuf>,
+}
+
+impl DigestConfig {
+    /// Constructs a new `DigestConfig`.
+    pub fn new(args: &commands::DigestArgs) -> DigestConfig {
+        Self {
+            crystal: PathBuf::from(&args.crystal),
+            against: args.against.as_ref().map(PathBuf::from),
+        }
+    }
+
+    /// Checks validity of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if paths expected to exist do not exist.
+    pub fn check(&self) -> Result<(), ConfigCheckError> {
+        utils::dir_exists(&self.crystal)?;
+        if let Some(against) = &self.against {
+            utils::dir_exists(against)?;
         }
         Ok(())
     }
@@ -1088,39 +2164,100 @@ impl From<&CrystalizationConfig> for SubstrateConfig {
     }
 }
 
+/// Configuration of the logger.
+#[must_use]
+#[derive(Clone, Debug, JsonSchema)]
+pub struct LoggingConfig {
+    /// Format of the log output.
+    pub format: LogFormat,
+
+    /// Name of the processing stage (the command being run).
+    pub stage: &'static str,
+
+    /// Whether to log to the standard error, leaving the standard output for the results.
+    pub stderr: bool,
+}
+
 /// Configuration for the program.
 #[must_use]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JsonSchema)]
 pub enum Config {
     Absorbing(AbsorbingConfig),
+    Fetching(FetchConfig),
     Extracting(ExtractingConfig),
+    Caching(CachingConfig),
     Filtering(FilteringConfig),
     Updating(UpdatingConfig),
     Condensation(CondensationConfig),
     Coagulation(CoagulationConfig),
     Crystalization(CrystalizationConfig),
     Oxidation(OxidationConfig),
+    Feeding(FeedingConfig),
     Connection(ConnectionConfig),
     Sample(SamplingConfig),
+    Tracing(TracingConfig),
+    Bundle(BundleConfig),
+    Duplicates(DuplicatesConfig),
+    QaSample(QaSampleConfig),
+    ResearchExport(ResearchExportConfig),
+    SitemapExport(SitemapExportConfig),
+    Verification(VerificationConfig),
+    Digest(DigestConfig),
+    SubstrateMerging(SubstrateMergingConfig),
+    AdvisorCheck(AdvisorCheckConfig),
+    Explaining(ExplainConfig),
+    ShoppingCheck(ShoppingCheckConfig),
+    SubstrateReview(SubstrateReviewConfig),
 }
 
 impl Config {
     /// Constructs a new config from `Args::parse()`.
-    pub fn new_from_args() -> Config {
+    pub fn new_from_args() -> (Config, LoggingConfig) {
         use commands::{Args, Commands};
 
         let args = Args::parse();
-        match args.command {
+        let logging = LoggingConfig {
+            format: args.log_format,
+            stage: args.command.name(),
+            stderr: matches!(
+                args.command,
+                Commands::ExplainConfig(_) | Commands::ReviewSubstrate(_)
+            ),
+        };
+        let config = match args.command {
             Commands::Absorb(args) => Config::Absorbing(AbsorbingConfig::new(&args)),
+            Commands::Fetch(args) => Config::Fetching(FetchConfig::new(&args)),
             Commands::Extract(args) => Config::Extracting(ExtractingConfig::new(&args)),
+            Commands::Cache(args) => Config::Caching(CachingConfig::new(&args)),
             Commands::Filter(args) => Config::Filtering(FilteringConfig::new(&args)),
             Commands::Update(args) => Config::Updating(UpdatingConfig::new(&args)),
             Commands::Condense(args) => Config::Condensation(CondensationConfig::new(&args)),
             Commands::Coagulate(args) => Config::Coagulation(CoagulationConfig::new(&args)),
             Commands::Crystalize(args) => Config::Crystalization(CrystalizationConfig::new(&args)),
             Commands::Oxidize(args) => Config::Oxidation(OxidationConfig::new(&args)),
+            Commands::Feed(args) => Config::Feeding(FeedingConfig::new(&args)),
             Commands::Connect(args) => Config::Connection(ConnectionConfig::new(&args)),
             Commands::Sample(args) => Config::Sample(SamplingConfig::new(&args)),
-        }
+            Commands::Trace(args) => Config::Tracing(TracingConfig::new(&args)),
+            Commands::BundleRepro(args) => Config::Bundle(BundleConfig::new(&args)),
+            Commands::Duplicates(args) => Config::Duplicates(DuplicatesConfig::new(&args)),
+            Commands::QaSample(args) => Config::QaSample(QaSampleConfig::new(&args)),
+            Commands::ExportResearch(args) => {
+                Config::ResearchExport(ResearchExportConfig::new(&args))
+            }
+            Commands::ExportSitemap(args) => Config::SitemapExport(SitemapExportConfig::new(&args)),
+            Commands::VerifyDb(args) => Config::Verification(VerificationConfig::new(&args)),
+            Commands::Digest(args) => Config::Digest(DigestConfig::new(&args)),
+            Commands::MergeSubstrates(args) => {
+                Config::SubstrateMerging(SubstrateMergingConfig::new(&args))
+            }
+            Commands::CheckAdvisors(args) => Config::AdvisorCheck(AdvisorCheckConfig::new(&args)),
+            Commands::ExplainConfig(args) => Config::Explaining(ExplainConfig::new(&args)),
+            Commands::CheckShopping(args) => Config::ShoppingCheck(ShoppingCheckConfig::new(&args)),
+            Commands::ReviewSubstrate(args) => {
+                Config::SubstrateReview(SubstrateReviewConfig::new(&args))
+            }
+        };
+        (config, logging)
     }
 }
@@ -2,13 +2,20 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::collections::{BTreeMap, BTreeSet, HashSet, btree_map::Entry};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, btree_map::Entry};
 
 use maplit::btreeset;
-
-use transpaer_collecting::categories::{self, Category};
+use serde::{Serialize, de::DeserializeOwned};
+
+use transpaer_collecting::{
+    categories::{self, Category},
+    deny_list::{self, data::DenyList},
+    shopping_availability::ShoppingAvailability,
+    sitelinks,
+    transpaer::reader::parse_id_map,
+};
 use transpaer_models::{
-    buckets::{Bucket, BucketError, DbStore},
+    buckets::{Bucket, BucketError, DbStore, Storage, StorageEngine},
     combine::Combine,
     gather, store, transpaer, utils,
 };
@@ -18,7 +25,9 @@ use crate::{
     coagulate::{Coagulate, ExternalId, InnerId},
     config,
     errors::{self, CrystalizationError},
+    score::{Scorer, ScorerConfig},
     substrate::{DataSetId, Substrate, Substrates},
+    utils::InvalidVatIds,
 };
 
 // TODO: Rework as reports per data source
@@ -27,11 +36,42 @@ use crate::{
 #[derive(Debug, Default)]
 pub struct CrystalizationReport {
     invalid_ids: BTreeMap<DataSetId, BTreeSet<String>>,
+    invalid_vat_ids: BTreeMap<DataSetId, InvalidVatIds>,
     empty_ids: BTreeMap<DataSetId, BTreeSet<InnerId>>,
     missing_inner_ids: BTreeMap<DataSetId, BTreeSet<InnerId>>,
+    substrate_metas: BTreeMap<String, store::SubstrateMeta>,
 }
 
 impl CrystalizationReport {
+    /// Records the provenance of a processed substrate file.
+    pub fn add_substrate_meta(
+        &mut self,
+        substrate: &Substrate,
+        num_producers: usize,
+        num_products: usize,
+    ) {
+        let file_size = std::fs::metadata(&substrate.path).map_or(0, |metadata| metadata.len());
+        let meta = store::SubstrateMeta {
+            file_size,
+            created: substrate.created,
+            version: substrate.version.clone(),
+            num_producers,
+            num_products,
+            crystalizer_version: env!("CARGO_PKG_VERSION").to_owned(),
+        };
+        self.substrate_metas.insert(substrate.name.clone(), meta);
+    }
+
+    /// Returns the provenance of the processed substrate files.
+    #[must_use]
+    pub fn substrate_metas(&self) -> &BTreeMap<String, store::SubstrateMeta> {
+        &self.substrate_metas
+    }
+
+    pub fn add_invalid_vat_id(&mut self, data_set_id: DataSetId, error: &gather::ParseIdError) {
+        self.invalid_vat_ids.entry(data_set_id).or_default().add(error);
+    }
+
     pub fn add_invalid_id(&mut self, data_set_id: DataSetId, id: String) {
         match self.invalid_ids.entry(data_set_id) {
             Entry::Occupied(mut e) => {
@@ -77,6 +117,15 @@ impl CrystalizationReport {
                 log::warn!("  - `{}`: {}", path, ids.len());
             }
         }
+        if !self.invalid_vat_ids.is_empty() {
+            log::warn!(" rejected VAT numbers:");
+            for (data_set_id, rejects) in &self.invalid_vat_ids {
+                let path = substrates
+                    .get_path_for_id(*data_set_id)
+                    .map_or_else(|| UNKNOWN.to_string(), |path| format!("{}", path.display()));
+                log::warn!("  - `{}`: {}", path, rejects);
+            }
+        }
         if !self.empty_ids.is_empty() {
             log::warn!(" empty IDs:");
             for (data_set_id, ids) in &self.empty_ids {
@@ -96,6 +145,139 @@ impl CrystalizationReport {
             }
         }
         log::warn!("End of the report");
+
+        log::info!("Substrates crystalized by version {}:", env!("CARGO_PKG_VERSION"));
+        for (name, meta) in &self.substrate_metas {
+            log::info!(
+                " - `{name}`: {} producers, {} products, {} bytes, created at {:?} by version {:?}",
+                meta.num_producers,
+                meta.num_products,
+                meta.file_size,
+                meta.created,
+                meta.version,
+            );
+        }
+    }
+}
+
+/// Number of sample products listed per source in the matching report.
+const NUM_MATCHING_SAMPLES: usize = 10;
+
+/// Products without a manufacturer from a single source.
+#[derive(Debug, Default)]
+struct MissingManufacturers {
+    /// Number of products with at least one manufacturer which could not be found.
+    num_products: usize,
+
+    /// Samples of the affected products and the IDs of their missing manufacturers.
+    samples: Vec<(String, gather::OrganisationId)>,
+}
+
+/// Lists products whose manufacturers could not be found, per source claiming the manufacturer.
+///
+/// Most often these are results of bugs in Wikidata or of bad matches during coagulation.
+#[derive(Debug, Default)]
+pub struct MatchingReport {
+    missing_manufacturers: BTreeMap<gather::Source, MissingManufacturers>,
+}
+
+impl MatchingReport {
+    pub fn add_missing_manufacturers(
+        &mut self,
+        product_id: &gather::ProductId,
+        product: &gather::Product,
+        missing: &[gather::OrganisationId],
+    ) {
+        let mut sources = BTreeMap::<gather::Source, &gather::OrganisationId>::new();
+        for manufacturer_id in missing {
+            for source in product.manufacturers.get(manufacturer_id).into_iter().flatten() {
+                sources.entry(source.clone()).or_insert(manufacturer_id);
+            }
+        }
+
+        for (source, manufacturer_id) in sources {
+            let entry = self.missing_manufacturers.entry(source).or_default();
+            entry.num_products += 1;
+            if entry.samples.len() < NUM_MATCHING_SAMPLES {
+                entry.samples.push((Self::describe(product_id, product), manufacturer_id.clone()));
+            }
+        }
+    }
+
+    /// Describes the product with the ID most useful for finding it in the sources.
+    fn describe(product_id: &gather::ProductId, product: &gather::Product) -> String {
+        if let Some(wiki_id) = product.ids.wiki.keys().first() {
+            format!("wiki:{}", wiki_id.to_canonical_string())
+        } else if let Some(gtin) = product.ids.gtins.keys().first() {
+            format!("gtin:{}", gtin.to_canonical_string())
+        } else if let Some(ean) = product.ids.eans.keys().first() {
+            format!("ean:{}", ean.to_canonical_string())
+        } else {
+            format!("id:{product_id}")
+        }
+    }
+
+    pub fn report(&self) {
+        if self.missing_manufacturers.is_empty() {
+            log::info!("Matching report: all manufacturers found");
+            return;
+        }
+
+        log::warn!("Matching report:");
+        log::warn!(" products with missing manufacturers:");
+        for (source, missing) in &self.missing_manufacturers {
+            log::warn!("  - {}: {}", source.to_label(), missing.num_products);
+            for (product, manufacturer_id) in &missing.samples {
+                log::warn!("    - product `{product}`, manufacturer `{manufacturer_id}`");
+            }
+        }
+        log::warn!("End of the report");
+    }
+}
+
+/// Counts of products dropped because of the deny-list.
+#[derive(Debug, Default)]
+pub struct DenialReport {
+    /// Number of dropped products per denied category.
+    by_category: BTreeMap<String, usize>,
+
+    /// Number of products dropped because of their Wikidata ID.
+    by_wiki_id: usize,
+}
+
+impl DenialReport {
+    pub fn report(&self) {
+        let num_denied = self.by_category.values().sum::<usize>() + self.by_wiki_id;
+        log::info!("Deny-list report: {num_denied} products dropped");
+        for (category, amount) in &self.by_category {
+            log::info!(" - category {category: <100} {amount: >7}");
+        }
+        if self.by_wiki_id > 0 {
+            log::info!(" - by Wikidata ID {: >7}", self.by_wiki_id);
+        }
+    }
+}
+
+/// Counts of shopping entries found dead by the `check-shopping` command.
+#[derive(Debug, Default)]
+pub struct DeadShoppingReport {
+    /// Number of dead entries per shop.
+    by_shop: BTreeMap<&'static str, usize>,
+
+    /// Number of products with at least one dead entry.
+    num_products: usize,
+}
+
+impl DeadShoppingReport {
+    pub fn report(&self) {
+        let num_dead = self.by_shop.values().sum::<usize>();
+        log::info!(
+            "Shopping report: {num_dead} dead entries in {} products dropped",
+            self.num_products
+        );
+        for (shop, amount) in &self.by_shop {
+            log::info!(" - {shop: <100} {amount: >7}");
+        }
     }
 }
 
@@ -154,12 +336,111 @@ pub struct CrystalizationCollector {
     ///
     /// Since the lists contain several gigabytes of data it's necessary to store them in a database
     /// stored on a disk.
-    store: kv::Store,
+    store: Storage,
 }
 
 impl CrystalizationCollector {
     pub fn new(path: &std::path::Path) -> Result<Self, BucketError> {
-        Ok(Self { store: kv::Store::new(kv::Config::new(path))? })
+        Ok(Self { store: Storage::open(path, StorageEngine::Kv)? })
+    }
+
+    /// Removes products matching the deny-list.
+    ///
+    /// Products are counted under the first matching category, or under Wikidata IDs if no
+    /// category matched.
+    pub fn deny(&self, deny_list: &DenyList) -> Result<DenialReport, BucketError> {
+        let categories: Vec<String> =
+            deny_list.categories.iter().map(Category::get_string).collect();
+        let wiki_ids: HashSet<gather::WikiId> =
+            deny_list.wiki.iter().map(|id| gather::WikiId::from(*id)).collect();
+
+        let mut report = DenialReport::default();
+        let mut denied = Vec::new();
+        let products = self.get_product_bucket()?;
+        for item in products.iter() {
+            let (product_id, product) = item?;
+            let product_categories = product.all_categories(categories::SEPARATOR);
+            if let Some(category) = categories.iter().find(|c| product_categories.contains(*c)) {
+                *report.by_category.entry(category.clone()).or_default() += 1;
+                denied.push(product_id);
+            } else if product.ids.wiki.keys().iter().any(|id| wiki_ids.contains(id)) {
+                report.by_wiki_id += 1;
+                denied.push(product_id);
+            }
+        }
+
+        for product_id in denied {
+            products.remove(&product_id)?;
+        }
+        Ok(report)
+    }
+
+    /// Removes shopping entries found dead by the `check-shopping` command.
+    pub fn check_shopping(
+        &self,
+        availability: &ShoppingAvailability,
+    ) -> Result<DeadShoppingReport, BucketError> {
+        let mut report = DeadShoppingReport::default();
+        let mut updated = Vec::new();
+        let products = self.get_product_bucket()?;
+        for item in products.iter() {
+            let (product_id, mut product) = item?;
+            let dead: Vec<gather::ShoppingKey> = product
+                .shopping
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| availability.is_dead(key.shop.name(), &key.id))
+                .cloned()
+                .collect();
+            if dead.is_empty() {
+                continue;
+            }
+
+            report.num_products += 1;
+            for key in &dead {
+                *report.by_shop.entry(key.shop.name()).or_default() += 1;
+            }
+            for key in &dead {
+                product.shopping.remove(key);
+            }
+            updated.push((product_id, product));
+        }
+
+        for (product_id, product) in updated {
+            products.insert(&product_id, &product)?;
+        }
+        Ok(report)
+    }
+
+    /// Assigns the sitelink counts recorded by the `filter` command to the products.
+    ///
+    /// Products merged from several Wikidata items get the highest count.
+    ///
+    /// Returns the number of updated products.
+    pub fn assign_sitelinks(&self, counts: &HashMap<u64, u32>) -> Result<usize, BucketError> {
+        let mut updated = Vec::new();
+        let products = self.get_product_bucket()?;
+        for item in products.iter() {
+            let (product_id, mut product) = item?;
+            let sitelinks = product
+                .ids
+                .wiki
+                .iter()
+                .filter_map(|(id, _)| counts.get(&id.as_value()))
+                .max()
+                .copied()
+                .unwrap_or(0);
+            if sitelinks != product.sitelinks {
+                product.sitelinks = sitelinks;
+                updated.push((product_id, product));
+            }
+        }
+
+        let num_updated = updated.len();
+        for (product_id, product) in updated {
+            products.insert(&product_id, &product)?;
+        }
+        Ok(num_updated)
     }
 
     pub fn update_organisation(
@@ -190,6 +471,20 @@ impl CrystalizationCollector {
         Ok(())
     }
 
+    pub fn get_organisation(
+        &self,
+        id: &gather::OrganisationId,
+    ) -> Result<Option<gather::Organisation>, BucketError> {
+        self.get_organisation_bucket()?.get(id)
+    }
+
+    pub fn get_product(
+        &self,
+        id: &gather::ProductId,
+    ) -> Result<Option<gather::Product>, BucketError> {
+        self.get_product_bucket()?.get(id)
+    }
+
     fn get_organisation_bucket(
         &self,
     ) -> Result<Bucket<'_, gather::OrganisationId, gather::Organisation>, BucketError> {
@@ -203,6 +498,13 @@ impl CrystalizationCollector {
     }
 }
 
+/// Entity to trace through the crystalization.
+#[derive(Debug, Clone)]
+pub enum TraceTarget {
+    Organisation(gather::OrganisationId),
+    Product(gather::ProductId),
+}
+
 #[derive(Debug)]
 pub struct Processor {
     /// Collected data.
@@ -210,6 +512,9 @@ pub struct Processor {
 
     /// Report listing warnings from substrate files.
     report: CrystalizationReport,
+
+    /// If set, only the traced entity is collected and its contributions are printed.
+    trace: Option<TraceTarget>,
 }
 
 impl Processor {
@@ -217,10 +522,63 @@ impl Processor {
         Ok(Self {
             collector: CrystalizationCollector::new(runtime_path)?,
             report: CrystalizationReport::default(),
+            trace: None,
         })
     }
 
-    fn process(
+    /// Constructs a processor collecting only the traced entity.
+    pub fn new_tracing(
+        runtime_path: &std::path::Path,
+        target: TraceTarget,
+    ) -> Result<Self, BucketError> {
+        Ok(Self { trace: Some(target), ..Self::new(runtime_path)? })
+    }
+
+    fn update_organisation(
+        &mut self,
+        substrate: &Substrate,
+        id: &gather::OrganisationId,
+        organisation: gather::Organisation,
+    ) -> Result<(), errors::CrystalizationError> {
+        match &self.trace {
+            None => {}
+            Some(TraceTarget::Organisation(traced)) if traced == id => {
+                log::info!(
+                    " - contribution from `{}`:
+{organisation:#?}",
+                    substrate.name
+                );
+            }
+            Some(_) => return Ok(()),
+        }
+        self.collector.update_organisation(id, organisation)
+    }
+
+    fn update_product(
+        &mut self,
+        substrate: &Substrate,
+        id: &gather::ProductId,
+        mut product: gather::Product,
+    ) -> Result<(), errors::CrystalizationError> {
+        // TODO: Prefer the per-record update times once the substrate schema can carry them.
+        if let Some(created) = substrate.created {
+            product.stamp(created);
+        }
+        match &self.trace {
+            None => {}
+            Some(TraceTarget::Product(traced)) if traced == id => {
+                log::info!(
+                    " - contribution from `{}`:
+{product:#?}",
+                    substrate.name
+                );
+            }
+            Some(_) => return Ok(()),
+        }
+        self.collector.update_product(id, product)
+    }
+
+    pub fn process(
         mut self,
         substrates: &Substrates,
         coagulate: &Coagulate,
@@ -228,14 +586,18 @@ impl Processor {
         log::info!("Processing substrates");
         for substrate in substrates.list() {
             log::info!(" => {}", substrate.name);
+            let mut num_producers = 0;
+            let mut num_products = 0;
             match schema::read::iter_file(&substrate.path)? {
                 schema::read::FileIterVariant::Catalog(iter) => {
                     for entry in iter {
                         match entry? {
                             schema::CatalogEntry::Producer(producer) => {
+                                num_producers += 1;
                                 self.process_catalog_producer(producer, substrate, coagulate)?;
                             }
                             schema::CatalogEntry::Product(product) => {
+                                num_products += 1;
                                 self.process_catalog_product(product, substrate, coagulate)?;
                             }
                         }
@@ -245,6 +607,7 @@ impl Processor {
                     for entry in iter {
                         match entry? {
                             schema::ProducerEntry::Product(product) => {
+                                num_products += 1;
                                 self.process_producer_product(product, substrate, coagulate)?;
                             }
                             schema::ProducerEntry::Reviewer(_reviewer) => {
@@ -257,15 +620,18 @@ impl Processor {
                     for entry in iter {
                         match entry? {
                             schema::ReviewEntry::Producer(producer) => {
+                                num_producers += 1;
                                 self.process_review_producer(producer, substrate, coagulate)?;
                             }
                             schema::ReviewEntry::Product(product) => {
+                                num_products += 1;
                                 self.process_review_product(product, substrate, coagulate)?;
                             }
                         }
                     }
                 }
             }
+            self.report.add_substrate_meta(substrate, num_producers, num_products);
         }
         Ok((self.collector, self.report))
     }
@@ -276,10 +642,13 @@ impl Processor {
         substrate: &Substrate,
         coagulate: &Coagulate,
     ) -> Result<(), errors::CrystalizationError> {
-        let external_id = ExternalId::new(substrate.id, InnerId::new(producer.id));
-        let unique_id = coagulate
-            .get_unique_id_for_producer_external_id(&external_id)
-            .map_err(|id| id.to_error_not_found(substrate, "processing catalog producer"))?;
+        let external_id = ExternalId::from_substrate(substrate, &producer.id);
+        let unique_id = match coagulate.get_unique_id_for_producer_external_id(&external_id) {
+            Ok(unique_id) => unique_id,
+            // Brands not matched to any known producer during coagulation are skipped.
+            Err(_) if substrate.source.is_open_supply_hub() => return Ok(()),
+            Err(id) => return Err(id.to_error_not_found(substrate, "processing catalog producer")),
+        };
         let ids = self.convert_organisation_ids(producer.ids, substrate);
         let images = producer
             .images
@@ -287,7 +656,8 @@ impl Processor {
             .map(|image| gather::Image { image, source: substrate.source.clone() })
             .collect();
 
-        self.collector.update_organisation(
+        self.update_organisation(
+            substrate,
             &unique_id,
             gather::Organisation {
                 ids,
@@ -322,7 +692,7 @@ impl Processor {
         substrate: &Substrate,
         coagulate: &Coagulate,
     ) -> Result<(), errors::CrystalizationError> {
-        let external_id = ExternalId::new(substrate.id, InnerId::new(product.id));
+        let external_id = ExternalId::from_substrate(substrate, &product.id);
         let unique_id = coagulate
             .get_unique_id_for_product_external_id(&external_id)
             .map_err(|id| id.to_error_not_found(substrate, "processing catalog product"))?;
@@ -340,7 +710,8 @@ impl Processor {
             .categorisation
             .map_or_else(BTreeSet::new, |c| c.categories.iter().map(|c| c.0.clone()).collect());
 
-        self.collector.update_product(
+        self.update_product(
+            substrate,
             &unique_id,
             gather::Product {
                 ids,
@@ -349,11 +720,14 @@ impl Processor {
                     product.description,
                     substrate.source.clone(),
                 ),
+                summaries: gather::MultiMap::new_empty(),
                 images,
                 categories: gather::MultiMap::new_many(
                     categories.into_iter().collect(),
                     substrate.source.clone(),
                 ),
+                google_product_category: None, //< Assigned later
+                ineligible_badges: BTreeSet::new(), //< Assigned later
                 availability: gather::Availability {
                     regions: Self::extract_regions(product.availability.as_ref()).map_err(
                         |source| errors::CrystalizationError::IsoCountry {
@@ -389,9 +763,14 @@ impl Processor {
                     )
                 }),
                 media: BTreeSet::new(),
+                reports: gather::MultiMap::new_empty(),
                 follows,
                 followed_by,
+                sitelinks: 0, //< Assigned later
+                updated: gather::FieldTimestamps::default(), //< Assigned later
                 certifications: gather::Certifications::default(),
+                nutri_score: None,
+                price_levels: gather::PriceLevels::default(),
                 transpaer: gather::TranspaerProductData::default(), //< Calculated later
             },
         )?;
@@ -405,7 +784,7 @@ impl Processor {
         substrate: &Substrate,
         coagulate: &Coagulate,
     ) -> Result<(), errors::CrystalizationError> {
-        let external_id = ExternalId::new(substrate.id, InnerId::new(product.id));
+        let external_id = ExternalId::from_substrate(substrate, &product.id);
         let unique_id = coagulate
             .get_unique_id_for_product_external_id(&external_id)
             .map_err(|id| id.to_error_not_found(substrate, "processing producer product"))?;
@@ -421,17 +800,21 @@ impl Processor {
             self.extract_manufacturer_ids(product.origins.as_ref(), substrate, coagulate);
         let categories = product.categorisation.categories.iter().map(|c| c.0.clone()).collect();
 
-        self.collector.update_product(
+        self.update_product(
+            substrate,
             &unique_id,
             gather::Product {
                 ids,
                 names: gather::MultiMap::new_many(product.names, substrate.source.clone()),
                 descriptions: gather::MultiMap::new_empty(),
+                summaries: gather::MultiMap::new_empty(),
                 images,
                 categories: gather::MultiMap::new_many(
                     categories,
                     substrate.source.clone(),
                 ),
+                google_product_category: None, //< Assigned later
+                ineligible_badges: BTreeSet::new(), //< Assigned later
                 availability: gather::Availability {
                     regions: Self::extract_regions(product.availability.as_ref()).map_err(
                         |source| errors::CrystalizationError::IsoCountry {
@@ -467,9 +850,16 @@ impl Processor {
                     )
                 }),
                 media: BTreeSet::new(),
+                reports: gather::MultiMap::new_empty(),
                 follows,
                 followed_by,
+                sitelinks: 0, //< Assigned later
+                updated: gather::FieldTimestamps::default(), //< Assigned later
                 certifications: gather::Certifications::default(),
+                nutri_score: None,
+                // The producer schema has no field for price levels. They come only from the
+                // `price_level` reviewer substrates.
+                price_levels: gather::PriceLevels::default(),
                 transpaer: gather::TranspaerProductData::default(), //< Calculated later
             },
         )?;
@@ -490,7 +880,7 @@ impl Processor {
             tco: Self::extract_tco_cert(&producer, substrate),
         };
 
-        let external_id = ExternalId::new(substrate.id, InnerId::new(producer.id.clone()));
+        let external_id = ExternalId::from_substrate(substrate, &producer.id);
         let unique_id = coagulate
             .get_unique_id_for_producer_external_id(&external_id)
             .map_err(|id| id.to_error_not_found(substrate, "processing review producer"))?;
@@ -501,7 +891,8 @@ impl Processor {
             .map(|image| gather::Image { image, source: substrate.source.clone() })
             .collect();
 
-        self.collector.update_organisation(
+        self.update_organisation(
+            substrate,
             &unique_id,
             gather::Organisation {
                 ids,
@@ -539,10 +930,18 @@ impl Processor {
         substrate: &Substrate,
         coagulate: &Coagulate,
     ) -> Result<(), errors::CrystalizationError> {
-        let external_id = ExternalId::new(substrate.id, InnerId::new(product.id));
+        let external_id = ExternalId::from_substrate(substrate, &product.id);
         let unique_id = coagulate
             .get_unique_id_for_product_external_id(&external_id)
             .map_err(|id| id.to_error_not_found(substrate, "processing review product"))?;
+        let nutri_score = Self::extract_nutri_score(&product, substrate);
+        let regions = Self::extract_regions(product.availability.as_ref()).map_err(|source| {
+            errors::CrystalizationError::IsoCountry {
+                source,
+                when: "processing review product regions",
+            }
+        })?;
+        let price_levels = Self::extract_price_levels(&product, &regions, substrate);
         let ids = self.convert_product_ids(product.ids, substrate);
         let images = product
             .images
@@ -557,24 +956,23 @@ impl Processor {
             .categorisation
             .map_or_else(BTreeSet::new, |c| c.categories.iter().map(|c| c.0.clone()).collect());
 
-        self.collector.update_product(
+        self.update_product(
+            substrate,
             &unique_id,
             gather::Product {
                 ids,
                 names: gather::MultiMap::new_many(product.names, substrate.source.clone()),
                 descriptions: gather::MultiMap::new_empty(),
+                summaries: gather::MultiMap::new_or_empty(product.summary, substrate.source.clone()),
                 images,
                 categories: gather::MultiMap::new_many(
                     categories.into_iter().collect(),
                     substrate.source.clone(),
                 ),
+                google_product_category: None, //< Assigned later
+                ineligible_badges: BTreeSet::new(), //< Assigned later
                 availability: gather::Availability {
-                    regions: Self::extract_regions(product.availability.as_ref()).map_err(
-                        |source| errors::CrystalizationError::IsoCountry {
-                            source,
-                            when: "processing review product regions",
-                        },
-                    )?,
+                    regions,
                     sources: btreeset! { substrate.source.clone() },
                 },
                 origins: Self::extract_product_origins(
@@ -606,9 +1004,14 @@ impl Processor {
                     product.reports.as_ref(),
                     substrate.source.clone(),
                 ),
+                reports: Self::extract_reports(product.reports.as_ref(), substrate.source.clone()),
                 follows,
                 followed_by,
+                sitelinks: 0, //< Assigned later
+                updated: gather::FieldTimestamps::default(), //< Assigned later
                 certifications: gather::Certifications::default(), //< Assigned later from producers
+                nutri_score,
+                price_levels,
                 transpaer: gather::TranspaerProductData::default(), //< Calculated later
             },
         )?;
@@ -626,7 +1029,7 @@ impl Processor {
         let mut manufacturer_ids = BTreeSet::new();
         if let Some(origins) = &origins {
             for producer_id in &origins.producer_ids {
-                let external_id = ExternalId::new(substrate.id, InnerId::new(producer_id.clone()));
+                let external_id = ExternalId::from_substrate(substrate, producer_id);
                 match coagulate.get_unique_id_for_producer_external_id(&external_id) {
                     Ok(unique_id) => {
                         manufacturer_ids.insert(unique_id);
@@ -733,11 +1136,13 @@ impl Processor {
             let mut mentions = Vec::new();
             for report in &reports.0 {
                 if let Some(url) = &report.url
-                    && utils::extract_domain_from_url(url) == "youtube.com"
+                    && Self::is_media_mention(url, &source)
                 {
+                    let (link, timestamp) = utils::split_timestamp(url);
                     mentions.push(gather::Mention {
                         title: report.title.as_ref().unwrap_or(url).clone(),
-                        link: url.clone(),
+                        link: link.to_owned(),
+                        timestamp,
                     });
                 }
             }
@@ -751,6 +1156,31 @@ impl Processor {
         }
     }
 
+    /// Extracts the links to the reports which are not mentions in media.
+    fn extract_reports(
+        reports: Option<&schema::Reports>,
+        source: gather::Source,
+    ) -> gather::MultiMap<gather::ReportLink, gather::Source> {
+        let mut result = gather::MultiMap::new_empty();
+        if let Some(reports) = reports {
+            for report in &reports.0 {
+                if let Some(url) = &report.url
+                    && !Self::is_media_mention(url, &source)
+                {
+                    let link =
+                        gather::ReportLink { title: report.title.clone(), link: url.clone() };
+                    result.insert(link, source.clone());
+                }
+            }
+        }
+        result
+    }
+
+    /// Checks if the report is a mention in media (e.g. a podcast episode or a video).
+    fn is_media_mention(url: &str, source: &gather::Source) -> bool {
+        source.is_podcasts() || utils::extract_domain_from_url(url) == "youtube.com"
+    }
+
     fn convert_inner_ids(
         &mut self,
         input: &[String],
@@ -759,7 +1189,7 @@ impl Processor {
     ) -> BTreeSet<gather::ProductId> {
         let mut result = BTreeSet::new();
         for product_id in input {
-            let external_id = ExternalId::new(substrate.id, InnerId::new(product_id.clone()));
+            let external_id = ExternalId::from_substrate(substrate, product_id);
             match coagulate.get_unique_id_for_product_external_id(&external_id) {
                 Ok(unique_id) => {
                     result.insert(unique_id);
@@ -788,6 +1218,7 @@ impl Processor {
                 .first()
                 .and_then(|report| report.url.clone())
                 .unwrap_or_default(),
+            inherited: false,
         })
     }
 
@@ -808,13 +1239,53 @@ impl Processor {
         }
 
         match &producer.review {
+            Some(schema::Review::ScoreReview(review)) => Some(gather::FtiCert {
+                score: review.value,
+                scale: gather::FtiCert::SCALE,
+                inherited: false,
+            }),
+            _ => None,
+        }
+    }
+
+    fn extract_nutri_score(
+        product: &schema::ReviewProduct,
+        substrate: &Substrate,
+    ) -> Option<gather::NutriScore> {
+        if !substrate.source.is_nutri_score() {
+            return None;
+        }
+
+        match &product.review {
             Some(schema::Review::ScoreReview(review)) => {
-                Some(gather::FtiCert { score: review.value })
+                gather::NutriScore::from_value(review.value)
             }
             _ => None,
         }
     }
 
+    /// Extracts price tiers from a price level substrate.
+    ///
+    /// The tier applies to the regions where the reviewed product is available.
+    fn extract_price_levels(
+        product: &schema::ReviewProduct,
+        regions: &gather::Regions,
+        substrate: &Substrate,
+    ) -> gather::PriceLevels {
+        if !substrate.source.is_price_level() {
+            return gather::PriceLevels::default();
+        }
+
+        match &product.review {
+            Some(schema::Review::ScoreReview(review)) => {
+                gather::PriceTier::from_value(review.value)
+                    .map(|tier| gather::PriceLevels::new(tier, regions))
+                    .unwrap_or_default()
+            }
+            _ => gather::PriceLevels::default(),
+        }
+    }
+
     fn extract_tco_cert(
         producer: &schema::ReviewProducer,
         substrate: &Substrate,
@@ -824,7 +1295,11 @@ impl Processor {
         }
 
         // TODO: which name to pick?
-        producer.names.first().cloned().map(|brand_name| gather::TcoCert { brand_name })
+        producer
+            .names
+            .first()
+            .cloned()
+            .map(|brand_name| gather::TcoCert { brand_name, inherited: false })
     }
 
     fn convert_product_ids(
@@ -883,7 +1358,7 @@ impl Processor {
                     Ok(vat) => {
                         vat_ids.insert(vat, substrate.source.clone());
                     }
-                    Err(_) => self.report.add_invalid_id(substrate.id, id),
+                    Err(error) => self.report.add_invalid_vat_id(substrate.id, &error),
                 }
             }
         }
@@ -911,68 +1386,254 @@ impl Processor {
     }
 }
 
+/// Product entry of a ranked category index.
+#[derive(Debug, Clone)]
+struct RankedProduct {
+    score: f64,
+    product_id: store::ProductId,
+
+    /// The first of the product manufacturers, used for deduplication.
+    producer_id: Option<store::OrganisationId>,
+}
+
 #[derive(Debug, derive_new::new)]
 pub struct Saver {
     store: DbStore,
+
+    /// Number of entries written to the database at once.
+    write_batch_size: usize,
+
+    /// Deduplication of products in the ranked category indices.
+    category_dedup: config::CategoryDedup,
+
+    /// Limit of the number of products listed in a category.
+    category_cap: config::CategoryCap,
+
+    /// Number of shards the product keyword indices are built in.
+    keyword_shards: usize,
+
+    /// Number of threads building the keyword index shards.
+    keyword_workers: usize,
+
+    /// Directory for the temporary files of the keyword index shards.
+    shard_path: std::path::PathBuf,
 }
 
 impl Saver {
+    /// Returns the shard of the keyword index the keyword belongs to.
+    ///
+    /// Hashing spreads the keywords evenly, while e.g. their first characters would crowd
+    /// a few shards.
+    fn keyword_shard(keyword: &str, num_shards: usize) -> usize {
+        use std::hash::BuildHasher;
+
+        let hasher = std::hash::BuildHasherDefault::<std::hash::DefaultHasher>::default();
+        let num_shards = u64::try_from(num_shards).unwrap_or(u64::MAX).max(1);
+        usize::try_from(hasher.hash_one(keyword) % num_shards).unwrap_or_default()
+    }
+
+    /// Builds a keyword index in shards by the hash of the keywords.
+    ///
+    /// The products are read once and their keywords are partitioned into one temporary file per
+    /// shard. Then each worker aggregates and writes one shard at a time, so only the keywords of
+    /// as many shards as there are workers are held in memory. The IDs are listed in the order of
+    /// the products bucket regardless of sharding.
+    fn store_sharded_keywords<K, I>(
+        &self,
+        name: &str,
+        products: &Bucket<gather::ProductId, gather::Product>,
+        bucket: &Bucket<K, Vec<store::ProductId>>,
+        extract: impl Fn(&gather::Product) -> I,
+        keyword: impl Fn(&K) -> &str,
+    ) -> Result<(), errors::CrystalizationError>
+    where
+        K: Ord + Serialize + DeserializeOwned + Sync,
+        I: IntoIterator<Item = K>,
+    {
+        let num_shards = self.keyword_shards.max(1);
+        let num_workers = self.keyword_workers.clamp(1, num_shards);
+        let dir = self.shard_path.join(name);
+        std::fs::create_dir_all(&dir).map_err(|e| CrystalizationError::Io(e, dir.clone()))?;
+        let paths: Vec<_> =
+            (0..num_shards).map(|shard| dir.join(format!("{shard}.jsonl"))).collect();
+
+        let result = Self::partition_keywords(products, &paths, extract, keyword)
+            .and_then(|()| self.build_keyword_shards(bucket, &paths, num_workers));
+        if let Err(err) = std::fs::remove_dir_all(&dir) {
+            log::warn!("Failed to remove the keyword shards `{}`: {err}", dir.display());
+        }
+        result
+    }
+
+    /// Writes the keywords of the products into the shard files in one pass over the products.
+    fn partition_keywords<K, I>(
+        products: &Bucket<gather::ProductId, gather::Product>,
+        paths: &[std::path::PathBuf],
+        extract: impl Fn(&gather::Product) -> I,
+        keyword: impl Fn(&K) -> &str,
+    ) -> Result<(), errors::CrystalizationError>
+    where
+        K: Serialize,
+        I: IntoIterator<Item = K>,
+    {
+        let mut writers = paths
+            .iter()
+            .map(|path| {
+                std::fs::File::create(path)
+                    .map(|file| {
+                        serde_jsonlines::JsonLinesWriter::new(std::io::BufWriter::new(file))
+                    })
+                    .map_err(|e| CrystalizationError::Io(e, path.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for item in products.iter() {
+            let (product_id, product) = item?;
+            for key in extract(&product) {
+                let shard = Self::keyword_shard(keyword(&key), paths.len());
+                writers[shard]
+                    .write(&(key, &product_id))
+                    .map_err(|e| CrystalizationError::Io(e, paths[shard].clone()))?;
+            }
+        }
+
+        for (writer, path) in writers.iter_mut().zip(paths) {
+            writer.flush().map_err(|e| CrystalizationError::Io(e, path.clone()))?;
+        }
+        Ok(())
+    }
+
+    /// Aggregates the shard files and writes them into the bucket, one shard per worker at a time.
+    fn build_keyword_shards<K>(
+        &self,
+        bucket: &Bucket<K, Vec<store::ProductId>>,
+        paths: &[std::path::PathBuf],
+        num_workers: usize,
+    ) -> Result<(), errors::CrystalizationError>
+    where
+        K: Ord + Serialize + DeserializeOwned + Sync,
+    {
+        let build_shard = |path: &std::path::PathBuf| -> Result<(), errors::CrystalizationError> {
+            let mut data = BTreeMap::<K, Vec<store::ProductId>>::new();
+            let entries = serde_jsonlines::json_lines::<(K, store::ProductId), _>(path)
+                .map_err(|e| CrystalizationError::Io(e, path.clone()))?;
+            for entry in entries {
+                let (key, product_id) =
+                    entry.map_err(|e| CrystalizationError::Io(e, path.clone()))?;
+                data.entry(key).or_default().push(product_id);
+            }
+
+            let mut writer = bucket.batch_writer(self.write_batch_size);
+            for (key, ids) in data {
+                writer.insert(&key, &ids)?;
+            }
+            writer.finish()?;
+            Ok(())
+        };
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..num_workers)
+                .map(|worker| {
+                    let build_shard = &build_shard;
+                    scope.spawn(move || {
+                        paths.iter().skip(worker).step_by(num_workers).try_for_each(build_shard)
+                    })
+                })
+                .collect();
+            handles.into_iter().try_for_each(|handle| {
+                handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+        })
+    }
+
     /// Extracts keywords for DB text search from passed texts.
     fn extract_keywords(texts: &gather::MultiMap<String, gather::Source>) -> BTreeSet<String> {
         let mut result = BTreeSet::new();
         for text in texts.keys() {
-            for word in text.split_whitespace() {
-                result.insert(word.to_lowercase());
-            }
+            result.extend(utils::tokenize(&text));
         }
-        result.remove("");
         result
     }
 
     fn finalize<'a>(
         organisations: &'a mut Bucket<'a, gather::OrganisationId, gather::Organisation>,
         products: &Bucket<gather::ProductId, gather::Product>,
-    ) -> Result<(), CrystalizationError> {
+        scorer: &dyn Scorer,
+        manual_matches: &HashMap<gather::WikiId, f64>,
+    ) -> Result<MatchingReport, CrystalizationError> {
         log::info!("Finalizing products");
 
+        let mut report = MatchingReport::default();
+
         // Assign
         //  - certifications to products
+        //  - facility-derived production regions to products
         //  - product to organisations
         log::info!(" -> assigning certifications");
         for product in products.clone().iter_autosave() {
             let mut product = product?;
+            let mut missing = Vec::new();
+            product.value.google_product_category =
+                Self::find_google_product_category(&product.value);
+            let product_categories = product.value.all_categories(categories::SEPARATOR);
+            product.value.ineligible_badges = gather::Badge::find_ineligible(&product_categories);
             for manufacturer_id in &product.value.manufacturers.keys() {
                 if let Some(mut organisation) = organisations.edit(manufacturer_id.clone())? {
                     product
                         .value
                         .certifications
                         .inherit(&organisation.value.certifications.clone());
+                    for region in
+                        organisation.value.origins.keys_from_source(&gather::Source::OpenSupplyHub)
+                    {
+                        product.value.origins.insert(region, gather::Source::OpenSupplyHub);
+                    }
                     organisation.value.products.insert(product.key.clone());
+                } else {
+                    missing.push(manufacturer_id.clone());
                 }
-
-                // TODO: There are many organisations that cannot be found.
-                //       It seems like all of them are bugs in Wikidata.
-                //       Make sure all organisations are found.
+            }
+            if !missing.is_empty() {
+                report.add_missing_manufacturers(&product.key, &product.value, &missing);
             }
         }
 
         // Calculate product Transpaer scores and significances
-        log::info!(" -> calculating Transpaer scores and significances for organisations");
+        log::info!(" -> calculating Transpaer significances, matches and ranks for organisations");
         for organisation in organisations.clone().iter_autosave() {
             let mut organisation = organisation?;
             organisation.value.transpaer.significance =
                 transpaer::calculate_organisation_significances(&organisation.value);
+            organisation.value.transpaer.matches =
+                transpaer::calculate_organisation_matches(&organisation.value, manual_matches);
+            organisation.value.transpaer.rank =
+                transpaer::calculate_organisation_rank(&organisation.value);
         }
 
         log::info!(" -> calculating Transpaer scores and significances for proucts");
         for product in products.clone().iter_autosave() {
             let mut product = product?;
-            product.value.transpaer.score = crate::score::calculate(&product.value);
+            product.value.transpaer.score = scorer.calculate(&product.value);
             product.value.transpaer.significance =
                 transpaer::calculate_product_significances(&product.value);
         }
 
-        Ok(())
+        Ok(report)
+    }
+
+    /// Finds the Google Product Category closest to the most specific category of the product.
+    fn find_google_product_category(product: &gather::Product) -> Option<u32> {
+        product
+            .categories
+            .keys()
+            .into_iter()
+            .filter_map(|name| {
+                let depth = name.matches(categories::SEPARATOR).count();
+                let info = Category::new(name).ok()?.get_info()?;
+                info.google_product_category.map(|id| (depth, id))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, id)| id)
     }
 
     fn convert_category_status(
@@ -1017,11 +1678,13 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_organisation_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
         for iter in organisations.iter() {
             let (id, org) = iter?;
-            bucket.insert(&id, &org.store())?;
+            writer.insert(&id, &org.store())?;
         }
 
+        writer.finish()?;
         Ok(())
     }
 
@@ -1046,11 +1709,12 @@ impl Saver {
         }
 
         let bucket = self.store.get_keyword_to_organisation_ids_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
         for (keyword, ids) in data {
-            bucket.insert(&keyword, &ids)?;
+            writer.insert(&keyword, &ids)?;
         }
 
-        bucket.flush()?;
+        writer.finish()?;
         Ok(())
     }
 
@@ -1066,20 +1730,21 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_vat_id_to_organisation_id_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         let mut uniqueness_check = HashSet::new();
         for item in organisations.iter() {
             let (organisation_id, organisation) = item?;
             for vat_id in organisation.ids.vat_ids.keys() {
-                bucket.insert(&vat_id, &organisation_id)?;
+                writer.insert(&vat_id, &organisation_id)?;
                 uniqueness_check.insert(vat_id);
             }
         }
 
+        writer.finish()?;
+
         // Sanity check: all keys should be unique
         Self::uniqueness_check(&uniqueness_check, &bucket, COMMENT)?;
-
-        bucket.flush()?;
         Ok(())
     }
 
@@ -1095,20 +1760,21 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_wiki_id_to_organisation_id_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         let mut uniqueness_check = HashSet::new();
         for item in organisations.iter() {
             let (organisation_id, organisation) = item?;
             for wiki_id in organisation.ids.wiki.keys() {
-                bucket.insert(&wiki_id, &organisation_id)?;
+                writer.insert(&wiki_id, &organisation_id)?;
                 uniqueness_check.insert(wiki_id);
             }
         }
 
+        writer.finish()?;
+
         // Sanity check: all keys should be unique
         Self::uniqueness_check(&uniqueness_check, &bucket, COMMENT)?;
-
-        bucket.flush()?;
         Ok(())
     }
 
@@ -1124,20 +1790,21 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_www_domain_to_organisation_id_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         let mut uniqueness_check = HashSet::new();
         for item in organisations.iter() {
             let (organisation_id, organisation) = item?;
             for domain in organisation.ids.domains.keys() {
-                bucket.insert(&domain, &organisation_id)?;
+                writer.insert(&domain, &organisation_id)?;
                 uniqueness_check.insert(domain);
             }
         }
 
+        writer.finish()?;
+
         // Sanity check: all keys should be unique
         Self::uniqueness_check(&uniqueness_check, &bucket, COMMENT)?;
-
-        bucket.flush()?;
         Ok(())
     }
 
@@ -1150,16 +1817,20 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_product_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
         for item in products.iter() {
             let (product_id, product) = item?;
             let product = product.store();
-            bucket.insert(&product_id, &product)?;
+            writer.insert(&product_id, &product)?;
+        }
 
-            // Make sure that the DB can be deserialized
-            assert!(bucket.get(&product_id).is_ok(), "DB integrity: {product_id:?} => {product:?}");
+        writer.finish()?;
+
+        // Make sure that the DB can be deserialized
+        for item in bucket.iter() {
+            assert!(item.is_ok(), "DB integrity: {:?}", item.err());
         }
 
-        bucket.flush()?;
         Ok(())
     }
 
@@ -1173,28 +1844,19 @@ impl Saver {
         const COMMENT: &str = "keywords => [product.id]";
         log::info!(" -> `{COMMENT}`");
 
-        let mut data = BTreeMap::<String, Vec<store::ProductId>>::new();
-        for item in products.iter() {
-            let (product_id, product) = item?;
-            for keyword in Self::extract_keywords(&product.names) {
-                data.entry(keyword)
-                    .and_modify(|ids| ids.push(product_id.clone()))
-                    .or_insert_with(|| vec![product_id.clone()]);
-            }
-        }
-
-        let bucket = self.store.get_keyword_to_product_ids_bucket()?;
-        for (keyword, ids) in data {
-            bucket.insert(&keyword, &ids)?;
-        }
-
-        bucket.flush()?;
-        Ok(())
+        self.store_sharded_keywords(
+            "keywords",
+            products,
+            &self.store.get_keyword_to_product_ids_bucket()?,
+            |product| Self::extract_keywords(&product.names),
+            String::as_str,
+        )
     }
 
     /// Stores EAN data.
     ///
     /// This data is needed to implement an efficient EAN search index.
+    /// GTINs having an EAN-13 equivalent are indexed as well.
     fn store_product_eans(
         &self,
         products: &mut Bucket<gather::ProductId, gather::Product>,
@@ -1204,26 +1866,30 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_ean_to_product_id_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         let mut uniqueness_check = HashSet::new();
         for item in products.iter() {
             let (product_id, product) = item?;
-            for ean in product.ids.eans.keys() {
-                bucket.insert(&ean, &product_id)?;
+            let gtins = product.ids.gtins.keys();
+            let gtins = gtins.iter().filter_map(gather::Gtin::to_ean);
+            for ean in product.ids.eans.keys().into_iter().chain(gtins) {
+                writer.insert(&ean, &product_id)?;
                 uniqueness_check.insert(ean);
             }
         }
 
+        writer.finish()?;
+
         // Sanity check: all keys should be unique
         Self::uniqueness_check(&uniqueness_check, &bucket, COMMENT)?;
-
-        bucket.flush()?;
         Ok(())
     }
 
     /// Stores GTIN data.
     ///
     /// This data is needed to implement an efficient GTIN search index.
+    /// EANs are indexed as well in their zero-padded GTIN-14 form.
     fn store_product_gtins(
         &self,
         products: &mut Bucket<gather::ProductId, gather::Product>,
@@ -1233,20 +1899,23 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_gtin_to_product_id_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         let mut uniqueness_check = HashSet::new();
         for item in products.iter() {
             let (product_id, product) = item?;
-            for gtin in product.ids.gtins.keys() {
-                bucket.insert(&gtin, &product_id)?;
+            let eans = product.ids.eans.keys();
+            let eans = eans.iter().map(gather::Ean::to_gtin);
+            for gtin in product.ids.gtins.keys().into_iter().chain(eans) {
+                writer.insert(&gtin, &product_id)?;
                 uniqueness_check.insert(gtin);
             }
         }
 
+        writer.finish()?;
+
         // Sanity check: all keys should be unique
         Self::uniqueness_check(&uniqueness_check, &bucket, COMMENT)?;
-
-        bucket.flush()?;
         Ok(())
     }
 
@@ -1263,20 +1932,21 @@ impl Saver {
         log::info!(" -> `{COMMENT}`");
 
         let bucket = self.store.get_wiki_id_to_product_id_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         let mut uniqueness_check = HashSet::new();
         for item in products.iter() {
             let (product_id, product) = item?;
             for wiki_id in product.ids.wiki.keys() {
-                bucket.insert(&wiki_id, &product_id)?;
+                writer.insert(&wiki_id, &product_id)?;
                 uniqueness_check.insert(wiki_id);
             }
         }
 
+        writer.finish()?;
+
         // Sanity check: all keys should be unique
         Self::uniqueness_check(&uniqueness_check, &bucket, COMMENT)?;
-
-        bucket.flush()?;
         Ok(())
     }
 
@@ -1292,59 +1962,174 @@ impl Saver {
 
         log::info!(" -> `{COMMENT}`");
 
-        let mut data = BTreeMap::<String, Vec<store::ProductId>>::new();
+        let mut data = BTreeMap::<String, Vec<RankedProduct>>::new();
+        let mut quantities = HashMap::<store::ProductId, store::ProductQuantity>::new();
         for item in products.iter() {
             let (product_id, product) = item?;
+            if let Some(quantity) = product.quantity() {
+                quantities.insert(product_id.clone(), quantity);
+            }
+            let ranked = RankedProduct {
+                score: product.certifications.score(),
+                producer_id: product.manufacturers.iter().next().map(|(id, _)| id.clone()),
+                product_id,
+            };
             for category in product.all_categories(categories::SEPARATOR) {
-                data.entry(category.clone())
-                    .and_modify(|ids| ids.push(product_id.clone()))
-                    .or_insert_with(|| vec![product_id.clone()]);
+                data.entry(category.clone()).or_default().push(ranked.clone());
             }
         }
 
         let bucket = self.store.get_categories_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
 
         #[allow(clippy::unwrap_used)]
         let info = Category::new(String::new())
             .expect("root category must exist")
             .get_info()
             .expect("root category must exist");
-        bucket.insert(
+        writer.insert(
             &String::new(),
             &store::Category {
                 status: store::CategoryStatus::Broad,
                 subcategories: info.subcategories,
+                label: info.title,
                 products: None,
+                quantity: None,
             },
         )?;
 
-        for (category_name, ids) in data {
+        let mut oversized = Vec::new();
+        for (category_name, ranked) in data {
+            let ids = Self::rank_category_products(ranked, self.category_dedup);
+
             #[allow(clippy::unwrap_used)]
             let info = Category::new(category_name.clone())
                 .expect("all categories should be valid at this point")
                 .get_info()
                 .expect("all categories should be valid at this point");
 
-            let product_ids = if info.status.are_products_comparable() { Some(ids) } else { None };
+            let ids = if info.status.are_products_comparable() {
+                if self.category_cap.max_products.is_some_and(|max| ids.len() > max) {
+                    oversized.push((category_name.clone(), ids.len()));
+                }
+                Self::cap_category_products(ids, self.category_cap)
+            } else {
+                None
+            };
+
+            let quantity = ids.as_ref().and_then(|ids| {
+                store::ProductQuantity::median(
+                    ids.iter().filter_map(|id| quantities.get(id).copied()),
+                )
+            });
 
             let category = store::Category {
                 status: Self::convert_category_status(info.status),
                 subcategories: info.subcategories,
-                products: product_ids,
+                label: info.title,
+                products: ids,
+                quantity,
             };
 
-            bucket.insert(&category_name, &category)?;
+            writer.insert(&category_name, &category)?;
         }
 
-        bucket.flush()?;
+        writer.finish()?;
+
+        if let Some(max_products) = self.category_cap.max_products
+            && !oversized.is_empty()
+        {
+            log::warn!(
+                "    {} categories have more than {} products ({:?}):",
+                oversized.len(),
+                max_products,
+                self.category_cap.overflow,
+            );
+            for (category_name, size) in oversized {
+                log::warn!("     - `{category_name}`: {size}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the limit of the number of products listed in a category.
+    ///
+    /// Returns `None` if the products of the category should not be listed at all.
+    fn cap_category_products(
+        mut ids: Vec<store::ProductId>,
+        cap: config::CategoryCap,
+    ) -> Option<Vec<store::ProductId>> {
+        let Some(max_products) = cap.max_products.filter(|max| ids.len() > *max) else {
+            return Some(ids);
+        };
+        match cap.overflow {
+            config::CategoryOverflow::Drop => None,
+            config::CategoryOverflow::Truncate => {
+                // The products are already sorted from the best scored one.
+                ids.truncate(max_products);
+                Some(ids)
+            }
+        }
+    }
+
+    /// Sorts the products of a category from the best scored one and deduplicates them.
+    ///
+    /// Sorting here lets the backend list the best products without loading all of them.
+    // TODO: Deduplicate also by variant group once products are grouped into variants.
+    fn rank_category_products(
+        mut ranked: Vec<RankedProduct>,
+        dedup: config::CategoryDedup,
+    ) -> Vec<store::ProductId> {
+        ranked.sort_by(|r1, r2| {
+            r2.score.total_cmp(&r1.score).then_with(|| r1.product_id.cmp(&r2.product_id))
+        });
+        match dedup {
+            config::CategoryDedup::None => ranked.into_iter().map(|r| r.product_id).collect(),
+            config::CategoryDedup::Producer => {
+                let mut seen_producers = HashSet::new();
+                ranked
+                    .into_iter()
+                    .filter(|r| {
+                        r.producer_id.as_ref().is_none_or(|id| seen_producers.insert(id.clone()))
+                    })
+                    .map(|r| r.product_id)
+                    .collect()
+            }
+        }
+    }
+
+    /// Stores the provenance of the substrate files the database was crystalized from.
+    fn store_substrate_metas(
+        &self,
+        metas: &BTreeMap<String, store::SubstrateMeta>,
+    ) -> Result<(), errors::CrystalizationError> {
+        const COMMENT: &str = "dataset.substrate => dataset.meta";
+        log::info!(" -> `{COMMENT}`");
+
+        let bucket = self.store.get_substrate_meta_bucket()?;
+        let mut writer = bucket.batch_writer(self.write_batch_size);
+        for (name, meta) in metas {
+            writer.insert(name, meta)?;
+        }
+
+        writer.finish()?;
         Ok(())
     }
 
-    fn store_all(self, collector: &CrystalizationCollector) -> Result<(), errors::ProcessingError> {
+    fn store_all(
+        self,
+        collector: &CrystalizationCollector,
+        scorer: &dyn Scorer,
+        manual_matches: &HashMap<gather::WikiId, f64>,
+        substrate_metas: &BTreeMap<String, store::SubstrateMeta>,
+    ) -> Result<(), errors::ProcessingError> {
         Self::finalize(
             &mut collector.get_organisation_bucket()?,
             &collector.get_product_bucket()?,
-        )?;
+            scorer,
+            manual_matches,
+        )?
+        .report();
 
         self.store_organisation_keywords(&mut collector.get_organisation_bucket()?)?;
         self.store_organisation_vat_ids(&mut collector.get_organisation_bucket()?)?;
@@ -1358,6 +2143,7 @@ impl Saver {
         self.store_product_wiki_ids(&mut collector.get_product_bucket()?)?;
         self.store_categories(&mut collector.get_product_bucket()?)?;
         self.store_products(&mut collector.get_product_bucket()?)?;
+        self.store_substrate_metas(substrate_metas)?;
 
         log::info!("Crystalisation finished");
 
@@ -1369,6 +2155,13 @@ pub struct Crystalizer;
 
 impl Crystalizer {
     pub fn run(config: &config::CrystalizationConfig) -> Result<(), errors::ProcessingError> {
+        let scorer = ScorerConfig::load(config.scorer.as_deref())?.build();
+        log::info!("Scoring products with the `{}` scorer", scorer.id());
+        let manual_matches = Self::load_matches(config)?;
+        let deny_list = Self::load_deny_list(config)?;
+        let shopping_availability = Self::load_shopping_availability(config)?;
+        let sitelinks = Self::load_sitelinks(config)?;
+
         futures::executor::block_on(async {
             let (substrates, substrate_report) =
                 Substrates::prepare(&config.substrate.substrate_path)?;
@@ -1378,11 +2171,336 @@ impl Crystalizer {
             let (collector, crystalizer_report) =
                 Processor::new(&config.runtime)?.process(&substrates, &coagulate)?;
             crystalizer_report.report(&substrates);
+            if let Some(deny_list) = &deny_list {
+                collector.deny(deny_list)?.report();
+            }
+            if let Some(availability) = &shopping_availability {
+                collector.check_shopping(availability)?.report();
+            }
+            if let Some(sitelinks) = &sitelinks {
+                let num_updated = collector.assign_sitelinks(sitelinks)?;
+                log::info!("Assigned sitelink counts to {num_updated} products");
+            }
             Summary::create(&collector)?.report();
 
-            let store = DbStore::new(&config.crystal)?;
-            Saver::new(store).store_all(&collector)?;
+            let store = DbStore::with_engine(&config.crystal, config.engine)?;
+            Saver::new(
+                store,
+                config.write_batch_size,
+                config.category_dedup,
+                config.category_cap,
+                config.keyword_shards,
+                config.keyword_workers,
+                config.runtime.join("keyword-shards"),
+            )
+            .store_all(
+                &collector,
+                scorer.as_ref(),
+                &manual_matches,
+                crystalizer_report.substrate_metas(),
+            )?;
             Ok(())
         })
     }
+
+    /// Loads accuracies of the accepted name matches, if configured.
+    fn load_matches(
+        config: &config::CrystalizationConfig,
+    ) -> Result<HashMap<gather::WikiId, f64>, errors::ProcessingError> {
+        let Some(path) = &config.matches else {
+            return Ok(HashMap::new());
+        };
+
+        log::info!("Loading name matches from `{}`", path.display());
+        Ok(parse_id_map(path)?
+            .iter()
+            .filter_map(|entry| {
+                entry.matched().map(|m| (gather::WikiId::from(m.wiki_id), m.match_accuracy))
+            })
+            .collect())
+    }
+
+    /// Loads the deny-list, if configured.
+    fn load_deny_list(
+        config: &config::CrystalizationConfig,
+    ) -> Result<Option<DenyList>, errors::ProcessingError> {
+        let Some(path) = &config.deny_list else {
+            return Ok(None);
+        };
+
+        log::info!("Loading deny-list from `{}`", path.display());
+        Ok(Some(deny_list::reader::parse(path)?))
+    }
+
+    /// Loads the results of the checks of the shopping links, if configured.
+    fn load_shopping_availability(
+        config: &config::CrystalizationConfig,
+    ) -> Result<Option<ShoppingAvailability>, errors::ProcessingError> {
+        let Some(path) = &config.shopping_availability else {
+            return Ok(None);
+        };
+
+        log::info!("Loading shopping availability from `{}`", path.display());
+        Ok(Some(ShoppingAvailability::read(path)?))
+    }
+
+    /// Loads the sitelink counts of the Wikidata products, if configured.
+    fn load_sitelinks(
+        config: &config::CrystalizationConfig,
+    ) -> Result<Option<HashMap<u64, u32>>, errors::ProcessingError> {
+        let Some(path) = &config.wikidata_sitelinks else {
+            return Ok(None);
+        };
+
+        log::info!("Loading Wikidata sitelink counts from `{}`", path.display());
+        Ok(Some(sitelinks::read(path)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use transpaer_models::{
+        buckets::{DbStore, StorageEngine},
+        store,
+    };
+    use transpaer_testgen::{ProducerBuilder, ProductBuilder, SubstrateBuilder, SubstrateDir};
+
+    use super::{Crystalizer, RankedProduct, Saver};
+    use crate::{coagulating::Coagulator, config};
+
+    fn p(id: u32) -> store::ProductId {
+        store::ProductId::from_value(id)
+    }
+
+    /// Runs coagulation and crystalization on the substrates and opens the resulting database.
+    fn run_pipeline(
+        substrates: impl IntoIterator<Item = SubstrateBuilder>,
+    ) -> (tempfile::TempDir, DbStore) {
+        let substrates = SubstrateDir::with(substrates).unwrap();
+        let target = tempfile::tempdir().unwrap();
+        let substrate = config::SubstrateConfig { substrate_path: substrates.path().to_owned() };
+        let coagulate = target.path().join("coagulate.yaml");
+
+        Coagulator::run(&config::CoagulationConfig {
+            substrate: substrate.clone(),
+            workspace: config::WorkspaceConfig {
+                root: target.path().join("coagulate-runtime"),
+                max_age_days: 1,
+            },
+            coagulate: coagulate.clone(),
+        })
+        .unwrap();
+
+        let crystalization = config::CrystalizationConfig {
+            substrate,
+            coagulate,
+            crystal: target.path().join("db"),
+            runtime: target.path().join("runtime"),
+            write_batch_size: 10,
+            engine: StorageEngine::Kv,
+            scorer: None,
+            matches: None,
+            category_dedup: config::CategoryDedup::default(),
+            category_cap: config::CategoryCap::default(),
+            keyword_shards: 3,
+            keyword_workers: 2,
+            deny_list: None,
+            shopping_availability: None,
+            wikidata_sitelinks: None,
+            watch: None,
+        };
+        Crystalizer::run(&crystalization).unwrap();
+
+        let store = DbStore::with_engine(&crystalization.crystal, StorageEngine::Kv).unwrap();
+        (target, store)
+    }
+
+    #[test]
+    fn category_ranking_dedup() {
+        let ranked = |score, product_id, producer_id: Option<u32>| RankedProduct {
+            score,
+            product_id: p(product_id),
+            producer_id: producer_id.map(store::OrganisationId::from_value),
+        };
+        let products = vec![
+            ranked(0.5, 1, Some(10)),
+            ranked(0.9, 2, Some(10)),
+            ranked(0.7, 3, Some(11)),
+            ranked(0.8, 4, None),
+            ranked(0.6, 5, None),
+        ];
+
+        assert_eq!(
+            Saver::rank_category_products(products.clone(), config::CategoryDedup::None),
+            vec![p(2), p(4), p(3), p(5), p(1)]
+        );
+        assert_eq!(
+            Saver::rank_category_products(products, config::CategoryDedup::Producer),
+            vec![p(2), p(4), p(3), p(5)]
+        );
+    }
+
+    #[test]
+    fn category_cap() {
+        use config::{CategoryCap, CategoryOverflow};
+
+        let ids = vec![p(2), p(4), p(3)];
+        let cap =
+            |max_products, overflow| CategoryCap { max_products: Some(max_products), overflow };
+
+        assert_eq!(
+            Saver::cap_category_products(ids.clone(), CategoryCap::default()),
+            Some(ids.clone())
+        );
+        assert_eq!(
+            Saver::cap_category_products(ids.clone(), cap(3, CategoryOverflow::Drop)),
+            Some(ids.clone())
+        );
+        assert_eq!(Saver::cap_category_products(ids.clone(), cap(2, CategoryOverflow::Drop)), None);
+        assert_eq!(
+            Saver::cap_category_products(ids, cap(2, CategoryOverflow::Truncate)),
+            Some(vec![p(2), p(4)])
+        );
+    }
+
+    #[test]
+    fn pipeline_merges_producers() {
+        let (_target, store) = run_pipeline([
+            SubstrateBuilder::cataloger("wikidata")
+                .producer(
+                    ProducerBuilder::new("1")
+                        .vat("NL004495445B01")
+                        .wiki("1")
+                        .name("Fairphone")
+                        .region("NLD"),
+                )
+                .product(
+                    ProductBuilder::new("10")
+                        .wiki("10")
+                        .gtin("4000000000001")
+                        .name("Fairphone 5")
+                        .producer("1"),
+                ),
+            SubstrateBuilder::reviewer("eu_ecolabel").producer(
+                ProducerBuilder::new("NL004495445B01").vat("NL004495445B01").name("Fairphone B.V."),
+            ),
+        ]);
+
+        let organisations = store.get_organisation_bucket().unwrap();
+        assert_eq!(organisations.len(), 1);
+        let (_, organisation) = organisations.iter().next().unwrap().unwrap();
+        assert!(organisation.certifications.eu_ecolabel.is_some());
+        assert_eq!(organisation.products.len(), 1);
+
+        let products = store.get_product_bucket().unwrap();
+        assert_eq!(products.len(), 1);
+        let (_, product) = products.iter().next().unwrap().unwrap();
+        assert_eq!(product.ids.gtins.len(), 1);
+        assert_eq!(product.manufacturers.len(), 1);
+    }
+
+    #[test]
+    fn pipeline_collects_product_reports() {
+        let (_target, store) = run_pipeline([
+            SubstrateBuilder::cataloger("wikidata")
+                .product(ProductBuilder::new("10").wiki("10").name("Fairphone 5")),
+            SubstrateBuilder::reviewer("tco").product(
+                ProductBuilder::new("10")
+                    .wiki("10")
+                    .name("Fairphone 5")
+                    .report("Certificate", "https://tcocertified.com/1")
+                    .report("Unboxing", "https://youtube.com/watch?v=1"),
+            ),
+        ]);
+
+        let products = store.get_product_bucket().unwrap();
+        let (_, product) = products.iter().next().unwrap().unwrap();
+        assert_eq!(
+            product.reports,
+            vec![store::SourcedReport {
+                title: Some("Certificate".to_owned()),
+                link: "https://tcocertified.com/1".to_owned(),
+                sources: vec![store::Source::Tco],
+            }]
+        );
+        assert_eq!(product.media.len(), 1);
+    }
+
+    #[test]
+    fn pipeline_collects_review_summaries() {
+        let (_target, store) = run_pipeline([
+            SubstrateBuilder::cataloger("wikidata").product(
+                ProductBuilder::new("10").wiki("10").name("Fairphone 5").description("Smartphone"),
+            ),
+            SubstrateBuilder::reviewer("tco").product(
+                ProductBuilder::new("10")
+                    .wiki("10")
+                    .name("Fairphone 5")
+                    .summary("Modular phone with replaceable battery"),
+            ),
+        ]);
+
+        let products = store.get_product_bucket().unwrap();
+        let (_, product) = products.iter().next().unwrap().unwrap();
+        assert_eq!(
+            product.summaries,
+            vec![store::Text {
+                text: "Modular phone with replaceable battery".to_owned(),
+                sources: vec![store::Source::Tco],
+            }]
+        );
+        assert_eq!(product.descriptions.len(), 1);
+    }
+
+    #[test]
+    fn pipeline_shards_keywords() {
+        let (_target, store) = run_pipeline([SubstrateBuilder::cataloger("wikidata")
+            .product(ProductBuilder::new("10").wiki("10").name("Fairphone 5"))
+            .product(ProductBuilder::new("11").wiki("11").name("Fairphone 6"))
+            .product(ProductBuilder::new("12").wiki("12").name("Shift 6mq"))]);
+
+        let keywords = store.get_keyword_to_product_ids_bucket().unwrap().gather().unwrap();
+        let mut names: Vec<_> = keywords.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["5", "6", "6mq", "fairphone", "shift"]);
+        assert_eq!(keywords["fairphone"].len(), 2);
+        assert_eq!(keywords["6"].len(), 1);
+        assert_eq!(keywords["shift"], keywords["6mq"]);
+    }
+
+    #[test]
+    fn pipeline_stores_substrate_metas() {
+        let (_target, store) = run_pipeline([SubstrateBuilder::cataloger("wikidata")
+            .producer(ProducerBuilder::new("1").wiki("1").name("Fairphone"))
+            .product(ProductBuilder::new("10").wiki("10").name("Fairphone 5"))
+            .product(ProductBuilder::new("11").wiki("11").name("Fairphone 6"))]);
+
+        let metas = store.get_substrate_meta_bucket().unwrap();
+        assert_eq!(metas.len(), 1);
+        let meta = metas.get(&"wikidata".to_owned()).unwrap().unwrap();
+        assert_eq!(meta.num_producers, 1);
+        assert_eq!(meta.num_products, 2);
+        assert!(meta.file_size > 0);
+        assert_eq!(meta.version.as_deref(), Some("0.0.0"));
+        assert_eq!(meta.crystalizer_version, env!("CARGO_PKG_VERSION"));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn pipeline_never_creates_entries(
+            catalog in transpaer_testgen::strategies::cataloger("wikidata"),
+            review in transpaer_testgen::strategies::reviewer("eu_ecolabel"),
+        ) {
+            let (_target, store) = run_pipeline([catalog.clone(), review.clone()]);
+
+            let num_producers = catalog.num_producers() + review.num_producers();
+            let num_products = catalog.num_products() + review.num_products();
+            prop_assert!(store.get_organisation_bucket().unwrap().len() <= num_producers);
+            prop_assert!(store.get_product_bucket().unwrap().len() <= num_products);
+        }
+    }
 }
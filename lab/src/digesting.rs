@@ -0,0 +1,171 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `digest` command.
+//!
+//! Comparing whole databases between runs is slow and the differences are hard to read. This
+//! command computes a digest of the contents of every bucket and stores them in the database, so
+//! that two runs can be compared bucket by bucket.
+
+use std::collections::BTreeMap;
+
+use transpaer_models::{buckets::DbStore, store};
+
+use crate::{config, errors};
+
+/// Differences between the bucket digests of two databases.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DigestDiff {
+    /// Buckets present in both databases with different contents.
+    pub changed: Vec<String>,
+
+    /// Buckets present only in the new database.
+    pub added: Vec<String>,
+
+    /// Buckets present only in the old database.
+    pub removed: Vec<String>,
+
+    /// Number of buckets with the same contents in both databases.
+    pub unchanged: usize,
+}
+
+impl DigestDiff {
+    /// Compares the digests of the old and the new database.
+    #[must_use]
+    pub fn new(
+        old: &BTreeMap<String, store::BucketDigest>,
+        new: &BTreeMap<String, store::BucketDigest>,
+    ) -> Self {
+        let mut diff = Self::default();
+        for (name, digest) in new {
+            match old.get(name) {
+                Some(old_digest) if old_digest == digest => diff.unchanged += 1,
+                Some(_) => diff.changed.push(name.clone()),
+                None => diff.added.push(name.clone()),
+            }
+        }
+        for name in old.keys() {
+            if !new.contains_key(name) {
+                diff.removed.push(name.clone());
+            }
+        }
+        diff
+    }
+
+    /// Checks if the contents of all the buckets are the same.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn report(
+        &self,
+        old: &BTreeMap<String, store::BucketDigest>,
+        new: &BTreeMap<String, store::BucketDigest>,
+    ) {
+        if self.is_empty() {
+            log::info!("All {} buckets are the same", self.unchanged);
+            return;
+        }
+
+        log::info!("Bucket differences ({} unchanged):", self.unchanged);
+        for name in &self.changed {
+            let entries = |digests: &BTreeMap<String, store::BucketDigest>| {
+                digests.get(name).map_or(0, |digest| digest.entries)
+            };
+            log::info!(" - changed `{name}`: {} -> {} entries", entries(old), entries(new));
+        }
+        for name in &self.added {
+            log::info!(" - added `{name}`");
+        }
+        for name in &self.removed {
+            log::info!(" - removed `{name}`");
+        }
+    }
+}
+
+pub struct DigestRunner;
+
+impl DigestRunner {
+    /// Reads the digests stored in the database or computes them if none were stored yet.
+    fn load_digests(
+        store: &DbStore,
+    ) -> Result<BTreeMap<String, store::BucketDigest>, errors::ProcessingError> {
+        let stored: BTreeMap<_, _> =
+            store.get_bucket_digest_bucket()?.gather()?.into_iter().collect();
+        if stored.is_empty() {
+            log::info!("No digests stored, computing them");
+            Ok(store.compute_digests()?)
+        } else {
+            Ok(stored)
+        }
+    }
+
+    /// Computes the digests and stores them in the database.
+    fn update_digests(
+        store: &DbStore,
+    ) -> Result<BTreeMap<String, store::BucketDigest>, errors::ProcessingError> {
+        let digests = store.compute_digests()?;
+        let bucket = store.get_bucket_digest_bucket()?;
+        for (name, digest) in &digests {
+            bucket.insert(name, digest)?;
+        }
+        bucket.flush()?;
+        Ok(digests)
+    }
+
+    pub fn run(config: &config::DigestConfig) -> Result<(), errors::ProcessingError> {
+        let store = DbStore::new(&config.crystal)?;
+        let digests = Self::update_digests(&store)?;
+        log::info!("Bucket digests:");
+        for (name, digest) in &digests {
+            log::info!(" - {: <50} {: >9} entries {}", name, digest.entries, digest.hash);
+        }
+
+        if let Some(against) = &config.against {
+            let other = DbStore::open_read_only(against)?;
+            let previous = Self::load_digests(&other)?;
+            DigestDiff::new(&previous, &digests).report(&previous, &digests);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transpaer_models::gather;
+
+    use super::*;
+
+    #[test]
+    fn digests_of_changed_buckets() {
+        let dir1 = tempfile::tempdir().unwrap();
+        let dir2 = tempfile::tempdir().unwrap();
+        let store1 = DbStore::new(dir1.path()).unwrap();
+        let store2 = DbStore::new(dir2.path()).unwrap();
+
+        let p1 = store::ProductId::from_value(1);
+        for store in [&store1, &store2] {
+            let products = store.get_product_bucket().unwrap();
+            products.insert(&p1, &gather::Product::default().store()).unwrap();
+            products.flush().unwrap();
+        }
+
+        let keywords = store2.get_keyword_to_product_ids_bucket().unwrap();
+        keywords.insert(&"phone".to_owned(), &vec![p1]).unwrap();
+        keywords.flush().unwrap();
+
+        let old = DigestRunner::update_digests(&store1).unwrap();
+        let new = DigestRunner::update_digests(&store2).unwrap();
+        let diff = DigestDiff::new(&old, &new);
+        assert_eq!(diff.changed, vec!["keyword => [product.id]".to_owned()]);
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.unchanged, old.len() - 1);
+
+        assert_eq!(DigestRunner::load_digests(&store1).unwrap(), old);
+        assert!(DigestDiff::new(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `fetch` command.
+//!
+//! Downloads the raw inputs described in the fetch descriptions file. Interrupted downloads are
+//! kept in a `.part` file next to the target and resumed on the next run.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+use transpaer_collecting::fetch_info::{FetchDescription, FetchInfo};
+
+use crate::{config, errors::FetchingError};
+
+const USER_AGENT: &str = "transpaer-lab";
+
+/// Extension appended to the target path while the download is incomplete.
+const PARTIAL_EXTENSION: &str = "part";
+
+/// Computes the SHA-256 checksum of the file in hex.
+fn sha256(path: &Path) -> Result<String, FetchingError> {
+    let mut file = std::fs::File::open(path).map_err(|e| FetchingError::Io(e, path.into()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| FetchingError::Io(e, path.into()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the path where the incomplete download is stored.
+fn partial_path(target: &Path) -> PathBuf {
+    let mut path = target.as_os_str().to_owned();
+    path.push(".");
+    path.push(PARTIAL_EXTENSION);
+    PathBuf::from(path)
+}
+
+pub struct FetchRunner;
+
+impl FetchRunner {
+    /// Downloads a single source into `target` resuming a previous partial download if present.
+    #[allow(clippy::cast_precision_loss)]
+    async fn download(
+        client: &reqwest::Client,
+        description: &FetchDescription,
+        target: &Path,
+    ) -> Result<(), FetchingError> {
+        let partial = partial_path(target);
+        let offset = std::fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+
+        let mut request = client
+            .get(&description.url)
+            .header(reqwest::header::ACCEPT, description.format.mime_type());
+        if let Some(auth) = &description.auth {
+            let token =
+                std::env::var(&auth.env).map_err(|_| FetchingError::MissingCredentials {
+                    name: description.name.clone(),
+                    env: auth.env.clone(),
+                })?;
+            request = request.header(reqwest::header::AUTHORIZATION, auth.header_value(&token));
+        }
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+        }
+
+        let mut resp = request.send().await?;
+        let (mut file, mut bytes) = match resp.status() {
+            reqwest::StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                println!(" Already downloaded");
+                std::fs::rename(&partial, target)
+                    .map_err(|e| FetchingError::Io(e, target.into()))?;
+                return Ok(());
+            }
+            reqwest::StatusCode::PARTIAL_CONTENT => {
+                println!(" Resuming from {offset}B");
+                let file = std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&partial)
+                    .map_err(|e| FetchingError::Io(e, partial.clone()))?;
+                (file, offset)
+            }
+            _ => {
+                resp = resp.error_for_status()?;
+                let file = std::fs::File::create(&partial)
+                    .map_err(|e| FetchingError::Io(e, partial.clone()))?;
+                (file, 0)
+            }
+        };
+
+        let content_length = resp.content_length().map(|length| length + bytes);
+        while let Some(chunk) = resp.chunk().await? {
+            bytes += chunk.len() as u64;
+            file.write_all(&chunk).map_err(|e| FetchingError::Io(e, partial.clone()))?;
+            if let Some(content_length) = &content_length {
+                print!(" Downloading: {:>6.2}%\r", 100.0 * bytes as f64 / *content_length as f64);
+            } else {
+                print!(" Downloading: {bytes}B\r");
+            }
+        }
+        println!();
+
+        file.flush().map_err(|e| FetchingError::Io(e, partial.clone()))?;
+        std::fs::rename(&partial, target).map_err(|e| FetchingError::Io(e, target.into()))?;
+        Ok(())
+    }
+
+    /// Runs the `fetch` command.
+    pub async fn run(config: &config::FetchConfig) -> Result<(), FetchingError> {
+        let descriptions = FetchDescription::read_all(&config.meta.fetch_path)?;
+        let client = reqwest::ClientBuilder::new().user_agent(USER_AGENT).build()?;
+
+        for description in descriptions {
+            if !config.sources.is_empty() && !config.sources.contains(&description.name) {
+                continue;
+            }
+
+            let target = config.origin.join(&description.target);
+            println!("Fetching `{}` into `{}`", description.name, target.display());
+            if target.exists()
+                && let Some(expected) = &description.sha256
+                && sha256(&target)? == *expected
+            {
+                println!(" Checksum matches, skipping");
+                continue;
+            }
+
+            Self::download(&client, &description, &target).await?;
+
+            let received = sha256(&target)?;
+            if let Some(expected) = &description.sha256
+                && received != *expected
+            {
+                std::fs::remove_file(&target).map_err(|e| FetchingError::Io(e, target.clone()))?;
+                return Err(FetchingError::ChecksumMismatch {
+                    path: target,
+                    expected: expected.clone(),
+                    received,
+                });
+            }
+
+            let mut info = FetchInfo::read(&config.meta.absorbents)?;
+            info.update_source(&description.name, received);
+            info.write(&config.meta.absorbents)?;
+        }
+
+        Ok(())
+    }
+}
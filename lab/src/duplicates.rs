@@ -0,0 +1,219 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `duplicates` command.
+//!
+//! Coagulation merges only entries sharing some hard ID, so near-duplicates without common IDs
+//! survive it. This command looks for products of the same producer with similar names and
+//! reports them for review by curators.
+//!
+//! TODO: Let coagulation merge the pairs confirmed by the curators.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use serde::Serialize;
+
+use transpaer_collecting::errors::{MapIo, MapSerde};
+use transpaer_models::{buckets::DbStore, store};
+
+use crate::{config, errors};
+
+/// Length of the character shingles the names are split into.
+const SHINGLE_LENGTH: usize = 3;
+
+/// Number of the `MinHash` bands used for finding candidate pairs.
+const NUM_BANDS: usize = 8;
+
+/// Number of `MinHash` values in one band.
+const BAND_SIZE: usize = 4;
+
+/// Splits the normalized name into overlapping character shingles.
+fn shingles(name: &str) -> HashSet<String> {
+    let normalized: Vec<char> = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+    if normalized.len() <= SHINGLE_LENGTH {
+        return std::iter::once(normalized.into_iter().collect()).collect();
+    }
+    normalized.windows(SHINGLE_LENGTH).map(|window| window.iter().collect()).collect()
+}
+
+/// Jaccard similarity of two shingle sets.
+#[allow(clippy::cast_precision_loss)]
+fn similarity(s1: &HashSet<String>, s2: &HashSet<String>) -> f64 {
+    let union = s1.union(s2).count();
+    if union == 0 { 0.0 } else { s1.intersection(s2).count() as f64 / union as f64 }
+}
+
+/// Computes the `MinHash` signature of the shingle set.
+fn signature(shingles: &HashSet<String>) -> [u64; NUM_BANDS * BAND_SIZE] {
+    std::array::from_fn(|seed| {
+        shingles
+            .iter()
+            .map(|shingle| {
+                let mut hasher = DefaultHasher::new();
+                (seed, shingle).hash(&mut hasher);
+                hasher.finish()
+            })
+            .min()
+            .unwrap_or(u64::MAX)
+    })
+}
+
+/// Product prepared for the comparison.
+struct Entry {
+    id: store::ProductId,
+    name: String,
+    shingles: HashSet<String>,
+    signature: [u64; NUM_BANDS * BAND_SIZE],
+}
+
+impl Entry {
+    fn new(id: store::ProductId, name: String) -> Self {
+        let shingles = shingles(&name);
+        let signature = signature(&shingles);
+        Self { id, name, shingles, signature }
+    }
+}
+
+/// Finds pairs of entries with similarity at least `threshold`.
+///
+/// Candidates are selected using locality-sensitive hashing so that not all pairs need to be
+/// compared. Returns indices of the entries in the pair and their similarity.
+fn find_similar(entries: &[Entry], threshold: f64) -> Vec<(usize, usize, f64)> {
+    let mut candidates = BTreeSet::new();
+    for band in 0..NUM_BANDS {
+        let mut buckets = HashMap::<&[u64], Vec<usize>>::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let key = &entry.signature[band * BAND_SIZE..(band + 1) * BAND_SIZE];
+            buckets.entry(key).or_default().push(index);
+        }
+        for bucket in buckets.values() {
+            for (i, first) in bucket.iter().enumerate() {
+                for second in &bucket[i + 1..] {
+                    candidates.insert((*first, *second));
+                }
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|(i1, i2)| {
+            let similarity = similarity(&entries[i1].shingles, &entries[i2].shingles);
+            (similarity >= threshold).then_some((i1, i2, similarity))
+        })
+        .collect()
+}
+
+/// Product in the report.
+#[derive(Serialize, Debug)]
+struct ReportedProduct {
+    /// ID assigned during coagulation.
+    id: store::ProductId,
+
+    /// Name used for the comparison.
+    name: String,
+
+    /// IDs which stay the same across dataset versions.
+    refs: Vec<store::ProductRef>,
+}
+
+/// Likely duplicate pair for the review.
+#[derive(Serialize, Debug)]
+struct DuplicateCandidate {
+    /// Shared producer.
+    producer: store::OrganisationId,
+
+    /// Similarity of the names (from 0.0 to 1.0).
+    similarity: f64,
+
+    /// The two products.
+    products: [ReportedProduct; 2],
+}
+
+pub struct DuplicatesRunner;
+
+impl DuplicatesRunner {
+    pub fn run(config: &config::DuplicatesConfig) -> Result<(), errors::ProcessingError> {
+        let store = DbStore::new(&config.crystal)?;
+
+        log::info!("Indexing product names");
+        let mut producers = BTreeMap::<store::OrganisationId, Vec<Entry>>::new();
+        let mut refs = HashMap::<store::ProductId, Vec<store::ProductRef>>::new();
+        for item in store.get_product_bucket()?.iter() {
+            let (product_id, product) = item?;
+            let Some(name) = product.names.first() else { continue };
+            for manufacturer in &product.manufacturers {
+                producers
+                    .entry(manufacturer.id.clone())
+                    .or_default()
+                    .push(Entry::new(product_id.clone(), name.text.clone()));
+            }
+            refs.insert(product_id, product.ids.to_refs());
+        }
+
+        log::info!("Comparing products of {} producers", producers.len());
+        let mut report = Vec::new();
+        for (producer, entries) in &producers {
+            for (i1, i2, similarity) in find_similar(entries, config.threshold) {
+                let reported = |entry: &Entry| ReportedProduct {
+                    id: entry.id.clone(),
+                    name: entry.name.clone(),
+                    refs: refs.get(&entry.id).cloned().unwrap_or_default(),
+                };
+                report.push(DuplicateCandidate {
+                    producer: producer.clone(),
+                    similarity,
+                    products: [reported(&entries[i1]), reported(&entries[i2])],
+                });
+            }
+        }
+        report.sort_by(|c1, c2| c2.similarity.total_cmp(&c1.similarity));
+
+        log::info!("Found {} likely duplicate pairs", report.len());
+        let contents = serde_yaml::to_string(&report).map_serde()?;
+        std::fs::write(&config.output, contents).map_with_path(&config.output)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_similarity() {
+        let s1 = shingles("Fairphone 4");
+        let s2 = shingles("FAIRPHONE-4");
+        let s3 = shingles("Fairphone 5");
+        let s4 = shingles("Oat milk");
+
+        assert!((similarity(&s1, &s2) - 1.0).abs() < f64::EPSILON);
+        assert!(similarity(&s1, &s3) > 0.7);
+        assert!(similarity(&s1, &s4) < 0.1);
+        assert_eq!(shingles("Go"), maplit::hashset! { "go".to_owned() });
+    }
+
+    #[test]
+    fn similar_pairs() {
+        let entries = vec![
+            Entry::new(store::ProductId::from_value(1), "Organic Oat Drink 1l".to_owned()),
+            Entry::new(store::ProductId::from_value(2), "Oat milk".to_owned()),
+            Entry::new(store::ProductId::from_value(3), "organic oat-drink 1L".to_owned()),
+        ];
+
+        let pairs: Vec<_> =
+            find_similar(&entries, 0.6).into_iter().map(|(i1, i2, _)| (i1, i2)).collect();
+        assert_eq!(pairs, vec![(0, 2)]);
+    }
+}
@@ -35,6 +35,28 @@ pub enum ConfigCheckError {
 
     #[error("Path '{0}' has no parent")]
     NoParent(PathBuf),
+
+    #[error("'{0}' is not a valid unique ID, use `--source` for IDs of substrate entries")]
+    InvalidUniqueId(String),
+
+    #[error("'{0}' is not a name of a command")]
+    UnknownCommand(String),
+
+    #[error("URL '{0}' is not supported, expected an HTTP(S) or S3 URL")]
+    UnsupportedUrl(String),
+
+    #[error("Base URL '{0}' is not supported, expected an HTTP(S) URL")]
+    InvalidBaseUrl(String),
+
+    #[error(
+        "Cache '{path}' was not extracted from the current dump (expected namespace '{expected}'), run the `extract` command first"
+    )]
+    CacheNamespace { path: PathBuf, expected: String },
+
+    #[error(
+        "Not enough free space in '{path}': about {required_mib} MiB required, {available_mib} MiB available"
+    )]
+    InsufficientSpace { path: PathBuf, required_mib: u64, available_mib: u64 },
 }
 
 /// Error related to validating the input data.
@@ -45,6 +67,39 @@ pub enum SourcesCheckError {
     RepeatedIds(std::collections::HashSet<WikiId>),
 }
 
+/// Error returned when an advisor data file contains invalid data.
+///
+/// Points to the offending place in the file as precisely as possible.
+#[derive(Debug)]
+pub struct AdvisorDataError {
+    /// Path to the file.
+    pub path: PathBuf,
+
+    /// Line of the offending entry, if known.
+    pub line: Option<usize>,
+
+    /// The offending key, if known.
+    pub key: Option<String>,
+
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl std::fmt::Display for AdvisorDataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "In file `{}`", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ", line {line}")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ", key `{key}`")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for AdvisorDataError {}
+
 /// Errors specific to the `absorb` command.
 #[derive(Error, Debug)]
 pub enum AbsorbingError {
@@ -58,6 +113,25 @@ pub enum AbsorbingError {
     CreateFile(std::io::Error, PathBuf),
 }
 
+/// Errors specific to the `fetch` command.
+#[derive(Error, Debug)]
+pub enum FetchingError {
+    #[error("HTTP query: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("IO or serde error: {0}")]
+    IoOrSerde(#[from] IoOrSerdeError),
+
+    #[error("IO error: {0} ({1:?})")]
+    Io(std::io::Error, PathBuf),
+
+    #[error("Source `{name}` requires a token in the `{env}` environment variable")]
+    MissingCredentials { name: String, env: String },
+
+    #[error("Checksum mismatch for `{path:?}`: expected {expected}, received {received}")]
+    ChecksumMismatch { path: PathBuf, expected: String, received: String },
+}
+
 /// Errors specific to the `condense` command.
 #[derive(Error, Debug)]
 pub enum CondensationError {
@@ -68,6 +142,32 @@ pub enum CondensationError {
     WriteSubstrate(#[from] transpaer_schema::errors::SaveError),
 }
 
+/// Errors specific to the `merge-substrates` command.
+#[derive(Error, Debug)]
+pub enum SubstrateMergingError {
+    #[error("IO error: {0} ({1:?})")]
+    Io(std::io::Error, PathBuf),
+
+    #[error("Reading substrate: {0}")]
+    ReadSubstrate(#[from] transpaer_schema::errors::ReadError),
+
+    #[error("Saving Substrate error: {0}")]
+    WriteSubstrate(#[from] transpaer_schema::errors::SaveError),
+
+    #[error("Condensation error: {0}")]
+    Condensation(#[from] CondensationError),
+}
+
+/// Errors related to the per-run temporary directories.
+#[derive(Error, Debug)]
+pub enum WorkspaceError {
+    #[error("IO error: {0} ({1:?})")]
+    Io(std::io::Error, PathBuf),
+
+    #[error("Another run of the command holds the lock `{0:?}`")]
+    Locked(PathBuf),
+}
+
 /// Errors specific to the crystalisation command.
 #[derive(Error, Debug)]
 pub enum CoagulationError {
@@ -94,6 +194,9 @@ pub enum CoagulationError {
 
     #[error("Bucket: {0}")]
     Bucket(#[from] BucketError),
+
+    #[error("Workspace: {0}")]
+    Workspace(#[from] WorkspaceError),
 }
 
 /// Errors specific to the crystalisation command.
@@ -114,6 +217,9 @@ pub enum CrystalizationError {
     #[error("Keys are not unique for: {comment} (only {unique} unique out of {all})")]
     NotUniqueKeys { comment: String, unique: usize, all: usize },
 
+    #[error("IO error: {0} ({1:?})")]
+    Io(std::io::Error, PathBuf),
+
     // TODO: Inline the variants
     #[error("Coagulation error: {0}")]
     Coagulation(#[from] CoagulationError),
@@ -130,6 +236,41 @@ pub enum SamplingError {
 
     #[error("Failed initialize the API client: {0}")]
     APiClientInit(#[from] transpaer_api::client::ClientInitError),
+
+    #[error("HTTP query: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("IO or serde error: {0}")]
+    IoOrSerde(#[from] IoOrSerdeError),
+
+    #[error("Entity `{0}` not found in the Wikidata response")]
+    EntityNotFound(String),
+}
+
+/// Errors specific to the `check-shopping` command.
+#[derive(Error, Debug)]
+pub enum ShoppingCheckError {
+    #[error("HTTP client: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("IO or serde error: {0}")]
+    IoOrSerde(#[from] IoOrSerdeError),
+}
+
+/// Errors specific to the watch mode of the `crystalize` command.
+#[derive(Error, Debug)]
+pub enum WatchingError {
+    #[error("File system watcher: {0}")]
+    Notify(#[from] notify::Error),
+
+    #[error("Notifying about the new database: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("The file system watcher stopped")]
+    WatcherStopped,
+
+    #[error("Rebuilding task: {0}")]
+    Join(#[from] tokio::task::JoinError),
 }
 
 // TODO: Ideally this type could be removed.
@@ -154,6 +295,9 @@ pub enum ProcessingError {
     #[error("In file `{1}`.\nYAML parsing error: {0}")]
     ReadYaml(serde_yaml::Error, PathBuf),
 
+    #[error("In file `{1}`.\nXML parsing error: {0}")]
+    ReadXml(quick_xml::Error, PathBuf),
+
     #[error("Reading Substrate error: {0}")]
     ReadSubstrate(#[from] transpaer_schema::errors::ReadError),
 
@@ -181,6 +325,9 @@ pub enum ProcessingError {
     #[error("Unknown compression method: {0:?}")]
     CompressionMethod(Option<String>),
 
+    #[error("Unknown feed format of `{0}`, expected a `.csv` or `.json` file")]
+    FeedFormat(PathBuf),
+
     #[error("Channel sending error: {0}")]
     Channel(#[from] async_channel::SendError<std::string::String>),
 
@@ -193,6 +340,15 @@ pub enum ProcessingError {
     #[error("Absorbing error: {0}")]
     Absorbing(#[from] AbsorbingError),
 
+    #[error("Fetching error: {0}")]
+    Fetching(#[from] FetchingError),
+
+    #[error("Advisor data: {0}")]
+    AdvisorData(#[from] AdvisorDataError),
+
+    #[error("Substrate merging error: {0}")]
+    SubstrateMerging(#[from] SubstrateMergingError),
+
     #[error("Crystalization error: {0}")]
     Condensation(#[from] CondensationError),
 
@@ -205,6 +361,12 @@ pub enum ProcessingError {
     #[error("Sampling error: {0}")]
     Sampling(#[from] SamplingError),
 
+    #[error("Watching error: {0}")]
+    Watching(#[from] WatchingError),
+
+    #[error("Shopping check error: {0}")]
+    ShoppingCheck(#[from] ShoppingCheckError),
+
     #[error("ID parsing: {0}")]
     IdParsing(#[from] transpaer_models::ids::ParseIdError),
 
@@ -216,6 +378,12 @@ pub enum ProcessingError {
 
     #[error("After processing the data collector was empty")]
     EmptyCollector,
+
+    #[error("Database verification failed {failed} checks")]
+    VerificationFailed { failed: usize },
+
+    #[error("Advisor data check found {failed} issues")]
+    AdvisorCheckFailed { failed: usize },
 }
 
 impl<T> From<std::sync::PoisonError<T>> for ProcessingError {
@@ -234,6 +402,7 @@ impl From<IoOrSerdeError> for ProcessingError {
                 Self::ReadJsonLines(error, path, line)
             }
             IoOrSerdeError::ReadYaml(error, path) => Self::ReadYaml(error, path),
+            IoOrSerdeError::ReadXml(error, path) => Self::ReadXml(error, path),
             IoOrSerdeError::WriteCsv(error) => Self::WriteCsv(error),
             IoOrSerdeError::WriteJson(error) => Self::WriteJson(error),
             IoOrSerdeError::WriteYaml(error) => Self::WriteYaml(error),
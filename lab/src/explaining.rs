@@ -0,0 +1,294 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `explain-config` command describing the configuration of the other commands.
+//!
+//! The descriptions are generated from the doc comments of the configuration types, so they stay
+//! in sync with the code.
+
+use clap::CommandFactory;
+use schemars::{
+    Map,
+    schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec},
+    schema_for,
+};
+
+use crate::{commands, config, errors};
+
+/// Returns the schema of the configuration of the command with the given name.
+fn config_schema(command: &str) -> Option<RootSchema> {
+    let schema = match command {
+        "absorb" => schema_for!(config::AbsorbingConfig),
+        "fetch" => schema_for!(config::FetchConfig),
+        "extract" => schema_for!(config::ExtractingConfig),
+        "cache" => schema_for!(config::CachingConfig),
+        "condense" => schema_for!(config::CondensationConfig),
+        "filter" => schema_for!(config::FilteringConfig),
+        "coagulate" => schema_for!(config::CoagulationConfig),
+        "crystalize" => schema_for!(config::CrystalizationConfig),
+        "oxidize" => schema_for!(config::OxidationConfig),
+        "feed" => schema_for!(config::FeedingConfig),
+        "update" => schema_for!(config::UpdatingConfig),
+        "connect" => schema_for!(config::ConnectionConfig),
+        "sample" => schema_for!(config::SamplingConfig),
+        "trace" => schema_for!(config::TracingConfig),
+        "bundle-repro" => schema_for!(config::BundleConfig),
+        "duplicates" => schema_for!(config::DuplicatesConfig),
+        "qa-sample" => schema_for!(config::QaSampleConfig),
+        "export-research" => schema_for!(config::ResearchExportConfig),
+        "export-sitemap" => schema_for!(config::SitemapExportConfig),
+        "verify-db" => schema_for!(config::VerificationConfig),
+        "digest" => schema_for!(config::DigestConfig),
+        "merge-substrates" => schema_for!(config::SubstrateMergingConfig),
+        "check-advisors" => schema_for!(config::AdvisorCheckConfig),
+        "explain-config" => schema_for!(config::ExplainConfig),
+        "check-shopping" => schema_for!(config::ShoppingCheckConfig),
+        "review-substrate" => schema_for!(config::SubstrateReviewConfig),
+        _ => return None,
+    };
+    Some(schema)
+}
+
+/// A single path or knob of a configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Knob {
+    /// Dot-separated path to the knob.
+    path: String,
+
+    /// Type of the knob's value.
+    kind: String,
+
+    /// Meaning of the knob.
+    description: Option<String>,
+}
+
+/// Returns the schema referenced by the given schema object.
+fn dereference<'a>(
+    object: &SchemaObject,
+    definitions: &'a Map<String, Schema>,
+) -> Option<&'a Schema> {
+    let name = object.reference.as_deref()?.strip_prefix("#/definitions/")?;
+    definitions.get(name)
+}
+
+/// Checks if the schema describes only the `null` value, like the empty alternative of an `Option`.
+fn is_null(schema: &Schema) -> bool {
+    matches!(
+        schema,
+        Schema::Object(SchemaObject {
+            instance_type: Some(SingleOrVec::Single(kind)),
+            ..
+        }) if **kind == InstanceType::Null
+    )
+}
+
+/// Returns the values of an enum variant without fields.
+fn unit_values(schema: &Schema) -> Option<Vec<String>> {
+    let Schema::Object(object) = schema else {
+        return None;
+    };
+    object.enum_values.as_ref().map(|values| values.iter().map(ToString::to_string).collect())
+}
+
+/// Describes the type of a leaf of the schema.
+fn describe_kind(object: &SchemaObject) -> String {
+    if let Some(values) = &object.enum_values {
+        return values.iter().map(ToString::to_string).collect::<Vec<_>>().join(" | ");
+    }
+
+    let name = |kind: &InstanceType| format!("{kind:?}").to_lowercase();
+    match &object.instance_type {
+        Some(SingleOrVec::Single(kind)) => name(kind.as_ref()),
+        Some(SingleOrVec::Vec(kinds)) => {
+            let names: Vec<String> =
+                kinds.iter().filter(|kind| **kind != InstanceType::Null).map(name).collect();
+            if kinds.contains(&InstanceType::Null) {
+                format!("{} (optional)", names.join(" | "))
+            } else {
+                names.join(" | ")
+            }
+        }
+        None => "any".to_owned(),
+    }
+}
+
+/// Flattens the schema into a list of documented knobs.
+///
+/// Nested structures are expanded into dot-separated paths, references and `Option`s are
+/// resolved and enums without fields are shown as lists of their values.
+fn collect_knobs(
+    schema: &Schema,
+    definitions: &Map<String, Schema>,
+    path: &str,
+    inherited: Option<&str>,
+    knobs: &mut Vec<Knob>,
+) {
+    let Schema::Object(object) = schema else {
+        return;
+    };
+
+    let description =
+        object.metadata.as_ref().and_then(|metadata| metadata.description.as_deref()).or(inherited);
+
+    if object.reference.is_some() {
+        if let Some(schema) = dereference(object, definitions) {
+            collect_knobs(schema, definitions, path, description, knobs);
+        }
+        return;
+    }
+
+    if let Some(validation) = &object.object {
+        for (name, property) in &validation.properties {
+            let path = if path.is_empty() { name.clone() } else { format!("{path}.{name}") };
+            collect_knobs(property, definitions, &path, None, knobs);
+        }
+        return;
+    }
+
+    if let Some(subschemas) = &object.subschemas {
+        let alternatives: Vec<&Schema> =
+            [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|schema| !is_null(schema))
+                .collect();
+        let values: Option<Vec<Vec<String>>> =
+            alternatives.iter().map(|schema| unit_values(schema)).collect();
+        if let Some(values) = values {
+            let kind = values.concat().join(" | ");
+            let description = description.map(str::to_owned);
+            knobs.push(Knob { path: path.to_owned(), kind, description });
+        } else {
+            for alternative in alternatives {
+                collect_knobs(alternative, definitions, path, description, knobs);
+            }
+        }
+        return;
+    }
+
+    let description = description.map(str::to_owned);
+    knobs.push(Knob { path: path.to_owned(), kind: describe_kind(object), description });
+}
+
+/// Returns the documented knobs of the configuration of the given schema.
+fn knobs(schema: &RootSchema) -> Vec<Knob> {
+    let mut knobs = Vec::new();
+    for (name, property) in schema.schema.object.iter().flat_map(|object| &object.properties) {
+        collect_knobs(property, &schema.definitions, name, None, &mut knobs);
+    }
+    knobs
+}
+
+pub struct ExplainRunner;
+
+impl ExplainRunner {
+    /// Lists all the commands.
+    fn list_commands() {
+        println!("Commands (use `explain-config <COMMAND>` to explain one of them):");
+        println!();
+        for command in commands::Args::command().get_subcommands() {
+            let about = command.get_about().map(ToString::to_string).unwrap_or_default();
+            println!("  {:<20} {about}", command.get_name());
+        }
+    }
+
+    /// Prints the options of the command with their default values.
+    fn explain_options(command: &clap::Command) {
+        println!("Options of `{}`:", command.get_name());
+        println!();
+        for arg in command.get_arguments().filter(|arg| arg.get_id() != "help") {
+            let name = match arg.get_long() {
+                Some(long) => format!("--{long}"),
+                None => format!("<{}>", arg.get_id().as_str().to_uppercase()),
+            };
+            let defaults: Vec<String> =
+                arg.get_default_values().iter().map(|v| v.to_string_lossy().into_owned()).collect();
+            if defaults.is_empty() {
+                println!("  {name}");
+            } else {
+                println!("  {name} [default: {}]", defaults.join(","));
+            }
+            if let Some(help) = arg.get_help() {
+                println!("      {help}");
+            }
+        }
+    }
+
+    /// Prints the paths and knobs of the configuration created from the options.
+    fn explain_knobs(name: &str, schema: &RootSchema) {
+        println!("Configuration of `{name}`:");
+        println!();
+        for knob in knobs(schema) {
+            println!("  {}: {}", knob.path, knob.kind);
+            if let Some(description) = knob.description {
+                for line in description.lines() {
+                    println!("      {line}");
+                }
+            }
+        }
+    }
+
+    pub fn run(config: &config::ExplainConfig) -> Result<(), errors::ProcessingError> {
+        let Some(name) = &config.command else {
+            if config.json {
+                let schema = schema_for!(config::Config);
+                let json = serde_json::to_string_pretty(&schema)
+                    .map_err(errors::ProcessingError::WriteJson)?;
+                println!("{json}");
+            } else {
+                Self::list_commands();
+            }
+            return Ok(());
+        };
+
+        let command = commands::Args::command();
+        let (Some(command), Some(schema)) = (command.find_subcommand(name), config_schema(name))
+        else {
+            return Err(errors::ConfigCheckError::UnknownCommand(name.clone()).into());
+        };
+
+        if config.json {
+            let json = serde_json::to_string_pretty(&schema)
+                .map_err(errors::ProcessingError::WriteJson)?;
+            println!("{json}");
+        } else {
+            Self::explain_options(command);
+            println!();
+            Self::explain_knobs(name, &schema);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every command has a configuration with all the knobs documented.
+    #[test]
+    fn all_commands_explained() {
+        for command in commands::Args::command().get_subcommands() {
+            let schema = config_schema(command.get_name());
+            assert!(schema.is_some(), "{}", command.get_name());
+
+            let knobs = knobs(&schema.unwrap());
+            assert!(knobs.iter().all(|knob| knob.description.is_some()), "{knobs:#?}");
+        }
+    }
+
+    /// Nested paths, optional values and enums are flattened into documented knobs.
+    #[test]
+    fn condense_knobs() {
+        let knobs = knobs(&config_schema("condense").unwrap());
+        let knob = |path: &str| knobs.iter().find(|knob| knob.path == path).unwrap().clone();
+
+        let substrate = knob("substrate.substrate_path");
+        assert_eq!(substrate.kind, "string");
+        assert_eq!(substrate.description.as_deref(), Some("Path to the substrate file directory."));
+
+        assert_eq!(knob("group").kind, r#""filtered" | "immediate" | "all""#);
+        assert_eq!(knob("off.filter.max_age_days").kind, "integer (optional)");
+    }
+}
@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use async_trait::async_trait;
 use merge::Merge;
@@ -19,17 +19,25 @@ use crate::{cache, config, errors, parallel, runners, utils, wikidata::ItemExt};
 pub struct ExtractingCollector {
     /// IDs of manufacturers.
     manufacturer_ids: HashSet<WikiId>,
+
+    /// Map from classes to their direct superclasses.
+    superclasses: HashMap<WikiId, Vec<WikiId>>,
 }
 
 impl ExtractingCollector {
     pub fn add_manufacturer_ids(&mut self, ids: &[WikiId]) {
         self.manufacturer_ids.extend(ids.iter().copied());
     }
+
+    pub fn add_superclasses(&mut self, class: WikiId, superclasses: Vec<WikiId>) {
+        self.superclasses.insert(class, superclasses);
+    }
 }
 
 impl merge::Merge for ExtractingCollector {
     fn merge(&mut self, other: Self) {
         self.manufacturer_ids.extend(other.manufacturer_ids);
+        self.superclasses.extend(other.superclasses);
     }
 }
 
@@ -61,6 +69,9 @@ impl runners::WikidataWorker for ExtractingWorker {
                 if let Some(manufacturer_ids) = item.get_manufacturer_ids()? {
                     self.collector.add_manufacturer_ids(&manufacturer_ids);
                 }
+                if let Some(superclasses) = item.get_superclasses()? {
+                    self.collector.add_superclasses(item.id, superclasses);
+                }
             }
             Entity::Property(_property) => (),
         }
@@ -97,16 +108,32 @@ impl runners::Stash for ExtractingStash {
     type Input = ExtractingCollector;
 
     fn stash(&mut self, input: Self::Input) -> Result<(), errors::ProcessingError> {
-        log::info!("Merging {} manufacturers", input.manufacturer_ids.len(),);
+        log::info!(
+            "Merging {} manufacturers and {} classes",
+            input.manufacturer_ids.len(),
+            input.superclasses.len(),
+        );
         self.collector.merge(input);
         Ok(())
     }
 
     fn finish(self) -> Result<(), errors::ProcessingError> {
-        log::info!("Found {} manufacturers", self.collector.manufacturer_ids.len());
+        log::info!(
+            "Found {} manufacturers and {} classes",
+            self.collector.manufacturer_ids.len(),
+            self.collector.superclasses.len(),
+        );
 
         let mut cache = cache::Wikidata {
             manufacturer_ids: self.collector.manufacturer_ids.iter().copied().collect(),
+            superclasses: self
+                .collector
+                .superclasses
+                .iter()
+                .map(|(class, superclasses)| {
+                    (class.get_value(), superclasses.iter().map(WikiId::get_value).collect())
+                })
+                .collect(),
         };
 
         cache.manufacturer_ids.sort();
@@ -128,12 +155,19 @@ pub struct ExtractingRunner;
 
 impl ExtractingRunner {
     pub fn run(config: &config::ExtractingConfig) -> Result<(), errors::ProcessingError> {
+        let source = &config.wikidata_gatherer;
+        let namespace = source
+            .namespace_name()
+            .map_err(|e| errors::ProcessingError::Io(e, source.location()))?;
+        cache::activate_namespace(&config.cache.cache_path, &namespace)?;
+
         let worker = ExtractingWorker::new();
         let stash = ExtractingStash::new(config.clone());
 
         let flow = parallel::Flow::new();
         runners::WikidataRunner::flow(flow, config, worker, stash)?.join();
 
+        cache::cleanup(&config.cache.cache_path)?;
         Ok(())
     }
 }
@@ -0,0 +1,161 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Imports product availability from retailer feeds.
+//!
+//! The feeds are expected to be delivered frequently (e.g. daily), so the data is stored in a
+//! dedicated bucket in the app storage instead of being merged into product records. Each feed
+//! replaces all the data imported earlier from the same retailer.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use transpaer_collecting::errors::{MapIo, MapSerde};
+use transpaer_models::{buckets, ids, store};
+
+use crate::{config, errors};
+
+/// Single entry of a retailer feed.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FeedRecord {
+    /// GTIN of the product.
+    pub gtin: String,
+
+    /// Alpha-2 or alpha-3 country code of the region.
+    pub region: String,
+
+    /// Whether the product is currently available in the region.
+    pub available: bool,
+
+    /// Link to the product in the retailer's shop.
+    pub url: Option<String>,
+}
+
+/// Format of the feed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Csv,
+    Json,
+}
+
+impl FeedFormat {
+    /// Guesses the format of the feed from the file extension.
+    fn from_path(path: &std::path::Path) -> Option<Self> {
+        match path.extension().and_then(std::ffi::OsStr::to_str) {
+            Some("csv") => Some(Self::Csv),
+            Some("json") => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+pub struct FeedingRunner;
+
+impl FeedingRunner {
+    /// Runs the feed command.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if reading or parsing the feed or saving the data failed.
+    pub fn run(config: &config::FeedingConfig) -> Result<(), errors::ProcessingError> {
+        let records = Self::load(&config.feed_path)?;
+        log::info!("Loaded {} feed records from `{}`", records.len(), config.retailer);
+
+        let mut entries = BTreeMap::<ids::Gtin, Vec<store::RetailerAvailability>>::new();
+        let mut num_invalid_gtins = 0;
+        let mut num_invalid_regions = 0;
+        for record in records {
+            let Ok(gtin) = ids::Gtin::try_from(&record.gtin) else {
+                log::debug!("Skipping feed record with invalid GTIN `{}`", record.gtin);
+                num_invalid_gtins += 1;
+                continue;
+            };
+            let Ok(region) = Self::parse_region(&record.region) else {
+                log::debug!("Skipping feed record with invalid region `{}`", record.region);
+                num_invalid_regions += 1;
+                continue;
+            };
+
+            // Later records for the same product and region replace the earlier ones.
+            let gtin_entries = entries.entry(gtin).or_default();
+            gtin_entries.retain(|entry| entry.region != region);
+            gtin_entries.push(store::RetailerAvailability {
+                retailer: config.retailer.clone(),
+                region,
+                available: record.available,
+                url: record.url,
+            });
+        }
+        if num_invalid_gtins > 0 {
+            log::warn!("Skipped {num_invalid_gtins} feed records with invalid GTINs");
+        }
+        if num_invalid_regions > 0 {
+            log::warn!("Skipped {num_invalid_regions} feed records with invalid regions");
+        }
+
+        let store = buckets::AppStore::new(&config.app_storage)?;
+        let bucket = store.get_gtin_to_retailer_availability_bucket()?;
+
+        // The feed is a full snapshot of the retailer's offer, so the products missing in it are
+        // not reported by the retailer anymore.
+        let mut stale = Vec::new();
+        for item in bucket.iter() {
+            let (gtin, current) = item?;
+            if !entries.contains_key(&gtin)
+                && current.iter().any(|entry| entry.retailer == config.retailer)
+            {
+                stale.push((gtin, current));
+            }
+        }
+        log::info!("Pruning {} products not listed in the feed anymore", stale.len());
+        for (gtin, mut current) in stale {
+            current.retain(|entry| entry.retailer != config.retailer);
+            if current.is_empty() {
+                bucket.remove(&gtin)?;
+            } else {
+                bucket.insert(&gtin, &current)?;
+            }
+        }
+
+        for (gtin, mut new_entries) in entries {
+            let mut current = bucket.get(&gtin)?.unwrap_or_default();
+            current.retain(|entry| entry.retailer != config.retailer);
+            current.append(&mut new_entries);
+            bucket.insert(&gtin, &current)?;
+        }
+
+        log::info!("Availability known for {} products", bucket.len());
+        bucket.flush()?;
+        Ok(())
+    }
+
+    fn load(path: &std::path::Path) -> Result<Vec<FeedRecord>, errors::ProcessingError> {
+        match FeedFormat::from_path(path) {
+            Some(FeedFormat::Csv) => {
+                let mut reader = csv::ReaderBuilder::new().from_path(path).map_with_path(path)?;
+                let mut records = Vec::new();
+                for result in reader.deserialize() {
+                    records.push(result.map_with_path(path)?);
+                }
+                Ok(records)
+            }
+            Some(FeedFormat::Json) => {
+                let contents = std::fs::read_to_string(path).map_with_path(path)?;
+                Ok(serde_json::from_str(&contents).map_with_path(path)?)
+            }
+            None => Err(errors::ProcessingError::FeedFormat(path.to_owned())),
+        }
+    }
+
+    fn parse_region(
+        region: &str,
+    ) -> Result<isocountry::CountryCode, isocountry::CountryCodeParseErr> {
+        if region.len() == 2 {
+            isocountry::CountryCode::for_alpha2(region)
+        } else {
+            isocountry::CountryCode::for_alpha3(region)
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Fetches entities from the live Wikidata service.
+//!
+//! This allows building small test substrates without downloading the full Wikidata dump.
+
+use std::collections::BTreeSet;
+
+use transpaer_collecting::errors::{MapIo, MapSerde};
+
+use crate::{config, errors::SamplingError};
+
+const USER_AGENT: &str = "transpaer-lab";
+const ENTITY_DATA_URL: &str = "https://www.wikidata.org/wiki/Special:EntityData";
+const SPARQL_URL: &str = "https://query.wikidata.org/sparql";
+const ENTITY_URI_PREFIX: &str = "http://www.wikidata.org/entity/";
+
+/// Response of the `Special:EntityData` endpoint.
+#[derive(serde::Deserialize, Debug)]
+struct EntityDataResponse {
+    entities: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Response of the SPARQL endpoint.
+#[derive(serde::Deserialize, Debug)]
+struct SparqlResponse {
+    results: SparqlResults,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct SparqlResults {
+    bindings: Vec<std::collections::HashMap<String, SparqlValue>>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct SparqlValue {
+    value: String,
+}
+
+/// Fetches Wikidata entities caching the responses and limiting the rate of the requests.
+pub struct WikidataFetcher<'a> {
+    client: reqwest::Client,
+    config: &'a config::SamplingWikidataConfig,
+    last_request: Option<std::time::Instant>,
+}
+
+impl<'a> WikidataFetcher<'a> {
+    pub fn new(config: &'a config::SamplingWikidataConfig) -> Result<Self, SamplingError> {
+        let client = reqwest::ClientBuilder::new().user_agent(USER_AGENT).build()?;
+        Ok(Self { client, config, last_request: None })
+    }
+
+    /// Fetches all configured entities and saves them in the format of the filtered Wikidata dump.
+    pub async fn run(mut self) -> Result<usize, SamplingError> {
+        let mut ids: BTreeSet<String> = self.config.ids.iter().cloned().collect();
+        if let Some(path) = &self.config.sparql_path {
+            let query = std::fs::read_to_string(path).map_with_path(path)?;
+            ids.extend(self.query(&query).await?);
+        }
+
+        let cache_dir = &self.config.entity_cache_dir;
+        std::fs::create_dir_all(cache_dir).map_with_path(cache_dir)?;
+
+        let mut entities = Vec::with_capacity(ids.len());
+        for id in &ids {
+            entities.push(self.entity(id).await?);
+        }
+
+        let path = &self.config.wikidata_path;
+        serde_jsonlines::write_json_lines(path, &entities).map_with_path(path)?;
+        Ok(entities.len())
+    }
+
+    /// Returns IDs of all entities found in the results of the SPARQL query.
+    async fn query(&mut self, query: &str) -> Result<Vec<String>, SamplingError> {
+        log::info!("Querying Wikidata SPARQL endpoint");
+        self.wait().await;
+        let text = self
+            .client
+            .get(SPARQL_URL)
+            .query(&[("query", query), ("format", "json")])
+            .header(reqwest::header::ACCEPT, "application/sparql-results+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let response: SparqlResponse = serde_json::from_str(&text).map_serde()?;
+        Ok(response
+            .results
+            .bindings
+            .into_iter()
+            .flat_map(std::collections::HashMap::into_values)
+            .filter_map(|value| value.value.strip_prefix(ENTITY_URI_PREFIX).map(str::to_owned))
+            .collect())
+    }
+
+    /// Returns the entity either from the cache or from the Wikidata service.
+    async fn entity(&mut self, id: &str) -> Result<serde_json::Value, SamplingError> {
+        let path = self.config.entity_cache_dir.join(id).with_extension("json");
+        if path.exists() {
+            log::info!(" - `{id}` from cache");
+            let contents = std::fs::read_to_string(&path).map_with_path(&path)?;
+            return Ok(serde_json::from_str(&contents).map_with_path(&path)?);
+        }
+
+        log::info!(" - `{id}` from Wikidata");
+        self.wait().await;
+        let text = self
+            .client
+            .get(format!("{ENTITY_DATA_URL}/{id}.json"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let mut response: EntityDataResponse = serde_json::from_str(&text).map_serde()?;
+
+        // Redirected entities are returned under the ID they were redirected to.
+        let entity = match response.entities.remove(id) {
+            Some(entity) => entity,
+            None => response
+                .entities
+                .into_iter()
+                .next()
+                .map(|(_, entity)| entity)
+                .ok_or_else(|| SamplingError::EntityNotFound(id.to_owned()))?,
+        };
+
+        let contents = serde_json::to_string(&entity).map_serde()?;
+        std::fs::write(&path, contents).map_with_path(&path)?;
+        Ok(entity)
+    }
+
+    /// Waits until the configured delay since the last request passes.
+    async fn wait(&mut self) {
+        if let Some(last_request) = self.last_request {
+            let elapsed = last_request.elapsed();
+            if elapsed < self.config.delay {
+                tokio::time::sleep(self.config.delay - elapsed).await;
+            }
+        }
+        self.last_request = Some(std::time::Instant::now());
+    }
+}
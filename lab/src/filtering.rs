@@ -6,6 +6,7 @@ use std::{io::Write, sync::Arc};
 
 use async_trait::async_trait;
 
+use transpaer_collecting::sitelinks::{self, SitelinkCount};
 use transpaer_wikidata::data::{Entity, Item};
 
 use crate::{advisors, config, errors, parallel, runners, wikidata::ItemExt};
@@ -13,9 +14,27 @@ use crate::{advisors, config, errors, parallel, runners, wikidata::ItemExt};
 const WIKIDATA_SUBSTRATE_NAME: &str = "wikidata";
 
 #[derive(Clone)]
-pub struct Message {
-    entry: String,
-    has_wikipedia_page: bool,
+pub enum Message {
+    /// Entry to be kept.
+    ///
+    /// `product` holds the sitelink count if the entry is a product.
+    Keep {
+        entry: String,
+        has_wikipedia_page: bool,
+        sitelinks: usize,
+        product: Option<SitelinkCount>,
+    },
+
+    /// Product or organisation dropped because of too few sitelinks.
+    Obscure,
+}
+
+/// Decision about a single Wikidata item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    Keep,
+    Obscure,
+    Drop,
 }
 
 /// Filters product entries out from the wikidata dump file.
@@ -23,43 +42,50 @@ pub struct Message {
 pub struct FilteringWorker {
     wikidata: Arc<advisors::WikidataAdvisor>,
     substrate: Arc<advisors::SubstrateAdvisor>,
+    min_sitelinks: usize,
 }
 
 impl FilteringWorker {
     fn new(
         wikidata: Arc<advisors::WikidataAdvisor>,
         substrate: Arc<advisors::SubstrateAdvisor>,
+        min_sitelinks: usize,
     ) -> Self {
-        Self { wikidata, substrate }
+        Self { wikidata, substrate, min_sitelinks }
     }
 
     /// Decides if the passed item should be kept or filtered out.
     ///
     /// The item is kept if it:
-    /// - is a product or
-    /// - is a manufacturer.
-    fn should_keep(&self, item: &Item) -> bool {
-        // Is a product or organisation according to wikidata?
-        if self.wikidata.is_product(item) || self.wikidata.is_organisation(item) {
-            return true;
-        }
-
+    /// - is referenced by any of the substrates (e.g. is certified) or
+    /// - is a notable product or
+    /// - is a notable organisation or a manufacturer.
+    fn judge(&self, item: &Item) -> Verdict {
         // Is a product according to any of the substrates?
         if self.substrate.has_product_wiki_id(&item.id.into()) {
-            return true;
+            return Verdict::Keep;
         }
 
         // Is an organisation according to any of the substrates?
         if self.substrate.has_producer_wiki_id(&item.id.into()) {
-            return true;
+            return Verdict::Keep;
         }
         if let Some(websites) = item.get_official_websites()
             && self.substrate.has_domains(&websites)
         {
-            return true;
+            return Verdict::Keep;
+        }
+
+        // Is a product or organisation according to wikidata?
+        if self.wikidata.is_product(item) || self.wikidata.is_organisation(item) {
+            // Manufacturers are kept for the sake of their products.
+            if item.is_notable(self.min_sitelinks) || self.wikidata.has_manufacturer_id(&item.id) {
+                return Verdict::Keep;
+            }
+            return Verdict::Obscure;
         }
 
-        false
+        Verdict::Drop
     }
 }
 
@@ -74,18 +100,27 @@ impl runners::WikidataWorker for FilteringWorker {
         tx: parallel::Sender<Self::Output>,
     ) -> Result<(), errors::ProcessingError> {
         match entity {
-            Entity::Item(item) => {
-                let mut has_wikipedia_page = false;
-                if self.should_keep(&item) {
-                    for sl in item.sitelinks.values() {
-                        if sl.site == "enwiki" {
-                            has_wikipedia_page = true;
-                            break;
-                        }
-                    }
-                    tx.send(Message { entry: msg.to_string(), has_wikipedia_page }).await;
+            Entity::Item(item) => match self.judge(&item) {
+                Verdict::Keep => {
+                    let has_wikipedia_page = item.sitelinks.values().any(|sl| sl.site == "enwiki");
+                    let sitelinks = item.get_sitelink_count();
+                    let is_product = self.wikidata.is_product(&item)
+                        || self.substrate.has_product_wiki_id(&item.id.into());
+                    let product = is_product.then(|| SitelinkCount {
+                        wiki_id: item.id.get_value(),
+                        sitelinks: u32::try_from(sitelinks).unwrap_or(u32::MAX),
+                    });
+                    tx.send(Message::Keep {
+                        entry: msg.to_string(),
+                        has_wikipedia_page,
+                        sitelinks,
+                        product,
+                    })
+                    .await;
                 }
-            }
+                Verdict::Obscure => tx.send(Message::Obscure).await,
+                Verdict::Drop => {}
+            },
             Entity::Property(_property) => {}
         }
         Ok(())
@@ -105,12 +140,21 @@ pub struct FilteringStash {
     /// Filtered Wikidata entries.
     entries: Vec<String>,
 
+    /// Sitelink counts of the kept products.
+    product_sitelinks: Vec<SitelinkCount>,
+
     /// Number of all entries.
     all_entries: usize,
 
     /// Number of entries with a corresponding wikipedia page.
     with_wikipedia_page: usize,
 
+    /// Number of entries without any sitelinks.
+    without_sitelinks: usize,
+
+    /// Number of products and organisations dropped because of too few sitelinks.
+    obscure_entries: usize,
+
     /// Configuration.
     config: config::FilteringConfig,
 }
@@ -118,14 +162,31 @@ pub struct FilteringStash {
 impl FilteringStash {
     #[must_use]
     pub fn new(config: config::FilteringConfig) -> Self {
-        Self { entries: Vec::new(), all_entries: 0, with_wikipedia_page: 0, config }
+        Self {
+            entries: Vec::new(),
+            product_sitelinks: Vec::new(),
+            all_entries: 0,
+            with_wikipedia_page: 0,
+            without_sitelinks: 0,
+            obscure_entries: 0,
+            config,
+        }
     }
 
     pub fn add_input(&mut self, input: Message) {
-        self.entries.push(input.entry);
-        self.all_entries += 1;
-        if input.has_wikipedia_page {
-            self.with_wikipedia_page += 1;
+        match input {
+            Message::Keep { entry, has_wikipedia_page, sitelinks, product } => {
+                self.entries.push(entry);
+                self.product_sitelinks.extend(product);
+                self.all_entries += 1;
+                if has_wikipedia_page {
+                    self.with_wikipedia_page += 1;
+                }
+                if sitelinks == 0 {
+                    self.without_sitelinks += 1;
+                }
+            }
+            Message::Obscure => self.obscure_entries += 1,
         }
     }
 
@@ -172,12 +233,21 @@ impl runners::Stash for FilteringStash {
         Ok(())
     }
 
-    fn finish(self) -> Result<(), errors::ProcessingError> {
+    fn finish(mut self) -> Result<(), errors::ProcessingError> {
         self.save().map_err(|e| {
             errors::ProcessingError::Io(e, self.config.wikidata_filtered_dump_path.clone())
         })?;
+        self.product_sitelinks.sort_by_key(|count| count.wiki_id);
+        sitelinks::write(&self.config.wikidata_sitelinks_path, &self.product_sitelinks)?;
         log::info!(" - {} processed entries", self.all_entries);
         log::info!(" - {} entries have a corresponding wikipedia page", self.with_wikipedia_page);
+        log::info!(" - {} entries have no sitelinks", self.without_sitelinks);
+        log::info!(" - {} products have recorded sitelink counts", self.product_sitelinks.len());
+        log::info!(
+            " - {} entries dropped for having fewer than {} sitelinks",
+            self.obscure_entries,
+            self.config.wikidata_min_sitelinks,
+        );
         Ok(())
     }
 }
@@ -195,7 +265,7 @@ impl FilteringRunner {
             &config.meta.wikidata_categories_path,
         )?);
 
-        let worker = FilteringWorker::new(wikidata, substrate);
+        let worker = FilteringWorker::new(wikidata, substrate, config.wikidata_min_sitelinks);
         let stash = FilteringStash::new(config.clone());
 
         let flow = parallel::Flow::new();
@@ -10,6 +10,7 @@
 // TODO: add more structure to the files
 mod absorbing;
 mod advisors;
+mod bundling;
 mod cache;
 mod coagulate;
 mod coagulating;
@@ -19,22 +20,65 @@ mod config;
 mod connecting;
 mod convert;
 mod crystalizing;
+mod digesting;
+mod downloading;
+mod duplicates;
 mod errors;
+mod explaining;
 mod extracting;
+mod feeding;
+mod fetching;
 mod filtering;
+mod merging;
 mod oxidation;
 mod parallel;
+mod qa_sample;
+mod remote;
+mod research;
 mod runners;
 mod sampling;
 mod score;
+mod shopping;
+mod sitemap;
 mod substrate;
+mod substrate_review;
+mod trace;
 mod updating;
 mod utils;
+mod validating;
+mod verification;
+mod watching;
 mod wikidata;
+mod workspace;
 
 pub use crate::{
-    absorbing::Absorber, coagulating::Coagulator, condensing::CondensingRunner, config::Config,
-    connecting::ConnectionRunner, crystalizing::Crystalizer, errors::ProcessingError,
-    extracting::ExtractingRunner, filtering::FilteringRunner, oxidation::Oxidizer,
-    sampling::SamplingRunner, updating::UpdateRunner,
+    absorbing::Absorber,
+    bundling::BundleRunner,
+    cache::CachingRunner,
+    coagulating::Coagulator,
+    condensing::CondensingRunner,
+    config::{Config, LogFormat, LoggingConfig},
+    connecting::ConnectionRunner,
+    crystalizing::Crystalizer,
+    digesting::DigestRunner,
+    downloading::FetchRunner,
+    duplicates::DuplicatesRunner,
+    errors::ProcessingError,
+    explaining::ExplainRunner,
+    extracting::ExtractingRunner,
+    feeding::FeedingRunner,
+    filtering::FilteringRunner,
+    merging::SubstrateMergingRunner,
+    oxidation::Oxidizer,
+    qa_sample::QaSampleRunner,
+    research::ResearchExportRunner,
+    sampling::SamplingRunner,
+    shopping::ShoppingCheckRunner,
+    sitemap::SitemapExportRunner,
+    substrate_review::SubstrateReviewRunner,
+    trace::TracingRunner,
+    updating::UpdateRunner,
+    validating::AdvisorCheckRunner,
+    verification::VerificationRunner,
+    watching::WatchingRunner,
 };
@@ -17,19 +17,87 @@ fn format_elapsed_time(duration: std::time::Duration) -> String {
     format!("{hours}h {minutes}m {seconds}s")
 }
 
-async fn run() -> Result<(), transpaer_lab::ProcessingError> {
+/// Collects structured key-value pairs attached to a log record.
+struct JsonFields<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl<'kvs> log::kv::VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        self.0.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+        Ok(())
+    }
+}
+
+/// Formats a log record as a single line JSON object.
+fn format_json(message: &std::fmt::Arguments, record: &log::Record, stage: &str) -> String {
+    let mut fields = serde_json::Map::new();
+    if let Err(err) = record.key_values().visit(&mut JsonFields(&mut fields)) {
+        fields.insert("error".to_owned(), serde_json::Value::String(err.to_string()));
+    }
+
+    serde_json::json!({
+        "timestamp": humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+        "level": record.level().as_str(),
+        "stage": stage,
+        "target": record.target(),
+        "message": message.to_string(),
+        "fields": fields,
+    })
+    .to_string()
+}
+
+fn setup_logger(logging: &transpaer_lab::LoggingConfig) -> Result<(), log::SetLoggerError> {
+    let dispatch = match logging.format {
+        transpaer_lab::LogFormat::Text => fern::Dispatch::new().format(|out, message, record| {
+            out.finish(format_args!(
+                "[{} {: <5}] {}",
+                humantime::format_rfc3339_seconds(std::time::SystemTime::now()),
+                record.level(),
+                message
+            ));
+        }),
+        transpaer_lab::LogFormat::Json => {
+            let stage = logging.stage;
+            fern::Dispatch::new().format(move |out, message, record| {
+                out.finish(format_args!("{}", format_json(message, record, stage)));
+            })
+        }
+    };
+
+    let dispatch = dispatch.level(log::LevelFilter::Info);
+    if logging.stderr {
+        dispatch.chain(std::io::stderr()).apply()
+    } else {
+        dispatch.chain(std::io::stdout()).apply()
+    }
+}
+
+async fn run(config: transpaer_lab::Config) -> Result<(), transpaer_lab::ProcessingError> {
     use transpaer_lab::Config;
-    match Config::new_from_args() {
+    match config {
         Config::Absorbing(config) => {
             config.check()?;
             log::info!("Start absorbing");
             transpaer_lab::Absorber::run(&config).await?;
         }
+        Config::Fetching(config) => {
+            config.check()?;
+            log::info!("Start fetching");
+            transpaer_lab::FetchRunner::run(&config).await?;
+        }
         Config::Extracting(config) => {
             config.check()?;
             log::info!("Start extracting");
             transpaer_lab::ExtractingRunner::run(&config)?;
         }
+        Config::Caching(config) => {
+            config.check()?;
+            log::info!("Start caching");
+            transpaer_lab::CachingRunner::run(&config)?;
+        }
         Config::Filtering(config) => {
             config.check()?;
             log::info!("Start filtering");
@@ -52,14 +120,24 @@ async fn run() -> Result<(), transpaer_lab::ProcessingError> {
         }
         Config::Crystalization(config) => {
             config.check()?;
-            log::info!("Start crystalization!");
-            transpaer_lab::Crystalizer::run(&config)?;
+            if let Some(watch) = &config.watch {
+                log::info!("Start watching!");
+                transpaer_lab::WatchingRunner::run(&config, watch).await?;
+            } else {
+                log::info!("Start crystalization!");
+                transpaer_lab::Crystalizer::run(&config)?;
+            }
         }
         Config::Oxidation(config) => {
             config.check()?;
             log::info!("Start oxidizing!");
             transpaer_lab::Oxidizer::run(&config)?;
         }
+        Config::Feeding(config) => {
+            config.check()?;
+            log::info!("Start feeding!");
+            transpaer_lab::FeedingRunner::run(&config)?;
+        }
         Config::Connection(config) => {
             config.check()?;
             log::info!("Start connecting!");
@@ -71,32 +149,87 @@ async fn run() -> Result<(), transpaer_lab::ProcessingError> {
             log::info!("Start sampling!");
             transpaer_lab::SamplingRunner::run(&config).await?;
         }
+        Config::Tracing(config) => {
+            config.check()?;
+            log::info!("Start tracing!");
+            transpaer_lab::TracingRunner::run(&config)?;
+        }
+        Config::Bundle(config) => {
+            config.check()?;
+            log::info!("Start bundling!");
+            transpaer_lab::BundleRunner::run(&config)?;
+        }
+        Config::Duplicates(config) => {
+            config.check()?;
+            log::info!("Start looking for duplicates!");
+            transpaer_lab::DuplicatesRunner::run(&config)?;
+        }
+        Config::QaSample(config) => {
+            config.check()?;
+            log::info!("Start sampling products for review!");
+            transpaer_lab::QaSampleRunner::run(&config)?;
+        }
+        Config::ResearchExport(config) => {
+            config.check()?;
+            log::info!("Start exporting the research dataset!");
+            transpaer_lab::ResearchExportRunner::run(&config)?;
+        }
+        Config::SitemapExport(config) => {
+            config.check()?;
+            log::info!("Start exporting the sitemap!");
+            transpaer_lab::SitemapExportRunner::run(&config)?;
+        }
+        Config::Verification(config) => {
+            config.check()?;
+            log::info!("Start verifying the database!");
+            transpaer_lab::VerificationRunner::run(&config)?;
+        }
+        Config::Digest(config) => {
+            config.check()?;
+            log::info!("Start computing the database digests!");
+            transpaer_lab::DigestRunner::run(&config)?;
+        }
+        Config::SubstrateMerging(config) => {
+            config.check()?;
+            log::info!("Start merging substrates!");
+            transpaer_lab::SubstrateMergingRunner::run(&config)?;
+        }
+        Config::AdvisorCheck(config) => {
+            config.check()?;
+            log::info!("Start checking advisor data!");
+            transpaer_lab::AdvisorCheckRunner::run(&config)?;
+        }
+        Config::Explaining(config) => {
+            config.check()?;
+            log::info!("Start explaining the configuration!");
+            transpaer_lab::ExplainRunner::run(&config)?;
+        }
+        Config::ShoppingCheck(config) => {
+            config.check()?;
+            log::info!("Start checking shopping links!");
+            transpaer_lab::ShoppingCheckRunner::run(&config).await?;
+        }
+        Config::SubstrateReview(config) => {
+            config.check()?;
+            log::info!("Start reviewing the substrate!");
+            transpaer_lab::SubstrateReviewRunner::run(&config)?;
+        }
     }
     Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    if let Err(err) = fern::Dispatch::new()
-        .format(|out, message, record| {
-            out.finish(format_args!(
-                "[{} {: <5}] {}",
-                humantime::format_rfc3339_seconds(std::time::SystemTime::now()),
-                record.level(),
-                message
-            ));
-        })
-        .level(log::LevelFilter::Info)
-        .chain(std::io::stdout())
-        .apply()
-    {
+    let (config, logging) = transpaer_lab::Config::new_from_args();
+
+    if let Err(err) = setup_logger(&logging) {
         println!("Logger error:\n{err}");
         return;
     }
 
     let start_time = std::time::Instant::now();
 
-    if let Err(err) = run().await {
+    if let Err(err) = run(config).await {
         log::error!("Processing error:\n{err}");
         std::process::exit(1);
     }
@@ -0,0 +1,234 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `merge-substrates` command.
+//!
+//! Substrates are often prepared on different machines. This command combines two substrate
+//! directories into one. Files of sources present in only one of the directories are copied,
+//! identical files are copied once and differing files of the same source are merged using the
+//! collector of that source. Files which cannot be merged are reported as conflicts.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    condensing::{self, MergedSubstrate},
+    config,
+    errors::{self, SubstrateMergingError},
+    substrate::Substrates,
+    utils,
+};
+
+/// Maximal number of conflicting IDs listed for a single source.
+const MAX_EXAMPLES: usize = 10;
+
+/// Summary of a merge of files of one source.
+#[derive(Debug, Default)]
+struct MergeSummary {
+    /// Number of producers present in more than one file.
+    num_merged_producers: usize,
+
+    /// IDs of products present in more than one file.
+    duplicated_products: BTreeSet<String>,
+}
+
+/// Report of merging the substrate directories.
+#[must_use]
+#[derive(Debug, Default)]
+pub struct SubstrateMergingReport {
+    /// Sources present in only one of the directories.
+    copied: BTreeSet<String>,
+
+    /// Sources with identical files in both directories.
+    identical: BTreeSet<String>,
+
+    /// Sources whose files were merged.
+    merged: BTreeMap<String, MergeSummary>,
+
+    /// Sources whose files differ, but cannot be merged. The file from the first directory is kept.
+    conflicts: BTreeSet<String>,
+}
+
+impl SubstrateMergingReport {
+    fn add_copied(&mut self, name: String) {
+        self.copied.insert(name);
+    }
+
+    fn add_identical(&mut self, name: String) {
+        self.identical.insert(name);
+    }
+
+    fn add_merged(&mut self, name: String, merged: &MergedSubstrate) {
+        self.merged.insert(
+            name,
+            MergeSummary {
+                num_merged_producers: merged.merged_producers.len(),
+                duplicated_products: merged.duplicated_products.clone(),
+            },
+        );
+    }
+
+    fn add_conflict(&mut self, name: String) {
+        self.conflicts.insert(name);
+    }
+
+    /// Checks if any conflicts were found.
+    #[must_use]
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+            || self.merged.values().any(|summary| !summary.duplicated_products.is_empty())
+    }
+
+    pub fn report(&self) {
+        log::info!("Substrate merging report:");
+        for name in &self.copied {
+            log::info!(" - `{name}`: copied");
+        }
+        for name in &self.identical {
+            log::info!(" - `{name}`: identical in both directories");
+        }
+        for (name, summary) in &self.merged {
+            log::info!(" - `{name}`: merged, {} producers combined", summary.num_merged_producers);
+            if !summary.duplicated_products.is_empty() {
+                log::warn!(
+                    "   {} products present in both directories were kept twice:",
+                    summary.duplicated_products.len()
+                );
+                for id in summary.duplicated_products.iter().take(MAX_EXAMPLES) {
+                    log::warn!("     - `{id}`");
+                }
+            }
+        }
+        for name in &self.conflicts {
+            log::warn!(" - `{name}`: differs and cannot be merged, kept from the first directory");
+        }
+    }
+}
+
+pub struct SubstrateMergingRunner;
+
+impl SubstrateMergingRunner {
+    /// Copies the file into the output directory keeping its name.
+    fn copy(path: &Path, output: &Path) -> Result<(), SubstrateMergingError> {
+        let target = output.join(path.file_name().unwrap_or_default());
+        std::fs::copy(path, &target).map_err(|e| SubstrateMergingError::Io(e, target))?;
+        Ok(())
+    }
+
+    /// Checks if all the files have the same contents.
+    fn all_identical(paths: &[PathBuf]) -> Result<bool, SubstrateMergingError> {
+        let mut contents = Vec::with_capacity(paths.len());
+        for path in paths {
+            contents
+                .push(std::fs::read(path).map_err(|e| SubstrateMergingError::Io(e, path.clone()))?);
+        }
+        Ok(contents.windows(2).all(|pair| pair[0] == pair[1]))
+    }
+
+    /// Merges the substrate directories into the output directory.
+    pub fn merge(
+        config: &config::SubstrateMergingConfig,
+    ) -> Result<SubstrateMergingReport, errors::ProcessingError> {
+        let mut files = BTreeMap::<String, Vec<PathBuf>>::new();
+        for directory in [&config.first, &config.second] {
+            let (substrates, substrates_report) = Substrates::prepare(directory)?;
+            substrates_report.report();
+            for substrate in substrates.list() {
+                files.entry(substrate.name.clone()).or_default().push(substrate.path.clone());
+            }
+        }
+
+        std::fs::create_dir_all(&config.output)
+            .map_err(|e| SubstrateMergingError::Io(e, config.output.clone()))?;
+
+        let mut report = SubstrateMergingReport::default();
+        for (name, paths) in files {
+            log::info!(" => {name}");
+            if let [path] = paths.as_slice() {
+                Self::copy(path, &config.output)?;
+                report.add_copied(name);
+            } else if Self::all_identical(&paths)? {
+                Self::copy(&paths[0], &config.output)?;
+                report.add_identical(name);
+            } else if let Some(mut merged) = condensing::merge_substrate_files(&name, &paths)? {
+                let sources =
+                    paths.iter().map(|path| utils::describe_file(path)).collect::<Vec<_>>();
+                let description = merged.substrate.meta.description.take().unwrap_or_default();
+                merged.substrate.meta.description =
+                    Some(format!("{description} Merged from: {}.", sources.join(", ")));
+
+                let path = config.output.join(&name).with_extension(merged.extension.as_str());
+                merged.substrate.sort();
+                merged.substrate.save(&path).map_err(SubstrateMergingError::from)?;
+                report.add_merged(name, &merged);
+            } else {
+                Self::copy(&paths[0], &config.output)?;
+                report.add_conflict(name);
+            }
+        }
+        Ok(report)
+    }
+
+    pub fn run(config: &config::SubstrateMergingConfig) -> Result<(), errors::ProcessingError> {
+        let report = Self::merge(config)?;
+        report.report();
+        if report.has_conflicts() {
+            log::warn!("Substrates merged with conflicts");
+        } else {
+            log::info!("Substrates merged without conflicts");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transpaer_testgen::{ProducerBuilder, ProductBuilder, SubstrateBuilder, SubstrateDir};
+
+    use super::*;
+
+    #[test]
+    fn merge_directories() {
+        let first = SubstrateDir::with([
+            SubstrateBuilder::reviewer("tco")
+                .producer(ProducerBuilder::new("1").name("Fairphone"))
+                .product(ProductBuilder::new("10").name("Fairphone 5")),
+            SubstrateBuilder::cataloger("wikidata"),
+            SubstrateBuilder::cataloger("custom"),
+        ])
+        .unwrap();
+        let second = SubstrateDir::with([
+            SubstrateBuilder::reviewer("tco")
+                .producer(ProducerBuilder::new("1").name("Fairphone"))
+                .producer(ProducerBuilder::new("2").name("Shift"))
+                .product(ProductBuilder::new("10").name("Fairphone 5"))
+                .product(ProductBuilder::new("11").name("Shift 6mq")),
+            SubstrateBuilder::cataloger("custom")
+                .producer(ProducerBuilder::new("3").name("Patagonia")),
+        ])
+        .unwrap();
+        let output = tempfile::tempdir().unwrap();
+
+        let config = config::SubstrateMergingConfig {
+            first: first.path().to_owned(),
+            second: second.path().to_owned(),
+            output: output.path().join("merged"),
+        };
+        let report = SubstrateMergingRunner::merge(&config).unwrap();
+
+        assert_eq!(report.copied, BTreeSet::from(["wikidata".to_owned()]));
+        assert_eq!(report.conflicts, BTreeSet::from(["custom".to_owned()]));
+        let summary = &report.merged["tco"];
+        assert_eq!(summary.num_merged_producers, 1);
+        assert_eq!(summary.duplicated_products, BTreeSet::from(["10".to_owned()]));
+        assert!(report.has_conflicts());
+
+        let (substrates, _) = Substrates::prepare(&config.output).unwrap();
+        let mut names: Vec<_> = substrates.list().iter().map(|s| s.name.clone()).collect();
+        names.sort();
+        assert_eq!(names, vec!["custom", "tco", "wikidata"]);
+    }
+}
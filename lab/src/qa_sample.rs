@@ -0,0 +1,326 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `qa-sample` command.
+//!
+//! Most products come from a few big sources, so problems introduced by a small source are easy
+//! to miss when reviewing random products. This command samples products separately for every
+//! source which contributed to them and renders short summaries for review by curators.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use serde::Serialize;
+
+use transpaer_collecting::errors::{MapIo, MapSerde};
+use transpaer_models::{buckets::DbStore, store};
+
+use crate::{config, errors};
+
+/// Returns the fields of the product each of the sources contributed to.
+fn contributions(product: &store::Product) -> BTreeMap<store::Source, BTreeSet<&'static str>> {
+    let mut result = BTreeMap::<store::Source, BTreeSet<&'static str>>::new();
+    let mut add = |field: &'static str, sources: &[store::Source]| {
+        for source in sources {
+            result.entry(source.clone()).or_default().insert(field);
+        }
+    };
+
+    for id in &product.ids.wiki {
+        add("wiki_ids", &id.sources);
+    }
+    for id in &product.ids.gtins {
+        add("gtins", &id.sources);
+    }
+    for id in &product.ids.eans {
+        add("eans", &id.sources);
+    }
+    for name in &product.names {
+        add("names", &name.sources);
+    }
+    for description in &product.descriptions {
+        add("descriptions", &description.sources);
+    }
+    for summary in &product.summaries {
+        add("summaries", &summary.sources);
+    }
+    for category in &product.categories {
+        add("categories", &category.sources);
+    }
+    for image in &product.images {
+        add("images", std::slice::from_ref(&image.source));
+    }
+    for origin in &product.origins {
+        add("origins", &origin.sources);
+    }
+    for manufacturer in &product.manufacturers {
+        add("manufacturers", &manufacturer.sources);
+    }
+    for entry in &product.shopping {
+        add("shopping", &entry.sources);
+    }
+    for medium in &product.media {
+        add("media", std::slice::from_ref(&medium.source));
+    }
+    for report in &product.reports {
+        add("reports", &report.sources);
+    }
+    let availability: Vec<store::Source> = product.availability.sources.iter().cloned().collect();
+    add("availability", &availability);
+
+    result
+}
+
+/// Picks a fixed number of products per source.
+///
+/// Every product gets a pseudo-random key derived from the seed, the source and the product ID and
+/// the products with the smallest keys are kept. This way the sample does not depend on the order
+/// of the products in the database and stays the same between runs with the same seed.
+struct Sampler {
+    seed: u64,
+    per_source: usize,
+    samples: BTreeMap<store::Source, (usize, BinaryHeap<(u64, store::ProductId)>)>,
+}
+
+impl Sampler {
+    fn new(seed: u64, per_source: usize) -> Self {
+        Self { seed, per_source, samples: BTreeMap::new() }
+    }
+
+    fn add(&mut self, source: &store::Source, id: &store::ProductId) {
+        let mut hasher = DefaultHasher::new();
+        (self.seed, source, id).hash(&mut hasher);
+        let key = hasher.finish();
+
+        let (count, heap) = self.samples.entry(source.clone()).or_default();
+        *count += 1;
+        heap.push((key, id.clone()));
+        if heap.len() > self.per_source {
+            heap.pop();
+        }
+    }
+
+    /// Returns the number of all the products and the sampled product IDs per source.
+    fn finish(self) -> BTreeMap<store::Source, (usize, Vec<store::ProductId>)> {
+        self.samples
+            .into_iter()
+            .map(|(source, (count, heap))| {
+                let ids = heap.into_sorted_vec().into_iter().map(|(_, id)| id).collect();
+                (source, (count, ids))
+            })
+            .collect()
+    }
+}
+
+/// Formats a value together with the labels of its sources.
+fn sourced(value: &str, sources: &[store::Source]) -> String {
+    let labels: Vec<String> = sources.iter().map(store::Source::to_label).collect();
+    format!("{value} ({})", labels.join(", "))
+}
+
+/// Readable summary of a sampled product.
+#[derive(Serialize, Debug)]
+struct ProductSummary {
+    /// ID assigned during coagulation.
+    id: store::ProductId,
+
+    /// IDs which stay the same across dataset versions.
+    refs: Vec<store::ProductRef>,
+
+    /// Fields to which the sampled source contributed.
+    contributed: BTreeSet<&'static str>,
+
+    /// Names with their sources.
+    names: Vec<String>,
+
+    /// Descriptions with their sources.
+    descriptions: Vec<String>,
+
+    /// Summaries with their sources.
+    summaries: Vec<String>,
+
+    /// Categories with their sources.
+    categories: Vec<String>,
+
+    /// DB IDs of the manufacturers.
+    manufacturers: Vec<store::OrganisationId>,
+
+    /// Names of the certifications.
+    certifications: Vec<&'static str>,
+
+    /// Shopping links with their sources.
+    shopping: Vec<String>,
+
+    /// Total Transpaer score.
+    score: f64,
+}
+
+impl ProductSummary {
+    fn new(id: store::ProductId, product: &store::Product, source: &store::Source) -> Self {
+        let texts = |texts: &[store::Text]| -> Vec<String> {
+            texts.iter().map(|text| sourced(&text.text, &text.sources)).collect()
+        };
+        let certifications = [
+            ("bcorp", product.certifications.bcorp.is_some()),
+            ("eu_ecolabel", product.certifications.eu_ecolabel.is_some()),
+            ("fti", product.certifications.fti.is_some()),
+            ("tco", product.certifications.tco.is_some()),
+        ];
+
+        Self {
+            id,
+            refs: product.ids.to_refs(),
+            contributed: contributions(product).remove(source).unwrap_or_default(),
+            names: texts(&product.names),
+            descriptions: texts(&product.descriptions),
+            summaries: texts(&product.summaries),
+            categories: texts(&product.categories),
+            manufacturers: product.manufacturers.iter().map(|m| m.id.clone()).collect(),
+            certifications: certifications
+                .into_iter()
+                .filter_map(|(name, given)| given.then_some(name))
+                .collect(),
+            shopping: product
+                .shopping
+                .iter()
+                .map(|entry| sourced(&entry.shop.link(&entry.id), &entry.sources))
+                .collect(),
+            score: product.transpaer.score.total,
+        }
+    }
+}
+
+/// Sample of products of a single source.
+#[derive(Serialize, Debug)]
+struct SourceSample {
+    /// Number of all the products the source contributed to.
+    products: usize,
+
+    /// The sampled products.
+    sample: Vec<ProductSummary>,
+}
+
+pub struct QaSampleRunner;
+
+impl QaSampleRunner {
+    /// Samples products of every source from the database.
+    fn sample(
+        store: &DbStore,
+        seed: u64,
+        per_source: usize,
+    ) -> Result<BTreeMap<String, SourceSample>, errors::ProcessingError> {
+        let bucket = store.get_product_bucket()?;
+        let mut sampler = Sampler::new(seed, per_source);
+        for item in bucket.iter() {
+            let (id, product) = item?;
+            for source in contributions(&product).keys() {
+                sampler.add(source, &id);
+            }
+        }
+
+        let mut report = BTreeMap::new();
+        for (source, (products, ids)) in sampler.finish() {
+            let mut sample = Vec::with_capacity(ids.len());
+            for id in ids {
+                if let Some(product) = bucket.get(&id)? {
+                    sample.push(ProductSummary::new(id, &product, &source));
+                }
+            }
+            report.insert(source.to_label(), SourceSample { products, sample });
+        }
+        Ok(report)
+    }
+
+    pub fn run(config: &config::QaSampleConfig) -> Result<(), errors::ProcessingError> {
+        let store = DbStore::new(&config.crystal)?;
+
+        log::info!("Sampling products");
+        let report = Self::sample(&store, config.seed, config.per_source)?;
+        for (source, sample) in &report {
+            log::info!(" - {source}: {} of {} products", sample.sample.len(), sample.products);
+        }
+
+        let contents = serde_yaml::to_string(&report).map_serde()?;
+        std::fs::write(&config.output, contents).map_with_path(&config.output)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transpaer_models::gather;
+
+    use super::*;
+
+    fn product(name: &str, sources: Vec<store::Source>) -> store::Product {
+        let mut product = gather::Product::default().store();
+        product.names = vec![store::Text { text: name.to_owned(), sources, language: None }];
+        product
+    }
+
+    #[test]
+    fn source_contributions() {
+        let mut product = product("Fairphone 4", vec![store::Source::Wikidata, store::Source::Tco]);
+        product.categories = vec![store::Text {
+            text: "smartphone".to_owned(),
+            sources: vec![store::Source::Wikidata],
+            language: None,
+        }];
+
+        let contributions = contributions(&product);
+        assert_eq!(contributions.len(), 2);
+        assert_eq!(
+            contributions[&store::Source::Wikidata],
+            BTreeSet::from(["names", "categories"])
+        );
+        assert_eq!(contributions[&store::Source::Tco], BTreeSet::from(["names"]));
+    }
+
+    /// The sample is limited, independent of the insertion order and changes with the seed.
+    #[test]
+    fn deterministic_sampling() {
+        let ids: Vec<store::ProductId> = (0..100).map(store::ProductId::from_value).collect();
+        let sample = |seed: u64, ids: &[store::ProductId]| {
+            let mut sampler = Sampler::new(seed, 5);
+            for id in ids {
+                sampler.add(&store::Source::Wikidata, id);
+            }
+            sampler.finish().remove(&store::Source::Wikidata).unwrap()
+        };
+
+        let (count, sampled) = sample(1, &ids);
+        assert_eq!(count, 100);
+        assert_eq!(sampled.len(), 5);
+
+        let reversed: Vec<store::ProductId> = ids.iter().rev().cloned().collect();
+        assert_eq!(sample(1, &reversed).1, sampled);
+        assert_ne!(sample(2, &ids).1, sampled);
+    }
+
+    #[test]
+    fn sample_per_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DbStore::new(dir.path()).unwrap();
+        let products = store.get_product_bucket().unwrap();
+        for i in 0..10 {
+            let product = product(&format!("Product {i}"), vec![store::Source::OpenFoodFacts]);
+            products.insert(&store::ProductId::from_value(i), &product).unwrap();
+        }
+        let fairphone = product("Fairphone 4", vec![store::Source::Tco]);
+        products.insert(&store::ProductId::from_value(10), &fairphone).unwrap();
+        products.flush().unwrap();
+
+        let report = QaSampleRunner::sample(&store, 0, 3).unwrap();
+        assert_eq!(report.keys().collect::<Vec<_>>(), ["open_food_facts", "tco"]);
+        assert_eq!(report["open_food_facts"].products, 10);
+        assert_eq!(report["open_food_facts"].sample.len(), 3);
+
+        let tco = &report["tco"];
+        assert_eq!(tco.products, 1);
+        assert_eq!(tco.sample[0].names, ["Fairphone 4 (tco)"]);
+        assert_eq!(tco.sample[0].contributed, BTreeSet::from(["names"]));
+    }
+}
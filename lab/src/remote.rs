@@ -0,0 +1,280 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Streaming of big input files (like the Wikidata dump) from HTTP(S) or S3 URLs.
+
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const USER_AGENT: &str = "transpaer-lab";
+
+/// Number of consecutive failed attempts after which the download is given up.
+const MAX_RETRIES: u32 = 10;
+
+/// Delay before the first retry. Each following retry waits proportionally longer.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+const HTTP_SCHEMES: &[&str] = &["http://", "https://"];
+const S3_SCHEME: &str = "s3://";
+
+/// Checks if the location is a URL supported by `RemoteReader`.
+#[must_use]
+pub fn is_remote(location: &str) -> bool {
+    HTTP_SCHEMES.iter().chain(&[S3_SCHEME]).any(|scheme| location.starts_with(scheme))
+}
+
+/// Translates `s3://bucket/key` URLs to HTTPS URLs of the object.
+///
+/// Only publicly readable objects are supported as the requests are not signed.
+#[must_use]
+pub fn resolve_url(location: &str) -> String {
+    match location.strip_prefix(S3_SCHEME).and_then(|rest| rest.split_once('/')) {
+        Some((bucket, key)) => format!("https://{bucket}.s3.amazonaws.com/{key}"),
+        None => location.to_owned(),
+    }
+}
+
+/// Converts an HTTP date (e.g. `Wed, 21 Oct 2015 07:28:00 GMT`) to the `YYYY-MM-DD` format.
+fn parse_http_date(date: &str) -> Option<String> {
+    const MONTHS: [&str; 12] =
+        ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    let mut parts = date.split_whitespace().skip(1);
+    let day: u8 = parts.next()?.parse().ok()?;
+    let month = MONTHS.iter().position(|month| Some(*month) == parts.next())? + 1;
+    let year: u16 = parts.next()?.parse().ok()?;
+    Some(format!("{year:04}-{month:02}-{day:02}"))
+}
+
+fn to_io_error(err: reqwest::Error) -> std::io::Error {
+    std::io::Error::other(err)
+}
+
+/// Builds the HTTP client.
+///
+/// Blocking requests must not be made from within an async runtime, so the client is used only
+/// from the producer threads or from a dedicated thread.
+fn build_client() -> std::io::Result<reqwest::blocking::Client> {
+    reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(None)
+        .build()
+        .map_err(to_io_error)
+}
+
+/// Metadata of a remote file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteMetadata {
+    /// Size of the file in bytes.
+    pub length: u64,
+
+    /// Date of the last modification in the `YYYY-MM-DD` format.
+    pub date: String,
+}
+
+/// Reads the size and the modification date of a remote file.
+pub fn metadata(url: &str) -> std::io::Result<RemoteMetadata> {
+    let url = resolve_url(url);
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let response = build_client()?
+                    .head(&url)
+                    .send()
+                    .and_then(reqwest::blocking::Response::error_for_status)
+                    .map_err(to_io_error)?;
+                let invalid = |header| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("missing or invalid `{header}` header of `{url}`"),
+                    )
+                };
+                let length = response.content_length().ok_or_else(|| invalid("Content-Length"))?;
+                let date = response
+                    .headers()
+                    .get(reqwest::header::LAST_MODIFIED)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(parse_http_date)
+                    .ok_or_else(|| invalid("Last-Modified"))?;
+                Ok(RemoteMetadata { length, date })
+            })
+            .join()
+            .unwrap_or_else(|_| Err(std::io::Error::other("metadata request panicked")))
+    })
+}
+
+/// Copy of the downloaded data saved to the local disk.
+///
+/// The data are written to a temporary file which is renamed once the download completes.
+struct LocalCopy {
+    writer: std::io::BufWriter<std::fs::File>,
+    tmp_path: PathBuf,
+    path: PathBuf,
+}
+
+impl LocalCopy {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".part");
+        let tmp_path = PathBuf::from(tmp_path);
+        let writer = std::io::BufWriter::new(std::fs::File::create(&tmp_path)?);
+        Ok(Self { writer, tmp_path, path: path.to_owned() })
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        std::fs::rename(&self.tmp_path, &self.path)?;
+        log::info!("Saved the downloaded copy to `{}`", self.path.display());
+        Ok(())
+    }
+}
+
+/// Streams a remote file.
+///
+/// If the connection breaks, the download is resumed from the last read byte using an HTTP range
+/// request. Optionally the downloaded data are saved to the local disk on the fly.
+pub struct RemoteReader {
+    url: String,
+    client: Option<reqwest::blocking::Client>,
+    response: Option<reqwest::blocking::Response>,
+    length: Option<u64>,
+    position: u64,
+    failures: u32,
+    copy: Option<LocalCopy>,
+}
+
+impl RemoteReader {
+    /// Constructs a new `RemoteReader`.
+    ///
+    /// No request is sent until the first read.
+    pub fn new(url: &str, copy_path: Option<&Path>) -> std::io::Result<Self> {
+        let copy = copy_path.map(LocalCopy::create).transpose()?;
+        Ok(Self {
+            url: resolve_url(url),
+            client: None,
+            response: None,
+            length: None,
+            position: 0,
+            failures: 0,
+            copy,
+        })
+    }
+
+    /// Sends a request for the data starting from the current position.
+    fn connect(&mut self) -> std::io::Result<reqwest::blocking::Response> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let client = build_client()?;
+                self.client = Some(client.clone());
+                client
+            }
+        };
+
+        let mut request = client.get(&self.url);
+        if self.position > 0 {
+            log::info!("Resuming download of `{}` from byte {}", self.url, self.position);
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", self.position));
+        }
+
+        let response = request
+            .send()
+            .and_then(reqwest::blocking::Response::error_for_status)
+            .map_err(to_io_error)?;
+        if self.position > 0 && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("`{}` does not support range requests", self.url),
+            ));
+        }
+        if self.length.is_none() {
+            self.length = response.content_length();
+        }
+        Ok(response)
+    }
+
+    /// Reads from the current response connecting first if needed.
+    ///
+    /// A premature end of the data is reported as an error so that the download is resumed.
+    fn try_read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.response.is_none() {
+            self.response = Some(self.connect()?);
+        }
+
+        let Some(response) = self.response.as_mut() else {
+            return Ok(0);
+        };
+        let read = response.read(buf)?;
+        if read == 0 && !buf.is_empty() && self.length.is_some_and(|len| self.position < len) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("connection closed at byte {}", self.position),
+            ));
+        }
+        Ok(read)
+    }
+}
+
+impl Read for RemoteReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            match self.try_read(buf) {
+                Ok(read) => {
+                    self.failures = 0;
+                    self.position += read as u64;
+                    if read == 0 {
+                        if let Some(copy) = self.copy.take() {
+                            copy.finish()?;
+                        }
+                    } else if let Some(copy) = &mut self.copy {
+                        copy.writer.write_all(&buf[..read])?;
+                    }
+                    return Ok(read);
+                }
+                Err(err) if self.failures < MAX_RETRIES => {
+                    self.failures += 1;
+                    self.response = None;
+                    log::warn!(
+                        "Reading `{}` failed (attempt {}/{MAX_RETRIES}): {err}",
+                        self.url,
+                        self.failures,
+                    );
+                    std::thread::sleep(RETRY_DELAY * self.failures);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_urls() {
+        assert!(is_remote("https://dumps.wikimedia.org/wikidatawiki/entities/latest-all.json.gz"));
+        assert!(is_remote("s3://dumps/wikidata/latest-all.json.bz2"));
+        assert!(!is_remote("/data/origin/wikidata.json.gz"));
+
+        assert_eq!(
+            resolve_url("s3://dumps/wikidata/latest-all.json.bz2"),
+            "https://dumps.s3.amazonaws.com/wikidata/latest-all.json.bz2"
+        );
+        assert_eq!(
+            resolve_url("https://example.com/dump.json.gz"),
+            "https://example.com/dump.json.gz"
+        );
+    }
+
+    #[test]
+    fn http_dates() {
+        assert_eq!(parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT"), Some("2015-10-21".to_owned()));
+        assert_eq!(parse_http_date("Mon, 3 Feb 2025 00:00:00 GMT"), Some("2025-02-03".to_owned()));
+        assert_eq!(parse_http_date("yesterday"), None);
+    }
+}
@@ -0,0 +1,406 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `export-research` command.
+//!
+//! Exports the database into a tarball of JSON-lines files meant to be cited in research.
+//! Internal database IDs are reassigned during every coagulation, so the records are identified
+//! with external IDs instead (e.g. `wiki:Q123` or `gtin:00012345678905`), which stay the same
+//! across dataset versions.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use transpaer_models::{buckets::DbStore, store};
+
+use crate::{config, errors};
+
+/// Name of the dataset in the manifest and of the directory in the tarball.
+const DATASET_NAME: &str = "transpaer";
+
+/// Name of the manifest file.
+const MANIFEST_FILE: &str = "DATASET.json";
+
+const PRODUCTS_FILE: &str = "products.jsonl";
+const ORGANISATIONS_FILE: &str = "organisations.jsonl";
+const CERTIFICATIONS_FILE: &str = "certifications.jsonl";
+const MAPPINGS_FILE: &str = "mappings.jsonl";
+
+/// Returns the stable ID of a product.
+///
+/// Wikidata IDs are preferred over GTINs and GTINs over EANs. The smallest ID of the preferred
+/// kind is picked, so the choice does not depend on the order in which sources were merged.
+fn product_stable_id(ids: &store::ProductIds) -> Option<String> {
+    if let Some(id) = ids.wiki.iter().map(|id| id.id).min() {
+        return Some(format!("wiki:Q{}", id.to_canonical_string()));
+    }
+    if let Some(id) = ids.gtins.iter().map(|id| id.id.to_canonical_string()).min() {
+        return Some(format!("gtin:{id}"));
+    }
+    ids.eans.iter().map(|id| id.id.to_canonical_string()).min().map(|id| format!("ean:{id}"))
+}
+
+/// Returns the stable ID of an organisation.
+///
+/// Wikidata IDs are preferred over VAT IDs and VAT IDs over web domains.
+fn organisation_stable_id(ids: &store::OrganisationIds) -> Option<String> {
+    if let Some(id) = ids.wiki.iter().map(|id| id.id).min() {
+        return Some(format!("wiki:Q{}", id.to_canonical_string()));
+    }
+    if let Some(id) = ids.vat_ids.iter().map(|id| id.id.to_canonical_string()).min() {
+        return Some(format!("vat:{id}"));
+    }
+    ids.domains.iter().map(|domain| &domain.website).min().map(|domain| format!("domain:{domain}"))
+}
+
+/// Product record.
+#[derive(Serialize, Debug)]
+struct ResearchProduct {
+    id: String,
+    names: Vec<String>,
+    descriptions: Vec<String>,
+    categories: Vec<String>,
+    origins: Vec<String>,
+    manufacturers: Vec<String>,
+    score: f64,
+}
+
+/// Organisation record.
+#[derive(Serialize, Debug)]
+struct ResearchOrganisation {
+    id: String,
+    names: Vec<String>,
+    descriptions: Vec<String>,
+    websites: Vec<String>,
+    origins: Vec<String>,
+}
+
+/// Kind of a record.
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum Subject {
+    Product,
+    Organisation,
+}
+
+/// Certifications of a product or an organisation.
+#[derive(Serialize, Debug)]
+struct ResearchCertification<'a> {
+    id: &'a str,
+    subject: Subject,
+    certifications: &'a store::Certifications,
+}
+
+/// Mapping from a stable ID to one of the external IDs of the same entry.
+#[derive(Serialize, Debug)]
+struct ResearchMapping<'a> {
+    id: &'a str,
+    subject: Subject,
+    scheme: &'static str,
+    value: String,
+}
+
+/// Description of a file in the manifest.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+struct ManifestFile {
+    path: String,
+    records: usize,
+    sha256: String,
+}
+
+/// Manifest describing the dataset.
+#[derive(Serialize, Debug)]
+struct Manifest {
+    name: &'static str,
+    version: String,
+    license: String,
+    created: String,
+    generator: String,
+    sources: Vec<String>,
+    files: Vec<ManifestFile>,
+}
+
+/// Writes JSON-lines records while calculating the checksum of the file.
+struct RecordWriter {
+    path: PathBuf,
+    writer: std::io::BufWriter<std::fs::File>,
+    hasher: Sha256,
+    records: usize,
+}
+
+impl RecordWriter {
+    fn create(path: PathBuf) -> Result<Self, errors::ProcessingError> {
+        let file = std::fs::File::create(&path)
+            .map_err(|e| errors::ProcessingError::Io(e, path.clone()))?;
+        Ok(Self { path, writer: std::io::BufWriter::new(file), hasher: Sha256::new(), records: 0 })
+    }
+
+    fn write<T: Serialize>(&mut self, record: &T) -> Result<(), errors::ProcessingError> {
+        let mut line = serde_json::to_vec(record).map_err(errors::ProcessingError::WriteJson)?;
+        line.push(b'\n');
+        self.hasher.update(&line);
+        self.writer
+            .write_all(&line)
+            .map_err(|e| errors::ProcessingError::Io(e, self.path.clone()))?;
+        self.records += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<ManifestFile, errors::ProcessingError> {
+        self.writer.flush().map_err(|e| errors::ProcessingError::Io(e, self.path.clone()))?;
+        let path = self.path.file_name().map(|name| name.to_string_lossy().into_owned());
+        Ok(ManifestFile {
+            path: path.unwrap_or_default(),
+            records: self.records,
+            sha256: format!("{:x}", self.hasher.finalize()),
+        })
+    }
+}
+
+/// Writes the dataset files into a staging directory.
+struct ResearchExporter<'a> {
+    store: &'a DbStore,
+    dir: &'a Path,
+}
+
+impl<'a> ResearchExporter<'a> {
+    fn new(store: &'a DbStore, dir: &'a Path) -> Self {
+        Self { store, dir }
+    }
+
+    fn writer(&self, name: &str) -> Result<RecordWriter, errors::ProcessingError> {
+        RecordWriter::create(self.dir.join(name))
+    }
+
+    /// Writes all the data files and returns their descriptions.
+    fn export(&self) -> Result<Vec<ManifestFile>, errors::ProcessingError> {
+        let mut certifications = self.writer(CERTIFICATIONS_FILE)?;
+        let mut mappings = self.writer(MAPPINGS_FILE)?;
+
+        log::info!("Exporting organisations");
+        let mut organisations = self.writer(ORGANISATIONS_FILE)?;
+        let mut organisation_ids = HashMap::<store::OrganisationId, String>::new();
+        let mut num_skipped = 0;
+        for item in self.store.get_organisation_bucket()?.iter() {
+            let (organisation_id, organisation) = item?;
+            let Some(id) = organisation_stable_id(&organisation.ids) else {
+                num_skipped += 1;
+                continue;
+            };
+
+            organisations.write(&ResearchOrganisation {
+                id: id.clone(),
+                names: texts(&organisation.names),
+                descriptions: texts(&organisation.descriptions),
+                websites: organisation.websites.iter().map(|w| w.website.clone()).collect(),
+                origins: organisation
+                    .origins
+                    .iter()
+                    .map(|c| c.country.alpha3().to_owned())
+                    .collect(),
+            })?;
+            if organisation.certifications.get_num() > 0 {
+                certifications.write(&ResearchCertification {
+                    id: &id,
+                    subject: Subject::Organisation,
+                    certifications: &organisation.certifications,
+                })?;
+            }
+
+            let subject = Subject::Organisation;
+            for wiki in &organisation.ids.wiki {
+                let value = format!("Q{}", wiki.id.to_canonical_string());
+                mappings.write(&ResearchMapping { id: &id, subject, scheme: "wiki", value })?;
+            }
+            for vat in &organisation.ids.vat_ids {
+                let value = vat.id.to_canonical_string();
+                mappings.write(&ResearchMapping { id: &id, subject, scheme: "vat", value })?;
+            }
+            for domain in &organisation.ids.domains {
+                let value = domain.website.clone();
+                mappings.write(&ResearchMapping { id: &id, subject, scheme: "domain", value })?;
+            }
+
+            organisation_ids.insert(organisation_id, id);
+        }
+        log::info!("    skipped {num_skipped} organisations without external IDs");
+
+        log::info!("Exporting products");
+        let mut products = self.writer(PRODUCTS_FILE)?;
+        let mut num_skipped = 0;
+        for item in self.store.get_product_bucket()?.iter() {
+            let (_, product) = item?;
+            let Some(id) = product_stable_id(&product.ids) else {
+                num_skipped += 1;
+                continue;
+            };
+
+            products.write(&ResearchProduct {
+                id: id.clone(),
+                names: texts(&product.names),
+                descriptions: texts(&product.descriptions),
+                categories: texts(&product.categories),
+                origins: product.origins.iter().map(|c| c.country.alpha3().to_owned()).collect(),
+                manufacturers: product
+                    .manufacturers
+                    .iter()
+                    .filter_map(|manufacturer| organisation_ids.get(&manufacturer.id).cloned())
+                    .collect(),
+                score: product.transpaer.score.total,
+            })?;
+            if product.certifications.get_num() > 0 {
+                certifications.write(&ResearchCertification {
+                    id: &id,
+                    subject: Subject::Product,
+                    certifications: &product.certifications,
+                })?;
+            }
+
+            let subject = Subject::Product;
+            for wiki in &product.ids.wiki {
+                let value = format!("Q{}", wiki.id.to_canonical_string());
+                mappings.write(&ResearchMapping { id: &id, subject, scheme: "wiki", value })?;
+            }
+            for gtin in &product.ids.gtins {
+                let value = gtin.id.to_canonical_string();
+                mappings.write(&ResearchMapping { id: &id, subject, scheme: "gtin", value })?;
+            }
+            for ean in &product.ids.eans {
+                let value = ean.id.to_canonical_string();
+                mappings.write(&ResearchMapping { id: &id, subject, scheme: "ean", value })?;
+            }
+        }
+        log::info!("    skipped {num_skipped} products without external IDs");
+
+        Ok(vec![
+            products.finish()?,
+            organisations.finish()?,
+            certifications.finish()?,
+            mappings.finish()?,
+        ])
+    }
+
+    /// Returns names of the substrates the database was crystalized from.
+    fn sources(&self) -> Result<Vec<String>, errors::ProcessingError> {
+        let mut sources = Vec::new();
+        for item in self.store.get_substrate_meta_bucket()?.iter() {
+            let (name, _meta) = item?;
+            sources.push(name);
+        }
+        sources.sort();
+        Ok(sources)
+    }
+}
+
+fn texts(texts: &[store::Text]) -> Vec<String> {
+    texts.iter().map(|text| text.text.clone()).collect()
+}
+
+/// Packs the files from the staging directory into a gzipped tarball.
+fn pack(dir: &Path, prefix: &str, output: &Path) -> Result<(), errors::ProcessingError> {
+    let file = std::fs::File::create(output)
+        .map_err(|e| errors::ProcessingError::Io(e, output.to_owned()))?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for name in
+        [MANIFEST_FILE, PRODUCTS_FILE, ORGANISATIONS_FILE, CERTIFICATIONS_FILE, MAPPINGS_FILE]
+    {
+        let path = dir.join(name);
+        builder
+            .append_path_with_name(&path, format!("{prefix}/{name}"))
+            .map_err(|e| errors::ProcessingError::Io(e, path))?;
+    }
+    builder
+        .into_inner()
+        .and_then(flate2::write::GzEncoder::finish)
+        .map_err(|e| errors::ProcessingError::Io(e, output.to_owned()))?;
+    Ok(())
+}
+
+pub struct ResearchExportRunner;
+
+impl ResearchExportRunner {
+    pub fn run(config: &config::ResearchExportConfig) -> Result<(), errors::ProcessingError> {
+        let store = DbStore::new(&config.crystal)?;
+
+        let staging = config.output.with_extension("staging");
+        std::fs::create_dir_all(&staging)
+            .map_err(|e| errors::ProcessingError::Io(e, staging.clone()))?;
+
+        let exporter = ResearchExporter::new(&store, &staging);
+        let files = exporter.export()?;
+        for file in &files {
+            log::info!("    {}: {} records", file.path, file.records);
+        }
+
+        let manifest = Manifest {
+            name: DATASET_NAME,
+            version: config.version.clone(),
+            license: config.license.clone(),
+            created: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            generator: format!("transpaer-lab {}", env!("CARGO_PKG_VERSION")),
+            sources: exporter.sources()?,
+            files,
+        };
+        let path = staging.join(MANIFEST_FILE);
+        let contents =
+            serde_json::to_string_pretty(&manifest).map_err(errors::ProcessingError::WriteJson)?;
+        std::fs::write(&path, contents).map_err(|e| errors::ProcessingError::Io(e, path))?;
+
+        log::info!("Packing into `{}`", config.output.display());
+        pack(&staging, &format!("{DATASET_NAME}-{}", config.version), &config.output)?;
+        std::fs::remove_dir_all(&staging).map_err(|e| errors::ProcessingError::Io(e, staging))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wiki(id: u64) -> store::SourcedWikiId {
+        store::SourcedWikiId { id: store::WikiId::new(id), sources: Vec::new() }
+    }
+
+    fn gtin(id: &str) -> store::SourcedGtin {
+        store::SourcedGtin { id: store::Gtin::try_from(id).unwrap(), sources: Vec::new() }
+    }
+
+    #[test]
+    fn stable_ids() {
+        let mut ids = store::ProductIds {
+            eans: Vec::new(),
+            gtins: vec![gtin("5901234123457"), gtin("4006381333931")],
+            wiki: Vec::new(),
+        };
+        assert_eq!(product_stable_id(&ids).as_deref(), Some("gtin:04006381333931"));
+
+        ids.wiki = vec![wiki(7), wiki(3)];
+        assert_eq!(product_stable_id(&ids).as_deref(), Some("wiki:Q3"));
+
+        let ids = store::ProductIds { eans: Vec::new(), gtins: Vec::new(), wiki: Vec::new() };
+        assert_eq!(product_stable_id(&ids), None);
+    }
+
+    #[test]
+    fn record_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RecordWriter::create(dir.path().join("test.jsonl")).unwrap();
+        writer.write(&serde_json::json!({"id": "wiki:Q1"})).unwrap();
+        writer.write(&serde_json::json!({"id": "wiki:Q2"})).unwrap();
+        let file = writer.finish().unwrap();
+
+        let contents = std::fs::read(dir.path().join("test.jsonl")).unwrap();
+        assert_eq!(contents, b"{\"id\":\"wiki:Q1\"}\n{\"id\":\"wiki:Q2\"}\n");
+        assert_eq!(file.path, "test.jsonl");
+        assert_eq!(file.records, 2);
+        assert_eq!(file.sha256, format!("{:x}", Sha256::digest(&contents)));
+    }
+}
@@ -11,8 +11,12 @@ use transpaer_collecting::{eu_ecolabel, open_food_facts, open_food_repo};
 use crate::{
     config, errors,
     parallel::{self, Consumer, Flow, Processor, Producer, Sender},
+    remote,
 };
 
+/// Size of the buffer used when streaming remote input files.
+const STREAM_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+
 pub trait Stash: Send {
     type Input: Clone + Send;
 
@@ -65,7 +69,18 @@ pub struct WikidataProducer {
 impl WikidataProducer {
     /// Constructs a new `WikidataProducer`
     pub fn new(config: &config::WikidataProducerConfig) -> Result<Self, errors::ProcessingError> {
-        Ok(Self { wiki: transpaer_wikidata::dump::Loader::load(&config.wikidata_path)? })
+        let wiki = if let Some(url) = &config.wikidata_url {
+            log::info!("Streaming Wikidata dump from `{url}`");
+            let copy_path = config.keep_download.then_some(config.wikidata_path.as_path());
+            let reader = remote::RemoteReader::new(url, copy_path)
+                .map_err(|e| errors::ProcessingError::Io(e, config.wikidata_path.clone()))?;
+            let reader = std::io::BufReader::with_capacity(STREAM_BUFFER_SIZE, reader);
+            transpaer_wikidata::dump::Loader::from_reader(reader, url)?
+        } else {
+            transpaer_wikidata::dump::Loader::load(&config.wikidata_path)?
+        };
+        let wiki = wiki.with_format(config.format);
+        Ok(Self { wiki })
     }
 }
 
@@ -207,6 +222,84 @@ pub struct OpenFoodFactsRunnerMessage {
     headers: csv::StringRecord,
 }
 
+/// Number of seconds in a day.
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Skips Open Food Facts records not fulfilling the quality requirements.
+///
+/// Many records are stubs without a name or a brand which only add noise to the catalog.
+#[derive(Debug)]
+struct OpenFoodFactsFilter {
+    config: config::OpenFoodFactsFilterConfig,
+
+    /// Records older than this UNIX timestamp are outdated.
+    oldest_timestamp: Option<u64>,
+
+    /// Positions of the checked fields in the records.
+    positions: std::sync::OnceLock<(Option<usize>, Option<usize>)>,
+
+    missing_name: std::sync::atomic::AtomicUsize,
+    too_few_fields: std::sync::atomic::AtomicUsize,
+    outdated: std::sync::atomic::AtomicUsize,
+}
+
+impl OpenFoodFactsFilter {
+    fn new(config: config::OpenFoodFactsFilterConfig, now: std::time::SystemTime) -> Self {
+        let now = now.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs();
+        let oldest_timestamp = config
+            .max_age_days
+            .map(|days| now.saturating_sub(days.saturating_mul(SECONDS_PER_DAY)));
+        Self {
+            config,
+            oldest_timestamp,
+            positions: std::sync::OnceLock::new(),
+            missing_name: std::sync::atomic::AtomicUsize::new(0),
+            too_few_fields: std::sync::atomic::AtomicUsize::new(0),
+            outdated: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// Checks if the record fulfils all the requirements and counts the ones which do not.
+    fn accept(&self, headers: &csv::StringRecord, record: &csv::StringRecord) -> bool {
+        use std::sync::atomic::Ordering;
+
+        let (name_position, modified_position) = *self.positions.get_or_init(|| {
+            let position = |name: &str| headers.iter().position(|header| header == name);
+            (position("product_name"), position("last_modified_t"))
+        });
+        let field = |position: Option<usize>| {
+            position.and_then(|position| record.get(position)).unwrap_or_default().trim()
+        };
+
+        if self.config.require_name && field(name_position).is_empty() {
+            self.missing_name.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        if record.iter().filter(|value| !value.trim().is_empty()).count() < self.config.min_fields {
+            self.too_few_fields.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        if let Some(oldest_timestamp) = self.oldest_timestamp {
+            // Records without a valid modification time are treated as outdated.
+            let modified = field(modified_position).parse::<u64>().unwrap_or(0);
+            if modified < oldest_timestamp {
+                self.outdated.fetch_add(1, Ordering::Relaxed);
+                return false;
+            }
+        }
+        true
+    }
+
+    fn report(&self) {
+        use std::sync::atomic::Ordering;
+
+        log::info!("Filtered out Open Food Facts records:");
+        log::info!(" - missing name: {}", self.missing_name.load(Ordering::Relaxed));
+        log::info!(" - too few fields: {}", self.too_few_fields.load(Ordering::Relaxed));
+        log::info!(" - outdated: {}", self.outdated.load(Ordering::Relaxed));
+    }
+}
+
 /// Implementation of `Producer` trait for Open Food Facts data.
 #[must_use]
 #[derive(Debug)]
@@ -231,16 +324,25 @@ impl Producer for OpenFoodFactsProducer {
 
     async fn produce(self, tx: Sender<Self::Output>) -> Result<(), errors::ProcessingError> {
         let loader = open_food_facts::loader::Loader::load(&self.config.open_food_facts_path)?;
+        let filter = std::sync::Arc::new(OpenFoodFactsFilter::new(
+            self.config.filter,
+            std::time::SystemTime::now(),
+        ));
+        let filter2 = filter.clone();
         let num = loader
             .run(move |headers: csv::StringRecord, record: csv::StringRecord| {
                 let tx2 = tx.clone();
+                let accepted = filter2.accept(&headers, &record);
                 async move {
-                    tx2.send(OpenFoodFactsRunnerMessage { record, headers }).await;
+                    if accepted {
+                        tx2.send(OpenFoodFactsRunnerMessage { record, headers }).await;
+                    }
                 }
             })
             .await?;
 
         log::info!("Read {num} Open Food Facts records");
+        filter.report();
         Ok(())
     }
 }
@@ -724,3 +826,36 @@ where
         Ok(flow)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn open_food_facts_filter() {
+        let now = std::time::UNIX_EPOCH + std::time::Duration::from_secs(100 * SECONDS_PER_DAY);
+        let config = config::OpenFoodFactsFilterConfig {
+            require_name: true,
+            min_fields: 3,
+            max_age_days: Some(10),
+        };
+        let filter = OpenFoodFactsFilter::new(config, now);
+
+        let headers = csv::StringRecord::from(vec!["code", "last_modified_t", "product_name"]);
+        let recent = (95 * SECONDS_PER_DAY).to_string();
+        let old = (50 * SECONDS_PER_DAY).to_string();
+        let record = |values: [&str; 3]| csv::StringRecord::from(values.to_vec());
+
+        assert!(filter.accept(&headers, &record(["123", &recent, "Oat drink"])));
+        assert!(!filter.accept(&headers, &record(["123", &recent, " "])));
+        assert!(!filter.accept(&headers, &record(["", &recent, "Oat drink"])));
+        assert!(!filter.accept(&headers, &record(["123", &old, "Oat drink"])));
+        assert!(!filter.accept(&headers, &record(["123", "unknown", "Oat drink"])));
+
+        assert_eq!(filter.missing_name.load(Ordering::Relaxed), 1);
+        assert_eq!(filter.too_few_fields.load(Ordering::Relaxed), 1);
+        assert_eq!(filter.outdated.load(Ordering::Relaxed), 2);
+    }
+}
@@ -12,7 +12,7 @@ use transpaer_models::{
 
 use swagger::XSpanIdString;
 
-use crate::{config, errors};
+use crate::{config, errors, fetching};
 
 #[derive(Clone, Default)]
 struct Context {}
@@ -120,12 +120,24 @@ impl SamplingRunner {
         if let Some(config) = &config.backend {
             Self::run_with_backend(config).await?;
         }
-        if config.target.is_none() && config.backend.is_none() {
+        if let Some(config) = &config.wikidata {
+            Self::run_with_wikidata(config).await?;
+        }
+        if config.target.is_none() && config.backend.is_none() && config.wikidata.is_none() {
             log::error!("No data source was given");
         }
         Ok(())
     }
 
+    pub async fn run_with_wikidata(
+        config: &config::SamplingWikidataConfig,
+    ) -> Result<(), errors::SamplingError> {
+        log::info!("Fetching entities from Wikidata");
+        let num = fetching::WikidataFetcher::new(config)?.run().await?;
+        log::info!("Saved {num} entities to `{}`", config.wikidata_path.display());
+        Ok(())
+    }
+
     pub fn run_with_store(config: &config::SamplingTargetConfig) {
         log::info!("Verifying the kv store");
 
@@ -164,10 +176,11 @@ impl SamplingRunner {
                 bcorp: Some(models::BCorpCert {
                     id: BCORP_FAIRPHONE_ID.to_owned(),
                     report_url: BCORP_FAIRPHONE_URL.to_owned(),
+                    inherited: true,
                 }),
                 eu_ecolabel: None,
                 fti: None,
-                tco: Some(models::TcoCert { brand_name: "FAIRPHONE".to_owned() }),
+                tco: Some(models::TcoCert { brand_name: "FAIRPHONE".to_owned(), inherited: true }),
             },
             "wrong certifications"
         );
@@ -213,10 +226,11 @@ impl SamplingRunner {
                 bcorp: Some(models::BCorpCert {
                     id: BCORP_FAIRPHONE_ID.to_owned(),
                     report_url: BCORP_FAIRPHONE_URL.to_owned(),
+                    inherited: false,
                 }),
                 eu_ecolabel: None,
                 fti: None,
-                tco: Some(models::TcoCert { brand_name: "FAIRPHONE".to_owned() }),
+                tco: Some(models::TcoCert { brand_name: "FAIRPHONE".to_owned(), inherited: false }),
             },
             "wrong certifications"
         );
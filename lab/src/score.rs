@@ -2,8 +2,23 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use serde::Deserialize;
+
+use transpaer_collecting::errors::{IoOrSerdeError, MapIo};
 use transpaer_models::gather as models;
 
+/// Kind of evidence referring to a product field.
+const FIELD: &str = "field";
+
+/// Kind of evidence referring to a certification.
+const CERTIFICATION: &str = "certification";
+
+/// Kind of evidence referring to a product category.
+const CATEGORY: &str = "category";
+
+/// Number of seconds in a day.
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
 enum ScoreBranch {
     Leaf(models::TranspaerScoreBranch),
     Branch(SubscoreCalculator),
@@ -35,86 +50,362 @@ impl SubscoreCalculator {
             weight: self.weight,
             score,
             branches,
+            evidence: Vec::new(),
         }
     }
 }
 
-#[must_use]
-pub fn calculate(product: &models::Product) -> models::TranspaerScore {
-    let has_producer = !product.manufacturers.is_empty();
-    let has_categories = !product.categories.is_empty();
-    let num_certs = product.certifications.get_num();
+fn evidence(kind: &str, name: &str) -> String {
+    format!("{kind}:{name}")
+}
+
+/// Lists the evidence for the product being identifiable.
+fn id_evidence(ids: &models::ProductIds) -> Vec<String> {
+    let mut result = Vec::new();
+    if !ids.eans.is_empty() {
+        result.push(evidence(FIELD, "ids.eans"));
+    }
+    if !ids.gtins.is_empty() {
+        result.push(evidence(FIELD, "ids.gtins"));
+    }
+    if !ids.wiki.is_empty() {
+        result.push(evidence(FIELD, "ids.wiki"));
+    }
+    result
+}
 
-    let mut category_contributions = Vec::new();
-    if product.categories.contains("smartphone") {
-        category_contributions.push(ScoreBranch::Leaf(models::TranspaerScoreBranch {
-            category: models::TranspaerScoreCategory::WarrantyLength,
-            weight: 1,
-            score: 0.5,
-            branches: vec![],
-        }));
+/// Lists the certifications counted by `Certifications::get_num`.
+fn certification_evidence(certifications: &models::Certifications) -> Vec<String> {
+    let mut result = Vec::new();
+    if certifications.bcorp.is_some() {
+        result.push(evidence(CERTIFICATION, "bcorp"));
     }
+    if certifications.eu_ecolabel.is_some() {
+        result.push(evidence(CERTIFICATION, "eu_ecolabel"));
+    }
+    if certifications.fti.is_some() {
+        result.push(evidence(CERTIFICATION, "fti"));
+    }
+    if certifications.tco.is_some() {
+        result.push(evidence(CERTIFICATION, "tco"));
+    }
+    result
+}
+
+/// Returns the evidence if the condition is satisfied, otherwise no evidence.
+fn evidence_if(condition: bool, evidence: Vec<String>) -> Vec<String> {
+    if condition { evidence } else { Vec::new() }
+}
+
+/// Strategy of calculating Transpaer scores of products.
+pub trait Scorer: std::fmt::Debug + Send + Sync {
+    /// Returns the ID of the scorer recorded in the calculated scores.
+    fn id(&self) -> &'static str;
+
+    /// Calculates the score of the product.
+    fn calculate(&self, product: &models::Product) -> models::TranspaerScore;
+}
+
+/// Selection of the scorer together with its configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "scorer", rename_all = "snake_case")]
+pub enum ScorerConfig {
+    /// Scorer calculating weighted averages over a tree of score categories.
+    Tree(TreeScorerConfig),
+}
+
+impl ScorerConfig {
+    /// Reads the scorer configuration from a YAML file or returns the default one.
+    pub fn load(path: Option<&std::path::Path>) -> Result<Self, IoOrSerdeError> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        let contents = std::fs::read_to_string(path).map_with_path(path)?;
+        serde_yaml::from_str(&contents).map_with_path(path)
+    }
+
+    /// Constructs the configured scorer.
+    #[must_use]
+    pub fn build(&self) -> Box<dyn Scorer> {
+        match self {
+            Self::Tree(config) => {
+                Box::new(TreeScorer::new(config.clone(), crate::utils::now_millis() / 1000))
+            }
+        }
+    }
+}
+
+impl Default for ScorerConfig {
+    fn default() -> Self {
+        Self::Tree(TreeScorerConfig::default())
+    }
+}
+
+/// Configuration of `TreeScorer`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TreeScorerConfig {
+    /// Weight of the data availability subscore.
+    pub data_availability_weight: i32,
+
+    /// Weight of the category-specific subscore.
+    pub category_weight: i32,
+
+    /// Weight of the certifications subscore.
+    pub certifications_weight: i32,
+
+    /// Decay of the data availability subscores with the age of the data.
+    pub freshness_decay: FreshnessDecay,
+}
+
+impl Default for TreeScorerConfig {
+    fn default() -> Self {
+        Self {
+            data_availability_weight: 1,
+            category_weight: 2,
+            certifications_weight: 2,
+            freshness_decay: FreshnessDecay::default(),
+        }
+    }
+}
+
+/// Curve by which old data counts less.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "curve", rename_all = "snake_case")]
+pub enum FreshnessDecay {
+    /// The age of the data is not taken into account.
+    #[default]
+    None,
+
+    /// The data counts half as much after every `half_life_days`.
+    Exponential { half_life_days: f64 },
 
-    let tree = SubscoreCalculator {
-        category: models::TranspaerScoreCategory::Root,
-        weight: 1,
-        branches: vec![
-            ScoreBranch::Branch(SubscoreCalculator {
-                category: models::TranspaerScoreCategory::DataAvailability,
+    /// The data counts linearly less until it does not count at all after `max_age_days`.
+    Linear { max_age_days: f64 },
+}
+
+impl FreshnessDecay {
+    /// Returns the factor from 0.0 to 1.0 by which data of the given age counts.
+    #[must_use]
+    pub fn factor(&self, age_seconds: u64) -> f64 {
+        let days = f64::from(u32::try_from(age_seconds / SECONDS_PER_DAY).unwrap_or(u32::MAX));
+        match self {
+            Self::None => 1.0,
+            Self::Exponential { half_life_days } if *half_life_days > 0.0 => {
+                0.5_f64.powf(days / half_life_days)
+            }
+            Self::Linear { max_age_days } if *max_age_days > 0.0 => {
+                (1.0 - days / max_age_days).clamp(0.0, 1.0)
+            }
+            Self::Exponential { .. } | Self::Linear { .. } => 0.0,
+        }
+    }
+}
+
+/// Scorer calculating weighted averages over a tree of score categories.
+#[derive(Debug, derive_new::new)]
+pub struct TreeScorer {
+    config: TreeScorerConfig,
+
+    /// Time relative to which the age of the data is calculated, in seconds since the Unix epoch.
+    now: u64,
+}
+
+impl TreeScorer {
+    /// ID of this scorer.
+    pub const ID: &'static str = "tree";
+
+    /// Scores availability of a field decaying it with the time since the field was updated.
+    ///
+    /// Fields without a known update time do not decay, and old data never scores lower than
+    /// missing data.
+    fn availability_score(&self, available: bool, updated: Option<u64>) -> f64 {
+        if !available {
+            return 0.5;
+        }
+        let age = updated.map_or(0, |updated| self.now.saturating_sub(updated));
+        0.5 + 0.5 * self.config.freshness_decay.factor(age)
+    }
+}
+
+impl Scorer for TreeScorer {
+    fn id(&self) -> &'static str {
+        Self::ID
+    }
+
+    fn calculate(&self, product: &models::Product) -> models::TranspaerScore {
+        let has_producer = !product.manufacturers.is_empty();
+        let has_categories = !product.categories.is_empty();
+        let has_origins = !product.origins.is_empty();
+        let num_certs = product.certifications.get_num();
+        let certs = certification_evidence(&product.certifications);
+
+        let mut category_contributions = Vec::new();
+        if product.categories.contains("smartphone") {
+            category_contributions.push(ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                category: models::TranspaerScoreCategory::WarrantyLength,
                 weight: 1,
-                branches: vec![
-                    ScoreBranch::Leaf(models::TranspaerScoreBranch {
-                        category: models::TranspaerScoreCategory::ProducerKnown,
-                        weight: 1,
-                        score: if has_producer { 1.0 } else { 0.5 },
-                        branches: vec![],
-                    }),
-                    ScoreBranch::Leaf(models::TranspaerScoreBranch {
-                        category: models::TranspaerScoreCategory::CategoryAssigned,
-                        weight: 1,
-                        score: if has_categories { 1.0 } else { 0.5 },
-                        branches: vec![],
-                    }),
-                    ScoreBranch::Leaf(models::TranspaerScoreBranch {
-                        category: models::TranspaerScoreCategory::ProductionPlaceKnown,
-                        weight: 1,
-                        score: 0.5, // TODO
-                        branches: vec![],
-                    }),
-                    ScoreBranch::Leaf(models::TranspaerScoreBranch {
-                        category: models::TranspaerScoreCategory::IdKnown,
-                        weight: 1,
-                        score: if product.ids.is_empty() { 0.5 } else { 1.0 },
-                        branches: vec![],
-                    }),
-                ],
-            }),
-            ScoreBranch::Branch(SubscoreCalculator {
-                category: models::TranspaerScoreCategory::Category,
-                weight: 2,
-                branches: category_contributions,
-            }),
-            ScoreBranch::Branch(SubscoreCalculator {
-                category: models::TranspaerScoreCategory::NumCerts,
-                weight: 2,
-                branches: vec![
-                    ScoreBranch::Leaf(models::TranspaerScoreBranch {
-                        category: models::TranspaerScoreCategory::AtLeastOneCert,
-                        weight: 1,
-                        score: if num_certs > 0 { 1.0 } else { 0.0 },
-                        branches: vec![],
-                    }),
-                    ScoreBranch::Leaf(models::TranspaerScoreBranch {
-                        category: models::TranspaerScoreCategory::AtLeastTwoCerts,
-                        weight: 2,
-                        score: if num_certs > 1 { 1.0 } else { 0.0 },
-                        branches: vec![],
-                    }),
-                ],
-            }),
-        ],
-    }
-    .calculate();
-
-    models::TranspaerScore { tree: tree.branches, total: tree.score }
+                score: 0.5,
+                branches: vec![],
+                evidence: vec![evidence(CATEGORY, "smartphone")],
+            }));
+        }
+
+        let tree = SubscoreCalculator {
+            category: models::TranspaerScoreCategory::Root,
+            weight: 1,
+            branches: vec![
+                ScoreBranch::Branch(SubscoreCalculator {
+                    category: models::TranspaerScoreCategory::DataAvailability,
+                    weight: self.config.data_availability_weight,
+                    branches: vec![
+                        ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                            category: models::TranspaerScoreCategory::ProducerKnown,
+                            weight: 1,
+                            score: self
+                                .availability_score(has_producer, product.updated.manufacturers),
+                            branches: vec![],
+                            evidence: evidence_if(
+                                has_producer,
+                                vec![evidence(FIELD, "manufacturers")],
+                            ),
+                        }),
+                        ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                            category: models::TranspaerScoreCategory::CategoryAssigned,
+                            weight: 1,
+                            score: self
+                                .availability_score(has_categories, product.updated.categories),
+                            branches: vec![],
+                            evidence: evidence_if(
+                                has_categories,
+                                vec![evidence(FIELD, "categories")],
+                            ),
+                        }),
+                        ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                            category: models::TranspaerScoreCategory::ProductionPlaceKnown,
+                            weight: 1,
+                            score: self.availability_score(has_origins, product.updated.origins),
+                            branches: vec![],
+                            evidence: evidence_if(has_origins, vec![evidence(FIELD, "origins")]),
+                        }),
+                        ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                            category: models::TranspaerScoreCategory::IdKnown,
+                            weight: 1,
+                            score: self
+                                .availability_score(!product.ids.is_empty(), product.updated.ids),
+                            branches: vec![],
+                            evidence: id_evidence(&product.ids),
+                        }),
+                    ],
+                }),
+                ScoreBranch::Branch(SubscoreCalculator {
+                    category: models::TranspaerScoreCategory::Category,
+                    weight: self.config.category_weight,
+                    branches: category_contributions,
+                }),
+                ScoreBranch::Branch(SubscoreCalculator {
+                    category: models::TranspaerScoreCategory::NumCerts,
+                    weight: self.config.certifications_weight,
+                    branches: vec![
+                        ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                            category: models::TranspaerScoreCategory::AtLeastOneCert,
+                            weight: 1,
+                            score: if num_certs > 0 { 1.0 } else { 0.0 },
+                            branches: vec![],
+                            evidence: evidence_if(num_certs > 0, certs.clone()),
+                        }),
+                        ScoreBranch::Leaf(models::TranspaerScoreBranch {
+                            category: models::TranspaerScoreCategory::AtLeastTwoCerts,
+                            weight: 2,
+                            score: if num_certs > 1 { 1.0 } else { 0.0 },
+                            branches: vec![],
+                            evidence: evidence_if(num_certs > 1, certs),
+                        }),
+                    ],
+                }),
+            ],
+        }
+        .calculate();
+
+        models::TranspaerScore {
+            scorer: self.id().to_owned(),
+            tree: tree.branches,
+            total: tree.score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use transpaer_models::gather as models;
+
+    use super::{FreshnessDecay, Scorer, ScorerConfig, TreeScorer, TreeScorerConfig};
+
+    #[test]
+    fn tree_scorer_config() {
+        let config: ScorerConfig =
+            serde_yaml::from_str("scorer: tree\ncategory_weight: 3").unwrap();
+        let score = config.build().calculate(&models::Product::default());
+
+        assert_eq!(score.scorer, "tree");
+        let weights: Vec<i32> = score.tree.iter().map(|branch| branch.weight).collect();
+        assert_eq!(weights, vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn freshness_decay_curves() {
+        const DAY: u64 = 24 * 60 * 60;
+
+        let config: ScorerConfig = serde_yaml::from_str(
+            "scorer: tree\nfreshness_decay:\n  curve: linear\n  max_age_days: 100",
+        )
+        .unwrap();
+        let ScorerConfig::Tree(config) = config;
+        assert!(matches!(config.freshness_decay, FreshnessDecay::Linear { .. }));
+        assert!((config.freshness_decay.factor(25 * DAY) - 0.75).abs() < f64::EPSILON);
+        assert!(config.freshness_decay.factor(200 * DAY).abs() < f64::EPSILON);
+
+        let exponential = FreshnessDecay::Exponential { half_life_days: 10.0 };
+        assert!((exponential.factor(0) - 1.0).abs() < f64::EPSILON);
+        assert!((exponential.factor(20 * DAY) - 0.25).abs() < f64::EPSILON);
+        assert!((FreshnessDecay::None.factor(1000 * DAY) - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn old_data_scores_lower() {
+        const NOW: u64 = 1000 * 24 * 60 * 60;
+
+        let config = TreeScorerConfig {
+            freshness_decay: FreshnessDecay::Exponential { half_life_days: 365.0 },
+            ..TreeScorerConfig::default()
+        };
+        let scorer = TreeScorer::new(config, NOW);
+        let mut product = models::Product::default();
+        product.categories.insert("electronics".to_owned(), models::Source::Wikidata);
+
+        product.stamp(NOW);
+        let fresh = scorer.calculate(&product);
+        product.stamp(0);
+        let old = scorer.calculate(&product);
+        product.updated = models::FieldTimestamps::default();
+        let unknown = scorer.calculate(&product);
+        product.categories = models::MultiMap::new_empty();
+        let missing = scorer.calculate(&product);
+
+        assert!(old.total < fresh.total);
+        assert!((unknown.total - fresh.total).abs() < f64::EPSILON);
+        assert!(missing.total <= old.total);
+    }
+
+    #[test]
+    fn known_production_place_scores_higher() {
+        let scorer = TreeScorer::new(TreeScorerConfig::default(), 0);
+        let mut product = models::Product::default();
+        let unknown = scorer.calculate(&product);
+        product.origins.insert(isocountry::CountryCode::NLD, models::Source::Wikidata);
+        let known = scorer.calculate(&product);
+
+        assert!(unknown.total < known.total);
+    }
 }
@@ -0,0 +1,276 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `check-shopping` command checking if the shopping links still lead to the products.
+//!
+//! Shops are checked in parallel, but requests to the same shop are sent one by one with a delay,
+//! so that the checks do not burden the shops.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+use transpaer_collecting::shopping_availability::{Availability, LinkCheck, ShoppingAvailability};
+use transpaer_models::gather;
+use transpaer_schema as schema;
+
+use crate::{
+    config,
+    errors::{self, ShoppingCheckError},
+    substrate::Substrates,
+};
+
+const USER_AGENT: &str = "transpaer-lab";
+
+/// Number of checks after which the results are saved, so that long runs can be interrupted.
+const SAVE_INTERVAL: usize = 100;
+
+/// Maximal factor by which the delay between requests grows when a shop asks to slow down.
+const MAX_BACKOFF: u32 = 16;
+
+/// Interprets the HTTP status of the response to a request for a product page.
+fn classify(status: reqwest::StatusCode) -> Availability {
+    if status.is_success() {
+        Availability::Available
+    } else if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+        Availability::Dead
+    } else {
+        // Other errors are often caused by bot protections or temporary outages.
+        Availability::Unknown
+    }
+}
+
+/// Results of the checks shared by the shop checkers.
+struct Results {
+    availability: ShoppingAvailability,
+    path: std::path::PathBuf,
+    num_checked: usize,
+}
+
+impl Results {
+    fn record(
+        &mut self,
+        key: &gather::ShoppingKey,
+        check: LinkCheck,
+    ) -> Result<(), ShoppingCheckError> {
+        self.availability.record(key.shop.name(), &key.id, check);
+        self.num_checked += 1;
+        if self.num_checked % SAVE_INTERVAL == 0 {
+            log::info!(" - {} links checked", self.num_checked);
+            self.availability.write(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks links of a single shop limiting the rate of the requests.
+struct ShopChecker {
+    client: reqwest::Client,
+    delay: std::time::Duration,
+    backoff: u32,
+    last_request: Option<std::time::Instant>,
+}
+
+impl ShopChecker {
+    fn new(client: reqwest::Client, delay: std::time::Duration) -> Self {
+        Self { client, delay, backoff: 1, last_request: None }
+    }
+
+    /// Waits until the delay since the last request passes.
+    async fn wait(&mut self) {
+        if let Some(last_request) = self.last_request {
+            let delay = self.delay * self.backoff;
+            let elapsed = last_request.elapsed();
+            if elapsed < delay {
+                tokio::time::sleep(delay - elapsed).await;
+            }
+        }
+        self.last_request = Some(std::time::Instant::now());
+    }
+
+    /// Checks the link slowing down if the shop responds with "too many requests".
+    async fn check(&mut self, url: &str) -> LinkCheck {
+        self.wait().await;
+        match self.client.get(url).send().await {
+            Ok(response) => {
+                let status = response.status();
+                if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                    log::warn!(
+                        "Too many requests, slowing down to {:?}",
+                        self.delay * self.backoff
+                    );
+                }
+                LinkCheck::now(classify(status), Some(status.as_u16()))
+            }
+            Err(err) => {
+                log::warn!("Checking `{url}` failed: {err}");
+                LinkCheck::now(Availability::Unknown, None)
+            }
+        }
+    }
+
+    /// Checks all the links recording the results.
+    async fn run(
+        mut self,
+        keys: Vec<gather::ShoppingKey>,
+        results: Arc<Mutex<Results>>,
+    ) -> Result<(), errors::ProcessingError> {
+        for key in keys {
+            let check = self.check(&key.shop.link(&key.id)).await;
+            results.lock()?.record(&key, check)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ShoppingCheckRunner;
+
+impl ShoppingCheckRunner {
+    /// Collects the shopping links from all the substrate files.
+    fn collect_links(
+        substrates: &Substrates,
+    ) -> Result<BTreeSet<gather::ShoppingKey>, errors::ProcessingError> {
+        let mut links = BTreeSet::new();
+        let mut add = |shopping: Option<&schema::Shopping>| {
+            for entry in shopping.iter().flat_map(|shopping| &shopping.0) {
+                links.insert(gather::ShoppingKey::from_schema(entry));
+            }
+        };
+
+        for substrate in substrates.list() {
+            match schema::read::iter_file(&substrate.path)? {
+                schema::read::FileIterVariant::Catalog(iter) => {
+                    for entry in iter {
+                        if let schema::CatalogEntry::Product(product) = entry? {
+                            add(product.shopping.as_ref());
+                        }
+                    }
+                }
+                schema::read::FileIterVariant::Producer(iter) => {
+                    for entry in iter {
+                        if let schema::ProducerEntry::Product(product) = entry? {
+                            add(product.shopping.as_ref());
+                        }
+                    }
+                }
+                schema::read::FileIterVariant::Review(iter) => {
+                    for entry in iter {
+                        if let schema::ReviewEntry::Product(product) = entry? {
+                            add(product.shopping.as_ref());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(links)
+    }
+
+    /// Picks the links to check, the ones not checked for the longest time first.
+    fn select(
+        links: BTreeSet<gather::ShoppingKey>,
+        availability: &ShoppingAvailability,
+        limit: Option<usize>,
+    ) -> Vec<gather::ShoppingKey> {
+        let mut links: Vec<gather::ShoppingKey> = links.into_iter().collect();
+        links.sort_by_cached_key(|key| {
+            availability.get(key.shop.name(), &key.id).map(|check| check.check_time.clone())
+        });
+        if let Some(limit) = limit {
+            links.truncate(limit);
+        }
+        links
+    }
+
+    pub async fn run(config: &config::ShoppingCheckConfig) -> Result<(), errors::ProcessingError> {
+        let (substrates, substrate_report) = Substrates::prepare(&config.substrate.substrate_path)?;
+        substrate_report.report();
+
+        let availability = ShoppingAvailability::read(&config.shopping_availability)
+            .map_err(ShoppingCheckError::from)?;
+        let links = Self::collect_links(&substrates)?;
+        let num_links = links.len();
+        let selected = Self::select(links, &availability, config.limit);
+        log::info!("Checking {} of {num_links} shopping links", selected.len());
+
+        let mut by_shop = BTreeMap::<gather::VerifiedShop, Vec<gather::ShoppingKey>>::new();
+        for key in selected {
+            by_shop.entry(key.shop.clone()).or_default().push(key);
+        }
+
+        let client = reqwest::ClientBuilder::new()
+            .user_agent(USER_AGENT)
+            .timeout(config.timeout)
+            .build()
+            .map_err(ShoppingCheckError::from)?;
+        let results = Arc::new(Mutex::new(Results {
+            availability,
+            path: config.shopping_availability.clone(),
+            num_checked: 0,
+        }));
+        let checkers = by_shop
+            .into_values()
+            .map(|keys| ShopChecker::new(client.clone(), config.delay).run(keys, results.clone()));
+        for result in futures::future::join_all(checkers).await {
+            result?;
+        }
+
+        let results = results.lock()?;
+        results
+            .availability
+            .write(&config.shopping_availability)
+            .map_err(ShoppingCheckError::from)?;
+        log::info!(
+            "Checked {} links: {} available, {} dead and {} unknown in total",
+            results.num_checked,
+            results.availability.count(Availability::Available),
+            results.availability.count(Availability::Dead),
+            results.availability.count(Availability::Unknown),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_classification() {
+        assert_eq!(classify(reqwest::StatusCode::OK), Availability::Available);
+        assert_eq!(classify(reqwest::StatusCode::NOT_FOUND), Availability::Dead);
+        assert_eq!(classify(reqwest::StatusCode::GONE), Availability::Dead);
+        assert_eq!(classify(reqwest::StatusCode::SERVICE_UNAVAILABLE), Availability::Unknown);
+        assert_eq!(classify(reqwest::StatusCode::TOO_MANY_REQUESTS), Availability::Unknown);
+    }
+
+    /// Links never checked come first, followed by the ones checked the longest time ago.
+    #[test]
+    fn selection_prefers_stale_links() {
+        let key = |id: &str| gather::ShoppingKey {
+            id: id.to_owned(),
+            shop: gather::VerifiedShop::Amazon,
+        };
+        let check = |time: &str| LinkCheck {
+            availability: Availability::Available,
+            status: Some(200),
+            check_time: time.to_owned(),
+        };
+
+        let mut availability = ShoppingAvailability::default();
+        availability.record("amazon", "A", check("2026-01-02T00:00:00+00:00"));
+        availability.record("amazon", "B", check("2026-01-01T00:00:00+00:00"));
+
+        let links = BTreeSet::from([key("A"), key("B"), key("C")]);
+        assert_eq!(
+            ShoppingCheckRunner::select(links.clone(), &availability, None),
+            vec![key("C"), key("B"), key("A")]
+        );
+        assert_eq!(
+            ShoppingCheckRunner::select(links, &availability, Some(2)),
+            vec![key("C"), key("B")]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `export-sitemap` command.
+//!
+//! Writes the URLs of all the products and organisations into sitemap shards together with a
+//! sitemap index pointing to them. Every entry is listed once, under its most stable external ID.
+//! The last-modified date of all the entries is the version of the dataset, i.e. the creation
+//! time of the newest substrate it was crystalized from.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use transpaer_models::{buckets::DbStore, store};
+
+use crate::{config, errors};
+
+/// Maximal number of URLs in a single sitemap file allowed by the sitemap protocol.
+pub const MAX_URLS_PER_SHARD: usize = 50_000;
+
+/// Name of the sitemap index file.
+const INDEX_FILE: &str = "sitemap.xml";
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const XML_NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+/// Returns the path of the product page.
+///
+/// Wikidata IDs are preferred over GTINs and GTINs over EANs.
+fn product_path(ids: &store::ProductIds) -> Option<String> {
+    if let Some(id) = ids.wiki.iter().map(|id| id.id).min() {
+        return Some(format!("products/wiki/{}", id.to_canonical_string()));
+    }
+    if let Some(id) = ids.gtins.iter().map(|id| id.id.to_canonical_string()).min() {
+        return Some(format!("products/gtin/{id}"));
+    }
+    ids.eans
+        .iter()
+        .map(|id| id.id.to_canonical_string())
+        .min()
+        .map(|id| format!("products/ean/{id}"))
+}
+
+/// Returns the path of the organisation page.
+///
+/// Wikidata IDs are preferred over VAT IDs and VAT IDs over web domains.
+fn organisation_path(ids: &store::OrganisationIds) -> Option<String> {
+    if let Some(id) = ids.wiki.iter().map(|id| id.id).min() {
+        return Some(format!("organisations/wiki/{}", id.to_canonical_string()));
+    }
+    if let Some(id) = ids.vat_ids.iter().map(|id| id.id.to_canonical_string()).min() {
+        return Some(format!("organisations/vat/{id}"));
+    }
+    ids.domains
+        .iter()
+        .map(|domain| &domain.website)
+        .min()
+        .map(|domain| format!("organisations/www/{domain}"))
+}
+
+/// Formats the dataset version (seconds since the Unix epoch) as a W3C date.
+fn format_lastmod(version: u64) -> String {
+    let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(version);
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+/// Returns the `<lastmod>` element or nothing if the date is unknown.
+fn lastmod_element(lastmod: Option<&str>) -> String {
+    lastmod.map(|lastmod| format!("<lastmod>{lastmod}</lastmod>")).unwrap_or_default()
+}
+
+/// Writes URLs into numbered sitemap shards.
+struct ShardWriter {
+    dir: PathBuf,
+    base_url: String,
+    lastmod: Option<String>,
+    shard_size: usize,
+    current: Option<(PathBuf, std::io::BufWriter<std::fs::File>)>,
+    num_urls_in_shard: usize,
+    num_urls: usize,
+    shards: Vec<String>,
+}
+
+impl ShardWriter {
+    fn new(dir: &Path, base_url: &str, lastmod: Option<String>, shard_size: usize) -> Self {
+        Self {
+            dir: dir.to_owned(),
+            base_url: base_url.to_owned(),
+            lastmod,
+            shard_size: shard_size.clamp(1, MAX_URLS_PER_SHARD),
+            current: None,
+            num_urls_in_shard: 0,
+            num_urls: 0,
+            shards: Vec::new(),
+        }
+    }
+
+    /// Adds the URL of the page with the given path, opening a new shard if the current is full.
+    fn add(&mut self, path: &str) -> Result<(), errors::ProcessingError> {
+        if self.current.is_none() || self.num_urls_in_shard >= self.shard_size {
+            self.close()?;
+            self.open()?;
+        }
+
+        if let Some((file_path, writer)) = &mut self.current {
+            let loc = quick_xml::escape::escape(format!("{}/{path}", self.base_url));
+            let lastmod = lastmod_element(self.lastmod.as_deref());
+            writeln!(writer, "<url><loc>{loc}</loc>{lastmod}</url>")
+                .map_err(|e| errors::ProcessingError::Io(e, file_path.clone()))?;
+        }
+        self.num_urls_in_shard += 1;
+        self.num_urls += 1;
+        Ok(())
+    }
+
+    fn open(&mut self) -> Result<(), errors::ProcessingError> {
+        let name = format!("sitemap-{}.xml", self.shards.len() + 1);
+        let path = self.dir.join(&name);
+        let file = std::fs::File::create(&path)
+            .map_err(|e| errors::ProcessingError::Io(e, path.clone()))?;
+        let mut writer = std::io::BufWriter::new(file);
+        writeln!(writer, "{XML_HEADER}\n<urlset xmlns=\"{XML_NAMESPACE}\">")
+            .map_err(|e| errors::ProcessingError::Io(e, path.clone()))?;
+        self.shards.push(name);
+        self.current = Some((path, writer));
+        self.num_urls_in_shard = 0;
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), errors::ProcessingError> {
+        if let Some((path, mut writer)) = self.current.take() {
+            writeln!(writer, "</urlset>")
+                .and_then(|()| writer.flush())
+                .map_err(|e| errors::ProcessingError::Io(e, path))?;
+        }
+        Ok(())
+    }
+
+    /// Closes the last shard and writes the sitemap index.
+    ///
+    /// Returns the number of written URLs.
+    fn finish(mut self) -> Result<usize, errors::ProcessingError> {
+        self.close()?;
+
+        let lastmod = lastmod_element(self.lastmod.as_deref());
+        let mut index = format!("{XML_HEADER}\n<sitemapindex xmlns=\"{XML_NAMESPACE}\">\n");
+        for shard in &self.shards {
+            let loc = quick_xml::escape::escape(format!("{}/{shard}", self.base_url));
+            index.push_str(&format!("<sitemap><loc>{loc}</loc>{lastmod}</sitemap>\n"));
+        }
+        index.push_str("</sitemapindex>\n");
+
+        let path = self.dir.join(INDEX_FILE);
+        std::fs::write(&path, index).map_err(|e| errors::ProcessingError::Io(e, path))?;
+        Ok(self.num_urls)
+    }
+}
+
+/// Returns the version of the dataset, if recorded.
+fn dataset_version(store: &DbStore) -> Result<Option<u64>, errors::ProcessingError> {
+    let mut version = None;
+    for item in store.get_substrate_meta_bucket()?.iter() {
+        let (_, meta) = item?;
+        version = version.max(meta.created);
+    }
+    Ok(version)
+}
+
+pub struct SitemapExportRunner;
+
+impl SitemapExportRunner {
+    pub fn run(config: &config::SitemapExportConfig) -> Result<(), errors::ProcessingError> {
+        let store = DbStore::new(&config.crystal)?;
+        std::fs::create_dir_all(&config.output)
+            .map_err(|e| errors::ProcessingError::Io(e, config.output.clone()))?;
+
+        let lastmod = dataset_version(&store)?.map(format_lastmod);
+        if lastmod.is_none() {
+            log::warn!("The dataset version is not recorded, skipping last-modified dates");
+        }
+        let mut writer =
+            ShardWriter::new(&config.output, &config.base_url, lastmod, config.shard_size);
+
+        log::info!("Listing organisations");
+        let mut num_skipped = 0;
+        for item in store.get_organisation_bucket()?.iter() {
+            let (_, organisation) = item?;
+            match organisation_path(&organisation.ids) {
+                Some(path) => writer.add(&path)?,
+                None => num_skipped += 1,
+            }
+        }
+        log::info!("    skipped {num_skipped} organisations without external IDs");
+
+        log::info!("Listing products");
+        let mut num_skipped = 0;
+        for item in store.get_product_bucket()?.iter() {
+            let (_, product) = item?;
+            match product_path(&product.ids) {
+                Some(path) => writer.add(&path)?,
+                None => num_skipped += 1,
+            }
+        }
+        log::info!("    skipped {num_skipped} products without external IDs");
+
+        let num_urls = writer.finish()?;
+        log::info!("Written {num_urls} URLs into `{}`", config.output.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn product_paths() {
+        let gtin = |id: &str| store::SourcedGtin {
+            id: store::Gtin::try_from(id).unwrap(),
+            sources: vec![],
+        };
+        let mut ids = store::ProductIds {
+            eans: Vec::new(),
+            gtins: vec![gtin("5901234123457"), gtin("4006381333931")],
+            wiki: Vec::new(),
+        };
+        assert_eq!(product_path(&ids).as_deref(), Some("products/gtin/04006381333931"));
+
+        ids.wiki = vec![store::SourcedWikiId { id: store::WikiId::new(3), sources: Vec::new() }];
+        assert_eq!(product_path(&ids).as_deref(), Some("products/wiki/3"));
+    }
+
+    #[test]
+    fn shards_and_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let lastmod = Some(format_lastmod(1_700_000_000));
+        let mut writer = ShardWriter::new(dir.path(), "https://transpaer.com", lastmod, 2);
+        for path in ["products/wiki/1", "products/wiki/2", "organisations/www/a.com?b&c"] {
+            writer.add(path).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), 3);
+
+        let date = "<lastmod>2023-11-14T22:13:20Z</lastmod>";
+        let index = std::fs::read_to_string(dir.path().join(INDEX_FILE)).unwrap();
+        assert!(index.contains(&format!("<loc>https://transpaer.com/sitemap-1.xml</loc>{date}")));
+        assert!(index.contains(&format!("<loc>https://transpaer.com/sitemap-2.xml</loc>{date}")));
+        assert!(!index.contains("sitemap-3.xml"));
+
+        let shard1 = std::fs::read_to_string(dir.path().join("sitemap-1.xml")).unwrap();
+        assert!(
+            shard1.contains(&format!("<loc>https://transpaer.com/products/wiki/2</loc>{date}"))
+        );
+        assert!(shard1.ends_with("</urlset>\n"));
+
+        let shard2 = std::fs::read_to_string(dir.path().join("sitemap-2.xml")).unwrap();
+        assert!(
+            shard2.contains("<loc>https://transpaer.com/organisations/www/a.com?b&amp;c</loc>")
+        );
+    }
+}
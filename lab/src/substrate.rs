@@ -7,6 +7,7 @@ use std::collections::BTreeSet;
 use serde::{Deserialize, Serialize};
 
 use transpaer_models::gather;
+use transpaer_schema as schema;
 
 use crate::errors;
 
@@ -28,6 +29,35 @@ pub struct Substrate {
     pub path: std::path::PathBuf,
     pub name: String,
     pub source: gather::Source,
+
+    /// Creation time of the substrate recorded in its meta block, in seconds since the Unix epoch.
+    pub created: Option<u64>,
+
+    /// Version of the tool which created the substrate, as recorded in its meta block.
+    pub version: Option<String>,
+}
+
+impl Substrate {
+    /// Reads the meta block of the substrate.
+    ///
+    /// The meta block is the first line of the JSON-lines substrates. Returns `None` for the other
+    /// formats or if the meta block cannot be read.
+    fn read_meta(path: &std::path::Path) -> Option<schema::Meta> {
+        let extension = schema::SubstrateExtension::JsonLines.as_str();
+        if path.extension().and_then(std::ffi::OsStr::to_str) != Some(extension) {
+            return None;
+        }
+
+        let file = std::fs::File::open(path).ok()?;
+        let mut line = String::new();
+        std::io::BufRead::read_line(&mut std::io::BufReader::new(file), &mut line).ok()?;
+        serde_json::from_str(&line).ok()
+    }
+
+    /// Returns the creation time recorded in the meta block, if any.
+    fn read_creation_time(meta: &schema::Meta) -> Option<u64> {
+        u64::try_from(meta.creation_timestamp?.timestamp()).ok()
+    }
 }
 
 pub struct Substrates {
@@ -49,11 +79,14 @@ impl Substrates {
             if path.is_file() {
                 if let Some(stem) = path.file_stem() {
                     if let Some(stem) = stem.to_str() {
+                        let meta = Substrate::read_meta(&path);
                         list.push(Substrate {
                             id: DataSetId::new(list.len()),
                             path: path.clone(),
                             name: stem.to_owned(),
                             source: gather::Source::from_stem(stem),
+                            created: meta.as_ref().and_then(Substrate::read_creation_time),
+                            version: meta.map(|meta| meta.version),
                         });
                     } else {
                         report.add_path_not_unicode(path.clone());
@@ -0,0 +1,564 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `review-substrate` command.
+//!
+//! When a producer submits a substrate file we want to tell them what is weak about their data
+//! before it gets merged. This command scores a single substrate file and renders a readable report
+//! with feedback on what to improve.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use transpaer_collecting::errors::MapIo;
+use transpaer_models::{buckets::DbStore, store};
+use transpaer_schema as schema;
+
+use crate::{config, errors};
+
+/// Coverage (in percent) below which a criterion gets a piece of feedback.
+const FEEDBACK_THRESHOLD: usize = 90;
+
+/// Maximal number of invalid IDs quoted in the report.
+const MAX_EXAMPLES: usize = 5;
+
+/// A property every product is checked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Criterion {
+    Barcode,
+    WikiId,
+    Name,
+    Description,
+    Category,
+    Image,
+    Origin,
+    Availability,
+    Shopping,
+}
+
+impl Criterion {
+    const ALL: [Self; 9] = [
+        Self::Barcode,
+        Self::WikiId,
+        Self::Name,
+        Self::Description,
+        Self::Category,
+        Self::Image,
+        Self::Origin,
+        Self::Availability,
+        Self::Shopping,
+    ];
+
+    /// Returns the name of the criterion shown in the report.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Barcode => "valid GTIN or EAN",
+            Self::WikiId => "valid Wikidata ID",
+            Self::Name => "name",
+            Self::Description => "description",
+            Self::Category => "category",
+            Self::Image => "image",
+            Self::Origin => "production region",
+            Self::Availability => "availability regions",
+            Self::Shopping => "shopping link",
+        }
+    }
+
+    /// Returns the weight of the criterion in the total score. The weights sum up to 100.
+    fn weight(self) -> usize {
+        match self {
+            Self::Barcode => 30,
+            Self::Name | Self::Category => 15,
+            Self::Description | Self::Image => 10,
+            Self::WikiId | Self::Origin | Self::Availability | Self::Shopping => 5,
+        }
+    }
+
+    /// Returns the advice for products not meeting the criterion.
+    fn advice(self) -> &'static str {
+        match self {
+            Self::Barcode => {
+                "Add barcodes (GTIN or EAN). They are the main way of matching your products with \
+                 data from other sources."
+            }
+            Self::WikiId => "Add Wikidata IDs of the products which have an entry in Wikidata.",
+            Self::Name => "Add product names. Products without a name cannot be found in search.",
+            Self::Description => "Add short descriptions of the products.",
+            Self::Category => {
+                "Add categories. Alternatives are proposed only among products of the same \
+                 category."
+            }
+            Self::Image => "Add links to product images.",
+            Self::Origin => "Add the regions where the products are produced.",
+            Self::Availability => "Add the regions where the products can be bought.",
+            Self::Shopping => "Add links to shops selling the products.",
+        }
+    }
+}
+
+/// Properties of a single product relevant for the review.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default)]
+struct ProductFacts {
+    barcode: bool,
+    wiki_id: bool,
+    name: bool,
+
+    /// `None` if the substrate variant cannot carry descriptions.
+    description: Option<bool>,
+
+    image: bool,
+    origin: bool,
+    availability: bool,
+    shopping: bool,
+    categories: Vec<String>,
+}
+
+impl ProductFacts {
+    /// Tells if the product meets the criterion or `None` if the criterion does not apply to it.
+    fn meets(&self, criterion: Criterion) -> Option<bool> {
+        match criterion {
+            Criterion::Barcode => Some(self.barcode),
+            Criterion::WikiId => Some(self.wiki_id),
+            Criterion::Name => Some(self.name),
+            Criterion::Description => self.description,
+            Criterion::Category => Some(!self.categories.is_empty()),
+            Criterion::Image => Some(self.image),
+            Criterion::Origin => Some(self.origin),
+            Criterion::Availability => Some(self.availability),
+            Criterion::Shopping => Some(self.shopping),
+        }
+    }
+}
+
+/// Checks if any of the texts is not blank.
+fn any_text(texts: &[String]) -> bool {
+    texts.iter().any(|text| !text.trim().is_empty())
+}
+
+/// Checks if the optional text is given and not blank.
+fn some_text(text: Option<&String>) -> bool {
+    text.is_some_and(|text| !text.trim().is_empty())
+}
+
+fn has_origin(origins: Option<&schema::ProductOrigins>) -> bool {
+    origins
+        .and_then(|origins| origins.regions.as_ref())
+        .is_some_and(|regions| !regions.0.is_empty())
+}
+
+fn has_shopping(shopping: Option<&schema::Shopping>) -> bool {
+    shopping.is_some_and(|shopping| !shopping.0.is_empty())
+}
+
+fn categories(categorisation: Option<&schema::ProductCategorisation>) -> Vec<String> {
+    categorisation
+        .iter()
+        .flat_map(|categorisation| &categorisation.categories)
+        .map(|category| category.0.clone())
+        .collect()
+}
+
+/// Number of products meeting a criterion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Coverage {
+    /// Number of products meeting the criterion.
+    met: usize,
+
+    /// Number of products to which the criterion applies.
+    applicable: usize,
+}
+
+impl Coverage {
+    /// Returns the percent of the products meeting the criterion.
+    fn percent(self) -> Option<usize> {
+        (self.applicable > 0).then(|| self.met * 100 / self.applicable)
+    }
+}
+
+/// Looks up product IDs in an existing crystal.
+struct Matcher<'a> {
+    gtins: transpaer_models::buckets::Bucket<'a, store::Gtin, store::ProductId>,
+    wiki_ids: transpaer_models::buckets::Bucket<'a, store::WikiId, store::ProductId>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(store: &'a DbStore) -> Result<Self, errors::ProcessingError> {
+        Ok(Self {
+            gtins: store.get_gtin_to_product_id_bucket()?,
+            wiki_ids: store.get_wiki_id_to_product_id_bucket()?,
+        })
+    }
+
+    /// Checks if any of the IDs is already known.
+    fn matches(
+        &self,
+        gtins: &[store::Gtin],
+        wiki_ids: &[store::WikiId],
+    ) -> Result<bool, errors::ProcessingError> {
+        for gtin in gtins {
+            if self.gtins.get(gtin)?.is_some() {
+                return Ok(true);
+            }
+        }
+        for wiki_id in wiki_ids {
+            if self.wiki_ids.get(wiki_id)?.is_some() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Result of the review of a substrate file.
+#[derive(Debug, Default)]
+struct SubstrateReview {
+    /// Number of products in the substrate.
+    products: usize,
+
+    /// Coverage of every criterion.
+    coverage: BTreeMap<Criterion, Coverage>,
+
+    /// Number of IDs which could not be parsed.
+    invalid_ids: usize,
+
+    /// A few of the invalid IDs.
+    invalid_id_examples: Vec<String>,
+
+    /// Distinct categories used in the substrate.
+    categories: BTreeSet<String>,
+
+    /// Number of products matching already known products, if a crystal was given.
+    matched: Option<usize>,
+}
+
+impl SubstrateReview {
+    /// Parses the product IDs, adds them to the facts and remembers the invalid ones.
+    fn parse_ids(
+        &mut self,
+        ids: &schema::ProductIds,
+        facts: &mut ProductFacts,
+    ) -> (Vec<store::Gtin>, Vec<store::WikiId>) {
+        let mut invalid = Vec::new();
+        let mut gtins = Vec::new();
+        for id in ids.gtin.iter().flatten() {
+            match store::Gtin::try_from(id.as_str()) {
+                Ok(gtin) => gtins.push(gtin),
+                Err(_) => invalid.push(id.clone()),
+            }
+        }
+        for id in ids.ean.iter().flatten() {
+            match store::Ean::try_from(id) {
+                Ok(ean) => gtins.push(ean.to_gtin()),
+                Err(_) => invalid.push(id.clone()),
+            }
+        }
+        let mut wiki_ids = Vec::new();
+        for id in ids.wiki.iter().flatten() {
+            match store::WikiId::try_from(id) {
+                Ok(wiki_id) => wiki_ids.push(wiki_id),
+                Err(_) => invalid.push(id.clone()),
+            }
+        }
+
+        self.invalid_ids += invalid.len();
+        let missing = MAX_EXAMPLES.saturating_sub(self.invalid_id_examples.len());
+        self.invalid_id_examples.extend(invalid.into_iter().take(missing));
+        facts.barcode = !gtins.is_empty();
+        facts.wiki_id = !wiki_ids.is_empty();
+        (gtins, wiki_ids)
+    }
+
+    /// Adds a single product to the review.
+    fn add(
+        &mut self,
+        ids: &schema::ProductIds,
+        mut facts: ProductFacts,
+        matcher: Option<&Matcher>,
+    ) -> Result<(), errors::ProcessingError> {
+        let (gtins, wiki_ids) = self.parse_ids(ids, &mut facts);
+        if let Some(matcher) = matcher {
+            let matched = self.matched.get_or_insert(0);
+            if matcher.matches(&gtins, &wiki_ids)? {
+                *matched += 1;
+            }
+        }
+
+        self.products += 1;
+        for criterion in Criterion::ALL {
+            if let Some(met) = facts.meets(criterion) {
+                let coverage = self.coverage.entry(criterion).or_default();
+                coverage.applicable += 1;
+                coverage.met += usize::from(met);
+            }
+        }
+        self.categories.extend(facts.categories);
+        Ok(())
+    }
+
+    /// Returns the weighted score from 0 to 100 of the criteria applicable to the substrate.
+    fn score(&self) -> usize {
+        let mut total = 0;
+        let mut weights = 0;
+        for (criterion, coverage) in &self.coverage {
+            if let Some(percent) = coverage.percent() {
+                total += criterion.weight() * percent;
+                weights += criterion.weight();
+            }
+        }
+        if weights == 0 { 0 } else { total / weights }
+    }
+
+    /// Returns actionable feedback, the most important first.
+    fn feedback(&self) -> Vec<String> {
+        if self.products == 0 {
+            return vec!["The substrate contains no products.".to_owned()];
+        }
+
+        let mut feedback = Vec::new();
+        if self.invalid_ids > 0 {
+            feedback.push(format!(
+                "Found {} invalid product IDs (e.g. {}). Check their format.",
+                self.invalid_ids,
+                self.invalid_id_examples.join(", "),
+            ));
+        }
+
+        let mut criteria: Vec<(Criterion, Coverage)> =
+            self.coverage.iter().map(|(criterion, coverage)| (*criterion, *coverage)).collect();
+        criteria.sort_by_key(|(criterion, _)| std::cmp::Reverse(criterion.weight()));
+        for (criterion, coverage) in criteria {
+            if coverage.percent().is_some_and(|percent| percent < FEEDBACK_THRESHOLD) {
+                feedback.push(format!(
+                    "{} of {} products have no {}. {}",
+                    coverage.applicable - coverage.met,
+                    coverage.applicable,
+                    criterion.name(),
+                    criterion.advice(),
+                ));
+            }
+        }
+        feedback
+    }
+
+    /// Renders the review as a human-readable report.
+    fn render(&self, name: &str) -> String {
+        let percent =
+            |percent: Option<usize>| percent.map_or("n/a".to_owned(), |p| format!("{p}%"));
+
+        let mut lines = vec![
+            format!("Review of substrate `{name}`"),
+            String::new(),
+            format!("Score: {}/100", self.score()),
+            format!("Products: {}", self.products),
+            format!("Distinct categories: {}", self.categories.len()),
+            format!("Invalid IDs: {}", self.invalid_ids),
+        ];
+        if let Some(matched) = self.matched {
+            let coverage = Coverage { met: matched, applicable: self.products };
+            lines.push(format!(
+                "Matching known products: {matched} ({})",
+                percent(coverage.percent())
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push("Products with:".to_owned());
+        for (criterion, coverage) in &self.coverage {
+            lines.push(format!(
+                "  {:<22} {:>5} ({} of {})",
+                criterion.name(),
+                percent(coverage.percent()),
+                coverage.met,
+                coverage.applicable,
+            ));
+        }
+
+        lines.push(String::new());
+        let feedback = self.feedback();
+        if feedback.is_empty() {
+            lines.push("Feedback: the data looks complete, thank you!".to_owned());
+        } else {
+            lines.push("Feedback:".to_owned());
+            lines.extend(feedback.into_iter().map(|line| format!("  - {line}")));
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+pub struct SubstrateReviewRunner;
+
+impl SubstrateReviewRunner {
+    /// Reviews all the products in the substrate file.
+    fn review(
+        path: &std::path::Path,
+        matcher: Option<&Matcher>,
+    ) -> Result<SubstrateReview, errors::ProcessingError> {
+        let mut review = SubstrateReview::default();
+        match schema::read::iter_file(path)? {
+            schema::read::FileIterVariant::Catalog(iter) => {
+                for entry in iter {
+                    if let schema::CatalogEntry::Product(product) = entry? {
+                        let facts = ProductFacts {
+                            name: any_text(&product.names),
+                            description: Some(some_text(product.description.as_ref())),
+                            image: any_text(&product.images),
+                            origin: has_origin(product.origins.as_ref()),
+                            availability: product.availability.is_some(),
+                            shopping: has_shopping(product.shopping.as_ref()),
+                            categories: categories(product.categorisation.as_ref()),
+                            ..ProductFacts::default()
+                        };
+                        review.add(&product.ids, facts, matcher)?;
+                    }
+                }
+            }
+            schema::read::FileIterVariant::Producer(iter) => {
+                for entry in iter {
+                    if let schema::ProducerEntry::Product(product) = entry? {
+                        let facts = ProductFacts {
+                            name: any_text(&product.names),
+                            // TODO: Check once the producer schema can carry descriptions.
+                            description: None,
+                            image: any_text(&product.images),
+                            origin: has_origin(product.origins.as_ref()),
+                            availability: product.availability.is_some(),
+                            shopping: has_shopping(product.shopping.as_ref()),
+                            categories: categories(Some(&product.categorisation)),
+                            ..ProductFacts::default()
+                        };
+                        review.add(&product.ids, facts, matcher)?;
+                    }
+                }
+            }
+            schema::read::FileIterVariant::Review(iter) => {
+                for entry in iter {
+                    if let schema::ReviewEntry::Product(product) = entry? {
+                        let facts = ProductFacts {
+                            name: any_text(&product.names),
+                            description: Some(some_text(product.summary.as_ref())),
+                            image: any_text(&product.images),
+                            origin: has_origin(product.origins.as_ref()),
+                            availability: product.availability.is_some(),
+                            shopping: has_shopping(product.shopping.as_ref()),
+                            categories: categories(product.categorisation.as_ref()),
+                            ..ProductFacts::default()
+                        };
+                        review.add(&product.ids, facts, matcher)?;
+                    }
+                }
+            }
+        }
+        Ok(review)
+    }
+
+    pub fn run(config: &config::SubstrateReviewConfig) -> Result<(), errors::ProcessingError> {
+        let store = config.crystal.as_deref().map(DbStore::new).transpose()?;
+        let matcher = store.as_ref().map(Matcher::new).transpose()?;
+
+        log::info!("Reviewing `{}`", config.substrate_file.display());
+        let review = Self::review(&config.substrate_file, matcher.as_ref())?;
+        let name = config.substrate_file.file_name().unwrap_or_default().to_string_lossy();
+        let report = review.render(&name);
+
+        if let Some(output) = &config.output {
+            std::fs::write(output, report).map_with_path(output)?;
+            log::info!("Score {}/100, report written to `{}`", review.score(), output.display());
+        } else {
+            print!("{report}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transpaer_testgen::{ProductBuilder, SubstrateBuilder, SubstrateDir};
+
+    use super::*;
+
+    #[test]
+    fn complete_substrate() {
+        let substrates = SubstrateDir::new().unwrap();
+        let path = substrates
+            .add(
+                &SubstrateBuilder::cataloger("acme").products([
+                    ProductBuilder::new("1")
+                        .gtin("4006381333931")
+                        .name("Washer")
+                        .description("Washing machine")
+                        .category("washing_machines"),
+                    ProductBuilder::new("2")
+                        .gtin("4000000000001")
+                        .name("Dryer")
+                        .description("Tumble dryer")
+                        .category("dryers"),
+                ]),
+            )
+            .unwrap();
+
+        let review = SubstrateReviewRunner::review(&path, None).unwrap();
+        assert_eq!(review.products, 2);
+        assert_eq!(review.categories.len(), 2);
+        assert_eq!(review.invalid_ids, 0);
+        assert_eq!(review.matched, None);
+        assert_eq!(review.coverage[&Criterion::Barcode], Coverage { met: 2, applicable: 2 });
+        assert_eq!(review.coverage[&Criterion::Image], Coverage { met: 0, applicable: 2 });
+
+        let feedback = review.feedback();
+        assert!(feedback.iter().all(|line| !line.contains("GTIN")), "{feedback:?}");
+        assert!(feedback.iter().any(|line| line.contains("no image")), "{feedback:?}");
+    }
+
+    #[test]
+    fn weak_substrate() {
+        let substrates = SubstrateDir::new().unwrap();
+        let path = substrates
+            .add(&SubstrateBuilder::cataloger("acme").products([
+                ProductBuilder::new("1").gtin("123").name("Washer"),
+                ProductBuilder::new("2").wiki("1234").name("Dryer"),
+                ProductBuilder::new("3"),
+            ]))
+            .unwrap();
+
+        let review = SubstrateReviewRunner::review(&path, None).unwrap();
+        assert_eq!(review.invalid_ids, 1);
+        assert_eq!(review.invalid_id_examples, ["123"]);
+        assert_eq!(review.coverage[&Criterion::Barcode], Coverage { met: 0, applicable: 3 });
+        assert_eq!(review.coverage[&Criterion::WikiId], Coverage { met: 1, applicable: 3 });
+        assert_eq!(review.coverage[&Criterion::Name], Coverage { met: 2, applicable: 3 });
+        assert!(review.score() < 20);
+
+        let feedback = review.feedback();
+        assert!(feedback[0].contains("123"), "{feedback:?}");
+        assert!(
+            feedback[1].starts_with("3 of 3 products have no valid GTIN or EAN"),
+            "{feedback:?}"
+        );
+        assert!(review.render("acme.jsonl").contains("Feedback:\n  - Found 1 invalid product IDs"));
+    }
+
+    #[test]
+    fn match_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DbStore::new(dir.path()).unwrap();
+        let gtins = store.get_gtin_to_product_id_bucket().unwrap();
+        let gtin = store::Gtin::try_from("4006381333931").unwrap();
+        gtins.insert(&gtin, &store::ProductId::from_value(1)).unwrap();
+        gtins.flush().unwrap();
+        let matcher = Matcher::new(&store).unwrap();
+
+        let substrates = SubstrateDir::new().unwrap();
+        let path = substrates
+            .add(&SubstrateBuilder::cataloger("acme").products([
+                ProductBuilder::new("1").gtin("4006381333931"),
+                ProductBuilder::new("2").gtin("4000000000001"),
+            ]))
+            .unwrap();
+
+        let review = SubstrateReviewRunner::review(&path, Some(&matcher)).unwrap();
+        assert_eq!(review.matched, Some(1));
+        assert!(review.render("acme.jsonl").contains("Matching known products: 1 (50%)"));
+    }
+}
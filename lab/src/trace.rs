@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `trace` command.
+//!
+//! Replays coagulation and crystalization for a single organisation or product to help debugging
+//! how the substrate entries were merged into it.
+
+use std::collections::BTreeSet;
+
+use transpaer_collecting::errors::MapIo;
+
+use crate::{
+    coagulate::{Coagulate, ExternalId},
+    coagulating::Coagulator,
+    config,
+    crystalizing::{Processor, TraceTarget},
+    errors,
+    substrate::Substrates,
+};
+
+pub struct TracingRunner;
+
+impl TracingRunner {
+    pub fn run(config: &config::TracingConfig) -> Result<(), errors::ProcessingError> {
+        let (substrates, substrates_report) =
+            Substrates::prepare(&config.substrate.substrate_path)?;
+        substrates_report.report();
+
+        let coagulate = Coagulate::read(&config.coagulate, &substrates)?;
+        let members: BTreeSet<ExternalId> = match &config.target {
+            TraceTarget::Organisation(id) => coagulate.get_producer_external_ids(id),
+            TraceTarget::Product(id) => coagulate.get_product_external_ids(id),
+        }
+        .into_iter()
+        .collect();
+        if members.is_empty() {
+            log::warn!("No substrate entries were merged into {:?}", config.target);
+            return Ok(());
+        }
+
+        Self::trace_coagulation(&substrates, &members, &config.target)?;
+        Self::trace_crystalization(config, &substrates, &coagulate)?;
+        Ok(())
+    }
+
+    /// Prints the substrate entries merged into the traced entity and the IDs linking them.
+    fn trace_coagulation(
+        substrates: &Substrates,
+        members: &BTreeSet<ExternalId>,
+        target: &TraceTarget,
+    ) -> Result<(), errors::ProcessingError> {
+        log::info!("Coagulation of {target:?}:");
+        log::info!(" merged substrate entries:");
+        for member in members {
+            log::info!("  - {}", Self::describe(substrates, member));
+        }
+
+        let (summary, _report) = Coagulator::summarize(substrates)?;
+        let shared = match target {
+            TraceTarget::Organisation(_) => summary.shared_producer_ids(members),
+            TraceTarget::Product(_) => summary.shared_product_ids(members),
+        };
+        log::info!(" linked by shared IDs:");
+        for (id, externals) in &shared {
+            let externals: Vec<String> =
+                externals.iter().map(|external| Self::describe(substrates, external)).collect();
+            log::info!("  - {id}: {}", externals.join(", "));
+        }
+        for member in members.iter().filter(|member| summary.is_brand(member)) {
+            log::info!("  - {}: matched by the brand name", Self::describe(substrates, member));
+        }
+        Ok(())
+    }
+
+    /// Prints the contributions of the substrate entries and the merged result.
+    fn trace_crystalization(
+        config: &config::TracingConfig,
+        substrates: &Substrates,
+        coagulate: &Coagulate,
+    ) -> Result<(), errors::ProcessingError> {
+        if config.runtime.exists() {
+            std::fs::remove_dir_all(&config.runtime).map_with_path(&config.runtime)?;
+        }
+
+        log::info!("Crystalization of {:?}:", config.target);
+        let processor = Processor::new_tracing(&config.runtime, config.target.clone())?;
+        let (collector, _report) = processor.process(substrates, coagulate)?;
+        match &config.target {
+            TraceTarget::Organisation(id) => {
+                if let Some(organisation) = collector.get_organisation(id)? {
+                    log::info!(" merged organisation:\n{organisation:#?}");
+                }
+            }
+            TraceTarget::Product(id) => {
+                if let Some(product) = collector.get_product(id)? {
+                    log::info!(" merged product:\n{product:#?}");
+                    log::info!(" certifications inherited from the manufacturers are not included");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(substrates: &Substrates, external: &ExternalId) -> String {
+        let name = substrates.get_name_for_id(external.data_set_id()).unwrap_or("unknown");
+        format!("`{name}` entry `{}`", external.inner())
+    }
+}
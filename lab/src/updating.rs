@@ -122,6 +122,9 @@ pub struct OpenFoodFactsCollector {
     /// Counts how many empty production countries were present.
     empty_production_count: usize,
 
+    /// Counts how many empty origin countries were present.
+    empty_origin_count: usize,
+
     /// Counts how many products without categories were present.
     empty_category_count: usize,
 }
@@ -137,6 +140,7 @@ impl Combine for OpenFoodFactsCollector {
             records: o1.records + o2.records,
             empty_sell_count: o1.empty_sell_count + o2.empty_sell_count,
             empty_production_count: o1.empty_production_count + o2.empty_production_count,
+            empty_origin_count: o1.empty_origin_count + o2.empty_origin_count,
             empty_category_count: o1.empty_category_count + o2.empty_category_count,
         }
     }
@@ -177,6 +181,15 @@ impl runners::OpenFoodFactsWorker for OpenFoodFactsWorker {
             }
         }
 
+        let origin_countries = record.extract_origin_countries();
+        if origin_countries.is_empty() {
+            self.collector.empty_origin_count += 1;
+        } else {
+            for tag in origin_countries {
+                self.collector.countries.entry(tag).and_modify(|n| *n += 1).or_insert(1);
+            }
+        }
+
         let categories = record.extract_categories();
         if categories.is_empty() {
             self.collector.empty_category_count += 1;
@@ -252,6 +265,7 @@ impl runners::Stash for OpenFoodFactsStash {
         log::info!(" - found {} countries", countries.countries.len());
         log::info!("   - {} entries had no sell country", collector.empty_sell_count);
         log::info!("   - {} entries had no production country", collector.empty_production_count);
+        log::info!("   - {} entries had no origin country", collector.empty_origin_count);
         log::info!("   - {country_percentage}% of tag use-cases assigned");
         log::info!(" - found {} categories", categories.categories.len());
         log::info!(
@@ -6,6 +6,9 @@
 
 use std::collections::HashMap;
 
+use transpaer_collecting::errors::MapIo;
+use transpaer_models::ids;
+
 use crate::errors;
 
 /// Verifies that the path exists and is a file.
@@ -126,6 +129,78 @@ pub fn parent_creatable(path: &std::path::Path) -> Result<(), errors::ConfigChec
     }
 }
 
+/// Number of bytes in a mebibyte.
+const MIB: u64 = 1024 * 1024;
+
+/// Free space (in percent of the estimated required space) below which a warning is printed.
+const SPACE_WARNING_PERCENT: u64 = 150;
+
+/// Returns the size of the file or the total size of all files in the directory.
+///
+/// Paths that do not exist or cannot be read count as empty.
+#[must_use]
+pub fn disk_usage(path: &std::path::Path) -> u64 {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return 0;
+    };
+    if metadata.is_dir() {
+        std::fs::read_dir(path).map_or(0, |entries| {
+            entries.filter_map(Result::ok).map(|entry| disk_usage(&entry.path())).sum()
+        })
+    } else {
+        metadata.len()
+    }
+}
+
+/// Verifies that the file system the output will be written to has enough free space.
+///
+/// The required space is estimated as the total size of the inputs multiplied by the expected
+/// expansion factor of the stage (in percent). If the free space cannot be determined or it is
+/// only slightly larger than the estimate, a warning is printed.
+///
+/// # Errors
+///
+/// Returns an error if there is less free space than the estimated required space.
+pub fn space_available(
+    output: &std::path::Path,
+    inputs: &[&std::path::Path],
+    expansion_percent: u64,
+) -> Result<(), errors::ConfigCheckError> {
+    let input_size: u64 = inputs.iter().map(|input| disk_usage(input)).sum();
+    let required = input_size.saturating_mul(expansion_percent) / 100;
+
+    let Some(existing) = output
+        .ancestors()
+        .map(|path| if path.as_os_str().is_empty() { std::path::Path::new(".") } else { path })
+        .find(|path| path.exists())
+    else {
+        return Ok(());
+    };
+
+    match fs4::available_space(existing) {
+        Ok(available) if available < required => Err(errors::ConfigCheckError::InsufficientSpace {
+            path: existing.to_owned(),
+            required_mib: required / MIB,
+            available_mib: available / MIB,
+        }),
+        Ok(available) => {
+            if available < required.saturating_mul(SPACE_WARNING_PERCENT) / 100 {
+                log::warn!(
+                    "Little free space in '{}': about {} MiB required, {} MiB available",
+                    existing.display(),
+                    required / MIB,
+                    available / MIB,
+                );
+            }
+            Ok(())
+        }
+        Err(err) => {
+            log::warn!("Could not determine free space in '{}': {err}", existing.display());
+            Ok(())
+        }
+    }
+}
+
 /// Creates the parent of the given path.
 pub fn create_parent(path: &std::path::Path) -> Result<(), errors::ProcessingError> {
     if let Some(base) = path.parent() {
@@ -137,6 +212,61 @@ pub fn create_parent(path: &std::path::Path) -> Result<(), errors::ProcessingErr
     }
 }
 
+/// Atomically replaces the link with a link pointing to the `target`.
+pub fn switch_link(
+    target: &std::path::Path,
+    link: &std::path::Path,
+) -> Result<(), errors::ProcessingError> {
+    let tmp = link.with_extension("new");
+    if tmp.is_symlink() {
+        std::fs::remove_file(&tmp).map_with_path(&tmp)?;
+    }
+    std::os::unix::fs::symlink(std::path::absolute(target).map_with_path(target)?, &tmp)
+        .map_with_path(&tmp)?;
+    std::fs::rename(&tmp, link).map_with_path(link)?;
+    Ok(())
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+#[must_use]
+pub fn now_millis() -> u64 {
+    let now =
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    u64::try_from(now.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Returns the size of the file in bytes and its modification time in seconds since the Unix
+/// epoch, or `None` if the file metadata cannot be read.
+#[must_use]
+pub fn file_stamp(path: &std::path::Path) -> Option<(u64, Option<u64>)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs());
+    Some((metadata.len(), modified))
+}
+
+/// Identifies a version of a data file by its name, size and modification time.
+#[must_use]
+pub fn describe_file(path: &std::path::Path) -> String {
+    let name = path
+        .file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    match file_stamp(path) {
+        Some((size, Some(modified))) => {
+            let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(modified);
+            format!(
+                "{name} ({size} bytes, modified {})",
+                humantime::format_rfc3339_seconds(modified)
+            )
+        }
+        Some((size, None)) => format!("{name} ({size} bytes)"),
+        None => format!("{name} (unavailable)"),
+    }
+}
+
 /// Trims the given name and transforms it to lower case.
 #[must_use]
 pub fn disambiguate_name(name: &str) -> String {
@@ -170,6 +300,42 @@ where
     }
 }
 
+/// Counts of VAT numbers rejected in a single substrate, by the reason of the rejection.
+#[derive(Debug, Default)]
+pub struct InvalidVatIds {
+    format: usize,
+    checksum: usize,
+    other: usize,
+}
+
+impl InvalidVatIds {
+    pub fn add(&mut self, error: &ids::ParseIdError) {
+        match error {
+            ids::ParseIdError::Format { .. } => self.format += 1,
+            ids::ParseIdError::Checksum { .. } => self.checksum += 1,
+            _ => self.other += 1,
+        }
+    }
+
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.format + self.checksum + self.other
+    }
+}
+
+impl std::fmt::Display for InvalidVatIds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (format: {}, checksum: {}, other: {})",
+            self.total(),
+            self.format,
+            self.checksum,
+            self.other
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -198,4 +364,30 @@ mod tests {
         merge_hashmaps_with(&mut input1, input2, |a, b| *a += b);
         assert_eq!(input1, output);
     }
+
+    #[test]
+    fn test_disk_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), [0; 10]).unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub").join("b"), [0; 5]).unwrap();
+
+        assert_eq!(disk_usage(&dir.path().join("a")), 10);
+        assert_eq!(disk_usage(dir.path()), 15);
+        assert_eq!(disk_usage(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_space_available() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input");
+        std::fs::write(&input, [0; 10]).unwrap();
+        let output = dir.path().join("not").join("yet").join("created");
+
+        assert!(space_available(&output, &[&input], 100).is_ok());
+        assert!(matches!(
+            space_available(&output, &[&input], u64::MAX),
+            Err(errors::ConfigCheckError::InsufficientSpace { .. })
+        ));
+    }
 }
@@ -0,0 +1,516 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Validation of the advisor data files and the `check-advisors` command.
+//!
+//! Advisor data files (name matches, region maps, category maps) are edited by hand. The plain
+//! serde errors do not say much about where the problem is, so the files are validated here and
+//! the issues point to the file, line and key of the offending entry.
+//!
+//! Repeated keys are only warned about when the files are loaded, but `check-advisors` reports
+//! them as errors.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use transpaer_collecting::{
+    errors::IoOrSerdeError,
+    fashion_transparency_index, tco,
+    transpaer::{self, data::Countries},
+};
+
+use crate::{
+    config, convert,
+    errors::{self, AdvisorDataError},
+    wikidata::WikiId,
+};
+
+/// Kind of the tags used as keys in region and category maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    /// Any string, e.g. a country name used by the source.
+    Plain,
+
+    /// A Wikidata ID.
+    WikiId,
+}
+
+/// Contents of a data file used to find lines of the reported issues.
+struct Locator<'a> {
+    path: &'a Path,
+    contents: String,
+}
+
+impl<'a> Locator<'a> {
+    fn new(path: &'a Path) -> Self {
+        Self { path, contents: std::fs::read_to_string(path).unwrap_or_default() }
+    }
+
+    /// Finds the line of the `nth` entry in which the `field` has the given `value`.
+    fn find_line(&self, field: &str, value: &str, nth: usize) -> Option<usize> {
+        self.contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| {
+                line.trim_start()
+                    .trim_start_matches('-')
+                    .trim_start()
+                    .strip_prefix(field)
+                    .and_then(|rest| rest.strip_prefix(':'))
+                    .is_some_and(|rest| rest.trim().trim_matches(['"', '\'']) == value)
+            })
+            .nth(nth)
+            .map(|(index, _)| index + 1)
+    }
+
+    /// Constructs an error pointing to the `nth` entry in which the `field` has the given `key`.
+    fn error(&self, field: &str, key: &str, nth: usize, message: String) -> AdvisorDataError {
+        AdvisorDataError {
+            path: self.path.to_owned(),
+            line: self.find_line(field, key, nth),
+            key: Some(key.to_owned()),
+            message,
+        }
+    }
+}
+
+/// Extracts the offending key from a YAML parsing error.
+///
+/// `serde_yaml` prefixes the messages with the path to the key, e.g. `countries[2].regions: ...`.
+fn yaml_error_key(error: &serde_yaml::Error) -> Option<String> {
+    let message = error.to_string();
+    let (prefix, _) = message.split_once(": ")?;
+    if prefix.is_empty() || prefix.contains(char::is_whitespace) {
+        None
+    } else {
+        Some(prefix.to_owned())
+    }
+}
+
+/// Converts a reading error to an error pointing to the offending place in the file.
+fn to_data_error(path: &Path, error: IoOrSerdeError) -> AdvisorDataError {
+    let (line, key) = match &error {
+        IoOrSerdeError::ReadYaml(e, _) => (e.location().map(|l| l.line()), yaml_error_key(e)),
+        IoOrSerdeError::ReadJson(e, _) => (Some(e.line()).filter(|line| *line > 0), None),
+        IoOrSerdeError::ReadCsv(e, _) => {
+            (e.position().and_then(|p| usize::try_from(p.line()).ok()), None)
+        }
+        IoOrSerdeError::ReadJsonLines(_, _, line) => (Some(*line), None),
+        _ => (None, None),
+    };
+    let message = match error {
+        IoOrSerdeError::Io(e, _) | IoOrSerdeError::ReadJsonLines(e, _, _) => e.to_string(),
+        IoOrSerdeError::ReadYaml(e, _) => e.to_string(),
+        IoOrSerdeError::ReadJson(e, _) => e.to_string(),
+        IoOrSerdeError::ReadCsv(e, _) => e.to_string(),
+        IoOrSerdeError::ReadXml(e, _) => e.to_string(),
+        other => other.to_string(),
+    };
+    AdvisorDataError { path: path.to_owned(), line, key, message }
+}
+
+/// Reads a data file with the given reader reporting the location of parsing errors.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to read from `path` or parse the contents.
+pub fn read<T, R>(path: &Path, reader: R) -> Result<T, AdvisorDataError>
+where
+    R: FnOnce(&Path) -> Result<T, IoOrSerdeError>,
+{
+    reader(path).map_err(|e| to_data_error(path, e))
+}
+
+/// Issues found in a single advisor data file.
+#[must_use]
+#[derive(Debug, Default)]
+pub struct Issues {
+    /// Entries which are invalid.
+    pub invalid: Vec<AdvisorDataError>,
+
+    /// Entries repeating a key of an earlier entry.
+    pub repeated: Vec<AdvisorDataError>,
+}
+
+impl Issues {
+    /// Returns all the issues ordered by their lines.
+    pub fn into_all(self) -> Vec<AdvisorDataError> {
+        let mut all = self.invalid;
+        all.extend(self.repeated);
+        all.sort_by_key(|issue| issue.line);
+        all
+    }
+
+    /// Logs the repeated entries as warnings and returns the first invalid entry, if any.
+    fn into_result(self) -> Result<(), AdvisorDataError> {
+        for issue in self.repeated {
+            log::warn!("{issue}");
+        }
+        match self.invalid.into_iter().next() {
+            Some(issue) => Err(issue),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Checks if the tag is valid and was not seen before.
+///
+/// Returns the number of previous occurrences of the tag.
+fn check_tag(
+    locator: &Locator,
+    tag: &str,
+    kind: TagKind,
+    seen: &mut HashMap<String, usize>,
+    issues: &mut Issues,
+) -> usize {
+    let count = seen.entry(tag.to_owned()).or_default();
+    let nth = *count;
+    *count += 1;
+    if nth > 0 {
+        issues.repeated.push(locator.error("tag", tag, nth, "repeated tag".to_owned()));
+    }
+    if kind == TagKind::WikiId
+        && let Err(err) = WikiId::try_from(tag)
+    {
+        issues.invalid.push(locator.error("tag", tag, nth, format!("invalid Wikidata ID: {err}")));
+    }
+    nth
+}
+
+/// Checks the mapping from country tags to Transpaer regions.
+pub fn check_countries(path: &Path, data: &Countries, kind: TagKind) -> Issues {
+    let locator = Locator::new(path);
+    let mut issues = Issues::default();
+    let mut seen = HashMap::new();
+    for entry in &data.countries {
+        let nth = check_tag(&locator, &entry.tag, kind, &mut seen, &mut issues);
+        if let Some(regions) = &entry.regions
+            && let Err(err) = convert::to_model_regions(regions)
+        {
+            let message = format!("invalid region: {err}");
+            issues.invalid.push(locator.error("tag", &entry.tag, nth, message));
+        }
+    }
+    issues
+}
+
+/// Checks the mapping from source categories to Transpaer categories.
+pub fn check_categories(path: &Path, data: &transpaer::data::Categories, kind: TagKind) -> Issues {
+    let locator = Locator::new(path);
+    let mut issues = Issues::default();
+    let mut seen = HashMap::new();
+    for entry in &data.categories {
+        check_tag(&locator, &entry.tag, kind, &mut seen, &mut issues);
+    }
+    issues
+}
+
+/// Checks the mapping from company or product names to Wikidata IDs.
+pub fn check_matches(path: &Path, data: &[transpaer::data::NameMatching]) -> Issues {
+    let locator = Locator::new(path);
+    let mut issues = Issues::default();
+    let mut seen = HashMap::<&str, usize>::new();
+    for entry in data {
+        let count = seen.entry(&entry.name).or_default();
+        let nth = *count;
+        *count += 1;
+        if !(0.0..=1.0).contains(&entry.similarity) {
+            issues.invalid.push(locator.error(
+                "name",
+                &entry.name,
+                nth,
+                format!("similarity {} is out of range [0, 1]", entry.similarity),
+            ));
+        }
+    }
+    issues
+}
+
+/// Checks the Fashion Transparency Index data.
+pub fn check_fashion_transparency_index(
+    path: &Path,
+    data: &[fashion_transparency_index::data::Entry],
+) -> Issues {
+    let locator = Locator::new(path);
+    let mut issues = Issues::default();
+    let mut seen = HashMap::<WikiId, usize>::new();
+    for entry in data {
+        if let Some(wiki_id) = entry.wikidata_id {
+            let count = seen.entry(wiki_id).or_default();
+            let nth = *count;
+            *count += 1;
+            if nth > 0 {
+                let key = format!("Q{}", wiki_id.get_value());
+                let issue = locator.error("wiki", &key, nth, "repeated Wikidata ID".to_owned());
+                issues.repeated.push(issue);
+            }
+        }
+    }
+    issues
+}
+
+/// Reads and validates the mapping from country tags to Transpaer regions.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to read from `path`, parse the contents or the contents are invalid.
+/// Repeated keys are only logged.
+pub fn load_countries(path: &Path, kind: TagKind) -> Result<Countries, AdvisorDataError> {
+    let data = read(path, transpaer::reader::parse_countries)?;
+    check_countries(path, &data, kind).into_result()?;
+    Ok(data)
+}
+
+/// Reads and validates the mapping from source categories to Transpaer categories.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to read from `path`, parse the contents or the contents are invalid.
+/// Repeated keys are only logged.
+pub fn load_categories(
+    path: &Path,
+    kind: TagKind,
+) -> Result<transpaer::data::Categories, AdvisorDataError> {
+    let data = read(path, transpaer::reader::parse_categories)?;
+    check_categories(path, &data, kind).into_result()?;
+    Ok(data)
+}
+
+/// Reads and validates the mapping from company or product names to Wikidata IDs.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to read from `path`, parse the contents or the contents are invalid.
+pub fn load_matches(path: &Path) -> Result<Vec<transpaer::data::NameMatching>, AdvisorDataError> {
+    let data = read(path, transpaer::reader::parse_id_map)?;
+    check_matches(path, &data).into_result()?;
+    Ok(data)
+}
+
+/// Reads and validates the Fashion Transparency Index data.
+///
+/// # Errors
+///
+/// Returns `Err` if fails to read from `path`, parse the contents or the contents are invalid.
+/// Repeated keys are only logged.
+pub fn load_fashion_transparency_index(
+    path: &Path,
+) -> Result<Vec<fashion_transparency_index::data::Entry>, AdvisorDataError> {
+    let data = read(path, fashion_transparency_index::reader::parse)?;
+    check_fashion_transparency_index(path, &data).into_result()?;
+    Ok(data)
+}
+
+/// Result of checking a single advisor data file.
+#[derive(Debug)]
+enum FileStatus {
+    /// The file does not exist. Advisors skip missing files.
+    Missing,
+
+    /// The file is valid.
+    Valid,
+
+    /// The file contains issues.
+    Invalid(Vec<AdvisorDataError>),
+}
+
+/// Report of checking the advisor data files.
+#[must_use]
+#[derive(Debug, Default)]
+pub struct AdvisorCheckReport {
+    files: Vec<(PathBuf, FileStatus)>,
+}
+
+impl AdvisorCheckReport {
+    fn add(&mut self, path: &Path, issues: Result<Vec<AdvisorDataError>, AdvisorDataError>) {
+        let status = match issues {
+            Ok(issues) if issues.is_empty() => FileStatus::Valid,
+            Ok(issues) => FileStatus::Invalid(issues),
+            Err(err) => FileStatus::Invalid(vec![err]),
+        };
+        self.files.push((path.to_owned(), status));
+    }
+
+    fn add_missing(&mut self, path: &Path) {
+        self.files.push((path.to_owned(), FileStatus::Missing));
+    }
+
+    /// Returns the number of found issues.
+    #[must_use]
+    pub fn num_issues(&self) -> usize {
+        self.files
+            .iter()
+            .map(|(_, status)| match status {
+                FileStatus::Invalid(issues) => issues.len(),
+                FileStatus::Missing | FileStatus::Valid => 0,
+            })
+            .sum()
+    }
+
+    pub fn report(&self) {
+        log::info!("Advisor data check report:");
+        for (path, status) in &self.files {
+            match status {
+                FileStatus::Missing => log::warn!(" - `{}`: missing", path.display()),
+                FileStatus::Valid => log::info!(" - `{}`: valid", path.display()),
+                FileStatus::Invalid(issues) => {
+                    log::error!(" - `{}`: {} issues", path.display(), issues.len());
+                    for issue in issues {
+                        log::error!("   - {issue}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct AdvisorCheckRunner;
+
+impl AdvisorCheckRunner {
+    /// Checks the file if it exists.
+    fn check_file<F>(report: &mut AdvisorCheckReport, path: &Path, check: F)
+    where
+        F: FnOnce(&Path) -> Result<Vec<AdvisorDataError>, AdvisorDataError>,
+    {
+        if path.is_file() {
+            report.add(path, check(path));
+        } else {
+            report.add_missing(path);
+        }
+    }
+
+    fn check_country_file(report: &mut AdvisorCheckReport, path: &Path, kind: TagKind) {
+        Self::check_file(report, path, |path| {
+            let data = read(path, transpaer::reader::parse_countries)?;
+            Ok(check_countries(path, &data, kind).into_all())
+        });
+    }
+
+    fn check_category_file(report: &mut AdvisorCheckReport, path: &Path, kind: TagKind) {
+        Self::check_file(report, path, |path| {
+            let data = read(path, transpaer::reader::parse_categories)?;
+            Ok(check_categories(path, &data, kind).into_all())
+        });
+    }
+
+    /// Checks all the meta and support files used by advisors.
+    pub fn check(config: &config::AdvisorCheckConfig) -> AdvisorCheckReport {
+        let meta = &config.meta;
+        let support = &config.support;
+        let mut report = AdvisorCheckReport::default();
+
+        Self::check_country_file(&mut report, &meta.wikidata_regions_path, TagKind::WikiId);
+        Self::check_category_file(&mut report, &meta.wikidata_categories_path, TagKind::WikiId);
+        Self::check_country_file(&mut report, &meta.bcorp_regions_path, TagKind::Plain);
+        Self::check_country_file(&mut report, &meta.eu_ecolabel_regions_path, TagKind::Plain);
+        Self::check_country_file(&mut report, &meta.open_food_facts_regions_path, TagKind::Plain);
+        Self::check_category_file(
+            &mut report,
+            &meta.open_food_facts_categories_path,
+            TagKind::Plain,
+        );
+        Self::check_file(&mut report, &meta.matches_path, |path| {
+            let data = read(path, transpaer::reader::parse_id_map)?;
+            Ok(check_matches(path, &data).into_all())
+        });
+        Self::check_file(&mut report, &support.tco_path, |path| {
+            read(path, tco::reader::parse).map(|_| Vec::new())
+        });
+        Self::check_file(&mut report, &support.fashion_transparency_index_path, |path| {
+            let data = read(path, fashion_transparency_index::reader::parse)?;
+            Ok(check_fashion_transparency_index(path, &data).into_all())
+        });
+
+        report
+    }
+
+    pub fn run(config: &config::AdvisorCheckConfig) -> Result<(), errors::ProcessingError> {
+        let report = Self::check(config);
+        report.report();
+
+        match report.num_issues() {
+            0 => Ok(()),
+            failed => Err(errors::ProcessingError::AdvisorCheckFailed { failed }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn report_invalid_regions_with_line_and_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "regions.yaml",
+            "countries:\n\
+             - tag: Q30\n  regions: !list [USA]\n  count: 5\n\
+             - tag: Q183\n  regions: !list [XYZ]\n  count: 3\n\
+             - tag: Q30\n  count: 1\n\
+             - tag: poland\n  count: 1\n",
+        );
+
+        let error = load_countries(&path, TagKind::Plain).unwrap_err();
+        assert_eq!(error.line, Some(5));
+        assert_eq!(error.key.as_deref(), Some("Q183"));
+
+        let data = read(&path, transpaer::reader::parse_countries).unwrap();
+        let issues = check_countries(&path, &data, TagKind::WikiId).into_all();
+        let found: Vec<_> = issues
+            .iter()
+            .map(|issue| (issue.line, issue.key.clone().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(5), "Q183".to_owned()),
+                (Some(8), "Q30".to_owned()),
+                (Some(10), "poland".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tolerate_repeated_tags_when_loading() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "regions.yaml",
+            "countries:\n- tag: poland\n  count: 5\n- tag: poland\n  count: 1\n",
+        );
+
+        let data = load_countries(&path, TagKind::Plain).unwrap();
+        assert_eq!(data.countries.len(), 2);
+
+        let issues = check_countries(&path, &data, TagKind::Plain);
+        assert!(issues.invalid.is_empty());
+        assert_eq!(issues.repeated.len(), 1);
+        assert_eq!(issues.repeated[0].line, Some(4));
+    }
+
+    #[test]
+    fn report_parsing_errors_with_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(
+            dir.path(),
+            "categories.yaml",
+            "categories:\n- tag: Q1\n  count: 1\n- tag: Q2\n  count: many\n",
+        );
+
+        let error = load_categories(&path, TagKind::WikiId).unwrap_err();
+        assert_eq!(error.path, path);
+        assert_eq!(error.line, Some(5));
+        assert!(error.to_string().contains("line 5"));
+    }
+}
@@ -0,0 +1,365 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `verify-db` command.
+//!
+//! Corrupted or partially written buckets otherwise surface only as errors in the backend. This
+//! command reads every entry of every bucket of the database and checks that the IDs stored in
+//! the entries and indices point to existing records.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use transpaer_models::{
+    buckets::{Bucket, BucketError, Codec, DbStore},
+    store,
+};
+
+use crate::{config, errors};
+
+/// Maximal number of problems listed for a single check.
+const MAX_EXAMPLES: usize = 10;
+
+/// Outcome of a single check.
+#[derive(Debug)]
+struct Check {
+    /// Description of the check.
+    name: String,
+
+    /// Number of checked entries or IDs.
+    checked: usize,
+
+    /// Number of problems found.
+    failed: usize,
+
+    /// The first few problems found.
+    examples: Vec<String>,
+}
+
+impl Check {
+    fn new(name: String) -> Self {
+        Self { name, checked: 0, failed: 0, examples: Vec::new() }
+    }
+
+    fn pass(&mut self) {
+        self.checked += 1;
+    }
+
+    fn fail(&mut self, problem: String) {
+        self.checked += 1;
+        self.failed += 1;
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(problem);
+        }
+    }
+
+    fn passed(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// Pass/fail report of the database verification.
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    checks: Vec<Check>,
+}
+
+impl VerificationReport {
+    /// Returns the number of failed checks.
+    #[must_use]
+    pub fn num_failed(&self) -> usize {
+        self.checks.iter().filter(|check| !check.passed()).count()
+    }
+
+    pub fn report(&self) {
+        log::info!("Verification report:");
+        for check in &self.checks {
+            let status = if check.passed() { "PASS" } else { "FAIL" };
+            log::info!(
+                " - {status} {: <70} {: >9} checked {: >7} failed",
+                check.name,
+                check.checked,
+                check.failed,
+            );
+            for example in &check.examples {
+                log::warn!("     - {example}");
+            }
+        }
+
+        let num_failed = self.num_failed();
+        if num_failed == 0 {
+            log::info!("Database verification passed all {} checks", self.checks.len());
+        } else {
+            log::error!(
+                "Database verification failed {num_failed} out of {} checks",
+                self.checks.len()
+            );
+        }
+    }
+}
+
+/// IDs referenced by entries of one bucket which are expected in another bucket.
+///
+/// Only the first referrer of each ID is remembered.
+struct References<T> {
+    referrers: HashMap<T, String>,
+}
+
+impl<T> References<T>
+where
+    T: Debug + Hash + Eq,
+{
+    fn new() -> Self {
+        Self { referrers: HashMap::new() }
+    }
+
+    fn add(&mut self, id: T, referrer: impl FnOnce() -> String) {
+        self.referrers.entry(id).or_insert_with(referrer);
+    }
+
+    /// Checks that all the referenced IDs exist.
+    fn verify(self, name: String, existing: &HashSet<T>) -> Check {
+        let mut check = Check::new(name);
+        for (id, referrer) in self.referrers {
+            if existing.contains(&id) {
+                check.pass();
+            } else {
+                check.fail(format!("{referrer} -> {id:?}"));
+            }
+        }
+        check
+    }
+}
+
+/// Reads all entries of the bucket passing the ones which could be read to `visit`.
+fn scan<K, V, C>(
+    report: &mut VerificationReport,
+    name: &str,
+    bucket: Result<Bucket<'_, K, V, C>, BucketError>,
+    mut visit: impl FnMut(K, V),
+) where
+    K: Serialize + DeserializeOwned + Eq + Hash,
+    C: Codec<V>,
+{
+    let mut check = Check::new(format!("`{name}` is readable"));
+    match bucket {
+        Ok(bucket) => {
+            for item in bucket.iter() {
+                match item {
+                    Ok((key, value)) => {
+                        check.pass();
+                        visit(key, value);
+                    }
+                    Err(error @ BucketError::Serde(_)) => check.fail(error.to_string()),
+                    Err(error) => {
+                        // Storage errors might repeat infinitely.
+                        check.fail(error.to_string());
+                        break;
+                    }
+                }
+            }
+        }
+        Err(error) => check.fail(error.to_string()),
+    }
+    report.checks.push(check);
+}
+
+/// Reads all entries of an index bucket and checks that the IDs it points to exist.
+///
+/// `targets` extracts the pointed IDs from an entry.
+fn scan_index<K, V, C, T, I>(
+    report: &mut VerificationReport,
+    name: &str,
+    bucket: Result<Bucket<'_, K, V, C>, BucketError>,
+    existing: &HashSet<T>,
+    mut targets: impl FnMut(&K, V) -> I,
+) where
+    K: Serialize + DeserializeOwned + Eq + Hash + Debug,
+    C: Codec<V>,
+    T: Debug + Hash + Eq,
+    I: IntoIterator<Item = T>,
+{
+    let mut check = Check::new(format!("`{name}` points to existing entries"));
+    scan(report, name, bucket, |key, value| {
+        for target in targets(&key, value) {
+            if existing.contains(&target) {
+                check.pass();
+            } else {
+                check.fail(format!("{key:?} -> {target:?}"));
+            }
+        }
+    });
+    report.checks.push(check);
+}
+
+pub struct VerificationRunner;
+
+impl VerificationRunner {
+    /// Runs all the checks on the database.
+    #[must_use]
+    pub fn verify(store: &DbStore) -> VerificationReport {
+        let mut report = VerificationReport::default();
+
+        log::info!("Verifying organisations");
+        let mut organisation_ids = HashSet::<store::OrganisationId>::new();
+        let mut organisation_products = References::<store::ProductId>::new();
+        scan(
+            &mut report,
+            "organisation.id => organisation",
+            store.get_organisation_bucket(),
+            |id, organisation| {
+                for product_id in organisation.products {
+                    organisation_products.add(product_id, || format!("organisation {id:?}"));
+                }
+                organisation_ids.insert(id);
+            },
+        );
+
+        log::info!("Verifying products");
+        let mut product_ids = HashSet::<store::ProductId>::new();
+        let mut related_products = References::<store::ProductId>::new();
+        let mut manufacturers = Check::new("product manufacturers exist".to_owned());
+        scan(&mut report, "product.id => product", store.get_product_bucket(), |id, product| {
+            for manufacturer in product.manufacturers {
+                if organisation_ids.contains(&manufacturer.id) {
+                    manufacturers.pass();
+                } else {
+                    manufacturers.fail(format!("product {id:?} -> {:?}", manufacturer.id));
+                }
+            }
+            for related in product.follows.into_iter().chain(product.followed_by) {
+                related_products.add(related, || format!("product {id:?}"));
+            }
+            product_ids.insert(id);
+        });
+        report.checks.push(manufacturers);
+        report.checks.push(
+            related_products
+                .verify("followed and following products exist".to_owned(), &product_ids),
+        );
+        report.checks.push(
+            organisation_products.verify("organisation products exist".to_owned(), &product_ids),
+        );
+
+        log::info!("Verifying organisation indices");
+        scan_index(
+            &mut report,
+            "keyword => [organisation.id]",
+            store.get_keyword_to_organisation_ids_bucket(),
+            &organisation_ids,
+            |_, ids| ids,
+        );
+        scan_index(
+            &mut report,
+            "organisation.vat_id => organisation.id",
+            store.get_vat_id_to_organisation_id_bucket(),
+            &organisation_ids,
+            |_, id| Some(id),
+        );
+        scan_index(
+            &mut report,
+            "organisation.wiki_id => organisation.id",
+            store.get_wiki_id_to_organisation_id_bucket(),
+            &organisation_ids,
+            |_, id| Some(id),
+        );
+        scan_index(
+            &mut report,
+            "organisation.www_domain => organisation.id",
+            store.get_www_domain_to_organisation_id_bucket(),
+            &organisation_ids,
+            |_, id| Some(id),
+        );
+
+        log::info!("Verifying product indices");
+        scan_index(
+            &mut report,
+            "product.category => [product.id]",
+            store.get_categories_bucket(),
+            &product_ids,
+            |_, category| category.products.unwrap_or_default(),
+        );
+        scan_index(
+            &mut report,
+            "keyword => [product.id]",
+            store.get_keyword_to_product_ids_bucket(),
+            &product_ids,
+            |_, ids| ids,
+        );
+        scan_index(
+            &mut report,
+            "product.ean => product.id",
+            store.get_ean_to_product_id_bucket(),
+            &product_ids,
+            |_, id| Some(id),
+        );
+        scan_index(
+            &mut report,
+            "product.gtin => product.id",
+            store.get_gtin_to_product_id_bucket(),
+            &product_ids,
+            |_, id| Some(id),
+        );
+        scan_index(
+            &mut report,
+            "product.wiki_id => product.id",
+            store.get_wiki_id_to_product_id_bucket(),
+            &product_ids,
+            |_, id| Some(id),
+        );
+
+        report
+    }
+
+    pub fn run(config: &config::VerificationConfig) -> Result<(), errors::ProcessingError> {
+        let store = DbStore::open_read_only(&config.crystal)?;
+        let report = Self::verify(&store);
+        report.report();
+
+        match report.num_failed() {
+            0 => Ok(()),
+            failed => Err(errors::ProcessingError::VerificationFailed { failed }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use transpaer_models::gather;
+
+    use super::*;
+
+    #[test]
+    fn dangling_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DbStore::new(dir.path()).unwrap();
+
+        let p1 = store::ProductId::from_value(1);
+        let p2 = store::ProductId::from_value(2);
+        let products = store.get_product_bucket().unwrap();
+        products.insert(&p1, &gather::Product::default().store()).unwrap();
+        products.flush().unwrap();
+
+        let keywords = store.get_keyword_to_product_ids_bucket().unwrap();
+        keywords.insert(&"phone".to_owned(), &vec![p1.clone()]).unwrap();
+        keywords.flush().unwrap();
+
+        let report = VerificationRunner::verify(&store);
+        assert_eq!(report.num_failed(), 0);
+
+        keywords.insert(&"tablet".to_owned(), &vec![p1, p2]).unwrap();
+        keywords.flush().unwrap();
+
+        let report = VerificationRunner::verify(&store);
+        assert_eq!(report.num_failed(), 1);
+        let failed = report.checks.iter().find(|check| !check.passed()).unwrap();
+        assert_eq!(failed.name, "`keyword => [product.id]` points to existing entries");
+        assert_eq!(failed.failed, 1);
+    }
+}
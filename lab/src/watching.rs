@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Watch mode of the `crystalize` command.
+//!
+//! Re-runs coagulation and crystalization whenever new substrate files are dropped into the
+//! substrate directory. Every run creates a fresh database, so the one currently in use is never
+//! modified. After a successful run the database link is switched to the new database.
+//!
+//! The runs are synchronous, so they are executed on the blocking thread pool to keep the async
+//! runtime responsive.
+
+use std::{path::Path, time::Duration};
+
+use notify::Watcher;
+
+use transpaer_collecting::errors::MapIo;
+
+use crate::{coagulating::Coagulator, config, crystalizing::Crystalizer, errors, utils};
+
+/// Number of the newest databases to keep.
+///
+/// The previous database is kept because it may still be in use until the backend reloads.
+const NUM_GENERATIONS_TO_KEEP: usize = 2;
+
+pub struct WatchingRunner;
+
+impl WatchingRunner {
+    /// Runs the watch mode until an unrecoverable error occurs.
+    ///
+    /// Failures of a single run are logged and the next change to the substrates triggers
+    /// a new run.
+    pub async fn run(
+        config: &config::CrystalizationConfig,
+        watch: &config::WatchingConfig,
+    ) -> Result<(), errors::ProcessingError> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                // The receiver is dropped only when the watch mode ends.
+                let _ = tx.send(event);
+            })
+            .map_err(errors::WatchingError::from)?;
+        watcher
+            .watch(&config.substrate.substrate_path, notify::RecursiveMode::NonRecursive)
+            .map_err(errors::WatchingError::from)?;
+
+        log::info!("Watching `{}`", config.substrate.substrate_path.display());
+        loop {
+            if let Err(err) = Self::rebuild(config, watch).await {
+                log::error!("Rebuilding failed:\n{err}");
+            }
+
+            Self::wait_for_changes(&mut rx, watch.debounce).await?;
+            log::info!("Substrates changed, rebuilding");
+        }
+    }
+
+    /// Waits for a change and then until no more changes are reported for the `debounce` time.
+    async fn wait_for_changes(
+        rx: &mut tokio::sync::mpsc::UnboundedReceiver<notify::Result<notify::Event>>,
+        debounce: Duration,
+    ) -> Result<(), errors::WatchingError> {
+        loop {
+            let event = rx.recv().await.ok_or(errors::WatchingError::WatcherStopped)?;
+            if Self::is_change(event)? {
+                break;
+            }
+        }
+
+        loop {
+            match tokio::time::timeout(debounce, rx.recv()).await {
+                Ok(Some(event)) => {
+                    Self::is_change(event)?;
+                }
+                Ok(None) => return Err(errors::WatchingError::WatcherStopped),
+                Err(_) => return Ok(()),
+            }
+        }
+    }
+
+    fn is_change(event: notify::Result<notify::Event>) -> Result<bool, errors::WatchingError> {
+        Ok(!event?.kind.is_access())
+    }
+
+    /// Runs the synchronous `f` on the blocking thread pool.
+    async fn blocking<T, F>(f: F) -> Result<T, errors::ProcessingError>
+    where
+        F: FnOnce() -> Result<T, errors::ProcessingError> + Send + 'static,
+        T: Send + 'static,
+    {
+        tokio::task::spawn_blocking(f).await.map_err(errors::WatchingError::from)?
+    }
+
+    /// Creates a new database and notifies the backend about it.
+    async fn rebuild(
+        config: &config::CrystalizationConfig,
+        watch: &config::WatchingConfig,
+    ) -> Result<(), errors::ProcessingError> {
+        let (config, watch) = (config.clone(), watch.clone());
+        Self::blocking(move || Self::build(&config, &watch)).await?;
+
+        if let Some(url) = &watch.reload_url {
+            log::info!("Notifying `{url}`");
+            reqwest::Client::new()
+                .post(url)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(errors::WatchingError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Coagulates and crystalizes the substrates into a new generation and links it.
+    fn build(
+        config: &config::CrystalizationConfig,
+        watch: &config::WatchingConfig,
+    ) -> Result<(), errors::ProcessingError> {
+        let coagulation = config::CoagulationConfig {
+            substrate: config.substrate.clone(),
+            workspace: watch.coagulate_workspace.clone(),
+            coagulate: config.coagulate.clone(),
+        };
+        log::info!("Start coagulation!");
+        Coagulator::run(&coagulation)?;
+
+        let generation = watch.generations.join(Self::generation_name());
+        let crystalization = config::CrystalizationConfig {
+            crystal: generation.join("db"),
+            runtime: generation.join("runtime"),
+            watch: None,
+            ..config.clone()
+        };
+        log::info!("Start crystalization into `{}`", generation.display());
+        Crystalizer::run(&crystalization)?;
+
+        utils::switch_link(&crystalization.crystal, &config.crystal)?;
+        log::info!(
+            "Database `{}` now links to `{}`",
+            config.crystal.display(),
+            generation.display()
+        );
+
+        Self::remove_old_generations(&watch.generations)?;
+        Ok(())
+    }
+
+    fn generation_name() -> String {
+        let now =
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+        format!("{:020}", now.as_millis())
+    }
+
+    fn remove_old_generations(path: &Path) -> Result<(), errors::ProcessingError> {
+        let mut generations = Vec::new();
+        for entry in std::fs::read_dir(path).map_with_path(path)? {
+            generations.push(entry.map_with_path(path)?.path());
+        }
+        generations.sort();
+
+        let num_to_remove = generations.len().saturating_sub(NUM_GENERATIONS_TO_KEEP);
+        for generation in generations.into_iter().take(num_to_remove) {
+            log::info!("Removing `{}`", generation.display());
+            std::fs::remove_dir_all(&generation).map_with_path(&generation)?;
+        }
+        Ok(())
+    }
+}
@@ -49,18 +49,75 @@ pub mod organisations {
     ];
 }
 
+/// Wikidata IDs of units and their factors for conversion to the SI base unit.
+pub mod units {
+    pub const KILOGRAM: u64 = 11_570;
+    pub const GRAM: u64 = 41_803;
+    pub const MILLIGRAM: u64 = 3_241_121;
+    pub const TONNE: u64 = 191_118;
+    pub const POUND: u64 = 100_995;
+    pub const OUNCE: u64 = 48_013;
+
+    pub const METRE: u64 = 11_573;
+    pub const CENTIMETRE: u64 = 174_728;
+    pub const MILLIMETRE: u64 = 174_789;
+    pub const INCH: u64 = 218_593;
+    pub const FOOT: u64 = 3_710;
+
+    /// Mass units with factors for conversion to kilograms.
+    pub const MASS: &[(u64, f64)] = &[
+        (KILOGRAM, 1.0),
+        (GRAM, 1e-3),
+        (MILLIGRAM, 1e-6),
+        (TONNE, 1e3),
+        (POUND, 0.453_592_37),
+        (OUNCE, 0.028_349_523_125),
+    ];
+
+    /// Length units with factors for conversion to metres.
+    pub const LENGTH: &[(u64, f64)] =
+        &[(METRE, 1.0), (CENTIMETRE, 1e-2), (MILLIMETRE, 1e-3), (INCH, 0.0254), (FOOT, 0.3048)];
+}
+
+/// Properties which only individual artifacts (as opposed to product models) have.
+const INDIVIDUAL_PROPERTIES: &[&str] = &[
+    properties::SERIAL_NUMBER,
+    properties::IMO_SHIP_NUMBER,
+    properties::VESSEL_CLASS,
+    properties::AIRCRAFT_REGISTRATION,
+];
+
+/// Properties hinting at a unique instance if the item also has an inception date.
+const UNIQUE_INSTANCE_HINTS: &[&str] = &[properties::LOCATION, properties::OWNED_BY];
+
+/// Physical dimensions of a product in metres.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dimensions {
+    pub height: Option<f64>,
+    pub width: Option<f64>,
+    pub thickness: Option<f64>,
+}
+
 #[allow(dead_code)]
 pub trait ItemExt {
     /// Returns items label in the speified language.
     fn get_label(&self, lang: data::Language) -> Option<&str>;
 
-    /// Returns all labels proritizing English.
-    fn get_labels(&self) -> Vec<&str>;
+    /// Returns labels in all languages, the preferred languages first in the order of preference.
+    ///
+    /// Labels with repeated values are returned only once.
+    fn get_labels(&self, languages: &[String]) -> Vec<&data::Label>;
+
+    /// Returns the description in the most preferred of the accepted languages.
+    fn get_description(&self, languages: &[String]) -> Option<&data::Label>;
 
     /// Returns all labels and aliases.
     fn get_all_labels_and_aliases(&self) -> HashSet<&str>;
 
     /// Returns ID associated with the passed property.
+    ///
+    /// Deprecated statements are ignored and preferred ones come first. The same holds for all the
+    /// other accessors of property values.
     fn get_entity_ids(
         &self,
         property_id: &str,
@@ -69,7 +126,23 @@ pub trait ItemExt {
     /// Returns strings associated with the passed property.
     fn get_strings(&self, property_id: &str) -> Option<Vec<String>>;
 
-    /// Checks if has at least one passed property.
+    /// Returns quantities associated with the passed property.
+    fn get_quantities(&self, property_id: &str) -> Option<Vec<data::Quantity>>;
+
+    /// Returns the first quantity of the passed property which has one of the passed units.
+    ///
+    /// The amount is multiplied by the factor associated with the unit.
+    fn get_converted_quantity(&self, property_id: &str, units: &[(u64, f64)]) -> Option<f64>;
+
+    /// Returns the mass in kilograms.
+    #[must_use]
+    fn get_mass(&self) -> Option<f64>;
+
+    /// Returns the dimensions in metres.
+    #[must_use]
+    fn get_dimensions(&self) -> Option<Dimensions>;
+
+    /// Checks if has at least one non-deprecated statement of the passed property.
     fn has_property(&self, property_id: &str) -> bool;
 
     /// Checks if this item is related to another via a specified property.
@@ -170,9 +243,23 @@ pub trait ItemExt {
     #[must_use]
     fn is_product(&self) -> bool;
 
+    /// Checks if this item is an individual artifact (e.g. a specific ship) rather than a model.
+    #[must_use]
+    fn is_individual(&self) -> bool;
+
     /// Extracts internet domains from website addresses.
     #[must_use]
     fn extract_domains(&self) -> Option<HashSet<String>>;
+
+    /// Returns the number of sitelinks (links to Wikipedia and other Wikimedia projects).
+    #[must_use]
+    fn get_sitelink_count(&self) -> usize;
+
+    /// Checks if the item has at least `min_sitelinks` sitelinks.
+    ///
+    /// Items with GTINs are always considered notable as they can be looked up by scanning.
+    #[must_use]
+    fn is_notable(&self, min_sitelinks: usize) -> bool;
 }
 
 impl ItemExt for data::Item {
@@ -180,18 +267,25 @@ impl ItemExt for data::Item {
         self.labels.get(lang.as_str()).map(|label| label.value.as_str())
     }
 
-    fn get_labels(&self) -> Vec<&str> {
-        if let Some(en_label) = self.labels.get(data::Language::En.as_str()) {
-            vec![en_label.value.as_str()]
-        } else {
-            let mut labels = Vec::new();
-            for (lang, label) in &self.labels {
-                if lang != data::Language::En.as_str() {
-                    labels.push(label.value.as_str());
-                }
-            }
-            labels
-        }
+    fn get_labels(&self, languages: &[String]) -> Vec<&data::Label> {
+        let mut labels: Vec<&data::Label> =
+            languages.iter().filter_map(|lang| self.labels.get(lang)).collect();
+        let mut others: Vec<&data::Label> = self
+            .labels
+            .iter()
+            .filter(|(lang, _)| !languages.contains(lang))
+            .map(|(_, label)| label)
+            .collect();
+        others.sort_by(|a, b| a.language.cmp(&b.language));
+        labels.extend(others);
+
+        let mut values = HashSet::new();
+        labels.retain(|label| values.insert(label.value.as_str()));
+        labels
+    }
+
+    fn get_description(&self, languages: &[String]) -> Option<&data::Label> {
+        languages.iter().find_map(|lang| self.descriptions.get(lang))
     }
 
     fn get_all_labels_and_aliases(&self) -> HashSet<&str> {
@@ -211,10 +305,9 @@ impl ItemExt for data::Item {
         &self,
         property_id: &str,
     ) -> Result<Option<Vec<data::Id>>, errors::ParseIdError> {
-        if let Some(claims) = self.claims.get(property_id) {
+        if let Some(statements) = self.get_statements(property_id) {
             let mut result = Vec::<data::Id>::new();
-            for claim in claims {
-                let data::Claim::Statement(statement) = claim;
+            for statement in statements {
                 if let data::Snak::Value(value) = &statement.mainsnak
                     && let data::DataValue::WikibaseEntityId(data::EntityIdDataValue::Item(
                         entity_info,
@@ -230,9 +323,8 @@ impl ItemExt for data::Item {
     }
 
     fn relates(&self, property: &str, class: &str) -> bool {
-        if let Some(claims) = self.claims.get(property) {
-            for claim in claims {
-                let data::Claim::Statement(statement) = claim;
+        if let Some(statements) = self.get_statements(property) {
+            for statement in statements {
                 if let data::Snak::Value(value) = &statement.mainsnak
                     && let data::DataValue::WikibaseEntityId(data::EntityIdDataValue::Item(
                         entity_info,
@@ -247,10 +339,9 @@ impl ItemExt for data::Item {
     }
 
     fn get_strings(&self, property_id: &str) -> Option<Vec<String>> {
-        if let Some(claims) = self.claims.get(property_id) {
+        if let Some(statements) = self.get_statements(property_id) {
             let mut result = Vec::new();
-            for claim in claims {
-                let data::Claim::Statement(statement) = claim;
+            for statement in statements {
                 if let data::Snak::Value(value) = &statement.mainsnak {
                     if let data::DataValue::String(website) = &value.datavalue {
                         result.push(website.clone());
@@ -270,8 +361,59 @@ impl ItemExt for data::Item {
         }
     }
 
+    fn get_quantities(&self, property_id: &str) -> Option<Vec<data::Quantity>> {
+        if let Some(statements) = self.get_statements(property_id) {
+            let mut result = Vec::new();
+            for statement in statements {
+                if let data::Snak::Value(value) = &statement.mainsnak {
+                    if let data::DataValue::Quantity(quantity) = &value.datavalue {
+                        match quantity.to_quantity() {
+                            Ok(quantity) => result.push(quantity),
+                            Err(err) => log::warn!(
+                                "Item {:?} has property {} with invalid quantity: {}",
+                                self.id,
+                                property_id,
+                                err
+                            ),
+                        }
+                    } else {
+                        log::warn!(
+                            "Item {:?} has properties {} which are not quantities: {:?}",
+                            self.id,
+                            property_id,
+                            value
+                        );
+                    }
+                }
+            }
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    fn get_converted_quantity(&self, property_id: &str, units: &[(u64, f64)]) -> Option<f64> {
+        self.get_quantities(property_id)?.into_iter().find_map(|quantity| {
+            let unit = quantity.unit?.get_value();
+            units.iter().find(|(id, _)| *id == unit).map(|(_, factor)| quantity.amount * factor)
+        })
+    }
+
+    fn get_mass(&self) -> Option<f64> {
+        self.get_converted_quantity(properties::MASS, units::MASS)
+    }
+
+    fn get_dimensions(&self) -> Option<Dimensions> {
+        let dimensions = Dimensions {
+            height: self.get_converted_quantity(properties::HEIGHT, units::LENGTH),
+            width: self.get_converted_quantity(properties::WIDTH, units::LENGTH),
+            thickness: self.get_converted_quantity(properties::THICKNESS, units::LENGTH),
+        };
+        if dimensions == Dimensions::default() { None } else { Some(dimensions) }
+    }
+
     fn has_property(&self, property_id: &str) -> bool {
-        if let Some(claims) = self.claims.get(property_id) { !claims.is_empty() } else { false }
+        self.get_statements(property_id).is_some_and(|statements| !statements.is_empty())
     }
 
     fn get_countries(&self) -> Result<Option<Vec<data::Id>>, errors::ParseIdError> {
@@ -394,7 +536,137 @@ impl ItemExt for data::Item {
         self.has_manufacturer() || self.has_gtin()
     }
 
+    fn is_individual(&self) -> bool {
+        if INDIVIDUAL_PROPERTIES.iter().any(|property| self.has_property(property)) {
+            return true;
+        }
+
+        self.has_property(properties::INCEPTION)
+            && UNIQUE_INSTANCE_HINTS.iter().any(|property| self.has_property(property))
+    }
+
     fn extract_domains(&self) -> Option<HashSet<String>> {
         self.get_official_websites().map(|u| utils::extract_domains_from_urls(&u))
     }
+
+    fn get_sitelink_count(&self) -> usize {
+        self.sitelinks.len()
+    }
+
+    fn is_notable(&self, min_sitelinks: usize) -> bool {
+        self.get_sitelink_count() >= min_sitelinks || self.has_gtin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn statement(property: &str, rank: &str, datavalue: &Value) -> Value {
+        json!({
+            "type": "statement",
+            "id": format!("Q1${property}-{rank}"),
+            "rank": rank,
+            "mainsnak": { "snaktype": "value", "property": property, "datavalue": datavalue },
+        })
+    }
+
+    fn string(value: &str) -> Value {
+        json!({ "type": "string", "value": value })
+    }
+
+    fn quantity(amount: &str) -> Value {
+        json!({ "type": "quantity", "value": { "amount": amount, "unit": "1" } })
+    }
+
+    fn class(id: u64) -> Value {
+        json!({
+            "type": "wikibase-entityid",
+            "value": { "entity-type": "item", "id": format!("Q{id}"), "numeric-id": id },
+        })
+    }
+
+    fn item(claims: Value) -> data::Item {
+        serde_json::from_value(json!({
+            "id": "Q1",
+            "lastrevid": 1,
+            "labels": {},
+            "descriptions": {},
+            "aliases": {},
+            "claims": claims,
+            "sitelinks": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn labels_in_all_languages() {
+        let product: data::Item = serde_json::from_value(json!({
+            "id": "Q1",
+            "lastrevid": 1,
+            "labels": {
+                "de": { "language": "de", "value": "Telefon" },
+                "nl": { "language": "nl", "value": "Telefoon" },
+                "fr": { "language": "fr", "value": "Téléphone" },
+                "pl": { "language": "pl", "value": "Telefon" },
+            },
+            "descriptions": {},
+            "aliases": {},
+            "claims": {},
+            "sitelinks": {},
+        }))
+        .unwrap();
+
+        let labels: Vec<(&str, &str)> = product
+            .get_labels(&["en".to_owned(), "nl".to_owned()])
+            .into_iter()
+            .map(|label| (label.language.as_str(), label.value.as_str()))
+            .collect();
+        assert_eq!(labels, vec![("nl", "Telefoon"), ("de", "Telefon"), ("fr", "Téléphone")]);
+    }
+
+    #[test]
+    fn deprecated_statements_are_skipped() {
+        let product = item(json!({
+            (properties::GTIN): [
+                statement(properties::GTIN, "deprecated", &string("0000000000017")),
+                statement(properties::GTIN, "normal", &string("0000000000024")),
+                statement(properties::GTIN, "preferred", &string("0000000000031")),
+            ],
+            (properties::ASIN): [statement(properties::ASIN, "deprecated", &string("B000000000"))],
+            (properties::INSTANCE_OF): [
+                statement(properties::INSTANCE_OF, "deprecated", &class(organisations::COMPANY)),
+            ],
+        }));
+
+        assert_eq!(
+            product.get_gtins(),
+            Some(vec!["0000000000031".to_owned(), "0000000000024".to_owned()])
+        );
+        assert!(product.has_gtin());
+        assert_eq!(product.get_asins(), Some(vec![]));
+        assert!(!product.has_asin());
+        assert!(!product.is_instance_of(&format!("Q{}", organisations::COMPANY)));
+        assert_eq!(product.get_classes().unwrap(), Some(vec![]));
+    }
+
+    #[test]
+    fn preferred_statements_come_first() {
+        let product = item(json!({
+            (properties::MASS): [
+                statement(properties::MASS, "normal", &quantity("+200")),
+                statement(properties::MASS, "preferred", &quantity("+150")),
+                statement(properties::MASS, "deprecated", &quantity("+999")),
+            ],
+        }));
+        let amounts: Vec<f64> = product
+            .get_quantities(properties::MASS)
+            .unwrap()
+            .into_iter()
+            .map(|quantity| quantity.amount)
+            .collect();
+        assert_eq!(amounts, vec![150.0, 200.0]);
+    }
 }
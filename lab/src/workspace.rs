@@ -0,0 +1,188 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Temporary directories of single runs.
+//!
+//! Every run gets a fresh directory under the configured workspace root, named after the command
+//! and the start time of the run. Only such directories are ever removed, so pointing the root at
+//! a wrong place cannot wipe unrelated data. An exclusive lock on a lockfile next to them prevents
+//! concurrent runs of the same command from sharing the workspace. The lock is held by the
+//! operating system, so it is released also when a run gets killed and the lockfile it leaves
+//! behind does not block the next runs.
+
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use crate::{config, errors::WorkspaceError};
+
+/// Default number of days after which directories left by previous runs are removed.
+pub const DEFAULT_MAX_AGE_DAYS: u64 = 7;
+
+/// Number of seconds in a day.
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Temporary directory of a single run.
+///
+/// The directory is removed and the workspace unlocked when dropped.
+#[derive(Debug)]
+pub struct RunDir {
+    path: PathBuf,
+
+    /// The locked lockfile.
+    ///
+    /// The file is kept after the run, removing it could let two runs lock different files.
+    lock: File,
+}
+
+impl RunDir {
+    /// Locks the workspace for the given command, removes directories of its previous runs older
+    /// than the configured age and creates a fresh directory for this run.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if another run of the command holds the lock or the directories cannot be
+    /// created or removed.
+    pub fn create(config: &config::WorkspaceConfig, name: &str) -> Result<Self, WorkspaceError> {
+        let root = &config.root;
+        std::fs::create_dir_all(root).map_err(|e| WorkspaceError::Io(e, root.clone()))?;
+
+        let lock_path = root.join(format!("{name}.lock"));
+        let lock = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| WorkspaceError::Io(e, lock_path.clone()))?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(std::fs::TryLockError::WouldBlock) => {
+                return Err(WorkspaceError::Locked(lock_path));
+            }
+            Err(std::fs::TryLockError::Error(err)) => {
+                return Err(WorkspaceError::Io(err, lock_path));
+            }
+        }
+
+        // The PID is recorded only to help finding the run holding the lock.
+        {
+            use std::io::Write;
+            lock.set_len(0)
+                .and_then(|()| writeln!(&lock, "{}", std::process::id()))
+                .map_err(|e| WorkspaceError::Io(e, lock_path.clone()))?;
+        }
+
+        // From here on the lock is released by `drop` also on errors.
+        let mut result = Self { path: PathBuf::new(), lock };
+        Self::remove_old(root, name, config.max_age_days)?;
+
+        let path = root.join(format!("{name}-{}", crate::utils::now_millis()));
+        std::fs::create_dir(&path).map_err(|e| WorkspaceError::Io(e, path.clone()))?;
+        log::info!("Using `{}` as the run directory", path.display());
+        result.path = path;
+        Ok(result)
+    }
+
+    /// Returns the path to the directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Removes directories of the previous runs of the command older than `max_age_days`.
+    fn remove_old(root: &Path, name: &str, max_age_days: u64) -> Result<(), WorkspaceError> {
+        let prefix = format!("{name}-");
+        let max_age = std::time::Duration::from_secs(max_age_days.saturating_mul(SECONDS_PER_DAY));
+        let now = std::time::SystemTime::now();
+        for entry in std::fs::read_dir(root).map_err(|e| WorkspaceError::Io(e, root.to_owned()))? {
+            let entry = entry.map_err(|e| WorkspaceError::Io(e, root.to_owned()))?;
+            let path = entry.path();
+            let is_run_dir = entry.file_name().to_str().is_some_and(|n| n.starts_with(&prefix));
+            if !is_run_dir || !path.is_dir() {
+                continue;
+            }
+
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map_err(|e| WorkspaceError::Io(e, path.clone()))?;
+            if now.duration_since(modified).unwrap_or_default() > max_age {
+                log::info!("Removing old run directory `{}`", path.display());
+                std::fs::remove_dir_all(&path).map_err(|e| WorkspaceError::Io(e, path))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RunDir {
+    fn drop(&mut self) {
+        if !self.path.as_os_str().is_empty()
+            && let Err(err) = std::fs::remove_dir_all(&self.path)
+        {
+            log::warn!("Failed to remove the run directory `{}`: {err}", self.path.display());
+        }
+        if let Err(err) = self.lock.unlock() {
+            log::warn!("Failed to unlock the workspace: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace(root: &Path, max_age_days: u64) -> config::WorkspaceConfig {
+        config::WorkspaceConfig { root: root.to_owned(), max_age_days }
+    }
+
+    #[test]
+    fn run_dir_is_locked_and_removed() {
+        let root = tempfile::tempdir().unwrap();
+        let config = workspace(root.path(), DEFAULT_MAX_AGE_DAYS);
+
+        let run = RunDir::create(&config, "coagulation").unwrap();
+        assert!(run.path().is_dir());
+        assert!(root.path().join("coagulation.lock").exists());
+        assert!(matches!(RunDir::create(&config, "coagulation"), Err(WorkspaceError::Locked(_))));
+
+        let path = run.path().to_owned();
+        drop(run);
+        assert!(!path.exists());
+        assert!(RunDir::create(&config, "coagulation").is_ok());
+    }
+
+    #[test]
+    fn stale_lockfile_does_not_lock() {
+        let root = tempfile::tempdir().unwrap();
+        let config = workspace(root.path(), DEFAULT_MAX_AGE_DAYS);
+
+        // A lockfile left behind by a killed run.
+        std::fs::write(root.path().join("coagulation.lock"), "4294967295\n").unwrap();
+
+        let run = RunDir::create(&config, "coagulation").unwrap();
+        assert!(run.path().is_dir());
+        let pid = std::fs::read_to_string(root.path().join("coagulation.lock")).unwrap();
+        assert_eq!(pid.trim(), std::process::id().to_string());
+    }
+
+    #[test]
+    fn only_old_run_dirs_are_removed() {
+        let root = tempfile::tempdir().unwrap();
+        let old_time =
+            std::time::SystemTime::now() - std::time::Duration::from_secs(2 * SECONDS_PER_DAY);
+        for name in ["coagulation-1", "unrelated-1"] {
+            let path = root.path().join(name);
+            std::fs::create_dir(&path).unwrap();
+            std::fs::File::open(&path).unwrap().set_modified(old_time).unwrap();
+        }
+        std::fs::create_dir(root.path().join("coagulation-2")).unwrap();
+
+        let _run = RunDir::create(&workspace(root.path(), 1), "coagulation").unwrap();
+        assert!(!root.path().join("coagulation-1").exists());
+        assert!(root.path().join("coagulation-2").exists());
+        assert!(root.path().join("unrelated-1").exists());
+    }
+}
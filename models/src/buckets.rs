@@ -2,12 +2,36 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
 
+use redb::{ReadableTable, ReadableTableMetadata};
 use serde::{Serialize, de::DeserializeOwned};
 use thiserror::Error;
 
-use crate::store;
+use crate::{store, versions};
+
+/// Number of entries read from a redb table at once during iteration.
+const REDB_ITER_CHUNK_SIZE: usize = 1024;
+
+/// Names of the buckets of `DbStore` covered by the digests.
+const DIGESTED_BUCKETS: &[&str] = &[
+    "organisation.id => organisation",
+    "keyword => [organisation.id]",
+    "organisation.vat_id => organisation.id",
+    "organisation.wiki_id => organisation.id",
+    "organisation.www_domain => organisation.id",
+    "product.category => [product.id]",
+    "product.id => product",
+    "keyword => [product.id]",
+    "product.ean => product.id",
+    "product.gtin => product.id",
+    "product.wiki_id => product.id",
+    "dataset.substrate => dataset.meta",
+];
 
 /// Errors related to key-value store.
 #[derive(Error, Debug)]
@@ -17,18 +41,442 @@ pub enum BucketError {
 
     #[error("KV operation failed: {0}")]
     Store(#[from] kv::Error),
+
+    #[error("Redb operation failed: {0}")]
+    Redb(#[from] redb::Error),
+
+    #[error("Failed to prepare the storage: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Unknown storage engine: {0}")]
+    UnknownEngine(String),
+
+    #[error("Storage not found: {0:?}")]
+    NotFound(std::path::PathBuf),
+
+    #[error("Attempted to write into a read-only storage")]
+    ReadOnly,
+}
+
+/// Storage engine backing the buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageEngine {
+    /// The `kv` crate (based on `sled`). The storage is a directory.
+    #[default]
+    Kv,
+
+    /// The `redb` crate. The storage is a single file.
+    Redb,
+}
+
+impl StorageEngine {
+    /// Guesses the engine of an existing storage.
+    ///
+    /// `redb` stores everything in a single file while `kv` creates a directory. If the path does
+    /// not exist the default engine is returned.
+    #[must_use]
+    pub fn detect(path: &Path) -> Self {
+        if path.is_file() { Self::Redb } else { Self::Kv }
+    }
+}
+
+impl std::str::FromStr for StorageEngine {
+    type Err = BucketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kv" => Ok(Self::Kv),
+            "redb" => Ok(Self::Redb),
+            _ => Err(BucketError::UnknownEngine(s.to_owned())),
+        }
+    }
+}
+
+impl std::fmt::Display for StorageEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Kv => write!(f, "kv"),
+            Self::Redb => write!(f, "redb"),
+        }
+    }
+}
+
+/// Converts bucket values to and from their stored representation.
+pub trait Codec<V> {
+    fn encode(value: &V) -> Result<Vec<u8>, BucketError>;
+
+    fn decode(data: &[u8]) -> Result<V, BucketError>;
+}
+
+/// Encodes values directly with `postcard`.
+///
+/// Suitable for values with a layout that never changes, e.g. IDs or lists of IDs.
+pub struct Plain;
+
+impl<V: Serialize + DeserializeOwned> Codec<V> for Plain {
+    fn encode(value: &V) -> Result<Vec<u8>, BucketError> {
+        Ok(postcard::to_stdvec(value)?)
+    }
+
+    fn decode(data: &[u8]) -> Result<V, BucketError> {
+        Ok(postcard::from_bytes(data)?)
+    }
+}
+
+/// Encodes values together with the version of their layout.
+///
+/// Entries written with an older version of the value type are migrated when read.
+pub struct Versioned;
+
+impl<V: versions::Evolving> Codec<V> for Versioned {
+    fn encode(value: &V) -> Result<Vec<u8>, BucketError> {
+        Ok(versions::encode(value)?)
+    }
+
+    fn decode(data: &[u8]) -> Result<V, BucketError> {
+        Ok(versions::decode(data)?)
+    }
+}
+
+/// Iterator over raw entries of a bucket.
+pub type RawIter = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>), BucketError>> + Send>;
+
+/// Operations a storage engine has to provide for a single bucket.
+///
+/// Keys and values are already serialized. Entries are expected to be iterated in the order of
+/// their keys.
+pub trait RawBucket: Send + Sync {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError>;
+
+    fn set(&self, key: &[u8], value: &[u8]) -> Result<(), BucketError>;
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError>;
+
+    /// Writes all the entries at once.
+    fn set_all(&self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), BucketError>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn flush(&self) -> Result<(), BucketError>;
+
+    fn iter(&self) -> RawIter;
+}
+
+impl RawBucket for kv::Bucket<'_, Vec<u8>, Vec<u8>> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError> {
+        Ok(kv::Bucket::get(self, &key.to_vec())?)
+    }
+
+    fn set(&self, key: &[u8], value: &[u8]) -> Result<(), BucketError> {
+        kv::Bucket::set(self, &key.to_vec(), &value.to_vec())?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError> {
+        Ok(kv::Bucket::remove(self, &key.to_vec())?)
+    }
+
+    fn set_all(&self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), BucketError> {
+        let mut batch = kv::Batch::new();
+        for (key, value) in entries {
+            batch.set(&key, &value)?;
+        }
+        kv::Bucket::batch(self, batch)?;
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        kv::Bucket::len(self)
+    }
+
+    fn flush(&self) -> Result<(), BucketError> {
+        kv::Bucket::flush(self)?;
+        Ok(())
+    }
+
+    fn iter(&self) -> RawIter {
+        Box::new(kv::Bucket::iter(self).map(|item| {
+            let item = item?;
+            Ok((item.key::<Vec<u8>>()?, item.value::<Vec<u8>>()?))
+        }))
+    }
 }
 
+/// Bucket stored as a table in a redb database.
+///
+/// Every write is a separate transaction committed with eventual durability. `flush` makes all
+/// of them durable.
+struct RedbBucket {
+    db: Arc<redb::Database>,
+    name: String,
+}
+
+impl RedbBucket {
+    fn definition(&self) -> redb::TableDefinition<'_, &'static [u8], &'static [u8]> {
+        redb::TableDefinition::new(&self.name)
+    }
+
+    /// Opens the table for reading. Returns `None` if nothing was written to the table yet.
+    fn read(
+        &self,
+    ) -> Result<Option<redb::ReadOnlyTable<&'static [u8], &'static [u8]>>, redb::Error> {
+        let txn = self.db.begin_read()?;
+        match txn.open_table(self.definition()) {
+            Ok(table) => Ok(Some(table)),
+            Err(redb::TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn write<T>(
+        &self,
+        f: impl FnOnce(&mut redb::Table<'_, &'static [u8], &'static [u8]>) -> Result<T, redb::Error>,
+    ) -> Result<T, redb::Error> {
+        let mut txn = self.db.begin_write()?;
+        txn.set_durability(redb::Durability::Eventual);
+        let result = {
+            let mut table = txn.open_table(self.definition())?;
+            f(&mut table)?
+        };
+        txn.commit()?;
+        Ok(result)
+    }
+
+    /// Reads a chunk of entries following the `last` key.
+    fn read_chunk(&self, last: Option<&[u8]>) -> Result<Vec<(Vec<u8>, Vec<u8>)>, redb::Error> {
+        use std::ops::Bound;
+
+        let Some(table) = self.read()? else {
+            return Ok(Vec::new());
+        };
+        let start = last.map_or(Bound::Unbounded, Bound::Excluded);
+        let mut chunk = Vec::with_capacity(REDB_ITER_CHUNK_SIZE);
+        for item in table.range::<&[u8]>((start, Bound::Unbounded))?.take(REDB_ITER_CHUNK_SIZE) {
+            let (key, value) = item?;
+            chunk.push((key.value().to_vec(), value.value().to_vec()));
+        }
+        Ok(chunk)
+    }
+}
+
+impl RawBucket for RedbBucket {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError> {
+        let Some(table) = self.read()? else {
+            return Ok(None);
+        };
+        Ok(table.get(key).map_err(redb::Error::from)?.map(|value| value.value().to_vec()))
+    }
+
+    fn set(&self, key: &[u8], value: &[u8]) -> Result<(), BucketError> {
+        self.write(|table| {
+            table.insert(key, value)?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError> {
+        Ok(self.write(|table| Ok(table.remove(key)?.map(|value| value.value().to_vec())))?)
+    }
+
+    fn set_all(&self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), BucketError> {
+        self.write(|table| {
+            for (key, value) in &entries {
+                table.insert(key.as_slice(), value.as_slice())?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Returns zero also if the table could not be read.
+    fn len(&self) -> usize {
+        self.read()
+            .ok()
+            .flatten()
+            .and_then(|table| table.len().ok())
+            .and_then(|len| usize::try_from(len).ok())
+            .unwrap_or(0)
+    }
+
+    fn flush(&self) -> Result<(), BucketError> {
+        // Committing a durable transaction makes also all the previous ones durable.
+        let txn = self.db.begin_write().map_err(redb::Error::from)?;
+        txn.commit().map_err(redb::Error::from)?;
+        Ok(())
+    }
+
+    fn iter(&self) -> RawIter {
+        Box::new(RedbIter {
+            bucket: RedbBucket { db: self.db.clone(), name: self.name.clone() },
+            last: None,
+            chunk: std::collections::VecDeque::new(),
+            finished: false,
+        })
+    }
+}
+
+/// Iterates over a redb table in chunks.
+///
+/// Every chunk is read in a separate transaction, so the bucket can be modified during the
+/// iteration, the same way as with `kv`.
+struct RedbIter {
+    bucket: RedbBucket,
+    last: Option<Vec<u8>>,
+    chunk: std::collections::VecDeque<(Vec<u8>, Vec<u8>)>,
+    finished: bool,
+}
+
+impl Iterator for RedbIter {
+    type Item = Result<(Vec<u8>, Vec<u8>), BucketError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.chunk.is_empty() && !self.finished {
+            match self.bucket.read_chunk(self.last.as_deref()) {
+                Ok(chunk) => {
+                    self.finished = chunk.len() < REDB_ITER_CHUNK_SIZE;
+                    self.chunk = chunk.into();
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+
+        let (key, value) = self.chunk.pop_front()?;
+        self.last = Some(key.clone());
+        Some(Ok((key, value)))
+    }
+}
+
+/// Bucket rejecting all the writes.
+struct ReadOnlyBucket<'a> {
+    inner: Arc<dyn RawBucket + 'a>,
+}
+
+impl RawBucket for ReadOnlyBucket<'_> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BucketError> {
+        self.inner.get(key)
+    }
+
+    fn set(&self, _key: &[u8], _value: &[u8]) -> Result<(), BucketError> {
+        Err(BucketError::ReadOnly)
+    }
+
+    fn remove(&self, _key: &[u8]) -> Result<Option<Vec<u8>>, BucketError> {
+        Err(BucketError::ReadOnly)
+    }
+
+    fn set_all(&self, _entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<(), BucketError> {
+        Err(BucketError::ReadOnly)
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Nothing to flush as nothing could be written.
+    fn flush(&self) -> Result<(), BucketError> {
+        Ok(())
+    }
+
+    fn iter(&self) -> RawIter {
+        self.inner.iter()
+    }
+}
+
+/// Opened storage holding the buckets.
 #[derive(Clone)]
-pub struct Bucket<'a, K, V> {
-    bucket: kv::Bucket<'a, Vec<u8>, Vec<u8>>,
-    phantom: std::marker::PhantomData<(K, V)>,
+pub enum Storage {
+    Kv(kv::Store),
+    Redb(Arc<redb::Database>),
+
+    /// Storage opened only for reading.
+    ReadOnly(Box<Storage>),
 }
 
-impl<'a, K, V> Bucket<'a, K, V> {
-    pub fn obtain(store: &kv::Store, name: &str) -> Result<Self, BucketError> {
-        let bucket = store.bucket::<Vec<u8>, Vec<u8>>(Some(name))?;
-        Ok(Bucket { bucket, phantom: std::marker::PhantomData })
+impl Storage {
+    /// Opens the storage or creates it if it does not exist.
+    pub fn open(path: &Path, engine: StorageEngine) -> Result<Self, BucketError> {
+        match engine {
+            StorageEngine::Kv => Ok(Self::Kv(kv::Store::new(kv::Config::new(path))?)),
+            StorageEngine::Redb => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                let db = redb::Database::create(path).map_err(redb::Error::from)?;
+                Ok(Self::Redb(Arc::new(db)))
+            }
+        }
+    }
+
+    /// Opens an existing storage only for reading.
+    ///
+    /// Unlike `open` it fails if the storage does not exist instead of creating an empty one.
+    pub fn open_read_only(path: &Path, engine: StorageEngine) -> Result<Self, BucketError> {
+        if !path.exists() {
+            return Err(BucketError::NotFound(path.to_owned()));
+        }
+        let storage = match engine {
+            StorageEngine::Kv => Self::Kv(kv::Store::new(kv::Config::new(path))?),
+            StorageEngine::Redb => {
+                Self::Redb(Arc::new(redb::Database::open(path).map_err(redb::Error::from)?))
+            }
+        };
+        Ok(Self::ReadOnly(Box::new(storage)))
+    }
+
+    #[must_use]
+    pub fn engine(&self) -> StorageEngine {
+        match self {
+            Self::Kv(_) => StorageEngine::Kv,
+            Self::Redb(_) => StorageEngine::Redb,
+            Self::ReadOnly(storage) => storage.engine(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Self::ReadOnly(_))
+    }
+
+    fn bucket(&self, name: &str) -> Result<Arc<dyn RawBucket + '_>, BucketError> {
+        Ok(match self {
+            Self::Kv(store) => Arc::new(store.bucket::<Vec<u8>, Vec<u8>>(Some(name))?),
+            Self::Redb(db) => Arc::new(RedbBucket { db: db.clone(), name: name.to_owned() }),
+            Self::ReadOnly(storage) => Arc::new(ReadOnlyBucket { inner: storage.bucket(name)? }),
+        })
+    }
+}
+
+impl std::fmt::Debug for Storage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Storage")
+            .field("engine", &self.engine())
+            .field("read_only", &self.is_read_only())
+            .finish()
+    }
+}
+
+pub struct Bucket<'a, K, V, C = Plain> {
+    bucket: Arc<dyn RawBucket + 'a>,
+    phantom: std::marker::PhantomData<(K, V, C)>,
+}
+
+impl<K, V, C> Clone for Bucket<'_, K, V, C> {
+    fn clone(&self) -> Self {
+        Self { bucket: self.bucket.clone(), phantom: std::marker::PhantomData }
+    }
+}
+
+impl<'a, K, V, C> Bucket<'a, K, V, C> {
+    pub fn obtain(store: &'a Storage, name: &str) -> Result<Self, BucketError> {
+        Ok(Bucket { bucket: store.bucket(name)?, phantom: std::marker::PhantomData })
     }
 
     pub fn flush(&self) -> Result<(), BucketError> {
@@ -49,28 +497,25 @@ impl<'a, K, V> Bucket<'a, K, V> {
     pub fn get(&self, key: &K) -> Result<Option<V>, BucketError>
     where
         K: Serialize,
-        V: DeserializeOwned,
+        C: Codec<V>,
     {
         let key_data = postcard::to_stdvec(key)?;
         let value_data = self.bucket.get(&key_data)?;
-        Ok(if let Some(value_data) = value_data {
-            Some(postcard::from_bytes(&value_data)?)
-        } else {
-            None
-        })
+        Ok(if let Some(value_data) = value_data { Some(C::decode(&value_data)?) } else { None })
     }
 
-    pub fn edit(&self, key: K) -> Result<Option<BucketEntry<'a, K, V>>, BucketError>
+    pub fn edit(&self, key: K) -> Result<Option<BucketEntry<'a, K, V, C>>, BucketError>
     where
         K: Clone + Serialize,
-        V: Clone + Serialize + DeserializeOwned,
+        V: Clone,
+        C: Codec<V>,
     {
         let key_data = postcard::to_stdvec(&key)?;
         let value_data = self.bucket.get(&key_data)?;
         Ok(if let Some(value_data) = value_data {
             Some(BucketEntry {
                 key,
-                value: postcard::from_bytes(&value_data)?,
+                value: C::decode(&value_data)?,
                 key_data,
                 bucket: self.clone(),
             })
@@ -82,85 +527,153 @@ impl<'a, K, V> Bucket<'a, K, V> {
     pub fn remove(&self, key: &K) -> Result<Option<V>, BucketError>
     where
         K: Serialize,
-        V: DeserializeOwned,
+        C: Codec<V>,
     {
         let key_data = postcard::to_stdvec(key)?;
         let value_data = self.bucket.remove(&key_data)?;
-        Ok(if let Some(value_data) = value_data {
-            Some(postcard::from_bytes(&value_data)?)
-        } else {
-            None
-        })
+        Ok(if let Some(value_data) = value_data { Some(C::decode(&value_data)?) } else { None })
     }
 
     pub fn insert(&self, key: &K, value: &V) -> Result<(), BucketError>
     where
         K: Serialize,
-        V: Serialize,
+        C: Codec<V>,
     {
         let key_data = postcard::to_stdvec(key)?;
-        let value_data = postcard::to_stdvec(value)?;
+        let value_data = C::encode(value)?;
         self.bucket.set(&key_data, &value_data)?;
         Ok(())
     }
 
+    /// Reads all the entries without deserializing them.
+    ///
+    /// Useful for warming up the caches of the storage engine. Returns the number of entries.
+    pub fn preload(&self) -> Result<usize, BucketError> {
+        let mut count = 0;
+        for item in self.bucket.iter() {
+            item?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Returns a writer inserting entries in batches of the given size.
+    pub fn batch_writer(&self, batch_size: usize) -> BatchWriter<'a, K, V, C> {
+        BatchWriter { bucket: self.clone(), batch: Vec::new(), batch_size: batch_size.max(1) }
+    }
+
     pub fn gather(&self) -> Result<HashMap<K, V>, BucketError>
     where
         K: DeserializeOwned + Eq + std::hash::Hash,
-        V: DeserializeOwned,
+        C: Codec<V>,
     {
         let mut result = HashMap::new();
         for item in self.bucket.iter() {
-            let item = item?;
-            let key = postcard::from_bytes(&item.key::<Vec<u8>>()?)?;
-            let value = postcard::from_bytes(&item.value::<Vec<u8>>()?)?;
-            result.insert(key, value);
+            let (key_data, value_data) = item?;
+            result.insert(postcard::from_bytes(&key_data)?, C::decode(&value_data)?);
         }
         Ok(result)
     }
 
-    pub fn iter(&self) -> BucketIter<K, V>
+    pub fn iter(&self) -> BucketIter<K, V, C>
     where
         K: Serialize,
-        V: Serialize,
+        C: Codec<V>,
     {
         BucketIter { iter: self.bucket.iter(), phantom: std::marker::PhantomData }
     }
 
-    pub fn iter_autosave(self) -> BucketIterAutosave<'a, K, V>
+    pub fn iter_autosave(self) -> BucketIterAutosave<'a, K, V, C>
     where
         K: Clone + Serialize,
-        V: Clone + Serialize,
+        V: Clone,
+        C: Codec<V>,
     {
         BucketIterAutosave { iter: self.bucket.iter(), bucket: self }
     }
+
+    /// Computes a digest of the encoded entries.
+    ///
+    /// The entries are read in the order of their encoded keys, so the digest depends only on the
+    /// contents of the bucket and not on the order in which the entries were written.
+    pub fn digest(&self) -> Result<store::BucketDigest, BucketError> {
+        let mut context = md5::Context::new();
+        let mut entries = 0;
+        for item in self.bucket.iter() {
+            let (key_data, value_data) = item?;
+            for data in [&key_data, &value_data] {
+                context.consume((data.len() as u64).to_le_bytes());
+                context.consume(data);
+            }
+            entries += 1;
+        }
+        Ok(store::BucketDigest { entries, hash: format!("{:x}", context.compute()) })
+    }
+}
+
+/// Collects inserts and writes them into the bucket in batches.
+///
+/// Writing entries one by one is slow for millions of entries. The entries are written when the
+/// batch fills up and the rest of them together with a single flush in `finish`.
+pub struct BatchWriter<'a, K, V, C = Plain> {
+    bucket: Bucket<'a, K, V, C>,
+    batch: Vec<(Vec<u8>, Vec<u8>)>,
+    batch_size: usize,
+}
+
+impl<K, V, C> BatchWriter<'_, K, V, C> {
+    pub fn insert(&mut self, key: &K, value: &V) -> Result<(), BucketError>
+    where
+        K: Serialize,
+        C: Codec<V>,
+    {
+        let key_data = postcard::to_stdvec(key)?;
+        let value_data = C::encode(value)?;
+        self.batch.push((key_data, value_data));
+        if self.batch.len() >= self.batch_size {
+            self.write()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining entries and flushes the bucket.
+    pub fn finish(mut self) -> Result<(), BucketError> {
+        self.write()?;
+        self.bucket.flush()
+    }
+
+    fn write(&mut self) -> Result<(), BucketError> {
+        if !self.batch.is_empty() {
+            let batch = std::mem::take(&mut self.batch);
+            self.bucket.bucket.set_all(batch)?;
+        }
+        Ok(())
+    }
 }
 
-pub struct BucketIter<K, V> {
-    iter: kv::Iter<Vec<u8>, Vec<u8>>,
-    phantom: std::marker::PhantomData<(K, V)>,
+pub struct BucketIter<K, V, C = Plain> {
+    iter: RawIter,
+    phantom: std::marker::PhantomData<(K, V, C)>,
 }
 
-impl<K, V> BucketIter<K, V>
+impl<K, V, C> BucketIter<K, V, C>
 where
     K: Serialize + DeserializeOwned + Eq + std::hash::Hash,
-    V: Serialize + DeserializeOwned,
+    C: Codec<V>,
 {
     fn go(&mut self) -> Result<Option<(K, V)>, BucketError> {
-        Ok(if let Some(item) = self.iter.next().transpose()? {
-            let key = postcard::from_bytes(&item.key::<Vec<u8>>()?)?;
-            let value = postcard::from_bytes(&item.value::<Vec<u8>>()?)?;
-            Some((key, value))
+        Ok(if let Some((key_data, value_data)) = self.iter.next().transpose()? {
+            Some((postcard::from_bytes(&key_data)?, C::decode(&value_data)?))
         } else {
             None
         })
     }
 }
 
-impl<K, V> Iterator for BucketIter<K, V>
+impl<K, V, C> Iterator for BucketIter<K, V, C>
 where
     K: Serialize + DeserializeOwned + Eq + std::hash::Hash,
-    V: Serialize + DeserializeOwned,
+    C: Codec<V>,
 {
     type Item = Result<(K, V), BucketError>;
 
@@ -169,25 +682,25 @@ where
     }
 }
 
-pub struct BucketIterAutosave<'a, K, V>
+pub struct BucketIterAutosave<'a, K, V, C = Plain>
 where
     K: Clone,
     V: Clone,
 {
-    iter: kv::Iter<Vec<u8>, Vec<u8>>,
-    bucket: Bucket<'a, K, V>,
+    iter: RawIter,
+    bucket: Bucket<'a, K, V, C>,
 }
 
-impl<'a, K, V> BucketIterAutosave<'a, K, V>
+impl<'a, K, V, C> BucketIterAutosave<'a, K, V, C>
 where
     K: Clone + Serialize + DeserializeOwned + Eq + std::hash::Hash,
-    V: Clone + Serialize + DeserializeOwned,
+    V: Clone,
+    C: Codec<V>,
 {
-    fn go(&mut self) -> Result<Option<BucketEntry<'a, K, V>>, BucketError> {
-        Ok(if let Some(item) = self.iter.next().transpose()? {
-            let key_data = item.key::<Vec<u8>>()?;
+    fn go(&mut self) -> Result<Option<BucketEntry<'a, K, V, C>>, BucketError> {
+        Ok(if let Some((key_data, value_data)) = self.iter.next().transpose()? {
             let key = postcard::from_bytes(&key_data)?;
-            let value = postcard::from_bytes(&item.value::<Vec<u8>>()?)?;
+            let value = C::decode(&value_data)?;
             Some(BucketEntry { key, value, key_data, bucket: self.bucket.clone() })
         } else {
             None
@@ -195,36 +708,39 @@ where
     }
 }
 
-impl<'a, K, V> Iterator for BucketIterAutosave<'a, K, V>
+impl<'a, K, V, C> Iterator for BucketIterAutosave<'a, K, V, C>
 where
     K: Clone + Serialize + DeserializeOwned + Eq + std::hash::Hash,
-    V: Clone + Serialize + DeserializeOwned,
+    V: Clone,
+    C: Codec<V>,
 {
-    type Item = Result<BucketEntry<'a, K, V>, BucketError>;
+    type Item = Result<BucketEntry<'a, K, V, C>, BucketError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.go().transpose()
     }
 }
 
-pub struct BucketEntry<'a, K, V>
+pub struct BucketEntry<'a, K, V, C = Plain>
 where
     K: Clone + Serialize,
-    V: Clone + Serialize,
+    V: Clone,
+    C: Codec<V>,
 {
     pub key: K,
     pub value: V,
     key_data: Vec<u8>,
-    bucket: Bucket<'a, K, V>,
+    bucket: Bucket<'a, K, V, C>,
 }
 
-impl<K, V> BucketEntry<'_, K, V>
+impl<K, V, C> BucketEntry<'_, K, V, C>
 where
     K: Clone + Serialize,
-    V: Clone + Serialize,
+    V: Clone,
+    C: Codec<V>,
 {
     pub fn store(&mut self) -> Result<(), BucketError> {
-        let value_data = postcard::to_stdvec(&self.value)?;
+        let value_data = C::encode(&self.value)?;
         self.bucket.bucket.set(&self.key_data, &value_data)?;
         Ok(())
     }
@@ -234,10 +750,11 @@ where
     }
 }
 
-impl<K, V> Drop for BucketEntry<'_, K, V>
+impl<K, V, C> Drop for BucketEntry<'_, K, V, C>
 where
     K: Clone + Serialize,
-    V: Clone + Serialize,
+    V: Clone,
+    C: Codec<V>,
 {
     fn drop(&mut self) {
         self.store().expect("Failed to automatically save a bucket entry");
@@ -246,17 +763,39 @@ where
 
 #[derive(Debug, Clone)]
 pub struct DbStore {
-    store: kv::Store,
+    store: Storage,
 }
 
 impl DbStore {
-    pub fn new(path: &std::path::Path) -> Result<Self, BucketError> {
-        Ok(Self { store: kv::Store::new(kv::Config::new(path))? })
+    /// Opens the database, detecting the engine it was created with.
+    pub fn new(path: &Path) -> Result<Self, BucketError> {
+        Self::with_engine(path, StorageEngine::detect(path))
+    }
+
+    pub fn with_engine(path: &Path, engine: StorageEngine) -> Result<Self, BucketError> {
+        Ok(Self { store: Storage::open(path, engine)? })
+    }
+
+    /// Opens an existing database only for reading, detecting the engine it was created with.
+    pub fn open_read_only(path: &Path) -> Result<Self, BucketError> {
+        Ok(Self { store: Storage::open_read_only(path, StorageEngine::detect(path))? })
+    }
+
+    /// Reads all the entries of the buckets used by most of the requests.
+    ///
+    /// Returns the number of read entries.
+    pub fn preload_hot_buckets(&self) -> Result<usize, BucketError> {
+        Ok(self.get_keyword_to_organisation_ids_bucket()?.preload()?
+            + self.get_keyword_to_product_ids_bucket()?.preload()?
+            + self.get_categories_bucket()?.preload()?
+            + self.get_organisation_bucket()?.preload()?
+            + self.get_product_bucket()?.preload()?)
     }
 
     pub fn get_organisation_bucket(
         &self,
-    ) -> Result<Bucket<'_, store::OrganisationId, store::Organisation>, BucketError> {
+    ) -> Result<Bucket<'_, store::OrganisationId, store::Organisation, Versioned>, BucketError>
+    {
         Bucket::obtain(&self.store, "organisation.id => organisation")
     }
 
@@ -286,13 +825,13 @@ impl DbStore {
 
     pub fn get_categories_bucket(
         &self,
-    ) -> Result<Bucket<'_, String, store::Category>, BucketError> {
+    ) -> Result<Bucket<'_, String, store::Category, Versioned>, BucketError> {
         Bucket::obtain(&self.store, "product.category => [product.id]")
     }
 
     pub fn get_product_bucket(
         &self,
-    ) -> Result<Bucket<'_, store::ProductId, store::Product>, BucketError> {
+    ) -> Result<Bucket<'_, store::ProductId, store::Product, Versioned>, BucketError> {
         Bucket::obtain(&self.store, "product.id => product")
     }
 
@@ -319,43 +858,109 @@ impl DbStore {
     ) -> Result<Bucket<'_, store::WikiId, store::ProductId>, BucketError> {
         Bucket::obtain(&self.store, "product.wiki_id => product.id")
     }
+
+    pub fn get_substrate_meta_bucket(
+        &self,
+    ) -> Result<Bucket<'_, String, store::SubstrateMeta, Versioned>, BucketError> {
+        Bucket::obtain(&self.store, "dataset.substrate => dataset.meta")
+    }
+
+    /// Digests of the contents of the other buckets, keyed by the bucket names.
+    pub fn get_bucket_digest_bucket(
+        &self,
+    ) -> Result<Bucket<'_, String, store::BucketDigest, Versioned>, BucketError> {
+        Bucket::obtain(&self.store, "dataset.bucket => dataset.digest")
+    }
+
+    /// Computes the digests of all the buckets except the one holding the digests.
+    pub fn compute_digests(&self) -> Result<BTreeMap<String, store::BucketDigest>, BucketError> {
+        let mut digests = BTreeMap::new();
+        for name in DIGESTED_BUCKETS {
+            let bucket = Bucket::<(), ()>::obtain(&self.store, name)?;
+            digests.insert((*name).to_owned(), bucket.digest()?);
+        }
+        Ok(digests)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct AppStore {
-    store: kv::Store,
+    store: Storage,
 }
 
 impl AppStore {
-    pub fn new(path: &std::path::Path) -> Result<Self, BucketError> {
-        Ok(Self { store: kv::Store::new(kv::Config::new(path))? })
+    /// Opens the database, detecting the engine it was created with.
+    pub fn new(path: &Path) -> Result<Self, BucketError> {
+        Self::with_engine(path, StorageEngine::detect(path))
+    }
+
+    pub fn with_engine(path: &Path, engine: StorageEngine) -> Result<Self, BucketError> {
+        Ok(Self { store: Storage::open(path, engine)? })
+    }
+
+    /// Opens an existing database only for reading, detecting the engine it was created with.
+    pub fn open_read_only(path: &Path) -> Result<Self, BucketError> {
+        Ok(Self { store: Storage::open_read_only(path, StorageEngine::detect(path))? })
+    }
+
+    /// Reads all the entries of the buckets used by most of the requests.
+    ///
+    /// Returns the number of read entries.
+    pub fn preload_hot_buckets(&self) -> Result<usize, BucketError> {
+        Ok(self.get_library_bucket()?.preload()? + self.get_presentation_bucket()?.preload()?)
+    }
+
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.store.is_read_only()
     }
 
     pub fn get_library_bucket(
         &self,
-    ) -> Result<Bucket<'_, store::LibraryTopic, store::LibraryItem>, BucketError> {
+    ) -> Result<Bucket<'_, store::LibraryTopic, store::LibraryItem, Versioned>, BucketError> {
         Bucket::obtain(&self.store, "library.topic => library.item")
     }
 
     pub fn get_presentation_bucket(
         &self,
-    ) -> Result<Bucket<'_, store::LibraryTopic, store::Presentation>, BucketError> {
+    ) -> Result<Bucket<'_, store::LibraryTopic, store::Presentation, Versioned>, BucketError> {
         Bucket::obtain(&self.store, "library.topic => library.presentation")
     }
+
+    pub fn get_gtin_to_retailer_availability_bucket(
+        &self,
+    ) -> Result<Bucket<'_, store::Gtin, Vec<store::RetailerAvailability>, Versioned>, BucketError>
+    {
+        Bucket::obtain(&self.store, "product.gtin => [product.retailer_availability]")
+    }
+
+    pub fn get_missed_lookup_bucket(
+        &self,
+    ) -> Result<Bucket<'_, store::Gtin, store::MissedLookup, Versioned>, BucketError> {
+        Bucket::obtain(&self.store, "feedback.gtin => feedback.missed_lookup")
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Bucket;
+    use super::{Bucket, BucketError, Storage, StorageEngine};
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug)]
     pub struct TestStore {
-        store: kv::Store,
+        store: Storage,
+        dir: tempfile::TempDir,
     }
 
     impl TestStore {
-        pub fn new() -> Self {
-            Self { store: kv::Store::new(kv::Config::new(tempfile::tempdir().unwrap())).unwrap() }
+        pub fn new(engine: StorageEngine) -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let store = Storage::open(&dir.path().join("db"), engine).unwrap();
+            Self { store, dir }
+        }
+
+        /// Creates a store for every supported engine.
+        pub fn all() -> Vec<Self> {
+            vec![Self::new(StorageEngine::Kv), Self::new(StorageEngine::Redb)]
         }
 
         pub fn get_test_bucket<'a>(&'a self) -> Bucket<'a, u32, String> {
@@ -363,113 +968,200 @@ mod tests {
         }
     }
 
+    /// Check if a read-only storage serves the existing entries and rejects writes.
+    #[test]
+    fn storage_read_only() {
+        for engine in [StorageEngine::Kv, StorageEngine::Redb] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("db");
+            {
+                let store = Storage::open(&path, engine).unwrap();
+                let bucket = Bucket::<u32, String>::obtain(&store, "test").unwrap();
+                bucket.insert(&1, &String::from("1")).unwrap();
+                bucket.insert(&2, &String::from("2")).unwrap();
+                bucket.flush().unwrap();
+            }
+
+            let store = Storage::open_read_only(&path, engine).unwrap();
+            assert!(store.is_read_only());
+            assert_eq!(store.engine(), engine);
+
+            let bucket = Bucket::<u32, String>::obtain(&store, "test").unwrap();
+            assert_eq!(bucket.preload().unwrap(), 2);
+            assert_eq!(bucket.get(&2).unwrap(), Some(String::from("2")));
+            assert!(matches!(bucket.insert(&3, &String::from("3")), Err(BucketError::ReadOnly)));
+            assert!(matches!(bucket.remove(&1), Err(BucketError::ReadOnly)));
+
+            let missing = dir.path().join("missing");
+            assert!(matches!(
+                Storage::open_read_only(&missing, engine),
+                Err(BucketError::NotFound(_))
+            ));
+        }
+    }
+
+    /// Check if the engine of an existing storage is detected.
+    #[test]
+    fn storage_engine_detect() {
+        for store in TestStore::all() {
+            let path = store.dir.path().join("db");
+            assert_eq!(StorageEngine::detect(&path), store.store.engine());
+        }
+    }
+
     /// Check if bucket clones edit the same entries.
     ///
-    /// This should be possible per guaranties of the `kv` crate.
+    /// This should be possible per guaranties of the `kv` crate and `RedbBucket` mirrors it.
     #[test]
     fn bucket_clone() {
-        let store = TestStore::new();
-
-        let bucket1 = store.get_test_bucket();
-        let bucket2 = bucket1.clone();
-        let bucket3 = store.get_test_bucket();
-
-        bucket1.insert(&3, &String::from("3")).unwrap();
-        bucket1.insert(&4, &String::from("4")).unwrap();
-        bucket1.insert(&5, &String::from("5")).unwrap();
-        bucket1.insert(&6, &String::from("6")).unwrap();
-
-        {
-            let mut iter = bucket2.iter();
-            assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("4"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("5"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
-            assert_eq!(iter.next().transpose().unwrap(), None);
-            assert_eq!(iter.next().transpose().unwrap(), None);
+        for store in TestStore::all() {
+            let bucket1 = store.get_test_bucket();
+            let bucket2 = bucket1.clone();
+            let bucket3 = store.get_test_bucket();
+
+            bucket1.insert(&3, &String::from("3")).unwrap();
+            bucket1.insert(&4, &String::from("4")).unwrap();
+            bucket1.insert(&5, &String::from("5")).unwrap();
+            bucket1.insert(&6, &String::from("6")).unwrap();
+
+            {
+                let mut iter = bucket2.iter();
+                assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("4"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("5"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
+                assert_eq!(iter.next().transpose().unwrap(), None);
+                assert_eq!(iter.next().transpose().unwrap(), None);
+            }
+
+            {
+                let mut iter = bucket3.iter();
+                assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("4"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("5"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
+                assert_eq!(iter.next().transpose().unwrap(), None);
+                assert_eq!(iter.next().transpose().unwrap(), None);
+            }
         }
+    }
 
-        {
-            let mut iter = bucket3.iter();
-            assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("4"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("5"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
-            assert_eq!(iter.next().transpose().unwrap(), None);
-            assert_eq!(iter.next().transpose().unwrap(), None);
+    /// Check if all entries are written by the batch writer, also the ones not filling a whole batch.
+    #[test]
+    fn bucket_batch_writer() {
+        for store in TestStore::all() {
+            let bucket = store.get_test_bucket();
+
+            let mut writer = bucket.batch_writer(3);
+            for i in 0..7 {
+                writer.insert(&i, &i.to_string()).unwrap();
+            }
+            assert_eq!(bucket.len(), 6);
+
+            writer.finish().unwrap();
+            assert_eq!(bucket.len(), 7);
+            for i in 0..7 {
+                assert_eq!(bucket.get(&i).unwrap(), Some(i.to_string()));
+            }
         }
     }
 
     /// Check if iteration works properly and edited entries are available during next iteration.
     #[test]
     fn bucket_iter_and_edit() {
-        let store = TestStore::new();
-        let bucket = store.get_test_bucket();
-
-        bucket.insert(&3, &String::from("3")).unwrap();
-        bucket.insert(&4, &String::from("4")).unwrap();
-        bucket.insert(&5, &String::from("5")).unwrap();
-        bucket.insert(&6, &String::from("6")).unwrap();
-
-        {
-            let mut iter = bucket.iter();
-            assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("4"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("5"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
-            assert_eq!(iter.next().transpose().unwrap(), None);
-            assert_eq!(iter.next().transpose().unwrap(), None);
-        }
+        for store in TestStore::all() {
+            let bucket = store.get_test_bucket();
+
+            bucket.insert(&3, &String::from("3")).unwrap();
+            bucket.insert(&4, &String::from("4")).unwrap();
+            bucket.insert(&5, &String::from("5")).unwrap();
+            bucket.insert(&6, &String::from("6")).unwrap();
+
+            {
+                let mut iter = bucket.iter();
+                assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("4"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("5"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
+                assert_eq!(iter.next().transpose().unwrap(), None);
+                assert_eq!(iter.next().transpose().unwrap(), None);
+            }
 
-        {
-            let mut editor = bucket.edit(4).unwrap().unwrap();
-            editor.value = String::from("44");
-        }
+            {
+                let mut editor = bucket.edit(4).unwrap().unwrap();
+                editor.value = String::from("44");
+            }
 
-        {
-            let mut editor = bucket.edit(5).unwrap().unwrap();
-            // Editing the key should have no impact on where the value is saved.
-            editor.key = 9;
-            editor.value = String::from("55");
-        }
+            {
+                let mut editor = bucket.edit(5).unwrap().unwrap();
+                // Editing the key should have no impact on where the value is saved.
+                editor.key = 9;
+                editor.value = String::from("55");
+            }
 
-        {
-            let mut iter = bucket.iter();
-            assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("44"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("55"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
-            assert_eq!(iter.next().transpose().unwrap(), None);
-            assert_eq!(iter.next().transpose().unwrap(), None);
+            {
+                let mut iter = bucket.iter();
+                assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("3"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("44"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("55"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("6"))));
+                assert_eq!(iter.next().transpose().unwrap(), None);
+                assert_eq!(iter.next().transpose().unwrap(), None);
+            }
         }
     }
 
     /// Check if autosave iteration works properly.
     #[test]
     fn bucket_iter_autosave() {
-        let store = TestStore::new();
-        let bucket = store.get_test_bucket();
-
-        bucket.insert(&3, &String::from("3")).unwrap();
-        bucket.insert(&4, &String::from("4")).unwrap();
-        bucket.insert(&5, &String::from("5")).unwrap();
-        bucket.insert(&6, &String::from("6")).unwrap();
-
-        {
-            for item in bucket.clone().iter_autosave() {
-                let mut item = item.unwrap();
-                item.value = (11 * item.key).to_string();
+        for store in TestStore::all() {
+            let bucket = store.get_test_bucket();
+
+            bucket.insert(&3, &String::from("3")).unwrap();
+            bucket.insert(&4, &String::from("4")).unwrap();
+            bucket.insert(&5, &String::from("5")).unwrap();
+            bucket.insert(&6, &String::from("6")).unwrap();
+
+            {
+                for item in bucket.clone().iter_autosave() {
+                    let mut item = item.unwrap();
+                    item.value = (11 * item.key).to_string();
+                }
+            }
+
+            {
+                let mut iter = bucket.iter();
+                assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("33"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("44"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("55"))));
+                assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("66"))));
+                assert_eq!(iter.next().transpose().unwrap(), None);
+                assert_eq!(iter.next().transpose().unwrap(), None);
             }
         }
+    }
 
-        {
-            let mut iter = bucket.iter();
-            assert_eq!(iter.next().transpose().unwrap(), Some((3, String::from("33"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((4, String::from("44"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((5, String::from("55"))));
-            assert_eq!(iter.next().transpose().unwrap(), Some((6, String::from("66"))));
-            assert_eq!(iter.next().transpose().unwrap(), None);
-            assert_eq!(iter.next().transpose().unwrap(), None);
+    /// Check if the digest depends only on the contents, not on the order of the writes.
+    #[test]
+    fn bucket_digest() {
+        for store in TestStore::all() {
+            let b1 = Bucket::<String, u32>::obtain(&store.store, "digest1").unwrap();
+            let b2 = Bucket::<String, u32>::obtain(&store.store, "digest2").unwrap();
+            let empty = Bucket::<String, u32>::obtain(&store.store, "digest3").unwrap();
+
+            b1.insert(&"a".to_owned(), &1).unwrap();
+            b1.insert(&"b".to_owned(), &2).unwrap();
+            b2.insert(&"b".to_owned(), &2).unwrap();
+            b2.insert(&"a".to_owned(), &1).unwrap();
+
+            let digest = b1.digest().unwrap();
+            assert_eq!(digest.entries, 2);
+            assert_eq!(digest, b2.digest().unwrap());
+            assert_eq!(empty.digest().unwrap().entries, 0);
+            assert_ne!(empty.digest().unwrap().hash, digest.hash);
+
+            b2.insert(&"b".to_owned(), &3).unwrap();
+            assert_ne!(digest, b2.digest().unwrap());
         }
     }
 }
@@ -4,11 +4,32 @@
 
 use std::collections::{HashMap, hash_map::Entry};
 
+/// Combines two objects into a new one.
+///
+/// Implementations for structs construct the result from scratch and destructure both inputs
+/// without `..`, so that a newly added field fails to compile until it is handled in `combine`
+/// instead of being silently taken from only one of the inputs. Structs whose fields are all
+/// combined with their own `Combine` implementations can use `combine_fields!`.
 pub trait Combine {
     /// Combines two objects of the same type.
     fn combine(o1: Self, o2: Self) -> Self;
 }
 
+/// Implements `Combine` for a struct by combining each of its fields.
+///
+/// All the fields have to be listed, otherwise the implementation fails to compile.
+macro_rules! combine_fields {
+    ($type:ty { $($field:ident),+ $(,)? }) => {
+        impl $crate::combine::Combine for $type {
+            fn combine(o1: Self, o2: Self) -> Self {
+                Self { $($field: $crate::combine::Combine::combine(o1.$field, o2.$field)),+ }
+            }
+        }
+    };
+}
+
+pub(crate) use combine_fields;
+
 pub trait TryCombine: Sized {
     type Error: Sized;
 
@@ -5,12 +5,13 @@
 pub use crate::{
     ids::{Asin, Ean, Gtin, OrganisationId, ParseIdError, ProductId, VatId, WikiId},
     models::{
-        Availability, BCorpCert, Certifications, Domain, EuEcolabelCert, FtiCert,
-        GatherOrganisation as Organisation, GatherOrganisationIds as OrganisationIds,
+        Availability, BCorpCert, Badge, Certifications, Domain, EuEcolabelCert, FieldTimestamps,
+        FtiCert, GatherOrganisation as Organisation, GatherOrganisationIds as OrganisationIds,
         GatherProduct as Product, GatherProductIds as ProductIds, Image, LibraryItem, LibraryTopic,
-        Medium, Mention, MultiMap, Presentation, PresentationData, Regions,
-        ScoredPresentationEntry, ShoppingData, ShoppingEntry, ShoppingKey, Source, TcoCert, Text,
-        TranspaerOrganisationData, TranspaerProductData, TranspaerScore, TranspaerScoreBranch,
-        TranspaerScoreCategory,
+        MatchMethod, Medium, Mention, MultiMap, NutriScore, OrganisationMatch, Presentation,
+        PresentationData, PriceLevels, PriceTier, ProductQuantity, QuantityUnit, Regions,
+        ReportLink, ScoreScale, ScoredPresentationEntry, SearchRank, ShoppingData, ShoppingEntry,
+        ShoppingKey, Source, TcoCert, Text, TranspaerOrganisationData, TranspaerProductData,
+        TranspaerScore, TranspaerScoreBranch, TranspaerScoreCategory, VerifiedShop,
     },
 };
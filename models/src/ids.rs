@@ -29,6 +29,14 @@ pub enum ParseIdError {
     /// The ID didn't contain the expected prefix.
     #[snafu(display("The ID `{string}` has unexpected prefix"))]
     Prefix { string: String },
+
+    /// The ID doesn't match any of the formats allowed for it.
+    #[snafu(display("The ID `{string}` has wrong format"))]
+    Format { string: String },
+
+    /// The check digits of the ID don't match the rest of the ID.
+    #[snafu(display("The ID `{string}` has wrong checksum"))]
+    Checksum { string: String },
 }
 
 impl ParseIdError {
@@ -43,6 +51,14 @@ impl ParseIdError {
     pub fn prefix(string: String) -> Self {
         Self::Prefix { string }
     }
+
+    pub fn format(string: String) -> Self {
+        Self::Format { string }
+    }
+
+    pub fn checksum(string: String) -> Self {
+        Self::Checksum { string }
+    }
 }
 
 impl From<transpaer_wikidata::errors::ParseIdError> for ParseIdError {
@@ -142,6 +158,14 @@ impl Ean {
     pub fn to_canonical_string(&self) -> String {
         self.0.to_string()
     }
+
+    /// Converts to the equivalent GTIN-14 (EAN-13 zero-padded from the left).
+    ///
+    /// This is the canonical form for comparing EANs with GTINs.
+    #[must_use]
+    pub const fn to_gtin(&self) -> Gtin {
+        Gtin::new(self.0)
+    }
 }
 
 impl std::fmt::Display for Ean {
@@ -218,6 +242,14 @@ impl Gtin {
         format!("{:0>14}", self.0)
     }
 
+    /// Converts to the equivalent EAN-13.
+    ///
+    /// Returns `None` if the GTIN has a non-zero indicator digit and so it has no EAN-13 equivalent.
+    #[must_use]
+    pub fn to_ean(&self) -> Option<Ean> {
+        if self.0 > MAX_EAN { None } else { Some(Ean::new(self.0)) }
+    }
+
     /// Converts optional vector of strings to a vector of VAT IDs.
     ///
     /// # Errors
@@ -347,6 +379,162 @@ impl<'de> Deserialize<'de> for Asin {
     }
 }
 
+/// Checksum algorithm used by VAT numbers of a country.
+#[derive(Debug, Clone, Copy)]
+enum VatChecksum {
+    /// No checksum or checksum not verified.
+    None,
+
+    /// Dutch numbers: "11-proef" for the older numbers and ISO 7064 MOD 97-10 for the newer ones.
+    Netherlands,
+
+    /// German numbers: ISO 7064 MOD 11-10.
+    Germany,
+
+    /// French numbers: the two-character key is derived from the SIREN.
+    France,
+}
+
+/// Format of VAT numbers of a single country.
+struct VatFormat {
+    /// Prefix of the VAT numbers, usually the ISO 3166 code of the country.
+    prefix: &'static str,
+
+    /// Allowed patterns of the part following the prefix.
+    ///
+    /// In the patterns `9` stands for a digit, `A` for a letter and `X` for a digit or a letter.
+    /// Any other character stands for itself.
+    patterns: &'static [&'static str],
+
+    /// Checksum of the numbers.
+    checksum: VatChecksum,
+}
+
+/// Formats of the VAT numbers in the EU and the neighbouring countries.
+const VAT_FORMATS: &[VatFormat] = &[
+    VatFormat { prefix: "AT", patterns: &["U99999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "BE", patterns: &["9999999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "BG", patterns: &["999999999", "9999999999"], checksum: VatChecksum::None },
+    VatFormat {
+        prefix: "CHE",
+        patterns: &["999999999", "999999999MWST", "999999999TVA", "999999999IVA"],
+        checksum: VatChecksum::None,
+    },
+    VatFormat { prefix: "CY", patterns: &["99999999A"], checksum: VatChecksum::None },
+    VatFormat {
+        prefix: "CZ",
+        patterns: &["99999999", "999999999", "9999999999"],
+        checksum: VatChecksum::None,
+    },
+    VatFormat { prefix: "DE", patterns: &["999999999"], checksum: VatChecksum::Germany },
+    VatFormat { prefix: "DK", patterns: &["99999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "EE", patterns: &["999999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "EL", patterns: &["999999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "ES", patterns: &["X9999999X"], checksum: VatChecksum::None },
+    VatFormat { prefix: "FI", patterns: &["99999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "FR", patterns: &["XX999999999"], checksum: VatChecksum::France },
+    VatFormat {
+        prefix: "GB",
+        patterns: &["999999999", "999999999999", "GD999", "HA999"],
+        checksum: VatChecksum::None,
+    },
+    VatFormat { prefix: "HR", patterns: &["99999999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "HU", patterns: &["99999999"], checksum: VatChecksum::None },
+    VatFormat {
+        prefix: "IE",
+        patterns: &["9999999A", "9X99999A", "9999999AA"],
+        checksum: VatChecksum::None,
+    },
+    VatFormat { prefix: "IT", patterns: &["99999999999"], checksum: VatChecksum::None },
+    VatFormat {
+        prefix: "LT",
+        patterns: &["999999999", "999999999999"],
+        checksum: VatChecksum::None,
+    },
+    VatFormat { prefix: "LU", patterns: &["99999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "LV", patterns: &["99999999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "MT", patterns: &["99999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "NL", patterns: &["999999999B99"], checksum: VatChecksum::Netherlands },
+    VatFormat { prefix: "NO", patterns: &["999999999MVA"], checksum: VatChecksum::None },
+    VatFormat { prefix: "PL", patterns: &["9999999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "PT", patterns: &["999999999"], checksum: VatChecksum::None },
+    VatFormat {
+        prefix: "RO",
+        patterns: &["99", "999", "9999", "99999", "999999", "9999999", "99999999", "999999999"],
+        checksum: VatChecksum::None,
+    },
+    VatFormat { prefix: "SE", patterns: &["999999999901"], checksum: VatChecksum::None },
+    VatFormat { prefix: "SI", patterns: &["99999999"], checksum: VatChecksum::None },
+    VatFormat { prefix: "SK", patterns: &["9999999999"], checksum: VatChecksum::None },
+];
+
+impl VatFormat {
+    /// Finds the format of the VAT number by its prefix.
+    fn find(vat: &str) -> Option<&'static Self> {
+        VAT_FORMATS.iter().find(|format| vat.starts_with(format.prefix))
+    }
+
+    /// Checks if the part following the prefix matches any of the allowed patterns.
+    fn matches(&self, number: &str) -> bool {
+        self.patterns.iter().any(|pattern| {
+            pattern.len() == number.len()
+                && pattern.bytes().zip(number.bytes()).all(|(p, c)| match p {
+                    b'9' => c.is_ascii_digit(),
+                    b'A' => c.is_ascii_alphabetic(),
+                    b'X' => c.is_ascii_alphanumeric(),
+                    _ => p == c,
+                })
+        })
+    }
+
+    /// Verifies the checksum of the part following the prefix.
+    ///
+    /// Expects the number to match one of the patterns.
+    fn verify(&self, number: &str) -> bool {
+        let digits = |s: &str| s.bytes().map(|c| u32::from(c - b'0')).collect::<Vec<_>>();
+        match self.checksum {
+            VatChecksum::None => true,
+            VatChecksum::Netherlands => {
+                let d = digits(&number[..9]);
+                let weighted: u32 = d[..8].iter().zip((2..=9).rev()).map(|(d, w)| d * w).sum();
+                let eleven_test = (weighted + 11 * 9 - d[8]) % 11 == 0;
+                eleven_test || mod97(&format!("{}{number}", self.prefix)) == 1
+            }
+            VatChecksum::Germany => {
+                let d = digits(number);
+                let mut product = 10;
+                for digit in &d[..8] {
+                    let sum = match (digit + product) % 10 {
+                        0 => 10,
+                        sum => sum,
+                    };
+                    product = (2 * sum) % 11;
+                }
+                (11 - product) % 10 == d[8]
+            }
+            VatChecksum::France => match number[..2].parse::<u64>() {
+                Ok(key) => {
+                    let siren = number[2..].parse::<u64>().unwrap_or_default();
+                    key == (12 + 3 * (siren % 97)) % 97
+                }
+                // Keys of the newer numbers contain letters and are not verified.
+                Err(_) => true,
+            },
+        }
+    }
+}
+
+/// Computes the ISO 7064 MOD 97-10 remainder of an alphanumeric string.
+///
+/// Letters are converted to numbers from 10 (`A`) to 35 (`Z`).
+fn mod97(string: &str) -> u32 {
+    string.chars().fold(0, |rest, c| match c.to_digit(36) {
+        Some(value) if value >= 10 => (rest * 100 + value) % 97,
+        Some(value) => (rest * 10 + value) % 97,
+        None => rest,
+    })
+}
+
 /// Represents a VAT number.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct VatId(String);
@@ -355,7 +543,7 @@ impl VatId {
     /// Constructs a new `VatId`.
     #[must_use]
     pub fn new(id: &str) -> Self {
-        let mut reduced = id.to_owned();
+        let mut reduced = id.to_ascii_uppercase();
         reduced.retain(|c| c.is_ascii_alphanumeric());
         Self(reduced)
     }
@@ -401,6 +589,24 @@ impl TryFrom<&str> for VatId {
             return Err(ParseIdError::length(vat.0));
         }
 
+        match VatFormat::find(&vat.0) {
+            Some(format) => {
+                let number = &vat.0[format.prefix.len()..];
+                if !format.matches(number) {
+                    return Err(ParseIdError::format(vat.0));
+                }
+                if !format.verify(number) {
+                    return Err(ParseIdError::checksum(vat.0));
+                }
+            }
+            None => {
+                // Numbers of other countries are accepted as long as they look like numbers.
+                if !vat.0.bytes().any(|c| c.is_ascii_digit()) {
+                    return Err(ParseIdError::format(vat.0));
+                }
+            }
+        }
+
         Ok(vat)
     }
 }
@@ -10,3 +10,4 @@ pub mod models;
 pub mod store;
 pub mod transpaer;
 pub mod utils;
+pub mod versions;
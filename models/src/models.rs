@@ -12,7 +12,10 @@ use std::{
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::combine::Combine;
+use crate::combine::{Combine, combine_fields};
+
+#[cfg(feature = "into-api")]
+use thiserror::Error;
 
 #[cfg(feature = "into-api")]
 use transpaer_api::models as api;
@@ -20,10 +23,116 @@ use transpaer_api::models as api;
 #[cfg(feature = "from-substrate")]
 use transpaer_schema as schema;
 
-use crate::ids;
+use crate::{ids, utils};
 
 pub type LibraryTopic = String;
 
+/// Errors returned by strict conversions into the API models.
+#[cfg(feature = "into-api")]
+#[derive(Error, Debug)]
+pub enum ApiConversionError {
+    #[error("Could not convert `{value}` into {target}: {message}")]
+    Value { value: String, target: &'static str, message: String },
+
+    #[error("Score category {0:?} cannot be presented")]
+    ScoreCategory(TranspaerScoreCategory),
+}
+
+/// Decides what to do with data that cannot be converted into the API models.
+#[cfg(feature = "into-api")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionMode {
+    /// The error is logged and the value replaced with a default.
+    Lenient,
+
+    /// The error is returned.
+    Strict,
+}
+
+#[cfg(feature = "into-api")]
+impl ConversionMode {
+    /// Converts the string into an API type.
+    fn convert<T>(self, value: &str, default: impl FnOnce() -> T) -> Result<T, ApiConversionError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        parse(value).or_else(|err| self.recover(err, default))
+    }
+
+    /// Converts the text into an API string type.
+    ///
+    /// Unlike `convert`, in the lenient mode texts exceeding the API limits are truncated to fit
+    /// instead of being replaced with the default.
+    fn convert_text<T>(
+        self,
+        value: &str,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, ApiConversionError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        parse(value).or_else(|err| {
+            if self == Self::Lenient
+                && let Some(truncated) = utils::truncate_graphemes(value, |s| T::from_str(s).ok())
+            {
+                NUM_TRUNCATED_TEXTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Ok(truncated);
+            }
+            self.recover(err, default)
+        })
+    }
+
+    /// Handles the conversion error according to the mode.
+    fn recover<T>(
+        self,
+        err: ApiConversionError,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, ApiConversionError> {
+        match self {
+            Self::Lenient => {
+                log::error!("{err}");
+                Ok(default())
+            }
+            Self::Strict => Err(err),
+        }
+    }
+}
+
+/// Number of texts truncated to fit the API limits since the start of the process.
+#[cfg(feature = "into-api")]
+static NUM_TRUNCATED_TEXTS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Returns the number of texts truncated to fit the API limits since the start of the process.
+#[cfg(feature = "into-api")]
+#[must_use]
+pub fn num_truncated_texts() -> u64 {
+    NUM_TRUNCATED_TEXTS.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Parses the string into an API type.
+#[cfg(feature = "into-api")]
+fn parse<T>(value: &str) -> Result<T, ApiConversionError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    T::from_str(value).map_err(|err| ApiConversionError::Value {
+        value: value.to_owned(),
+        target: std::any::type_name::<T>(),
+        message: err.to_string(),
+    })
+}
+
+/// Unwraps the result of a lenient conversion.
+///
+/// Lenient conversions fail only if the data is broken beyond recovery.
+#[cfg(feature = "into-api")]
+fn lenient<T>(result: Result<T, ApiConversionError>) -> T {
+    result.expect("Lenient conversion")
+}
+
 // TODO: Validate the domain when deserializing.
 pub type Domain = String;
 
@@ -61,6 +170,18 @@ pub enum Source {
     /// The "Simple Environmentalist" youtube channel.
     SimpleEnvironmentalist,
 
+    /// Nutri-Score grades extracted from Open Food Facts.
+    NutriScore,
+
+    /// Open Supply Hub facilities.
+    OpenSupplyHub,
+
+    /// Regional price levels of products.
+    PriceLevel,
+
+    /// Podcast episodes discussing products and producers.
+    Podcasts,
+
     Other,
 }
 
@@ -76,6 +197,10 @@ impl Source {
             "tco" => Source::Tco,
             "wikidata" => Source::Wikidata,
             "simple_environmentalist" => Source::SimpleEnvironmentalist,
+            "nutri_score" => Source::NutriScore,
+            "open_supply_hub" => Source::OpenSupplyHub,
+            "price_level" => Source::PriceLevel,
+            "podcasts" => Source::Podcasts,
             _ => {
                 log::warn!("Source `{string}` is not covered");
                 Source::Other
@@ -99,6 +224,22 @@ impl Source {
         matches!(self, Self::Tco)
     }
 
+    pub fn is_nutri_score(&self) -> bool {
+        matches!(self, Self::NutriScore)
+    }
+
+    pub fn is_open_supply_hub(&self) -> bool {
+        matches!(self, Self::OpenSupplyHub)
+    }
+
+    pub fn is_price_level(&self) -> bool {
+        matches!(self, Self::PriceLevel)
+    }
+
+    pub fn is_podcasts(&self) -> bool {
+        matches!(self, Self::Podcasts)
+    }
+
     #[cfg(feature = "into-api")]
     pub fn get_icon_link(&self) -> Option<String> {
         match self {
@@ -108,7 +249,6 @@ impl Source {
     }
 }
 
-#[cfg(feature = "into-api")]
 impl Source {
     pub fn to_label(&self) -> String {
         match self {
@@ -121,11 +261,18 @@ impl Source {
             Self::Tco => "tco",
             Self::Wikidata => "wikidata",
             Self::SimpleEnvironmentalist => "simple_environmentalist",
+            Self::NutriScore => "nutri_score",
+            Self::OpenSupplyHub => "open_supply_hub",
+            Self::PriceLevel => "price_level",
+            Self::Podcasts => "podcasts",
             Self::Other => "other",
         }
         .to_owned()
     }
+}
 
+#[cfg(feature = "into-api")]
+impl Source {
     pub fn into_api(&self) -> api::DataSource {
         api::DataSource(self.to_label())
     }
@@ -141,6 +288,80 @@ pub struct Text {
     pub sources: Vec<Source>,
 }
 
+/// Descriptions shorter than this (in characters) are considered low quality.
+pub const MIN_DESCRIPTION_LENGTH: usize = 20;
+
+/// Descriptions with word similarity above this value are considered duplicates.
+pub const DESCRIPTION_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+impl Text {
+    /// Selects at most one description per detected language.
+    ///
+    /// Too short descriptions are dropped unless there is nothing else. The best description in
+    /// each language is the one confirmed by the most sources, then the longest one. Sources of
+    /// its near-duplicates are merged into it while other descriptions are dropped.
+    ///
+    /// Descriptions of undetected language are all kept after the selected ones, only their
+    /// near-duplicates are merged.
+    #[must_use]
+    pub fn select_descriptions(texts: Vec<Text>) -> Vec<Text> {
+        let (mut candidates, short): (Vec<_>, Vec<_>) = texts
+            .into_iter()
+            .partition(|t| t.text.trim().chars().count() >= MIN_DESCRIPTION_LENGTH);
+        if candidates.is_empty() {
+            candidates = short;
+        }
+
+        candidates.sort_by(|a, b| {
+            b.sources
+                .len()
+                .cmp(&a.sources.len())
+                .then_with(|| b.text.chars().count().cmp(&a.text.chars().count()))
+                .then_with(|| a.text.cmp(&b.text))
+        });
+
+        let mut selected = BTreeMap::<&'static str, Text>::new();
+        let mut undetected = Vec::<Text>::new();
+        for candidate in candidates {
+            match utils::detect_language(&candidate.text) {
+                Some(language) => match selected.entry(language) {
+                    std::collections::btree_map::Entry::Vacant(entry) => {
+                        entry.insert(candidate);
+                    }
+                    std::collections::btree_map::Entry::Occupied(mut entry) => {
+                        let best = entry.get_mut();
+                        if best.is_near_duplicate(&candidate) {
+                            best.merge_sources(candidate.sources);
+                        }
+                    }
+                },
+                None => {
+                    if let Some(kept) =
+                        undetected.iter_mut().find(|t| t.is_near_duplicate(&candidate))
+                    {
+                        kept.merge_sources(candidate.sources);
+                    } else {
+                        undetected.push(candidate);
+                    }
+                }
+            }
+        }
+        selected.into_values().chain(undetected).collect()
+    }
+
+    /// Checks if the texts are similar enough to be considered duplicates.
+    fn is_near_duplicate(&self, other: &Text) -> bool {
+        utils::word_similarity(&self.text, &other.text) >= DESCRIPTION_SIMILARITY_THRESHOLD
+    }
+
+    /// Adds the sources of a duplicate text.
+    fn merge_sources(&mut self, sources: Vec<Source>) {
+        self.sources.extend(sources);
+        self.sources.sort();
+        self.sources.dedup();
+    }
+}
+
 #[cfg(feature = "into-api")]
 impl Text {
     pub fn new(text: &str, source: Source) -> Self {
@@ -151,28 +372,17 @@ impl Text {
         Self { text: text.to_string(), sources }
     }
 
-    pub fn into_api_long(self) -> api::LongText {
-        let text = match api::LongString::from_str(&self.text) {
-            Ok(ok) => ok,
-            Err(err) => {
-                log::error!("Could not convert to a LongString: {err}");
-                default_long_string()
-            }
-        };
-
-        api::LongText { text, sources: sources_to_api(&self.sources) }
+    pub fn convert_long(&self, mode: ConversionMode) -> Result<api::LongText, ApiConversionError> {
+        let text = mode.convert_text(&self.text, default_long_string)?;
+        Ok(api::LongText { text, sources: sources_to_api(&self.sources) })
     }
 
-    pub fn into_api_short(self) -> api::ShortText {
-        let text = match api::ShortString::from_str(&self.text) {
-            Ok(ok) => ok,
-            Err(err) => {
-                log::error!("Could not convert to a ShortString: {err}");
-                default_short_string()
-            }
-        };
-
-        api::ShortText { text, sources: sources_to_api(&self.sources) }
+    pub fn convert_short(
+        &self,
+        mode: ConversionMode,
+    ) -> Result<api::ShortText, ApiConversionError> {
+        let text = mode.convert_text(&self.text, default_short_string)?;
+        Ok(api::ShortText { text, sources: sources_to_api(&self.sources) })
     }
 }
 
@@ -207,24 +417,15 @@ pub struct Website {
 
 #[cfg(feature = "into-api")]
 impl Website {
-    pub fn into_api_short_string(self) -> api::ShortString {
-        match api::ShortString::from_str(&self.website) {
-            Ok(website) => website,
-            Err(err) => {
-                log::error!("Could not convert a website to a ShortString: {err}");
-                default_short_string()
-            }
-        }
+    pub fn convert_short_string(
+        &self,
+        mode: ConversionMode,
+    ) -> Result<api::ShortString, ApiConversionError> {
+        mode.convert(&self.website, default_short_string)
     }
 
-    pub fn into_api_id(self) -> api::Id {
-        match api::Id::from_str(&self.website) {
-            Ok(website) => website,
-            Err(err) => {
-                log::error!("Could not convert a website to an Id {err}");
-                default_id()
-            }
-        }
+    pub fn convert_id(&self, mode: ConversionMode) -> Result<api::Id, ApiConversionError> {
+        mode.convert(&self.website, default_id)
     }
 }
 
@@ -391,6 +592,29 @@ where
     {
         self.0.contains_key(key)
     }
+
+    /// Returns the values assigned to the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&BTreeSet<V>>
+    where
+        Q: Ord + ?Sized,
+        K: std::borrow::Borrow<Q>,
+    {
+        self.0.get(key)
+    }
+
+    /// Iterates over the keys together with their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &BTreeSet<V>)> {
+        self.0.iter()
+    }
+
+    /// Removes the key returning its values.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<BTreeSet<V>>
+    where
+        Q: Ord + ?Sized,
+        K: std::borrow::Borrow<Q>,
+    {
+        self.0.remove(key)
+    }
 }
 
 impl<K, V> MultiMap<K, V>
@@ -440,6 +664,30 @@ where
         }
         result.into_iter().collect()
     }
+
+    /// Returns the keys which were provided by the given source.
+    pub fn keys_from_source(&self, source: &Source) -> Vec<K>
+    where
+        K: Clone,
+    {
+        self.0
+            .iter()
+            .filter(|(_, sources)| sources.contains(source))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+impl MultiMap<ReportLink, Source> {
+    pub fn into_vec_report(self) -> Vec<SourcedReport> {
+        self.0
+            .into_iter()
+            .map(|(report, sources)| {
+                let sources = sources.into_iter().collect();
+                SourcedReport { title: report.title, link: report.link, sources }
+            })
+            .collect()
+    }
 }
 
 impl MultiMap<ids::OrganisationId, Source> {
@@ -622,6 +870,37 @@ impl Combine for Availability {
     }
 }
 
+impl Availability {
+    /// Adds regions in which retailers reported the product as available.
+    ///
+    /// Entries marking the product as unavailable are ignored, as other shops in the same region
+    /// may still be selling it.
+    pub fn overlay(&mut self, entries: &[RetailerAvailability]) {
+        let regions: Vec<_> =
+            entries.iter().filter(|entry| entry.available).map(|entry| entry.region).collect();
+        if !regions.is_empty() {
+            let current = std::mem::take(&mut self.regions);
+            self.regions = Combine::combine(current, Regions::List(regions));
+        }
+    }
+}
+
+/// Availability of a product in a region as reported by a retailer feed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RetailerAvailability {
+    /// Name of the retailer which provided the feed.
+    pub retailer: String,
+
+    /// Region the entry applies to.
+    pub region: isocountry::CountryCode,
+
+    /// Whether the product is currently available.
+    pub available: bool,
+
+    /// Link to the product in the retailer's shop.
+    pub url: Option<String>,
+}
+
 /// Data about a `BCorp` company.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct BCorpCert {
@@ -630,28 +909,29 @@ pub struct BCorpCert {
 
     /// Link to the BCorp page about the company.
     pub report_url: String,
+
+    /// Whether the certification was inherited from the producer.
+    #[serde(default)]
+    pub inherited: bool,
 }
 
 #[cfg(feature = "into-api")]
 impl BCorpCert {
-    pub fn into_api(self) -> api::Medallion {
-        let bcorp = match (api::Id::from_str(&self.id), api::LongString::from_str(&self.report_url))
-        {
+    // TODO: Present the `inherited` flag once the API supports it.
+    pub fn convert(self, mode: ConversionMode) -> Result<api::Medallion, ApiConversionError> {
+        let bcorp = match (parse(&self.id), parse(&self.report_url)) {
             (Ok(id), Ok(report_url)) => Some(api::BCorpMedallion { id, report_url }),
-            (id, report_url) => {
-                log::error!("Could not convert medallion: {id:?}, {report_url:?}");
-                None
-            }
+            (Err(err), _) | (_, Err(err)) => mode.recover(err, || None)?,
         };
 
-        api::Medallion {
+        Ok(api::Medallion {
             variant: api::MedallionVariant::BCorp,
             bcorp,
             eu_ecolabel: None,
             fti: None,
             transpaer: None,
             tco: None,
-        }
+        })
     }
 }
 
@@ -662,10 +942,15 @@ pub struct EuEcolabelCert;
 #[cfg(feature = "into-api")]
 impl EuEcolabelCert {
     pub fn into_api(self) -> api::Medallion {
+        self.into_api_with_accuracy(None)
+    }
+
+    /// Converts into a medallion carrying the accuracy of the match with other sources.
+    pub fn into_api_with_accuracy(self, match_accuracy: Option<f64>) -> api::Medallion {
         api::Medallion {
             variant: api::MedallionVariant::EuEcolabel,
             bcorp: None,
-            eu_ecolabel: Some(api::EuEcolabelMedallion { match_accuracy: None }),
+            eu_ecolabel: Some(api::EuEcolabelMedallion { match_accuracy }),
             fti: None,
             transpaer: None,
             tco: None,
@@ -673,15 +958,60 @@ impl EuEcolabelCert {
     }
 }
 
+/// Range of values of a score given by a reviewer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ScoreScale {
+    /// Lowest possible value.
+    pub min: i64,
+
+    /// Highest possible value.
+    pub max: i64,
+
+    /// Divisor to apply to the values to get the presented score.
+    pub div: i64,
+}
+
+impl ScoreScale {
+    /// Returns the position of the value on the scale (from 0.0 to 1.0).
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn normalize(&self, value: i64) -> f64 {
+        if self.max > self.min {
+            ((value - self.min) as f64 / (self.max - self.min) as f64).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    #[cfg(feature = "from-substrate")]
+    #[must_use]
+    pub fn to_schema(&self) -> schema::AboutScoreReview {
+        schema::AboutScoreReview { min: self.min, max: self.max, div: self.div }
+    }
+}
+
 /// Data about a company scored by Fashion Transparency Index.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct FtiCert {
     /// Score (from 0% to 100%).
     pub score: i64,
+
+    /// Scale of the score.
+    pub scale: ScoreScale,
+
+    /// Whether the score was inherited from the producer.
+    #[serde(default)]
+    pub inherited: bool,
+}
+
+impl FtiCert {
+    /// Scale of the Fashion Transparency Index scores.
+    pub const SCALE: ScoreScale = ScoreScale { min: 0, max: 100, div: 1 };
 }
 
 #[cfg(feature = "into-api")]
 impl FtiCert {
+    // TODO: Present the scale and the `inherited` flag once the API supports them.
     pub fn into_api(self) -> api::Medallion {
         api::Medallion {
             variant: api::MedallionVariant::Fti,
@@ -699,27 +1029,29 @@ impl FtiCert {
 pub struct TcoCert {
     /// Name identifying the company.
     pub brand_name: String,
+
+    /// Whether the certification was inherited from the producer.
+    #[serde(default)]
+    pub inherited: bool,
 }
 
 #[cfg(feature = "into-api")]
 impl TcoCert {
-    pub fn into_api(self) -> api::Medallion {
-        let tco = match api::ShortString::from_str(&self.brand_name) {
+    // TODO: Present the `inherited` flag once the API supports it.
+    pub fn convert(self, mode: ConversionMode) -> Result<api::Medallion, ApiConversionError> {
+        let tco = match parse(&self.brand_name) {
             Ok(brand_name) => Some(api::TcoMedallion { brand_name }),
-            Err(err) => {
-                log::error!("Could not convert a brand name to a ShortString: {err}");
-                None
-            }
+            Err(err) => mode.recover(err, || None)?,
         };
 
-        api::Medallion {
+        Ok(api::Medallion {
             variant: api::MedallionVariant::Tco,
             bcorp: None,
             eu_ecolabel: None,
             fti: None,
             transpaer: None,
             tco,
-        }
+        })
     }
 }
 
@@ -754,36 +1086,133 @@ impl Certifications {
     /// Copies certifications.
     ///
     /// EU Ecolabel is not inherited - this certification is assigned directly to products, not companies.
+    ///
+    /// Inherited certifications are marked so that they can be told apart from the ones given
+    /// directly to the product. Certifications the product already holds are kept.
     pub fn inherit(&mut self, other: &Self) {
-        if other.bcorp.is_some() {
-            self.bcorp.clone_from(&other.bcorp);
+        if self.bcorp.is_none()
+            && let Some(bcorp) = &other.bcorp
+        {
+            self.bcorp = Some(BCorpCert { inherited: true, ..bcorp.clone() });
         }
-        if other.fti.is_some() {
-            self.fti.clone_from(&other.fti);
+        if self.fti.is_none()
+            && let Some(fti) = &other.fti
+        {
+            self.fti = Some(FtiCert { inherited: true, ..fti.clone() });
+        }
+        if self.tco.is_none()
+            && let Some(tco) = &other.tco
+        {
+            self.tco = Some(TcoCert { inherited: true, ..tco.clone() });
         }
-        if other.tco.is_some() {
-            self.tco.clone_from(&other.tco);
+    }
+
+    /// Score used for ordering products in categories.
+    #[must_use]
+    pub fn score(&self) -> f64 {
+        0.0 + 0.9 * self.bcorp.is_some() as u32 as f64
+            + 0.9 * self.eu_ecolabel.is_some() as u32 as f64
+            + 0.6 * self.fti.as_ref().map_or(0.0, |c| c.scale.normalize(c.score))
+            + 0.3 * self.tco.is_some() as u32 as f64
+    }
+
+    /// Checks if the certification corresponding to the passed badge was given.
+    #[must_use]
+    pub fn has_badge(&self, badge: Badge) -> bool {
+        match badge {
+            Badge::BCorp => self.bcorp.is_some(),
+            Badge::EuEcolabel => self.eu_ecolabel.is_some(),
+            Badge::Tco => self.tco.is_some(),
         }
     }
 }
 
-impl Combine for Certifications {
-    fn combine(o1: Self, o2: Self) -> Self {
-        Self {
-            bcorp: Combine::combine(o1.bcorp, o2.bcorp),
-            eu_ecolabel: Combine::combine(o1.eu_ecolabel, o2.eu_ecolabel),
-            fti: Combine::combine(o1.fti, o2.fti),
-            tco: Combine::combine(o1.tco, o2.tco),
+/// Certifications which can be presented as a badge.
+///
+/// Scores (like FTI) are not badges.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Badge {
+    BCorp,
+    EuEcolabel,
+    Tco,
+}
+
+impl Badge {
+    /// All the badges.
+    pub const ALL: [Self; 3] = [Self::BCorp, Self::EuEcolabel, Self::Tco];
+
+    /// Returns badges a product from the given categories can never be given.
+    ///
+    /// A badge is eligible if any of the product categories listed in `BADGE_ELIGIBILITY` allows
+    /// it. Products from categories not listed there are eligible for all the badges.
+    ///
+    /// The product categories are expected to contain all the parent categories too.
+    #[must_use]
+    pub fn find_ineligible(product_categories: &BTreeSet<String>) -> BTreeSet<Self> {
+        let mut listed = false;
+        let mut eligible = BTreeSet::new();
+        for (category, badges) in BADGE_ELIGIBILITY {
+            if product_categories.contains(*category) {
+                listed = true;
+                eligible.extend(badges.iter().copied());
+            }
+        }
+
+        if listed {
+            Self::ALL.into_iter().filter(|badge| !eligible.contains(badge)).collect()
+        } else {
+            BTreeSet::new()
         }
     }
 }
 
+/// Badges which products from a given category can be given.
+///
+/// BCorp certifies companies so it is eligible everywhere, TCO covers only IT products and
+/// EU Ecolabel does not certify food or vehicles.
+pub const BADGE_ELIGIBILITY: &[(&str, &[Badge])] = &[
+    ("arts_and_entertainment", &[Badge::BCorp, Badge::EuEcolabel]),
+    ("cameras_and_optics", &[Badge::BCorp, Badge::EuEcolabel]),
+    ("electronics", &[Badge::BCorp, Badge::EuEcolabel, Badge::Tco]),
+    ("food_beverages_and_tobacco", &[Badge::BCorp]),
+    ("home_and_garden", &[Badge::BCorp, Badge::EuEcolabel]),
+    ("office_supplies", &[Badge::BCorp, Badge::EuEcolabel]),
+    ("toys_and_games", &[Badge::BCorp, Badge::EuEcolabel]),
+    ("vehicles_and_parts", &[Badge::BCorp]),
+];
+
+/// State of a badge for a given product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BadgeStatus {
+    /// The product holds the badge.
+    Given,
+
+    /// The product could hold the badge but it does not.
+    Missing,
+
+    /// The product can never hold the badge because of its categories.
+    NotEligible,
+}
+
+combine_fields!(Certifications { bcorp, eu_ecolabel, fti, tco });
+
 #[cfg(feature = "into-api")]
 impl Certifications {
     pub fn into_api_medallions(self) -> Vec<api::Medallion> {
+        lenient(self.convert_medallions(ConversionMode::Lenient))
+    }
+
+    pub fn try_into_api_medallions(self) -> Result<Vec<api::Medallion>, ApiConversionError> {
+        self.convert_medallions(ConversionMode::Strict)
+    }
+
+    fn convert_medallions(
+        self,
+        mode: ConversionMode,
+    ) -> Result<Vec<api::Medallion>, ApiConversionError> {
         let mut medallions = Vec::new();
         if let Some(bcorp) = self.bcorp {
-            medallions.push(bcorp.into_api());
+            medallions.push(bcorp.convert(mode)?);
         }
         if let Some(eu_ecolabel) = self.eu_ecolabel {
             medallions.push(eu_ecolabel.into_api());
@@ -792,9 +1221,9 @@ impl Certifications {
             medallions.push(fti.into_api());
         }
         if let Some(tco) = self.tco {
-            medallions.push(tco.into_api());
+            medallions.push(tco.convert(mode)?);
         }
-        medallions
+        Ok(medallions)
     }
 
     pub fn to_api_badges(&self) -> Vec<api::BadgeName> {
@@ -827,12 +1256,17 @@ pub struct Mention {
 
     /// External link to the mention.
     pub link: String,
+
+    /// Time (in seconds from the start of the recording) when the subject is discussed.
+    pub timestamp: Option<u32>,
 }
 
 #[cfg(feature = "into-api")]
 impl Mention {
     pub fn into_api(self) -> api::Mention {
-        api::Mention { title: self.title, link: self.link }
+        // TODO: Pass the timestamp in a separate field once the API spec supports it.
+        let link = utils::with_timestamp(&self.link, self.timestamp);
+        api::Mention { title: self.title, link }
     }
 }
 
@@ -855,6 +1289,50 @@ impl Medium {
     }
 }
 
+/// Link to an external report about the subject, e.g. a review or an audit.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ReportLink {
+    /// Title of the report.
+    pub title: Option<String>,
+
+    /// External link to the report.
+    pub link: String,
+}
+
+/// Report link with its sources.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourcedReport {
+    /// Title of the report.
+    pub title: Option<String>,
+
+    /// External link to the report.
+    pub link: String,
+
+    /// Sources which provided the report.
+    pub sources: Vec<Source>,
+}
+
+#[cfg(feature = "into-api")]
+impl SourcedReport {
+    /// Converts the reports into media grouped by the source which provided them.
+    // TODO: Present the reports in a separate section once the API spec supports it.
+    pub fn into_api_media(reports: Vec<Self>) -> Vec<api::Medium> {
+        let mut grouped = BTreeMap::<Source, Vec<api::Mention>>::new();
+        for report in reports {
+            let Some(source) = report.sources.first() else { continue };
+            let title = report.title.unwrap_or_else(|| report.link.clone());
+            grouped
+                .entry(source.clone())
+                .or_default()
+                .push(api::Mention { title, link: report.link });
+        }
+        grouped
+            .into_iter()
+            .map(|(source, mentions)| api::Medium { icon: source.get_icon_link(), mentions })
+            .collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
 pub enum VerifiedShop {
@@ -869,6 +1347,22 @@ impl VerifiedShop {
             schema::VerifiedShop::Amazon => Self::Amazon,
         }
     }
+
+    /// Returns the name of the shop used in the meta files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fairphone => "fairphone",
+            Self::Amazon => "amazon",
+        }
+    }
+
+    /// Returns the link to the product with the given ID in the shop.
+    pub fn link(&self, id: &str) -> String {
+        match self {
+            Self::Fairphone => format!("https://shop.fairphone.com/{id}"),
+            Self::Amazon => format!("https://www.amazon.nl/-/en/_/dp/{id}"),
+        }
+    }
 }
 
 #[cfg(feature = "into-api")]
@@ -915,14 +1409,11 @@ pub struct ShoppingEntry {
 
 #[cfg(feature = "into-api")]
 impl ShoppingEntry {
-    pub fn into_api(self) -> api::ShoppingEntry {
-        let link = match &self.shop {
-            VerifiedShop::Fairphone => format!("https://shop.fairphone.com/{}", self.id),
-            VerifiedShop::Amazon => format!("https://www.amazon.nl/-/en/_/dp/{}", self.id),
-        };
+    pub fn convert(self, mode: ConversionMode) -> Result<api::ShoppingEntry, ApiConversionError> {
+        let link = self.shop.link(&self.id);
         let shop = self.shop.into_api();
-        let description = str_to_short_string(self.description);
-        api::ShoppingEntry { shop, link, description }
+        let description = mode.convert_text(&self.description, default_short_string)?;
+        Ok(api::ShoppingEntry { shop, link, description })
     }
 }
 
@@ -944,9 +1435,12 @@ pub enum TranspaerScoreCategory {
 
 #[cfg(feature = "into-api")]
 impl TranspaerScoreCategory {
-    pub fn into_api(self) -> api::TranspaerScoreCategory {
-        match self {
-            Self::Root => unimplemented!(), //< This category is never passed to the API
+    /// Converts into the API category.
+    ///
+    /// The root category is never passed to the API.
+    pub fn convert(self) -> Result<api::TranspaerScoreCategory, ApiConversionError> {
+        Ok(match self {
+            Self::Root => return Err(ApiConversionError::ScoreCategory(self)),
             Self::DataAvailability => api::TranspaerScoreCategory::DataAvailability,
             Self::ProducerKnown => api::TranspaerScoreCategory::ProducerKnown,
             Self::ProductionPlaceKnown => api::TranspaerScoreCategory::ProductionPlaceKnown,
@@ -957,7 +1451,7 @@ impl TranspaerScoreCategory {
             Self::NumCerts => api::TranspaerScoreCategory::NumCerts,
             Self::AtLeastOneCert => api::TranspaerScoreCategory::AtLeastOneCert,
             Self::AtLeastTwoCerts => api::TranspaerScoreCategory::AtLeastTwoCerts,
-        }
+        })
     }
 }
 
@@ -974,17 +1468,25 @@ pub struct TranspaerScoreBranch {
 
     /// Calculated subscore of this branch.
     pub score: f64,
+
+    /// References to the data which satisfied this branch.
+    ///
+    /// The references have form `<kind>:<name>`, e.g. `certification:bcorp` or
+    /// `field:manufacturers`. Empty if nothing satisfied the branch or if the score is derived
+    /// only from the subbranches.
+    pub evidence: Vec<String>,
 }
 
 #[cfg(feature = "into-api")]
 impl TranspaerScoreBranch {
-    pub fn into_api(self) -> api::TranspaerScoreBranch {
-        api::TranspaerScoreBranch {
-            branches: self.branches.into_iter().map(|b| b.into_api()).collect(),
-            category: self.category.into_api(),
+    pub fn convert(self) -> Result<api::TranspaerScoreBranch, ApiConversionError> {
+        // TODO: Pass the evidence once the API provides a field for it.
+        Ok(api::TranspaerScoreBranch {
+            branches: self.branches.into_iter().map(|b| b.convert()).collect::<Result<_, _>>()?,
+            category: self.category.convert()?,
             weight: self.weight as i64,
             score: self.score,
-        }
+        })
     }
 }
 
@@ -995,32 +1497,39 @@ pub struct TranspaerScore {
 
     /// Total calculated score.
     pub total: f64,
+
+    /// ID of the scorer which calculated the score (empty if the score was not calculated).
+    pub scorer: String,
 }
 
 #[cfg(feature = "into-api")]
 impl TranspaerScore {
     pub fn into_api_score(self) -> api::TranspaerScore {
-        api::TranspaerScore {
-            tree: self.tree.into_iter().map(|t| t.into_api()).collect(),
+        lenient(self.try_into_api_score())
+    }
+
+    pub fn try_into_api_score(self) -> Result<api::TranspaerScore, ApiConversionError> {
+        Ok(api::TranspaerScore {
+            tree: self.tree.into_iter().map(|t| t.convert()).collect::<Result<_, _>>()?,
             total: self.total,
-        }
+        })
     }
 
-    fn into_api_medallion(self) -> api::Medallion {
-        api::Medallion {
+    fn convert_medallion(self) -> Result<api::Medallion, ApiConversionError> {
+        Ok(api::Medallion {
             variant: api::MedallionVariant::Transpaer,
-            transpaer: Some(api::TranspaerMedallion { score: self.into_api_score() }),
+            transpaer: Some(api::TranspaerMedallion { score: self.try_into_api_score()? }),
             bcorp: None,
             eu_ecolabel: None,
             fti: None,
             tco: None,
-        }
+        })
     }
 }
 
 impl Default for TranspaerScore {
     fn default() -> Self {
-        Self { tree: Vec::default(), total: 0.0 }
+        Self { tree: Vec::default(), total: 0.0, scorer: String::new() }
     }
 }
 
@@ -1043,10 +1552,70 @@ pub struct TranspaerProductData {
     pub significance: HashMap<Source, Significance>,
 }
 
+/// Method used to recognise entries from different sources as the same organisation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum MatchMethod {
+    /// Listed in the match file prepared manually or by the `connect` command.
+    ManualFile,
+
+    /// Sharing a VAT number.
+    Vat,
+
+    /// Sharing a web domain.
+    Domain,
+
+    /// Sharing a Wikidata ID.
+    Wiki,
+
+    /// Having similar names.
+    NameHeuristic,
+}
+
+/// Describes how entries from some sources were merged into one organisation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OrganisationMatch {
+    /// Method used for matching.
+    pub method: MatchMethod,
+
+    /// Sources whose entries were merged.
+    pub sources: BTreeSet<Source>,
+
+    /// Accuracy of the match as reported by the matcher, if known.
+    pub accuracy: Option<f64>,
+}
+
+/// Factors ranking an organisation in text searches independently of the query.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchRank {
+    /// Number of products of the organisation.
+    pub num_products: u32,
+
+    /// Number of certifications held by the organisation.
+    pub num_certifications: u32,
+
+    /// Number of sources describing the organisation.
+    pub notability: u32,
+}
+
 // TODO: Introduce score for organisations
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct TranspaerOrganisationData {
     pub significance: HashMap<Source, Significance>,
+
+    /// Information about how this organisation was merged from different sources.
+    #[serde(default)]
+    pub matches: Vec<OrganisationMatch>,
+
+    /// Static factors ranking the organisation in text searches.
+    #[serde(default)]
+    pub rank: SearchRank,
+}
+
+impl TranspaerOrganisationData {
+    /// Returns the accuracy of the match from the manual match file, if any.
+    pub fn manual_match_accuracy(&self) -> Option<f64> {
+        self.matches.iter().filter(|m| m.method == MatchMethod::ManualFile).find_map(|m| m.accuracy)
+    }
 }
 
 /// Represents a set of IDs of an organisation.
@@ -1076,14 +1645,7 @@ impl GatherOrganisationIds {
     }
 }
 
-impl Combine for GatherOrganisationIds {
-    fn combine(o1: Self, o2: Self) -> Self {
-        let wiki = Combine::combine(o1.wiki, o2.wiki);
-        let vat_ids = Combine::combine(o1.vat_ids, o2.vat_ids);
-        let domains = Combine::combine(o1.domains, o2.domains);
-        Self { wiki, vat_ids, domains }
-    }
-}
+combine_fields!(GatherOrganisationIds { wiki, vat_ids, domains });
 
 /// Represents a set of IDs of an organisation.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -1100,12 +1662,12 @@ pub struct StoreOrganisationIds {
 
 #[cfg(feature = "into-api")]
 impl StoreOrganisationIds {
-    pub fn into_api(self) -> api::OrganisationIds {
-        api::OrganisationIds {
+    pub fn convert(self, mode: ConversionMode) -> Result<api::OrganisationIds, ApiConversionError> {
+        Ok(api::OrganisationIds {
             wiki: self.wiki.into_iter().map(|id| id.into_api()).collect(),
             vat: self.vat_ids.into_iter().map(|id| id.into_api()).collect(),
-            domains: self.domains.into_iter().map(|id| id.into_api_id()).collect(),
-        }
+            domains: self.domains.iter().map(|id| id.convert_id(mode)).collect::<Result<_, _>>()?,
+        })
     }
 }
 
@@ -1147,7 +1709,7 @@ impl GatherOrganisation {
     pub fn store(self) -> StoreOrganisation {
         let ids = self.ids.store();
         let mut names: Vec<_> = self.names.into_vec_text();
-        let mut descriptions: Vec<_> = self.descriptions.into_vec_text();
+        let mut descriptions = Text::select_descriptions(self.descriptions.into_vec_text());
         let mut images: Vec<_> = self.images.into_iter().collect();
         let mut websites: Vec<_> = self.websites.into_vec_website();
         let mut products: Vec<_> = self.products.into_iter().collect();
@@ -1180,33 +1742,48 @@ impl GatherOrganisation {
 }
 
 impl Combine for GatherOrganisation {
-    fn combine(mut o1: Self, o2: Self) -> Self {
-        let ids = Combine::combine(o1.ids, o2.ids);
-
-        let names = Combine::combine(o1.names, o2.names);
-        let descriptions = Combine::combine(o1.descriptions, o2.descriptions);
-        let websites = Combine::combine(o1.websites, o2.websites);
-        let origins = Combine::combine(o1.origins, o2.origins);
-        let certifications = Combine::combine(o1.certifications, o2.certifications);
-
-        // This data is filled after merging all organisations.
-        let transpaer = TranspaerOrganisationData::default();
-
-        o1.images.extend(o2.images);
-        o1.products.extend(o2.products);
-        o1.media.extend(o2.media);
+    fn combine(o1: Self, o2: Self) -> Self {
+        let Self {
+            ids: ids1,
+            names: names1,
+            descriptions: descriptions1,
+            images: mut images1,
+            websites: websites1,
+            products: mut products1,
+            origins: origins1,
+            certifications: certifications1,
+            media: mut media1,
+            // This data is filled after merging all organisations.
+            transpaer: _,
+        } = o1;
+        let Self {
+            ids: ids2,
+            names: names2,
+            descriptions: descriptions2,
+            images: images2,
+            websites: websites2,
+            products: products2,
+            origins: origins2,
+            certifications: certifications2,
+            media: media2,
+            transpaer: _,
+        } = o2;
+
+        images1.extend(images2);
+        products1.extend(products2);
+        media1.extend(media2);
 
         Self {
-            ids,
-            names,
-            descriptions,
-            images: o1.images,
-            websites,
-            products: o1.products,
-            origins,
-            certifications,
-            media: o1.media,
-            transpaer,
+            ids: Combine::combine(ids1, ids2),
+            names: Combine::combine(names1, names2),
+            descriptions: Combine::combine(descriptions1, descriptions2),
+            images: images1,
+            websites: Combine::combine(websites1, websites2),
+            products: products1,
+            origins: Combine::combine(origins1, origins2),
+            certifications: Combine::combine(certifications1, certifications2),
+            media: media1,
+            transpaer: TranspaerOrganisationData::default(),
         }
     }
 }
@@ -1267,25 +1844,38 @@ fn sources_to_api(sources: &[Source]) -> api::DataSources {
 
 #[cfg(feature = "into-api")]
 fn str_to_short_string(s: String) -> api::ShortString {
-    api::ShortString::from_str(&s).expect("Converting strings")
+    lenient(ConversionMode::Lenient.convert_text(&s, default_short_string))
 }
 
 #[cfg(feature = "into-api")]
 fn str_to_long_string(s: String) -> api::LongString {
-    api::LongString::from_str(&s).expect("Converting strings")
+    lenient(ConversionMode::Lenient.convert_text(&s, default_long_string))
 }
 
 #[cfg(feature = "into-api")]
-fn text_to_short_string(text: &Text) -> api::ShortString {
-    api::ShortString::from_str(&text.text).expect("Converting texts")
+fn first_to_short_string(
+    texts: &[Text],
+    mode: ConversionMode,
+) -> Result<api::ShortString, ApiConversionError> {
+    match texts.first() {
+        Some(text) => mode.convert_text(&text.text, default_short_string),
+        None => Ok(default_short_string()),
+    }
 }
 
+/// Converts the longest of the texts fitting the API limits.
+///
+/// If none of them fits, the first one is truncated.
 #[cfg(feature = "into-api")]
-fn text_to_long_text(text: &Text) -> api::LongText {
-    api::LongText {
-        text: api::LongString::from_str(&text.text).expect("Converting texts"),
-        sources: sources_to_api(&text.sources),
-    }
+fn longest_to_long_text(
+    texts: &[Text],
+    mode: ConversionMode,
+) -> Result<Option<api::LongText>, ApiConversionError> {
+    let longest_fitting = texts
+        .iter()
+        .filter(|text| parse::<api::LongString>(&text.text).is_ok())
+        .max_by_key(|text| text.text.chars().count());
+    longest_fitting.or(texts.first()).map(|text| text.convert_long(mode)).transpose()
 }
 
 #[cfg(feature = "into-api")]
@@ -1296,27 +1886,70 @@ fn country_code_to_region_code(country: Country) -> api::RegionCode {
 #[cfg(feature = "into-api")]
 impl StoreOrganisation {
     pub fn into_api_short(self) -> api::OrganisationShort {
-        api::OrganisationShort {
-            organisation_ids: self.ids.into_api(),
-            name: self.names.first().map_or_else(default_short_string, text_to_short_string),
-            description: self.descriptions.first().map(text_to_long_text),
+        lenient(self.convert_short(ConversionMode::Lenient))
+    }
+
+    pub fn try_into_api_short(self) -> Result<api::OrganisationShort, ApiConversionError> {
+        self.convert_short(ConversionMode::Strict)
+    }
+
+    pub fn into_api_full(self, products: Vec<api::ProductShort>) -> api::OrganisationFull {
+        lenient(self.convert_full(products, ConversionMode::Lenient))
+    }
+
+    pub fn try_into_api_full(
+        self,
+        products: Vec<api::ProductShort>,
+    ) -> Result<api::OrganisationFull, ApiConversionError> {
+        self.convert_full(products, ConversionMode::Strict)
+    }
+
+    fn convert_short(
+        self,
+        mode: ConversionMode,
+    ) -> Result<api::OrganisationShort, ApiConversionError> {
+        Ok(api::OrganisationShort {
+            name: first_to_short_string(&self.names, mode)?,
+            description: longest_to_long_text(&self.descriptions, mode)?,
             badges: self.certifications.to_api_badges(),
             scores: self.certifications.to_api_scores(),
-        }
+            organisation_ids: self.ids.convert(mode)?,
+        })
     }
 
-    pub fn into_api_full(self, products: Vec<api::ProductShort>) -> api::OrganisationFull {
-        api::OrganisationFull {
-            organisation_ids: self.ids.into_api(),
-            names: self.names.into_iter().map(|n| n.into_api_short()).collect(),
-            descriptions: self.descriptions.into_iter().map(|d| d.into_api_long()).collect(),
+    fn convert_full(
+        self,
+        products: Vec<api::ProductShort>,
+        mode: ConversionMode,
+    ) -> Result<api::OrganisationFull, ApiConversionError> {
+        // TODO: Expose all the matches in a separate section once the API spec supports it.
+        let match_accuracy = self.transpaer.manual_match_accuracy();
+        let mut medallions = self.certifications.convert_medallions(mode)?;
+        for medallion in &mut medallions {
+            if let Some(eu_ecolabel) = &mut medallion.eu_ecolabel {
+                eu_ecolabel.match_accuracy = match_accuracy;
+            }
+        }
+
+        Ok(api::OrganisationFull {
+            organisation_ids: self.ids.convert(mode)?,
+            names: self.names.iter().map(|n| n.convert_short(mode)).collect::<Result<_, _>>()?,
+            descriptions: self
+                .descriptions
+                .iter()
+                .map(|d| d.convert_long(mode))
+                .collect::<Result<_, _>>()?,
             images: self.images.into_iter().map(|i| i.into_api()).collect(),
-            websites: self.websites.into_iter().map(|w| w.into_api_short_string()).collect(),
+            websites: self
+                .websites
+                .iter()
+                .map(|w| w.convert_short_string(mode))
+                .collect::<Result<_, _>>()?,
             origins: self.origins.into_iter().map(country_code_to_region_code).collect(),
-            medallions: self.certifications.into_api_medallions(),
+            medallions,
             media: self.media.into_iter().map(|m| m.into_api()).collect(),
             products,
-        }
+        })
     }
 }
 
@@ -1351,14 +1984,7 @@ impl GatherProductIds {
     }
 }
 
-impl Combine for GatherProductIds {
-    fn combine(o1: Self, o2: Self) -> Self {
-        let eans = Combine::combine(o1.eans, o2.eans);
-        let gtins = Combine::combine(o1.gtins, o2.gtins);
-        let wiki = Combine::combine(o1.wiki, o2.wiki);
-        Self { eans, gtins, wiki }
-    }
-}
+combine_fields!(GatherProductIds { eans, gtins, wiki });
 
 /// Represents a set of product IDs.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -1384,6 +2010,236 @@ impl StoreProductIds {
     }
 }
 
+impl StoreProductIds {
+    /// Returns all the IDs as references usable by the clients.
+    pub fn to_refs(&self) -> Vec<ProductRef> {
+        let wiki = self.wiki.iter().map(|id| ProductRef::Wiki(id.id));
+        let gtins = self.gtins.iter().map(|id| ProductRef::Gtin(id.id.clone()));
+        let eans = self.eans.iter().map(|id| ProductRef::Ean(id.id.clone()));
+        wiki.chain(gtins).chain(eans).collect()
+    }
+}
+
+/// Product ID as used by the clients to refer to a product.
+///
+/// Unlike `ProductId` it stays the same across the dataset versions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProductRef {
+    Wiki(ids::WikiId),
+    Gtin(ids::Gtin),
+    Ean(ids::Ean),
+}
+
+/// Nutri-Score grade of a food product.
+///
+/// This is a score of the nutritional quality, not of sustainability, so it is kept separately
+/// from the certifications and does not contribute to the Transpaer score.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NutriScore {
+    A,
+    B,
+    C,
+    D,
+    E,
+}
+
+impl NutriScore {
+    /// Scale of the grades as score review values.
+    pub const SCALE: ScoreScale = ScoreScale { min: 1, max: 5, div: 1 };
+
+    /// Parses the grade as used in the Open Food Facts data (e.g. `a` or `A`).
+    pub fn from_grade(grade: &str) -> Option<Self> {
+        match grade.trim().to_ascii_lowercase().as_str() {
+            "a" => Some(Self::A),
+            "b" => Some(Self::B),
+            "c" => Some(Self::C),
+            "d" => Some(Self::D),
+            "e" => Some(Self::E),
+            _ => None,
+        }
+    }
+
+    /// Constructs the grade from a score review value (from 5 for `A` to 1 for `E`).
+    pub fn from_value(value: i64) -> Option<Self> {
+        match value {
+            5 => Some(Self::A),
+            4 => Some(Self::B),
+            3 => Some(Self::C),
+            2 => Some(Self::D),
+            1 => Some(Self::E),
+            _ => None,
+        }
+    }
+
+    /// Returns the grade as a score review value (from 5 for `A` to 1 for `E`).
+    pub fn to_value(self) -> i64 {
+        match self {
+            Self::A => 5,
+            Self::B => 4,
+            Self::C => 3,
+            Self::D => 2,
+            Self::E => 1,
+        }
+    }
+}
+
+/// Price tier of a product, from 1 for the most affordable to 5 for the most expensive.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PriceTier(u8);
+
+impl PriceTier {
+    /// Scale of the tiers as score review values.
+    pub const SCALE: ScoreScale = ScoreScale { min: 1, max: 5, div: 1 };
+
+    /// Constructs the tier from a score review value.
+    pub fn from_value(value: i64) -> Option<Self> {
+        if (Self::SCALE.min..=Self::SCALE.max).contains(&value) {
+            u8::try_from(value).ok().map(Self)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the tier as a score review value.
+    pub fn to_value(self) -> i64 {
+        i64::from(self.0)
+    }
+}
+
+/// Price tiers of a product in different regions.
+///
+/// The tiers are read only from the `price_level` reviewer substrates, as the producer substrate
+/// schema has no field for them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriceLevels {
+    /// Tier used in regions without a specific tier.
+    pub default: Option<PriceTier>,
+
+    /// Tiers in specific regions.
+    pub regional: BTreeMap<isocountry::CountryCode, PriceTier>,
+}
+
+impl PriceLevels {
+    /// Constructs price levels assigning the tier to the given regions.
+    ///
+    /// World-wide or unknown regions set the default tier.
+    pub fn new(tier: PriceTier, regions: &Regions) -> Self {
+        match regions {
+            Regions::World | Regions::Unknown => Self { default: Some(tier), ..Self::default() },
+            Regions::List(codes) => {
+                Self { default: None, regional: codes.iter().map(|code| (*code, tier)).collect() }
+            }
+        }
+    }
+
+    /// Returns the tier in the region given as an alpha-3 code.
+    pub fn get(&self, region: Option<&str>) -> Option<PriceTier> {
+        region
+            .and_then(|region| isocountry::CountryCode::for_alpha3(region).ok())
+            .and_then(|code| self.regional.get(&code).copied())
+            .or(self.default)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.regional.is_empty()
+    }
+}
+
+impl Combine for PriceLevels {
+    /// Merges the levels preferring the tiers from the first ones.
+    fn combine(mut o1: Self, o2: Self) -> Self {
+        for (region, tier) in o2.regional {
+            o1.regional.entry(region).or_insert(tier);
+        }
+        Self { default: o1.default.or(o2.default), regional: o1.regional }
+    }
+}
+
+/// Base unit of a product quantity.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QuantityUnit {
+    /// Mass in kilograms.
+    Kilogram,
+
+    /// Volume in litres.
+    Litre,
+
+    /// Number of pieces.
+    Piece,
+}
+
+/// Net quantity of a product, e.g. the mass of a package of pasta.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ProductQuantity {
+    /// Amount in the base unit.
+    pub amount: f64,
+
+    /// Base unit of the amount.
+    pub unit: QuantityUnit,
+}
+
+impl ProductQuantity {
+    /// Calculates how similar (from 0.0 to 1.0) the quantities are.
+    ///
+    /// Quantities in different units are not comparable and have the similarity of 0.0.
+    #[must_use]
+    pub fn similarity(&self, other: &Self) -> f64 {
+        if self.unit != other.unit {
+            return 0.0;
+        }
+        let (min, max) = if self.amount < other.amount {
+            (self.amount, other.amount)
+        } else {
+            (other.amount, self.amount)
+        };
+        if min > 0.0 { min / max } else { 0.0 }
+    }
+
+    /// Finds the median of the quantities in the most common unit.
+    ///
+    /// Ties between the units are resolved in favour of the unit listed first in `QuantityUnit`.
+    #[must_use]
+    pub fn median(quantities: impl IntoIterator<Item = Self>) -> Option<Self> {
+        let mut by_unit = BTreeMap::<QuantityUnit, Vec<f64>>::new();
+        for quantity in quantities {
+            by_unit.entry(quantity.unit).or_default().push(quantity.amount);
+        }
+        let (unit, mut amounts) =
+            by_unit.into_iter().rev().max_by_key(|(_, amounts)| amounts.len())?;
+        amounts.sort_by(f64::total_cmp);
+        Some(Self { amount: amounts[amounts.len() / 2], unit })
+    }
+}
+
+/// Times of the latest updates of product fields in seconds since the Unix epoch.
+///
+/// A field is `None` if it was never filled or the time of the update is not known.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldTimestamps {
+    /// Latest update of the product IDs.
+    pub ids: Option<u64>,
+
+    /// Latest update of the manufacturers.
+    pub manufacturers: Option<u64>,
+
+    /// Latest update of the categories.
+    pub categories: Option<u64>,
+
+    /// Latest update of the production places.
+    pub origins: Option<u64>,
+}
+
+impl Combine for FieldTimestamps {
+    fn combine(o1: Self, o2: Self) -> Self {
+        Self {
+            ids: o1.ids.max(o2.ids),
+            manufacturers: o1.manufacturers.max(o2.manufacturers),
+            categories: o1.categories.max(o2.categories),
+            origins: o1.origins.max(o2.origins),
+        }
+    }
+}
+
 /// Represents a product.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct GatherProduct {
@@ -1396,12 +2252,22 @@ pub struct GatherProduct {
     /// Descriptions of the product.
     pub descriptions: MultiMap<String, Source>,
 
+    /// Short summaries of the product written by the review sources.
+    #[serde(default)]
+    pub summaries: MultiMap<String, Source>,
+
     /// Product images.
     pub images: BTreeSet<Image>,
 
     /// Product categories.
     pub categories: MultiMap<String, Source>,
 
+    /// ID of the Google Product Category closest to the product categories.
+    pub google_product_category: Option<u32>,
+
+    /// Badges the product can never be given because of its categories.
+    pub ineligible_badges: BTreeSet<Badge>,
+
     /// Regions where the product is available.
     pub availability: Availability,
 
@@ -1411,6 +2277,12 @@ pub struct GatherProduct {
     /// Known certifications.
     pub certifications: Certifications,
 
+    /// Nutri-Score grade.
+    pub nutri_score: Option<NutriScore>,
+
+    /// Price tiers in different regions.
+    pub price_levels: PriceLevels,
+
     /// DB IDs of manufacturers.
     pub manufacturers: MultiMap<ids::OrganisationId, Source>,
 
@@ -1420,12 +2292,22 @@ pub struct GatherProduct {
     /// Mentions in media.
     pub media: BTreeSet<Medium>,
 
+    /// Links to external reports about the product.
+    pub reports: MultiMap<ReportLink, Source>,
+
     /// Wikidata IDs newer version products.
     pub follows: BTreeSet<ids::ProductId>,
 
     /// Wikidata IDs older version products.
     pub followed_by: BTreeSet<ids::ProductId>,
 
+    /// Number of sitelinks of the Wikidata items of the product.
+    pub sitelinks: u32,
+
+    /// Times of the latest updates of the fields.
+    #[serde(default)]
+    pub updated: FieldTimestamps,
+
     /// The Transpaer data.
     pub transpaer: TranspaerProductData,
 }
@@ -1435,16 +2317,23 @@ impl GatherProduct {
         let ids = self.ids.store();
         let mut names = self.names.into_vec_text();
         let descriptions = self.descriptions.into_vec_text();
+        let summaries = self.summaries.into_vec_text();
         let mut images: Vec<_> = self.images.into_iter().collect();
         let mut categories = self.categories.into_vec_text();
+        let google_product_category = self.google_product_category;
         let availability = self.availability;
         let origins = self.origins.into_vec_country();
+        let ineligible_badges = self.ineligible_badges.into_iter().collect();
         let certifications = self.certifications;
+        let nutri_score = self.nutri_score;
+        let price_levels = self.price_levels;
         let mut manufacturers = self.manufacturers.into_vec_organisation_ids();
         let mut shopping = self.shopping.into_vec_shopping_entry();
         let mut media: Vec<_> = self.media.into_iter().collect();
+        let mut reports = self.reports.into_vec_report();
         let mut follows: Vec<_> = self.follows.into_iter().collect();
         let mut followed_by: Vec<_> = self.followed_by.into_iter().collect();
+        let sitelinks = self.sitelinks;
         let transpaer = self.transpaer;
 
         names.sort();
@@ -1453,6 +2342,7 @@ impl GatherProduct {
         manufacturers.sort();
         shopping.sort();
         media.sort();
+        reports.sort();
         follows.sort();
         followed_by.sort();
 
@@ -1460,20 +2350,49 @@ impl GatherProduct {
             ids,
             names,
             descriptions,
+            summaries,
             images,
             categories,
+            google_product_category,
             availability,
             origins,
             certifications,
+            ineligible_badges,
+            nutri_score,
+            price_levels,
             manufacturers,
             shopping,
             media,
+            reports,
             follows,
             followed_by,
+            sitelinks,
             transpaer,
         }
     }
 
+    /// Records the given time as the time of the latest update of all the filled fields.
+    pub fn stamp(&mut self, time: u64) {
+        if !self.ids.is_empty() {
+            self.updated.ids = Some(time);
+        }
+        if !self.manufacturers.is_empty() {
+            self.updated.manufacturers = Some(time);
+        }
+        if !self.categories.is_empty() {
+            self.updated.categories = Some(time);
+        }
+        if !self.origins.is_empty() {
+            self.updated.origins = Some(time);
+        }
+    }
+
+    /// Returns the net quantity found in the first name mentioning one.
+    // TODO: Prefer the Wikidata mass once the substrate schema can hold it.
+    pub fn quantity(&self) -> Option<ProductQuantity> {
+        self.names.keys().iter().find_map(|name| utils::parse_quantity(name))
+    }
+
     pub fn all_categories(&self, category_separator: char) -> BTreeSet<String> {
         let sep = category_separator.to_string();
         let mut result = BTreeSet::new();
@@ -1492,40 +2411,85 @@ impl GatherProduct {
 }
 
 impl Combine for GatherProduct {
-    fn combine(mut o1: Self, o2: Self) -> Self {
-        let ids = Combine::combine(o1.ids, o2.ids);
-        let names = Combine::combine(o1.names, o2.names);
-        let descriptions = Combine::combine(o1.descriptions, o2.descriptions);
-        let categories = Combine::combine(o1.categories, o2.categories);
-        let origins = Combine::combine(o1.origins, o2.origins);
-        let availability = Combine::combine(o1.availability, o2.availability);
-        let certifications = Combine::combine(o1.certifications, o2.certifications);
-        let manufacturers = Combine::combine(o1.manufacturers, o2.manufacturers);
-        let shopping = Combine::combine(o1.shopping, o2.shopping);
-
-        // This data is filled after merging all organisations.
-        let transpaer = TranspaerProductData::default();
-
-        o1.images.extend(o2.images);
-        o1.media.extend(o2.media);
-        o1.follows.extend(o2.follows);
-        o1.followed_by.extend(o2.followed_by);
+    fn combine(o1: Self, o2: Self) -> Self {
+        let Self {
+            ids: ids1,
+            names: names1,
+            descriptions: descriptions1,
+            summaries: summaries1,
+            images: mut images1,
+            categories: categories1,
+            availability: availability1,
+            origins: origins1,
+            certifications: certifications1,
+            nutri_score: nutri_score1,
+            price_levels: price_levels1,
+            manufacturers: manufacturers1,
+            shopping: shopping1,
+            media: mut media1,
+            reports: reports1,
+            follows: mut follows1,
+            followed_by: mut followed_by1,
+            updated: updated1,
+            // This data is filled after merging all organisations.
+            google_product_category: _,
+            ineligible_badges: _,
+            sitelinks: _,
+            transpaer: _,
+        } = o1;
+        let Self {
+            ids: ids2,
+            names: names2,
+            descriptions: descriptions2,
+            summaries: summaries2,
+            images: images2,
+            categories: categories2,
+            availability: availability2,
+            origins: origins2,
+            certifications: certifications2,
+            nutri_score: nutri_score2,
+            price_levels: price_levels2,
+            manufacturers: manufacturers2,
+            shopping: shopping2,
+            media: media2,
+            reports: reports2,
+            follows: follows2,
+            followed_by: followed_by2,
+            updated: updated2,
+            google_product_category: _,
+            ineligible_badges: _,
+            sitelinks: _,
+            transpaer: _,
+        } = o2;
+
+        images1.extend(images2);
+        media1.extend(media2);
+        follows1.extend(follows2);
+        followed_by1.extend(followed_by2);
 
         Self {
-            ids,
-            names,
-            descriptions,
-            images: o1.images,
-            categories,
-            availability,
-            origins,
-            certifications,
-            manufacturers,
-            shopping,
-            media: o1.media,
-            follows: o1.follows,
-            followed_by: o1.followed_by,
-            transpaer,
+            ids: Combine::combine(ids1, ids2),
+            names: Combine::combine(names1, names2),
+            descriptions: Combine::combine(descriptions1, descriptions2),
+            summaries: Combine::combine(summaries1, summaries2),
+            images: images1,
+            categories: Combine::combine(categories1, categories2),
+            google_product_category: None,
+            ineligible_badges: BTreeSet::new(),
+            availability: Combine::combine(availability1, availability2),
+            origins: Combine::combine(origins1, origins2),
+            certifications: Combine::combine(certifications1, certifications2),
+            nutri_score: nutri_score1.or(nutri_score2),
+            price_levels: Combine::combine(price_levels1, price_levels2),
+            manufacturers: Combine::combine(manufacturers1, manufacturers2),
+            shopping: Combine::combine(shopping1, shopping2),
+            media: media1,
+            reports: Combine::combine(reports1, reports2),
+            follows: follows1,
+            followed_by: followed_by1,
+            sitelinks: 0,
+            updated: Combine::combine(updated1, updated2),
+            transpaer: TranspaerProductData::default(),
         }
     }
 }
@@ -1542,12 +2506,18 @@ pub struct StoreProduct {
     /// Descriptions of the product.
     pub descriptions: Vec<Text>,
 
+    /// Short summaries of the product written by the review sources.
+    pub summaries: Vec<Text>,
+
     /// Product images.
     pub images: Vec<Image>,
 
     /// Product categories.
     pub categories: Vec<Text>,
 
+    /// ID of the Google Product Category closest to the product categories.
+    pub google_product_category: Option<u32>,
+
     /// Regions where the product is available.
     pub availability: Availability,
 
@@ -1557,6 +2527,18 @@ pub struct StoreProduct {
     /// Known certifications.
     pub certifications: Certifications,
 
+    /// Badges the product can never be given because of its categories.
+    ///
+    /// Computed during crystalization from the product categories.
+    #[serde(default)]
+    pub ineligible_badges: Vec<Badge>,
+
+    /// Nutri-Score grade.
+    pub nutri_score: Option<NutriScore>,
+
+    /// Price tiers in different regions.
+    pub price_levels: PriceLevels,
+
     /// DB IDs of manufacturers.
     pub manufacturers: Vec<SourcedOrganisationId>,
 
@@ -1566,26 +2548,39 @@ pub struct StoreProduct {
     /// Mentions in media.
     pub media: Vec<Medium>,
 
+    /// Links to external reports about the product.
+    #[serde(default)]
+    pub reports: Vec<SourcedReport>,
+
     /// Wikidata IDs newer version products.
     pub follows: Vec<ids::ProductId>,
 
     /// Wikidata IDs older version products.
     pub followed_by: Vec<ids::ProductId>,
 
+    /// Number of sitelinks of the Wikidata items of the product.
+    #[serde(default)]
+    pub sitelinks: u32,
+
     /// The Transpaer data.
     pub transpaer: TranspaerProductData,
 }
 
+impl StoreProduct {
+    /// Returns the net quantity found in the first name mentioning one.
+    pub fn quantity(&self) -> Option<ProductQuantity> {
+        self.names.iter().find_map(|name| utils::parse_quantity(&name.text))
+    }
+}
+
 #[cfg(feature = "into-api")]
 impl StoreProduct {
     pub fn into_api_short(self) -> api::ProductShort {
-        api::ProductShort {
-            product_ids: self.ids.to_api(),
-            name: self.names.first().map_or_else(default_short_string, text_to_short_string),
-            description: self.descriptions.first().map(text_to_long_text),
-            badges: self.certifications.to_api_badges(),
-            scores: self.certifications.to_api_scores(),
-        }
+        lenient(self.convert_short(ConversionMode::Lenient))
+    }
+
+    pub fn try_into_api_short(self) -> Result<api::ProductShort, ApiConversionError> {
+        self.convert_short(ConversionMode::Strict)
     }
 
     pub fn into_api_full(
@@ -1593,28 +2588,88 @@ impl StoreProduct {
         manufacturers: Vec<api::OrganisationShort>,
         alternatives: Vec<api::CategoryAlternatives>,
     ) -> api::ProductFull {
-        let mut medallions = self.certifications.into_api_medallions();
-        medallions.push(self.transpaer.score.into_api_medallion());
+        lenient(self.convert_full(manufacturers, alternatives, ConversionMode::Lenient))
+    }
 
-        api::ProductFull {
+    pub fn try_into_api_full(
+        self,
+        manufacturers: Vec<api::OrganisationShort>,
+        alternatives: Vec<api::CategoryAlternatives>,
+    ) -> Result<api::ProductFull, ApiConversionError> {
+        self.convert_full(manufacturers, alternatives, ConversionMode::Strict)
+    }
+
+    fn convert_short(self, mode: ConversionMode) -> Result<api::ProductShort, ApiConversionError> {
+        Ok(api::ProductShort {
+            name: first_to_short_string(&self.names, mode)?,
+            // The summaries are written by the reviewers for a quick overview, so they are
+            // preferred over the general descriptions.
+            description: longest_to_long_text(
+                if self.summaries.is_empty() { &self.descriptions } else { &self.summaries },
+                mode,
+            )?,
+            badges: self.certifications.to_api_badges(),
+            // TODO: Add the Nutri-Score once the API spec defines a scorer name for it.
+            // TODO: Add the price levels once the API spec defines a field for them.
+            scores: self.certifications.to_api_scores(),
             product_ids: self.ids.to_api(),
-            names: self.names.into_iter().map(|n| n.into_api_short()).collect(),
-            descriptions: self.descriptions.into_iter().map(|d| d.into_api_long()).collect(),
+        })
+    }
+
+    fn convert_full(
+        self,
+        manufacturers: Vec<api::OrganisationShort>,
+        alternatives: Vec<api::CategoryAlternatives>,
+        mode: ConversionMode,
+    ) -> Result<api::ProductFull, ApiConversionError> {
+        // TODO: Present the badges the product is not eligible for separately from the missing
+        // ones once the API spec defines a medallion state for it.
+        let mut medallions = self.certifications.convert_medallions(mode)?;
+        medallions.push(self.transpaer.score.convert_medallion()?);
+
+        // TODO: Add the Google Product Category once the API spec defines a field for it.
+        // TODO: Add the categories with their labels once the API spec defines a field for them.
+        Ok(api::ProductFull {
+            product_ids: self.ids.to_api(),
+            names: self.names.iter().map(|n| n.convert_short(mode)).collect::<Result<_, _>>()?,
+            descriptions: self
+                .descriptions
+                .iter()
+                .chain(&self.summaries)
+                .map(|d| d.convert_long(mode))
+                .collect::<Result<_, _>>()?,
             images: self.images.into_iter().map(|i| i.into_api()).collect(),
             origins: self.origins.into_iter().map(country_code_to_region_code).collect(),
-            shopping: self.shopping.into_iter().map(|l| l.into_api()).collect(),
-            media: self.media.into_iter().map(|m| m.into_api()).collect(),
+            shopping: self
+                .shopping
+                .into_iter()
+                .map(|l| l.convert(mode))
+                .collect::<Result<_, _>>()?,
+            media: self
+                .media
+                .into_iter()
+                .map(|m| m.into_api())
+                .chain(SourcedReport::into_api_media(self.reports))
+                .collect(),
             manufacturers,
             alternatives,
             medallions,
-        }
+        })
     }
 
     pub fn score(&self) -> f64 {
-        0.0 + 0.9 * self.certifications.bcorp.is_some() as u32 as f64
-            + 0.9 * self.certifications.eu_ecolabel.is_some() as u32 as f64
-            + 0.6 * self.certifications.fti.as_ref().map(|c| 0.01 * c.score as f64).unwrap_or(0.0)
-            + 0.3 * self.certifications.tco.is_some() as u32 as f64
+        self.certifications.score()
+    }
+
+    /// Tells whether the product holds, misses or can never hold the given badge.
+    pub fn badge_status(&self, badge: Badge) -> BadgeStatus {
+        if self.certifications.has_badge(badge) {
+            BadgeStatus::Given
+        } else if self.ineligible_badges.contains(&badge) {
+            BadgeStatus::NotEligible
+        } else {
+            BadgeStatus::Missing
+        }
     }
 }
 
@@ -1649,11 +2704,31 @@ pub struct Category {
     /// List of subcategories.
     pub subcategories: Vec<String>,
 
+    /// Human-readable name of the category from the category taxonomy.
+    ///
+    /// Empty if the taxonomy does not define one.
+    #[serde(default)]
+    pub label: String,
+
     /// List of products in this categories.
     ///
     /// If `None`, the the category does not need products, e.g. it's a very broad category
     /// and product comparisons don't make sense.
     pub products: Option<Vec<ids::ProductId>>,
+
+    /// Typical net quantity of the products, i.e. the median in the most common unit.
+    ///
+    /// Used as a hint to prefer alternatives of a similar size when the compared product does not
+    /// mention its own quantity.
+    pub quantity: Option<ProductQuantity>,
+}
+
+impl Category {
+    /// Returns the human-readable name falling back to the passed raw name.
+    #[must_use]
+    pub fn label_or<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.label.is_empty() { name } else { &self.label }
+    }
 }
 
 /// One enttry in `PresentationData::Scored`.
@@ -1744,6 +2819,53 @@ pub struct LibraryItem {
     pub links: Vec<ReferenceLink>,
 }
 
+/// Provenance of a substrate file the database was crystalized from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubstrateMeta {
+    /// Size of the substrate file in bytes.
+    pub file_size: u64,
+
+    /// Creation time recorded in the meta block of the substrate in seconds since the Unix epoch.
+    pub created: Option<u64>,
+
+    /// Version of the tool which created the substrate, as recorded in its meta block.
+    pub version: Option<String>,
+
+    /// Number of producers in the substrate.
+    pub num_producers: usize,
+
+    /// Number of products in the substrate.
+    pub num_products: usize,
+
+    /// Version of the lab which crystalized the substrate.
+    pub crystalizer_version: String,
+}
+
+/// Digest of the contents of a database bucket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BucketDigest {
+    /// Number of entries in the bucket.
+    pub entries: u64,
+
+    /// MD5 hash of the encoded entries in the order of their keys.
+    pub hash: String,
+}
+
+/// Lookups of a GTIN missing from the dataset.
+///
+/// Collected to show the curators which products the users look for the most.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MissedLookup {
+    /// Number of recorded lookups.
+    pub count: u64,
+
+    /// Time of the first recorded lookup in seconds since the Unix epoch.
+    pub first_seen: u64,
+
+    /// Time of the last recorded lookup in seconds since the Unix epoch.
+    pub last_seen: u64,
+}
+
 #[cfg(feature = "into-api")]
 impl LibraryItem {
     pub fn into_api_short(self) -> api::LibraryItemShort {
@@ -1826,6 +2948,69 @@ mod tests {
         assert_eq!(deserialized_presentation, original_presentation);
     }
 
+    #[test]
+    fn certifications_inherit_keeps_own() {
+        let organisation = Certifications {
+            bcorp: Some(BCorpCert {
+                id: "producer".to_owned(),
+                report_url: String::new(),
+                inherited: false,
+            }),
+            fti: Some(FtiCert { score: 10, scale: FtiCert::SCALE, inherited: false }),
+            tco: Some(TcoCert { brand_name: "producer".to_owned(), inherited: false }),
+            ..Certifications::default()
+        };
+
+        let mut product = Certifications {
+            bcorp: Some(BCorpCert {
+                id: "product".to_owned(),
+                report_url: String::new(),
+                inherited: false,
+            }),
+            tco: Some(TcoCert { brand_name: "product".to_owned(), inherited: false }),
+            ..Certifications::default()
+        };
+        product.inherit(&organisation);
+
+        assert!(product.bcorp.as_ref().is_some_and(|c| c.id == "product" && !c.inherited));
+        assert!(product.tco.as_ref().is_some_and(|c| c.brand_name == "product" && !c.inherited));
+        assert!(product.fti.as_ref().is_some_and(|c| c.score == 10 && c.inherited));
+    }
+
+    #[test]
+    fn badge_eligibility_by_category() {
+        let food = maplit::btreeset! {
+            "food_beverages_and_tobacco".to_string(),
+            "food_beverages_and_tobacco/beverages".to_string(),
+        };
+        let electronics = maplit::btreeset! { "electronics".to_string() };
+        let unknown = maplit::btreeset! { "aaa".to_string() };
+
+        assert_eq!(
+            Badge::find_ineligible(&food),
+            maplit::btreeset! { Badge::EuEcolabel, Badge::Tco }
+        );
+        assert_eq!(Badge::find_ineligible(&electronics), BTreeSet::new());
+        assert_eq!(Badge::find_ineligible(&unknown), BTreeSet::new());
+
+        let product = GatherProduct {
+            ineligible_badges: Badge::find_ineligible(&food),
+            certifications: Certifications {
+                bcorp: Some(BCorpCert {
+                    id: "1".to_owned(),
+                    report_url: String::new(),
+                    inherited: true,
+                }),
+                ..Certifications::default()
+            },
+            ..GatherProduct::default()
+        }
+        .store();
+        assert_eq!(product.badge_status(Badge::BCorp), BadgeStatus::Given);
+        assert_eq!(product.badge_status(Badge::EuEcolabel), BadgeStatus::NotEligible);
+        assert_eq!(product.badge_status(Badge::Tco), BadgeStatus::NotEligible);
+    }
+
     #[test]
     fn products_all_categories() {
         let product = GatherProduct {
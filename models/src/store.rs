@@ -5,12 +5,15 @@
 pub use crate::{
     ids::{Ean, Gtin, OrganisationId, ProductId, VatId, WikiId},
     models::{
-        Availability, BCorpCert, Category, CategoryStatus, Certifications, Domain, EuEcolabelCert,
-        FtiCert, Image, LibraryItem, LibraryTopic, Medium, Mention, Presentation, PresentationData,
-        ReferenceLink, Regions, ScoredPresentationEntry, ShoppingEntry, Source, SourcedEan,
-        SourcedGtin, SourcedOrganisationId, SourcedWikiId, StoreOrganisation as Organisation,
+        Availability, BCorpCert, Badge, BadgeStatus, BucketDigest, Category, CategoryStatus,
+        Certifications, Domain, EuEcolabelCert, FtiCert, Image, LibraryItem, LibraryTopic,
+        MatchMethod, Medium, Mention, MissedLookup, NutriScore, OrganisationMatch, Presentation,
+        PresentationData, PriceLevels, PriceTier, ProductQuantity, ProductRef, QuantityUnit,
+        ReferenceLink, Regions, RetailerAvailability, ScoreScale, ScoredPresentationEntry,
+        SearchRank, ShoppingEntry, Source, SourcedEan, SourcedGtin, SourcedOrganisationId,
+        SourcedReport, SourcedWikiId, StoreOrganisation as Organisation,
         StoreOrganisationIds as OrganisationIds, StoreProduct as Product,
-        StoreProductIds as ProductIds, TcoCert, Text, TranspaerOrganisationData,
+        StoreProductIds as ProductIds, SubstrateMeta, TcoCert, Text, TranspaerOrganisationData,
         TranspaerProductData, TranspaerScore, TranspaerScoreBranch,
     },
 };
@@ -2,11 +2,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::collections::{HashMap, hash_map::Entry};
+use std::collections::{BTreeMap, BTreeSet, HashMap, hash_map::Entry};
 
 use crate::{
     gather::{Organisation, Product},
-    models::{Significance, Source},
+    ids::WikiId,
+    models::{MatchMethod, OrganisationMatch, SearchRank, Significance, Source},
+    utils::normalize_company_name,
 };
 
 pub fn calculate_product_significances(product: &Product) -> HashMap<Source, Significance> {
@@ -105,3 +107,82 @@ pub fn calculate_organisation_significances(
 
     result
 }
+
+/// Calculates the static factors ranking the organisation in text searches.
+///
+/// Expects the products and significances of the organisation to be already filled.
+pub fn calculate_organisation_rank(organisation: &Organisation) -> SearchRank {
+    let count = |num: usize| u32::try_from(num).unwrap_or(u32::MAX);
+
+    // TODO: Use the number of Wikidata sitelinks once the substrate schema can carry it.
+    let notability = count(organisation.transpaer.significance.len());
+
+    SearchRank {
+        num_products: count(organisation.products.len()),
+        num_certifications: count(organisation.certifications.get_num()),
+        notability,
+    }
+}
+
+/// Describes how entries from different sources were merged into the given organisation.
+///
+/// `manual_matches` maps Wiki IDs listed in the manual match file to the accuracy of the match.
+/// Sources sharing only a name are reported as matched by the name heuristic.
+pub fn calculate_organisation_matches(
+    organisation: &Organisation,
+    manual_matches: &HashMap<WikiId, f64>,
+) -> Vec<OrganisationMatch> {
+    #[derive(Default)]
+    struct MatchEntry {
+        sources: BTreeSet<Source>,
+        accuracy: Option<f64>,
+    }
+
+    let mut entries = BTreeMap::<MatchMethod, MatchEntry>::new();
+    let mut add = |method: MatchMethod, sources: &BTreeSet<Source>, accuracy: Option<f64>| {
+        if sources.len() > 1 {
+            let entry = entries.entry(method).or_default();
+            entry.sources.extend(sources.iter().cloned());
+            entry.accuracy = match (entry.accuracy, accuracy) {
+                (Some(a1), Some(a2)) => Some(a1.max(a2)),
+                (a1, a2) => a1.or(a2),
+            };
+        }
+    };
+
+    for (_, sources) in organisation.ids.vat_ids.iter() {
+        add(MatchMethod::Vat, sources, None);
+    }
+    for (_, sources) in organisation.ids.domains.iter() {
+        add(MatchMethod::Domain, sources, None);
+    }
+    for (wiki_id, sources) in organisation.ids.wiki.iter() {
+        match manual_matches.get(wiki_id) {
+            Some(accuracy) if sources.contains(&Source::EuEcolabel) => {
+                add(MatchMethod::ManualFile, sources, Some(*accuracy));
+            }
+            _ => add(MatchMethod::Wiki, sources, None),
+        }
+    }
+
+    let matched: BTreeSet<Source> =
+        entries.values().flat_map(|entry| entry.sources.iter().cloned()).collect();
+    let mut names = BTreeMap::<String, BTreeSet<Source>>::new();
+    for (name, sources) in organisation.names.iter() {
+        names.entry(normalize_company_name(name)).or_default().extend(sources.iter().cloned());
+    }
+    for sources in names.values() {
+        if !sources.is_subset(&matched) {
+            add(MatchMethod::NameHeuristic, sources, None);
+        }
+    }
+
+    entries
+        .into_iter()
+        .map(|(method, entry)| OrganisationMatch {
+            method,
+            sources: entry.sources,
+            accuracy: entry.accuracy,
+        })
+        .collect()
+}
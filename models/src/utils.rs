@@ -4,6 +4,8 @@
 
 use std::collections::HashSet;
 
+use crate::models::{ProductQuantity, QuantityUnit};
+
 /// Extracts domain from a URL.
 #[must_use]
 pub fn extract_domain_from_url(url: &str) -> String {
@@ -61,6 +63,238 @@ where
     result
 }
 
+/// Legal-form suffixes ignored when comparing company names.
+const LEGAL_FORMS: &[&str] = &[
+    "ab",
+    "ag",
+    "bv",
+    "co",
+    "company",
+    "corp",
+    "corporation",
+    "gmbh",
+    "inc",
+    "limited",
+    "llc",
+    "ltd",
+    "nv",
+    "oy",
+    "plc",
+    "sa",
+    "sas",
+    "spa",
+];
+
+/// Prepares a company or brand name for easy comparison.
+///
+/// - lower-cases the name
+/// - removes punctuation
+/// - removes trailing legal-form suffixes like "Inc." or "GmbH"
+#[must_use]
+pub fn normalize_company_name(name: &str) -> String {
+    let name = name.to_lowercase().replace(['.', ',', '\'', '’'], "");
+    let mut words: Vec<&str> =
+        name.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect();
+    while words.len() > 1 && words.last().is_some_and(|word| LEGAL_FORMS.contains(word)) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+/// Splits a text into lower-case search tokens.
+///
+/// Used both for building the keyword index and for parsing search queries.
+#[must_use]
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_lowercase).collect()
+}
+
+/// Splits a text into lower-case words ignoring punctuation.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Common words of the languages recognised by `detect_language`.
+const LANGUAGE_STOPWORDS: &[(&str, &[&str])] = &[
+    ("de", &["der", "die", "und", "das", "ist", "ein", "eine", "mit", "von", "für"]),
+    ("en", &["the", "and", "of", "is", "in", "to", "for", "with", "that", "from"]),
+    ("es", &["el", "los", "las", "y", "es", "una", "del", "para", "con", "por"]),
+    ("fr", &["le", "les", "et", "est", "une", "des", "du", "pour", "dans", "avec"]),
+    ("it", &["il", "di", "e", "è", "una", "per", "con", "che", "gli", "della"]),
+    ("nl", &["het", "en", "van", "een", "is", "met", "voor", "op", "zijn", "bedrijf"]),
+];
+
+/// Minimal number of common words needed to recognise a language.
+const MIN_LANGUAGE_HITS: usize = 2;
+
+/// Guesses the language of a text from the common words it contains.
+///
+/// Returns the ISO 639-1 code or `None` if the text is too short or ambiguous.
+#[must_use]
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let words = words(text);
+    let mut best = None;
+    let mut best_hits = 0;
+    for (language, stopwords) in LANGUAGE_STOPWORDS {
+        let hits = words.iter().filter(|word| stopwords.contains(&word.as_str())).count();
+        if hits > best_hits {
+            best = Some(*language);
+            best_hits = hits;
+        } else if hits == best_hits {
+            best = None;
+        }
+    }
+    if best_hits < MIN_LANGUAGE_HITS { None } else { best }
+}
+
+/// Prefix of the media fragment (https://www.w3.org/TR/media-frags/) pointing to a moment in time.
+const TIMESTAMP_FRAGMENT: &str = "#t=";
+
+/// Splits a link into the part without the time media fragment and the timestamp in seconds.
+#[must_use]
+pub fn split_timestamp(link: &str) -> (&str, Option<u32>) {
+    if let Some((base, timestamp)) = link.rsplit_once(TIMESTAMP_FRAGMENT)
+        && let Ok(timestamp) = timestamp.parse()
+    {
+        (base, Some(timestamp))
+    } else {
+        (link, None)
+    }
+}
+
+/// Appends the time media fragment to the link so that players can start at the given moment.
+#[must_use]
+pub fn with_timestamp(link: &str, timestamp: Option<u32>) -> String {
+    match timestamp {
+        Some(timestamp) => format!("{link}{TIMESTAMP_FRAGMENT}{timestamp}"),
+        None => link.to_owned(),
+    }
+}
+
+/// Calculates the Jaccard similarity (from 0.0 to 1.0) of the sets of words of two texts.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn word_similarity(text1: &str, text2: &str) -> f64 {
+    let words1: HashSet<String> = words(text1).into_iter().collect();
+    let words2: HashSet<String> = words(text2).into_iter().collect();
+    let union = words1.union(&words2).count();
+    if union == 0 {
+        return 1.0;
+    }
+    words1.intersection(&words2).count() as f64 / union as f64
+}
+
+/// Appended to truncated texts.
+pub const ELLIPSIS: char = '…';
+
+/// Truncates the text to the longest prefix of whole graphemes which, followed by an ellipsis,
+/// is accepted by `fits`.
+///
+/// Returns `None` if not even the ellipsis alone is accepted.
+pub fn truncate_graphemes<T>(text: &str, fits: impl Fn(&str) -> Option<T>) -> Option<T> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let ends: Vec<usize> = text.grapheme_indices(true).map(|(start, _)| start).collect();
+    let prefix = |len: usize| {
+        let prefix = ends.get(len).map_or(text, |end| &text[..*end]);
+        fits(&format!("{}{ELLIPSIS}", prefix.trim_end()))
+    };
+
+    // Longer prefixes never fit if a shorter one does not, so the longest one can be bisected.
+    let mut best = prefix(0)?;
+    let (mut low, mut high) = (1, ends.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if let Some(fitting) = prefix(mid) {
+            best = fitting;
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Some(best)
+}
+
+/// Units recognised by `parse_quantity` with their factors to the base units.
+const QUANTITY_UNITS: &[(&str, f64, QuantityUnit)] = &[
+    ("mg", 0.000_001, QuantityUnit::Kilogram),
+    ("g", 0.001, QuantityUnit::Kilogram),
+    ("kg", 1.0, QuantityUnit::Kilogram),
+    ("ml", 0.001, QuantityUnit::Litre),
+    ("cl", 0.01, QuantityUnit::Litre),
+    ("dl", 0.1, QuantityUnit::Litre),
+    ("l", 1.0, QuantityUnit::Litre),
+    ("pcs", 1.0, QuantityUnit::Piece),
+    ("pieces", 1.0, QuantityUnit::Piece),
+];
+
+/// Token of a text being searched for a quantity.
+#[derive(Debug, Clone, PartialEq)]
+enum QuantityToken {
+    Number(f64),
+    Times,
+    Word(String),
+}
+
+/// Splits the text into numbers, multiplication signs and words.
+fn quantity_tokens(text: &str) -> Vec<QuantityToken> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.' || *c == ',') {
+                number.push(if c == ',' { '.' } else { c });
+            }
+            tokens.push(match number.parse() {
+                Ok(number) => QuantityToken::Number(number),
+                Err(_) => QuantityToken::Word(number),
+            });
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+                word.push(c);
+            }
+            tokens.push(if word == "x" { QuantityToken::Times } else { QuantityToken::Word(word) });
+        } else {
+            if c == '×' {
+                tokens.push(QuantityToken::Times);
+            }
+            chars.next();
+        }
+    }
+    tokens
+}
+
+/// Finds the first net quantity mentioned in the text, e.g. `500 g`, `1,5l` or `6 x 330 ml`.
+///
+/// Multipacks are summed up. Units are matched case-insensitively except for grams so that
+/// mobile network generations (e.g. `5G`) are not taken for masses.
+#[must_use]
+pub fn parse_quantity(text: &str) -> Option<ProductQuantity> {
+    let tokens = quantity_tokens(text);
+    for (i, token) in tokens.iter().enumerate() {
+        let QuantityToken::Number(count) = token else { continue };
+        let (amount, rest) = match &tokens[i + 1..] {
+            [QuantityToken::Times, QuantityToken::Number(size), rest @ ..] => (count * size, rest),
+            rest => (*count, rest),
+        };
+        if let [QuantityToken::Word(word), ..] = rest
+            && let Some((_, factor, unit)) = QUANTITY_UNITS.iter().find(|(name, _, _)| {
+                if *name == "g" { word == "g" } else { word.eq_ignore_ascii_case(name) }
+            })
+            && amount > 0.0
+        {
+            return Some(ProductQuantity { amount: amount * factor, unit: *unit });
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashSet;
@@ -100,6 +334,73 @@ mod tests {
         assert_eq!(extract_domain_from_str("http://notadomain").unwrap(), "notadomain");
     }
 
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("  Fairphone 4\tDual-SIM "), vec!["fairphone", "4", "dual-sim"]);
+        assert!(tokenize(" ").is_empty());
+    }
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("Het is the best"), None);
+        assert_eq!(
+            detect_language("The phone of the future is made in the Netherlands"),
+            Some("en")
+        );
+        assert_eq!(detect_language("Das Unternehmen ist ein Hersteller von Telefonen"), Some("de"));
+        assert_eq!(
+            detect_language("Een bedrijf dat telefoons maakt met een focus op reparatie"),
+            Some("nl")
+        );
+        assert_eq!(detect_language("Dutch electronics company"), None);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let link = "https://example.com/episode-12.mp3";
+        assert_eq!(split_timestamp(link), (link, None));
+        assert_eq!(with_timestamp(link, None), link);
+        assert_eq!(with_timestamp(link, Some(754)), "https://example.com/episode-12.mp3#t=754");
+        assert_eq!(split_timestamp(&with_timestamp(link, Some(754))), (link, Some(754)));
+        assert_eq!(split_timestamp("https://example.com/#t=x"), ("https://example.com/#t=x", None));
+    }
+
+    #[test]
+    fn test_parse_quantity() {
+        let q = |amount, unit| Some(ProductQuantity { amount, unit });
+        assert_eq!(parse_quantity("Spaghetti 500 g"), q(0.5, QuantityUnit::Kilogram));
+        assert_eq!(parse_quantity("Olive oil 0,75L"), q(0.75, QuantityUnit::Litre));
+        assert_eq!(parse_quantity("Cola 6 x 250 ml"), q(1.5, QuantityUnit::Litre));
+        assert_eq!(parse_quantity("Tea bags, 20 pcs"), q(20.0, QuantityUnit::Piece));
+        assert_eq!(parse_quantity("Galaxy S20 5G"), None);
+        assert_eq!(parse_quantity("Fairphone 5"), None);
+        assert_eq!(parse_quantity("Water 0 l"), None);
+    }
+
+    #[test]
+    fn test_word_similarity() {
+        assert!(
+            (word_similarity("Dutch phone maker", "dutch  Phone maker.") - 1.0).abs()
+                < f64::EPSILON
+        );
+        assert!(
+            (word_similarity("Dutch phone maker", "Dutch phone company") - 0.5).abs()
+                < f64::EPSILON
+        );
+        assert!(word_similarity("Dutch phone maker", "Clothing brand").abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_normalize_company_name() {
+        assert_eq!(normalize_company_name("Fairphone"), "fairphone");
+        assert_eq!(normalize_company_name("  H&M Hennes & Mauritz AB "), "h m hennes mauritz");
+        assert_eq!(normalize_company_name("Levi Strauss & Co."), "levi strauss");
+        assert_eq!(normalize_company_name("Patagonia, Inc."), "patagonia");
+        assert_eq!(normalize_company_name("Marc O'Polo"), "marc opolo");
+        assert_eq!(normalize_company_name("Company"), "company");
+        assert_eq!(normalize_company_name("---"), "");
+    }
+
     #[test]
     fn test_extract_domains_from_urls_vec() {
         let input = vec!["www.example.com", "http://www.example.com", "example2.com"];
@@ -114,4 +415,18 @@ mod tests {
         let output: HashSet<String> = ["example.com".into(), "example2.com".into()].into();
         assert_eq!(extract_domains_from_urls(&input), output);
     }
+
+    #[test]
+    fn test_truncate_graphemes() {
+        let at_most = |max: usize| move |s: &str| (s.chars().count() <= max).then(|| s.to_owned());
+
+        assert_eq!(truncate_graphemes("Hello wonderful world", at_most(8)).unwrap(), "Hello w…");
+        assert_eq!(truncate_graphemes("Hello wonderful world", at_most(7)).unwrap(), "Hello…");
+        assert_eq!(truncate_graphemes("Hello", at_most(1)).unwrap(), "…");
+        assert_eq!(truncate_graphemes("Hello", at_most(0)), None);
+
+        // Combining accents are not separated from their letters.
+        let text = "e\u{301}e\u{301}e\u{301}";
+        assert_eq!(truncate_graphemes(text, at_most(4)).unwrap(), "e\u{301}…");
+    }
 }
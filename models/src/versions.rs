@@ -0,0 +1,400 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Versioned encoding of the stored models.
+//!
+//! `postcard` is not self-describing: fields are decoded by their position, so adding, removing
+//! or reordering a field of a stored type makes the existing entries unreadable, or worse, read
+//! as garbage. To avoid that, every stored type is encoded prefixed with the index of its version
+//! and decoded through an enum listing all its known versions.
+//!
+//! To change a stored type:
+//!
+//! 1. freeze a copy of its current layout in this module (e.g. `ProductV1`),
+//! 2. point the previous variant of the versions enum at the frozen copy and append a new variant
+//!    for the changed type,
+//! 3. bump `Evolving::LATEST` to the index of the new variant,
+//! 4. implement the migration from the frozen copy to the changed type,
+//! 5. add a golden fixture of the new version to `tests/versions.rs`.
+//!
+//! The same applies when changing a type nested in a stored type.
+//!
+//! Databases written before the versioning was introduced have no version prefix and have to be
+//! crystalized again.
+
+use serde::{Serialize, Serializer, de::DeserializeOwned};
+
+use crate::{ids, models, store};
+
+/// Type stored in a database, possibly in one of its older versions.
+pub trait Evolving: Serialize + Sized {
+    /// All known versions of the type, the latest one being the type itself.
+    type Versions: DeserializeOwned + Into<Self>;
+
+    /// Index of the latest variant in `Versions`.
+    const LATEST: u32;
+}
+
+/// Serializes a value the same way as the latest variant of its `Versions` enum.
+struct Latest<'a, V>(&'a V);
+
+impl<V: Evolving> Serialize for Latest<'_, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_variant("Versions", V::LATEST, "Latest", self.0)
+    }
+}
+
+/// Encodes the value as its latest version.
+///
+/// # Errors
+///
+/// Returns `Err` if the value could not be serialized.
+pub fn encode<V: Evolving>(value: &V) -> Result<Vec<u8>, postcard::Error> {
+    postcard::to_stdvec(&Latest(value))
+}
+
+/// Decodes a value encoded in any of its known versions and migrates it to the latest one.
+///
+/// # Errors
+///
+/// Returns `Err` if the data does not represent any known version of the value.
+pub fn decode<V: Evolving>(data: &[u8]) -> Result<V, postcard::Error> {
+    Ok(postcard::from_bytes::<V::Versions>(data)?.into())
+}
+
+/// Implements `Evolving` for a type which has only one version so far.
+macro_rules! single_version {
+    ($versions:ident, $stored:ty) => {
+        #[derive(serde::Deserialize)]
+        pub enum $versions {
+            V1($stored),
+        }
+
+        impl From<$versions> for $stored {
+            fn from(versions: $versions) -> Self {
+                match versions {
+                    $versions::V1(value) => value,
+                }
+            }
+        }
+
+        impl Evolving for $stored {
+            type Versions = $versions;
+            const LATEST: u32 = 0;
+        }
+    };
+}
+
+single_version!(CategoryVersions, store::Category);
+single_version!(LibraryItemVersions, store::LibraryItem);
+single_version!(PresentationVersions, store::Presentation);
+single_version!(RetailerAvailabilityVersions, Vec<store::RetailerAvailability>);
+single_version!(SubstrateMetaVersions, store::SubstrateMeta);
+single_version!(BucketDigestVersions, store::BucketDigest);
+single_version!(MissedLookupVersions, store::MissedLookup);
+
+/// Known versions of `store::Organisation`.
+#[derive(serde::Deserialize)]
+pub enum OrganisationVersions {
+    /// Before the search rank was recorded.
+    V1(OrganisationV1),
+
+    /// Current version.
+    V2(store::Organisation),
+}
+
+impl From<OrganisationVersions> for store::Organisation {
+    fn from(versions: OrganisationVersions) -> Self {
+        match versions {
+            OrganisationVersions::V1(value) => value.into(),
+            OrganisationVersions::V2(value) => value,
+        }
+    }
+}
+
+impl Evolving for store::Organisation {
+    type Versions = OrganisationVersions;
+    const LATEST: u32 = 1;
+}
+
+/// Layout of `store::TranspaerOrganisationData` before the search rank was recorded.
+#[derive(serde::Deserialize)]
+pub struct TranspaerOrganisationDataV1 {
+    pub significance: std::collections::HashMap<store::Source, models::Significance>,
+    pub matches: Vec<store::OrganisationMatch>,
+}
+
+/// Layout of `store::Organisation` before the search rank was recorded.
+#[derive(serde::Deserialize)]
+pub struct OrganisationV1 {
+    pub ids: store::OrganisationIds,
+    pub names: Vec<store::Text>,
+    pub descriptions: Vec<store::Text>,
+    pub images: Vec<store::Image>,
+    pub websites: Vec<models::Website>,
+    pub origins: Vec<models::Country>,
+    pub products: Vec<ids::ProductId>,
+    pub certifications: store::Certifications,
+    pub media: Vec<store::Medium>,
+    pub transpaer: TranspaerOrganisationDataV1,
+}
+
+impl From<OrganisationV1> for store::Organisation {
+    fn from(organisation: OrganisationV1) -> Self {
+        Self {
+            ids: organisation.ids,
+            names: organisation.names,
+            descriptions: organisation.descriptions,
+            images: organisation.images,
+            websites: organisation.websites,
+            origins: organisation.origins,
+            products: organisation.products,
+            certifications: organisation.certifications,
+            media: organisation.media,
+            transpaer: store::TranspaerOrganisationData {
+                significance: organisation.transpaer.significance,
+                matches: organisation.transpaer.matches,
+                rank: store::SearchRank::default(),
+            },
+        }
+    }
+}
+
+/// Known versions of `store::Product`.
+#[derive(serde::Deserialize)]
+pub enum ProductVersions {
+    /// Before the ineligible badges were recorded.
+    V1(ProductV1),
+
+    /// Before the report links were recorded.
+    V2(ProductV2),
+
+    /// Before the summaries from the review sources were recorded.
+    V3(ProductV3),
+
+    /// Before the sitelink counts were recorded.
+    V4(ProductV4),
+
+    /// Current version.
+    V5(store::Product),
+}
+
+impl From<ProductVersions> for store::Product {
+    fn from(versions: ProductVersions) -> Self {
+        match versions {
+            ProductVersions::V1(value) => {
+                ProductV4::from(ProductV3::from(ProductV2::from(value))).into()
+            }
+            ProductVersions::V2(value) => ProductV4::from(ProductV3::from(value)).into(),
+            ProductVersions::V3(value) => ProductV4::from(value).into(),
+            ProductVersions::V4(value) => value.into(),
+            ProductVersions::V5(value) => value,
+        }
+    }
+}
+
+impl Evolving for store::Product {
+    type Versions = ProductVersions;
+    const LATEST: u32 = 4;
+}
+
+/// Layout of `store::Product` before the ineligible badges were recorded.
+#[derive(serde::Deserialize)]
+pub struct ProductV1 {
+    pub ids: store::ProductIds,
+    pub names: Vec<store::Text>,
+    pub descriptions: Vec<store::Text>,
+    pub images: Vec<store::Image>,
+    pub categories: Vec<store::Text>,
+    pub google_product_category: Option<u32>,
+    pub availability: store::Availability,
+    pub origins: Vec<models::Country>,
+    pub certifications: store::Certifications,
+    pub nutri_score: Option<store::NutriScore>,
+    pub price_levels: store::PriceLevels,
+    pub manufacturers: Vec<store::SourcedOrganisationId>,
+    pub shopping: Vec<store::ShoppingEntry>,
+    pub media: Vec<store::Medium>,
+    pub follows: Vec<ids::ProductId>,
+    pub followed_by: Vec<ids::ProductId>,
+    pub transpaer: store::TranspaerProductData,
+}
+
+impl From<ProductV1> for ProductV2 {
+    fn from(product: ProductV1) -> Self {
+        Self {
+            ids: product.ids,
+            names: product.names,
+            descriptions: product.descriptions,
+            images: product.images,
+            categories: product.categories,
+            google_product_category: product.google_product_category,
+            availability: product.availability,
+            origins: product.origins,
+            certifications: product.certifications,
+            ineligible_badges: Vec::new(),
+            nutri_score: product.nutri_score,
+            price_levels: product.price_levels,
+            manufacturers: product.manufacturers,
+            shopping: product.shopping,
+            media: product.media,
+            follows: product.follows,
+            followed_by: product.followed_by,
+            transpaer: product.transpaer,
+        }
+    }
+}
+
+/// Layout of `store::Product` before the report links were recorded.
+#[derive(serde::Deserialize)]
+pub struct ProductV2 {
+    pub ids: store::ProductIds,
+    pub names: Vec<store::Text>,
+    pub descriptions: Vec<store::Text>,
+    pub images: Vec<store::Image>,
+    pub categories: Vec<store::Text>,
+    pub google_product_category: Option<u32>,
+    pub availability: store::Availability,
+    pub origins: Vec<models::Country>,
+    pub certifications: store::Certifications,
+    pub ineligible_badges: Vec<store::Badge>,
+    pub nutri_score: Option<store::NutriScore>,
+    pub price_levels: store::PriceLevels,
+    pub manufacturers: Vec<store::SourcedOrganisationId>,
+    pub shopping: Vec<store::ShoppingEntry>,
+    pub media: Vec<store::Medium>,
+    pub follows: Vec<ids::ProductId>,
+    pub followed_by: Vec<ids::ProductId>,
+    pub transpaer: store::TranspaerProductData,
+}
+
+impl From<ProductV2> for ProductV3 {
+    fn from(product: ProductV2) -> Self {
+        Self {
+            ids: product.ids,
+            names: product.names,
+            descriptions: product.descriptions,
+            images: product.images,
+            categories: product.categories,
+            google_product_category: product.google_product_category,
+            availability: product.availability,
+            origins: product.origins,
+            certifications: product.certifications,
+            ineligible_badges: product.ineligible_badges,
+            nutri_score: product.nutri_score,
+            price_levels: product.price_levels,
+            manufacturers: product.manufacturers,
+            shopping: product.shopping,
+            media: product.media,
+            reports: Vec::new(),
+            follows: product.follows,
+            followed_by: product.followed_by,
+            transpaer: product.transpaer,
+        }
+    }
+}
+
+/// Layout of `store::Product` before the summaries from the review sources were recorded.
+#[derive(serde::Deserialize)]
+pub struct ProductV3 {
+    pub ids: store::ProductIds,
+    pub names: Vec<store::Text>,
+    pub descriptions: Vec<store::Text>,
+    pub images: Vec<store::Image>,
+    pub categories: Vec<store::Text>,
+    pub google_product_category: Option<u32>,
+    pub availability: store::Availability,
+    pub origins: Vec<models::Country>,
+    pub certifications: store::Certifications,
+    pub ineligible_badges: Vec<store::Badge>,
+    pub nutri_score: Option<store::NutriScore>,
+    pub price_levels: store::PriceLevels,
+    pub manufacturers: Vec<store::SourcedOrganisationId>,
+    pub shopping: Vec<store::ShoppingEntry>,
+    pub media: Vec<store::Medium>,
+    pub reports: Vec<store::SourcedReport>,
+    pub follows: Vec<ids::ProductId>,
+    pub followed_by: Vec<ids::ProductId>,
+    pub transpaer: store::TranspaerProductData,
+}
+
+impl From<ProductV3> for ProductV4 {
+    fn from(product: ProductV3) -> Self {
+        Self {
+            ids: product.ids,
+            names: product.names,
+            descriptions: product.descriptions,
+            summaries: Vec::new(),
+            images: product.images,
+            categories: product.categories,
+            google_product_category: product.google_product_category,
+            availability: product.availability,
+            origins: product.origins,
+            certifications: product.certifications,
+            ineligible_badges: product.ineligible_badges,
+            nutri_score: product.nutri_score,
+            price_levels: product.price_levels,
+            manufacturers: product.manufacturers,
+            shopping: product.shopping,
+            media: product.media,
+            reports: product.reports,
+            follows: product.follows,
+            followed_by: product.followed_by,
+            transpaer: product.transpaer,
+        }
+    }
+}
+
+/// Layout of `store::Product` before the sitelink counts were recorded.
+#[derive(serde::Deserialize)]
+pub struct ProductV4 {
+    pub ids: store::ProductIds,
+    pub names: Vec<store::Text>,
+    pub descriptions: Vec<store::Text>,
+    pub summaries: Vec<store::Text>,
+    pub images: Vec<store::Image>,
+    pub categories: Vec<store::Text>,
+    pub google_product_category: Option<u32>,
+    pub availability: store::Availability,
+    pub origins: Vec<models::Country>,
+    pub certifications: store::Certifications,
+    pub ineligible_badges: Vec<store::Badge>,
+    pub nutri_score: Option<store::NutriScore>,
+    pub price_levels: store::PriceLevels,
+    pub manufacturers: Vec<store::SourcedOrganisationId>,
+    pub shopping: Vec<store::ShoppingEntry>,
+    pub media: Vec<store::Medium>,
+    pub reports: Vec<store::SourcedReport>,
+    pub follows: Vec<ids::ProductId>,
+    pub followed_by: Vec<ids::ProductId>,
+    pub transpaer: store::TranspaerProductData,
+}
+
+impl From<ProductV4> for store::Product {
+    fn from(product: ProductV4) -> Self {
+        Self {
+            ids: product.ids,
+            names: product.names,
+            descriptions: product.descriptions,
+            summaries: product.summaries,
+            images: product.images,
+            categories: product.categories,
+            google_product_category: product.google_product_category,
+            availability: product.availability,
+            origins: product.origins,
+            certifications: product.certifications,
+            ineligible_badges: product.ineligible_badges,
+            nutri_score: product.nutri_score,
+            price_levels: product.price_levels,
+            manufacturers: product.manufacturers,
+            shopping: product.shopping,
+            media: product.media,
+            reports: product.reports,
+            follows: product.follows,
+            followed_by: product.followed_by,
+            sitelinks: 0,
+            transpaer: product.transpaer,
+        }
+    }
+}
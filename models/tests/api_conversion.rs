@@ -0,0 +1,322 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for conversions from the database models into the API models.
+
+use transpaer_api::models as api;
+use transpaer_models::{
+    ids,
+    models::{
+        ApiConversionError, Country, Medium, Mention, NutriScore, ShoppingEntry, Vat, VerifiedShop,
+        Website,
+    },
+    store::{
+        Availability, BCorpCert, Certifications, EuEcolabelCert, FtiCert, Image, Organisation,
+        OrganisationIds, PriceLevels, Product, ProductIds, Source, SourcedEan, SourcedGtin,
+        SourcedOrganisationId, SourcedWikiId, TcoCert, Text, TranspaerOrganisationData,
+        TranspaerProductData, TranspaerScore, TranspaerScoreBranch,
+    },
+};
+
+fn certifications() -> Certifications {
+    Certifications {
+        bcorp: Some(BCorpCert {
+            id: "001C000001Dz6afIAB".to_owned(),
+            report_url: "https://www.bcorporation.net/en-us/find-a-b-corp/company/fairphone/"
+                .to_owned(),
+            inherited: false,
+        }),
+        eu_ecolabel: Some(EuEcolabelCert),
+        fti: Some(FtiCert { score: 42, scale: FtiCert::SCALE, inherited: false }),
+        tco: Some(TcoCert { brand_name: "FAIRPHONE".to_owned(), inherited: true }),
+    }
+}
+
+fn score() -> TranspaerScore {
+    use transpaer_models::models::TranspaerScoreCategory;
+
+    TranspaerScore {
+        tree: vec![TranspaerScoreBranch {
+            branches: vec![
+                TranspaerScoreBranch {
+                    branches: Vec::new(),
+                    category: TranspaerScoreCategory::ProducerKnown,
+                    weight: 2,
+                    score: 1.0,
+                    evidence: vec!["field:manufacturers".to_owned()],
+                },
+                TranspaerScoreBranch {
+                    branches: Vec::new(),
+                    category: TranspaerScoreCategory::IdKnown,
+                    weight: 1,
+                    score: 0.0,
+                    evidence: Vec::new(),
+                },
+            ],
+            category: TranspaerScoreCategory::DataAvailability,
+            weight: 1,
+            score: 0.66,
+            evidence: Vec::new(),
+        }],
+        total: 0.66,
+        scorer: "tree".to_owned(),
+    }
+}
+
+fn product() -> Product {
+    Product {
+        ids: ProductIds {
+            eans: vec![SourcedEan { id: ids::Ean::new(34), sources: vec![Source::Wikidata] }],
+            gtins: vec![SourcedGtin {
+                id: ids::Gtin::new(8_717_677_339_556),
+                sources: vec![Source::OpenFoodFacts],
+            }],
+            wiki: vec![SourcedWikiId::new(ids::WikiId::new(109_851_604), Source::Wikidata)],
+        },
+        names: vec![Text::new_many("Fairphone 4", vec![Source::Wikidata, Source::Transpaer])],
+        descriptions: vec![Text::new("smartphone", Source::Wikidata)],
+        summaries: Vec::new(),
+        images: vec![Image { image: "fairphone_4.jpg".to_owned(), source: Source::Wikidata }],
+        categories: vec![Text::new("electronics/communications", Source::Wikidata)],
+        google_product_category: Some(262),
+        availability: Availability::default(),
+        origins: vec![Country {
+            country: isocountry::CountryCode::NLD,
+            sources: vec![Source::Wikidata],
+        }],
+        certifications: certifications(),
+        ineligible_badges: Vec::new(),
+        nutri_score: Some(NutriScore::B),
+        price_levels: PriceLevels::default(),
+        manufacturers: vec![SourcedOrganisationId {
+            id: ids::OrganisationId::from_value(7),
+            sources: vec![Source::Wikidata],
+        }],
+        shopping: vec![ShoppingEntry {
+            id: "B09K3Y1CQ1".to_owned(),
+            shop: VerifiedShop::Amazon,
+            description: "Fairphone 4".to_owned(),
+            sources: vec![Source::Wikidata],
+        }],
+        media: Vec::new(),
+        reports: Vec::new(),
+        follows: Vec::new(),
+        followed_by: Vec::new(),
+        sitelinks: 0,
+        transpaer: TranspaerProductData { score: score(), ..Default::default() },
+    }
+}
+
+fn organisation() -> Organisation {
+    Organisation {
+        ids: OrganisationIds {
+            wiki: vec![SourcedWikiId::new(ids::WikiId::new(5_019_402), Source::Wikidata)],
+            vat_ids: vec![Vat {
+                id: ids::VatId::try_from("NL004495445B01").unwrap(),
+                sources: vec![Source::Wikidata],
+            }],
+            domains: vec![Website {
+                website: "fairphone.com".to_owned(),
+                sources: vec![Source::Wikidata],
+            }],
+        },
+        names: vec![Text::new("Fairphone", Source::BCorp)],
+        descriptions: vec![Text::new("social enterprise", Source::BCorp)],
+        images: Vec::new(),
+        websites: vec![Website {
+            website: "www.fairphone.com".to_owned(),
+            sources: vec![Source::Wikidata],
+        }],
+        origins: vec![Country {
+            country: isocountry::CountryCode::NLD,
+            sources: vec![Source::Wikidata],
+        }],
+        products: vec![ids::ProductId::from_value(3)],
+        certifications: certifications(),
+        media: vec![Medium {
+            source: Source::SimpleEnvironmentalist,
+            mentions: vec![Mention {
+                title: "Zero waste brands".to_owned(),
+                link: "https://www.youtube.com/watch?v=Wx2ANP44bqQ".to_owned(),
+                timestamp: None,
+            }],
+        }],
+        transpaer: TranspaerOrganisationData::default(),
+    }
+}
+
+fn medallion_variants(medallions: &[api::Medallion]) -> Vec<&api::MedallionVariant> {
+    medallions.iter().map(|medallion| &medallion.variant).collect()
+}
+
+#[test]
+fn product_into_api_short() {
+    let short = product().try_into_api_short().unwrap();
+    assert_eq!(short.name.as_str(), "Fairphone 4");
+    assert_eq!(short.description.unwrap().text.as_str(), "smartphone");
+    assert!(short.badges.is_empty());
+    assert_eq!(short.scores, vec![api::Score { scorer_name: api::ScorerName::Fti, score: 42 }]);
+    assert_eq!(short.product_ids.eans.len(), 1);
+    assert_eq!(short.product_ids.gtins.len(), 1);
+    assert_eq!(short.product_ids.wiki.len(), 1);
+}
+
+#[test]
+fn product_into_api_full() {
+    let product = product();
+    let strict = product.clone().try_into_api_full(Vec::new(), Vec::new()).unwrap();
+    let lenient = product.into_api_full(Vec::new(), Vec::new());
+    assert_eq!(strict, lenient);
+
+    let names = strict.names.iter().map(|n| n.text.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Fairphone 4"]);
+    let descriptions = strict.descriptions.iter().map(|d| d.text.as_str()).collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["smartphone"]);
+    assert_eq!(strict.images.len(), 1);
+    assert_eq!(strict.origins.len(), 1);
+    assert_eq!(strict.shopping.len(), 1);
+    assert!(strict.media.is_empty());
+    assert_eq!(
+        medallion_variants(&strict.medallions),
+        vec![
+            &api::MedallionVariant::BCorp,
+            &api::MedallionVariant::EuEcolabel,
+            &api::MedallionVariant::Fti,
+            &api::MedallionVariant::Tco,
+            &api::MedallionVariant::Transpaer,
+        ]
+    );
+}
+
+#[test]
+fn product_reports_into_api_media() {
+    use transpaer_models::store::SourcedReport;
+
+    let mut product = product();
+    product.reports = vec![
+        SourcedReport {
+            title: Some("Audit".to_owned()),
+            link: "https://example.com/audit".to_owned(),
+            sources: vec![Source::Fti, Source::Tco],
+        },
+        SourcedReport {
+            title: None,
+            link: "https://example.com/review".to_owned(),
+            sources: vec![Source::Fti],
+        },
+    ];
+
+    let full = product.try_into_api_full(Vec::new(), Vec::new()).unwrap();
+    assert_eq!(full.media.len(), 1);
+    let titles = full.media[0].mentions.iter().map(|m| m.title.as_str()).collect::<Vec<_>>();
+    assert_eq!(titles, vec!["Audit", "https://example.com/review"]);
+}
+
+/// Summaries from the review sources are preferred in the short view and listed in the full one.
+#[test]
+fn product_summaries_into_api() {
+    let mut product = product();
+    product.summaries = vec![Text::new("Modular phone with replaceable parts", Source::Tco)];
+
+    let short = product.clone().try_into_api_short().unwrap();
+    assert_eq!(short.description.unwrap().text.as_str(), "Modular phone with replaceable parts");
+
+    let full = product.try_into_api_full(Vec::new(), Vec::new()).unwrap();
+    let descriptions = full.descriptions.iter().map(|d| d.text.as_str()).collect::<Vec<_>>();
+    assert_eq!(descriptions, vec!["smartphone", "Modular phone with replaceable parts"]);
+}
+
+#[test]
+fn organisation_into_api_short() {
+    let short = organisation().try_into_api_short().unwrap();
+    assert_eq!(short.name.as_str(), "Fairphone");
+    assert_eq!(short.description.unwrap().text.as_str(), "social enterprise");
+    assert_eq!(short.scores, vec![api::Score { scorer_name: api::ScorerName::Fti, score: 42 }]);
+    assert_eq!(short.organisation_ids.wiki.len(), 1);
+    assert_eq!(short.organisation_ids.vat.len(), 1);
+    assert_eq!(short.organisation_ids.domains.len(), 1);
+}
+
+#[test]
+fn organisation_into_api_full() {
+    let organisation = organisation();
+    let strict = organisation.clone().try_into_api_full(Vec::new()).unwrap();
+    let lenient = organisation.into_api_full(Vec::new());
+    assert_eq!(strict, lenient);
+
+    let names = strict.names.iter().map(|n| n.text.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Fairphone"]);
+    let websites = strict.websites.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+    assert_eq!(websites, vec!["www.fairphone.com"]);
+    assert_eq!(strict.origins.len(), 1);
+    assert_eq!(strict.media.len(), 1);
+    assert_eq!(strict.media[0].mentions[0].title.as_str(), "Zero waste brands");
+    assert!(strict.products.is_empty());
+    assert_eq!(
+        medallion_variants(&strict.medallions),
+        vec![
+            &api::MedallionVariant::BCorp,
+            &api::MedallionVariant::EuEcolabel,
+            &api::MedallionVariant::Fti,
+            &api::MedallionVariant::Tco,
+        ]
+    );
+}
+
+#[test]
+fn medallions_into_api() {
+    let medallions = certifications().try_into_api_medallions().unwrap();
+    assert_eq!(
+        medallion_variants(&medallions),
+        vec![
+            &api::MedallionVariant::BCorp,
+            &api::MedallionVariant::EuEcolabel,
+            &api::MedallionVariant::Fti,
+            &api::MedallionVariant::Tco,
+        ]
+    );
+    assert_eq!(medallions[0].bcorp.as_ref().unwrap().id.as_str(), "001C000001Dz6afIAB");
+    assert_eq!(medallions[1].eu_ecolabel.as_ref().unwrap().match_accuracy, None);
+    assert_eq!(medallions[2].fti.as_ref().unwrap().score, 42);
+    assert_eq!(medallions[3].tco.as_ref().unwrap().brand_name.as_str(), "FAIRPHONE");
+}
+
+#[test]
+fn score_tree_into_api() {
+    let score = score().try_into_api_score().unwrap();
+    assert!((score.total - 0.66).abs() < f64::EPSILON);
+    assert_eq!(score.tree.len(), 1);
+    assert_eq!(score.tree[0].category, api::TranspaerScoreCategory::DataAvailability);
+    assert_eq!(score.tree[0].weight, 1);
+    let branches =
+        score.tree[0].branches.iter().map(|b| (&b.category, b.weight, b.score)).collect::<Vec<_>>();
+    assert_eq!(
+        branches,
+        vec![
+            (&api::TranspaerScoreCategory::ProducerKnown, 2, 1.0),
+            (&api::TranspaerScoreCategory::IdKnown, 1, 0.0),
+        ]
+    );
+}
+
+/// The root category is never presented, so the strict conversion must fail.
+#[test]
+fn strict_score_tree_with_root_category() {
+    use transpaer_models::models::TranspaerScoreCategory;
+
+    let score = TranspaerScore {
+        tree: vec![TranspaerScoreBranch {
+            branches: Vec::new(),
+            category: TranspaerScoreCategory::Root,
+            weight: 1,
+            score: 1.0,
+            evidence: Vec::new(),
+        }],
+        total: 1.0,
+        scorer: "tree".to_owned(),
+    };
+
+    let result = score.try_into_api_score();
+    assert!(matches!(result, Err(ApiConversionError::ScoreCategory(TranspaerScoreCategory::Root))));
+}
@@ -25,18 +25,71 @@ fn gtin_from_string() {
     );
 }
 
+#[test]
+fn gtin_ean_canonicalization() {
+    use transpaer_models::ids::{Ean, Gtin};
+
+    let ean = Ean::try_from("5901234123457").unwrap();
+    let gtin = Gtin::try_from("05901234123457").unwrap();
+    assert_eq!(ean.to_gtin(), gtin);
+    assert_eq!(gtin.to_ean(), Some(ean));
+    assert_eq!(Gtin::try_from("15901234123454").unwrap().to_ean(), None);
+}
+
 #[test]
 fn vat_id_from_string() {
     use transpaer_models::ids::{ParseIdError, VatId};
 
-    assert_eq!(VatId::try_from("NL12345678"), Ok(VatId::new("NL12345678")));
-    assert_eq!(VatId::try_from("NL123-45 67.8"), Ok(VatId::new("NL12345678")));
+    assert_eq!(VatId::try_from("NL004495445B01"), Ok(VatId::new("NL004495445B01")));
+    assert_eq!(VatId::try_from("nl 0044.95-445 b01"), Ok(VatId::new("NL004495445B01")));
     assert_eq!(
         VatId::try_from("1"),
         Result::<VatId, ParseIdError>::Err(ParseIdError::length("1".to_string()))
     );
 }
 
+#[test]
+fn vat_id_format() {
+    use transpaer_models::ids::{ParseIdError, VatId};
+
+    assert!(VatId::try_from("ATU13585627").is_ok());
+    assert!(VatId::try_from("ESA12345674").is_ok());
+    assert!(VatId::try_from("CHE-123.456.789 MWST").is_ok());
+    assert_eq!(VatId::try_from("NL12345678"), Err(ParseIdError::format("NL12345678".to_string())));
+    assert_eq!(VatId::try_from("DE12345678"), Err(ParseIdError::format("DE12345678".to_string())));
+    assert_eq!(VatId::try_from("AT13585627"), Err(ParseIdError::format("AT13585627".to_string())));
+
+    // Countries without a known format only need to contain a digit.
+    assert!(VatId::try_from("US123").is_ok());
+    assert_eq!(VatId::try_from("N/A"), Err(ParseIdError::format("NA".to_string())));
+}
+
+#[test]
+fn vat_id_checksum() {
+    use transpaer_models::ids::{ParseIdError, VatId};
+
+    // Dutch numbers, both the "11-proef" and the MOD 97-10 variants.
+    assert!(VatId::try_from("NL004495445B01").is_ok());
+    assert!(VatId::try_from("NL000099998B57").is_ok());
+    assert_eq!(
+        VatId::try_from("NL853758543B01"),
+        Err(ParseIdError::checksum("NL853758543B01".to_string()))
+    );
+
+    assert!(VatId::try_from("DE136695976").is_ok());
+    assert_eq!(
+        VatId::try_from("DE136695977"),
+        Err(ParseIdError::checksum("DE136695977".to_string()))
+    );
+
+    assert!(VatId::try_from("FR40303265045").is_ok());
+    assert!(VatId::try_from("FRK7399859412").is_ok());
+    assert_eq!(
+        VatId::try_from("FR41303265045"),
+        Err(ParseIdError::checksum("FR41303265045".to_string()))
+    );
+}
+
 #[test]
 fn organisation_id_to_string() {
     use transpaer_models::ids::OrganisationId;
@@ -35,6 +35,118 @@ fn score_category_to_api() {
     }
 }
 
+#[test]
+fn nutri_score_grades() {
+    use transpaer_models::models::NutriScore;
+
+    assert_eq!(NutriScore::from_grade("a"), Some(NutriScore::A));
+    assert_eq!(NutriScore::from_grade("E"), Some(NutriScore::E));
+    assert_eq!(NutriScore::from_grade("unknown"), None);
+    assert_eq!(NutriScore::from_grade(""), None);
+
+    for score in [NutriScore::A, NutriScore::B, NutriScore::C, NutriScore::D, NutriScore::E] {
+        assert_eq!(NutriScore::from_value(score.to_value()), Some(score));
+    }
+}
+
+#[test]
+fn price_levels_lookup_and_combine() {
+    use transpaer_models::{
+        combine::Combine,
+        models::{PriceLevels, PriceTier, Regions},
+    };
+
+    assert_eq!(PriceTier::from_value(0), None);
+    assert_eq!(PriceTier::from_value(6), None);
+    let cheap = PriceTier::from_value(1).unwrap();
+    let expensive = PriceTier::from_value(5).unwrap();
+    assert_eq!(expensive.to_value(), 5);
+
+    let regional = PriceLevels::new(cheap, &Regions::List(vec![isocountry::CountryCode::NLD]));
+    let world = PriceLevels::new(expensive, &Regions::World);
+    let levels = Combine::combine(regional, world);
+    assert_eq!(levels.get(Some("NLD")), Some(cheap));
+    assert_eq!(levels.get(Some("FRA")), Some(expensive));
+    assert_eq!(levels.get(None), Some(expensive));
+    assert!(PriceLevels::default().is_empty());
+}
+
+#[test]
+fn quantity_median() {
+    use transpaer_models::models::{ProductQuantity, QuantityUnit};
+
+    let q = |amount, unit| ProductQuantity { amount, unit };
+
+    let quantities = [
+        q(0.5, QuantityUnit::Kilogram),
+        q(1.0, QuantityUnit::Litre),
+        q(0.25, QuantityUnit::Kilogram),
+        q(1.0, QuantityUnit::Kilogram),
+    ];
+    assert_eq!(ProductQuantity::median(quantities), Some(q(0.5, QuantityUnit::Kilogram)));
+
+    let quantities = [q(2.0, QuantityUnit::Piece), q(1.0, QuantityUnit::Litre)];
+    assert_eq!(ProductQuantity::median(quantities), Some(q(1.0, QuantityUnit::Litre)));
+
+    assert_eq!(ProductQuantity::median([]), None);
+}
+
+#[test]
+fn organisation_descriptions_selection() {
+    use transpaer_models::models::{Source, Text};
+
+    let text =
+        |text: &str, sources: &[Source]| Text { text: text.to_owned(), sources: sources.to_vec() };
+
+    let texts = vec![
+        text("Phones", &[Source::Wikidata]),
+        text("The maker of the most repairable phone", &[Source::Wikidata, Source::Fti]),
+        text("the maker of the most repairable phone.", &[Source::BCorp]),
+        text("The company from the Netherlands that makes phones", &[Source::Fti]),
+        text("Das Unternehmen ist ein Hersteller von Telefonen", &[Source::Wikidata]),
+    ];
+    let selected = Text::select_descriptions(texts);
+    assert_eq!(
+        selected,
+        vec![
+            text("Das Unternehmen ist ein Hersteller von Telefonen", &[Source::Wikidata]),
+            text(
+                "The maker of the most repairable phone",
+                &[Source::BCorp, Source::Fti, Source::Wikidata]
+            ),
+        ]
+    );
+
+    let short = vec![text("Phones", &[Source::Wikidata])];
+    assert_eq!(Text::select_descriptions(short.clone()), short);
+
+    let undetected = vec![
+        text("Dutch electronics company", &[Source::Wikidata]),
+        text("Dutch electronics company.", &[Source::BCorp]),
+        text("Fairphone B.V., Amsterdam", &[Source::Fti]),
+    ];
+    assert_eq!(
+        Text::select_descriptions(undetected),
+        vec![
+            text("Dutch electronics company.", &[Source::BCorp, Source::Wikidata]),
+            text("Fairphone B.V., Amsterdam", &[Source::Fti]),
+        ]
+    );
+}
+
+#[test]
+fn score_scale_normalize() {
+    use transpaer_models::models::{FtiCert, NutriScore, ScoreScale};
+
+    assert!((FtiCert::SCALE.normalize(0) - 0.0).abs() < f64::EPSILON);
+    assert!((FtiCert::SCALE.normalize(42) - 0.42).abs() < f64::EPSILON);
+    assert!((FtiCert::SCALE.normalize(150) - 1.0).abs() < f64::EPSILON);
+    assert!((NutriScore::SCALE.normalize(NutriScore::C.to_value()) - 0.5).abs() < f64::EPSILON);
+
+    let degenerate = ScoreScale { min: 3, max: 3, div: 1 };
+    assert!((degenerate.normalize(3) - 0.0).abs() < f64::EPSILON);
+}
+
 #[test]
 fn regions_merge() {
     use isocountry::CountryCode::{DEU, ESP, FRA, ITA, POL, SWE};
@@ -60,26 +172,217 @@ fn regions_merge() {
     assert!(combine(&list_2, &list_1) == list_3);
 }
 
+#[test]
+fn certifications_combine() {
+    use transpaer_models::{
+        combine::Combine,
+        models::{BCorpCert, Certifications, EuEcolabelCert, FtiCert, ScoreScale},
+    };
+
+    let bcorp =
+        |id: &str| BCorpCert { id: id.to_owned(), report_url: String::new(), inherited: false };
+    let fti =
+        FtiCert { score: 40, scale: ScoreScale { min: 0, max: 100, div: 1 }, inherited: false };
+
+    let c1 =
+        Certifications { bcorp: Some(bcorp("first")), eu_ecolabel: None, fti: None, tco: None };
+    let c2 = Certifications {
+        bcorp: Some(bcorp("second")),
+        eu_ecolabel: Some(EuEcolabelCert),
+        fti: Some(fti.clone()),
+        tco: None,
+    };
+    let combined = Combine::combine(c1, c2);
+
+    // Destructured without `..` so that new certifications have to be covered here.
+    let Certifications { bcorp: combined_bcorp, eu_ecolabel, fti: combined_fti, tco } = combined;
+    assert_eq!(combined_bcorp, Some(bcorp("first")));
+    assert_eq!(eu_ecolabel, Some(EuEcolabelCert));
+    assert_eq!(combined_fti, Some(fti));
+    assert_eq!(tco, None);
+}
+
+#[test]
+fn organisation_combine_keeps_every_field() {
+    use std::collections::BTreeSet;
+
+    use transpaer_models::{combine::Combine, gather};
+
+    fn organisation(id: u32) -> gather::Organisation {
+        let source = gather::Source::Wikidata;
+        gather::Organisation {
+            ids: gather::OrganisationIds {
+                vat_ids: gather::MultiMap::new_empty(),
+                wiki: gather::MultiMap::new_single(gather::WikiId::new(id.into()), source.clone()),
+                domains: gather::MultiMap::new_empty(),
+            },
+            names: gather::MultiMap::new_single(format!("name {id}"), source.clone()),
+            descriptions: gather::MultiMap::new_single(format!("description {id}"), source.clone()),
+            images: BTreeSet::from([gather::Image {
+                image: format!("{id}.jpg"),
+                source: source.clone(),
+            }]),
+            websites: gather::MultiMap::new_single(format!("{id}.example.com"), source.clone()),
+            products: BTreeSet::from([gather::ProductId::from_value(id)]),
+            origins: gather::MultiMap::new_single(isocountry::CountryCode::NLD, source),
+            certifications: gather::Certifications::default(),
+            media: BTreeSet::new(),
+            transpaer: gather::TranspaerOrganisationData::default(),
+        }
+    }
+
+    let combined = Combine::combine(organisation(1), organisation(2));
+
+    // Destructured without `..` so that new fields have to be covered here.
+    let gather::Organisation {
+        ids,
+        names,
+        descriptions,
+        images,
+        websites,
+        products,
+        origins,
+        certifications,
+        media,
+        transpaer: _,
+    } = combined;
+    assert_eq!(ids.wiki.keys(), BTreeSet::from([gather::WikiId::new(1), gather::WikiId::new(2)]));
+    assert_eq!(names.keys().len(), 2);
+    assert_eq!(descriptions.keys().len(), 2);
+    assert_eq!(images.len(), 2);
+    assert_eq!(websites.keys().len(), 2);
+    assert_eq!(
+        products,
+        BTreeSet::from([gather::ProductId::from_value(1), gather::ProductId::from_value(2)])
+    );
+    assert_eq!(origins.keys(), BTreeSet::from([isocountry::CountryCode::NLD]));
+    assert_eq!(certifications, gather::Certifications::default());
+    assert!(media.is_empty());
+}
+
+#[test]
+fn product_combine_keeps_every_field() {
+    use std::collections::BTreeSet;
+
+    use transpaer_models::{combine::Combine, gather};
+
+    let source = gather::Source::Wikidata;
+    let p1 = gather::Product {
+        names: gather::MultiMap::new_single("Phone".to_owned(), source.clone()),
+        images: BTreeSet::from([gather::Image {
+            image: "1.jpg".to_owned(),
+            source: source.clone(),
+        }]),
+        nutri_score: Some(gather::NutriScore::B),
+        follows: BTreeSet::from([gather::ProductId::from_value(1)]),
+        google_product_category: Some(267),
+        ..Default::default()
+    };
+    let p2 = gather::Product {
+        descriptions: gather::MultiMap::new_single("A phone".to_owned(), source.clone()),
+        categories: gather::MultiMap::new_single("smartphone".to_owned(), source.clone()),
+        images: BTreeSet::from([gather::Image {
+            image: "2.jpg".to_owned(),
+            source: source.clone(),
+        }]),
+        manufacturers: gather::MultiMap::new_single(gather::OrganisationId::from_value(3), source),
+        followed_by: BTreeSet::from([gather::ProductId::from_value(2)]),
+        ..Default::default()
+    };
+    let combined = Combine::combine(p1, p2);
+
+    // Destructured without `..` so that new fields have to be covered here.
+    let gather::Product {
+        ids,
+        names,
+        descriptions,
+        images,
+        categories,
+        google_product_category,
+        ineligible_badges,
+        availability: _,
+        origins,
+        certifications,
+        nutri_score,
+        price_levels: _,
+        manufacturers,
+        shopping,
+        media,
+        reports,
+        follows,
+        followed_by,
+        sitelinks,
+        updated: _,
+        transpaer: _,
+    } = combined;
+    assert_eq!(ids, gather::ProductIds::default());
+    assert!(names.contains("Phone"));
+    assert!(descriptions.contains("A phone"));
+    assert_eq!(images.len(), 2);
+    assert!(categories.contains("smartphone"));
+    assert_eq!(google_product_category, None, "filled only after merging");
+    assert!(ineligible_badges.is_empty());
+    assert!(origins.is_empty());
+    assert_eq!(certifications, gather::Certifications::default());
+    assert_eq!(nutri_score, Some(gather::NutriScore::B));
+    assert!(manufacturers.contains(&gather::OrganisationId::from_value(3)));
+    assert!(shopping.is_empty());
+    assert!(media.is_empty());
+    assert!(reports.is_empty());
+    assert_eq!(follows, BTreeSet::from([gather::ProductId::from_value(1)]));
+    assert_eq!(followed_by, BTreeSet::from([gather::ProductId::from_value(2)]));
+    assert_eq!(sitelinks, 0, "filled only after merging");
+}
+
+#[test]
+fn availability_overlay() {
+    use isocountry::CountryCode::{DEU, FRA, POL};
+    use transpaer_models::store::{Availability, Regions, RetailerAvailability};
+
+    fn entry(region: isocountry::CountryCode, available: bool) -> RetailerAvailability {
+        RetailerAvailability { retailer: "shop".to_owned(), region, available, url: None }
+    }
+
+    let mut unknown = Availability::default();
+    unknown.overlay(&[entry(POL, true), entry(FRA, false)]);
+    assert_eq!(unknown.regions, Regions::List(vec![POL]));
+
+    let mut list = Availability { regions: Regions::List(vec![DEU]), ..Default::default() };
+    list.overlay(&[entry(POL, true), entry(DEU, false)]);
+    assert_eq!(list.regions, Regions::List(vec![DEU, POL]));
+
+    let mut world = Availability { regions: Regions::World, ..Default::default() };
+    world.overlay(&[entry(POL, false)]);
+    assert_eq!(world.regions, Regions::World);
+}
+
 #[test]
 fn serde_product_defaults() {
     use transpaer_models::store::{
-        Availability, Certifications, Product, ProductIds, TranspaerProductData,
+        Availability, Certifications, PriceLevels, Product, ProductIds, TranspaerProductData,
     };
 
     let original_product = Product {
         ids: ProductIds { eans: vec![], gtins: vec![], wiki: vec![] },
         names: Vec::default(),
         descriptions: Vec::default(),
+        summaries: Vec::default(),
         images: Vec::default(),
         categories: Vec::default(),
+        google_product_category: None,
         availability: Availability::default(),
         origins: Vec::new(),
         certifications: Certifications::default(),
+        ineligible_badges: Vec::new(),
+        nutri_score: None,
+        price_levels: PriceLevels::default(),
         manufacturers: Vec::default(),
         shopping: Vec::default(),
         media: Vec::default(),
+        reports: Vec::default(),
         follows: Vec::default(),
         followed_by: Vec::default(),
+        sitelinks: 0,
         transpaer: TranspaerProductData::default(),
     };
 
@@ -92,8 +395,10 @@ fn serde_product_defaults() {
           },
           "names": [],
           "descriptions": [],
+          "summaries": [],
           "images": [],
           "categories": [],
+          "google_product_category": null,
           "availability": {
             "regions": "Unknown",
             "sources": []
@@ -105,15 +410,24 @@ fn serde_product_defaults() {
             "fti": null,
             "tco": null
           },
+          "ineligible_badges": [],
+          "nutri_score": null,
+          "price_levels": {
+            "default": null,
+            "regional": {}
+          },
           "manufacturers": [],
           "shopping": [],
           "media": [],
+          "reports": [],
           "follows": [],
           "followed_by": [],
+          "sitelinks": 0,
           "transpaer": {
             "score": {
               "tree": [],
-              "total": 0.0
+              "total": 0.0,
+              "scorer": ""
             },
             "significance": {}
           }
@@ -128,8 +442,8 @@ fn serde_product_defaults() {
 #[test]
 fn serde_product_filled() {
     use transpaer_models::store::{
-        Availability, Certifications, Product, ProductIds, Regions, Source, SourcedEan,
-        SourcedGtin, SourcedWikiId, TranspaerProductData,
+        Availability, Certifications, PriceLevels, PriceTier, Product, ProductIds, Regions, Source,
+        SourcedEan, SourcedGtin, SourcedWikiId, TranspaerProductData,
     };
 
     let original_product = Product {
@@ -143,8 +457,10 @@ fn serde_product_filled() {
         },
         names: Vec::default(),
         descriptions: Vec::default(),
+        summaries: Vec::default(),
         images: Vec::default(),
         categories: Vec::default(),
+        google_product_category: Some(267),
         availability: Availability {
             regions: Regions::List(vec![
                 isocountry::CountryCode::FRA,
@@ -154,11 +470,21 @@ fn serde_product_filled() {
         },
         origins: Vec::default(),
         certifications: Certifications::default(),
+        ineligible_badges: Vec::new(),
+        nutri_score: None,
+        price_levels: PriceLevels {
+            default: PriceTier::from_value(3),
+            regional: maplit::btreemap! {
+                isocountry::CountryCode::FRA => PriceTier::from_value(2).unwrap(),
+            },
+        },
         manufacturers: Vec::default(),
         shopping: Vec::default(),
         media: Vec::default(),
+        reports: Vec::default(),
         follows: Vec::default(),
         followed_by: Vec::default(),
+        sitelinks: 0,
         transpaer: TranspaerProductData::default(),
     };
 
@@ -192,8 +518,10 @@ fn serde_product_filled() {
           },
           "names": [],
           "descriptions": [],
+          "summaries": [],
           "images": [],
           "categories": [],
+          "google_product_category": 267,
           "availability": {
             "regions": {
               "List": [
@@ -212,15 +540,26 @@ fn serde_product_filled() {
             "fti": null,
             "tco": null
           },
+          "ineligible_badges": [],
+          "nutri_score": null,
+          "price_levels": {
+            "default": 3,
+            "regional": {
+              "FR": 2
+            }
+          },
           "manufacturers": [],
           "shopping": [],
           "media": [],
+          "reports": [],
           "follows": [],
           "followed_by": [],
+          "sitelinks": 0,
           "transpaer": {
             "score": {
               "tree": [],
-              "total": 0.0
+              "total": 0.0,
+              "scorer": ""
             },
             "significance": {}
           }
@@ -231,3 +570,73 @@ fn serde_product_filled() {
 
     pretty_assertions::assert_eq!(expected_string, received_string);
 }
+
+#[test]
+fn organisation_matches() {
+    use std::collections::{BTreeSet, HashMap};
+
+    use maplit::btreemap;
+    use transpaer_models::{
+        gather::{
+            Certifications, MatchMethod, MultiMap, Organisation, OrganisationIds,
+            OrganisationMatch, Source, TranspaerOrganisationData, VatId, WikiId,
+        },
+        transpaer::calculate_organisation_matches,
+    };
+
+    let sources = |sources: &[Source]| sources.iter().cloned().collect::<BTreeSet<_>>();
+
+    let organisation = Organisation {
+        ids: OrganisationIds {
+            vat_ids: MultiMap::new_from_map(btreemap! {
+                VatId::new("NL123") => sources(&[Source::BCorp, Source::Wikidata]),
+            }),
+            wiki: MultiMap::new_from_map(btreemap! {
+                WikiId::new(1) => sources(&[Source::EuEcolabel, Source::Wikidata]),
+            }),
+            domains: MultiMap::new_single("example.com".into(), Source::Wikidata),
+        },
+        names: MultiMap::new_from_map(btreemap! {
+            "Example".to_owned() => sources(&[Source::BCorp, Source::Wikidata]),
+            "EXAMPLE".to_owned() => sources(&[Source::OpenSupplyHub]),
+        }),
+        descriptions: MultiMap::new_empty(),
+        images: BTreeSet::new(),
+        websites: MultiMap::new_empty(),
+        products: BTreeSet::new(),
+        origins: MultiMap::new_empty(),
+        certifications: Certifications::default(),
+        media: BTreeSet::new(),
+        transpaer: TranspaerOrganisationData::default(),
+    };
+
+    let manual = HashMap::from([(WikiId::new(1), 0.8)]);
+    assert_eq!(
+        calculate_organisation_matches(&organisation, &manual),
+        vec![
+            OrganisationMatch {
+                method: MatchMethod::ManualFile,
+                sources: sources(&[Source::EuEcolabel, Source::Wikidata]),
+                accuracy: Some(0.8),
+            },
+            OrganisationMatch {
+                method: MatchMethod::Vat,
+                sources: sources(&[Source::BCorp, Source::Wikidata]),
+                accuracy: None,
+            },
+            OrganisationMatch {
+                method: MatchMethod::NameHeuristic,
+                sources: sources(&[Source::BCorp, Source::OpenSupplyHub, Source::Wikidata]),
+                accuracy: None,
+            },
+        ]
+    );
+
+    assert_eq!(
+        calculate_organisation_matches(&organisation, &HashMap::new())
+            .into_iter()
+            .map(|m| m.method)
+            .collect::<Vec<_>>(),
+        vec![MatchMethod::Vat, MatchMethod::Wiki, MatchMethod::NameHeuristic]
+    );
+}
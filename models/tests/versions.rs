@@ -0,0 +1,345 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Golden fixtures of the stored models.
+//!
+//! The fixtures must never change: they guarantee that the entries written by the previous
+//! versions of the code stay readable. When a stored type changes, add a fixture of the new
+//! version instead of editing the existing one.
+
+use transpaer_models::{ids, store, versions};
+
+/// `store::Product` before the ineligible badges were recorded.
+const PRODUCT_V1: &[u8] = &[
+    0x00, // version
+    0x00, 0x00, 0x00, // ids: eans, gtins, wiki
+    0x01, // names: one text
+    0x09, 0x46, 0x61, 0x69, 0x72, 0x70, 0x68, 0x6F, 0x6E, 0x65, // text: "Fairphone"
+    0x01, 0x07, // sources: Wikidata
+    0x00, 0x00, 0x00, // descriptions, images, categories
+    0x01, 0x05, // google_product_category: 5
+    0x00, 0x00, // availability: World, no sources
+    0x00, // origins
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x00, // nutri_score
+    0x00, 0x00, // price_levels: default, regional
+    0x00, 0x00, // manufacturers, shopping
+    0x00, 0x00, 0x00, // media, follows, followed_by
+    0x00, // transpaer.score.tree
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // transpaer.score.total: 0.0
+    0x00, // transpaer.score.scorer
+    0x00, // transpaer.significance
+];
+
+/// `store::Product` with the ineligible badges.
+const PRODUCT_V2: &[u8] = &[
+    0x01, // version
+    0x00, 0x00, 0x00, // ids: eans, gtins, wiki
+    0x01, // names: one text
+    0x09, 0x46, 0x61, 0x69, 0x72, 0x70, 0x68, 0x6F, 0x6E, 0x65, // text: "Fairphone"
+    0x01, 0x07, // sources: Wikidata
+    0x00, 0x00, 0x00, // descriptions, images, categories
+    0x01, 0x05, // google_product_category: 5
+    0x00, 0x00, // availability: World, no sources
+    0x00, // origins
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x01, 0x00, // ineligible_badges: BCorp
+    0x00, // nutri_score
+    0x00, 0x00, // price_levels: default, regional
+    0x00, 0x00, // manufacturers, shopping
+    0x00, 0x00, 0x00, // media, follows, followed_by
+    0x00, // transpaer.score.tree
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // transpaer.score.total: 0.0
+    0x00, // transpaer.score.scorer
+    0x00, // transpaer.significance
+];
+
+/// `store::Product` with the report links.
+const PRODUCT_V3: &[u8] = &[
+    0x02, // version
+    0x00, 0x00, 0x00, // ids: eans, gtins, wiki
+    0x01, // names: one text
+    0x09, 0x46, 0x61, 0x69, 0x72, 0x70, 0x68, 0x6F, 0x6E, 0x65, // text: "Fairphone"
+    0x01, 0x07, // sources: Wikidata
+    0x00, 0x00, 0x00, // descriptions, images, categories
+    0x01, 0x05, // google_product_category: 5
+    0x00, 0x00, // availability: World, no sources
+    0x00, // origins
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x01, 0x00, // ineligible_badges: BCorp
+    0x00, // nutri_score
+    0x00, 0x00, // price_levels: default, regional
+    0x00, 0x00, // manufacturers, shopping
+    0x00, // media
+    0x01, // reports: one report
+    0x01, 0x06, 0x52, 0x65, 0x70, 0x6F, 0x72, 0x74, // title: "Report"
+    0x07, 0x74, 0x63, 0x6F, 0x2E, 0x6F, 0x72, 0x67, // link: "tco.org"
+    0x01, 0x06, // sources: Tco
+    0x00, 0x00, // follows, followed_by
+    0x00, // transpaer.score.tree
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // transpaer.score.total: 0.0
+    0x00, // transpaer.score.scorer
+    0x00, // transpaer.significance
+];
+
+/// `store::Product` with the summaries.
+const PRODUCT_V4: &[u8] = &[
+    0x03, // version
+    0x00, 0x00, 0x00, // ids: eans, gtins, wiki
+    0x01, // names: one text
+    0x09, 0x46, 0x61, 0x69, 0x72, 0x70, 0x68, 0x6F, 0x6E, 0x65, // text: "Fairphone"
+    0x01, 0x07, // sources: Wikidata
+    0x00, // descriptions
+    0x01, // summaries: one text
+    0x07, 0x4D, 0x6F, 0x64, 0x75, 0x6C, 0x61, 0x72, // text: "Modular"
+    0x01, 0x06, // sources: Tco
+    0x00, 0x00, // images, categories
+    0x01, 0x05, // google_product_category: 5
+    0x00, 0x00, // availability: World, no sources
+    0x00, // origins
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x01, 0x00, // ineligible_badges: BCorp
+    0x00, // nutri_score
+    0x00, 0x00, // price_levels: default, regional
+    0x00, 0x00, // manufacturers, shopping
+    0x00, // media
+    0x01, // reports: one report
+    0x01, 0x06, 0x52, 0x65, 0x70, 0x6F, 0x72, 0x74, // title: "Report"
+    0x07, 0x74, 0x63, 0x6F, 0x2E, 0x6F, 0x72, 0x67, // link: "tco.org"
+    0x01, 0x06, // sources: Tco
+    0x00, 0x00, // follows, followed_by
+    0x00, // transpaer.score.tree
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // transpaer.score.total: 0.0
+    0x00, // transpaer.score.scorer
+    0x00, // transpaer.significance
+];
+
+/// `store::Product` with the sitelink counts.
+const PRODUCT_V5: &[u8] = &[
+    0x04, // version
+    0x00, 0x00, 0x00, // ids: eans, gtins, wiki
+    0x01, // names: one text
+    0x09, 0x46, 0x61, 0x69, 0x72, 0x70, 0x68, 0x6F, 0x6E, 0x65, // text: "Fairphone"
+    0x01, 0x07, // sources: Wikidata
+    0x00, // descriptions
+    0x01, // summaries: one text
+    0x07, 0x4D, 0x6F, 0x64, 0x75, 0x6C, 0x61, 0x72, // text: "Modular"
+    0x01, 0x06, // sources: Tco
+    0x00, 0x00, // images, categories
+    0x01, 0x05, // google_product_category: 5
+    0x00, 0x00, // availability: World, no sources
+    0x00, // origins
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x01, 0x00, // ineligible_badges: BCorp
+    0x00, // nutri_score
+    0x00, 0x00, // price_levels: default, regional
+    0x00, 0x00, // manufacturers, shopping
+    0x00, // media
+    0x01, // reports: one report
+    0x01, 0x06, 0x52, 0x65, 0x70, 0x6F, 0x72, 0x74, // title: "Report"
+    0x07, 0x74, 0x63, 0x6F, 0x2E, 0x6F, 0x72, 0x67, // link: "tco.org"
+    0x01, 0x06, // sources: Tco
+    0x00, 0x00, // follows, followed_by
+    0x05, // sitelinks: 5
+    0x00, // transpaer.score.tree
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // transpaer.score.total: 0.0
+    0x00, // transpaer.score.scorer
+    0x00, // transpaer.significance
+];
+
+/// `store::Organisation`, first version.
+const ORGANISATION_V1: &[u8] = &[
+    0x00, // version
+    0x00, 0x00, 0x00, // ids: wiki, vat_ids, domains
+    0x00, 0x00, 0x00, 0x00, 0x00, // names, descriptions, images, websites, origins
+    0x01, 0x01, // products: 1
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x00, // media
+    0x00, 0x00, // transpaer: significance, matches
+];
+
+/// `store::Organisation` with the search rank.
+const ORGANISATION_V2: &[u8] = &[
+    0x01, // version
+    0x00, 0x00, 0x00, // ids: wiki, vat_ids, domains
+    0x00, 0x00, 0x00, 0x00, 0x00, // names, descriptions, images, websites, origins
+    0x01, 0x01, // products: 1
+    0x00, 0x00, 0x00, 0x00, // certifications: bcorp, eu_ecolabel, fti, tco
+    0x00, // media
+    0x00, 0x00, // transpaer: significance, matches
+    0x01, 0x00, 0x02, // transpaer.rank: num_products, num_certifications, notability
+];
+
+/// `store::Category`, first version.
+const CATEGORY_V1: &[u8] = &[
+    0x00, // version
+    0x04, // status: Broad
+    0x00, // subcategories
+    0x06, 0x50, 0x68, 0x6F, 0x6E, 0x65, 0x73, // label: "Phones"
+    0x00, // products
+    0x00, // quantity
+];
+
+/// `store::BucketDigest`, first version.
+const BUCKET_DIGEST_V1: &[u8] = &[
+    0x00, // version
+    0x02, // entries: 2
+    0x02, 0x61, 0x62, // hash: "ab"
+];
+
+const MISSED_LOOKUP_V1: &[u8] = &[
+    0x00, // version
+    0x03, // count: 3
+    0x01, // first_seen: 1
+    0x02, // last_seen: 2
+];
+
+fn product(
+    ineligible_badges: Vec<store::Badge>,
+    reports: Vec<store::SourcedReport>,
+    summaries: Vec<store::Text>,
+    sitelinks: u32,
+) -> store::Product {
+    use transpaer_models::store::{
+        Availability, Certifications, PriceLevels, Product, ProductIds, Regions, Source, Text,
+        TranspaerProductData,
+    };
+
+    Product {
+        ids: ProductIds { eans: vec![], gtins: vec![], wiki: vec![] },
+        names: vec![Text { text: "Fairphone".to_owned(), sources: vec![Source::Wikidata] }],
+        descriptions: Vec::default(),
+        summaries,
+        images: Vec::default(),
+        categories: Vec::default(),
+        google_product_category: Some(5),
+        availability: Availability { regions: Regions::World, sources: Default::default() },
+        origins: Vec::default(),
+        certifications: Certifications::default(),
+        ineligible_badges,
+        nutri_score: None,
+        price_levels: PriceLevels::default(),
+        manufacturers: Vec::default(),
+        shopping: Vec::default(),
+        media: Vec::default(),
+        reports,
+        follows: Vec::default(),
+        followed_by: Vec::default(),
+        sitelinks,
+        transpaer: TranspaerProductData::default(),
+    }
+}
+
+fn summary() -> store::Text {
+    store::Text { text: "Modular".to_owned(), sources: vec![store::Source::Tco] }
+}
+
+fn report() -> store::SourcedReport {
+    store::SourcedReport {
+        title: Some("Report".to_owned()),
+        link: "tco.org".to_owned(),
+        sources: vec![store::Source::Tco],
+    }
+}
+
+#[test]
+fn product_latest_version_is_stable() {
+    let encoded =
+        versions::encode(&product(vec![store::Badge::BCorp], vec![report()], vec![summary()], 5))
+            .unwrap();
+    assert_eq!(encoded, PRODUCT_V5);
+}
+
+#[test]
+fn product_versions_are_readable() {
+    let v1 = versions::decode::<store::Product>(PRODUCT_V1).unwrap();
+    assert!(v1.ineligible_badges.is_empty());
+    assert!(v1.reports.is_empty());
+    assert_eq!(v1.names[0].text, "Fairphone");
+    assert_eq!(
+        versions::encode(&v1).unwrap(),
+        versions::encode(&product(vec![], vec![], vec![], 0)).unwrap()
+    );
+
+    let v2 = versions::decode::<store::Product>(PRODUCT_V2).unwrap();
+    assert_eq!(v2.ineligible_badges, vec![store::Badge::BCorp]);
+    assert!(v2.reports.is_empty());
+    assert_eq!(
+        versions::encode(&v2).unwrap(),
+        versions::encode(&product(vec![store::Badge::BCorp], vec![], vec![], 0)).unwrap()
+    );
+
+    let v3 = versions::decode::<store::Product>(PRODUCT_V3).unwrap();
+    assert_eq!(v3.reports, vec![report()]);
+    assert!(v3.summaries.is_empty());
+    assert_eq!(
+        versions::encode(&v3).unwrap(),
+        versions::encode(&product(vec![store::Badge::BCorp], vec![report()], vec![], 0)).unwrap()
+    );
+
+    let v4 = versions::decode::<store::Product>(PRODUCT_V4).unwrap();
+    assert_eq!(v4.summaries, vec![summary()]);
+    assert_eq!(v4.sitelinks, 0);
+    assert_eq!(
+        versions::encode(&v4).unwrap(),
+        versions::encode(&product(vec![store::Badge::BCorp], vec![report()], vec![summary()], 0))
+            .unwrap()
+    );
+
+    let v5 = versions::decode::<store::Product>(PRODUCT_V5).unwrap();
+    assert_eq!(v5.sitelinks, 5);
+    assert_eq!(versions::encode(&v5).unwrap(), PRODUCT_V5);
+}
+
+#[test]
+fn organisation_versions_are_readable() {
+    let v1 = versions::decode::<store::Organisation>(ORGANISATION_V1).unwrap();
+    assert_eq!(v1.products, vec![ids::ProductId::from_value(1)]);
+    assert_eq!(v1.transpaer.rank, store::SearchRank::default());
+    let mut expected = ORGANISATION_V2.to_vec();
+    expected[ORGANISATION_V2.len() - 3..].fill(0x00);
+    assert_eq!(versions::encode(&v1).unwrap(), expected);
+
+    let v2 = versions::decode::<store::Organisation>(ORGANISATION_V2).unwrap();
+    assert_eq!(
+        v2.transpaer.rank,
+        store::SearchRank { num_products: 1, num_certifications: 0, notability: 2 }
+    );
+    assert_eq!(versions::encode(&v2).unwrap(), ORGANISATION_V2);
+}
+
+#[test]
+fn category_versions_are_readable() {
+    let category = store::Category {
+        status: store::CategoryStatus::Broad,
+        subcategories: Vec::new(),
+        label: "Phones".to_owned(),
+        products: None,
+        quantity: None,
+    };
+    assert_eq!(versions::decode::<store::Category>(CATEGORY_V1).unwrap(), category);
+    assert_eq!(versions::encode(&category).unwrap(), CATEGORY_V1);
+}
+
+#[test]
+fn bucket_digest_versions_are_readable() {
+    let digest = store::BucketDigest { entries: 2, hash: "ab".to_owned() };
+    assert_eq!(versions::decode::<store::BucketDigest>(BUCKET_DIGEST_V1).unwrap(), digest);
+    assert_eq!(versions::encode(&digest).unwrap(), BUCKET_DIGEST_V1);
+}
+
+#[test]
+fn missed_lookup_versions_are_readable() {
+    let lookup = store::MissedLookup { count: 3, first_seen: 1, last_seen: 2 };
+    assert_eq!(versions::decode::<store::MissedLookup>(MISSED_LOOKUP_V1).unwrap(), lookup);
+    assert_eq!(versions::encode(&lookup).unwrap(), MISSED_LOOKUP_V1);
+}
+
+/// Entries of unknown versions must not be read as garbage.
+#[test]
+fn unknown_versions_are_rejected() {
+    let mut future = PRODUCT_V5.to_vec();
+    future[0] = 0x05;
+    assert!(versions::decode::<store::Product>(&future).is_err());
+}
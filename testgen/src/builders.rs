@@ -0,0 +1,286 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Builders of substrate producers and products.
+
+use transpaer_schema as schema;
+
+fn to_option(values: &[String]) -> Option<Vec<String>> {
+    if values.is_empty() { None } else { Some(values.to_vec()) }
+}
+
+fn to_region_list(regions: &[String]) -> Option<schema::RegionList> {
+    to_option(regions).map(schema::RegionList)
+}
+
+/// Builds producers for both catalog and review substrates.
+#[must_use]
+#[derive(Debug, Clone, Default)]
+pub struct ProducerBuilder {
+    id: String,
+    vat_ids: Vec<String>,
+    wiki_ids: Vec<String>,
+    domains: Vec<String>,
+    names: Vec<String>,
+    description: Option<String>,
+    websites: Vec<String>,
+    regions: Vec<String>,
+    certified: bool,
+}
+
+impl ProducerBuilder {
+    /// Constructs a new `ProducerBuilder` for a producer with the given substrate-local ID.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into(), ..Self::default() }
+    }
+
+    /// Returns the substrate-local ID of the producer.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn vat(mut self, vat_id: impl Into<String>) -> Self {
+        self.vat_ids.push(vat_id.into());
+        self
+    }
+
+    pub fn wiki(mut self, wiki_id: impl Into<String>) -> Self {
+        self.wiki_ids.push(wiki_id.into());
+        self
+    }
+
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domains.push(domain.into());
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn website(mut self, website: impl Into<String>) -> Self {
+        self.websites.push(website.into());
+        self
+    }
+
+    /// Adds a region by its alpha-3 country code.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.regions.push(region.into());
+        self
+    }
+
+    /// Marks the producer as certified in review substrates.
+    pub fn certified(mut self) -> Self {
+        self.certified = true;
+        self
+    }
+
+    fn build_ids(&self) -> schema::ProducerIds {
+        schema::ProducerIds {
+            vat: to_option(&self.vat_ids),
+            wiki: to_option(&self.wiki_ids),
+            domains: to_option(&self.domains),
+        }
+    }
+
+    /// Builds a producer for a catalog substrate.
+    #[must_use]
+    pub fn catalog(&self) -> schema::CatalogProducer {
+        schema::CatalogProducer {
+            id: self.id.clone(),
+            ids: self.build_ids(),
+            names: self.names.clone(),
+            description: self.description.clone(),
+            images: Vec::new(),
+            websites: self.websites.clone(),
+            origins: Some(schema::ProducerOrigins { regions: to_region_list(&self.regions) }),
+        }
+    }
+
+    /// Builds a producer for a review substrate.
+    #[must_use]
+    pub fn review(&self) -> schema::ReviewProducer {
+        schema::ReviewProducer {
+            id: self.id.clone(),
+            ids: self.build_ids(),
+            names: self.names.clone(),
+            description: self.description.clone(),
+            images: Vec::new(),
+            websites: self.websites.clone(),
+            origins: Some(schema::ProducerOrigins { regions: to_region_list(&self.regions) }),
+            reports: None,
+            review: self.certified.then(|| {
+                schema::Review::Certification(schema::Certification { is_certified: Some(true) })
+            }),
+        }
+    }
+}
+
+/// Builds products for both catalog and review substrates.
+#[must_use]
+#[derive(Debug, Clone, Default)]
+pub struct ProductBuilder {
+    id: String,
+    eans: Vec<String>,
+    gtins: Vec<String>,
+    wiki_ids: Vec<String>,
+    names: Vec<String>,
+    description: Option<String>,
+    summary: Option<String>,
+    categories: Vec<String>,
+    producer_ids: Vec<String>,
+    regions: Vec<String>,
+    reports: Vec<(String, String)>,
+    certified: bool,
+}
+
+impl ProductBuilder {
+    /// Constructs a new `ProductBuilder` for a product with the given substrate-local ID.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into(), ..Self::default() }
+    }
+
+    /// Returns the substrate-local ID of the product.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn ean(mut self, ean: impl Into<String>) -> Self {
+        self.eans.push(ean.into());
+        self
+    }
+
+    pub fn gtin(mut self, gtin: impl Into<String>) -> Self {
+        self.gtins.push(gtin.into());
+        self
+    }
+
+    pub fn wiki(mut self, wiki_id: impl Into<String>) -> Self {
+        self.wiki_ids.push(wiki_id.into());
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Gives the product a summary in review substrates.
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    /// Adds a category in the `a/b/c` format.
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.categories.push(category.into());
+        self
+    }
+
+    /// Adds a substrate-local ID of a producer of this product.
+    pub fn producer(mut self, producer_id: impl Into<String>) -> Self {
+        self.producer_ids.push(producer_id.into());
+        self
+    }
+
+    /// Adds a production region by its alpha-3 country code.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.regions.push(region.into());
+        self
+    }
+
+    /// Marks the product as certified in review substrates.
+    pub fn certified(mut self) -> Self {
+        self.certified = true;
+        self
+    }
+
+    /// Adds a link to a report in review substrates.
+    pub fn report(mut self, title: impl Into<String>, url: impl Into<String>) -> Self {
+        self.reports.push((title.into(), url.into()));
+        self
+    }
+
+    fn build_ids(&self) -> schema::ProductIds {
+        schema::ProductIds {
+            ean: to_option(&self.eans),
+            gtin: to_option(&self.gtins),
+            wiki: to_option(&self.wiki_ids),
+        }
+    }
+
+    fn build_categorisation(&self) -> Option<schema::ProductCategorisation> {
+        (!self.categories.is_empty()).then(|| schema::ProductCategorisation {
+            categories: self.categories.iter().cloned().map(schema::ProductCategory).collect(),
+        })
+    }
+
+    fn build_origins(&self) -> schema::ProductOrigins {
+        schema::ProductOrigins {
+            producer_ids: self.producer_ids.clone(),
+            regions: to_region_list(&self.regions),
+        }
+    }
+
+    /// Builds a product for a catalog substrate.
+    #[must_use]
+    pub fn catalog(&self) -> schema::CatalogProduct {
+        schema::CatalogProduct {
+            id: self.id.clone(),
+            ids: self.build_ids(),
+            names: self.names.clone(),
+            description: self.description.clone(),
+            images: Vec::new(),
+            categorisation: self.build_categorisation(),
+            origins: Some(self.build_origins()),
+            availability: None,
+            related: None,
+            shopping: None,
+        }
+    }
+
+    /// Builds a product for a review substrate.
+    #[must_use]
+    pub fn review(&self) -> schema::ReviewProduct {
+        schema::ReviewProduct {
+            id: self.id.clone(),
+            ids: self.build_ids(),
+            names: self.names.clone(),
+            summary: self.summary.clone(),
+            images: Vec::new(),
+            categorisation: self.build_categorisation(),
+            origins: Some(self.build_origins()),
+            availability: None,
+            related: None,
+            reports: (!self.reports.is_empty()).then(|| {
+                schema::Reports(
+                    self.reports
+                        .iter()
+                        .map(|(title, url)| schema::Report {
+                            title: Some(title.clone()),
+                            url: Some(url.clone()),
+                        })
+                        .collect(),
+                )
+            }),
+            review: self.certified.then(|| {
+                schema::Review::Certification(schema::Certification { is_certified: Some(true) })
+            }),
+            shopping: None,
+        }
+    }
+}
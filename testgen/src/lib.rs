@@ -0,0 +1,18 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Test data generator for substrates.
+//!
+//! - `builders` assemble single producers and products,
+//! - `substrates` assemble whole substrate files and write them to a temporary directory,
+//! - `strategies` generate random producers and products for property-based tests.
+
+#![deny(clippy::pedantic, clippy::unwrap_used)]
+
+pub mod builders;
+pub mod strategies;
+pub mod substrates;
+
+pub use builders::{ProducerBuilder, ProductBuilder};
+pub use substrates::{SubstrateBuilder, SubstrateDir, SubstrateDirError};
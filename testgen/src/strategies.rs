@@ -0,0 +1,167 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `proptest` strategies generating random substrate data.
+//!
+//! IDs are drawn from small ranges so that independently generated entries share IDs often enough
+//! to exercise the merging logic.
+
+use proptest::{collection, option, prelude::*, sample};
+
+use crate::{
+    builders::{ProducerBuilder, ProductBuilder},
+    substrates::SubstrateBuilder,
+};
+
+/// Country codes used for the generated regions.
+const REGIONS: &[&str] = &["DEU", "FRA", "NLD", "POL", "ESP"];
+
+/// Categories used for the generated products.
+const CATEGORIES: &[&str] =
+    &["food/beverages/coffee", "electronics/communication/smartphone", "clothing/shoes"];
+
+/// Maximal number of entries in a generated substrate.
+const MAX_ENTRIES: usize = 16;
+
+/// Generates VAT numbers.
+///
+/// Only countries without a checksum are used so that all the generated numbers are valid.
+pub fn vat_id() -> impl Strategy<Value = String> {
+    (sample::select(&["ATU", "BE", "IT", "PL"][..]), 0..50u32).prop_map(|(prefix, number)| {
+        match prefix {
+            "ATU" => format!("{prefix}{number:08}"),
+            "IT" => format!("{prefix}{number:011}"),
+            _ => format!("{prefix}{number:010}"),
+        }
+    })
+}
+
+/// Generates Wikidata IDs in the numeric form used in substrates.
+pub fn wiki_id() -> impl Strategy<Value = String> {
+    (1..50u64).prop_map(|id| id.to_string())
+}
+
+/// Generates web domains.
+pub fn domain() -> impl Strategy<Value = String> {
+    (0..50u32).prop_map(|id| format!("producer{id}.example.com"))
+}
+
+/// Generates 13-digit GTINs.
+pub fn gtin() -> impl Strategy<Value = String> {
+    (0..100u64).prop_map(|id| format!("{:013}", 4_000_000_000_000 + id))
+}
+
+/// Generates names made of one or two capitalised words.
+pub fn name() -> impl Strategy<Value = String> {
+    "[A-Z][a-z]{2,8}( [A-Z][a-z]{2,8})?"
+}
+
+/// Generates regions as alpha-3 country codes.
+pub fn region() -> impl Strategy<Value = String> {
+    sample::select(REGIONS).prop_map(str::to_owned)
+}
+
+/// Generates categories.
+pub fn category() -> impl Strategy<Value = String> {
+    sample::select(CATEGORIES).prop_map(str::to_owned)
+}
+
+/// Generates a producer with the given substrate-local ID.
+pub fn producer(id: String) -> impl Strategy<Value = ProducerBuilder> {
+    (
+        option::of(vat_id()),
+        option::of(wiki_id()),
+        option::of(domain()),
+        collection::vec(name(), 1..3),
+        collection::vec(region(), 0..2),
+        any::<bool>(),
+    )
+        .prop_map(move |(vat, wiki, domain, names, regions, certified)| {
+            let mut producer = ProducerBuilder::new(id.clone());
+            if let Some(vat) = vat {
+                producer = producer.vat(vat);
+            }
+            if let Some(wiki) = wiki {
+                producer = producer.wiki(wiki);
+            }
+            if let Some(domain) = domain {
+                producer = producer.domain(domain);
+            }
+            for name in names {
+                producer = producer.name(name);
+            }
+            for region in regions {
+                producer = producer.region(region);
+            }
+            if certified { producer.certified() } else { producer }
+        })
+}
+
+/// Generates a product with the given substrate-local ID.
+///
+/// The product is assigned to a subset of the passed producer IDs.
+pub fn product(id: String, producer_ids: Vec<String>) -> impl Strategy<Value = ProductBuilder> {
+    let max_producers = producer_ids.len().min(2);
+    (
+        option::of(gtin()),
+        option::of(wiki_id()),
+        collection::vec(name(), 1..3),
+        option::of(category()),
+        sample::subsequence(producer_ids, 0..=max_producers),
+        any::<bool>(),
+    )
+        .prop_map(move |(gtin, wiki, names, category, producer_ids, certified)| {
+            let mut product = ProductBuilder::new(id.clone());
+            if let Some(gtin) = gtin {
+                product = product.gtin(gtin);
+            }
+            if let Some(wiki) = wiki {
+                product = product.wiki(wiki);
+            }
+            for name in names {
+                product = product.name(name);
+            }
+            if let Some(category) = category {
+                product = product.category(category);
+            }
+            for producer_id in producer_ids {
+                product = product.producer(producer_id);
+            }
+            if certified { product.certified() } else { product }
+        })
+}
+
+/// Generates producers with IDs `p0`, `p1`, ...
+pub fn producers() -> impl Strategy<Value = Vec<ProducerBuilder>> {
+    (0..MAX_ENTRIES)
+        .prop_flat_map(|count| (0..count).map(|i| producer(format!("p{i}"))).collect::<Vec<_>>())
+}
+
+/// Generates products with IDs `g0`, `g1`, ... made by the passed producers.
+pub fn products(producer_ids: Vec<String>) -> impl Strategy<Value = Vec<ProductBuilder>> {
+    (0..MAX_ENTRIES).prop_flat_map(move |count| {
+        (0..count).map(|i| product(format!("g{i}"), producer_ids.clone())).collect::<Vec<_>>()
+    })
+}
+
+/// Generates a whole cataloger substrate.
+pub fn cataloger(name: &'static str) -> impl Strategy<Value = SubstrateBuilder> {
+    substrate(move || SubstrateBuilder::cataloger(name))
+}
+
+/// Generates a whole reviewer substrate.
+pub fn reviewer(name: &'static str) -> impl Strategy<Value = SubstrateBuilder> {
+    substrate(move || SubstrateBuilder::reviewer(name))
+}
+
+fn substrate(
+    new: impl Fn() -> SubstrateBuilder + Clone + 'static,
+) -> impl Strategy<Value = SubstrateBuilder> {
+    producers().prop_flat_map(move |producers| {
+        let ids = producers.iter().map(|p| p.id().to_owned()).collect();
+        let new = new.clone();
+        products(ids)
+            .prop_map(move |products| new().producers(producers.clone()).products(products))
+    })
+}
@@ -0,0 +1,206 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Builders of whole substrate files.
+
+use std::path::{Path, PathBuf};
+
+use transpaer_schema as schema;
+
+use crate::builders::{ProducerBuilder, ProductBuilder};
+
+/// Kind of the data provider the substrate comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderVariant {
+    Cataloger,
+    Reviewer,
+}
+
+/// Builds a substrate from producers and products.
+///
+/// The name of the substrate is used as the file stem, which in turn determines the data source
+/// (e.g. `wikidata` or `bcorp`).
+#[must_use]
+#[derive(Debug, Clone)]
+pub struct SubstrateBuilder {
+    name: String,
+    variant: ProviderVariant,
+    producers: Vec<ProducerBuilder>,
+    products: Vec<ProductBuilder>,
+}
+
+impl SubstrateBuilder {
+    /// Constructs a new `SubstrateBuilder` for a cataloger substrate.
+    pub fn cataloger(name: impl Into<String>) -> Self {
+        Self::new(name.into(), ProviderVariant::Cataloger)
+    }
+
+    /// Constructs a new `SubstrateBuilder` for a reviewer substrate.
+    pub fn reviewer(name: impl Into<String>) -> Self {
+        Self::new(name.into(), ProviderVariant::Reviewer)
+    }
+
+    fn new(name: String, variant: ProviderVariant) -> Self {
+        Self { name, variant, producers: Vec::new(), products: Vec::new() }
+    }
+
+    /// Returns the name of the substrate.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of producers in the substrate.
+    #[must_use]
+    pub fn num_producers(&self) -> usize {
+        self.producers.len()
+    }
+
+    /// Returns the number of products in the substrate.
+    #[must_use]
+    pub fn num_products(&self) -> usize {
+        self.products.len()
+    }
+
+    pub fn producer(mut self, producer: ProducerBuilder) -> Self {
+        self.producers.push(producer);
+        self
+    }
+
+    pub fn producers(mut self, producers: impl IntoIterator<Item = ProducerBuilder>) -> Self {
+        self.producers.extend(producers);
+        self
+    }
+
+    pub fn product(mut self, product: ProductBuilder) -> Self {
+        self.products.push(product);
+        self
+    }
+
+    pub fn products(mut self, products: impl IntoIterator<Item = ProductBuilder>) -> Self {
+        self.products.extend(products);
+        self
+    }
+
+    fn build_meta(&self) -> schema::Meta {
+        schema::Meta {
+            version: "0.0.0".to_owned(),
+            variant: match self.variant {
+                ProviderVariant::Cataloger => schema::ProviderVariant::Cataloger,
+                ProviderVariant::Reviewer => schema::ProviderVariant::Reviewer,
+            },
+            authors: vec!["Transpaer Test Generator".to_owned()],
+            title: format!("Test substrate `{}`", self.name),
+            description: None,
+            creation_timestamp: None,
+            valid_from: None,
+            valid_to: None,
+        }
+    }
+
+    /// Builds the substrate.
+    #[must_use]
+    pub fn build(&self) -> schema::Substrate {
+        let website = format!("https://{}.example.com", self.name);
+        let data = match self.variant {
+            ProviderVariant::Cataloger => schema::Data::Cataloger(schema::CatalogerData {
+                cataloger: schema::AboutCataloger {
+                    id: self.name.clone(),
+                    name: self.name.clone(),
+                    description: None,
+                    variant: schema::CatalogVariant::Database,
+                    website,
+                },
+                producers: self.producers.iter().map(ProducerBuilder::catalog).collect(),
+                products: self.products.iter().map(ProductBuilder::catalog).collect(),
+            }),
+            ProviderVariant::Reviewer => schema::Data::Reviewer(schema::ReviewerData {
+                reviewer: schema::AboutReviewer {
+                    id: self.name.clone(),
+                    name: self.name.clone(),
+                    description: String::new(),
+                    website,
+                    reviews: Some(schema::AboutReview::Certification(schema::AboutCertification(
+                        serde_json::Map::new(),
+                    ))),
+                },
+                producers: self.producers.iter().map(ProducerBuilder::review).collect(),
+                products: self.products.iter().map(ProductBuilder::review).collect(),
+            }),
+        };
+        schema::Substrate { meta: self.build_meta(), data }
+    }
+
+    /// Saves the substrate as a JSON-lines file in the given directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if writing the file fails.
+    pub fn save(&self, directory: &Path) -> Result<PathBuf, schema::errors::SaveError> {
+        let path = directory
+            .join(&self.name)
+            .with_extension(schema::SubstrateExtension::JsonLines.as_str());
+        let mut substrate = self.build();
+        substrate.sort();
+        substrate.save(&path)?;
+        Ok(path)
+    }
+}
+
+/// Error returned when preparing a `SubstrateDir`.
+#[derive(thiserror::Error, Debug)]
+pub enum SubstrateDirError {
+    #[error("Failed to create the directory: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to save a substrate: {0}")]
+    Save(#[from] schema::errors::SaveError),
+}
+
+/// Temporary directory with substrate files, removed when dropped.
+#[derive(Debug)]
+pub struct SubstrateDir {
+    dir: tempfile::TempDir,
+}
+
+impl SubstrateDir {
+    /// Constructs a new empty `SubstrateDir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the temporary directory could not be created.
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self { dir: tempfile::tempdir()? })
+    }
+
+    /// Constructs a new `SubstrateDir` with the passed substrates saved in it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the directory could not be created or writing a file fails.
+    pub fn with(
+        substrates: impl IntoIterator<Item = SubstrateBuilder>,
+    ) -> Result<Self, SubstrateDirError> {
+        let dir = Self::new()?;
+        for substrate in substrates {
+            dir.add(&substrate)?;
+        }
+        Ok(dir)
+    }
+
+    /// Returns the path to the directory.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Saves the substrate in the directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if writing the file fails.
+    pub fn add(&self, substrate: &SubstrateBuilder) -> Result<PathBuf, schema::errors::SaveError> {
+        substrate.save(self.path())
+    }
+}
@@ -10,7 +10,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize, de::Deserializer, ser::Serializer};
 
-use crate::errors::ParseIdError;
+use crate::errors::{ParseIdError, ParseQuantityError};
 
 /// Represents a Wikidata ID in a string form.
 ///
@@ -367,6 +367,46 @@ pub struct QuantityDataValue {
     pub unit: String,
 }
 
+impl QuantityDataValue {
+    /// Prefix of the entity URIs used as quantity units.
+    const UNIT_PREFIX: &'static str = "http://www.wikidata.org/entity/";
+
+    /// Unit of the dimensionless quantities.
+    const NO_UNIT: &'static str = "1";
+
+    /// Parses the amount and the unit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the amount is not a number or the unit is not a valid entity URI.
+    pub fn to_quantity(&self) -> Result<Quantity, ParseQuantityError> {
+        let amount = self
+            .amount
+            .parse::<f64>()
+            .map_err(|err| ParseQuantityError::Amount(self.amount.clone(), err))?;
+        let unit = if self.unit == Self::NO_UNIT {
+            None
+        } else {
+            let id = self
+                .unit
+                .strip_prefix(Self::UNIT_PREFIX)
+                .ok_or_else(|| ParseQuantityError::UnitUri(self.unit.clone()))?;
+            Some(Id::try_from(id)?)
+        };
+        Ok(Quantity { amount, unit })
+    }
+}
+
+/// Parsed quantity value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    /// Amount in the given units.
+    pub amount: f64,
+
+    /// ID of the unit item or `None` for dimensionless quantities.
+    pub unit: Option<Id>,
+}
+
 /// `DataValue` holds value and type of the data.
 #[allow(clippy::module_name_repetitions)]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -448,7 +488,11 @@ pub enum Snak {
     NoValue(NoValue),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Rank of a statement.
+///
+/// Deprecated statements are kept in Wikidata for reference (e.g. withdrawn GTINs), but are known
+/// to be wrong or outdated. Preferred statements are the current or most accurate values.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub enum Rank {
     #[serde(rename = "preferred")]
@@ -496,6 +540,20 @@ pub struct Statement {
     pub references: Option<Vec<Reference>>,
 }
 
+impl Statement {
+    /// Checks if the statement is deprecated.
+    #[must_use]
+    pub fn is_deprecated(&self) -> bool {
+        self.rank == Rank::Deprecated
+    }
+
+    /// Checks if the statement is preferred.
+    #[must_use]
+    pub fn is_preferred(&self) -> bool {
+        self.rank == Rank::Preferred
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type", deny_unknown_fields)]
 pub enum Claim {
@@ -544,6 +602,40 @@ pub struct Item {
     pub sitelinks: HashMap<String, Sitelink>,
 }
 
+impl Item {
+    /// Returns statements of the passed property skipping the deprecated ones.
+    ///
+    /// Preferred statements come first, otherwise the order from the data is kept.
+    #[must_use]
+    pub fn get_statements(&self, property_id: &str) -> Option<Vec<&Statement>> {
+        let mut statements: Vec<&Statement> = self
+            .claims
+            .get(property_id)?
+            .iter()
+            .map(|claim| {
+                let Claim::Statement(statement) = claim;
+                statement
+            })
+            .filter(|statement| !statement.is_deprecated())
+            .collect();
+        statements.sort_by_key(|statement| !statement.is_preferred());
+        Some(statements)
+    }
+
+    /// Returns statements of the passed property with the best rank.
+    ///
+    /// These are the preferred statements if there are any, otherwise the normal ones. This
+    /// corresponds to the "truthy" statements from the RDF dumps.
+    #[must_use]
+    pub fn get_best_statements(&self, property_id: &str) -> Option<Vec<&Statement>> {
+        let mut statements = self.get_statements(property_id)?;
+        if statements.first().is_some_and(|statement| statement.is_preferred()) {
+            statements.retain(|statement| statement.is_preferred());
+        }
+        Some(statements)
+    }
+}
+
 /// Represents a property ("P") entry.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
@@ -3,12 +3,16 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::{
+    collections::{HashMap, HashSet},
     future::Future,
-    io::{BufRead, Seek},
+    io::BufRead,
 };
 
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::{data, properties};
+
 /// Error returned id fig checking failed.
 /// Error returned when a problem with IO or sending over channel occured.
 #[derive(Error, Debug)]
@@ -18,12 +22,31 @@ pub enum LoaderError {
 
     #[error("Unknown compression method: {0:?}")]
     CompressionMethod(Option<String>),
+
+    #[error("Serializing an entity: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Format of the dump.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// The full JSON dump with one entity per line.
+    #[default]
+    Json,
+
+    /// The truthy N-Triples dump.
+    ///
+    /// It contains only the best ranked statements without qualifiers and references, which makes
+    /// it many times smaller than the JSON dump. The triples are converted into JSON entities
+    /// holding only the labels, descriptions, aliases, sitelinks and the properties listed in
+    /// `properties::USED`, so the rest of the processing does not need to know the format.
+    TruthyNt,
 }
 
 /// Compression method used in the dump.
 #[derive(Clone, Debug)]
 enum CompressionMethod {
-    /// `json` or `jsonl` file.
+    /// `json`, `jsonl` or `nt` file.
     None,
 
     /// `json.gz` file.
@@ -33,6 +56,18 @@ enum CompressionMethod {
     Bz2,
 }
 
+impl CompressionMethod {
+    /// Guesses the compression method from the extension of the file name.
+    fn from_path(path: &std::path::Path) -> Result<Self, LoaderError> {
+        match path.extension().and_then(std::ffi::OsStr::to_str) {
+            Some("json" | "jsonl" | "nt") => Ok(Self::None),
+            Some("gz") => Ok(Self::Gz),
+            Some("bz2") => Ok(Self::Bz2),
+            method => Err(LoaderError::CompressionMethod(method.map(ToString::to_string))),
+        }
+    }
+}
+
 /// Reads Wikidata dump file in.
 ///
 /// The Wikidata dump file, which is a compressed json file, is very big. The unpacked version would
@@ -43,18 +78,30 @@ enum CompressionMethod {
 /// composed of many confactenated zips, which in not supported by `flate2-rs`
 /// (`https://github.com/rust-lang/flate2-rs/issues/23`). Parsing such concatenated zip structure
 /// had to be implemented within this reader.
-#[derive(Debug)]
 pub struct Loader {
     /// Reader of the zip file.
-    reader: std::io::BufReader<std::fs::File>,
+    reader: Box<dyn BufRead + Send>,
 
     /// Compression method to use.
     compression_method: CompressionMethod,
 
+    /// Format of the dump.
+    format: DumpFormat,
+
     /// Path to the loaded file. Needed only for error reporting.
     path: std::path::PathBuf,
 }
 
+impl std::fmt::Debug for Loader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Loader")
+            .field("compression_method", &self.compression_method)
+            .field("format", &self.format)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Loader {
     /// Constructs a new `Loader`.
     ///
@@ -62,22 +109,36 @@ impl Loader {
     ///
     /// Returns `Err` if fails to read from `path`.
     pub fn load(path: &std::path::Path) -> Result<Self, LoaderError> {
-        let compression_method = match path.extension().and_then(std::ffi::OsStr::to_str) {
-            Some("json" | "jsonl") => CompressionMethod::None,
-            Some("gz") => CompressionMethod::Gz,
-            Some("bz2") => CompressionMethod::Bz2,
-            method => {
-                return Err(LoaderError::CompressionMethod(
-                    method.map(std::string::ToString::to_string),
-                ));
-            }
-        };
+        let compression_method = CompressionMethod::from_path(path)?;
 
         let path = path.to_owned();
         let file = std::fs::File::open(&path).map_err(|e| LoaderError::Io(e, path.clone()))?;
-        let reader = std::io::BufReader::new(file);
+        let reader = Box::new(std::io::BufReader::new(file));
+
+        Ok(Self { reader, compression_method, format: DumpFormat::Json, path })
+    }
+
+    /// Constructs a new `Loader` reading the dump from an arbitrary source, e.g. a network stream.
+    ///
+    /// The `name` is used to guess the compression method and for error reporting.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the compression method cannot be guessed from the `name`.
+    pub fn from_reader<R>(reader: R, name: &str) -> Result<Self, LoaderError>
+    where
+        R: BufRead + Send + 'static,
+    {
+        let path = std::path::PathBuf::from(name);
+        let compression_method = CompressionMethod::from_path(&path)?;
+        Ok(Self { reader: Box::new(reader), compression_method, format: DumpFormat::Json, path })
+    }
 
-        Ok(Self { reader, compression_method, path })
+    /// Sets the format of the dump.
+    #[must_use]
+    pub fn with_format(mut self, format: DumpFormat) -> Self {
+        self.format = format;
+        self
     }
 
     /// Parses the Wikidata dump file while unzipping it and sends the parsed out entries to the
@@ -88,7 +149,6 @@ impl Loader {
     /// Returns `Err` if fails to
     /// - read from the file
     /// - unzip the file
-    /// - seek position in the file
     /// - send a message over channel
     pub async fn run<C, F>(mut self, callback: C) -> Result<usize, LoaderError>
     where
@@ -107,31 +167,23 @@ impl Loader {
         C: Fn(String) -> F,
         F: Future<Output = ()>,
     {
-        let mut entries: usize = 0;
-
-        self.reader
-            .seek(std::io::SeekFrom::End(0))
-            .map_err(|e| LoaderError::Io(e, self.path.clone()))?;
-        let file_size =
-            self.reader.stream_position().map_err(|e| LoaderError::Io(e, self.path.clone()))?;
-        self.reader
-            .seek(std::io::SeekFrom::Start(0))
-            .map_err(|e| LoaderError::Io(e, self.path.clone()))?;
+        let mut handler = LineHandler::new(self.format);
 
+        // Each iteration decodes one of the concatenated members until the input is exhausted.
         loop {
             let decoder = flate2::bufread::GzDecoder::new(&mut self.reader);
             for line in std::io::BufReader::new(decoder).lines() {
                 let line = line.map_err(|e| LoaderError::Io(e, self.path.clone()))?;
-                entries += Self::handle_line(&mut callback, &line).await?;
+                handler.handle_line(&mut callback, &line).await?;
             }
 
-            let stream_position =
-                self.reader.stream_position().map_err(|e| LoaderError::Io(e, self.path.clone()))?;
-            if stream_position == file_size {
+            let remaining =
+                self.reader.fill_buf().map_err(|e| LoaderError::Io(e, self.path.clone()))?;
+            if remaining.is_empty() {
                 break;
             }
         }
-        Ok(entries)
+        handler.finish(&mut callback).await
     }
 
     async fn run_bz2<C, F>(&mut self, mut callback: C) -> Result<usize, LoaderError>
@@ -139,15 +191,15 @@ impl Loader {
         C: Fn(String) -> F,
         F: Future<Output = ()>,
     {
-        let mut entries: usize = 0;
+        let mut handler = LineHandler::new(self.format);
 
         let decoder = bzip2::bufread::MultiBzDecoder::new(&mut self.reader);
         for line in std::io::BufReader::new(decoder).lines() {
             let line = line.map_err(|e| LoaderError::Io(e, self.path.clone()))?;
-            entries += Self::handle_line(&mut callback, &line).await?;
+            handler.handle_line(&mut callback, &line).await?;
         }
 
-        Ok(entries)
+        handler.finish(&mut callback).await
     }
 
     async fn run_none<C, F>(&mut self, mut callback: C) -> Result<usize, LoaderError>
@@ -155,33 +207,639 @@ impl Loader {
         C: Fn(String) -> F,
         F: Future<Output = ()>,
     {
-        let mut entries: usize = 0;
+        let mut handler = LineHandler::new(self.format);
 
         for line in std::io::BufReader::new(&mut self.reader).lines() {
             let line = line.map_err(|e| LoaderError::Io(e, self.path.clone()))?;
-            entries += Self::handle_line(&mut callback, &line).await?;
+            handler.handle_line(&mut callback, &line).await?;
         }
 
-        Ok(entries)
+        handler.finish(&mut callback).await
+    }
+}
+
+/// Turns lines of the dump into JSON entities and passes them to the callback.
+enum LineHandler {
+    Json { entries: usize },
+    TruthyNt { assembler: Box<TruthyAssembler>, entries: usize },
+}
+
+impl LineHandler {
+    fn new(format: DumpFormat) -> Self {
+        match format {
+            DumpFormat::Json => Self::Json { entries: 0 },
+            DumpFormat::TruthyNt => {
+                let assembler = Box::new(TruthyAssembler::new(properties::USED));
+                Self::TruthyNt { assembler, entries: 0 }
+            }
+        }
     }
 
     fn should_ignore_line(line: &str) -> bool {
         line == "," || line == "[" || line == "]" || line.is_empty()
     }
 
-    async fn handle_line<C, F>(callback: &mut C, line: &str) -> Result<usize, LoaderError>
+    async fn handle_line<C, F>(&mut self, callback: &mut C, line: &str) -> Result<(), LoaderError>
+    where
+        C: Fn(String) -> F,
+        F: Future<Output = ()>,
+    {
+        match self {
+            Self::Json { entries } => {
+                if Self::should_ignore_line(line) {
+                    return Ok(());
+                }
+
+                let json_str =
+                    if line.ends_with(',') { line.strip_suffix(',').unwrap_or("") } else { line };
+
+                callback(json_str.to_string()).await;
+                *entries += 1;
+            }
+            Self::TruthyNt { assembler, entries } => {
+                if let Some(entity) = assembler.add_line(line)? {
+                    callback(entity).await;
+                    *entries += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Passes the last assembled entity to the callback and returns the number of entities.
+    async fn finish<C, F>(self, callback: &mut C) -> Result<usize, LoaderError>
     where
         C: Fn(String) -> F,
         F: Future<Output = ()>,
     {
-        if Self::should_ignore_line(line) {
-            return Ok(0);
+        match self {
+            Self::Json { entries } => Ok(entries),
+            Self::TruthyNt { mut assembler, mut entries } => {
+                if let Some(entity) = assembler.take()? {
+                    callback(entity).await;
+                    entries += 1;
+                }
+                Ok(entries)
+            }
+        }
+    }
+}
+
+/// Prefix of the IRIs of the entities.
+const ENTITY_PREFIX: &str = "http://www.wikidata.org/entity/";
+
+/// Prefix of the IRIs of the statement nodes.
+const STATEMENT_PREFIX: &str = "http://www.wikidata.org/entity/statement/";
+
+/// Prefix of the IRIs of the full value nodes.
+const VALUE_PREFIX: &str = "http://www.wikidata.org/value/";
+
+/// Prefix of the IRIs of the properties linking items directly to the best ranked values.
+const DIRECT_PROPERTY_PREFIX: &str = "http://www.wikidata.org/prop/direct/";
+
+/// Prefix of the IRIs of the properties linking statements to their full value nodes.
+const STATEMENT_VALUE_PREFIX: &str = "http://www.wikidata.org/prop/statement/value/";
+
+/// Prefix of the IRIs of the Wikimedia Commons files.
+const COMMONS_FILE_PREFIX: &str = "http://commons.wikimedia.org/wiki/Special:FilePath/";
+
+/// Prefix of the XML Schema data types.
+const XSD_PREFIX: &str = "http://www.w3.org/2001/XMLSchema#";
+
+const LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const DESCRIPTION: &str = "http://schema.org/description";
+const ALIAS: &str = "http://www.w3.org/2004/02/skos/core#altLabel";
+const ABOUT: &str = "http://schema.org/about";
+const QUANTITY_AMOUNT: &str = "http://wikiba.se/ontology#quantityAmount";
+const QUANTITY_UNIT: &str = "http://wikiba.se/ontology#quantityUnit";
+
+/// Unit of the dimensionless quantities, also used when the unit is not known.
+const NO_UNIT: &str = "1";
+
+/// Calendar model of the converted dates.
+const GREGORIAN_CALENDAR: &str = "http://www.wikidata.org/entity/Q1985727";
+
+/// Precision of a date given with a day.
+const PRECISION_DAY: i64 = 11;
+
+/// Term of an N-Triples statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Iri(String),
+    Literal { value: String, language: Option<String>, datatype: Option<String> },
+    Blank,
+}
+
+/// N-Triples statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Triple {
+    subject: Term,
+    predicate: String,
+    object: Term,
+}
+
+impl Triple {
+    /// Parses a line of an N-Triples file. Returns `None` for comments and malformed lines.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
         }
 
-        let json_str =
-            if line.ends_with(',') { line.strip_suffix(',').unwrap_or("") } else { line };
+        let (subject, rest) = Self::parse_term(line)?;
+        let (predicate, rest) = Self::parse_term(rest.trim_start())?;
+        let (object, rest) = Self::parse_term(rest.trim_start())?;
+        if !rest.trim_start().starts_with('.') {
+            return None;
+        }
+        let Term::Iri(predicate) = predicate else { return None };
+        Some(Self { subject, predicate, object })
+    }
+
+    fn parse_term(input: &str) -> Option<(Term, &str)> {
+        if let Some(rest) = input.strip_prefix('<') {
+            let end = rest.find('>')?;
+            Some((Term::Iri(rest[..end].to_owned()), &rest[end + 1..]))
+        } else if let Some(rest) = input.strip_prefix("_:") {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            Some((Term::Blank, &rest[end..]))
+        } else if let Some(rest) = input.strip_prefix('"') {
+            let (value, rest) = Self::parse_string(rest)?;
+            if let Some(rest) = rest.strip_prefix('@') {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let language = Some(rest[..end].to_owned());
+                Some((Term::Literal { value, language, datatype: None }, &rest[end..]))
+            } else if let Some(rest) = rest.strip_prefix("^^<") {
+                let end = rest.find('>')?;
+                let datatype = Some(rest[..end].to_owned());
+                Some((Term::Literal { value, language: None, datatype }, &rest[end + 1..]))
+            } else {
+                Some((Term::Literal { value, language: None, datatype: None }, rest))
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Parses an escaped string up to the closing quote.
+    fn parse_string(input: &str) -> Option<(String, &str)> {
+        let mut value = String::new();
+        let mut chars = input.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Some((value, &input[i + 1..])),
+                '\\' => match chars.next()?.1 {
+                    't' => value.push('\t'),
+                    'b' => value.push('\u{8}'),
+                    'n' => value.push('\n'),
+                    'r' => value.push('\r'),
+                    'f' => value.push('\u{c}'),
+                    'u' => value.push(Self::parse_unicode(&mut chars, 4)?),
+                    'U' => value.push(Self::parse_unicode(&mut chars, 8)?),
+                    other => value.push(other),
+                },
+                c => value.push(c),
+            }
+        }
+        None
+    }
+
+    fn parse_unicode(chars: &mut std::str::CharIndices<'_>, len: usize) -> Option<char> {
+        let code: String = chars.by_ref().take(len).map(|(_, c)| c).collect();
+        char::from_u32(u32::from_str_radix(&code, 16).ok()?)
+    }
+}
+
+/// Decodes percent-encoded characters, e.g. in file names.
+fn decode_percent(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let decoded = input.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = decoded {
+                result.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        result.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+/// Item assembled from truthy statements, serialized in the same form as the JSON dump entities.
+#[derive(Serialize, Debug, Default)]
+#[serde(tag = "type", rename = "item")]
+struct TruthyItem {
+    id: String,
+    lastrevid: u64,
+    labels: HashMap<String, data::Label>,
+    descriptions: HashMap<String, data::Label>,
+    aliases: HashMap<String, Vec<data::Label>>,
+    claims: HashMap<String, Vec<data::Claim>>,
+    sitelinks: HashMap<String, data::Sitelink>,
+
+    /// Properties of the statements pointing to the full value nodes, by the value node IRIs.
+    #[serde(skip)]
+    value_properties: HashMap<String, String>,
+
+    /// Amounts and units of the full quantity value nodes, by the value node IRIs.
+    #[serde(skip)]
+    quantity_nodes: HashMap<String, QuantityNode>,
+}
+
+/// Full quantity value node.
+#[derive(Debug, Default)]
+struct QuantityNode {
+    amount: Option<String>,
+    unit: Option<String>,
+}
+
+impl TruthyItem {
+    fn new(id: String) -> Self {
+        Self { id, ..Self::default() }
+    }
+
+    fn add_claim(&mut self, property: &str, datavalue: data::DataValue) {
+        let claims = self.claims.entry(property.to_owned()).or_default();
+        claims.push(data::Claim::Statement(data::Statement {
+            id: format!("{}$truthy-{property}-{}", self.id, claims.len()),
+            mainsnak: data::Snak::Value(data::Value {
+                hash: None,
+                property: property.to_owned(),
+                datatype: None,
+                datavalue,
+            }),
+            rank: data::Rank::Normal,
+            qualifiers: None,
+            qualifiers_order: None,
+            references: None,
+        }));
+    }
+
+    /// Adds a sitelink given by the IRI of the article, e.g. `https://en.wikipedia.org/wiki/X`.
+    fn add_sitelink(&mut self, article: &str) {
+        let Some((host, title)) =
+            article.strip_prefix("https://").and_then(|rest| rest.split_once("/wiki/"))
+        else {
+            return;
+        };
+        let Some((language, project)) = host.split_once('.') else { return };
+        let site = match project {
+            "wikipedia.org" => format!("{language}wiki"),
+            "wikiquote.org" | "wikisource.org" | "wikivoyage.org" | "wikinews.org"
+            | "wikibooks.org" | "wikiversity.org" => {
+                format!("{language}{}", project.trim_end_matches(".org"))
+            }
+            _ => host.replace('.', "_"),
+        }
+        .replace('-', "_");
+        let title = decode_percent(title).replace('_', " ");
+        self.sitelinks.insert(site.clone(), data::Sitelink { site, title, badges: Vec::new() });
+    }
+
+    /// Adds a triple about a statement node, e.g. `wds:Q42-... psv:P2067 wdv:...`.
+    fn add_statement_node(&mut self, statement: &str, triple: &Triple) {
+        let belongs_to_item =
+            statement.split_once('-').is_some_and(|(id, _)| id.eq_ignore_ascii_case(&self.id));
+        if !belongs_to_item {
+            return;
+        }
+        let Some(property) = triple.predicate.strip_prefix(STATEMENT_VALUE_PREFIX) else { return };
+        if let Term::Iri(node) = &triple.object {
+            self.value_properties.insert(node.clone(), property.to_owned());
+        }
+    }
+
+    /// Adds a triple about a full value node, e.g. `wdv:... wikibase:quantityUnit wd:Q11570`.
+    fn add_value_node(&mut self, node: &str, triple: &Triple) {
+        match (triple.predicate.as_str(), &triple.object) {
+            (QUANTITY_AMOUNT, Term::Literal { value, .. }) => {
+                self.quantity_nodes.entry(node.to_owned()).or_default().amount =
+                    Some(value.clone());
+            }
+            (QUANTITY_UNIT, Term::Iri(unit)) => {
+                self.quantity_nodes.entry(node.to_owned()).or_default().unit = Some(unit.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// Assigns the units from the full value nodes to the quantity claims with the same amounts.
+    fn resolve_units(&mut self) {
+        for (node, property) in &self.value_properties {
+            let Some(QuantityNode { amount: Some(amount), unit: Some(unit) }) =
+                self.quantity_nodes.get(node)
+            else {
+                continue;
+            };
+            let Some(claims) = self.claims.get_mut(property) else { continue };
+            let quantity = claims.iter_mut().find_map(|claim| {
+                let data::Claim::Statement(statement) = claim;
+                match &mut statement.mainsnak {
+                    data::Snak::Value(data::Value {
+                        datavalue: data::DataValue::Quantity(quantity),
+                        ..
+                    }) if quantity.amount == *amount && quantity.unit == NO_UNIT => Some(quantity),
+                    _ => None,
+                }
+            });
+            if let Some(quantity) = quantity {
+                quantity.unit.clone_from(unit);
+            }
+        }
+    }
+}
+
+/// Groups truthy N-Triples statements by items and converts them to JSON entities.
+///
+/// Statements about one item are expected to be placed next to each other in the dump, including
+/// the sitelinks which may come before or after the statements about the item itself.
+/// Only items are assembled; properties and lexemes are skipped.
+///
+/// The truthy statements do not carry the units of quantities. If the dump also contains the
+/// statement and full value nodes of an item (as the complete N-Triples dump does) the units are
+/// taken from them, otherwise the quantities are left without a unit.
+#[derive(Debug)]
+struct TruthyAssembler {
+    /// Properties to keep.
+    properties: HashSet<String>,
+
+    /// The item currently being assembled.
+    current: Option<TruthyItem>,
+}
+
+impl TruthyAssembler {
+    fn new(properties: &[&str]) -> Self {
+        Self { properties: properties.iter().map(|p| (*p).to_owned()).collect(), current: None }
+    }
+
+    /// Adds a line of the dump. Returns the previous item once a statement about a new one is met.
+    fn add_line(&mut self, line: &str) -> Result<Option<String>, LoaderError> {
+        let Some(triple) = Triple::parse(line) else { return Ok(None) };
+
+        // Sitelinks are stated about the articles, not about the items.
+        if triple.predicate == ABOUT {
+            let (Term::Iri(article), Term::Iri(object)) = (&triple.subject, &triple.object) else {
+                return Ok(None);
+            };
+            let Some(id) = object.strip_prefix(ENTITY_PREFIX).filter(|id| id.starts_with('Q'))
+            else {
+                return Ok(None);
+            };
+            let finished = self.switch_to(id)?;
+            if let Some(current) = &mut self.current {
+                current.add_sitelink(article);
+            }
+            return Ok(finished);
+        }
+
+        let Term::Iri(subject) = &triple.subject else { return Ok(None) };
+
+        // Statement and value nodes follow the item they belong to.
+        if let Some(statement) = subject.strip_prefix(STATEMENT_PREFIX) {
+            if let Some(current) = &mut self.current {
+                current.add_statement_node(statement, &triple);
+            }
+            return Ok(None);
+        }
+        if subject.starts_with(VALUE_PREFIX) {
+            if let Some(current) = &mut self.current {
+                current.add_value_node(subject, &triple);
+            }
+            return Ok(None);
+        }
+
+        let Some(id) = subject.strip_prefix(ENTITY_PREFIX) else { return Ok(None) };
+        if !id.starts_with('Q') {
+            return Ok(None);
+        }
+
+        let finished = self.switch_to(id)?;
+        if let Some(current) = &mut self.current {
+            Self::add_statement(&self.properties, current, &triple);
+        }
+        Ok(finished)
+    }
+
+    /// Starts assembling the given item unless it is already the current one.
+    /// Returns the previously assembled item if there was one.
+    fn switch_to(&mut self, id: &str) -> Result<Option<String>, LoaderError> {
+        if self.current.as_ref().is_some_and(|current| current.id == id) {
+            return Ok(None);
+        }
+        let finished = self.take()?;
+        self.current = Some(TruthyItem::new(id.to_owned()));
+        Ok(finished)
+    }
+
+    /// Returns the item currently being assembled, serialized to JSON.
+    fn take(&mut self) -> Result<Option<String>, LoaderError> {
+        match self.current.take() {
+            Some(mut item) => {
+                item.resolve_units();
+                Ok(Some(serde_json::to_string(&item)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn add_statement(properties: &HashSet<String>, item: &mut TruthyItem, triple: &Triple) {
+        match (triple.predicate.as_str(), &triple.object) {
+            (LABEL, Term::Literal { value, language: Some(language), .. }) => {
+                item.labels.insert(language.clone(), Self::label(language, value));
+            }
+            (DESCRIPTION, Term::Literal { value, language: Some(language), .. }) => {
+                item.descriptions.insert(language.clone(), Self::label(language, value));
+            }
+            (ALIAS, Term::Literal { value, language: Some(language), .. }) => {
+                item.aliases
+                    .entry(language.clone())
+                    .or_default()
+                    .push(Self::label(language, value));
+            }
+            (predicate, object) => {
+                let Some(property) = predicate.strip_prefix(DIRECT_PROPERTY_PREFIX) else {
+                    return;
+                };
+                if !properties.contains(property) {
+                    return;
+                }
+                if let Some(datavalue) = Self::convert_value(object) {
+                    item.add_claim(property, datavalue);
+                }
+            }
+        }
+    }
+
+    fn label(language: &str, value: &str) -> data::Label {
+        data::Label { language: language.to_owned(), value: value.to_owned() }
+    }
+
+    /// Converts the object of a statement to a value the way it would appear in the JSON dump.
+    fn convert_value(object: &Term) -> Option<data::DataValue> {
+        match object {
+            Term::Iri(iri) => {
+                if let Some(id) = iri.strip_prefix(ENTITY_PREFIX) {
+                    let numeric_id = id.get(1..)?.parse::<u64>().ok()?;
+                    let info =
+                        data::EntityIdInfo { id: data::StrId::new(id.to_owned()), numeric_id };
+                    match id.chars().next()? {
+                        'Q' => Some(data::DataValue::WikibaseEntityId(
+                            data::EntityIdDataValue::Item(info),
+                        )),
+                        'P' => Some(data::DataValue::WikibaseEntityId(
+                            data::EntityIdDataValue::Property(info),
+                        )),
+                        _ => None,
+                    }
+                } else if let Some(file) = iri.strip_prefix(COMMONS_FILE_PREFIX) {
+                    Some(data::DataValue::String(decode_percent(file)))
+                } else {
+                    Some(data::DataValue::String(iri.clone()))
+                }
+            }
+            Term::Literal { value, language: Some(language), .. } => {
+                Some(data::DataValue::MonolingualText(data::MonolingualTextDataValue {
+                    text: value.clone(),
+                    language: language.clone(),
+                }))
+            }
+            Term::Literal { value, language: None, datatype: Some(datatype) } => {
+                match datatype.strip_prefix(XSD_PREFIX)? {
+                    "decimal" | "integer" | "double" => {
+                        Some(data::DataValue::Quantity(data::QuantityDataValue {
+                            amount: value.clone(),
+                            upper_bound: None,
+                            lower_bound: None,
+                            // Units are not present in the truthy statements, they are
+                            // resolved from the full value nodes if the dump has them.
+                            unit: NO_UNIT.to_owned(),
+                        }))
+                    }
+                    "dateTime" => Some(data::DataValue::Time(data::TimeDataValue {
+                        time: if value.starts_with('-') {
+                            value.clone()
+                        } else {
+                            format!("+{value}")
+                        },
+                        timezone: 0,
+                        before: 0,
+                        after: 0,
+                        // The precision is not present in the truthy statements.
+                        precision: PRECISION_DAY,
+                        calendarmodel: GREGORIAN_CALENDAR.to_owned(),
+                    })),
+                    "string" => Some(data::DataValue::String(value.clone())),
+                    _ => None,
+                }
+            }
+            Term::Literal { value, language: None, datatype: None } => {
+                Some(data::DataValue::String(value.clone()))
+            }
+            Term::Blank => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Feeds the lines to a fresh assembler and returns all the assembled items.
+    fn assemble(lines: &[&str]) -> Vec<serde_json::Value> {
+        let mut assembler = TruthyAssembler::new(properties::USED);
+        let mut items = Vec::new();
+        for line in lines {
+            items.extend(assembler.add_line(line).unwrap());
+        }
+        items.extend(assembler.take().unwrap());
+        items.iter().map(|item| serde_json::from_str(item).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_escapes() {
+        let triple = Triple::parse(
+            r#"<http://www.wikidata.org/entity/Q1> <http://schema.org/description> "a \"b\"\\c\td\u00E9\U0001F600"@en ."#,
+        )
+        .unwrap();
+        assert_eq!(
+            triple.object,
+            Term::Literal {
+                value: "a \"b\"\\c\td\u{e9}\u{1F600}".to_owned(),
+                language: Some("en".to_owned()),
+                datatype: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert_eq!(Triple::parse("# comment"), None);
+        assert_eq!(Triple::parse(""), None);
+        assert_eq!(Triple::parse(r#"<http://a> <http://b> "unterminated ."#), None);
+        assert_eq!(Triple::parse("<http://a> <http://b> <http://c>"), None);
+    }
+
+    #[test]
+    fn test_language_tags() {
+        let items = assemble(&[
+            r#"<http://www.wikidata.org/entity/Q1> <http://www.w3.org/2000/01/rdf-schema#label> "Fairphone"@en ."#,
+            r#"<http://www.wikidata.org/entity/Q1> <http://www.w3.org/2000/01/rdf-schema#label> "Fairphone BR"@pt-br ."#,
+            r#"<http://www.wikidata.org/entity/Q1> <http://www.w3.org/2004/02/skos/core#altLabel> "FP"@en ."#,
+        ]);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["id"], "Q1");
+        assert_eq!(items[0]["labels"]["en"]["value"], "Fairphone");
+        assert_eq!(items[0]["labels"]["pt-br"]["value"], "Fairphone BR");
+        assert_eq!(items[0]["labels"]["pt-br"]["language"], "pt-br");
+        assert_eq!(items[0]["aliases"]["en"][0]["value"], "FP");
+    }
+
+    #[test]
+    fn test_quantity_with_unit() {
+        let items = assemble(&[
+            r#"<http://www.wikidata.org/entity/Q1> <http://www.wikidata.org/prop/direct/P2067> "+195"^^<http://www.w3.org/2001/XMLSchema#decimal> ."#,
+            r"<http://www.wikidata.org/entity/Q1> <http://www.wikidata.org/prop/P2067> <http://www.wikidata.org/entity/statement/Q1-ABC> .",
+            r"<http://www.wikidata.org/entity/statement/Q1-ABC> <http://www.wikidata.org/prop/statement/value/P2067> <http://www.wikidata.org/value/123abc> .",
+            r#"<http://www.wikidata.org/value/123abc> <http://wikiba.se/ontology#quantityAmount> "+195"^^<http://www.w3.org/2001/XMLSchema#decimal> ."#,
+            r"<http://www.wikidata.org/value/123abc> <http://wikiba.se/ontology#quantityUnit> <http://www.wikidata.org/entity/Q11570> .",
+        ]);
+        assert_eq!(items.len(), 1);
+        let value = &items[0]["claims"]["P2067"][0]["mainsnak"]["datavalue"]["value"];
+        assert_eq!(value["amount"], "+195");
+        assert_eq!(value["unit"], "http://www.wikidata.org/entity/Q11570");
+    }
+
+    #[test]
+    fn test_quantity_without_value_node() {
+        let items = assemble(&[
+            r#"<http://www.wikidata.org/entity/Q1> <http://www.wikidata.org/prop/direct/P2067> "+195"^^<http://www.w3.org/2001/XMLSchema#decimal> ."#,
+        ]);
+        let value = &items[0]["claims"]["P2067"][0]["mainsnak"]["datavalue"]["value"];
+        assert_eq!(value["amount"], "+195");
+        assert_eq!(value["unit"], NO_UNIT);
+    }
 
-        callback(json_str.to_string()).await;
-        Ok(1)
+    #[test]
+    fn test_sitelinks_before_and_after_statements() {
+        let items = assemble(&[
+            r"<https://en.wikipedia.org/wiki/Fairphone_4> <http://schema.org/about> <http://www.wikidata.org/entity/Q1> .",
+            r#"<http://www.wikidata.org/entity/Q1> <http://www.w3.org/2000/01/rdf-schema#label> "Fairphone 4"@en ."#,
+            r"<https://de.wikipedia.org/wiki/Fairphone_4> <http://schema.org/about> <http://www.wikidata.org/entity/Q1> .",
+            r"<https://pl.wikipedia.org/wiki/Fairphone_5> <http://schema.org/about> <http://www.wikidata.org/entity/Q2> .",
+            r#"<http://www.wikidata.org/entity/Q2> <http://www.w3.org/2000/01/rdf-schema#label> "Fairphone 5"@en ."#,
+        ]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["id"], "Q1");
+        assert_eq!(items[0]["sitelinks"]["enwiki"]["title"], "Fairphone 4");
+        assert_eq!(items[0]["sitelinks"]["dewiki"]["title"], "Fairphone 4");
+        assert_eq!(items[1]["id"], "Q2");
+        assert_eq!(items[1]["labels"]["en"]["value"], "Fairphone 5");
+        assert_eq!(items[1]["sitelinks"]["plwiki"]["title"], "Fairphone 5");
     }
 }
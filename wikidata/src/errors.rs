@@ -21,3 +21,19 @@ pub enum ParseIdError {
     #[error("The ID `{0}` has unexpected prefix")]
     Prefix(String),
 }
+
+/// Describes an error occured during parsing a quantity.
+#[derive(Error, Debug, Eq, PartialEq)]
+pub enum ParseQuantityError {
+    /// The amount was not a number.
+    #[error("Failed to parse amount from `{0}`: {1}")]
+    Amount(String, std::num::ParseFloatError),
+
+    /// The unit was not an entity URI.
+    #[error("The unit `{0}` is not an entity URI")]
+    UnitUri(String),
+
+    /// The unit URI did not contain a valid ID.
+    #[error("Failed to parse unit ID: {0}")]
+    UnitId(#[from] ParseIdError),
+}
@@ -11,6 +11,9 @@ pub const IMAGE: &str = "P18";
 /// "Is instance of" property.
 pub const INSTANCE_OF: &str = "P31";
 
+/// "Owned by" property.
+pub const OWNED_BY: &str = "P127";
+
 /// "Logo image" property.
 pub const LOGO_IMAGE: &str = "P154";
 
@@ -23,15 +26,45 @@ pub const FOLLOWED_BY: &str = "P156";
 /// "Manufacturer" property.
 pub const MANUFACTURER: &str = "P176";
 
+/// "Location" property.
+pub const LOCATION: &str = "P276";
+
 /// "Subclass of" property.
 pub const SUBCLASS_OF: &str = "P279";
 
+/// "Vessel class" property.
+pub const VESSEL_CLASS: &str = "P289";
+
+/// "Aircraft registration" property.
+pub const AIRCRAFT_REGISTRATION: &str = "P426";
+
+/// "IMO ship number" property.
+pub const IMO_SHIP_NUMBER: &str = "P458";
+
+/// "Inception" property.
+pub const INCEPTION: &str = "P571";
+
 /// "Official website" property.
 pub const OFFICIAL_WEBSITE: &str = "P856";
 
 /// "Product, material, or service produced or provided" property.
 pub const PRODUCT_MATERIAL_OR_SERVICE: &str = "P1056";
 
+/// "Height" property.
+pub const HEIGHT: &str = "P2048";
+
+/// "Width" property.
+pub const WIDTH: &str = "P2049";
+
+/// "Serial number" property.
+pub const SERIAL_NUMBER: &str = "P2598";
+
+/// "Mass" property.
+pub const MASS: &str = "P2067";
+
+/// "Thickness" property.
+pub const THICKNESS: &str = "P2610";
+
 /// "Global Trade Item Number" property.
 pub const GTIN: &str = "P3962";
 
@@ -40,3 +73,33 @@ pub const EU_VAT_NUMBER: &str = "P3608";
 
 /// Amazon Standard Identification Number.
 pub const ASIN: &str = "P5749";
+
+/// Properties of items used in the processing.
+///
+/// Used to limit the data read from the truthy N-Triples dumps.
+pub const USED: &[&str] = &[
+    COUNTRY,
+    IMAGE,
+    INSTANCE_OF,
+    OWNED_BY,
+    LOGO_IMAGE,
+    FOLLOWS,
+    FOLLOWED_BY,
+    MANUFACTURER,
+    LOCATION,
+    SUBCLASS_OF,
+    VESSEL_CLASS,
+    AIRCRAFT_REGISTRATION,
+    IMO_SHIP_NUMBER,
+    INCEPTION,
+    OFFICIAL_WEBSITE,
+    PRODUCT_MATERIAL_OR_SERVICE,
+    HEIGHT,
+    WIDTH,
+    SERIAL_NUMBER,
+    MASS,
+    THICKNESS,
+    GTIN,
+    EU_VAT_NUMBER,
+    ASIN,
+];